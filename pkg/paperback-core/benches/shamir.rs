@@ -58,6 +58,11 @@ fn benchmark_recover_secret(c: &mut Criterion) {
             &shards,
             |b, shards| b.iter(|| black_box(Dealer::recover(shards).unwrap()).secret()),
         );
+        group.bench_with_input(
+            format!("Dealer::recover_secret() N={:03}", quorum_size),
+            &shards,
+            |b, shards| b.iter(|| Dealer::recover_secret(black_box(shards)).unwrap()),
+        );
     }
     group.finish()
 }
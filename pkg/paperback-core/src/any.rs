@@ -0,0 +1,200 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{self, FromWire};
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Versions of the paperback wire format that this crate can parse.
+pub const SUPPORTED_VERSIONS: &[u32] = &[0];
+
+fn format_versions(versions: &[u32]) -> String {
+    versions
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unsupported paperback version {found} (supported versions: {})", format_versions(.supported))]
+    UnsupportedVersion {
+        found: u32,
+        supported: &'static [u32],
+    },
+
+    #[error("failed to decode multibase data: {0}")]
+    MultibaseDecode(multibase::Error),
+
+    #[error("failed to decode document: {0}")]
+    Decode(String),
+
+    #[error(transparent)]
+    V0(v0::Error),
+}
+
+impl From<v0::Error> for Error {
+    fn from(err: v0::Error) -> Self {
+        match err {
+            v0::Error::UnsupportedVersion { found, .. } => Self::UnsupportedVersion {
+                found,
+                supported: SUPPORTED_VERSIONS,
+            },
+            err => Self::V0(err),
+        }
+    }
+}
+
+/// Read the version of a serialised main document. Every version of the wire
+/// format starts with the version as a varint, so this is the only part of a
+/// document that can be parsed without knowing its version.
+pub fn document_version<B: AsRef<[u8]>>(data: B) -> Result<u32, Error> {
+    let (version, _) = unsigned_varint::decode::u32(data.as_ref())
+        .map_err(|err| Error::Decode(format!("failed to read version: {:?}", err)))?;
+    Ok(version)
+}
+
+/// A main document of any supported version.
+#[derive(Clone, Debug)]
+pub enum AnyMainDocument {
+    V0(v0::MainDocument),
+}
+
+impl AnyMainDocument {
+    /// Parse a main document, dispatching on its version.
+    pub fn from_wire<B: AsRef<[u8]>>(data: B) -> Result<Self, Error> {
+        let data = data.as_ref();
+        match document_version(data)? {
+            0 => Ok(Self::V0(
+                v0::MainDocument::from_wire(data).map_err(Error::Decode)?,
+            )),
+            found => Err(Error::UnsupportedVersion {
+                found,
+                supported: SUPPORTED_VERSIONS,
+            }),
+        }
+    }
+
+    /// Parse a multibase-encoded main document, dispatching on its version.
+    pub fn from_wire_multibase<S: AsRef<str>>(input: S) -> Result<Self, Error> {
+        let (_, data) = multibase::decode(input).map_err(Error::MultibaseDecode)?;
+        Self::from_wire(data)
+    }
+
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::V0(main_document) => main_document.version(),
+        }
+    }
+}
+
+/// A decrypted key shard of any supported version.
+#[derive(Clone, Debug)]
+pub enum AnyKeyShard {
+    V0(v0::KeyShard),
+}
+
+impl AnyKeyShard {
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::V0(shard) => shard.version(),
+        }
+    }
+}
+
+/// An encrypted key shard of any supported version.
+///
+/// Unlike main documents, the version of a key shard is stored inside the
+/// encrypted data and so is only known once the key shard is decrypted. A key
+/// shard from an unsupported version is reported as such when decrypting it.
+#[derive(Clone, Debug)]
+pub enum AnyEncryptedKeyShard {
+    V0(v0::EncryptedKeyShard),
+}
+
+impl AnyEncryptedKeyShard {
+    /// Parse an encrypted key shard.
+    pub fn from_wire<B: AsRef<[u8]>>(data: B) -> Result<Self, Error> {
+        // v0 is the only encrypted key shard format.
+        Ok(Self::V0(
+            v0::EncryptedKeyShard::from_wire(data).map_err(Error::Decode)?,
+        ))
+    }
+
+    /// Parse a multibase-encoded encrypted key shard.
+    pub fn from_wire_multibase<S: AsRef<str>>(input: S) -> Result<Self, Error> {
+        let (_, data) = multibase::decode(input).map_err(Error::MultibaseDecode)?;
+        Self::from_wire(data)
+    }
+
+    /// Decrypt the key shard, auto-detecting the language of the codewords.
+    #[cfg(feature = "std")]
+    pub fn decrypt<A: AsRef<[String]>>(&self, codewords: A) -> Result<AnyKeyShard, Error> {
+        match self {
+            Self::V0(shard) => Ok(AnyKeyShard::V0(shard.decrypt(codewords)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::v0::{Backup, ToWire};
+
+    #[quickcheck]
+    fn any_main_document_roundtrip(main: v0::MainDocument) -> bool {
+        match AnyMainDocument::from_wire(main.to_wire()).unwrap() {
+            AnyMainDocument::V0(main2) => main == main2,
+        }
+    }
+
+    #[test]
+    fn any_main_document_unsupported_version() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let mut data = backup.main_document().to_wire();
+        // The version is the first varint, and v0 encodes it as a single byte.
+        assert_eq!(data[0], 0);
+        data[0] = 7;
+
+        match AnyMainDocument::from_wire(data) {
+            Err(Error::UnsupportedVersion { found, supported }) => {
+                assert_eq!(found, 7);
+                assert_eq!(supported, SUPPORTED_VERSIONS);
+            }
+            result => panic!("expected unsupported version error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn any_key_shard_decrypt() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let shard = backup.next_shard().unwrap();
+        let (encrypted, codewords) = shard.encrypt().unwrap();
+
+        let any_encrypted = AnyEncryptedKeyShard::from_wire(encrypted.to_wire()).unwrap();
+        match any_encrypted.decrypt(&codewords).unwrap() {
+            AnyKeyShard::V0(shard2) => assert_eq!(shard, shard2),
+        }
+    }
+}
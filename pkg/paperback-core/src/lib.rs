@@ -17,6 +17,9 @@
  */
 
 #![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 #[cfg(test)]
 extern crate quickcheck;
@@ -33,6 +36,16 @@ mod shamir;
 #[cfg(feature = "donotuse_expose_internal_modules")]
 pub mod shamir;
 
+/// Generic Shamir Secret Sharing of arbitrary data, using the same `GF(2^32)`
+/// implementation as paperback backups (but without any signatures or
+/// encryption).
+pub mod sss {
+    pub use crate::shamir::{join, Error, Shard};
+
+    #[cfg(feature = "std")]
+    pub use crate::shamir::{join_into, split};
+}
+
 /// Initial version of paperback wire format types.
 ///
 /// This module also includes all of the necessary code to serialise and
@@ -41,3 +54,14 @@ pub mod v0;
 
 /// Re-export of the newest paperback wire format types.
 pub use v0 as latest;
+
+/// Conversion of secrets to and from SLIP-0039 (Trezor "Shamir Backup")
+/// shares.
+#[cfg(feature = "std")]
+pub mod slip39;
+
+/// Version-independent parsing of paperback documents.
+///
+/// This allows documents created by older versions of paperback to be
+/// recovered, and documents from unsupported versions to be reported as such.
+pub mod any;
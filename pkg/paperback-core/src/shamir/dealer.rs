@@ -17,46 +17,118 @@
  */
 
 use crate::shamir::{
-    gf::{EvaluablePolynomial, GfBarycentric, GfElem, GfElemPrimitive, GfPolynomial},
+    gf::{EvaluablePolynomial, GfBarycentric, GfElem, GfElemPrimitive},
     shard::Shard,
     Error,
 };
+#[cfg(feature = "std")]
+use crate::{shamir::gf::GfPolynomial, v0::ShardId};
+
+use alloc::{boxed::Box, collections::BTreeSet, vec::Vec};
+#[cfg(feature = "std")]
+use core::mem;
+#[cfg(feature = "std")]
+use std::{
+    io::Write,
+    sync::{Mutex, PoisonError},
+};
 
-use std::mem;
-
+#[cfg(feature = "std")]
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "std")]
 use rayon::prelude::*;
+#[cfg(not(feature = "std"))]
+use sequential::*;
+use zeroize::Zeroizing;
+
+/// Sequential stand-ins for the rayon iterators used by `Dealer`, for builds
+/// without the `std` feature (where there are no threads to use).
+#[cfg(not(feature = "std"))]
+mod sequential {
+    pub(super) trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+}
+
+/// Lock protecting the set of x values issued by a `Dealer`. Without the `std`
+/// feature there are no threads, so a `RefCell` is enough.
+#[cfg(feature = "std")]
+type IssuedLock = Mutex<BTreeSet<GfElem>>;
+#[cfg(not(feature = "std"))]
+type IssuedLock = core::cell::RefCell<BTreeSet<GfElem>>;
+
+/// Number of secret chunks interpolated at once by `Dealer::recover_secret_into`,
+/// which bounds how much of the secret is held in memory at any one time.
+const RECOVER_BATCH_SIZE: usize = 4096;
 
 /// Factory to share a secret using [Shamir Secret Sharing][sss].
 ///
 /// [sss]: https://en.wikipedia.org/wiki/Shamir%27s_Secret_Sharing
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Dealer {
     polys: Vec<Box<dyn EvaluablePolynomial>>,
     secret_len: usize,
     threshold: GfElemPrimitive,
+    // x values of the shards issued by (or used to recover) this dealer.
+    issued: IssuedLock,
+}
+
+// Mutex is not Clone.
+impl Clone for Dealer {
+    fn clone(&self) -> Self {
+        Self {
+            polys: self.polys.clone(),
+            secret_len: self.secret_len,
+            threshold: self.threshold,
+            issued: IssuedLock::new(self.issued_xs().clone()),
+        }
+    }
 }
 
 impl Dealer {
     /// Returns the number of *unique* `Shard`s generated by this `Dealer`
     /// required to recover the stored secret.
-    #[allow(dead_code)]
+    #[cfg(feature = "std")]
     pub fn threshold(&self) -> u32 {
         self.threshold
     }
 
     /// Construct a new `Dealer` to shard the `secret`, requiring at least
     /// `threshold` shards to reconstruct the secret.
+    ///
+    /// A `threshold` of one is supported, but is degenerate: the polynomials
+    /// are constant (degree zero), so every shard contains the secret itself
+    /// and any single shard can be used to recover it (or to create any other
+    /// shard).
+    #[cfg(feature = "std")]
     pub fn new<B: AsRef<[u8]>>(threshold: u32, secret: B) -> Self {
+        Self::new_with_rng(threshold, secret, &mut rand::thread_rng())
+    }
+
+    /// Like `Dealer::new`, but the random polynomials are generated using
+    /// `rng`. Given the same `rng` state, the same `Dealer` is constructed.
+    #[cfg(feature = "std")]
+    pub fn new_with_rng<B, R>(threshold: u32, secret: B, rng: &mut R) -> Self
+    where
+        B: AsRef<[u8]>,
+        R: CryptoRng + RngCore + ?Sized,
+    {
         assert!(threshold > 0, "must at least have a threshold of one");
         let k = threshold - 1;
         let secret = secret.as_ref();
+        // NOTE: This cannot be done in parallel, because the polynomials must
+        //       be generated in a fixed order for a given rng.
         let polys = secret
             // Generate &[u32] from &[u8], by chunking into sets of four.
-            .par_chunks(mem::size_of::<GfElemPrimitive>())
+            .chunks(mem::size_of::<GfElemPrimitive>())
             .map(GfElem::from_bytes)
             // Generate a random polynomial with the value as the constant.
             .map(|x0| {
-                let mut poly = GfPolynomial::new_rand(k, &mut rand::thread_rng());
+                let mut poly = GfPolynomial::new_rand(k, &mut *rng);
                 *poly.constant_mut() = x0;
                 Box::new(poly) as Box<dyn EvaluablePolynomial>
             })
@@ -65,17 +137,38 @@ impl Dealer {
             polys,
             threshold,
             secret_len: secret.len(),
+            issued: IssuedLock::new(BTreeSet::new()),
         }
     }
 
+    #[cfg(feature = "std")]
+    fn issued_xs(&self) -> std::sync::MutexGuard<'_, BTreeSet<GfElem>> {
+        self.issued.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn issued_xs(&self) -> core::cell::RefMut<'_, BTreeSet<GfElem>> {
+        self.issued.borrow_mut()
+    }
+
+    /// Returns whether a `Shard` with the x value `x` (see `Shard::id`) has
+    /// been issued by this `Dealer`. The shards used to recover a `Dealer` (see
+    /// `Dealer::recover`) count as having been issued by it.
+    #[cfg(feature = "std")]
+    pub fn is_issued(&self, x: GfElemPrimitive) -> bool {
+        self.issued_xs().contains(&GfElem::from_inner(x))
+    }
+
     /// Get the secret value stored by the `Dealer`.
-    pub fn secret(&self) -> Vec<u8> {
-        let mut secret = self
-            .polys
-            .par_iter()
-            .map(|poly| poly.constant())
-            .flat_map(|x| x.to_bytes())
-            .collect::<Vec<_>>();
+    #[cfg(feature = "std")]
+    pub fn secret(&self) -> Zeroizing<Vec<u8>> {
+        let mut secret = Zeroizing::new(
+            self.polys
+                .par_iter()
+                .map(|poly| poly.constant())
+                .flat_map(|x| x.to_bytes())
+                .collect::<Vec<_>>(),
+        );
 
         // Cannot call .take() on rayon::iter::FlatMap, so do it the
         // old-fashioned way instead.
@@ -85,23 +178,87 @@ impl Dealer {
 
     /// Generate a new `Shard` for the secret.
     ///
-    /// NOTE: The `x` value is calculated randomly, which means that there is a
-    ///       small chance that two separate calls to `Dealer::shard` will
-    ///       generate the same `Shard`. It is up to the caller to be sure that
-    ///       they have enough *unique* shards to reconstruct the secret.
-    // TODO: I'm not convinced the chances of collision are low enough...
+    /// NOTE: The `x` value is calculated randomly, but `x` values which have
+    ///       already been issued by this `Dealer` (see `Dealer::is_issued`) are
+    ///       never reused. Shards issued by *other* `Dealer`s for the same
+    ///       secret (such as one recovered from a different quorum) are not
+    ///       known, so there is still a small chance of colliding with those.
+    #[cfg(feature = "std")]
     pub fn next_shard(&self) -> Shard {
-        let mut g = rand::thread_rng();
-        // TODO: We should probably add some limit to this.
+        self.next_shard_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Like `Dealer::next_shard`, but the `x` value is generated using `rng`.
+    #[cfg(feature = "std")]
+    pub fn next_shard_with_rng<R: CryptoRng + RngCore + ?Sized>(&self, rng: &mut R) -> Shard {
+        // NOTE: Almost every x value can be used, so this only takes more
+        //       than a handful of attempts once nearly all 2^32-1 x values
+        //       have been issued (which would need billions of shards).
+        //       Dealer::next_shards refuses to issue more shards than there
+        //       are x values left.
         loop {
-            match self.shard(GfElem::new_rand(&mut g)) {
-                Some(shard) => return shard,
-                None => continue,
+            let x = GfElem::new_rand(rng);
+            // Skip x values which would leak the secret, or were already used.
+            match self.shard(x) {
+                Some(shard) if self.issued_xs().insert(x) => return shard,
+                _ => continue,
             }
         }
     }
 
+    /// Generate `n` new `Shard`s for the secret. As with `Dealer::next_shard`,
+    /// the returned `Shard`s are guaranteed to have distinct `x` values (and
+    /// thus can all be used together). Fails if there are fewer than `n` unused
+    /// `x` values left.
+    #[cfg(feature = "std")]
+    pub fn next_shards(&self, n: usize) -> Result<Vec<Shard>, Error> {
+        self.next_shards_with_rng(n, &mut rand::thread_rng())
+    }
+
+    /// Like `Dealer::next_shards`, but the `x` values are generated using
+    /// `rng`.
+    #[cfg(feature = "std")]
+    pub fn next_shards_with_rng<R: CryptoRng + RngCore + ?Sized>(
+        &self,
+        n: usize,
+        rng: &mut R,
+    ) -> Result<Vec<Shard>, Error> {
+        // There are only 2^32-1 usable x values.
+        let remaining = (GfElemPrimitive::MAX as usize).saturating_sub(self.issued_xs().len());
+        if n > remaining {
+            return Err(Error::ShardsExhausted { want: n, remaining });
+        }
+
+        Ok((0..n).map(|_| self.next_shard_with_rng(rng)).collect())
+    }
+
+    /// Generate the `Shard` with the caller-chosen x value `x` (see
+    /// `Shard::id`), and record it as issued.
+    ///
+    /// Unlike `Dealer::shard`, this fails if `x` is zero (the x value of the
+    /// secret itself), if the shard would leak part of the secret, or if a
+    /// shard with the same x value has already been issued by this `Dealer`.
+    #[cfg(feature = "std")]
+    pub fn shard_at(&self, x: GfElemPrimitive) -> Result<Shard, Error> {
+        let id = ShardId::from_x_value(x);
+        let x = GfElem::from_inner(x);
+        if x == GfElem::ZERO {
+            return Err(Error::InvalidShardId(
+                id,
+                "the secret is stored at this x value",
+            ));
+        }
+        let shard = self
+            .shard(x)
+            .ok_or_else(|| Error::InvalidShardId(id.clone(), "the shard would leak the secret"))?;
+        match self.issued_xs().insert(x) {
+            true => Ok(shard),
+            false => Err(Error::ShardAlreadyIssued(id)),
+        }
+    }
+
     /// Generate a `Shard` for the secret using the given `x` value.
+    #[cfg(feature = "std")]
     pub fn shard(&self, x: GfElem) -> Option<Shard> {
         if x == GfElem::ZERO {
             return None;
@@ -126,42 +283,138 @@ impl Dealer {
                 }
             })
             .collect::<Option<Vec<_>>>()
-            .map(|ys| Shard {
-                x,
-                ys,
-                threshold: self.threshold,
-                secret_len: self.secret_len,
-            })
+            .map(|ys| Shard::new(x, ys, self.secret_len, self.threshold))
+    }
+
+    /// Sanity-check that `shards` can be used to recover the secret.
+    fn check_shards(shards: &[Shard]) -> Result<(), Error> {
+        let first = shards
+            .first()
+            .ok_or(Error::InsufficientShards { have: 0, need: 1 })?;
+        if !first.is_consistent() || shards.iter().any(|shard| !shard.is_consistent_with(first)) {
+            return Err(Error::InconsistentShards);
+        }
+
+        let threshold = first.threshold();
+        if shards.len() < threshold as usize {
+            return Err(Error::InsufficientShards {
+                have: shards.len(),
+                need: threshold,
+            });
+        }
+        if shards.len() > threshold as usize {
+            return Err(Error::TooManyShards {
+                have: shards.len(),
+                need: threshold,
+            });
+        }
+
+        // Interpolating with a corrupted shard would silently give us the
+        // wrong polynomial, so figure out which shards are bad beforehand.
+        let corrupted = shards
+            .iter()
+            .filter(|shard| shard.is_corrupted())
+            .map(Shard::id)
+            .collect::<Vec<_>>();
+        if !corrupted.is_empty() {
+            return Err(Error::CorruptShards(corrupted));
+        }
+
+        Ok(())
+    }
+
+    /// Reconstruct only the secret from a *unique* set of `Shard`s, writing it
+    /// to `writer` as it is recovered. Only `RECOVER_BATCH_SIZE` chunks of the
+    /// secret are interpolated (in parallel) at a time, so large secrets can be
+    /// recovered with bounded memory usage.
+    ///
+    /// The same requirements as `Dealer::recover` apply to `shards`.
+    #[cfg(feature = "std")]
+    pub fn recover_secret_into<S, W>(shards: S, mut writer: W) -> Result<(), Error>
+    where
+        S: AsRef<[Shard]>,
+        W: Write,
+    {
+        Self::recover_secret_with(shards, |chunk| Ok(writer.write_all(chunk)?))
+    }
+
+    /// Like `Dealer::recover_secret_into`, but each recovered chunk of the
+    /// secret is passed to `sink` (which does not require `std::io`).
+    fn recover_secret_with<S, F>(shards: S, mut sink: F) -> Result<(), Error>
+    where
+        S: AsRef<[Shard]>,
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        let shards = shards.as_ref();
+        Self::check_shards(shards)?;
+
+        let threshold = shards[0].threshold();
+        let polys_len = shards[0].ys.len();
+        let mut remaining = shards[0].secret_len;
+
+        for start in (0..polys_len).step_by(RECOVER_BATCH_SIZE) {
+            let end = polys_len.min(start + RECOVER_BATCH_SIZE);
+            let constants = Zeroizing::new(
+                (start..end)
+                    .into_par_iter()
+                    .map(|i| {
+                        let points = shards.iter().map(|s| (s.x, s.ys[i])).collect::<Vec<_>>();
+                        GfBarycentric::recover(threshold - 1, points.as_slice())
+                            .map(|poly| poly.constant())
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+            let mut chunk = Zeroizing::new(
+                constants
+                    .iter()
+                    .flat_map(|x| x.to_bytes())
+                    .collect::<Vec<_>>(),
+            );
+            // The final chunk may contain padding.
+            chunk.truncate(remaining);
+            sink(&chunk)?;
+            remaining -= chunk.len();
+        }
+
+        Ok(())
+    }
+
+    /// Reconstruct only the secret from a *unique* set of `Shard`s. This is
+    /// much faster than `Dealer::recover` followed by `Dealer::secret`.
+    ///
+    /// The same requirements as `Dealer::recover` apply to `shards`.
+    pub fn recover_secret<S: AsRef<[Shard]>>(shards: S) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let shards = shards.as_ref();
+        // Pre-allocate the buffer, so that no copies of the secret are left
+        // behind when it is resized.
+        let mut secret = Zeroizing::new(Vec::with_capacity(
+            shards.first().map_or(0, |shard| shard.secret_len),
+        ));
+        Self::recover_secret_with(shards, |chunk| {
+            secret.extend_from_slice(chunk);
+            Ok(())
+        })?;
+        Ok(secret)
     }
 
     /// Reconstruct an entire `Dealer` from a *unique* set of `Shard`s.
     ///
-    /// The caller must pass exactly the correct number of shards.
+    /// The caller must pass exactly the correct number of shards. If any of the
+    /// shards fail their integrity check, an error listing the IDs of all of
+    /// the corrupted shards is returned.
     ///
     /// This operation is significantly slower than `recover_secret`, so it
     /// should only be used if it is necessary to construct additional shards
     /// with `Dealer::next_shard`.
+    #[cfg(feature = "std")]
     pub fn recover<S: AsRef<[Shard]>>(shards: S) -> Result<Self, Error> {
         let shards = shards.as_ref();
-        assert!(!shards.is_empty(), "must be provided at least one shard");
+        Self::check_shards(shards)?;
 
         let threshold = shards[0].threshold();
         let polys_len = shards[0].ys.len();
         let secret_len = shards[0].secret_len;
 
-        // TODO: Implement this consistency checking more nicely.
-        for shard in shards {
-            assert!(shard.threshold() == threshold, "shards must be consistent");
-            assert!(shard.ys.len() == polys_len, "shards must be consistent");
-            assert!(shard.secret_len == secret_len, "shards must be consistent");
-        }
-
-        assert!(
-            shards.len() == threshold as usize,
-            "must have exactly {} shards",
-            threshold
-        );
-
         let polys = (0..polys_len)
             .into_par_iter()
             .map(|i| {
@@ -178,6 +431,7 @@ impl Dealer {
             polys,
             secret_len,
             threshold,
+            issued: IssuedLock::new(shards.iter().map(|s| s.x).collect()),
         })
     }
 }
@@ -187,6 +441,7 @@ mod test {
     use super::*;
 
     use quickcheck::TestResult;
+    use rand::{rngs::StdRng, SeedableRng};
 
     // We use u16s and u8s here (and limit the range) because we cannot handle
     // ridiculously large quorum sizes in quickcheck tests. Some larger quorums
@@ -198,7 +453,149 @@ mod test {
             return TestResult::discard();
         }
         let dealer = Dealer::new(n.into(), &secret);
-        TestResult::from_bool(secret == dealer.secret())
+        TestResult::from_bool(secret == *dealer.secret())
+    }
+
+    #[quickcheck]
+    fn deterministic_with_rng(n: u8, secret: Vec<u8>, seed: u64) -> TestResult {
+        if n < 1 {
+            return TestResult::discard();
+        }
+        let shards = || {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let dealer = Dealer::new_with_rng(n.into(), &secret, &mut rng);
+            (0..n)
+                .map(|_| dealer.next_shard_with_rng(&mut rng))
+                .collect::<Vec<_>>()
+        };
+        TestResult::from_bool(shards() == shards())
+    }
+
+    // Deliberately terrible "CSPRNG" which cycles through a fixed set of values,
+    // used to force x value collisions.
+    struct CyclingRng(Vec<u32>, usize);
+
+    impl RngCore for CyclingRng {
+        fn next_u32(&mut self) -> u32 {
+            let value = self.0[self.1 % self.0.len()];
+            self.1 += 1;
+            value
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for chunk in dest.chunks_mut(4) {
+                let value = self.next_u32().to_le_bytes();
+                chunk.copy_from_slice(&value[..chunk.len()]);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for CyclingRng {}
+
+    #[test]
+    fn next_shards_distinct() {
+        let dealer = Dealer::new(3, b"secret");
+
+        let mut rng = CyclingRng(vec![1, 1, 2, 1, 2, 2, 3, 1], 0);
+        let shards = dealer.next_shards_with_rng(3, &mut rng).unwrap();
+        assert_eq!(
+            shards.iter().map(|s| s.x).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+                .into_iter()
+                .map(GfElem::from_inner)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(*Dealer::recover(shards).unwrap().secret(), b"secret");
+    }
+
+    #[test]
+    fn next_shards_exhausted() {
+        let dealer = Dealer::new(2, b"secret");
+        dealer.shard_at(1234).unwrap();
+
+        // There are only 2^32-1 usable x values, one of which is now issued.
+        let usable = GfElemPrimitive::MAX as usize;
+        assert!(matches!(
+            dealer.next_shards(usable),
+            Err(Error::ShardsExhausted { want, remaining })
+                if want == usable && remaining == usable - 1
+        ));
+        assert_eq!(dealer.next_shards(2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn shard_at() {
+        let dealer = Dealer::new(2, b"secret");
+        let shard = dealer.shard_at(1234).unwrap();
+        assert_eq!(shard.x, GfElem::from_inner(1234));
+        assert!(dealer.is_issued(1234));
+        assert!(!dealer.is_issued(4321));
+
+        assert!(matches!(
+            dealer.shard_at(1234),
+            Err(Error::ShardAlreadyIssued(_))
+        ));
+        assert!(matches!(
+            dealer.shard_at(0),
+            Err(Error::InvalidShardId(_, _))
+        ));
+
+        // Random x values never collide with the ones already issued.
+        let mut rng = CyclingRng(vec![1234, 1234, 5678], 0);
+        assert_eq!(
+            dealer.next_shard_with_rng(&mut rng).x,
+            GfElem::from_inner(5678)
+        );
+
+        // Shards used to recover a dealer count as issued.
+        let other = dealer.next_shard();
+        let recovered = Dealer::recover([shard, other.clone()]).unwrap();
+        assert!(recovered.is_issued(1234));
+        assert!(matches!(
+            recovered.shard_at(other.x.inner()),
+            Err(Error::ShardAlreadyIssued(_))
+        ));
+        assert!(!recovered.is_issued(5678));
+        assert!(recovered.shard_at(5678).is_ok());
+    }
+
+    #[test]
+    fn threshold_one() {
+        let secret = b"a secret which is not a multiple of four bytes";
+        let dealer = Dealer::new(1, secret);
+        let shards = dealer.next_shards(4).unwrap();
+
+        // Each shard is enough to recover the secret (and the other shards).
+        for shard in &shards {
+            assert_eq!(shard.threshold(), 1);
+            assert_eq!(*Dealer::recover_secret([shard.clone()]).unwrap(), secret);
+
+            let dealer2 = Dealer::recover([shard.clone()]).unwrap();
+            assert_eq!(*dealer2.secret(), secret);
+            for other in &shards {
+                assert_eq!(dealer2.shard(other.x).as_ref(), Some(other));
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn next_shards_unique(n: u8, k: u8) -> TestResult {
+        if n < 1 {
+            return TestResult::discard();
+        }
+        let dealer = Dealer::new(n.into(), b"secret");
+        let shards = dealer.next_shards(k.into()).unwrap();
+        let ids = shards.iter().map(Shard::id).collect::<BTreeSet<_>>();
+        TestResult::from_bool(shards.len() == k as usize && ids.len() == shards.len())
     }
 
     #[cfg(debug_assertions)] // not --release
@@ -222,13 +619,16 @@ mod test {
             .map(|_| {
                 let mut shard = dealer.next_shard();
                 shard.threshold -= 1;
+                // The integrity code covers the threshold, so pretend these
+                // are shards from an older version of paperback.
+                shard.integrity_code = None;
                 // Ensure shard IDs are always ID_LENGTH.
                 assert_eq!(shard.id().len(), Shard::ID_LENGTH);
                 shard
             })
             .collect::<Vec<_>>();
 
-        TestResult::from_bool(Dealer::recover(shards).unwrap().secret() != secret)
+        TestResult::from_bool(*Dealer::recover(shards).unwrap().secret() != secret)
     }
 
     #[quickcheck]
@@ -252,7 +652,71 @@ mod test {
             })
             .collect::<Vec<_>>();
 
-        TestResult::from_bool(Dealer::recover(shards).unwrap().secret() == secret)
+        TestResult::from_bool(*Dealer::recover(shards).unwrap().secret() == secret)
+    }
+
+    #[quickcheck]
+    fn recover_secret_only(n: u8, secret: Vec<u8>) -> TestResult {
+        if !(1..=SECRET_UPPER).contains(&n) {
+            return TestResult::discard();
+        }
+
+        let dealer = Dealer::new(n.into(), &secret);
+        let shards = (0..n).map(|_| dealer.next_shard()).collect::<Vec<_>>();
+
+        TestResult::from_bool(*Dealer::recover_secret(shards).unwrap() == secret)
+    }
+
+    #[test]
+    fn recover_secret_into_batches() {
+        // Records the size of each write.
+        #[derive(Default)]
+        struct BatchWriter(Vec<u8>, Vec<usize>);
+
+        impl Write for BatchWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.extend_from_slice(buf);
+                self.1.push(buf.len());
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let batch_bytes = RECOVER_BATCH_SIZE * mem::size_of::<GfElemPrimitive>();
+        let secret = (0..(batch_bytes * 2 + 7))
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let dealer = Dealer::new(3, &secret);
+        let shards = (0..3).map(|_| dealer.next_shard()).collect::<Vec<_>>();
+
+        let mut writer = BatchWriter::default();
+        Dealer::recover_secret_into(shards, &mut writer).unwrap();
+        assert_eq!(writer.0, secret);
+        assert_eq!(writer.1, vec![batch_bytes, batch_bytes, 7]);
+    }
+
+    #[quickcheck]
+    fn recover_corrupted_shard(n: u8, secret: Vec<u8>, idx: usize, delta: u32) -> TestResult {
+        // Recovery must fail and report the ID of the corrupted shard.
+        if !(1..=SECRET_UPPER).contains(&n) || secret.is_empty() || delta == 0 {
+            return TestResult::discard();
+        }
+
+        let dealer = Dealer::new(n.into(), &secret);
+        let mut shards = (0..n).map(|_| dealer.next_shard()).collect::<Vec<_>>();
+
+        let num_shards = shards.len();
+        let bad_shard = &mut shards[idx % num_shards];
+        bad_shard.ys[0] = GfElem::from_inner(bad_shard.ys[0].inner() ^ delta);
+        let bad_id = bad_shard.id();
+
+        TestResult::from_bool(matches!(
+            Dealer::recover(shards),
+            Err(Error::CorruptShards(ids)) if ids == vec![bad_id]
+        ))
     }
 
     #[cfg(debug_assertions)] // not --release
@@ -279,6 +743,9 @@ mod test {
             .map(|_| {
                 let mut shard = dealer.next_shard();
                 shard.threshold -= 1;
+                // The integrity code covers the threshold, so pretend these
+                // are shards from an older version of paperback.
+                shard.integrity_code = None;
                 // Ensure shard IDs are always ID_LENGTH.
                 assert_eq!(shard.id().len(), Shard::ID_LENGTH);
                 shard
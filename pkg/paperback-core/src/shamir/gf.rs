@@ -16,13 +16,16 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{
     cmp, fmt, mem,
     ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
 use itertools::Itertools;
+#[cfg(feature = "std")]
 use rand::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -46,9 +49,15 @@ pub type GfElemPrimitive = u32;
 /// implementations of `GF(2^n)` fields (and `GF(2^8)` is not suitable for our
 /// purposes).
 // NOTE: PartialEq is not timing-safe.
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct GfElem(GfElemPrimitive);
 
+impl Zeroize for GfElem {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 /// (x, y) in GF.
 pub type GfPoint = (GfElem, GfElem);
 
@@ -69,6 +78,7 @@ impl GfElem {
     /// Multiplicative identity.
     pub const ONE: GfElem = GfElem(1);
 
+    #[cfg(feature = "std")]
     pub fn new_rand<R: CryptoRng + RngCore + ?Sized>(r: &mut R) -> Self {
         Self(r.next_u32())
     }
@@ -246,6 +256,46 @@ impl GfElem {
         assert_eq!(r, 1, "Self::POLYNOMIAL not irreducible in GF(2)!");
         Some(Self(t))
     }
+
+    /// Compute the inverse of every element in `elems`. Returns `None` if any
+    /// of the elements are zero.
+    ///
+    /// This is equivalent to calling `GfElem::inverse` on each element, but
+    /// uses Montgomery's trick so that only a single inversion is needed. The
+    /// EEA is far more expensive than multiplication, so this makes a big
+    /// difference for large quorums.
+    pub fn batch_inverse<E: AsRef<[Self]>>(elems: E) -> Option<Vec<Self>> {
+        let elems = elems.as_ref();
+
+        // prefixes[i] = elems[0] * ... * elems[i]
+        let prefixes = elems
+            .iter()
+            .scan(Self::ONE, |acc, &elem| {
+                *acc *= elem;
+                Some(*acc)
+            })
+            .collect::<Vec<_>>();
+
+        // The product is only zero if one of the elements is zero.
+        let mut inv = match prefixes.last() {
+            Some(product) => product.inverse()?,
+            None => return Some(vec![]),
+        };
+
+        // Walk backwards, peeling off one element at a time:
+        //
+        //   elems[i]^-1 = prefixes[i-1] * prefixes[i]^-1
+        //   prefixes[i-1]^-1 = elems[i] * prefixes[i]^-1
+        let mut inverses = vec![Self::ZERO; elems.len()];
+        for i in (0..elems.len()).rev() {
+            inverses[i] = match i {
+                0 => inv,
+                _ => inv * prefixes[i - 1],
+            };
+            inv *= elems[i];
+        }
+        Some(inverses)
+    }
 }
 
 #[cfg(test)]
@@ -364,6 +414,7 @@ impl Clone for Box<dyn EvaluablePolynomial> {
 pub struct GfPolynomial(Vec<GfElem>);
 
 impl GfPolynomial {
+    #[cfg(feature = "std")]
     pub fn new_rand<R: CryptoRng + RngCore + ?Sized>(n: GfElemPrimitive, r: &mut R) -> Self {
         let k = n + 1;
         Self(
@@ -389,6 +440,7 @@ impl GfPolynomial {
     /// This allows you to modify the constant term of the polynomial. Note that
     /// this invalidates all previously computed `GfPolynomial::evaluate`
     /// results.
+    #[cfg(feature = "std")]
     pub fn constant_mut(&mut self) -> &mut GfElem {
         self.0
             .first_mut()
@@ -523,6 +575,16 @@ impl EvaluablePolynomial for GfPolynomial {
     }
 }
 
+// The constant terms of the polynomials used for sharding are the secret, so
+// wipe them once they are no longer needed.
+impl Drop for GfPolynomial {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for GfPolynomial {}
+
 #[cfg(test)]
 impl quickcheck::Arbitrary for GfPolynomial {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
@@ -596,12 +658,14 @@ impl EvaluablePolynomial for GfBarycentric {
         // Since the \sum_{j=0}^k \frac{w_j}{x-x_j} calculation is common, we
         // can first caclulate the terms and dot-product a copy by y_j.
 
-        // Terms of \sum_{j=0}^k \frac{w_j}{x-x_j}.
-        let sum_terms = self
-            .xs
+        // Terms of \sum_{j=0}^k \frac{w_j}{x-x_j}. All of the (x-x_j) terms
+        // are inverted together, since inversion is the expensive part.
+        let diffs = self.xs.iter().map(|&xj| x - xj).collect::<Vec<_>>();
+        let sum_terms = GfElem::batch_inverse(diffs)
+            .expect("x is not one of the interpolation points")
             .iter()
             .zip(&self.ws)
-            .map(|(&xj, &wj)| wj / (x - xj))
+            .map(|(&inv, &wj)| wj * inv)
             .collect::<Vec<_>>();
 
         // Sum(sum_terms . ys)
@@ -635,6 +699,17 @@ impl EvaluablePolynomial for GfBarycentric {
     }
 }
 
+// The points used for interpolation are enough to recover the secret.
+impl Drop for GfBarycentric {
+    fn drop(&mut self) {
+        self.xs.zeroize();
+        self.ys.zeroize();
+        self.ws.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for GfBarycentric {}
+
 impl GfBarycentric {
     pub fn recover<P: AsRef<[GfPoint]>>(n: GfElemPrimitive, points: P) -> Result<Self, Error> {
         let points = points.as_ref();
@@ -669,7 +744,9 @@ impl GfBarycentric {
         //
         //   L(x) = \frac{\sum_{j=0}^k \frac{w_j}{x-x_j} y_j}
         //               {\sum_{j=0}^k \frac{w_j}{x-x_j}}
-        let ws = xs
+        //
+        // We first compute every l'(x_j) and then invert them all at once.
+        let derivatives = xs
             .iter()
             .enumerate()
             .map(|(j, &xj)| {
@@ -678,22 +755,20 @@ impl GfBarycentric {
                     .filter(|&(i, _)| i != j)
                     .map(|(_, &xi)| xj - xi)
                     .reduce(Mul::mul)
-                    .map_or_else(
-                        || {
-                            // In this situation, any w_0 value is acceptable
-                            // because it will be cancelled out in L(x) since
-                            //   L(x) = y = secret     V x E ...
-                            assert!(
-                                n == 0,
-                                "zero-length weights should only happen with degree-0 polynomial"
-                            );
-                            Some(GfElem::ONE)
-                        },
-                        GfElem::inverse,
-                    )
-                    .expect("barycentric weights should not be zero")
+                    .unwrap_or_else(|| {
+                        // In this situation, any w_0 value is acceptable
+                        // because it will be cancelled out in L(x) since
+                        //   L(x) = y = secret     V x E ...
+                        assert!(
+                            n == 0,
+                            "zero-length weights should only happen with degree-0 polynomial"
+                        );
+                        GfElem::ONE
+                    })
             })
             .collect::<Vec<_>>();
+        let ws =
+            GfElem::batch_inverse(derivatives).expect("barycentric weights should not be zero");
 
         Ok(Self { xs, ys, ws })
     }
@@ -786,6 +861,15 @@ mod test {
         }
     }
 
+    #[quickcheck]
+    fn batch_inverse(elems: Vec<GfElem>) -> bool {
+        let inverses = elems
+            .iter()
+            .map(|a| a.inverse())
+            .collect::<Option<Vec<_>>>();
+        GfElem::batch_inverse(&elems) == inverses
+    }
+
     #[quickcheck]
     fn div_mul_invertibility(a: GfElem, b: GfElem) -> TestResult {
         match b {
@@ -855,6 +939,23 @@ mod test {
         TestResult::from_bool(poly == interpolated_poly)
     }
 
+    #[quickcheck]
+    fn barycentric_degree_zero(x0: GfElem, y0: GfElem, test_xs: Vec<GfElem>) -> TestResult {
+        if x0 == GfElem::ZERO {
+            return TestResult::discard();
+        }
+        // A single point defines a constant polynomial (this is what a
+        // threshold of one uses).
+        let poly = GfBarycentric::recover(0, [(x0, y0)])
+            .expect("should not get errors from barycentric recovery");
+
+        TestResult::from_bool(
+            poly.degree() == 0
+                && poly.constant() == y0
+                && test_xs.iter().all(|&x| poly.evaluate(x) == y0),
+        )
+    }
+
     #[quickcheck]
     fn polynomial_barycentric_recover(poly: GfPolynomial, test_xs: Vec<GfElem>) -> TestResult {
         let n = poly.degree();
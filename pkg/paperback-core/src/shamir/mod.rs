@@ -31,11 +31,163 @@ mod dealer;
 mod gf;
 pub(crate) mod shard;
 
+// Naive reference implementation, used to cross-check the real one in tests.
+#[cfg(test)]
+mod reference;
+
 pub use dealer::Dealer;
 pub use shard::Shard;
 
+use alloc::vec::Vec;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("lagrange interpolation failed: {0}")]
     LagrangeError(#[from] gf::Error),
+
+    #[error("shards failed integrity check (corrupted?): {}", .0.join(", "))]
+    CorruptShards(Vec<crate::v0::ShardId>),
+
+    #[cfg(feature = "std")]
+    #[error("failed to write recovered secret: {0}")]
+    WriteSecret(#[from] std::io::Error),
+
+    #[error("cannot issue shard {0}: {1}")]
+    InvalidShardId(crate::v0::ShardId, &'static str),
+
+    #[error("shard {0} has already been issued")]
+    ShardAlreadyIssued(crate::v0::ShardId),
+
+    #[error("cannot issue {want} new shards (only {remaining} unused shard ids are left)")]
+    ShardsExhausted { want: usize, remaining: usize },
+
+    #[error("shards are not all from the same secret")]
+    InconsistentShards,
+
+    #[error("not enough unique shards to recover the secret (have {have}, need {need})")]
+    InsufficientShards { have: usize, need: u32 },
+
+    #[error("too many shards to recover the secret (have {have}, need exactly {need})")]
+    TooManyShards { have: usize, need: u32 },
+}
+
+/// Split `secret` into `num_shards` new shards, any `threshold` of which can be
+/// used to recover the secret with `join`.
+///
+/// Unlike paperback backups, the shards are not signed or encrypted.
+#[cfg(feature = "std")]
+pub fn split<B: AsRef<[u8]>>(
+    threshold: u32,
+    num_shards: usize,
+    secret: B,
+) -> Result<Vec<Shard>, Error> {
+    Dealer::new(threshold, secret).next_shards(num_shards)
+}
+
+/// Recover the secret from shards created by `split`. Duplicate shards are
+/// ignored, and any shards beyond the threshold are not used.
+pub fn join<S: AsRef<[Shard]>>(shards: S) -> Result<zeroize::Zeroizing<Vec<u8>>, Error> {
+    Dealer::recover_secret(join_quorum(shards.as_ref())?)
+}
+
+/// Like `join`, but the secret is written to `writer` as it is recovered
+/// rather than being returned, so large secrets can be recovered with bounded
+/// memory usage (see `Dealer::recover_secret_into`).
+#[cfg(feature = "std")]
+pub fn join_into<S: AsRef<[Shard]>, W: std::io::Write>(shards: S, writer: W) -> Result<(), Error> {
+    Dealer::recover_secret_into(join_quorum(shards.as_ref())?, writer)
+}
+
+/// Pick a quorum of unique shards out of `shards` for `join`, checking that
+/// they are all from the same secret and that there are enough of them.
+fn join_quorum(shards: &[Shard]) -> Result<Vec<Shard>, Error> {
+    let mut unique: Vec<Shard> = Vec::new();
+    for shard in shards {
+        if !unique.iter().any(|s| s.id() == shard.id()) {
+            unique.push(shard.clone());
+        }
+    }
+
+    let threshold = unique.first().map(Shard::threshold).unwrap_or(1);
+    if unique
+        .iter()
+        .any(|s| !s.is_consistent() || !s.is_consistent_with(&unique[0]))
+    {
+        return Err(Error::InconsistentShards);
+    }
+    if unique.len() < threshold as usize {
+        return Err(Error::InsufficientShards {
+            have: unique.len(),
+            need: threshold,
+        });
+    }
+    unique.truncate(threshold as usize);
+
+    Ok(unique)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::{FromWire, ToWire};
+
+    use quickcheck::TestResult;
+
+    #[quickcheck]
+    fn split_join_roundtrip(n: u8, secret: Vec<u8>) -> TestResult {
+        let n = u32::from(n % 16 + 1);
+        let mut shards = split(n, n as usize + 2, &secret).unwrap();
+        // Duplicates are ignored, and any n shards are enough.
+        shards.insert(0, shards[1].clone());
+        shards.remove(2);
+        TestResult::from_bool(*join(&shards).unwrap() == secret)
+    }
+
+    #[quickcheck]
+    fn join_into_matches_join(n: u8, secret: Vec<u8>) -> TestResult {
+        let n = u32::from(n % 16 + 1);
+        let shards = split(n, n as usize + 1, &secret).unwrap();
+        let mut output = Vec::new();
+        join_into(&shards, &mut output).unwrap();
+        TestResult::from_bool(output == *join(&shards).unwrap() && output == secret)
+    }
+
+    #[test]
+    fn join_bad_shards() {
+        let shards = split(3, 3, b"secret").unwrap();
+        assert!(matches!(
+            join(&shards[..2]),
+            Err(Error::InsufficientShards { have: 2, need: 3 })
+        ));
+        assert!(matches!(
+            join([shards[0].clone(), shards[0].clone()]),
+            Err(Error::InsufficientShards { have: 1, need: 3 })
+        ));
+        assert!(matches!(
+            join([&shards[..2], &split(3, 1, b"other secret").unwrap()[..]].concat()),
+            Err(Error::InconsistentShards)
+        ));
+    }
+
+    #[test]
+    fn join_inflated_secret_len() {
+        // A shard claiming a longer secret than its y-values can hold must be
+        // rejected rather than causing a panic.
+        let shards = split(2, 2, b"secret")
+            .unwrap()
+            .into_iter()
+            .map(|mut shard| {
+                shard.secret_len = 4 * shard.ys.len() + 1;
+                shard
+            })
+            .collect::<Vec<_>>();
+        assert!(matches!(join(&shards), Err(Error::InconsistentShards)));
+
+        // The same goes for shards parsed from the wire format.
+        let shards = shards
+            .iter()
+            .map(|shard| Shard::from_wire(shard.to_wire()).unwrap())
+            .collect::<Vec<_>>();
+        assert!(matches!(join(&shards), Err(Error::InconsistentShards)));
+    }
 }
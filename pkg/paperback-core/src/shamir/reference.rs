@@ -0,0 +1,276 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A deliberately naive reference implementation of Shamir Secret Sharing in
+//! `GF(2^32)`, used to cross-check `Dealer` in tests.
+//!
+//! Field elements are treated as plain integers representing polynomials over
+//! `GF(2)`, and every operation is done in the most obvious way possible
+//! (schoolbook carry-less multiplication, bit-by-bit reduction, and inversion
+//! using Fermat's little theorem) without any of the optimisations in `gf`. It
+//! is far too slow for real use, but that makes it easy to verify by reading.
+
+use crate::shamir::{
+    gf::{GfElem, GfElemPrimitive},
+    Dealer, Shard,
+};
+
+use std::mem;
+
+use rand::{CryptoRng, RngCore};
+
+/// `x^32 + x^22 + x^2 + x^1 + 1`, including the `x^32` term.
+const MODULUS: u64 = (1 << 32) | (1 << 22) | (1 << 2) | (1 << 1) | 1;
+
+fn ref_mul(a: u32, b: u32) -> u32 {
+    // Carry-less multiplication.
+    let mut product = 0u64;
+    for i in 0..32 {
+        if (b >> i) & 1 == 1 {
+            product ^= (a as u64) << i;
+        }
+    }
+    // Reduce modulo the characteristic polynomial, one bit at a time.
+    for i in (32..64).rev() {
+        if (product >> i) & 1 == 1 {
+            product ^= MODULUS << (i - 32);
+        }
+    }
+    product as u32
+}
+
+fn ref_pow(a: u32, n: u64) -> u32 {
+    (0..64).rev().fold(1, |acc, i| match (n >> i) & 1 {
+        1 => ref_mul(ref_mul(acc, acc), a),
+        _ => ref_mul(acc, acc),
+    })
+}
+
+fn ref_inverse(a: u32) -> u32 {
+    assert_ne!(a, 0, "zero has no inverse");
+    // a^(2^32 - 1) = 1 for every non-zero a, so a^(2^32 - 2) = a^-1.
+    ref_pow(a, (1 << 32) - 2)
+}
+
+/// Evaluate the polynomial with the given coefficients (lowest degree first)
+/// using Horner's method.
+fn ref_evaluate(coeffs: &[u32], x: u32) -> u32 {
+    coeffs
+        .iter()
+        .rev()
+        .fold(0, |acc, &coeff| ref_mul(acc, x) ^ coeff)
+}
+
+/// Compute the Lagrange basis polynomials for `xs`, evaluated at zero.
+fn ref_lagrange_at_zero(xs: &[u32]) -> Vec<u32> {
+    xs.iter()
+        .enumerate()
+        .map(|(j, &xj)| {
+            xs.iter()
+                .enumerate()
+                .filter(|&(m, _)| m != j)
+                // In GF(2^n), subtraction is xor.
+                .map(|(_, &xm)| ref_mul(xm, ref_inverse(xm ^ xj)))
+                .fold(1, ref_mul)
+        })
+        .collect()
+}
+
+fn secret_chunks(secret: &[u8]) -> Vec<u32> {
+    secret
+        .chunks(mem::size_of::<GfElemPrimitive>())
+        .map(|chunk| {
+            let mut padded = [0u8; mem::size_of::<GfElemPrimitive>()];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            u32::from_le_bytes(padded)
+        })
+        .collect()
+}
+
+/// Split `secret` into one shard for each of `xs`, such that `threshold` of
+/// them are needed to recover the secret.
+pub(super) fn split<R: CryptoRng + RngCore + ?Sized>(
+    threshold: u32,
+    secret: &[u8],
+    xs: &[u32],
+    rng: &mut R,
+) -> Vec<Shard> {
+    let polys = secret_chunks(secret)
+        .into_iter()
+        .map(|constant| {
+            Some(constant)
+                .into_iter()
+                .chain((1..threshold).map(|_| rng.next_u32()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    xs.iter()
+        .map(|&x| {
+            let ys = polys
+                .iter()
+                .map(|coeffs| GfElem::from_inner(ref_evaluate(coeffs, x)))
+                .collect();
+            Shard::new(GfElem::from_inner(x), ys, secret.len(), threshold)
+        })
+        .collect()
+}
+
+/// Recover the secret from exactly `threshold` unique shards.
+pub(super) fn combine(shards: &[Shard]) -> Vec<u8> {
+    let xs = shards
+        .iter()
+        .map(|shard| shard.x.inner())
+        .collect::<Vec<_>>();
+    let basis = ref_lagrange_at_zero(&xs);
+
+    let mut secret = (0..shards[0].ys.len())
+        .flat_map(|i| {
+            shards
+                .iter()
+                .zip(&basis)
+                .map(|(shard, &l)| ref_mul(shard.ys[i].inner(), l))
+                .fold(0, |acc, term| acc ^ term)
+                .to_le_bytes()
+        })
+        .collect::<Vec<_>>();
+    secret.truncate(shards[0].secret_len);
+    secret
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use quickcheck::TestResult;
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    #[quickcheck]
+    fn mul_matches_reference(a: GfElem, b: GfElem) -> bool {
+        (a * b).inner() == ref_mul(a.inner(), b.inner())
+    }
+
+    #[quickcheck]
+    fn inverse_matches_reference(a: GfElem) -> TestResult {
+        if a == GfElem::ZERO {
+            return TestResult::discard();
+        }
+        TestResult::from_bool(a.inverse().map(|inv| inv.inner()) == Some(ref_inverse(a.inner())))
+    }
+
+    #[test]
+    fn reference_sanity() {
+        // x * x^31 = x^32 = x^22 + x^2 + x + 1.
+        assert_eq!(ref_mul(1 << 1, 1 << 31), (1 << 22) | 0b111);
+        for a in [1, 2, 3, 0xdead_beef, u32::MAX] {
+            assert_eq!(ref_mul(a, ref_inverse(a)), 1);
+        }
+    }
+
+    // Limit the quorum sizes because the reference implementation is slow.
+    const MAX_THRESHOLD: u8 = 12;
+
+    /// Shards created by `Dealer` can be recovered by the reference
+    /// implementation (using a random subset of the shards).
+    #[quickcheck]
+    fn dealer_to_reference(n: u8, extra: u8, secret: Vec<u8>, seed: u64) -> TestResult {
+        if !(1..=MAX_THRESHOLD).contains(&n) {
+            return TestResult::discard();
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let dealer = Dealer::new_with_rng(n.into(), &secret, &mut rng);
+        let shards = (0..n as usize + extra as usize % 8)
+            .map(|_| dealer.next_shard_with_rng(&mut rng))
+            .collect::<Vec<_>>();
+        let subset = shards
+            .choose_multiple(&mut rng, n.into())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        TestResult::from_bool(
+            combine(&subset) == secret
+                && *Dealer::recover_secret(&subset).expect("recover secret") == secret,
+        )
+    }
+
+    /// Shards created by the reference implementation can be recovered by
+    /// `Dealer` (using a random subset of the shards).
+    #[quickcheck]
+    fn reference_to_dealer(n: u8, extra: u8, secret: Vec<u8>, seed: u64) -> TestResult {
+        if !(1..=MAX_THRESHOLD).contains(&n) {
+            return TestResult::discard();
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut xs = (0..n as usize + extra as usize % 8)
+            .map(|_| rng.next_u32())
+            .filter(|&x| x != 0)
+            .collect::<Vec<_>>();
+        xs.sort_unstable();
+        xs.dedup();
+        if xs.len() < n.into() {
+            return TestResult::discard();
+        }
+        let shards = split(n.into(), &secret, &xs, &mut rng);
+        let subset = shards
+            .choose_multiple(&mut rng, n.into())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        TestResult::from_bool(
+            *Dealer::recover_secret(&subset).expect("recover secret") == secret
+                && combine(&subset) == secret,
+        )
+    }
+
+    /// New shards created by a recovered `Dealer` match the reference
+    /// implementation's evaluation of the same polynomials.
+    #[quickcheck]
+    fn recovered_dealer_matches_reference(
+        n: u8,
+        secret: Vec<u8>,
+        x: GfElem,
+        seed: u64,
+    ) -> TestResult {
+        if !(1..=MAX_THRESHOLD).contains(&n) || x == GfElem::ZERO {
+            return TestResult::discard();
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut xs = (0..n)
+            .map(|_| rng.next_u32())
+            .filter(|&v| v != 0)
+            .collect::<Vec<_>>();
+        xs.push(x.inner());
+        xs.sort_unstable();
+        xs.dedup();
+        if xs.len() < n as usize + 1 {
+            return TestResult::discard();
+        }
+        // The reference shards agree on every x (including the extra one),
+        // since they all come from the same polynomials.
+        let shards = split(n.into(), &secret, &xs, &mut rng);
+        let (expected, subset): (Vec<_>, Vec<_>) =
+            shards.into_iter().partition(|shard| shard.x == x);
+        let dealer = Dealer::recover(&subset[..n as usize]).expect("recover dealer");
+
+        match dealer.shard(x) {
+            Some(shard) => TestResult::from_bool(shard.ys == expected[0].ys),
+            // The dealer refuses to create shards which leak the secret.
+            None => TestResult::discard(),
+        }
+    }
+}
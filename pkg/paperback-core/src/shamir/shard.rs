@@ -18,10 +18,18 @@
 
 use crate::{
     shamir::gf::{GfElem, GfElemPrimitive},
-    v0::{FromWire, ShardId, ToWire},
+    v0::{
+        wire::{prefixes::PREFIX_SHARD_INTEGRITY_CODE, varuint_nom},
+        FromWire, ShardId, ToWire,
+    },
 };
 
-use unsigned_varint::{encode as varuint_encode, nom as varuint_nom};
+use alloc::{borrow::ToOwned, format, string::String, vec, vec::Vec};
+use core::mem;
+
+use multihash_codetable::{Code, MultihashDigest};
+use unsigned_varint::encode as varuint_encode;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Piece of a secret which has been sharded with [Shamir Secret Sharing][sss].
 ///
@@ -32,17 +40,100 @@ pub struct Shard {
     pub(super) ys: Vec<GfElem>,
     pub(super) secret_len: usize,
     pub(super) threshold: GfElemPrimitive,
+    // Shards created by older versions of paperback have no integrity code.
+    pub(super) integrity_code: Option<IntegrityCode>,
 }
 
+const INTEGRITY_CODE_LENGTH: usize = 8;
+
+/// Truncated Blake2b-256 hash of a shard's evaluation points, threshold and
+/// secret length, used to detect corrupted shards before they are used for
+/// interpolation.
+pub(super) type IntegrityCode = [u8; INTEGRITY_CODE_LENGTH];
+
 impl Shard {
     pub const ID_LENGTH: usize = 8;
 
+    /// Construct a new `Shard` with the given evaluation points, along with a
+    /// matching integrity code.
+    #[cfg(feature = "std")]
+    pub(super) fn new(
+        x: GfElem,
+        ys: Vec<GfElem>,
+        secret_len: usize,
+        threshold: GfElemPrimitive,
+    ) -> Self {
+        let mut shard = Shard {
+            x,
+            ys,
+            secret_len,
+            threshold,
+            integrity_code: None,
+        };
+        shard.integrity_code = Some(shard.compute_integrity_code());
+        shard
+    }
+
+    fn compute_integrity_code(&self) -> IntegrityCode {
+        let bytes = Some(self.x)
+            .iter()
+            .chain(&self.ys)
+            .flat_map(|elem| elem.inner().to_le_bytes())
+            .chain(self.threshold.to_le_bytes())
+            .chain((self.secret_len as u64).to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let mut code = IntegrityCode::default();
+        code.copy_from_slice(&Code::Blake2b256.digest(&bytes).digest()[..INTEGRITY_CODE_LENGTH]);
+        code
+    }
+
+    /// Returns whether the shard's evaluation points, threshold or secret
+    /// length no longer match its integrity code (meaning that the shard was
+    /// corrupted).
+    ///
+    /// Shards without an integrity code (created by older versions of
+    /// paperback) are never considered corrupted.
+    pub fn is_corrupted(&self) -> bool {
+        match self.integrity_code {
+            Some(code) => code != self.compute_integrity_code(),
+            None => false,
+        }
+    }
+
+    /// Returns whether the shard's threshold and secret length make sense for
+    /// its evaluation points. The secret is stored in 4-byte chunks, so the
+    /// secret cannot be longer than the y-values.
+    pub(super) fn is_consistent(&self) -> bool {
+        self.threshold != 0
+            && self.secret_len
+                <= self
+                    .ys
+                    .len()
+                    .saturating_mul(mem::size_of::<GfElemPrimitive>())
+    }
+
+    /// Returns whether the shard could be used with `other` to recover the
+    /// same secret.
+    pub(super) fn is_consistent_with(&self, other: &Shard) -> bool {
+        self.threshold == other.threshold
+            && self.secret_len == other.secret_len
+            && self.ys.len() == other.ys.len()
+    }
+
     /// Returns the *unique* identifier for a given `Shard`.
     ///
     /// If two shards have the same identifier, they cannot be used together for
     /// secret recovery.
     pub fn id(&self) -> ShardId {
-        multibase::encode(multibase::Base::Base32Z, self.x.to_bytes())
+        ShardId::from_x_value(self.x.inner())
+    }
+
+    /// Returns the same identifier as `Shard::id`, but written as English
+    /// BIP-39 words (which are harder to mis-transcribe).
+    #[cfg(feature = "std")]
+    pub fn friendly_id(&self) -> String {
+        crate::v0::codewords::friendly_id(self.x.inner())
     }
 
     /// Returns the number of *unique* sister `Shard`s required to recover the
@@ -52,9 +143,20 @@ impl Shard {
     }
 }
 
-pub fn parse_id(id: ShardId) -> Result<GfElem, multibase::Error> {
-    let (_, data) = multibase::decode(id)?;
-    Ok(GfElem::from_bytes(data))
+// A quorum of shards is enough to recover the secret, so wipe the y-values once
+// the shard is no longer needed.
+impl Drop for Shard {
+    fn drop(&mut self) {
+        self.ys.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Shard {}
+
+/// Get the x-value of the shard with the given identifier (see `Shard::id`).
+#[cfg(feature = "std")]
+pub fn parse_id(id: &ShardId) -> GfElem {
+    GfElem::from_inner(id.x_value())
 }
 
 impl ToWire for Shard {
@@ -85,13 +187,30 @@ impl ToWire for Shard {
             .iter()
             .for_each(|b| bytes.push(*b));
 
+        // Encode integrity code (if present).
+        if let Some(code) = self.integrity_code {
+            varuint_encode::u64(
+                PREFIX_SHARD_INTEGRITY_CODE,
+                &mut varuint_encode::u64_buffer(),
+            )
+            .iter()
+            .chain(&code)
+            .for_each(|b| bytes.push(*b));
+        }
+
         bytes
     }
 }
 
 impl FromWire for Shard {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
-        use nom::{combinator::complete, multi::many_m_n, IResult};
+        use nom::{
+            bytes::streaming::take,
+            combinator::{complete, opt, verify},
+            multi::many_m_n,
+            sequence::preceded,
+            IResult,
+        };
 
         fn parse(input: &[u8]) -> IResult<&[u8], Shard> {
             let (input, x) = varuint_nom::u32(input)?;
@@ -108,6 +227,18 @@ impl FromWire for Shard {
             let (input, threshold) = varuint_nom::u32(input)?;
             let (input, secret_len) = varuint_nom::usize(input)?;
 
+            // Integrity codes were added to the format later, so shards from
+            // older paperback versions lack them.
+            let (input, integrity_code) = opt(complete(preceded(
+                verify(varuint_nom::u64, |x| *x == PREFIX_SHARD_INTEGRITY_CODE),
+                take(INTEGRITY_CODE_LENGTH),
+            )))(input)?;
+            let integrity_code = integrity_code.map(|code: &[u8]| {
+                let mut buffer = IntegrityCode::default();
+                buffer.copy_from_slice(code);
+                buffer
+            });
+
             Ok((
                 input,
                 Shard {
@@ -115,6 +246,7 @@ impl FromWire for Shard {
                     ys,
                     secret_len,
                     threshold,
+                    integrity_code,
                 },
             ))
         }
@@ -129,12 +261,17 @@ impl FromWire for Shard {
 #[cfg(test)]
 impl quickcheck::Arbitrary for Shard {
     fn arbitrary(g: &mut quickcheck::Gen) -> Self {
-        Self {
-            x: GfElem::arbitrary(g),
-            ys: (0..g.size()).map(|_| GfElem::arbitrary(g)).collect(),
-            secret_len: usize::arbitrary(g),
-            threshold: u32::arbitrary(g),
+        let mut shard = Self::new(
+            GfElem::arbitrary(g),
+            (0..g.size()).map(|_| GfElem::arbitrary(g)).collect(),
+            usize::arbitrary(g),
+            u32::arbitrary(g),
+        );
+        // Make sure we also test shards from older paperback versions.
+        if bool::arbitrary(g) {
+            shard.integrity_code = None;
         }
+        shard
     }
 }
 
@@ -142,9 +279,41 @@ impl quickcheck::Arbitrary for Shard {
 mod test {
     use super::*;
 
+    use quickcheck::TestResult;
+
     #[quickcheck]
     fn shard_bytes_roundtrip(shard: Shard) -> bool {
         let shard2 = Shard::from_wire(shard.to_wire()).unwrap();
         shard == shard2
     }
+
+    #[quickcheck]
+    fn shard_corruption_detected(shard: Shard, idx: usize, delta: GfElemPrimitive) -> TestResult {
+        if shard.ys.is_empty() || delta == 0 {
+            return TestResult::discard();
+        }
+        let mut shard = shard;
+        let has_code = shard.integrity_code.is_some();
+        assert!(
+            !shard.is_corrupted(),
+            "unmodified shard must not be corrupted"
+        );
+
+        let idx = idx % shard.ys.len();
+        shard.ys[idx] = GfElem::from_inner(shard.ys[idx].inner() ^ delta);
+        TestResult::from_bool(shard.is_corrupted() == has_code)
+    }
+
+    #[quickcheck]
+    fn shard_metadata_corruption_detected(shard: Shard, delta: u32) -> TestResult {
+        if shard.integrity_code.is_none() || delta == 0 {
+            return TestResult::discard();
+        }
+
+        let mut bad_threshold = shard.clone();
+        bad_threshold.threshold ^= delta;
+        let mut bad_secret_len = shard;
+        bad_secret_len.secret_len ^= delta as usize;
+        TestResult::from_bool(bad_threshold.is_corrupted() && bad_secret_len.is_corrupted())
+    }
 }
@@ -0,0 +1,143 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The Feistel cipher used by SLIP-0039 to encrypt the master secret with a
+//! passphrase before it is split into shares.
+
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// Number of Feistel rounds.
+const ROUND_COUNT: u8 = 4;
+/// Total number of PBKDF2 iterations (across all rounds) for an iteration
+/// exponent of 0.
+const BASE_ITERATION_COUNT: u32 = 10000;
+
+fn round_function(
+    round: u8,
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    salt_prefix: &[u8],
+    block: &[u8],
+) -> Zeroizing<Vec<u8>> {
+    let password = Zeroizing::new([&[round][..], passphrase].concat());
+    let salt = Zeroizing::new([salt_prefix, block].concat());
+    let mut output = Zeroizing::new(vec![0; block.len()]);
+    pbkdf2_hmac::<Sha256>(
+        &password,
+        &salt,
+        (BASE_ITERATION_COUNT << iteration_exponent) / u32::from(ROUND_COUNT),
+        &mut output,
+    );
+    output
+}
+
+fn salt_prefix(identifier: u16, extendable: bool) -> Vec<u8> {
+    // Extendable backups don't include the identifier, so that new shares
+    // (with a different identifier) can be created for the same master
+    // secret.
+    match extendable {
+        true => vec![],
+        false => [&b"shamir"[..], &identifier.to_be_bytes()].concat(),
+    }
+}
+
+fn feistel<R: Iterator<Item = u8>>(
+    input: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+    rounds: R,
+) -> Zeroizing<Vec<u8>> {
+    let salt_prefix = salt_prefix(identifier, extendable);
+    let (left, right) = input.split_at(input.len() / 2);
+    let (mut left, mut right) = (
+        Zeroizing::new(left.to_vec()),
+        Zeroizing::new(right.to_vec()),
+    );
+    for round in rounds {
+        let f = round_function(round, passphrase, iteration_exponent, &salt_prefix, &right);
+        left.iter_mut().zip(f.iter()).for_each(|(l, f)| *l ^= f);
+        std::mem::swap(&mut left, &mut right);
+    }
+    Zeroizing::new([right.as_slice(), left.as_slice()].concat())
+}
+
+/// Encrypt `master_secret` (which must have an even length) with
+/// `passphrase`.
+pub(super) fn encrypt(
+    master_secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+        0..ROUND_COUNT,
+    )
+}
+
+/// Decrypt a master secret encrypted with `encrypt`. There is no way to tell
+/// whether the passphrase was correct -- a different passphrase simply results
+/// in a different master secret.
+pub(super) fn decrypt(
+    encrypted_master_secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        encrypted_master_secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+        (0..ROUND_COUNT).rev(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use quickcheck::TestResult;
+
+    #[quickcheck]
+    fn encrypt_decrypt_roundtrip(
+        secret: Vec<u8>,
+        passphrase: Vec<u8>,
+        identifier: u16,
+        extendable: bool,
+    ) -> TestResult {
+        if !secret.len().is_multiple_of(2) {
+            return TestResult::discard();
+        }
+
+        let encrypted = encrypt(&secret, &passphrase, 0, identifier, extendable);
+        let decrypted = decrypt(&encrypted, &passphrase, 0, identifier, extendable);
+        TestResult::from_bool(decrypted.as_slice() == secret.as_slice())
+    }
+}
@@ -0,0 +1,436 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This package implements [SLIP-0039][slip39] (the "Shamir Backup" scheme
+//! used by Trezor wallets), so that secrets already split into SLIP-0039 shares
+//! can be moved into a paperback backup (and vice versa).
+//!
+//! SLIP-0039 is a completely separate scheme from paperback (shares from the
+//! two schemes cannot be mixed), so converting between them always requires
+//! recovering the secret first.
+//!
+//! [slip39]: https://github.com/satoshilabs/slips/blob/master/slip-0039.md
+
+mod cipher;
+mod rs1024;
+mod shamir;
+mod share;
+mod wordlist;
+
+pub use share::Share;
+
+use std::collections::BTreeMap;
+
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unknown SLIP-0039 word {0:?}")]
+    UnknownWord(String),
+
+    #[error("invalid SLIP-0039 mnemonic length ({0} words)")]
+    InvalidLength(usize),
+
+    #[error("invalid SLIP-0039 mnemonic checksum (check for typos or missing words)")]
+    InvalidChecksum,
+
+    #[error("invalid SLIP-0039 mnemonic padding")]
+    InvalidPadding,
+
+    #[error(
+        "master secret must be an even number of bytes, and at least {} bytes long (is {} bytes)",
+        MIN_SECRET_LENGTH,
+        .0
+    )]
+    InvalidSecretLength(usize),
+
+    #[error("passphrase must only contain printable ASCII characters")]
+    InvalidPassphrase,
+
+    #[error("invalid SLIP-0039 sharing policy: {0}")]
+    InvalidPolicy(String),
+
+    #[error("SLIP-0039 shares do not belong together: {0}")]
+    MismatchedShares(&'static str),
+
+    #[error("not enough SLIP-0039 shares to recover the master secret: {0}")]
+    InsufficientShares(String),
+
+    #[error(
+        "SLIP-0039 share digest mismatch (the shares are corrupted or do not belong together)"
+    )]
+    InvalidDigest,
+}
+
+/// The minimum length (in bytes) of a master secret.
+pub const MIN_SECRET_LENGTH: usize = 16;
+
+/// The default iteration exponent used by `split_secret`, matching the
+/// SLIP-0039 reference implementation.
+pub const DEFAULT_ITERATION_EXPONENT: u8 = 1;
+
+/// The sharing policy of a single group of shares: `threshold` of the `count`
+/// shares in the group are required to recover the group's secret.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GroupPolicy {
+    pub threshold: u8,
+    pub count: u8,
+}
+
+impl GroupPolicy {
+    pub fn new(threshold: u8, count: u8) -> Self {
+        Self { threshold, count }
+    }
+}
+
+fn check_passphrase(passphrase: &[u8]) -> Result<(), Error> {
+    match passphrase.iter().all(|b| (32..=126).contains(b)) {
+        true => Ok(()),
+        false => Err(Error::InvalidPassphrase),
+    }
+}
+
+/// Split `master_secret` into groups of SLIP-0039 shares, encrypted with
+/// `passphrase` (which may be empty).
+///
+/// `group_threshold` of the groups are required to recover the master secret,
+/// and each group is split according to its `GroupPolicy`. The returned shares
+/// are in the same order as `groups`. The shares are not extendable, so that
+/// they can be recovered by implementations which predate extendable backups.
+pub fn split_secret(
+    group_threshold: u8,
+    groups: &[GroupPolicy],
+    master_secret: &[u8],
+    passphrase: &[u8],
+) -> Result<Vec<Vec<Share>>, Error> {
+    split_secret_with_rng(
+        group_threshold,
+        groups,
+        master_secret,
+        passphrase,
+        DEFAULT_ITERATION_EXPONENT,
+        &mut OsRng,
+    )
+}
+
+/// Like `split_secret`, but with a custom iteration exponent (the passphrase
+/// is stretched with `10000 * 2^iteration_exponent` PBKDF2 iterations) and
+/// with all randomness generated by `rng`.
+pub fn split_secret_with_rng<R: CryptoRng + RngCore + ?Sized>(
+    group_threshold: u8,
+    groups: &[GroupPolicy],
+    master_secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    rng: &mut R,
+) -> Result<Vec<Vec<Share>>, Error> {
+    if master_secret.len() < MIN_SECRET_LENGTH || !master_secret.len().is_multiple_of(2) {
+        return Err(Error::InvalidSecretLength(master_secret.len()));
+    }
+    check_passphrase(passphrase)?;
+    if iteration_exponent > 0xf {
+        return Err(Error::InvalidPolicy(format!(
+            "iteration exponent {} is too large (at most 15)",
+            iteration_exponent
+        )));
+    }
+    if group_threshold as usize > groups.len() {
+        return Err(Error::InvalidPolicy(format!(
+            "group threshold ({}) cannot be greater than the number of groups ({})",
+            group_threshold,
+            groups.len()
+        )));
+    }
+    if groups
+        .iter()
+        .any(|group| group.threshold == 1 && group.count > 1)
+    {
+        return Err(Error::InvalidPolicy(
+            "groups with a threshold of 1 must contain only one share".into(),
+        ));
+    }
+    let group_count = u8::try_from(groups.len())
+        .ok()
+        .filter(|count| *count <= shamir::MAX_SHARE_COUNT)
+        .ok_or_else(|| {
+            Error::InvalidPolicy(format!(
+                "at most {} groups can be created",
+                shamir::MAX_SHARE_COUNT
+            ))
+        })?;
+
+    let identifier = (rng.next_u32() & 0x7fff) as u16;
+    let extendable = false;
+    let encrypted_master_secret = cipher::encrypt(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+    );
+
+    let group_secrets =
+        shamir::split_secret(group_threshold, group_count, &encrypted_master_secret, rng)?;
+    groups
+        .iter()
+        .zip(group_secrets)
+        .map(|(group, (group_index, group_secret))| {
+            Ok(
+                shamir::split_secret(group.threshold, group.count, &group_secret, rng)?
+                    .into_iter()
+                    .map(|(member_index, value)| Share {
+                        identifier,
+                        extendable,
+                        iteration_exponent,
+                        group_index,
+                        group_threshold,
+                        group_count,
+                        member_index,
+                        member_threshold: group.threshold,
+                        value,
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Recover the master secret from a set of SLIP-0039 shares, decrypting it
+/// with `passphrase`.
+///
+/// Extra shares (beyond the threshold of each group, or beyond the group
+/// threshold) are ignored. Note that any passphrase will result in *some*
+/// master secret, so a mistyped passphrase cannot be detected.
+pub fn combine_shares(shares: &[Share], passphrase: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+    check_passphrase(passphrase)?;
+    let first = shares
+        .first()
+        .ok_or_else(|| Error::InsufficientShares("no shares provided".into()))?;
+    if shares.iter().any(|share| {
+        (
+            share.identifier,
+            share.extendable,
+            share.iteration_exponent,
+            share.group_threshold,
+            share.group_count,
+            share.value.len(),
+        ) != (
+            first.identifier,
+            first.extendable,
+            first.iteration_exponent,
+            first.group_threshold,
+            first.group_count,
+            first.value.len(),
+        )
+    }) {
+        return Err(Error::MismatchedShares(
+            "shares have different identifiers or parameters",
+        ));
+    }
+
+    // Group the shares, ignoring duplicates.
+    let mut groups = BTreeMap::<u8, (u8, Vec<shamir::RawShare>)>::new();
+    for share in shares {
+        let (member_threshold, members) = groups
+            .entry(share.group_index)
+            .or_insert_with(|| (share.member_threshold, vec![]));
+        if *member_threshold != share.member_threshold {
+            return Err(Error::MismatchedShares(
+                "shares in the same group have different thresholds",
+            ));
+        }
+        match members.iter().find(|(x, _)| *x == share.member_index) {
+            Some((_, value)) if *value != share.value => {
+                return Err(Error::MismatchedShares(
+                    "different shares have the same member index",
+                ))
+            }
+            Some(_) => (),
+            None => members.push((share.member_index, share.value.clone())),
+        }
+    }
+
+    let group_secrets = groups
+        .into_iter()
+        .filter(|(_, (threshold, members))| members.len() >= usize::from(*threshold))
+        .take(usize::from(first.group_threshold))
+        .map(|(group_index, (threshold, members))| {
+            Ok((
+                group_index,
+                shamir::recover_secret(threshold, &members[..usize::from(threshold)])?,
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    if group_secrets.len() < usize::from(first.group_threshold) {
+        return Err(Error::InsufficientShares(format!(
+            "{} complete groups are required, but only {} were provided",
+            first.group_threshold,
+            group_secrets.len()
+        )));
+    }
+
+    let encrypted_master_secret = shamir::recover_secret(first.group_threshold, &group_secrets)?;
+    Ok(cipher::decrypt(
+        &encrypted_master_secret,
+        passphrase,
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+    ))
+}
+
+/// Like `combine_shares`, but parses the shares from their mnemonics first.
+pub fn combine_mnemonics<S: AsRef<str>>(
+    mnemonics: &[S],
+    passphrase: &[u8],
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let shares = mnemonics
+        .iter()
+        .map(Share::from_mnemonic)
+        .collect::<Result<Vec<_>, _>>()?;
+    combine_shares(&shares, passphrase)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use quickcheck::TestResult;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // Test vectors from the SLIP-0039 specification (all with the passphrase
+    // "TREZOR").
+    const TEST_VECTORS: &[(&[&str], &str)] = &[
+        (
+            &["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"],
+            "bb54aac4b89dc868ba37d9cc21b2cece",
+        ),
+        (
+            &[
+                "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+                "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+            ],
+            "b43ceb7e57a0ea8766221624d01b0864",
+        ),
+        (
+            &["theory painting academic academic armed sweater year military elder discuss acne wildlife boring employer fused large satoshi bundle carbon diagnose anatomy hamster leaves tracks paces beyond phantom capital marvel lips brave detect luck"],
+            "989baf9dcaad5b10ca33dfd8cc75e42477025dce88ae83e75a230086a0e00e92",
+        ),
+    ];
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn slip39_test_vectors() {
+        for (mnemonics, master_secret) in TEST_VECTORS {
+            let recovered = combine_mnemonics(mnemonics, b"TREZOR").unwrap();
+            assert_eq!(to_hex(&recovered), *master_secret);
+
+            // The shares should also survive a round-trip through Share.
+            for mnemonic in mnemonics.iter() {
+                let share = Share::from_mnemonic(mnemonic).unwrap();
+                assert_eq!(share.to_mnemonic().as_str(), *mnemonic);
+            }
+        }
+    }
+
+    #[test]
+    fn slip39_insufficient_shares() {
+        let (mnemonics, _) = TEST_VECTORS[1];
+        assert!(matches!(
+            combine_mnemonics(&mnemonics[..1], b"TREZOR"),
+            Err(Error::InsufficientShares(_))
+        ));
+    }
+
+    #[quickcheck]
+    fn split_combine_roundtrip(
+        group_threshold: u8,
+        groups: Vec<(u8, u8)>,
+        master_secret: Vec<u8>,
+        seed: u64,
+    ) -> TestResult {
+        if master_secret.len() < MIN_SECRET_LENGTH || !master_secret.len().is_multiple_of(2) {
+            return TestResult::discard();
+        }
+        let groups = groups
+            .into_iter()
+            .take(4)
+            .map(|(threshold, count)| {
+                let count = count % 4 + 1;
+                let threshold = match count {
+                    1 => 1,
+                    _ => threshold % (count - 1) + 2,
+                };
+                GroupPolicy::new(threshold, count)
+            })
+            .collect::<Vec<_>>();
+        if groups.is_empty() {
+            return TestResult::discard();
+        }
+        let group_threshold = group_threshold % groups.len() as u8 + 1;
+
+        let shares = split_secret_with_rng(
+            group_threshold,
+            &groups,
+            &master_secret,
+            b"passphrase",
+            0,
+            &mut StdRng::seed_from_u64(seed),
+        )
+        .unwrap();
+
+        // Take the last threshold shares of the last group_threshold groups,
+        // going through the mnemonic encoding.
+        let mnemonics = shares
+            .iter()
+            .zip(&groups)
+            .rev()
+            .take(group_threshold.into())
+            .flat_map(|(shares, group)| shares.iter().rev().take(group.threshold.into()))
+            .map(Share::to_mnemonic)
+            .collect::<Vec<_>>();
+        let recovered = combine_mnemonics(&mnemonics, b"passphrase").unwrap();
+        TestResult::from_bool(recovered.as_slice() == master_secret.as_slice())
+    }
+
+    #[test]
+    fn split_secret_errors() {
+        let secret = [0u8; 16];
+        let one_group = [GroupPolicy::new(2, 3)];
+        assert!(matches!(
+            split_secret(1, &one_group, &secret[..15], b""),
+            Err(Error::InvalidSecretLength(15))
+        ));
+        assert!(matches!(
+            split_secret(2, &one_group, &secret, b""),
+            Err(Error::InvalidPolicy(_))
+        ));
+        assert!(matches!(
+            split_secret(1, &[GroupPolicy::new(1, 2)], &secret, b""),
+            Err(Error::InvalidPolicy(_))
+        ));
+        assert!(matches!(
+            split_secret(1, &one_group, &secret, "\u{e9}".as_bytes()),
+            Err(Error::InvalidPassphrase)
+        ));
+    }
+}
@@ -0,0 +1,98 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The RS1024 checksum used by SLIP-0039 mnemonics, which is a Reed-Solomon
+//! code over `GF(1024)` that detects any error affecting at most 3 words.
+
+/// Number of checksum words appended to each mnemonic.
+pub(super) const CHECKSUM_WORDS: usize = 3;
+
+const GENERATOR: [u32; 10] = [
+    0x00e0_e040,
+    0x01c1_c080,
+    0x0383_8100,
+    0x0707_0200,
+    0x0e0e_0009,
+    0x1c0c_2412,
+    0x3808_6c24,
+    0x3090_fc48,
+    0x21b1_f890,
+    0x03f3_f120,
+];
+
+fn polymod<I: IntoIterator<Item = u32>>(values: I) -> u32 {
+    values.into_iter().fold(1, |chk, value| {
+        let top = chk >> 20;
+        let chk = ((chk & 0xf_ffff) << 10) ^ value;
+        GENERATOR
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(chk, |chk, (_, gen)| chk ^ gen)
+    })
+}
+
+fn with_customization<'a>(
+    customization: &'a [u8],
+    data: &'a [u16],
+) -> impl Iterator<Item = u32> + 'a {
+    customization
+        .iter()
+        .map(|b| u32::from(*b))
+        .chain(data.iter().map(|w| u32::from(*w)))
+}
+
+/// Compute the checksum words for `data` (the share words, excluding the
+/// checksum).
+pub(super) fn create_checksum(customization: &[u8], data: &[u16]) -> [u16; CHECKSUM_WORDS] {
+    let chk = polymod(with_customization(customization, data).chain([0; CHECKSUM_WORDS])) ^ 1;
+    [
+        ((chk >> 20) & 0x3ff) as u16,
+        ((chk >> 10) & 0x3ff) as u16,
+        (chk & 0x3ff) as u16,
+    ]
+}
+
+/// Check the checksum of `data` (the share words, including the checksum).
+pub(super) fn verify_checksum(customization: &[u8], data: &[u16]) -> bool {
+    polymod(with_customization(customization, data)) == 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[quickcheck]
+    fn checksum_roundtrip(data: Vec<u16>) -> bool {
+        let mut data = data.into_iter().map(|w| w & 0x3ff).collect::<Vec<_>>();
+        data.extend_from_slice(&create_checksum(b"shamir", &data));
+        verify_checksum(b"shamir", &data) && !verify_checksum(b"shamir_extendable", &data)
+    }
+
+    #[quickcheck]
+    fn checksum_detects_errors(data: Vec<u16>, index: usize, error: u16) -> bool {
+        let mut data = data.into_iter().map(|w| w & 0x3ff).collect::<Vec<_>>();
+        data.extend_from_slice(&create_checksum(b"shamir", &data));
+
+        // Corrupt a single word.
+        let index = index % data.len();
+        let error = (error & 0x3ff).max(1);
+        data[index] ^= error;
+        !verify_checksum(b"shamir", &data)
+    }
+}
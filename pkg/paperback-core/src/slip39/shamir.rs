@@ -0,0 +1,229 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The Shamir Secret Sharing scheme used by SLIP-0039, which operates on each
+//! byte of the secret in `GF(256)` (with the Rijndael polynomial
+//! `x^8 + x^4 + x^3 + x + 1`).
+//!
+//! This is entirely separate from paperback's own `GF(2^32)` implementation
+//! (see `crate::shamir`), since the two schemes are not compatible.
+
+use crate::slip39::Error;
+
+use hmac::{Hmac, Mac};
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// x-value of the share containing the digest of the secret.
+const DIGEST_INDEX: u8 = 254;
+/// x-value of the share containing the secret.
+const SECRET_INDEX: u8 = 255;
+/// Length of the digest stored in the digest share.
+const DIGEST_LENGTH: usize = 4;
+
+/// Maximum number of shares that can be created from a single secret.
+pub(super) const MAX_SHARE_COUNT: u8 = 16;
+
+struct Tables {
+    exp: [u8; 255],
+    log: [u8; 256],
+}
+
+static TABLES: Tables = {
+    let mut exp = [0; 255];
+    let mut log = [0; 256];
+    // 3 is a generator of GF(256) with the Rijndael polynomial.
+    let mut poly: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        poly ^= poly << 1;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+        i += 1;
+    }
+    Tables { exp, log }
+};
+
+/// A single share, as an (x, y) pair where each byte of y is a separate
+/// y-value.
+pub(super) type RawShare = (u8, Zeroizing<Vec<u8>>);
+
+/// Compute the value of the polynomial passing through `shares` at `x`, using
+/// Lagrange interpolation.
+pub(super) fn interpolate(shares: &[RawShare], x: u8) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let length = shares.first().map(|(_, y)| y.len()).unwrap_or_default();
+    if shares.iter().any(|(_, y)| y.len() != length) {
+        return Err(Error::MismatchedShares(
+            "share values have different lengths",
+        ));
+    }
+    for (i, (xi, _)) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|(xj, _)| xi == xj) {
+            return Err(Error::MismatchedShares("share indices must be unique"));
+        }
+    }
+    if let Some((_, y)) = shares.iter().find(|(xi, _)| *xi == x) {
+        return Ok(y.clone());
+    }
+
+    let log = |v: u8| usize::from(TABLES.log[usize::from(v)]);
+    let log_product: usize = shares.iter().map(|(xi, _)| log(xi ^ x)).sum();
+
+    let mut result = Zeroizing::new(vec![0; length]);
+    for (xi, y) in shares {
+        // log of the Lagrange basis polynomial for this share, evaluated at x.
+        let log_denominator: usize = shares
+            .iter()
+            .filter(|(xj, _)| xj != xi)
+            .map(|(xj, _)| log(xj ^ xi))
+            .sum();
+        let log_basis = (log_product - log(xi ^ x) + 255 * shares.len() - log_denominator) % 255;
+        for (r, v) in result.iter_mut().zip(y.iter()) {
+            if *v != 0 {
+                *r ^= TABLES.exp[(log(*v) + log_basis) % 255];
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn digest(random: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(random).expect("HMAC accepts keys of any length");
+    mac.update(secret);
+    let mut digest = [0; DIGEST_LENGTH];
+    digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LENGTH]);
+    digest
+}
+
+/// Split `secret` into `count` shares, any `threshold` of which can be used to
+/// recover it with `recover_secret`.
+pub(super) fn split_secret<R: CryptoRng + RngCore + ?Sized>(
+    threshold: u8,
+    count: u8,
+    secret: &[u8],
+    rng: &mut R,
+) -> Result<Vec<RawShare>, Error> {
+    if threshold == 0 || threshold > count || count > MAX_SHARE_COUNT {
+        return Err(Error::InvalidPolicy(format!(
+            "cannot create {} shares with a threshold of {} (at most {} shares can be created)",
+            count, threshold, MAX_SHARE_COUNT
+        )));
+    }
+
+    if threshold == 1 {
+        return Ok((0..count)
+            .map(|x| (x, Zeroizing::new(secret.to_vec())))
+            .collect());
+    }
+
+    // The first (threshold - 2) shares are random, and the last two points
+    // needed to define the polynomial are the digest and the secret.
+    let mut shares = (0..threshold - 2)
+        .map(|x| {
+            let mut y = Zeroizing::new(vec![0; secret.len()]);
+            rng.fill_bytes(&mut y);
+            (x, y)
+        })
+        .collect::<Vec<_>>();
+
+    let mut digest_share = Zeroizing::new(vec![0; secret.len()]);
+    rng.fill_bytes(&mut digest_share[DIGEST_LENGTH..]);
+    let digest = digest(&digest_share[DIGEST_LENGTH..], secret);
+    digest_share[..DIGEST_LENGTH].copy_from_slice(&digest);
+
+    let mut base_shares = shares.clone();
+    base_shares.push((DIGEST_INDEX, digest_share));
+    base_shares.push((SECRET_INDEX, Zeroizing::new(secret.to_vec())));
+
+    for x in threshold - 2..count {
+        shares.push((x, interpolate(&base_shares, x)?));
+    }
+    Ok(shares)
+}
+
+/// Recover the secret from exactly `threshold` shares created by
+/// `split_secret`, checking its digest.
+pub(super) fn recover_secret(
+    threshold: u8,
+    shares: &[RawShare],
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+    if threshold == 1 {
+        return shares
+            .first()
+            .map(|(_, y)| y.clone())
+            .ok_or_else(|| Error::InsufficientShares("no shares provided".into()));
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX)?;
+    let digest_share = interpolate(shares, DIGEST_INDEX)?;
+    if digest_share.len() < DIGEST_LENGTH
+        || digest_share[..DIGEST_LENGTH] != digest(&digest_share[DIGEST_LENGTH..], &secret)
+    {
+        return Err(Error::InvalidDigest);
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use quickcheck::TestResult;
+
+    #[test]
+    fn tables_are_inverses() {
+        for v in 1..=255u8 {
+            let log = TABLES.log[usize::from(v)];
+            assert_eq!(TABLES.exp[usize::from(log)], v);
+        }
+    }
+
+    #[quickcheck]
+    fn split_recover_roundtrip(threshold: u8, count: u8, secret: Vec<u8>) -> TestResult {
+        let count = count % MAX_SHARE_COUNT + 1;
+        let threshold = threshold % count + 1;
+        if secret.len() < 16 {
+            return TestResult::discard();
+        }
+
+        let shares = split_secret(threshold, count, &secret, &mut rand::thread_rng()).unwrap();
+        // Any set of threshold shares can be used.
+        TestResult::from_bool(shares.windows(threshold.into()).all(|shares| {
+            recover_secret(threshold, shares).unwrap().as_slice() == secret.as_slice()
+        }))
+    }
+
+    #[quickcheck]
+    fn recover_detects_corruption(secret: Vec<u8>, index: usize, error: u8) -> TestResult {
+        if secret.len() < 16 || error == 0 {
+            return TestResult::discard();
+        }
+
+        let mut shares = split_secret(3, 5, &secret, &mut rand::thread_rng()).unwrap();
+        let index = index % secret.len();
+        shares[0].1[index] ^= error;
+        TestResult::from_bool(matches!(
+            recover_secret(3, &shares[..3]),
+            Err(Error::InvalidDigest)
+        ))
+    }
+}
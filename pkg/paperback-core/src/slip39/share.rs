@@ -0,0 +1,323 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::slip39::{
+    rs1024::{self, CHECKSUM_WORDS},
+    wordlist::{RADIX_BITS, WORDLIST},
+    Error, MIN_SECRET_LENGTH,
+};
+
+use std::fmt;
+
+use zeroize::Zeroizing;
+
+/// Number of words used for the identifier, extendable flag and iteration
+/// exponent.
+const ID_EXP_WORDS: usize = 2;
+/// Number of words used for the group and member parameters.
+const PARAMS_WORDS: usize = 2;
+/// Number of words needed to store a secret of `MIN_SECRET_LENGTH` bytes.
+const MIN_VALUE_WORDS: usize = (MIN_SECRET_LENGTH * 8).div_ceil(RADIX_BITS);
+
+/// The minimum number of words in a mnemonic.
+pub(super) const MIN_MNEMONIC_WORDS: usize =
+    ID_EXP_WORDS + PARAMS_WORDS + MIN_VALUE_WORDS + CHECKSUM_WORDS;
+
+/// A single SLIP-0039 share, which is written down as a mnemonic (see
+/// `Share::from_mnemonic` and `Share::to_mnemonic`).
+///
+/// The share value is wiped from memory when dropped.
+#[derive(Clone)]
+pub struct Share {
+    pub(super) identifier: u16,
+    pub(super) extendable: bool,
+    pub(super) iteration_exponent: u8,
+    pub(super) group_index: u8,
+    pub(super) group_threshold: u8,
+    pub(super) group_count: u8,
+    pub(super) member_index: u8,
+    pub(super) member_threshold: u8,
+    pub(super) value: Zeroizing<Vec<u8>>,
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    match extendable {
+        true => b"shamir_extendable",
+        false => b"shamir",
+    }
+}
+
+fn lookup_word(word: &str) -> Result<u16, Error> {
+    let word = word.to_lowercase();
+    // Every word is uniquely identified by its first four letters, so accept
+    // any prefix at least that long.
+    WORDLIST
+        .binary_search(&word.as_str())
+        .ok()
+        .or_else(|| {
+            (word.len() >= 4)
+                .then(|| WORDLIST.iter().position(|w| w.starts_with(&word)))
+                .flatten()
+        })
+        .map(|index| index as u16)
+        .ok_or(Error::UnknownWord(word))
+}
+
+/// Encode `bytes` as a big-endian integer in words of `RADIX_BITS` bits, with
+/// zero padding at the start.
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+    let num_words = (bytes.len() * 8).div_ceil(RADIX_BITS);
+    let (mut acc, mut acc_bits) = (0u32, num_words * RADIX_BITS - bytes.len() * 8);
+    let mut words = Vec::with_capacity(num_words);
+    for byte in bytes {
+        acc = (acc << 8) | u32::from(*byte);
+        acc_bits += 8;
+        while acc_bits >= RADIX_BITS {
+            acc_bits -= RADIX_BITS;
+            words.push(((acc >> acc_bits) & 0x3ff) as u16);
+        }
+        acc &= (1 << acc_bits) - 1;
+    }
+    words
+}
+
+/// Inverse of `bytes_to_words`.
+fn words_to_bytes(words: &[u16]) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let mut padding = (words.len() * RADIX_BITS) % 16;
+    if padding > 8 {
+        return Err(Error::InvalidLength(
+            words.len() + ID_EXP_WORDS + PARAMS_WORDS + CHECKSUM_WORDS,
+        ));
+    }
+
+    let (mut acc, mut acc_bits) = (0u32, 0);
+    let mut bytes = Zeroizing::new(Vec::with_capacity(words.len() * RADIX_BITS / 8));
+    for word in words {
+        acc = (acc << RADIX_BITS) | u32::from(*word);
+        acc_bits += RADIX_BITS;
+        if padding > 0 {
+            acc_bits -= padding;
+            if acc >> acc_bits != 0 {
+                return Err(Error::InvalidPadding);
+            }
+            padding = 0;
+        }
+        while acc_bits >= 8 {
+            acc_bits -= 8;
+            bytes.push((acc >> acc_bits) as u8);
+        }
+        acc &= (1 << acc_bits) - 1;
+    }
+    Ok(bytes)
+}
+
+impl Share {
+    /// Parse a share from its mnemonic, checking its checksum. Words can be
+    /// abbreviated to their first four letters.
+    pub fn from_mnemonic<S: AsRef<str>>(mnemonic: S) -> Result<Self, Error> {
+        let words = mnemonic
+            .as_ref()
+            .split_whitespace()
+            .map(lookup_word)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Zeroizing::new)?;
+        if words.len() < MIN_MNEMONIC_WORDS {
+            return Err(Error::InvalidLength(words.len()));
+        }
+
+        let id_exp = (u32::from(words[0]) << RADIX_BITS) | u32::from(words[1]);
+        let identifier = (id_exp >> 5) as u16;
+        let extendable = (id_exp >> 4) & 1 == 1;
+        let iteration_exponent = (id_exp & 0xf) as u8;
+
+        if !rs1024::verify_checksum(customization(extendable), &words) {
+            return Err(Error::InvalidChecksum);
+        }
+
+        let params = (u32::from(words[2]) << RADIX_BITS) | u32::from(words[3]);
+        let nibble = |shift: u32| ((params >> shift) & 0xf) as u8;
+        let share = Share {
+            identifier,
+            extendable,
+            iteration_exponent,
+            group_index: nibble(16),
+            group_threshold: nibble(12) + 1,
+            group_count: nibble(8) + 1,
+            member_index: nibble(4),
+            member_threshold: nibble(0) + 1,
+            value: words_to_bytes(
+                &words[ID_EXP_WORDS + PARAMS_WORDS..words.len() - CHECKSUM_WORDS],
+            )?,
+        };
+        if share.group_threshold > share.group_count {
+            return Err(Error::InvalidPolicy(format!(
+                "group threshold ({}) cannot be greater than the number of groups ({})",
+                share.group_threshold, share.group_count
+            )));
+        }
+        Ok(share)
+    }
+
+    /// Render the share as a mnemonic (with the words separated by spaces).
+    pub fn to_mnemonic(&self) -> Zeroizing<String> {
+        let id_exp = (u32::from(self.identifier) << 5)
+            | (u32::from(self.extendable) << 4)
+            | u32::from(self.iteration_exponent);
+        let params = (u32::from(self.group_index) << 16)
+            | (u32::from(self.group_threshold - 1) << 12)
+            | (u32::from(self.group_count - 1) << 8)
+            | (u32::from(self.member_index) << 4)
+            | u32::from(self.member_threshold - 1);
+
+        let mut words = Zeroizing::new(vec![
+            (id_exp >> RADIX_BITS) as u16,
+            (id_exp & 0x3ff) as u16,
+            (params >> RADIX_BITS) as u16,
+            (params & 0x3ff) as u16,
+        ]);
+        words.extend(bytes_to_words(&self.value));
+        let checksum = rs1024::create_checksum(customization(self.extendable), &words);
+        words.extend_from_slice(&checksum);
+
+        Zeroizing::new(
+            words
+                .iter()
+                .map(|w| WORDLIST[usize::from(*w)])
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    /// Random identifier shared by all shares of the same master secret.
+    pub fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// Whether new shares can be created for the same master secret with a
+    /// different identifier.
+    pub fn is_extendable(&self) -> bool {
+        self.extendable
+    }
+
+    pub fn iteration_exponent(&self) -> u8 {
+        self.iteration_exponent
+    }
+
+    /// Index of the group this share belongs to (starting from 0).
+    pub fn group_index(&self) -> u8 {
+        self.group_index
+    }
+
+    /// Number of groups required to recover the master secret.
+    pub fn group_threshold(&self) -> u8 {
+        self.group_threshold
+    }
+
+    /// Total number of groups.
+    pub fn group_count(&self) -> u8 {
+        self.group_count
+    }
+
+    /// Index of this share within its group (starting from 0).
+    pub fn member_index(&self) -> u8 {
+        self.member_index
+    }
+
+    /// Number of shares from this share's group required to recover the
+    /// group's secret.
+    pub fn member_threshold(&self) -> u8 {
+        self.member_threshold
+    }
+}
+
+// Don't leak the share value through debug output.
+impl fmt::Debug for Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Share")
+            .field("identifier", &self.identifier)
+            .field("extendable", &self.extendable)
+            .field("iteration_exponent", &self.iteration_exponent)
+            .field("group_index", &self.group_index)
+            .field("group_threshold", &self.group_threshold)
+            .field("group_count", &self.group_count)
+            .field("member_index", &self.member_index)
+            .field("member_threshold", &self.member_threshold)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for Share {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let group_count = u8::arbitrary(g) % 16 + 1;
+        let mut value = vec![0; MIN_SECRET_LENGTH + 2 * (usize::arbitrary(g) % 9)];
+        crate::v0::arbitrary_fill_slice(g, &mut value);
+        Self {
+            identifier: u16::arbitrary(g) & 0x7fff,
+            extendable: bool::arbitrary(g),
+            iteration_exponent: u8::arbitrary(g) % 16,
+            group_index: u8::arbitrary(g) % group_count,
+            group_threshold: u8::arbitrary(g) % group_count + 1,
+            group_count,
+            member_index: u8::arbitrary(g) % 16,
+            member_threshold: u8::arbitrary(g) % 16 + 1,
+            value: Zeroizing::new(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wordlist_sorted_unique_prefixes() {
+        assert!(WORDLIST.windows(2).all(|w| w[0] < w[1]));
+        for (i, word) in WORDLIST.iter().enumerate() {
+            assert_eq!(lookup_word(&word[..4]).unwrap(), i as u16);
+        }
+    }
+
+    #[quickcheck]
+    fn bytes_words_roundtrip(bytes: Vec<u8>) -> bool {
+        // Only even-length values have a valid padding length.
+        let bytes = &bytes[..bytes.len() & !1];
+        words_to_bytes(&bytes_to_words(bytes)).unwrap().as_slice() == bytes
+    }
+
+    #[quickcheck]
+    fn share_mnemonic_roundtrip(share: Share) -> bool {
+        let share2 = Share::from_mnemonic(share.to_mnemonic()).unwrap();
+        format!("{:?}", share) == format!("{:?}", share2) && share.value == share2.value
+    }
+
+    #[quickcheck]
+    fn share_mnemonic_typo(share: Share, index: usize) -> bool {
+        let mnemonic = share.to_mnemonic();
+        let mut words = mnemonic.split(' ').collect::<Vec<_>>();
+        let index = index % words.len();
+        words[index] = match words[index] {
+            "academic" => "acid",
+            _ => "academic",
+        };
+        matches!(
+            Share::from_mnemonic(words.join(" ")),
+            Err(Error::InvalidChecksum)
+        )
+    }
+}
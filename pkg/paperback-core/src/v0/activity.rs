@@ -0,0 +1,202 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    from_unix_timestamp, wire::prefixes::PREFIX_ED25519_PUB, DocumentId, Identity, MainDocument,
+    ShardId, ToWire, UnixTimestamp,
+};
+
+use std::time::SystemTime;
+
+use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use unsigned_varint::encode as varuint_encode;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(super) struct ActivityRecordBuilder {
+    pub(super) version: u32, // must be 0 for this version
+    pub(super) document_id: DocumentId,
+    pub(super) minted: UnixTimestamp,
+    pub(super) shard_ids: Vec<ShardId>,
+    pub(super) quorum: Vec<ShardId>,
+}
+
+impl ActivityRecordBuilder {
+    fn signable_bytes(&self, id_public_key: &VerifyingKey) -> Vec<u8> {
+        let mut bytes = self.to_wire();
+
+        // Append the Ed25519 public key used for signing.
+        varuint_encode::u32(PREFIX_ED25519_PUB, &mut varuint_encode::u32_buffer())
+            .iter()
+            .chain(id_public_key.as_bytes())
+            .for_each(|b| bytes.push(*b));
+        bytes
+    }
+
+    pub(super) fn sign(self, id_keypair: &SigningKey) -> ActivityRecord {
+        let bytes = self.signable_bytes(&id_keypair.verifying_key());
+        ActivityRecord {
+            inner: self,
+            identity: Identity {
+                id_public_key: id_keypair.verifying_key(),
+                id_signature: id_keypair.sign(&bytes),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ActivityRecordBuilder {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self {
+            version: crate::v0::PAPERBACK_VERSION,
+            document_id: DocumentId::arbitrary(g),
+            minted: UnixTimestamp::arbitrary(g),
+            shard_ids: Vec::<ShardId>::arbitrary(g),
+            quorum: Vec::<ShardId>::arbitrary(g),
+        }
+    }
+}
+
+/// A record of new key shards being minted from a quorum of an unsealed
+/// backup (see `Quorum::activity_record`).
+///
+/// The record is signed with the backup's identity key, which only a quorum
+/// of key shards can recover. Shard holders can keep these records to later
+/// audit when (and by which quorum) the backup was expanded, and check them
+/// against the main document with `ActivityRecord::is_signed_by`.
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct ActivityRecord {
+    pub(super) inner: ActivityRecordBuilder,
+    pub(super) identity: Identity,
+}
+
+impl ActivityRecord {
+    pub fn version(&self) -> u32 {
+        self.inner.version
+    }
+
+    /// Identifier of the main document the key shards were minted for.
+    pub fn document_id(&self) -> &DocumentId {
+        &self.inner.document_id
+    }
+
+    /// When the key shards were minted.
+    pub fn minted(&self) -> Option<SystemTime> {
+        from_unix_timestamp(self.inner.minted)
+    }
+
+    /// Identifiers of the key shards that were minted.
+    pub fn shard_ids(&self) -> &[ShardId] {
+        &self.inner.shard_ids
+    }
+
+    /// Identifiers of the key shards in the quorum that minted the key shards.
+    pub fn quorum(&self) -> &[ShardId] {
+        &self.inner.quorum
+    }
+
+    /// Whether the record has a valid signature from the identity key it
+    /// contains. Use `ActivityRecord::is_signed_by` to check that the identity
+    /// key is the one used by a particular backup.
+    pub fn has_valid_signature(&self) -> bool {
+        let id_public_key = self.identity.id_public_key;
+        id_public_key
+            .verify_strict(
+                &self.inner.signable_bytes(&id_public_key),
+                &self.identity.id_signature,
+            )
+            .is_ok()
+    }
+
+    /// Whether the record was signed by the backup that `main_document`
+    /// belongs to.
+    pub fn is_signed_by(&self, main_document: &MainDocument) -> bool {
+        self.inner.document_id == main_document.id()
+            && self.identity.id_public_key == main_document.identity.id_public_key
+            && self.has_valid_signature()
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ActivityRecord {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let id_keypair = SigningKey::generate(&mut rand::thread_rng());
+        ActivityRecordBuilder::arbitrary(g).sign(&id_keypair)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::v0::{Backup, Error, KeyShard, NewShardKind, UntrustedQuorum};
+
+    #[test]
+    fn activity_record_signed_by_backup() {
+        let backup = Backup::new(2, b"secret").unwrap();
+        let other_backup = Backup::new(2, b"secret").unwrap();
+
+        let mut quorum = UntrustedQuorum::new();
+        for _ in 0..2 {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+
+        let shards = (0..3)
+            .map(|_| quorum.new_shard(NewShardKind::NewShard).unwrap())
+            .collect::<Vec<_>>();
+        let record = quorum.activity_record(&shards).unwrap();
+        assert_eq!(record.document_id(), &backup.main_document().id());
+        assert_eq!(
+            record.shard_ids(),
+            shards.iter().map(KeyShard::id).collect::<Vec<_>>()
+        );
+        assert_eq!(record.quorum().len(), 2);
+        assert!(record.has_valid_signature());
+        assert!(record.is_signed_by(backup.main_document()));
+        assert!(!record.is_signed_by(other_backup.main_document()));
+
+        // Tampering with the record invalidates the signature.
+        let mut forged = record.clone();
+        forged.inner.shard_ids.pop();
+        assert!(!forged.has_valid_signature());
+        assert!(!forged.is_signed_by(backup.main_document()));
+
+        // Key shards of other backups cannot be recorded.
+        assert!(matches!(
+            quorum.activity_record([&other_backup.next_shard().unwrap()]),
+            Err(Error::InvariantViolation(_))
+        ));
+    }
+
+    #[test]
+    fn activity_record_sealed_backup() {
+        let backup = Backup::new_sealed(2, b"secret").unwrap();
+
+        let mut quorum = UntrustedQuorum::new();
+        for _ in 0..2 {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+
+        let shard = backup.next_shard().unwrap();
+        assert!(matches!(
+            quorum.activity_record([&shard]),
+            Err(Error::MissingCapability(_))
+        ));
+    }
+}
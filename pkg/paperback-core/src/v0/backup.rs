@@ -17,52 +17,164 @@
  */
 
 use crate::{
-    shamir::Dealer,
+    shamir::{Dealer, Shard},
     v0::{
-        Error, KeyShard, KeyShardBuilder, MainDocument, MainDocumentBuilder, MainDocumentMeta,
-        ShardSecret, ToWire, PAPERBACK_VERSION,
+        codewords_to_candidate_keys, key_to_codewords, to_unix_timestamp, AadVersion, AgeRecipient,
+        ChaChaPolyNonce, ChecksumAlgorithm, DerivePublicInfo, Error, FromWire, KeyShard,
+        KeyShardBuilder, KeyShardCodewords, MainDocument, MainDocumentBuilder, MainDocumentMeta,
+        NamedPayload, NamedPayloads, PlaintextKind, PublicInfo, SecretData, SeedPhrase,
+        ShardCommitment, ShardCommitmentProof, ShardId, ShardPolicy, ShardSecret, Timestamps,
+        ToWire, DEFAULT_CODEWORD_LANGUAGE, PAPERBACK_VERSION,
     },
 };
 
+#[cfg(feature = "pdf")]
+use crate::v0::PdfOptions;
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+
 use aead::{Aead, AeadCore, Payload};
 use chacha20poly1305::ChaCha20Poly1305;
 use crypto_common::KeyInit;
 use ed25519_dalek::SigningKey;
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use tracing::instrument;
+use zeroize::Zeroizing;
 
+/// The random number generator used by a `Backup` for all of its key, nonce
+/// and shard generation.
+trait BackupRng: CryptoRng + RngCore + Send {}
+
+impl<R: CryptoRng + RngCore + Send> BackupRng for R {}
+
+/// Secret material held by a `Backup` (the sharded secret and the identity
+/// keypair) is wiped from memory when it is dropped.
 pub struct Backup {
     main_document: MainDocument,
     dealer: Dealer,
     id_keypair: SigningKey,
+    shard_policy: ShardPolicy,
+    rng: Mutex<Box<dyn BackupRng>>,
+    // Committed key shards (see BackupBuilder::commit_shards) which have not
+    // been handed out yet.
+    committed_shards: Mutex<VecDeque<KeyShard>>,
 }
 
 impl Backup {
+    /// The largest quorum size allowed by default (see
+    /// `BackupBuilder::max_quorum_size`). Recovering (and especially
+    /// expanding) backups with larger quorums takes an unreasonable amount of
+    /// time.
+    pub const MAX_QUORUM_SIZE: u32 = 256;
+
+    /// The maximum length (in bytes) of a backup title.
+    pub const MAX_TITLE_LENGTH: usize = MainDocument::MAX_TITLE_LENGTH;
+
+    fn check_quorum_size(quorum_size: u32, max_quorum_size: u32) -> Result<(), Error> {
+        match quorum_size {
+            0 => Err(Error::QuorumTooSmall(quorum_size)),
+            n if n > max_quorum_size => Err(Error::QuorumTooLarge {
+                quorum_size,
+                max_quorum_size,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that creating `num_shards` key shards for a backup with the given
+    /// `quorum_size` results in a recoverable backup.
+    ///
+    /// The `Backup` constructors only check the quorum size (since the number
+    /// of key shards is up to the caller), so callers which create all of the
+    /// key shards at once should use this to check their policy beforehand.
+    pub fn check_policy(quorum_size: u32, num_shards: u32) -> Result<(), Error> {
+        Self::check_policy_with_limit(quorum_size, num_shards, Self::MAX_QUORUM_SIZE)
+    }
+
+    /// Like `Backup::check_policy`, but quorum sizes up to `max_quorum_size`
+    /// (rather than `Backup::MAX_QUORUM_SIZE`) are permitted.
+    pub fn check_policy_with_limit(
+        quorum_size: u32,
+        num_shards: u32,
+        max_quorum_size: u32,
+    ) -> Result<(), Error> {
+        Self::check_quorum_size(quorum_size, max_quorum_size)?;
+        if num_shards < quorum_size {
+            return Err(Error::ShardsLessThanQuorum {
+                shards: num_shards,
+                quorum_size,
+            });
+        }
+        Ok(())
+    }
+
     // XXX: This internal API is a bit ugly...
-    fn inner_new(quorum_size: u32, secret: &[u8], sealed: bool) -> Result<Self, Error> {
+    fn inner_new(
+        mut main_document_meta: MainDocumentMeta,
+        secret: &[u8],
+        recipient: Option<&AgeRecipient>,
+        shard_policy: ShardPolicy,
+        max_quorum_size: u32,
+        commit_shards: Option<u32>,
+        mut rng: Box<dyn BackupRng>,
+    ) -> Result<Self, Error> {
+        let quorum_size = main_document_meta.quorum_size;
+        Self::check_quorum_size(quorum_size, max_quorum_size)?;
+
+        // Wrap the secret to the recipient before it is encrypted, so that
+        // recovering it also requires the recipient's identity.
+        let wrapped_secret = recipient
+            .map(|recipient| {
+                age::encrypt(recipient, secret)
+                    .map_err(|err| Error::RecipientEncrypt(err.to_string()))
+            })
+            .transpose()?;
+        let secret = wrapped_secret.as_deref().unwrap_or(secret);
+
         // Generate identity keypair.
-        let id_keypair = SigningKey::generate(&mut OsRng);
+        let id_keypair = SigningKey::generate(&mut rng);
 
         // Generate key and nonce.
-        let doc_key = ChaCha20Poly1305::generate_key(&mut OsRng);
-        let doc_nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let doc_key = Zeroizing::new(ChaCha20Poly1305::generate_key(&mut *rng));
+        let doc_nonce = ChaCha20Poly1305::generate_nonce(&mut *rng);
 
         // Construct shard secret and serialise it.
-        let shard_secret = {
+        let shard_secret = Zeroizing::new(
             ShardSecret {
-                doc_key,
-                id_keypair: match sealed {
-                    false => Some(id_keypair.clone()),
-                    true => None,
+                doc_key: *doc_key,
+                id_keypair: match shard_policy {
+                    ShardPolicy::Sealed => None,
+                    _ => Some(id_keypair.clone()),
                 },
+                allow_recreate: shard_policy == ShardPolicy::Any,
+            }
+            .to_wire(),
+        );
+
+        // Construct SSS dealer.
+        let dealer = Dealer::new_with_rng(quorum_size, shard_secret.as_slice(), &mut *rng);
+
+        // Generate the committed key shards up-front, so that the commitment
+        // can be signed along with the rest of the MainDocument.
+        let committed_shards = commit_shards
+            .map(|n| dealer.next_shards_with_rng(n as usize, &mut *rng))
+            .transpose()?
+            .unwrap_or_default();
+        let (shard_commitment, commitment_proofs) = match &committed_shards[..] {
+            [] => (None, vec![]),
+            shards => {
+                let (commitment, proofs) = ShardCommitment::new(shards);
+                (Some(commitment), proofs)
             }
-            .to_wire()
         };
 
         // Construct the MainDocument.
-        let main_document_meta = MainDocumentMeta {
-            version: PAPERBACK_VERSION,
-            quorum_size,
-        };
+        main_document_meta.recipient_wrapped = recipient.is_some();
+        main_document_meta.shard_commitment = shard_commitment;
 
         // Encrypt the contents.
         let aead = ChaCha20Poly1305::new(&doc_key);
@@ -82,38 +194,917 @@ impl Backup {
         }
         .sign(&id_keypair);
 
-        // Construct SSS dealer.
-        let dealer = Dealer::new(quorum_size, shard_secret);
-
-        Ok(Backup {
+        let mut backup = Backup {
             main_document,
             dealer,
             id_keypair,
-        })
+            shard_policy,
+            rng: Mutex::new(rng),
+            committed_shards: Mutex::new(VecDeque::new()),
+        };
+        let committed_shards = committed_shards
+            .into_iter()
+            .zip(commitment_proofs)
+            .map(|(shard, proof)| backup.sign_shard(shard, Some(proof)))
+            .collect();
+        backup.committed_shards = Mutex::new(committed_shards);
+        Ok(backup)
     }
 
-    // TODO: Implement this as a BackupBuilder rather than two builder init
-    //       functions.
+    /// Start building a backup with options beyond those supported by the
+    /// other constructors. See `BackupBuilder`.
+    pub fn builder() -> BackupBuilder {
+        BackupBuilder::default()
+    }
 
     pub fn new<B: AsRef<[u8]>>(quorum_size: u32, secret: B) -> Result<Self, Error> {
-        Self::inner_new(quorum_size, secret.as_ref(), false)
+        Self::builder().quorum_size(quorum_size).build(secret)
     }
 
     pub fn new_sealed<B: AsRef<[u8]>>(quorum_size: u32, secret: B) -> Result<Self, Error> {
-        Self::inner_new(quorum_size, secret.as_ref(), true)
+        Self::builder()
+            .quorum_size(quorum_size)
+            .sealed(true)
+            .build(secret)
+    }
+
+    /// Like `Backup::new`, but the main document and key shards are marked as
+    /// expiring at the given time.
+    pub fn new_with_expiry<B: AsRef<[u8]>>(
+        quorum_size: u32,
+        secret: B,
+        expires: SystemTime,
+    ) -> Result<Self, Error> {
+        Self::builder()
+            .quorum_size(quorum_size)
+            .expires(expires)
+            .build(secret)
+    }
+
+    /// Like `Backup::new_sealed`, but the main document and key shards are
+    /// marked as expiring at the given time.
+    pub fn new_sealed_with_expiry<B: AsRef<[u8]>>(
+        quorum_size: u32,
+        secret: B,
+        expires: SystemTime,
+    ) -> Result<Self, Error> {
+        Self::builder()
+            .quorum_size(quorum_size)
+            .sealed(true)
+            .expires(expires)
+            .build(secret)
+    }
+
+    /// Like `Backup::new`, but all keys, nonces and shards are generated
+    /// using `rng` and the backup is marked as created at `created`.
+    ///
+    /// Given the same `rng` state, the generated main document and key shards
+    /// are byte-for-byte identical, which is useful for reproducible testing.
+    /// Unless you need reproducible documents, use `Backup::new` instead.
+    pub fn new_with_rng<B, R>(
+        quorum_size: u32,
+        secret: B,
+        created: SystemTime,
+        rng: R,
+    ) -> Result<Self, Error>
+    where
+        B: AsRef<[u8]>,
+        R: CryptoRng + RngCore + Send + 'static,
+    {
+        Self::builder()
+            .quorum_size(quorum_size)
+            .created(created)
+            .with_rng(rng)
+            .build(secret)
+    }
+
+    /// Create a backup containing several named payloads, which all share the
+    /// same key shards. Each payload can be recovered individually by name,
+    /// see `Quorum::recover_payload`.
+    ///
+    /// Payload names must be unique. Unless `sealed` is set, more shards can
+    /// be created later (as with `Backup::new`).
+    pub fn new_with_payloads<P: IntoIterator<Item = NamedPayload>>(
+        quorum_size: u32,
+        payloads: P,
+        sealed: bool,
+        expires: Option<SystemTime>,
+    ) -> Result<Self, Error> {
+        let mut builder = Self::builder().quorum_size(quorum_size).sealed(sealed);
+        if let Some(expires) = expires {
+            builder = builder.expires(expires);
+        }
+        builder.build_with_payloads(payloads)
+    }
+
+    /// Like `Backup::new`, but the secret is additionally encrypted to the
+    /// given age X25519 `recipient`. Recovering the secret then requires both
+    /// a quorum of key shards and the recipient's identity (see
+    /// `Quorum::set_identity`).
+    ///
+    /// Unless `sealed` is set, more shards can be created later (without the
+    /// recipient's identity).
+    pub fn new_with_recipient<B: AsRef<[u8]>>(
+        quorum_size: u32,
+        secret: B,
+        recipient: &AgeRecipient,
+        sealed: bool,
+        expires: Option<SystemTime>,
+    ) -> Result<Self, Error> {
+        let mut builder = Self::builder()
+            .quorum_size(quorum_size)
+            .sealed(sealed)
+            .recipient(recipient.clone());
+        if let Some(expires) = expires {
+            builder = builder.expires(expires);
+        }
+        builder.build(secret)
+    }
+
+    /// Create a new backup with entirely new keys, but the same (already
+    /// decrypted) contents, quorum size, title and expiry as an existing main
+    /// document. The new backup uses `shard_policy`, so it is only unsealed
+    /// if the caller asks for it. Used by `Quorum::unseal` (which always
+    /// passes `ShardPolicy::Any`) and `Quorum::rotate_keys` (which keeps the
+    /// policy of the existing backup).
+    pub(super) fn copy_with_new_keys(
+        meta: &MainDocumentMeta,
+        plaintext: &[u8],
+        recipient: Option<&AgeRecipient>,
+        shard_policy: ShardPolicy,
+    ) -> Result<Self, Error> {
+        Self::inner_new(
+            MainDocumentMeta {
+                timestamps: Timestamps::new(SystemTime::now(), meta.timestamps.expires),
+                // The copy is a new document, even if the original was not.
+                aad_version: AadVersion::V1,
+                ..meta.clone()
+            },
+            plaintext,
+            recipient,
+            shard_policy,
+            // The existing document may have been created with a raised limit.
+            meta.quorum_size.max(Self::MAX_QUORUM_SIZE),
+            // The key shards of the new backup are created by the caller.
+            None,
+            Box::new(OsRng),
+        )
     }
 
     pub fn main_document(&self) -> &MainDocument {
         &self.main_document
     }
 
-    pub fn next_shard(&self) -> Result<KeyShard, Error> {
-        // Extend new shard.
-        Ok(KeyShardBuilder {
+    fn sign_shard(&self, shard: Shard, commitment_proof: Option<ShardCommitmentProof>) -> KeyShard {
+        KeyShardBuilder {
             version: self.main_document.inner.meta.version,
             doc_chksum: self.main_document.checksum(),
-            shard: self.dealer.next_shard(),
+            shard,
+            timestamps: self.main_document.inner.meta.timestamps,
+            commitment_proof,
+        }
+        .sign(&self.id_keypair)
+    }
+
+    /// Committed key shards are handed out before any new key shards are
+    /// generated (see `BackupBuilder::commit_shards`).
+    #[instrument(skip_all)]
+    pub fn next_shard(&self) -> Result<KeyShard, Error> {
+        if let Some(shard) = self
+            .committed_shards
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front()
+        {
+            return Ok(shard);
+        }
+
+        let mut rng = self.rng.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // Extend new shard.
+        Ok(self.sign_shard(self.dealer.next_shard_with_rng(&mut **rng), None))
+    }
+
+    /// Generate `n` new `KeyShard`s. As with `Backup::next_shard`, the key
+    /// shards are guaranteed to have distinct shard ids.
+    #[instrument(skip(self))]
+    pub fn next_shards(&self, n: usize) -> Result<Vec<KeyShard>, Error> {
+        let mut rng = self.rng.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut committed_shards = self
+            .committed_shards
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Generate the new key shards first, so that no committed key shards
+        // are lost if there are not enough shard ids left.
+        let num_committed = n.min(committed_shards.len());
+        let new_shards = self
+            .dealer
+            .next_shards_with_rng(n - num_committed, &mut **rng)?;
+
+        Ok(committed_shards
+            .drain(..num_committed)
+            .chain(
+                new_shards
+                    .into_iter()
+                    .map(|shard| self.sign_shard(shard, None)),
+            )
+            .collect())
+    }
+
+    /// Generate the key shard with the given (caller-chosen) shard id, rather
+    /// than a random one. This fails if a key shard with the same id has
+    /// already been issued by this `Backup` (see `Backup::is_issued`), or if the
+    /// id cannot be used for a key shard.
+    ///
+    /// If `id` is the id of a committed key shard which has not been handed out
+    /// yet, that key shard is returned instead.
+    pub fn shard_at(&self, id: &ShardId) -> Result<KeyShard, Error> {
+        let mut committed_shards = self
+            .committed_shards
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(idx) = committed_shards.iter().position(|shard| &shard.id() == id) {
+            return Ok(committed_shards
+                .remove(idx)
+                .expect("position should be a valid index"));
+        }
+
+        Ok(self.sign_shard(self.dealer.shard_at(id.x_value())?, None))
+    }
+
+    /// Whether a key shard with the given id has been issued by this `Backup`
+    /// (committed key shards count as issued, even before they are handed
+    /// out). Key shards issued before a backup session was resumed (see
+    /// `Backup::resume_session`) are not known.
+    pub fn is_issued(&self, id: &ShardId) -> bool {
+        self.dealer.is_issued(id.x_value())
+            || self
+                .committed_shards
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .any(|shard| &shard.id() == id)
+    }
+
+    /// Export the state of the `Backup` as an encrypted session, which can be
+    /// used with `Backup::resume_session` to generate more shards for the same
+    /// main document at a later point.
+    ///
+    /// The session contains enough information to recover the secret, so it
+    /// must be treated as carefully as the original secret data.
+    ///
+    /// Sessions cannot be exported for sealed backups, since they would allow
+    /// new key shards to be created after the `Backup` is dropped.
+    pub fn seal_session(&self) -> Result<(EncryptedBackupSession, KeyShardCodewords), Error> {
+        if self.shard_policy == ShardPolicy::Sealed {
+            return Err(Error::MissingCapability(
+                "backup sessions cannot be exported for sealed backups",
+            ));
+        }
+
+        let mut rng = self.rng.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // The dealer is stored as a full quorum of shards, which is enough to
+        // reconstruct it with Dealer::recover.
+        let session = BackupSession {
+            version: PAPERBACK_VERSION,
+            main_document: self.main_document.clone(),
+            id_keypair: self.id_keypair.clone(),
+            shards: self
+                .dealer
+                .next_shards_with_rng(self.dealer.threshold() as usize, &mut **rng)?,
+            committed_shards: self
+                .committed_shards
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .cloned()
+                .collect(),
+        };
+
+        // Generate key and nonce.
+        let session_key = Zeroizing::new(ChaCha20Poly1305::generate_key(&mut **rng));
+        let session_nonce = ChaCha20Poly1305::generate_nonce(&mut **rng);
+
+        // Encrypt the contents.
+        let aead = ChaCha20Poly1305::new(&session_key);
+        let ciphertext = aead
+            .encrypt(&session_nonce, Zeroizing::new(session.to_wire()).as_slice())
+            .map_err(Error::AeadEncryption)?;
+
+        // Convert key to a BIP-39 mnemonic.
+        let codewords = key_to_codewords(&session_key, DEFAULT_CODEWORD_LANGUAGE)?;
+
+        Ok((
+            EncryptedBackupSession {
+                nonce: session_nonce,
+                ciphertext,
+            },
+            codewords,
+        ))
+    }
+
+    /// Re-create a `Backup` from a session exported with
+    /// `Backup::seal_session`.
+    ///
+    /// The resumed `Backup` has the same main document (and document ID) as
+    /// the original, and any shards it creates are compatible with the shards
+    /// created before the session was exported.
+    pub fn resume_session<A: AsRef<[String]>>(
+        session: &EncryptedBackupSession,
+        codewords: A,
+    ) -> Result<Self, Error> {
+        // Convert BIP-39 mnemonic to a key, trying every language the phrase is
+        // valid in.
+        let wire_session = codewords_to_candidate_keys(codewords.as_ref())?
+            .into_iter()
+            .map(|(_, session_key)| {
+                // Decrypt the contents.
+                ChaCha20Poly1305::new(&session_key)
+                    .decrypt(&session.nonce, session.ciphertext.as_slice())
+                    .map(Zeroizing::new)
+                    .map_err(Error::AeadDecryption)
+            })
+            .reduce(|acc, res| acc.or(res))
+            .expect("at least one candidate codeword key must exist")?;
+        let session =
+            BackupSession::from_wire(wire_session.as_slice()).map_err(Error::SessionDecode)?;
+
+        // Make sure the session is internally consistent before using it.
+        let main_document = session.main_document;
+        if main_document.identity.id_public_key != session.id_keypair.verifying_key() {
+            return Err(Error::InvariantViolation(
+                "session private key doesn't match main document public key",
+            ));
+        }
+        if session.shards.len() != main_document.quorum_size() as usize {
+            return Err(Error::InvariantViolation(
+                "session must contain exactly a quorum of shards",
+            ));
+        }
+        if session
+            .committed_shards
+            .iter()
+            .any(|shard| main_document.is_committed_shard(shard) != Some(true))
+        {
+            return Err(Error::InvariantViolation(
+                "session committed shards are not committed to by the main document",
+            ));
+        }
+
+        let dealer = Dealer::recover(session.shards)?;
+        let shard_secret =
+            ShardSecret::from_wire(dealer.secret().as_slice()).map_err(Error::ShardSecretDecode)?;
+
+        Ok(Backup {
+            dealer,
+            main_document,
+            id_keypair: session.id_keypair,
+            shard_policy: shard_secret.shard_policy(),
+            rng: Mutex::new(Box::new(OsRng)),
+            committed_shards: Mutex::new(session.committed_shards.into()),
+        })
+    }
+}
+
+/// Builder for a `Backup`, for combinations of options not covered by the
+/// `Backup::new*` constructors. Create one with `Backup::builder`.
+#[derive(Default)]
+pub struct BackupBuilder {
+    quorum_size: u32,
+    shard_policy: ShardPolicy,
+    max_quorum_size: Option<u32>,
+    created: Option<SystemTime>,
+    expires: Option<SystemTime>,
+    recipient: Option<AgeRecipient>,
+    checksum_algorithm: ChecksumAlgorithm,
+    title: Option<String>,
+    commit_shards: Option<u32>,
+    public_info: Option<Box<dyn DerivePublicInfo>>,
+    #[cfg(feature = "pdf")]
+    pdf_options: Option<PdfOptions>,
+    rng: Option<Box<dyn BackupRng>>,
+}
+
+impl BackupBuilder {
+    /// Number of key shards required to recover the backup. This must be set,
+    /// there is no default.
+    ///
+    /// A quorum size of one is allowed, in which case every key shard can
+    /// recover the backup (and re-create any other key shard) on its own --
+    /// only the codewords of the key shard protect the secret.
+    pub fn quorum_size(mut self, quorum_size: u32) -> Self {
+        self.quorum_size = quorum_size;
+        self
+    }
+
+    /// The largest quorum size to permit. Defaults to
+    /// `Backup::MAX_QUORUM_SIZE`, and can be lowered to enforce a stricter
+    /// policy or raised if you really need a larger quorum (and are willing to
+    /// wait for it to be recovered).
+    pub fn max_quorum_size(mut self, max_quorum_size: u32) -> Self {
+        self.max_quorum_size = Some(max_quorum_size);
+        self
+    }
+
+    /// Whether the backup is sealed, meaning no new key shards can be created
+    /// once the `Backup` is dropped. Defaults to `false`. This is shorthand
+    /// for `shard_policy(ShardPolicy::Sealed)` (or `ShardPolicy::Any`).
+    pub fn sealed(mut self, sealed: bool) -> Self {
+        self.shard_policy = match sealed {
+            true => ShardPolicy::Sealed,
+            false => ShardPolicy::Any,
+        };
+        self
+    }
+
+    /// Which kinds of key shards quorums can create once the `Backup` is
+    /// dropped (see `Quorum::new_shard`). Defaults to `ShardPolicy::Any`.
+    pub fn shard_policy(mut self, shard_policy: ShardPolicy) -> Self {
+        self.shard_policy = shard_policy;
+        self
+    }
+
+    /// When the backup is marked as created. Defaults to the current time.
+    pub fn created(mut self, created: SystemTime) -> Self {
+        self.created = Some(created);
+        self
+    }
+
+    /// Mark the main document and key shards as expiring at the given time.
+    pub fn expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Encrypt the secret to the given age X25519 `recipient` (see
+    /// `Backup::new_with_recipient`).
+    pub fn recipient(mut self, recipient: AgeRecipient) -> Self {
+        self.recipient = Some(recipient);
+        self
+    }
+
+    /// Digest algorithm used for the main document's checksum (and thus its
+    /// document id). Defaults to Blake2b-256.
+    pub fn checksum_algorithm(mut self, checksum_algorithm: ChecksumAlgorithm) -> Self {
+        self.checksum_algorithm = checksum_algorithm;
+        self
+    }
+
+    /// A human-readable title for the backup (such as "2024 estate
+    /// documents"), which is printed on the main document. The title is not
+    /// encrypted, but it is authenticated so it cannot be modified without
+    /// detection. Defaults to no title.
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Commit to the first `num_shards` key shards of the backup in the main
+    /// document (see `ShardCommitment`), so that key shards which were not
+    /// part of the original set can be detected with
+    /// `MainDocument::is_committed_shard`. Defaults to no commitment.
+    ///
+    /// The committed key shards are generated along with the main document,
+    /// and are handed out first by `Backup::next_shard` (and
+    /// `Backup::next_shards`). `num_shards` must be at least the quorum size.
+    pub fn commit_shards(mut self, num_shards: u32) -> Self {
+        self.commit_shards = Some(num_shards);
+        self
+    }
+
+    /// Derive public information from the secret data with `deriver` (such as
+    /// `Bip32`, to print the extended public key of a cryptocurrency wallet)
+    /// and print it on the main document, so that the backup can be
+    /// identified without recovering it. Only the derived public information
+    /// is stored in the clear. Defaults to no public information.
+    ///
+    /// Backups of named payloads cannot have public information.
+    pub fn derive_public_info<D: DerivePublicInfo + 'static>(mut self, deriver: D) -> Self {
+        self.public_info = Some(Box::new(deriver));
+        self
+    }
+
+    /// Fail with `Error::SecretTooLarge` if the main document cannot be
+    /// printed with `options` (see `MainDocument::max_secret_size`), rather
+    /// than only finding out when generating its PDF. Defaults to no limit.
+    #[cfg(feature = "pdf")]
+    pub fn pdf_options(mut self, options: &PdfOptions) -> Self {
+        self.pdf_options = Some(options.clone());
+        self
+    }
+
+    /// Generate all keys, nonces and shards using `rng` rather than the
+    /// operating system's random number generator (see `Backup::new_with_rng`).
+    pub fn with_rng<R: CryptoRng + RngCore + Send + 'static>(mut self, rng: R) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    /// Create a backup of `secret`.
+    pub fn build<B: AsRef<[u8]>>(self, secret: B) -> Result<Backup, Error> {
+        self.inner_build(secret.as_ref(), PlaintextKind::Secret)
+    }
+
+    /// Create a backup containing several named payloads (see
+    /// `Backup::new_with_payloads`).
+    pub fn build_with_payloads<P: IntoIterator<Item = NamedPayload>>(
+        self,
+        payloads: P,
+    ) -> Result<Backup, Error> {
+        let plaintext = Zeroizing::new(NamedPayloads::new(payloads)?.to_wire());
+        self.inner_build(&plaintext, PlaintextKind::NamedPayloads)
+    }
+
+    /// Create a backup of a BIP-39 seed phrase. The phrase is stored in its
+    /// binary form (which needs far fewer QR codes than the text), and is
+    /// rendered as text again by `Quorum::recover_document`.
+    pub fn build_seed_phrase(self, seed_phrase: &SeedPhrase) -> Result<Backup, Error> {
+        let plaintext = Zeroizing::new(seed_phrase.to_wire());
+        self.inner_build(&plaintext, PlaintextKind::SeedPhrase)
+    }
+
+    #[instrument(
+        name = "backup",
+        skip_all,
+        fields(
+            quorum_size = self.quorum_size,
+            secret_len = secret.len(),
+            plaintext_kind = ?plaintext_kind,
+        )
+    )]
+    fn inner_build(self, secret: &[u8], plaintext_kind: PlaintextKind) -> Result<Backup, Error> {
+        if let Some(title) = &self.title {
+            MainDocument::check_title(title)?;
+        }
+        let max_quorum_size = self.max_quorum_size.unwrap_or(Backup::MAX_QUORUM_SIZE);
+        if let Some(num_shards) = self.commit_shards {
+            Backup::check_policy_with_limit(self.quorum_size, num_shards, max_quorum_size)?;
+        }
+        let public_info = match &self.public_info {
+            Some(deriver) => {
+                let public_info = match plaintext_kind {
+                    PlaintextKind::Secret => deriver.derive_public_info(SecretData::Secret(secret)),
+                    PlaintextKind::SeedPhrase => {
+                        let seed_phrase =
+                            SeedPhrase::from_wire(secret).map_err(Error::SeedPhraseDecode)?;
+                        deriver.derive_public_info(SecretData::SeedPhrase(&seed_phrase))
+                    }
+                    PlaintextKind::NamedPayloads => Err(Error::DerivePublicInfo(
+                        "named payloads have no single secret to derive it from".to_string(),
+                    )),
+                }?;
+                PublicInfo::check_all(&public_info)?;
+                public_info
+            }
+            None => vec![],
+        };
+        let main_document_meta = MainDocumentMeta {
+            version: PAPERBACK_VERSION,
+            quorum_size: self.quorum_size,
+            timestamps: Timestamps::new(
+                self.created.unwrap_or_else(SystemTime::now),
+                self.expires.map(to_unix_timestamp),
+            ),
+            plaintext_kind,
+            recipient_wrapped: self.recipient.is_some(),
+            checksum_algorithm: self.checksum_algorithm,
+            title: self.title,
+            shard_commitment: None,
+            public_info,
+            aad_version: AadVersion::V1,
+        };
+        let backup = Backup::inner_new(
+            main_document_meta,
+            secret,
+            self.recipient.as_ref(),
+            self.shard_policy,
+            max_quorum_size,
+            self.commit_shards,
+            self.rng.unwrap_or_else(|| Box::new(OsRng)),
+        )?;
+        #[cfg(feature = "pdf")]
+        if let Some(options) = &self.pdf_options {
+            backup.main_document().check_secret_size(options)?;
+        }
+        Ok(backup)
+    }
+}
+
+/// Serialised state of a `Backup`, see `Backup::seal_session`.
+pub(super) struct BackupSession {
+    pub(super) version: u32, // must be 0 for this version
+    pub(super) main_document: MainDocument,
+    pub(super) id_keypair: SigningKey,
+    pub(super) shards: Vec<Shard>,
+    pub(super) committed_shards: Vec<KeyShard>,
+}
+
+/// An encrypted `Backup` session, which can be stored to resume generating
+/// shards for a backup at a later point.
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub struct EncryptedBackupSession {
+    pub(super) nonce: ChaChaPolyNonce,
+    pub(super) ciphertext: Vec<u8>,
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for EncryptedBackupSession {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let mut nonce = ChaChaPolyNonce::default();
+        crate::v0::arbitrary_fill_slice(g, &mut nonce);
+        let ciphertext = Vec::<u8>::arbitrary(g);
+        Self { nonce, ciphertext }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use multibase::Base;
+    use quickcheck::TestResult;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[quickcheck]
+    fn backup_session_roundtrip(quorum_size: u8, secret: Vec<u8>) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        let backup = Backup::new(quorum_size.into(), &secret).unwrap();
+        let old_shards = (0..quorum_size / 2 + 1)
+            .map(|_| backup.next_shard().unwrap())
+            .collect::<Vec<_>>();
+
+        // Go through a round-trip through serialisation.
+        let (session, codewords) = backup.seal_session().unwrap();
+        let session = {
+            let zbase32_bytes = session.to_wire_multibase(Base::Base32Z);
+            EncryptedBackupSession::from_wire_multibase(zbase32_bytes).unwrap()
+        };
+        let resumed = Backup::resume_session(&session, codewords).unwrap();
+        if resumed.main_document() != backup.main_document() {
+            return TestResult::failed();
+        }
+
+        // Mix shards from before and after the session was resumed.
+        let mut quorum = crate::v0::UntrustedQuorum::new();
+        quorum.main_document(resumed.main_document().clone());
+        for shard in old_shards
+            .into_iter()
+            .chain((0..).map(|_| resumed.next_shard().unwrap()))
+            .take(quorum_size.into())
+        {
+            quorum.push_shard(shard);
+        }
+        let quorum = quorum.validate().unwrap();
+
+        TestResult::from_bool(*quorum.recover_document().unwrap() == secret)
+    }
+
+    #[test]
+    fn sealed_backup_session() {
+        let backup = Backup::builder()
+            .quorum_size(2)
+            .shard_policy(ShardPolicy::Sealed)
+            .build(b"secret")
+            .unwrap();
+        assert!(matches!(
+            backup.seal_session(),
+            Err(Error::MissingCapability(_))
+        ));
+    }
+
+    #[quickcheck]
+    fn backup_with_rng_reproducible(quorum_size: u8, secret: Vec<u8>, seed: u64) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        let created = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let generate = || {
+            let backup = Backup::new_with_rng(
+                quorum_size.into(),
+                &secret,
+                created,
+                StdRng::seed_from_u64(seed),
+            )
+            .unwrap();
+            let mut rng = StdRng::seed_from_u64(seed);
+            let shards = (0..quorum_size)
+                .map(|_| backup.next_shard().unwrap())
+                .map(|s| {
+                    let (shard, codewords) = s
+                        .encrypt_with_rng(DEFAULT_CODEWORD_LANGUAGE, &mut rng)
+                        .unwrap();
+                    (shard.to_wire(), codewords)
+                })
+                .collect::<Vec<_>>();
+            (backup.main_document().to_wire(), shards)
+        };
+
+        TestResult::from_bool(generate() == generate())
+    }
+
+    #[quickcheck]
+    fn backup_builder_matches_new_with_rng(
+        quorum_size: u8,
+        secret: Vec<u8>,
+        seed: u64,
+    ) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        let created = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let constructor = Backup::new_with_rng(
+            quorum_size.into(),
+            &secret,
+            created,
+            StdRng::seed_from_u64(seed),
+        )
+        .unwrap();
+        let builder = Backup::builder()
+            .quorum_size(quorum_size.into())
+            .created(created)
+            .with_rng(StdRng::seed_from_u64(seed))
+            .build(&secret)
+            .unwrap();
+
+        TestResult::from_bool(
+            constructor.main_document().to_wire() == builder.main_document().to_wire(),
+        )
+    }
+
+    #[test]
+    fn backup_policy_errors() {
+        assert!(matches!(
+            Backup::new(0, b"secret"),
+            Err(Error::QuorumTooSmall(0))
+        ));
+        assert!(matches!(
+            Backup::builder().build(b"secret"),
+            Err(Error::QuorumTooSmall(0))
+        ));
+        assert!(matches!(
+            Backup::new(Backup::MAX_QUORUM_SIZE + 1, b"secret"),
+            Err(Error::QuorumTooLarge {
+                max_quorum_size: Backup::MAX_QUORUM_SIZE,
+                ..
+            })
+        ));
+        assert!(matches!(
+            Backup::builder()
+                .quorum_size(5)
+                .max_quorum_size(4)
+                .build(b"secret"),
+            Err(Error::QuorumTooLarge {
+                quorum_size: 5,
+                max_quorum_size: 4
+            })
+        ));
+        assert!(Backup::builder()
+            .quorum_size(Backup::MAX_QUORUM_SIZE + 1)
+            .max_quorum_size(Backup::MAX_QUORUM_SIZE + 1)
+            .build(b"secret")
+            .is_ok());
+        assert!(matches!(
+            Backup::check_policy(3, 2),
+            Err(Error::ShardsLessThanQuorum {
+                shards: 2,
+                quorum_size: 3
+            })
+        ));
+        assert!(Backup::check_policy(1, 1).is_ok());
+        assert!(Backup::check_policy(3, 5).is_ok());
+        assert!(matches!(
+            Backup::check_policy_with_limit(3, 5, 2),
+            Err(Error::QuorumTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn backup_title() {
+        let backup = Backup::builder()
+            .quorum_size(2)
+            .title("2024 estate documents")
+            .build(b"secret")
+            .unwrap();
+        let main_document = backup.main_document();
+        assert_eq!(main_document.title(), Some("2024 estate documents"));
+
+        // The title survives a round-trip through serialisation.
+        let main_document2 = MainDocument::from_wire(main_document.to_wire()).unwrap();
+        assert_eq!(main_document2.title(), Some("2024 estate documents"));
+
+        // Modifying the title invalidates the signature.
+        let mut tampered = main_document.clone();
+        tampered.inner.meta.title = Some("2025 estate documents".to_string());
+        assert!(main_document.has_valid_signature());
+        assert!(!tampered.has_valid_signature());
+
+        // Backups without a title don't have one.
+        assert_eq!(
+            Backup::new(2, b"secret").unwrap().main_document().title(),
+            None
+        );
+
+        let long_title = "x".repeat(Backup::MAX_TITLE_LENGTH + 1);
+        for title in ["", "multi\nline", &long_title] {
+            assert!(matches!(
+                Backup::builder()
+                    .quorum_size(2)
+                    .title(title)
+                    .build(b"secret"),
+                Err(Error::InvalidTitle(_))
+            ));
         }
-        .sign(&self.id_keypair))
+    }
+
+    #[test]
+    fn backup_public_info() {
+        use crate::v0::Bip32;
+
+        let seed_phrase = SeedPhrase::from_entropy([0x42; 16], DEFAULT_CODEWORD_LANGUAGE).unwrap();
+        let backup = Backup::builder()
+            .quorum_size(2)
+            .derive_public_info(Bip32::default())
+            .build_seed_phrase(&seed_phrase)
+            .unwrap();
+        let main_document = backup.main_document();
+        let public_info = main_document.public_info().to_vec();
+        assert_eq!(
+            public_info,
+            Bip32::default()
+                .derive_public_info(SecretData::SeedPhrase(&seed_phrase))
+                .unwrap()
+        );
+
+        // The public information survives a round-trip through serialisation.
+        let main_document2 = MainDocument::from_wire(main_document.to_wire()).unwrap();
+        assert_eq!(main_document2.public_info(), public_info.as_slice());
+
+        // Modifying the public information invalidates the signature.
+        let mut tampered = main_document.clone();
+        tampered.inner.meta.public_info[1].value = "xpub".to_string();
+        assert!(main_document.has_valid_signature());
+        assert!(!tampered.has_valid_signature());
+
+        // Backups without a deriver don't have any public information.
+        assert!(Backup::new(2, b"secret")
+            .unwrap()
+            .main_document()
+            .public_info()
+            .is_empty());
+
+        // The deriver can reject the secret data.
+        assert!(matches!(
+            Backup::builder()
+                .quorum_size(2)
+                .derive_public_info(Bip32::default())
+                .build(b"too short"),
+            Err(Error::DerivePublicInfo(_))
+        ));
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn backup_secret_size_limit() {
+        use crate::v0::{QrDensity, ToPdf};
+
+        let options = PdfOptions {
+            qr_density: QrDensity::Low,
+            ..Default::default()
+        };
+        let max_secret_size = Backup::new(2, b"secret")
+            .unwrap()
+            .main_document()
+            .max_secret_size(&options);
+
+        // The largest secret still fits in a main document PDF ...
+        let backup = Backup::builder()
+            .quorum_size(2)
+            .pdf_options(&options)
+            .build(vec![0xa5; max_secret_size])
+            .unwrap();
+        assert_eq!(backup.main_document().secret_size(), max_secret_size);
+        assert!(backup.main_document().to_pdf_with_options(&options).is_ok());
+
+        // ... but anything larger is rejected up front.
+        assert!(matches!(
+            Backup::builder()
+                .quorum_size(2)
+                .pdf_options(&options)
+                .build(vec![0xa5; max_secret_size + 1]),
+            Err(Error::SecretTooLarge { size, max_secret_size: max })
+                if size == max_secret_size + 1 && max == max_secret_size
+        ));
+
+        // Without any PdfOptions, there is no limit.
+        assert!(Backup::new(2, vec![0xa5; max_secret_size + 1]).is_ok());
     }
 }
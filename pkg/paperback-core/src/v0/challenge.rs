@@ -0,0 +1,186 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    ChaChaPolyKey, ChaChaPolyNonce, ChaChaPolyTag, ChecksumAlgorithm, KeyShard, Multihash, ShardId,
+    ToWire, PAPERBACK_VERSION,
+};
+
+use aead::{AeadCore, AeadInPlace};
+use chacha20poly1305::ChaCha20Poly1305;
+use crypto_common::KeyInit;
+use rand::rngs::OsRng;
+use zeroize::Zeroizing;
+
+/// Domain separation prefix for `ChallengeChecksum`, so that it cannot be
+/// confused with any other checksum of the key shard.
+const CHALLENGE_CHECKSUM_DOMAIN: &[u8] = b"paperback-v0-shard-challenge";
+
+/// A secret checksum of the contents of a key shard, used to verify responses
+/// to a `ShardChallenge` without having the key shard.
+///
+/// Unlike the (public) checksum of an `EncryptedKeyShard`, this can only be
+/// calculated from the decrypted key shard, and so must be kept as secret as
+/// the key shard codewords. The shard registry stores it for every key shard
+/// recorded with `--register`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChallengeChecksum(pub(super) Multihash);
+
+impl ChallengeChecksum {
+    pub(super) fn new(shard: &KeyShard) -> Self {
+        let mut bytes = Zeroizing::new(CHALLENGE_CHECKSUM_DOMAIN.to_vec());
+        bytes.extend_from_slice(&Zeroizing::new(shard.to_wire()));
+        Self(ChecksumAlgorithm::default().digest(&bytes))
+    }
+
+    /// Compute the response tag for `challenge` from the key shard `shard_id`.
+    fn tag(&self, challenge: &ShardChallenge, shard_id: &ShardId) -> Option<ChaChaPolyTag> {
+        // Encrypting an empty message with ChaCha20-Poly1305 gives us a MAC
+        // of the associated data, keyed with the checksum.
+        let key = ChaChaPolyKey::from_exact_iter(self.0.digest().iter().copied())?;
+        ChaCha20Poly1305::new(&key)
+            .encrypt_in_place_detached(&challenge.nonce, shard_id.as_bytes(), &mut [])
+            .ok()
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ChallengeChecksum {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self(ChecksumAlgorithm::default().digest(&Vec::<u8>::arbitrary(g)))
+    }
+}
+
+/// A random challenge which the holder of a key shard can only answer if they
+/// still have a working copy of their key shard (including its codewords).
+///
+/// The owner of a backup sends a new challenge to a key shard holder, who
+/// answers it with `ShardChallenge::respond`. The owner then checks the
+/// response against the `ChallengeChecksum` they recorded when the key shard
+/// was created, without having to gather a quorum. Neither the challenge nor
+/// the response reveal anything about the key shard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardChallenge {
+    pub(super) version: u32,
+    pub(super) nonce: ChaChaPolyNonce,
+}
+
+impl ShardChallenge {
+    /// Generate a new random challenge.
+    pub fn new() -> Self {
+        Self {
+            version: PAPERBACK_VERSION,
+            nonce: ChaCha20Poly1305::generate_nonce(&mut OsRng),
+        }
+    }
+
+    /// Answer the challenge with the (decrypted) key shard.
+    pub fn respond(&self, shard: &KeyShard) -> ShardResponse {
+        let shard_id = shard.id();
+        let tag = ChallengeChecksum::new(shard)
+            .tag(self, &shard_id)
+            .expect("challenge checksum should be a valid key");
+        ShardResponse { shard_id, tag }
+    }
+
+    /// Check whether `response` is a correct answer to the challenge from the
+    /// key shard with the given `ChallengeChecksum`.
+    pub fn verify(&self, checksum: &ChallengeChecksum, response: &ShardResponse) -> bool {
+        checksum.tag(self, &response.shard_id) == Some(response.tag)
+    }
+}
+
+impl Default for ShardChallenge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ShardChallenge {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let mut nonce = ChaChaPolyNonce::default();
+        crate::v0::arbitrary_fill_slice(g, &mut nonce);
+        Self {
+            version: PAPERBACK_VERSION,
+            nonce,
+        }
+    }
+}
+
+/// A key shard holder's answer to a `ShardChallenge`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardResponse {
+    pub(super) shard_id: ShardId,
+    pub(super) tag: ChaChaPolyTag,
+}
+
+impl ShardResponse {
+    /// Identifier of the key shard which answered the challenge.
+    pub fn shard_id(&self) -> &ShardId {
+        &self.shard_id
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ShardResponse {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let mut tag = ChaChaPolyTag::default();
+        crate::v0::arbitrary_fill_slice(g, &mut tag);
+        Self {
+            shard_id: ShardId::arbitrary(g),
+            tag,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::Backup;
+
+    #[quickcheck]
+    fn challenge_response(quorum_size: u8, secret: Vec<u8>) -> bool {
+        let quorum_size = 1 + quorum_size as u32 % 8;
+        let backup = Backup::new(quorum_size, &secret).unwrap();
+        let shards = backup.next_shards(2).unwrap();
+        let (shard, other) = (&shards[0], &shards[1]);
+
+        let checksum = shard.challenge_checksum();
+        let challenge = ShardChallenge::new();
+        let response = challenge.respond(shard);
+
+        challenge.verify(&checksum, &response)
+            // The response doesn't answer any other challenge.
+            && !ShardChallenge::new().verify(&checksum, &response)
+            // Other key shards can't answer the challenge.
+            && !challenge.verify(&checksum, &challenge.respond(other))
+            && !challenge.verify(&other.challenge_checksum(), &response)
+    }
+
+    #[quickcheck]
+    fn challenge_checksum_stable(secret: Vec<u8>) -> bool {
+        // The checksum only depends on the key shard, so it must survive the
+        // key shard being encrypted and decrypted.
+        let backup = Backup::new(2, &secret).unwrap();
+        let shard = backup.next_shard().unwrap();
+        let (encrypted, codewords) = shard.encrypt().unwrap();
+        let decrypted = encrypted.decrypt(&codewords).unwrap();
+        shard.challenge_checksum() == decrypted.challenge_checksum()
+    }
+}
@@ -0,0 +1,106 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::Multihash;
+
+use multihash_codetable::{Code, MultihashDigest};
+
+/// Digest algorithm used for document checksums (and thus document ids).
+///
+/// The algorithm is recorded in each checksum (they are multihashes), so
+/// documents using any of these algorithms can be recovered regardless of
+/// which algorithm is selected when creating new backups.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    #[default]
+    Blake2b256,
+    Sha2_256,
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    pub const ALL: &'static [Self] = &[Self::Blake2b256, Self::Sha2_256, Self::Blake3];
+
+    fn code(self) -> Code {
+        match self {
+            ChecksumAlgorithm::Blake2b256 => Code::Blake2b256,
+            ChecksumAlgorithm::Sha2_256 => Code::Sha2_256,
+            ChecksumAlgorithm::Blake3 => Code::Blake3_256,
+        }
+    }
+
+    /// The multihash code of the algorithm.
+    pub fn multihash_code(self) -> u64 {
+        self.code().into()
+    }
+
+    pub fn from_multihash_code(code: u64) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.multihash_code() == code)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Blake2b256 => "blake2b-256",
+            ChecksumAlgorithm::Sha2_256 => "sha2-256",
+            ChecksumAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.name() == name)
+    }
+
+    pub fn digest(self, data: &[u8]) -> Multihash {
+        self.code().digest(data)
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ChecksumAlgorithm {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        *g.choose(Self::ALL).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn name_roundtrip() {
+        for &algorithm in ChecksumAlgorithm::ALL {
+            assert_eq!(
+                ChecksumAlgorithm::from_name(algorithm.name()),
+                Some(algorithm)
+            );
+        }
+        assert_eq!(ChecksumAlgorithm::from_name("md5"), None);
+    }
+
+    #[quickcheck]
+    fn digest_records_algorithm(algorithm: ChecksumAlgorithm, data: Vec<u8>) -> bool {
+        let hash = algorithm.digest(&data);
+        ChecksumAlgorithm::from_multihash_code(hash.code()) == Some(algorithm) && hash.size() == 32
+    }
+}
@@ -0,0 +1,317 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    CodewordLanguage, Error, KeyShardCodewords, Multihash, CODEWORD_LANGUAGES,
+    DEFAULT_CODEWORD_LANGUAGE,
+};
+
+use zeroize::Zeroizing;
+
+/// Shortest prefix of a codeword accepted in place of the full codeword. The
+/// BIP-39 wordlists are chosen such that the first four letters of each word
+/// uniquely identify it.
+pub const MIN_CODEWORD_PREFIX_LENGTH: usize = 4;
+
+fn wordlist(language: CodewordLanguage) -> &'static [&'static str] {
+    // NOTE: Not all of the wordlists are sorted, so we can't make use of
+    //       get_words_by_prefix() for actual prefixes -- but every word starts
+    //       with "" so this gives us the whole wordlist.
+    language.wordlist().get_words_by_prefix("")
+}
+
+/// Number of words in a checksum fingerprint. Each BIP-39 word encodes 11
+/// bits, so fingerprints cover the first 44 bits of the checksum.
+pub const FINGERPRINT_WORDS: usize = 4;
+
+/// Derive a short fingerprint of `checksum` from the English BIP-39 wordlist,
+/// so that two copies of a document can be compared verbally. Fingerprints
+/// are always English (regardless of the codeword language) so they are the
+/// same no matter how the document was created.
+pub(super) fn fingerprint(checksum: &Multihash) -> Vec<&'static str> {
+    let wordlist = wordlist(DEFAULT_CODEWORD_LANGUAGE);
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&checksum.digest()[..8]);
+    let bits = u64::from_be_bytes(prefix);
+    (0..FINGERPRINT_WORDS)
+        .map(|idx| wordlist[((bits >> (64 - 11 * (idx + 1))) & 0x7ff) as usize])
+        .collect()
+}
+
+/// Number of words in a friendly shard id. Shard ids are 32-bit values and each
+/// BIP-39 word encodes 11 bits, so three words are needed to represent every
+/// shard id without collisions.
+pub const FRIENDLY_ID_WORDS: usize = 3;
+
+/// Render a shard's x-value as English BIP-39 words separated by "-" (see
+/// `KeyShard::friendly_id`).
+pub(crate) fn friendly_id(x: u32) -> String {
+    let wordlist = wordlist(DEFAULT_CODEWORD_LANGUAGE);
+    (0..FRIENDLY_ID_WORDS)
+        .rev()
+        .map(|idx| wordlist[((u64::from(x) >> (11 * idx)) & 0x7ff) as usize])
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Parse a friendly shard id back into the shard's x-value. The words are
+/// case-insensitive, and can be separated by "-" or whitespace.
+pub(crate) fn parse_friendly_id(id: &str) -> Option<u32> {
+    let wordlist = wordlist(DEFAULT_CODEWORD_LANGUAGE);
+    let words = id
+        .split(|ch: char| ch == '-' || ch.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    if words.len() != FRIENDLY_ID_WORDS {
+        return None;
+    }
+    let x = words.iter().try_fold(0u64, |x, word| {
+        let word = word.to_lowercase();
+        let idx = wordlist.iter().position(|w| *w == word)?;
+        Some((x << 11) | idx as u64)
+    })?;
+    u32::try_from(x).ok()
+}
+
+/// Returns all codewords (in any supported language) starting with `prefix`.
+pub fn completions(prefix: &str) -> Vec<&'static str> {
+    let mut completions = CODEWORD_LANGUAGES
+        .iter()
+        .flat_map(|&language| wordlist(language))
+        .filter(|word| word.starts_with(prefix))
+        .copied()
+        .collect::<Vec<_>>();
+    completions.sort_unstable();
+    completions.dedup();
+    completions
+}
+
+/// Returns whether `a` can be turned into `b` with a single insertion,
+/// deletion, or substitution.
+fn is_single_edit(a: &str, b: &str) -> bool {
+    let (a, b) = (a.chars().collect::<Vec<_>>(), b.chars().collect::<Vec<_>>());
+    let (short, long) = match a.len() <= b.len() {
+        true => (a, b),
+        false => (b, a),
+    };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+    if short.len() == long.len() {
+        // Exactly one substitution.
+        prefix < short.len() && short[prefix + 1..] == long[prefix + 1..]
+    } else {
+        // Exactly one insertion (into the shorter word).
+        short[prefix..] == long[prefix + 1..]
+    }
+}
+
+/// Resolve `word` to a codeword in `language`, or return the codewords the
+/// user might have meant.
+fn normalize_word(word: &str, language: CodewordLanguage) -> Result<&'static str, Vec<String>> {
+    let wordlist = wordlist(language);
+    if let Some(codeword) = wordlist.iter().copied().find(|&codeword| codeword == word) {
+        return Ok(codeword);
+    }
+
+    if word.chars().count() >= MIN_CODEWORD_PREFIX_LENGTH {
+        let matches = wordlist
+            .iter()
+            .copied()
+            .filter(|codeword| codeword.starts_with(word))
+            .collect::<Vec<_>>();
+        match matches.as_slice() {
+            [] => (),
+            [codeword] => return Ok(*codeword),
+            // An ambiguous prefix, so suggest every possibility.
+            _ => return Err(matches.iter().map(|s| s.to_string()).collect()),
+        }
+    }
+
+    Err(wordlist
+        .iter()
+        .filter(|codeword| is_single_edit(word, codeword))
+        .map(|s| s.to_string())
+        .collect())
+}
+
+/// Resolve every codeword in `codewords` (all in `language`), or return the
+/// index of the first codeword that could not be resolved along with any
+/// suggested corrections.
+fn normalize_language(
+    codewords: &[Zeroizing<String>],
+    language: CodewordLanguage,
+) -> Result<KeyShardCodewords, (usize, Vec<String>)> {
+    codewords
+        .iter()
+        .enumerate()
+        .map(|(idx, word)| {
+            normalize_word(word, language)
+                .map(|s| s.to_owned())
+                .map_err(|suggestions| (idx, suggestions))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Into::into)
+}
+
+pub(super) fn format_suggestions(suggestions: &[String]) -> String {
+    match suggestions {
+        [] => String::new(),
+        _ => format!(" (did you mean {}?)", suggestions.join(" or ")),
+    }
+}
+
+/// Normalise user-entered codewords before they are used to decrypt a key
+/// shard (or backup session).
+///
+/// Codewords are lowercased and any unique prefix of at least
+/// `MIN_CODEWORD_PREFIX_LENGTH` letters is expanded to the full codeword. If a
+/// word cannot be resolved, `Error::UnknownCodeword` is returned along with any
+/// codewords that are a single typo away from what was entered.
+///
+/// All of the codewords must come from the same wordlist, but the language is
+/// auto-detected.
+pub fn normalize<S: AsRef<str>>(codewords: &[S]) -> Result<KeyShardCodewords, Error> {
+    let codewords = codewords
+        .iter()
+        .map(|word| Zeroizing::new(word.as_ref().trim().to_lowercase()))
+        .collect::<Vec<_>>();
+
+    // If the codewords are already valid in some language, don't touch them.
+    // Wordlists share some words, so expanding prefixes in one language could
+    // otherwise mangle a valid phrase from another language.
+    if CODEWORD_LANGUAGES.iter().any(|&language| {
+        codewords
+            .iter()
+            .all(|word| wordlist(language).contains(&word.as_str()))
+    }) {
+        return Ok(codewords
+            .iter()
+            .map(|word| word.to_string())
+            .collect::<Vec<_>>()
+            .into());
+    }
+
+    // Otherwise, use the first language in which every word can be resolved.
+    // If there is none, report the failure from the language that got the
+    // furthest (which is most likely the language the user meant).
+    let mut best_err: Option<(usize, Vec<String>)> = None;
+    for &language in CODEWORD_LANGUAGES {
+        match normalize_language(&codewords, language) {
+            Ok(normalized) => return Ok(normalized),
+            Err((idx, suggestions)) => {
+                if best_err.as_ref().is_none_or(|(best, _)| idx > *best) {
+                    best_err = Some((idx, suggestions));
+                }
+            }
+        }
+    }
+    let (idx, suggestions) = best_err.unwrap_or_default();
+    Err(Error::UnknownCodeword {
+        index: idx,
+        word: codewords
+            .get(idx)
+            .map(|word| word.to_string())
+            .unwrap_or_default(),
+        suggestions,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::{ChecksumAlgorithm, KeyShard};
+
+    #[test]
+    fn single_edit() {
+        assert!(is_single_edit("abandon", "abandan"));
+        assert!(is_single_edit("abandon", "abandn"));
+        assert!(is_single_edit("abandon", "abandoon"));
+        assert!(!is_single_edit("abandon", "abandon"));
+        assert!(!is_single_edit("abandon", "abnadon"));
+        assert!(!is_single_edit("abandon", "aband"));
+    }
+
+    #[test]
+    fn normalize_typo_suggestions() {
+        match normalize(&["abandon", "zoa"]) {
+            Err(Error::UnknownCodeword {
+                index,
+                word,
+                suggestions,
+            }) => {
+                assert_eq!(index, 1);
+                assert_eq!(word, "zoa");
+                assert!(suggestions.contains(&"zoo".to_string()));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[quickcheck]
+    fn fingerprint_stable(data: Vec<u8>, other: Vec<u8>) -> bool {
+        let checksum = ChecksumAlgorithm::default().digest(&data);
+        let words = fingerprint(&checksum);
+        words.len() == FINGERPRINT_WORDS
+            && words == fingerprint(&ChecksumAlgorithm::default().digest(&data))
+            && (data == other || words != fingerprint(&ChecksumAlgorithm::default().digest(&other)))
+    }
+
+    #[quickcheck]
+    fn friendly_id_roundtrip(x: u32) -> bool {
+        let id = friendly_id(x);
+        id.split('-').count() == FRIENDLY_ID_WORDS
+            && parse_friendly_id(&id) == Some(x)
+            && parse_friendly_id(&id.to_uppercase().replace('-', " ")) == Some(x)
+    }
+
+    #[test]
+    fn friendly_id_invalid() {
+        assert_eq!(friendly_id(0), "abandon-abandon-abandon");
+        assert_eq!(parse_friendly_id("abandon-abandon"), None);
+        assert_eq!(parse_friendly_id("abandon-abandon-notaword"), None);
+        // The first word only encodes 10 bits.
+        assert_eq!(parse_friendly_id("zoo-abandon-abandon"), None);
+        assert_eq!(parse_friendly_id("hayyayyy"), None);
+    }
+
+    #[quickcheck]
+    fn normalize_prefixes(shard: KeyShard) -> bool {
+        let (_, codewords) = shard.encrypt().unwrap();
+        let prefixes = codewords
+            .iter()
+            .map(|word| {
+                word.chars()
+                    .take(MIN_CODEWORD_PREFIX_LENGTH)
+                    .collect::<String>()
+                    .to_uppercase()
+            })
+            .collect::<Vec<_>>();
+
+        normalize(&prefixes).unwrap() == codewords
+    }
+
+    #[quickcheck]
+    fn normalize_valid_unchanged(shard: KeyShard, lang_idx: usize) -> bool {
+        let language = CODEWORD_LANGUAGES[lang_idx % CODEWORD_LANGUAGES.len()];
+        let (_, codewords) = shard.encrypt_with_language(language).unwrap();
+
+        normalize(&codewords).unwrap() == codewords
+    }
+}
@@ -0,0 +1,236 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    shamir::Shard,
+    v0::{KeyShard, ToWire},
+};
+
+#[cfg(feature = "std")]
+use alloc::vec;
+use alloc::vec::Vec;
+
+use multihash_codetable::{Code, MultihashDigest};
+use zeroize::Zeroizing;
+
+/// Length of the hashes in a `ShardCommitment` Merkle tree.
+pub(super) const COMMITMENT_HASH_LENGTH: usize = 32;
+
+/// The maximum length of a `ShardCommitmentProof`, which allows for far more
+/// key shards than could ever be printed.
+pub(super) const MAX_COMMITMENT_PROOF_LENGTH: usize = 32;
+
+pub(super) type CommitmentHash = [u8; COMMITMENT_HASH_LENGTH];
+
+/// Domain separation prefixes for the leaves and inner nodes of the Merkle
+/// tree, so that an inner node can never be passed off as a key shard.
+const COMMITMENT_LEAF_DOMAIN: &[u8] = b"paperback-v0-shard-commitment-leaf";
+const COMMITMENT_NODE_DOMAIN: &[u8] = b"paperback-v0-shard-commitment-node";
+
+fn commitment_hash(domain: &[u8], parts: &[&[u8]]) -> CommitmentHash {
+    let mut bytes = Zeroizing::new(domain.to_vec());
+    parts.iter().for_each(|part| bytes.extend_from_slice(part));
+
+    let mut hash = CommitmentHash::default();
+    hash.copy_from_slice(Code::Blake2b256.digest(&bytes).digest());
+    hash
+}
+
+fn leaf_hash(shard: &Shard) -> CommitmentHash {
+    let wire_shard = Zeroizing::new(shard.to_wire());
+    commitment_hash(COMMITMENT_LEAF_DOMAIN, &[wire_shard.as_slice()])
+}
+
+fn node_hash(left: &CommitmentHash, right: &CommitmentHash) -> CommitmentHash {
+    commitment_hash(COMMITMENT_NODE_DOMAIN, &[&left[..], &right[..]])
+}
+
+/// A commitment (the root of a Merkle tree) to the key shards initially
+/// created for a backup, stored in the main document (see
+/// `BackupBuilder::commit_shards`).
+///
+/// Every key shard is signed with the same identity key, so anyone who saw
+/// the private key before a backup was sealed could sign a key shard which
+/// was never actually created with the backup. Each committed key shard
+/// carries a proof that it is part of the commitment, which cannot be forged
+/// even with the private key, so such key shards can be detected with
+/// `MainDocument::is_committed_shard`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShardCommitment {
+    pub(super) num_shards: u32,
+    pub(super) root: CommitmentHash,
+}
+
+impl ShardCommitment {
+    /// Commit to `shards`, returning the commitment along with the proof for
+    /// each of the shards (in the same order). `shards` must not be empty.
+    #[cfg(feature = "std")]
+    pub(super) fn new(shards: &[Shard]) -> (Self, Vec<ShardCommitmentProof>) {
+        assert!(
+            !shards.is_empty(),
+            "cannot commit to an empty set of shards"
+        );
+
+        let mut proofs = (0..shards.len())
+            .map(|index| ShardCommitmentProof {
+                index: index as u32,
+                path: vec![],
+            })
+            .collect::<Vec<_>>();
+
+        // Nodes without a sibling are moved up to the next level unchanged.
+        let mut level = shards.iter().map(leaf_hash).collect::<Vec<_>>();
+        let mut indices = (0..shards.len()).collect::<Vec<_>>();
+        while level.len() > 1 {
+            for (proof, idx) in proofs.iter_mut().zip(indices.iter_mut()) {
+                if let Some(sibling) = level.get(*idx ^ 1) {
+                    proof.path.push(*sibling);
+                }
+                *idx /= 2;
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [node] => *node,
+                    _ => unreachable!("chunks(2) only returns one or two nodes"),
+                })
+                .collect();
+        }
+
+        (
+            Self {
+                num_shards: shards.len() as u32,
+                root: level[0],
+            },
+            proofs,
+        )
+    }
+
+    /// Number of key shards committed to.
+    pub fn num_shards(&self) -> u32 {
+        self.num_shards
+    }
+
+    /// Whether `proof` shows that `shard` is one of the committed key shards.
+    pub(super) fn verify(&self, shard: &Shard, proof: &ShardCommitmentProof) -> bool {
+        if proof.index >= self.num_shards {
+            return false;
+        }
+
+        let mut path = proof.path.iter();
+        let mut hash = leaf_hash(shard);
+        let (mut idx, mut len) = (proof.index, self.num_shards);
+        while len > 1 {
+            if idx % 2 == 1 {
+                match path.next() {
+                    Some(sibling) => hash = node_hash(sibling, &hash),
+                    None => return false,
+                }
+            } else if idx + 1 < len {
+                match path.next() {
+                    Some(sibling) => hash = node_hash(&hash, sibling),
+                    None => return false,
+                }
+            }
+            idx /= 2;
+            len = len.div_ceil(2);
+        }
+        path.next().is_none() && hash == self.root
+    }
+
+    /// Whether `shard` is one of the committed key shards.
+    pub(super) fn contains(&self, shard: &KeyShard) -> bool {
+        shard
+            .inner
+            .commitment_proof
+            .as_ref()
+            .is_some_and(|proof| self.verify(&shard.inner.shard, proof))
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ShardCommitment {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let mut root = CommitmentHash::default();
+        crate::v0::arbitrary_fill_slice(g, &mut root);
+        Self {
+            num_shards: 1 + u32::arbitrary(g) % 1024,
+            root,
+        }
+    }
+}
+
+/// Proof that a key shard is one of the key shards committed to by a
+/// `ShardCommitment` (the sibling hashes along the path from the key shard's
+/// leaf to the root of the Merkle tree).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct ShardCommitmentProof {
+    pub(super) index: u32,
+    pub(super) path: Vec<CommitmentHash>,
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ShardCommitmentProof {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let len = usize::arbitrary(g) % (MAX_COMMITMENT_PROOF_LENGTH + 1);
+        Self {
+            index: u32::arbitrary(g),
+            path: (0..len)
+                .map(|_| {
+                    let mut hash = CommitmentHash::default();
+                    crate::v0::arbitrary_fill_slice(g, &mut hash);
+                    hash
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shamir::Dealer;
+
+    #[quickcheck]
+    fn commitment_proofs_verify(num_shards: u8, secret: Vec<u8>) -> bool {
+        let num_shards = 1 + num_shards as usize % 40;
+        let shards = Dealer::new(2, &secret).next_shards(num_shards).unwrap();
+        let (commitment, proofs) = ShardCommitment::new(&shards);
+
+        commitment.num_shards() as usize == num_shards
+            && shards
+                .iter()
+                .zip(&proofs)
+                .all(|(shard, proof)| commitment.verify(shard, proof))
+    }
+
+    #[quickcheck]
+    fn commitment_rejects_other_shards(num_shards: u8, secret: Vec<u8>) -> bool {
+        let num_shards = 1 + num_shards as usize % 40;
+        let dealer = Dealer::new(2, &secret);
+        let shards = dealer.next_shards(num_shards).unwrap();
+        let (commitment, proofs) = ShardCommitment::new(&shards);
+        let other = dealer.next_shard();
+
+        // Neither a new key shard nor a committed key shard with the wrong
+        // proof can pass the check.
+        proofs.iter().all(|proof| !commitment.verify(&other, proof))
+            && (num_shards == 1 || !commitment.verify(&shards[0], &proofs[1]))
+    }
+}
@@ -0,0 +1,295 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{Error, Multihash};
+
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::{borrow::Borrow, fmt, ops::Deref, str::FromStr};
+
+use multibase::Base;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// Both kinds of identifier are written in base32z, as it is easy to read and
+// transcribe (and doesn't mix cases).
+const ID_MULTIBASE: Base = Base::Base32Z;
+const ID_ALPHABET: &str = "ybndrfg8ejkmcpqxot1uwisza345h769";
+
+/// Identifier of a key shard, such as `"hxxxxxxx"`.
+///
+/// Shard identifiers are the base32z multibase encoding of the x-value of the
+/// key shard. When parsed with `FromStr`, the equivalent "friendly" form made
+/// of three words (see `KeyShard::friendly_id`) is also accepted and converted
+/// to the usual form.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct ShardId(String);
+
+impl ShardId {
+    /// Length of a shard identifier (including the multibase prefix).
+    pub const LENGTH: usize = 8;
+
+    pub(crate) fn from_x_value(x: u32) -> Self {
+        Self(multibase::encode(ID_MULTIBASE, x.to_le_bytes()))
+    }
+
+    /// The x-value of the key shard with this identifier.
+    #[cfg(feature = "std")]
+    pub(crate) fn x_value(&self) -> u32 {
+        multibase::decode(&self.0)
+            .ok()
+            .and_then(|(_, data)| data.try_into().ok())
+            .map(u32::from_le_bytes)
+            .expect("shard id was validated when created")
+    }
+}
+
+impl FromStr for ShardId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        #[cfg(feature = "std")]
+        if let Some(x) = crate::v0::codewords::parse_friendly_id(id) {
+            return Ok(Self::from_x_value(x));
+        }
+
+        let invalid = |reason: &str| Error::ShardIdDecode(format!("'{}' {}", id, reason));
+        if id.len() != Self::LENGTH {
+            return Err(invalid(
+                "must be 8 characters long (or three words in the friendly form)",
+            ));
+        }
+        let (base, data) = multibase::decode(id).map_err(|err| invalid(&err.to_string()))?;
+        if base != ID_MULTIBASE {
+            return Err(invalid("is not base32z-encoded"));
+        }
+        let x = <[u8; 4]>::try_from(data)
+            .map(u32::from_le_bytes)
+            .map_err(|_| invalid("does not encode a 32-bit x-value"))?;
+
+        // Only accept the canonical encoding, so that each key shard has
+        // exactly one identifier.
+        let shard_id = Self::from_x_value(x);
+        if shard_id.0 != id {
+            return Err(invalid("is not a canonical shard id"));
+        }
+        Ok(shard_id)
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for ShardId {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self::from_x_value(u32::arbitrary(g))
+    }
+}
+
+/// Identifier of a main document (and the backup it belongs to), such as
+/// `"8kqbetat"`.
+///
+/// Document identifiers are the last 8 characters of the base32z multibase
+/// encoding of the main document's checksum.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct DocumentId(String);
+
+impl DocumentId {
+    /// Length of a document identifier.
+    pub const LENGTH: usize = 8;
+
+    pub(crate) fn from_checksum(checksum: Multihash) -> Self {
+        let encoded = multibase::encode(ID_MULTIBASE, checksum.to_bytes());
+        // The *suffix* is the ID.
+        Self(encoded[encoded.len() - Self::LENGTH..].to_string())
+    }
+}
+
+impl FromStr for DocumentId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| Error::DocumentIdDecode(format!("'{}' {}", id, reason));
+        // The identifier is only the suffix of a multibase string, so we can
+        // only check that it is made of base32z characters.
+        if id.chars().count() != Self::LENGTH {
+            return Err(invalid("must be 8 characters long".into()));
+        }
+        if let Some(ch) = id.chars().find(|&ch| !ID_ALPHABET.contains(ch)) {
+            return Err(invalid(format!("contains non-base32z character '{}'", ch)));
+        }
+        Ok(Self(id.to_string()))
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for DocumentId {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        use crate::v0::ChecksumAlgorithm;
+
+        let bytes = Vec::<u8>::arbitrary(g);
+        Self::from_checksum(ChecksumAlgorithm::arbitrary(g).digest(&bytes[..]))
+    }
+}
+
+// Implement the conversions shared by both identifier types, which all treat
+// the identifier as its (already-validated) string form.
+macro_rules! impl_id_conversions {
+    ($id:ty) => {
+        impl $id {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $id {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.pad(&self.0)
+            }
+        }
+
+        impl Deref for $id {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $id {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        // Needed for [ids].join(" ").
+        impl Borrow<str> for $id {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $id {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $id {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl TryFrom<String> for $id {
+            type Error = Error;
+
+            fn try_from(id: String) -> Result<Self, Self::Error> {
+                id.parse()
+            }
+        }
+
+        impl TryFrom<&str> for $id {
+            type Error = Error;
+
+            fn try_from(id: &str) -> Result<Self, Self::Error> {
+                id.parse()
+            }
+        }
+
+        impl From<$id> for String {
+            fn from(id: $id) -> Self {
+                id.0
+            }
+        }
+
+        impl From<&$id> for String {
+            fn from(id: &$id) -> Self {
+                id.0.clone()
+            }
+        }
+    };
+}
+
+impl_id_conversions!(ShardId);
+impl_id_conversions!(DocumentId);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::codewords;
+
+    #[quickcheck]
+    fn shard_id_roundtrip(id: ShardId) -> bool {
+        id.as_str().parse::<ShardId>().unwrap() == id
+            && ShardId::from_x_value(id.x_value()) == id
+            && id.len() == ShardId::LENGTH
+    }
+
+    #[quickcheck]
+    fn shard_id_friendly_form(x: u32) -> bool {
+        let friendly_id = codewords::friendly_id(x);
+        friendly_id.parse::<ShardId>().unwrap() == ShardId::from_x_value(x)
+    }
+
+    #[test]
+    fn shard_id_invalid() {
+        for id in [
+            "",
+            "hayyayy",
+            "hayyayyyy",
+            "Hayyayyy",
+            "bayyayyy",
+            "h!yyayyy",
+            "abandon-abandon",
+        ] {
+            assert!(
+                matches!(id.parse::<ShardId>(), Err(Error::ShardIdDecode(_))),
+                "{:?} should be an invalid shard id",
+                id
+            );
+        }
+    }
+
+    #[quickcheck]
+    fn document_id_roundtrip(id: DocumentId) -> bool {
+        id.as_str().parse::<DocumentId>().unwrap() == id && id.len() == DocumentId::LENGTH
+    }
+
+    #[test]
+    fn document_id_invalid() {
+        for id in [
+            "",
+            "8kqbeta",
+            "8kqbetatt",
+            "8KQBETAT",
+            "8kqbeta0",
+            "8kqbetaé",
+        ] {
+            assert!(
+                matches!(id.parse::<DocumentId>(), Err(Error::DocumentIdDecode(_))),
+                "{:?} should be an invalid document id",
+                id
+            );
+        }
+    }
+}
@@ -0,0 +1,147 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{DocumentId, Error, MainDocument};
+
+use std::time::SystemTime;
+
+/// Instructions for whoever needs to recover a backup once its owner no
+/// longer can (such as the executors of their estate), listing who holds the
+/// key shards and how to recover the backup.
+///
+/// Like the `Manifest`, the instructions contain no secret information (only
+/// identifiers, the quorum policy and the names of the key shard holders), so
+/// they can be left with the main document or a will.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstructionsDocument {
+    document_id: DocumentId,
+    document_checksum: String,
+    title: Option<String>,
+    quorum_size: u32,
+    created: Option<SystemTime>,
+    expires: Option<SystemTime>,
+    shard_holders: Vec<String>,
+}
+
+impl InstructionsDocument {
+    /// Maximum length (in bytes) of the name of a key shard holder.
+    pub const MAX_HOLDER_LENGTH: usize = 128;
+
+    /// Construct recovery instructions for the backup of `main_document`,
+    /// whose key shards are held by `shard_holders` (a free-form description
+    /// of each holder, such as their name and how to contact them).
+    pub fn new<I, S>(main_document: &MainDocument, shard_holders: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let shard_holders = shard_holders
+            .into_iter()
+            .map(|holder| {
+                let holder = holder.into();
+                match holder.is_empty()
+                    || holder.len() > Self::MAX_HOLDER_LENGTH
+                    || holder.chars().any(char::is_control)
+                {
+                    true => Err(Error::InvalidShardHolder(holder)),
+                    false => Ok(holder),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            document_id: main_document.id(),
+            document_checksum: main_document.checksum_string(),
+            title: main_document.title().map(str::to_string),
+            quorum_size: main_document.quorum_size(),
+            created: main_document.created(),
+            expires: main_document.expires(),
+            shard_holders,
+        })
+    }
+
+    pub fn document_id(&self) -> &DocumentId {
+        &self.document_id
+    }
+
+    pub fn document_checksum(&self) -> &str {
+        &self.document_checksum
+    }
+
+    /// Title of the backup, if it has one.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn quorum_size(&self) -> u32 {
+        self.quorum_size
+    }
+
+    /// When the backup was created (`None` for backups created by older
+    /// versions of paperback).
+    pub fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+
+    /// When the backup expires, if an expiry date was set.
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
+    /// The people holding the key shards of the backup.
+    pub fn shard_holders(&self) -> &[String] {
+        &self.shard_holders
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::Backup;
+
+    #[test]
+    fn instructions_list_holders() {
+        let backup = Backup::builder()
+            .quorum_size(2)
+            .title("Family photos")
+            .build(b"secret")
+            .unwrap();
+        let main_document = backup.main_document();
+
+        let instructions =
+            InstructionsDocument::new(main_document, ["Alice (+61 400 000 000)", "Bob"]).unwrap();
+        assert_eq!(instructions.document_id(), &main_document.id());
+        assert_eq!(instructions.title(), Some("Family photos"));
+        assert_eq!(instructions.quorum_size(), 2);
+        assert_eq!(
+            instructions.shard_holders(),
+            ["Alice (+61 400 000 000)", "Bob"]
+        );
+    }
+
+    #[test]
+    fn instructions_invalid_holder() {
+        let backup = Backup::new(2, b"secret").unwrap();
+        for holder in ["", "Alice\nBob", &"x".repeat(129)] {
+            assert!(matches!(
+                InstructionsDocument::new(backup.main_document(), [holder]),
+                Err(Error::InvalidShardHolder(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,126 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{DocumentId, Error, KeyShard, MainDocument, ShardId};
+
+use std::time::SystemTime;
+
+/// A record of the key shards created for a backup, with space to write down
+/// who each key shard was given to.
+///
+/// The manifest contains no secret information (only identifiers and the
+/// quorum policy), so it can be stored alongside the main document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    document_id: DocumentId,
+    document_checksum: String,
+    quorum_size: u32,
+    created: Option<SystemTime>,
+    expires: Option<SystemTime>,
+    shard_ids: Vec<ShardId>,
+}
+
+impl Manifest {
+    /// Construct a manifest listing the given key shards of `main_document`.
+    pub fn new<'a, S>(main_document: &MainDocument, shards: S) -> Result<Self, Error>
+    where
+        S: IntoIterator<Item = &'a KeyShard>,
+    {
+        let document_id = main_document.id();
+        let shard_ids = shards
+            .into_iter()
+            .map(|shard| match shard.document_id() == document_id {
+                true => Ok(shard.id()),
+                false => Err(Error::InvariantViolation(
+                    "manifest key shards must belong to the main document",
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            document_id,
+            document_checksum: main_document.checksum_string(),
+            quorum_size: main_document.quorum_size(),
+            created: main_document.created(),
+            expires: main_document.expires(),
+            shard_ids,
+        })
+    }
+
+    pub fn document_id(&self) -> &DocumentId {
+        &self.document_id
+    }
+
+    pub fn document_checksum(&self) -> &str {
+        &self.document_checksum
+    }
+
+    pub fn quorum_size(&self) -> u32 {
+        self.quorum_size
+    }
+
+    /// When the backup was created (`None` for backups created by older
+    /// versions of paperback).
+    pub fn created(&self) -> Option<SystemTime> {
+        self.created
+    }
+
+    /// When the backup expires, if an expiry date was set.
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
+    /// Identifiers of the key shards listed in the manifest.
+    pub fn shard_ids(&self) -> &[ShardId] {
+        &self.shard_ids
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::Backup;
+
+    #[test]
+    fn manifest_lists_shards() {
+        let backup = Backup::new(2, b"secret").unwrap();
+        let shards = (0..3)
+            .map(|_| backup.next_shard().unwrap())
+            .collect::<Vec<_>>();
+
+        let manifest = Manifest::new(backup.main_document(), &shards).unwrap();
+        assert_eq!(manifest.document_id(), &backup.main_document().id());
+        assert_eq!(manifest.quorum_size(), 2);
+        assert_eq!(
+            manifest.shard_ids(),
+            shards.iter().map(KeyShard::id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn manifest_foreign_shard() {
+        let backup = Backup::new(2, b"secret").unwrap();
+        let other_backup = Backup::new(2, b"secret").unwrap();
+        let shards = [
+            backup.next_shard().unwrap(),
+            other_backup.next_shard().unwrap(),
+        ];
+
+        assert!(Manifest::new(backup.main_document(), &shards).is_err());
+    }
+}
@@ -21,20 +21,35 @@ use crate::{
     v0::wire::prefixes::*,
 };
 
-use aead::{Aead, AeadCore};
-use bip39::{Language, Mnemonic};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aead::Aead;
+#[cfg(feature = "std")]
+use aead::AeadCore;
+#[cfg(feature = "std")]
+use bip39::Mnemonic;
 use chacha20poly1305::ChaCha20Poly1305;
 use crypto_common::KeyInit;
-use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
-use multihash_codetable::MultihashDigest;
+use ed25519_dalek::{Signature, VerifyingKey};
+#[cfg(feature = "std")]
+use ed25519_dalek::{Signer, SigningKey};
+#[cfg(feature = "std")]
+use rand::{CryptoRng, RngCore};
 use unsigned_varint::encode as varuint_encode;
+use zeroize::Zeroizing;
+#[cfg(feature = "std")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 // Use a 64-byte buffer by default.
 type Multihash = multihash::Multihash<64>;
 
-pub type ShardId = String;
-pub type DocumentId = String;
-
 const PAPERBACK_VERSION: u32 = 0;
 
 type ChaChaPolyKey = chacha20poly1305::Key;
@@ -43,6 +58,10 @@ const CHACHAPOLY_KEY_LENGTH: usize = 32;
 type ChaChaPolyNonce = chacha20poly1305::Nonce;
 const CHACHAPOLY_NONCE_LENGTH: usize = 12;
 
+#[cfg(feature = "std")]
+type ChaChaPolyTag = chacha20poly1305::Tag;
+const CHACHAPOLY_TAG_LENGTH: usize = 16;
+
 #[cfg(test)]
 #[test]
 fn check_length_consts() {
@@ -50,9 +69,9 @@ fn check_length_consts() {
     // in a test...
     assert_eq!(CHACHAPOLY_KEY_LENGTH, ChaChaPolyKey::default().len());
     assert_eq!(CHACHAPOLY_NONCE_LENGTH, ChaChaPolyNonce::default().len());
+    assert_eq!(CHACHAPOLY_TAG_LENGTH, ChaChaPolyTag::default().len());
 }
 
-const CHECKSUM_ALGORITHM: multihash_codetable::Code = multihash_codetable::Code::Blake2b256;
 const CHECKSUM_MULTIBASE: multibase::Base = multibase::Base::Base32Z;
 
 #[derive(Debug, thiserror::Error)]
@@ -75,15 +94,133 @@ pub enum Error {
     #[error("failed to decode shard secret: {0}")]
     ShardSecretDecode(String),
 
-    #[error("failed to decode shard id: {0}")]
-    ShardIdDecode(multibase::Error),
+    #[error("failed to decode backup session: {0}")]
+    SessionDecode(String),
+
+    #[error("wrong shard registry passphrase (or the registry is corrupted)")]
+    WrongRegistryPassphrase,
+
+    #[error("failed to decode shard registry entry: {0}")]
+    RegistryDecode(String),
+
+    #[error("invalid shard id: {0}")]
+    ShardIdDecode(String),
+
+    #[error("invalid document id: {0}")]
+    DocumentIdDecode(String),
 
     #[error("failed to decode private key: {0}")]
     PrivateKeyDecode(ed25519_dalek::SignatureError),
 
+    #[cfg(feature = "std")]
     #[error("bip39 phrase failure: {0}")]
     Bip39(bip39::ErrorKind),
 
+    #[cfg(feature = "std")]
+    #[error("invalid key shard codewords (check for typos or missing words): {0}")]
+    InvalidCodewords(bip39::ErrorKind),
+
+    #[cfg(feature = "std")]
+    #[error("unknown codeword {} {word:?}{}", .index + 1, codewords::format_suggestions(.suggestions))]
+    UnknownCodeword {
+        index: usize,
+        word: String,
+        suggestions: Vec<String>,
+    },
+
+    #[error("wrong codewords for this key shard (or the key shard data is corrupted -- check the key shard checksum)")]
+    WrongCodewords,
+
+    #[error("key shards can only be encrypted with {} codewords (or {} for hardened key shards), not {}", CHACHAPOLY_KEY_LENGTH * 3 / 4, HARDENED_CODEWORD_ENTROPY_LENGTH * 3 / 4, .0)]
+    WrongCodewordCount(usize),
+
+    #[error("key shard data is corrupted (check the key shard checksum): {0}")]
+    CorruptedCiphertext(&'static str),
+
+    #[error("decrypted key shard is malformed: {0}")]
+    MalformedKeyShard(String),
+
+    #[error("failed to decode multibase data: {0}")]
+    MultibaseDecode(multibase::Error),
+
+    #[error("failed to reconstruct text fallback: {0}")]
+    TextFallbackDecode(String),
+
+    #[error("failed to decode armored document: {0}")]
+    ArmorDecode(String),
+
+    #[error("armored document has the wrong {header} header (found {found:?}, the document data has {expected:?})")]
+    ArmorHeaderMismatch {
+        header: &'static str,
+        found: String,
+        expected: String,
+    },
+
+    #[error("argon2 key derivation failed: {0}")]
+    Argon2(argon2::Error),
+
+    #[error("quorum size {0} is too small (at least one key shard must be required to recover the backup)")]
+    QuorumTooSmall(u32),
+
+    #[error("quorum size {quorum_size} is too large (at most {max_quorum_size} key shards can be required to recover the backup)")]
+    QuorumTooLarge {
+        quorum_size: u32,
+        max_quorum_size: u32,
+    },
+
+    #[error("secret data is too large to print ({size} bytes, at most {max_secret_size} bytes fit in a main document with these options)")]
+    SecretTooLarge { size: usize, max_secret_size: usize },
+
+    #[error("number of key shards ({shards}) cannot be smaller than the quorum size ({quorum_size}) -- such a backup is unrecoverable")]
+    ShardsLessThanQuorum { shards: u32, quorum_size: u32 },
+
+    #[error("invalid payload name {0:?}: payload names must be unique and non-empty")]
+    InvalidPayloadName(String),
+
+    #[error("failed to decode named payloads: {0}")]
+    PayloadsDecode(String),
+
+    #[cfg(feature = "std")]
+    #[error("invalid seed phrase (check for typos or missing words): {0}")]
+    InvalidSeedPhrase(bip39::ErrorKind),
+
+    #[error("failed to decode seed phrase: {0}")]
+    SeedPhraseDecode(String),
+
+    #[error("no payload named {0:?} in main document")]
+    UnknownPayload(String),
+
+    #[error("main document contains multiple named payloads ({}) -- select one to recover", .0.join(", "))]
+    MultiplePayloads(Vec<String>),
+
+    #[error("failed to encrypt secret to recipient: {0}")]
+    RecipientEncrypt(String),
+
+    #[error("failed to decrypt secret with recipient identity: {0}")]
+    RecipientDecrypt(String),
+
+    #[error("main document is encrypted to a recipient -- the recipient's identity is required to recover it")]
+    MissingIdentity,
+
+    #[error("invalid backup title {:?}: titles must be non-empty, at most {} bytes long and cannot contain control characters", .0, MainDocument::MAX_TITLE_LENGTH)]
+    InvalidTitle(String),
+
+    #[error("invalid public information {}: labels and values must be non-empty, at most {} and {} bytes long and cannot contain control characters", .0, PublicInfo::MAX_LABEL_LENGTH, PublicInfo::MAX_VALUE_LENGTH)]
+    InvalidPublicInfo(String),
+
+    #[error("failed to derive public information from secret data: {0}")]
+    DerivePublicInfo(String),
+
+    #[error("invalid BIP-32 derivation path {0:?}")]
+    InvalidDerivationPath(String),
+
+    #[cfg(feature = "std")]
+    #[error("invalid key shard holder {:?}: holders must be non-empty, at most {} bytes long and cannot contain control characters", .0, InstructionsDocument::MAX_HOLDER_LENGTH)]
+    InvalidShardHolder(String),
+
+    #[error("unsupported paperback version {found} (only version {supported} is supported)")]
+    UnsupportedVersion { found: u32, supported: u32 },
+
     #[error("other error: {0}")]
     Other(String),
 }
@@ -119,17 +256,140 @@ where
     slice.as_mut().fill_with(|| T::arbitrary(g))
 }
 
+/// Seconds since the Unix epoch.
+type UnixTimestamp = u64;
+
+#[cfg(feature = "std")]
+fn to_unix_timestamp(time: SystemTime) -> UnixTimestamp {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(feature = "std")]
+fn from_unix_timestamp(timestamp: UnixTimestamp) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(Duration::from_secs(timestamp))
+}
+
+/// Format a time as an ISO 8601 date (in UTC), such as "2022-01-31".
+#[cfg(feature = "std")]
+pub fn format_date(time: SystemTime) -> String {
+    // Algorithm from <https://howardhinnant.github.io/date_algorithms.html>.
+    let days = (to_unix_timestamp(time) / 86400) as i64 + 719468;
+    let (era, doe) = (days.div_euclid(146097), days.rem_euclid(146097));
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Creation and expiry times of a document. These are optional because
+/// documents created by older versions of paperback do not contain them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Timestamps {
+    created: Option<UnixTimestamp>,
+    expires: Option<UnixTimestamp>,
+}
+
+#[cfg(feature = "std")]
+impl Timestamps {
+    fn new(created: SystemTime, expires: Option<UnixTimestamp>) -> Self {
+        Self {
+            created: Some(to_unix_timestamp(created)),
+            expires,
+        }
+    }
+
+    fn created(&self) -> Option<SystemTime> {
+        self.created.and_then(from_unix_timestamp)
+    }
+
+    fn expires(&self) -> Option<SystemTime> {
+        self.expires.and_then(from_unix_timestamp)
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires
+            .is_some_and(|expires| to_unix_timestamp(SystemTime::now()) >= expires)
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for Timestamps {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        Self {
+            created: Option::<UnixTimestamp>::arbitrary(g),
+            expires: Option::<UnixTimestamp>::arbitrary(g),
+        }
+    }
+}
+
+/// Which kinds of key shards (see `NewShardKind`) a quorum is permitted to
+/// create for a backup. This is fixed when the backup is created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShardPolicy {
+    /// No key shards can be created once the `Backup` is dropped (a sealed
+    /// backup).
+    Sealed,
+    /// Only new key shards can be created. Existing key shards can never be
+    /// re-created, so a key shard which has been reported lost cannot be
+    /// quietly re-issued.
+    NewOnly,
+    /// Both new and re-created key shards can be created.
+    #[default]
+    Any,
+}
+
+#[cfg(feature = "std")]
 #[derive(Debug)]
 struct ShardSecret {
     doc_key: ChaChaPolyKey,
     id_keypair: Option<ed25519_dalek::SigningKey>,
+    // Only meaningful if id_keypair is present.
+    allow_recreate: bool,
+}
+
+#[cfg(feature = "std")]
+impl ShardSecret {
+    fn shard_policy(&self) -> ShardPolicy {
+        match (&self.id_keypair, self.allow_recreate) {
+            (None, _) => ShardPolicy::Sealed,
+            (Some(_), false) => ShardPolicy::NewOnly,
+            (Some(_), true) => ShardPolicy::Any,
+        }
+    }
+}
+
+/// Parse a `ShardSecret`, which is otherwise only ever parsed internally. This
+/// is only used to fuzz the parser.
+#[cfg(all(feature = "std", feature = "donotuse_expose_internal_modules"))]
+#[doc(hidden)]
+pub fn fuzz_shard_secret_from_wire(input: &[u8]) -> Result<(), String> {
+    ShardSecret::from_wire(input).map(|_| ())
 }
 
+// SigningKey already wipes itself when dropped.
+#[cfg(feature = "std")]
+impl Drop for ShardSecret {
+    fn drop(&mut self) {
+        self.doc_key.zeroize();
+    }
+}
+
+#[cfg(feature = "std")]
+impl ZeroizeOnDrop for ShardSecret {}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct KeyShardBuilder {
     version: u32, // must be 0 for this version
     doc_chksum: Multihash,
     shard: Shard,
+    timestamps: Timestamps,
+    // Only present for key shards committed to by the main document.
+    commitment_proof: Option<ShardCommitmentProof>,
 }
 
 impl KeyShardBuilder {
@@ -144,6 +404,7 @@ impl KeyShardBuilder {
         bytes
     }
 
+    #[cfg(feature = "std")]
     fn sign(self, id_keypair: &SigningKey) -> KeyShard {
         let bytes = self.signable_bytes(&id_keypair.verifying_key());
         KeyShard {
@@ -162,14 +423,219 @@ impl quickcheck::Arbitrary for KeyShardBuilder {
         let bytes = Vec::<u8>::arbitrary(g);
         Self {
             version: PAPERBACK_VERSION,
-            doc_chksum: CHECKSUM_ALGORITHM.digest(&bytes[..]),
+            doc_chksum: ChecksumAlgorithm::arbitrary(g).digest(&bytes[..]),
             shard: Shard::arbitrary(g),
+            timestamps: Timestamps::arbitrary(g),
+            commitment_proof: Option::<ShardCommitmentProof>::arbitrary(g),
+        }
+    }
+}
+
+/// An age X25519 recipient, which the secret can additionally be encrypted to
+/// (see `Backup::new_with_recipient`).
+#[cfg(feature = "std")]
+pub use age::x25519::Recipient as AgeRecipient;
+
+/// The identity (private key) corresponding to an `AgeRecipient`.
+#[cfg(feature = "std")]
+pub use age::x25519::Identity as AgeIdentity;
+
+/// Language of the BIP-39 wordlist used for codewords.
+#[cfg(feature = "std")]
+pub use bip39::Language as CodewordLanguage;
+
+/// Language used for codewords if none is specified.
+#[cfg(feature = "std")]
+pub const DEFAULT_CODEWORD_LANGUAGE: CodewordLanguage = CodewordLanguage::English;
+
+/// All supported codeword languages, in the order they are tried when
+/// auto-detecting the language of a set of codewords.
+// NOTE: The index of each language is used in the wire format of SeedPhrase,
+//       so new languages can only be appended.
+#[cfg(feature = "std")]
+pub const CODEWORD_LANGUAGES: &[CodewordLanguage] = &[
+    CodewordLanguage::English,
+    CodewordLanguage::Spanish,
+    CodewordLanguage::French,
+    CodewordLanguage::Italian,
+    CodewordLanguage::Japanese,
+    CodewordLanguage::Korean,
+    CodewordLanguage::ChineseSimplified,
+    CodewordLanguage::ChineseTraditional,
+];
+
+/// Codewords used to decrypt a key shard. They are wiped from memory when
+/// dropped.
+#[cfg(feature = "std")]
+pub type KeyShardCodewords = Zeroizing<Vec<String>>;
+
+#[cfg(feature = "std")]
+fn key_to_codewords(
+    entropy: &[u8],
+    language: CodewordLanguage,
+) -> Result<KeyShardCodewords, Error> {
+    // Mnemonic wipes its copy of the phrase when dropped.
+    Ok(Mnemonic::from_entropy(entropy, language)
+        .map_err(Error::Bip39)?
+        .phrase()
+        .split_whitespace()
+        .map(|s| s.to_owned())
+        .collect::<Vec<_>>()
+        .into())
+}
+
+#[cfg(feature = "std")]
+fn codewords_to_entropy(
+    codewords: &[String],
+    language: CodewordLanguage,
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let phrase = Zeroizing::new(codewords.join(" "));
+    let phrase = Zeroizing::new(phrase.to_lowercase());
+    let mnemonic = Mnemonic::from_phrase(&phrase, language).map_err(Error::InvalidCodewords)?;
+
+    Ok(Zeroizing::new(mnemonic.entropy().to_vec()))
+}
+
+/// Use the entropy of the codewords directly as a key. This requires the full
+/// 24 codewords (256 bits).
+fn entropy_to_key(entropy: &[u8]) -> Result<Zeroizing<ChaChaPolyKey>, Error> {
+    if entropy.len() != CHACHAPOLY_KEY_LENGTH {
+        return Err(Error::WrongCodewords);
+    }
+    let mut key = Zeroizing::new(ChaChaPolyKey::default());
+    key.copy_from_slice(entropy);
+    Ok(key)
+}
+
+/// The entropy represented by a set of codewords in one language.
+#[cfg(feature = "std")]
+type CandidateEntropy = (CodewordLanguage, Zeroizing<Vec<u8>>);
+
+/// Returns the entropy the codewords could represent, for each language the
+/// codewords are a valid BIP-39 phrase in. Some wordlists share words, so it is
+/// possible (though unlikely) for a phrase to be valid in several languages.
+#[cfg(feature = "std")]
+fn codewords_to_candidate_entropy(codewords: &[String]) -> Result<Vec<CandidateEntropy>, Error> {
+    let mut last_err = None;
+    let candidates = CODEWORD_LANGUAGES
+        .iter()
+        .filter_map(
+            |&language| match codewords_to_entropy(codewords, language) {
+                Ok(entropy) => Some((language, entropy)),
+                Err(err) => {
+                    last_err = Some(err);
+                    None
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+    match (candidates.is_empty(), last_err) {
+        (true, Some(err)) => Err(err),
+        _ => Ok(candidates),
+    }
+}
+
+/// Like `codewords_to_candidate_entropy`, but returns every key the codewords
+/// could represent (when used directly as a key).
+#[cfg(feature = "std")]
+fn codewords_to_candidate_keys(
+    codewords: &[String],
+) -> Result<Vec<(CodewordLanguage, Zeroizing<ChaChaPolyKey>)>, Error> {
+    codewords_to_candidate_entropy(codewords)?
+        .into_iter()
+        .map(|(language, entropy)| Ok((language, entropy_to_key(&entropy)?)))
+        .collect()
+}
+
+/// Number of codewords (in bytes of entropy) used for hardened key shards.
+const HARDENED_CODEWORD_ENTROPY_LENGTH: usize = 16; // 12 codewords
+
+const ARGON2_SALT_LENGTH: usize = 16;
+
+// Based on the second recommended option in RFC 9106.
+#[cfg(feature = "std")]
+const ARGON2_DEFAULT_MEMORY_KIB: u32 = 64 * 1024;
+#[cfg(feature = "std")]
+const ARGON2_DEFAULT_ITERATIONS: u32 = 3;
+#[cfg(feature = "std")]
+const ARGON2_DEFAULT_PARALLELISM: u32 = 1;
+
+// Limits on the (untrusted) parameters we will accept when decrypting, to
+// avoid being made to allocate absurd amounts of memory.
+const ARGON2_MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const ARGON2_MAX_ITERATIONS: u32 = 64;
+const ARGON2_MAX_PARALLELISM: u32 = 16;
+
+/// Argon2id parameters used to stretch the codewords of a hardened key shard
+/// into the key shard key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Argon2Params {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: [u8; ARGON2_SALT_LENGTH],
+}
+
+impl Argon2Params {
+    #[cfg(feature = "std")]
+    fn new_rand<R: CryptoRng + RngCore + ?Sized>(rng: &mut R) -> Self {
+        let mut salt = [0; ARGON2_SALT_LENGTH];
+        rng.fill_bytes(&mut salt);
+        Self {
+            memory_kib: ARGON2_DEFAULT_MEMORY_KIB,
+            iterations: ARGON2_DEFAULT_ITERATIONS,
+            parallelism: ARGON2_DEFAULT_PARALLELISM,
+            salt,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.memory_kib > ARGON2_MAX_MEMORY_KIB
+            || self.iterations > ARGON2_MAX_ITERATIONS
+            || self.parallelism > ARGON2_MAX_PARALLELISM
+        {
+            return Err(format!("argon2 parameters too large: {:?}", self));
         }
+        self.params().map(|_| ()).map_err(|err| err.to_string())
+    }
+
+    fn params(&self) -> Result<argon2::Params, argon2::Error> {
+        argon2::Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(CHACHAPOLY_KEY_LENGTH),
+        )
+    }
+
+    fn derive_key(&self, entropy: &[u8]) -> Result<Zeroizing<ChaChaPolyKey>, Error> {
+        let argon2 = argon2::Argon2::new(
+            argon2::Algorithm::Argon2id,
+            argon2::Version::V0x13,
+            self.params().map_err(Error::Argon2)?,
+        );
+        let mut key = Zeroizing::new(ChaChaPolyKey::default());
+        argon2
+            .hash_password_into(entropy, &self.salt, &mut key)
+            .map_err(Error::Argon2)?;
+        Ok(key)
     }
 }
 
-const CODEWORD_LANGUAGE: Language = Language::English;
-pub type KeyShardCodewords = Vec<String>;
+#[cfg(test)]
+impl quickcheck::Arbitrary for Argon2Params {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let mut salt = [0; ARGON2_SALT_LENGTH];
+        arbitrary_fill_slice(g, &mut salt);
+        // Keep the parameters small (and valid) so tests stay fast.
+        Self {
+            memory_kib: 16 + u32::arbitrary(g) % 1024,
+            iterations: 1 + u32::arbitrary(g) % 4,
+            parallelism: 1 + u32::arbitrary(g) % 2,
+            salt,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
@@ -193,25 +659,204 @@ impl KeyShard {
         self.inner.shard.id()
     }
 
+    /// Human-friendly form of `KeyShard::id`, made of three English BIP-39
+    /// words. Either form can be used to re-create the key shard.
+    #[cfg(feature = "std")]
+    pub fn friendly_id(&self) -> String {
+        self.inner.shard.friendly_id()
+    }
+
     fn document_checksum(&self) -> Multihash {
         self.inner.doc_chksum
     }
 
     pub fn document_id(&self) -> DocumentId {
-        multihash_short_id(self.document_checksum(), MainDocument::ID_LENGTH)
+        DocumentId::from_checksum(self.document_checksum())
+    }
+
+    /// The checksum of the main document this shard belongs to, in the same
+    /// form as `MainDocument::checksum_string` (which is printed on the main
+    /// document). Shard holders can compare the two to check that their key
+    /// shard belongs to the backup, without needing a quorum.
+    pub fn document_checksum_string(&self) -> String {
+        multibase::encode(CHECKSUM_MULTIBASE, self.document_checksum().to_bytes())
     }
 
     pub fn quorum_size(&self) -> u32 {
         self.inner.shard.threshold()
     }
 
+    pub fn version(&self) -> u32 {
+        self.inner.version
+    }
+
+    /// Whether the shard's signature is valid for the identity key it carries.
+    ///
+    /// This only shows that the shard has not been modified since it was
+    /// signed -- checking that the identity key matches the rest of the quorum
+    /// is done by `UntrustedQuorum::validate`.
+    pub fn has_valid_signature(&self) -> bool {
+        let id_public_key = self.identity.id_public_key;
+        id_public_key
+            .verify_strict(
+                &self.inner.signable_bytes(&id_public_key),
+                &self.identity.id_signature,
+            )
+            .is_ok()
+    }
+
+    /// When the backup this shard belongs to was created (`None` for shards
+    /// created by older versions of paperback).
+    ///
+    /// Shards carry the timestamps of their backup rather than when the shard
+    /// itself was created, so that re-created shards are identical to the
+    /// originals.
+    #[cfg(feature = "std")]
+    pub fn created(&self) -> Option<SystemTime> {
+        self.inner.timestamps.created()
+    }
+
+    /// When the backup this shard belongs to expires, if an expiry was set.
+    #[cfg(feature = "std")]
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.inner.timestamps.expires()
+    }
+
+    /// Whether the shard's expiry date has passed.
+    #[cfg(feature = "std")]
+    pub fn is_expired(&self) -> bool {
+        self.inner.timestamps.is_expired()
+    }
+
+    /// Whether the shard claims to be one of the key shards committed to by
+    /// its main document (see `BackupBuilder::commit_shards`). Use
+    /// `MainDocument::is_committed_shard` to actually check the claim.
+    pub fn has_commitment_proof(&self) -> bool {
+        self.inner.commitment_proof.is_some()
+    }
+
+    /// Secret checksum of the key shard, used to verify the key shard holder's
+    /// responses to a `ShardChallenge`.
+    #[cfg(feature = "std")]
+    pub fn challenge_checksum(&self) -> ChallengeChecksum {
+        ChallengeChecksum::new(self)
+    }
+
+    #[cfg(feature = "std")]
     pub fn encrypt(&self) -> Result<(EncryptedKeyShard, KeyShardCodewords), Error> {
+        self.encrypt_with_language(DEFAULT_CODEWORD_LANGUAGE)
+    }
+
+    /// Encrypt the shard, with the codewords taken from the BIP-39 wordlist for
+    /// the given `language`.
+    #[cfg(feature = "std")]
+    pub fn encrypt_with_language(
+        &self,
+        language: CodewordLanguage,
+    ) -> Result<(EncryptedKeyShard, KeyShardCodewords), Error> {
+        self.encrypt_with_rng(language, &mut rand::thread_rng())
+    }
+
+    /// Like `KeyShard::encrypt_with_language`, but the key and nonce are
+    /// generated using `rng`.
+    #[cfg(feature = "std")]
+    pub fn encrypt_with_rng<R: CryptoRng + RngCore + ?Sized>(
+        &self,
+        language: CodewordLanguage,
+        rng: &mut R,
+    ) -> Result<(EncryptedKeyShard, KeyShardCodewords), Error> {
+        self.encrypt_inner(language, None, rng)
+    }
+
+    /// Like `KeyShard::encrypt_with_language`, but with only 12 codewords
+    /// (rather than 24). The key is derived from the codewords using Argon2id,
+    /// which makes up for the shorter codewords by making each guess much
+    /// more expensive (at the cost of decryption taking a few seconds).
+    #[cfg(feature = "std")]
+    pub fn encrypt_hardened(
+        &self,
+        language: CodewordLanguage,
+    ) -> Result<(EncryptedKeyShard, KeyShardCodewords), Error> {
+        let mut rng = rand::thread_rng();
+        let kdf = Argon2Params::new_rand(&mut rng);
+        self.encrypt_inner(language, Some(kdf), &mut rng)
+    }
+
+    /// Encrypt the shard using the given (existing) `codewords`, rather than
+    /// generating new ones. 24 codewords are used directly as the key, while 12
+    /// codewords result in a hardened key shard (see
+    /// `KeyShard::encrypt_hardened`).
+    ///
+    /// **WARNING**: The security of paperback relies on each key shard having
+    /// its own random codewords. Reusing the same codewords for several key
+    /// shards means that anyone who learns them (for instance, by watching one
+    /// shard holder type them in) can decrypt *all* of those key shards, and
+    /// codewords that were not randomly generated may be guessable. Only use
+    /// this if you understand the risks (such as when the owner of a backup
+    /// wants to memorise a single set of codewords for all of their key
+    /// shards).
+    #[cfg(feature = "std")]
+    pub fn encrypt_with_codewords<A: AsRef<[String]>>(
+        &self,
+        codewords: A,
+    ) -> Result<EncryptedKeyShard, Error> {
+        // The phrase could (in principle) be valid in several languages, but
+        // EncryptedKeyShard::decrypt tries them all so we can use any of them.
+        let (_, entropy) = codewords_to_candidate_entropy(codewords.as_ref())?
+            .into_iter()
+            .next()
+            .ok_or(Error::WrongCodewordCount(codewords.as_ref().len()))?;
+
+        let mut rng = rand::thread_rng();
+        let kdf = match entropy.len() {
+            CHACHAPOLY_KEY_LENGTH => None,
+            HARDENED_CODEWORD_ENTROPY_LENGTH => Some(Argon2Params::new_rand(&mut rng)),
+            _ => return Err(Error::WrongCodewordCount(codewords.as_ref().len())),
+        };
+        self.encrypt_with_entropy(&entropy, kdf, &mut rng)
+    }
+
+    #[cfg(feature = "std")]
+    fn encrypt_inner<R: CryptoRng + RngCore + ?Sized>(
+        &self,
+        language: CodewordLanguage,
+        kdf: Option<Argon2Params>,
+        rng: &mut R,
+    ) -> Result<(EncryptedKeyShard, KeyShardCodewords), Error> {
+        // Generate the codeword entropy.
+        let entropy_len = match kdf {
+            None => CHACHAPOLY_KEY_LENGTH,
+            Some(_) => HARDENED_CODEWORD_ENTROPY_LENGTH,
+        };
+        let mut entropy = Zeroizing::new(vec![0; entropy_len]);
+        rng.fill_bytes(&mut entropy);
+
+        let shard = self.encrypt_with_entropy(&entropy, kdf, rng)?;
+
+        // Convert entropy to a BIP-39 mnemonic.
+        let codewords = key_to_codewords(&entropy, language)?;
+
+        Ok((shard, codewords))
+    }
+
+    #[cfg(feature = "std")]
+    fn encrypt_with_entropy<R: CryptoRng + RngCore + ?Sized>(
+        &self,
+        entropy: &[u8],
+        kdf: Option<Argon2Params>,
+        rng: &mut R,
+    ) -> Result<EncryptedKeyShard, Error> {
         // Serialise.
-        let wire_shard = self.to_wire();
+        let wire_shard = Zeroizing::new(self.to_wire());
+
+        // Derive the key from the codeword entropy.
+        let shard_key = match kdf {
+            None => entropy_to_key(entropy)?,
+            Some(kdf) => kdf.derive_key(entropy)?,
+        };
 
-        // Generate key and nonce.
-        let shard_key = ChaCha20Poly1305::generate_key(&mut rand::thread_rng());
-        let shard_nonce = ChaCha20Poly1305::generate_nonce(&mut rand::thread_rng());
+        // Generate nonce.
+        let shard_nonce = ChaCha20Poly1305::generate_nonce(&mut *rng);
 
         // Encrypt the contents.
         let aead = ChaCha20Poly1305::new(&shard_key);
@@ -219,22 +864,12 @@ impl KeyShard {
             .encrypt(&shard_nonce, wire_shard.as_slice())
             .map_err(Error::AeadEncryption)?;
 
-        // Convert key to a BIP-39 mnemonic.
-        let phrase = Mnemonic::from_entropy(&shard_key, CODEWORD_LANGUAGE)
-            .map_err(Error::Bip39)?
-            .into_phrase();
-        let codewords = phrase
-            .split_whitespace()
-            .map(|s| s.to_owned())
-            .collect::<Vec<_>>();
-
         // Create wrapper shard.
-        let shard = EncryptedKeyShard {
+        Ok(EncryptedKeyShard {
             nonce: shard_nonce,
             ciphertext: wire_shard,
-        };
-
-        Ok((shard, codewords))
+            kdf,
+        })
     }
 }
 
@@ -243,34 +878,108 @@ impl KeyShard {
 pub struct EncryptedKeyShard {
     nonce: ChaChaPolyNonce,
     ciphertext: Vec<u8>,
+    kdf: Option<Argon2Params>,
 }
 
 impl EncryptedKeyShard {
     pub fn checksum(&self) -> Multihash {
-        CHECKSUM_ALGORITHM.digest(&self.to_wire())
+        ChecksumAlgorithm::default().digest(&self.to_wire())
     }
 
     pub fn checksum_string(&self) -> String {
         multibase::encode(CHECKSUM_MULTIBASE, self.checksum().to_bytes())
     }
 
-    pub fn decrypt<A: AsRef<[String]>>(&self, codewords: A) -> Result<KeyShard, String> {
-        // Convert BIP-39 mnemonic to a key.
-        let phrase = codewords.as_ref().join(" ").to_lowercase();
-        let mnemonic =
-            Mnemonic::from_phrase(&phrase, CODEWORD_LANGUAGE).map_err(|e| format!("{:?}", e))?; // XXX: Ugly, fix this.
+    /// A short fingerprint of the checksum as BIP-39 words, which is easier
+    /// to compare verbally than `checksum_string`.
+    #[cfg(feature = "std")]
+    pub fn fingerprint_words(&self) -> Vec<&'static str> {
+        codewords::fingerprint(&self.checksum())
+    }
 
-        let mut shard_key = ChaChaPolyKey::default();
-        shard_key.copy_from_slice(mnemonic.entropy());
+    /// Whether the shard uses hardened (shorter, Argon2id-stretched)
+    /// codewords, see `KeyShard::encrypt_hardened`.
+    pub fn is_hardened(&self) -> bool {
+        self.kdf.is_some()
+    }
 
-        // Decrypt the contents.
-        let aead = ChaCha20Poly1305::new(&shard_key);
-        let wire_shard = aead
-            .decrypt(&self.nonce, self.ciphertext.as_slice())
-            .map_err(|err| format!("{:?}", err))?; // XXX: Ugly, fix this.
+    /// Decrypt the shard, auto-detecting the language of the codewords.
+    #[cfg(feature = "std")]
+    pub fn decrypt<A: AsRef<[String]>>(&self, codewords: A) -> Result<KeyShard, Error> {
+        // Convert BIP-39 mnemonic to a key. We try every language the phrase is
+        // valid in, since some wordlists share words.
+        let candidates = codewords_to_candidate_entropy(codewords.as_ref())?;
+
+        for (_, entropy) in candidates {
+            match self
+                .entropy_to_key(&entropy)
+                .and_then(|shard_key| self.decrypt_with_key(&shard_key))
+            {
+                // The AEAD tag only matches for the right key, so any other
+                // error is final.
+                Err(Error::WrongCodewords) => continue,
+                result => return result,
+            }
+        }
+        Err(Error::WrongCodewords)
+    }
+
+    /// Decrypt the shard, with codewords from the given `language`'s BIP-39
+    /// wordlist.
+    #[cfg(feature = "std")]
+    pub fn decrypt_with_language<A: AsRef<[String]>>(
+        &self,
+        codewords: A,
+        language: CodewordLanguage,
+    ) -> Result<KeyShard, Error> {
+        let entropy = codewords_to_entropy(codewords.as_ref(), language)?;
+        self.decrypt_with_entropy(&entropy)
+    }
+
+    /// Decrypt the shard using the entropy encoded by its codewords (the
+    /// BIP-39 entropy, without the checksum bits). This allows key shards to
+    /// be decrypted by callers which do not have the BIP-39 wordlists, such as
+    /// users of this crate without the `std` feature.
+    pub fn decrypt_with_entropy(&self, entropy: &[u8]) -> Result<KeyShard, Error> {
+        self.decrypt_with_key(&*self.entropy_to_key(entropy)?)
+    }
+
+    fn entropy_to_key(&self, entropy: &[u8]) -> Result<Zeroizing<ChaChaPolyKey>, Error> {
+        match self.kdf {
+            None => entropy_to_key(entropy),
+            Some(kdf) => kdf.derive_key(entropy),
+        }
+    }
+
+    fn decrypt_with_key(&self, shard_key: &ChaChaPolyKey) -> Result<KeyShard, Error> {
+        // A ciphertext without room for the tag can never be decrypted, no
+        // matter which codewords were used.
+        if self.ciphertext.len() < CHACHAPOLY_TAG_LENGTH {
+            return Err(Error::CorruptedCiphertext("ciphertext too short"));
+        }
+
+        // Decrypt the contents. The AEAD cannot tell us whether the key or the
+        // ciphertext was wrong, but the user can check the latter using the
+        // key shard checksum.
+        let aead = ChaCha20Poly1305::new(shard_key);
+        let wire_shard = Zeroizing::new(
+            aead.decrypt(&self.nonce, self.ciphertext.as_slice())
+                .map_err(|_| Error::WrongCodewords)?,
+        );
+
+        // Check the version before deserialising, so that key shards from
+        // other paperback versions are reported as such.
+        if let Ok((found, _)) = unsigned_varint::decode::u32(&wire_shard) {
+            if found != PAPERBACK_VERSION {
+                return Err(Error::UnsupportedVersion {
+                    found,
+                    supported: PAPERBACK_VERSION,
+                });
+            }
+        }
 
         // Deserialise.
-        KeyShard::from_wire(wire_shard)
+        KeyShard::from_wire(wire_shard).map_err(Error::MalformedKeyShard)
     }
 }
 
@@ -280,7 +989,51 @@ impl quickcheck::Arbitrary for EncryptedKeyShard {
         let mut nonce = ChaChaPolyNonce::default();
         arbitrary_fill_slice(g, &mut nonce);
         let ciphertext = Vec::<u8>::arbitrary(g);
-        Self { nonce, ciphertext }
+        let kdf = Option::<Argon2Params>::arbitrary(g);
+        Self {
+            nonce,
+            ciphertext,
+            kdf,
+        }
+    }
+}
+
+/// What the plaintext of a main document contains.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum PlaintextKind {
+    /// A single unnamed secret.
+    #[default]
+    Secret,
+    /// A set of named payloads (see `NamedPayloads`).
+    NamedPayloads,
+    /// A BIP-39 seed phrase (see `SeedPhrase`).
+    SeedPhrase,
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for PlaintextKind {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        *g.choose(&[Self::Secret, Self::NamedPayloads, Self::SeedPhrase])
+            .unwrap()
+    }
+}
+
+/// How the associated data of a main document is encoded (see
+/// `MainDocumentMeta::aad`). The version is recorded in the metadata, so
+/// exactly one encoding is ever accepted for a given document.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum AadVersion {
+    /// The metadata and public key concatenated together, used by documents
+    /// created before the encoding was versioned.
+    Legacy,
+    /// The versioned, length-prefixed encoding used by new documents.
+    V1,
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for AadVersion {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        *g.choose(&[Self::Legacy, Self::V1]).unwrap()
     }
 }
 
@@ -288,14 +1041,65 @@ impl quickcheck::Arbitrary for EncryptedKeyShard {
 struct MainDocumentMeta {
     version: u32, // must be 0 for this version
     quorum_size: u32,
+    timestamps: Timestamps,
+    plaintext_kind: PlaintextKind,
+    recipient_wrapped: bool,
+    checksum_algorithm: ChecksumAlgorithm,
+    title: Option<String>,
+    shard_commitment: Option<ShardCommitment>,
+    public_info: Vec<PublicInfo>,
+    aad_version: AadVersion,
 }
 
 impl MainDocumentMeta {
+    /// The associated data used when encrypting the contents of a main
+    /// document, which binds the ciphertext to the document's metadata and
+    /// public key (see `MainDocument::aad`). The encoding is picked using
+    /// `aad_version`.
     fn aad(&self, id_public_key: &VerifyingKey) -> Vec<u8> {
+        match self.aad_version {
+            AadVersion::Legacy => self.legacy_aad(id_public_key),
+            AadVersion::V1 => self.v1_aad(id_public_key),
+        }
+    }
+
+    /// The encoding starts with a prefix identifying its version, and the
+    /// metadata and public key are each length-prefixed so that no two
+    /// (metadata, public key) pairs have the same encoding.
+    fn v1_aad(&self, id_public_key: &VerifyingKey) -> Vec<u8> {
+        let meta = self.to_wire();
+        let public_key = id_public_key.as_bytes();
+        let mut bytes = vec![];
+
+        varuint_encode::u64(PREFIX_DOCUMENT_AAD_V1, &mut varuint_encode::u64_buffer())
+            .iter()
+            .chain(varuint_encode::usize(
+                meta.len(),
+                &mut varuint_encode::usize_buffer(),
+            ))
+            .chain(&meta)
+            .chain(varuint_encode::u32(
+                PREFIX_ED25519_PUB,
+                &mut varuint_encode::u32_buffer(),
+            ))
+            .chain(varuint_encode::usize(
+                public_key.len(),
+                &mut varuint_encode::usize_buffer(),
+            ))
+            .chain(public_key)
+            .for_each(|b| bytes.push(*b));
+
+        bytes
+    }
+
+    /// The associated data used by documents created before the encoding was
+    /// versioned. Appending the public key directly to the metadata is
+    /// ambiguous, so this is only used for documents which do not record an
+    /// `AadVersion`.
+    fn legacy_aad(&self, id_public_key: &VerifyingKey) -> Vec<u8> {
         let mut bytes = self.to_wire();
 
         // Append the public key used for signing.
-        // XXX: Make this much nicer...
         bytes.push(b'k');
         id_public_key.as_bytes().iter().for_each(|b| bytes.push(*b));
 
@@ -309,6 +1113,18 @@ impl quickcheck::Arbitrary for MainDocumentMeta {
         Self {
             version: PAPERBACK_VERSION,
             quorum_size: u32::arbitrary(g),
+            timestamps: Timestamps::arbitrary(g),
+            plaintext_kind: PlaintextKind::arbitrary(g),
+            recipient_wrapped: bool::arbitrary(g),
+            checksum_algorithm: ChecksumAlgorithm::arbitrary(g),
+            title: Option::<String>::arbitrary(g)
+                .filter(|title| MainDocument::check_title(title).is_ok()),
+            shard_commitment: Option::<ShardCommitment>::arbitrary(g),
+            public_info: Vec::<PublicInfo>::arbitrary(g)
+                .into_iter()
+                .take(PublicInfo::MAX_ENTRIES)
+                .collect(),
+            aad_version: AadVersion::arbitrary(g),
         }
     }
 }
@@ -332,6 +1148,7 @@ impl MainDocumentBuilder {
         bytes
     }
 
+    #[cfg(feature = "std")]
     fn sign(self, id_keypair: &SigningKey) -> MainDocument {
         let bytes = self.signable_bytes(&id_keypair.verifying_key());
         MainDocument {
@@ -364,28 +1181,43 @@ pub struct MainDocument {
     identity: Identity,
 }
 
-fn multihash_short_id(hash: Multihash, length: usize) -> String {
-    let doc_chksum = hash.to_bytes();
-    let encoded_chksum = multibase::encode(multibase::Base::Base32Z, doc_chksum);
-    // The *suffix* is the ID.
-    let short_id = &encoded_chksum[encoded_chksum.len() - length..];
+impl MainDocument {
+    pub const ID_LENGTH: usize = DocumentId::LENGTH;
 
-    short_id.to_string()
-}
+    /// The maximum length (in bytes) of a backup title.
+    pub const MAX_TITLE_LENGTH: usize = 128;
 
-impl MainDocument {
-    pub const ID_LENGTH: usize = 8;
+    pub(super) fn check_title(title: &str) -> Result<(), Error> {
+        if title.is_empty()
+            || title.len() > Self::MAX_TITLE_LENGTH
+            || title.chars().any(char::is_control)
+        {
+            return Err(Error::InvalidTitle(title.to_string()));
+        }
+        Ok(())
+    }
 
     pub fn checksum(&self) -> Multihash {
-        CHECKSUM_ALGORITHM.digest(&self.to_wire())
+        self.checksum_algorithm().digest(&self.to_wire())
+    }
+
+    pub fn checksum_algorithm(&self) -> ChecksumAlgorithm {
+        self.inner.meta.checksum_algorithm
     }
 
     pub fn checksum_string(&self) -> String {
         multibase::encode(CHECKSUM_MULTIBASE, self.checksum().to_bytes())
     }
 
+    /// A short fingerprint of the checksum as BIP-39 words, which is easier
+    /// to compare verbally than `checksum_string`.
+    #[cfg(feature = "std")]
+    pub fn fingerprint_words(&self) -> Vec<&'static str> {
+        codewords::fingerprint(&self.checksum())
+    }
+
     pub fn id(&self) -> DocumentId {
-        multihash_short_id(self.checksum(), Self::ID_LENGTH)
+        DocumentId::from_checksum(self.checksum())
     }
 
     pub fn quorum_size(&self) -> u32 {
@@ -395,6 +1227,146 @@ impl MainDocument {
     pub fn version(&self) -> u32 {
         self.inner.meta.version
     }
+
+    /// Whether the document's signature is valid for the identity key it
+    /// carries.
+    ///
+    /// This only shows that the document has not been modified since it was
+    /// signed -- checking that the identity key matches the key shards is done
+    /// by `UntrustedQuorum::validate`.
+    pub fn has_valid_signature(&self) -> bool {
+        let id_public_key = self.identity.id_public_key;
+        id_public_key
+            .verify_strict(
+                &self.inner.signable_bytes(&id_public_key),
+                &self.identity.id_signature,
+            )
+            .is_ok()
+    }
+
+    /// The associated data which the encrypted contents of the document are
+    /// bound to (in addition to being signed), derived from the document's
+    /// metadata and public key. Modifying any of the metadata (such as the
+    /// quorum size) makes the contents impossible to decrypt.
+    ///
+    /// Documents created by older versions of paperback use a different
+    /// encoding, which is recorded in the document's metadata so that only
+    /// the encoding the document was created with is accepted.
+    pub fn aad(&self) -> Vec<u8> {
+        self.inner.meta.aad(&self.identity.id_public_key)
+    }
+
+    /// The human-readable title given to the backup when it was created (see
+    /// `BackupBuilder::title`), if any.
+    ///
+    /// The title is authenticated along with the rest of the document, so it
+    /// cannot be modified without invalidating the document's signature.
+    pub fn title(&self) -> Option<&str> {
+        self.inner.meta.title.as_deref()
+    }
+
+    /// Public information derived from the secret data when the backup was
+    /// created (see `BackupBuilder::derive_public_info`), such as the extended
+    /// public key of a cryptocurrency wallet.
+    ///
+    /// Like the title, the public information is authenticated along with the
+    /// rest of the document.
+    pub fn public_info(&self) -> &[PublicInfo] {
+        &self.inner.meta.public_info
+    }
+
+    /// The commitment to the key shards initially created for the backup (see
+    /// `BackupBuilder::commit_shards`), if any.
+    pub fn shard_commitment(&self) -> Option<&ShardCommitment> {
+        self.inner.meta.shard_commitment.as_ref()
+    }
+
+    /// Whether `shard` is one of the key shards initially created for the
+    /// backup, or `None` if the document has no shard commitment.
+    ///
+    /// A correctly-signed key shard which is not committed to was either
+    /// created later (such as with `Quorum::new_shard`) or forged by someone
+    /// who had the backup's private key. Key shards from other backups are
+    /// never committed to.
+    pub fn is_committed_shard(&self, shard: &KeyShard) -> Option<bool> {
+        self.shard_commitment()
+            .map(|commitment| commitment.contains(shard))
+    }
+
+    /// When the backup was created (`None` for documents created by older
+    /// versions of paperback).
+    #[cfg(feature = "std")]
+    pub fn created(&self) -> Option<SystemTime> {
+        self.inner.meta.timestamps.created()
+    }
+
+    /// When the backup expires, if an expiry date was set.
+    #[cfg(feature = "std")]
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.inner.meta.timestamps.expires()
+    }
+
+    /// Whether the backup's expiry date has passed.
+    #[cfg(feature = "std")]
+    pub fn is_expired(&self) -> bool {
+        self.inner.meta.timestamps.is_expired()
+    }
+
+    /// Whether the secret was encrypted to a recipient (see
+    /// `Backup::new_with_recipient`), meaning the recipient's identity is
+    /// required to recover it.
+    pub fn requires_identity(&self) -> bool {
+        self.inner.meta.recipient_wrapped
+    }
+
+    /// Whether the secret is a BIP-39 seed phrase (see
+    /// `BackupBuilder::build_seed_phrase`).
+    pub fn contains_seed_phrase(&self) -> bool {
+        self.inner.meta.plaintext_kind == PlaintextKind::SeedPhrase
+    }
+
+    /// Size of the (encrypted) secret data stored in the document. For
+    /// documents encrypted to a recipient, this includes the overhead of the
+    /// recipient encryption.
+    pub fn secret_size(&self) -> usize {
+        self.inner
+            .ciphertext
+            .len()
+            .saturating_sub(CHACHAPOLY_TAG_LENGTH)
+    }
+
+    /// The largest secret which could be stored in a main document like this
+    /// one (with the same title and so on) and still fit in the QR codes of a
+    /// main document PDF printed with `options`.
+    #[cfg(feature = "pdf")]
+    pub fn max_secret_size(&self, options: &PdfOptions) -> usize {
+        // Leave room for the length prefix of the ciphertext to grow, without
+        // counting the prefix of this document's ciphertext (so that every
+        // document like this one has the same limit).
+        let ciphertext_prefix_length = varuint_encode::usize(
+            self.inner.ciphertext.len(),
+            &mut varuint_encode::usize_buffer(),
+        )
+        .len();
+        let overhead = self.to_wire().len() - self.secret_size() - ciphertext_prefix_length
+            + varuint_encode::usize_buffer().len();
+        pdf::generate::max_main_document_length(options).saturating_sub(overhead)
+    }
+
+    /// Check that the document can be printed with `options`, returning
+    /// `Error::SecretTooLarge` if its secret does not fit in the QR codes of a
+    /// main document PDF.
+    #[cfg(feature = "pdf")]
+    pub fn check_secret_size(&self, options: &PdfOptions) -> Result<(), Error> {
+        let max_secret_size = self.max_secret_size(options);
+        match self.secret_size() {
+            size if size > max_secret_size => Err(Error::SecretTooLarge {
+                size,
+                max_secret_size,
+            }),
+            _ => Ok(()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -405,17 +1377,76 @@ impl quickcheck::Arbitrary for MainDocument {
     }
 }
 
+#[cfg(feature = "std")]
+pub mod codewords;
+
+pub mod checksum;
+pub use checksum::ChecksumAlgorithm;
+
 pub mod wire;
 pub use wire::{FromWire, ToWire};
 
+#[cfg(feature = "std")]
 pub mod recover;
+#[cfg(feature = "std")]
 pub use recover::*;
 
+#[cfg(feature = "std")]
 pub mod backup;
+#[cfg(feature = "std")]
 pub use backup::*;
 
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
+pub use manifest::*;
+
+#[cfg(feature = "std")]
+pub mod instructions;
+#[cfg(feature = "std")]
+pub use instructions::*;
+
+#[cfg(feature = "std")]
+pub mod payload;
+#[cfg(feature = "std")]
+pub use payload::*;
+
+pub mod ids;
+pub use ids::*;
+
+#[cfg(feature = "std")]
+pub mod seed;
+#[cfg(feature = "std")]
+pub use seed::*;
+
+#[cfg(feature = "std")]
+pub mod activity;
+#[cfg(feature = "std")]
+pub use activity::*;
+
+#[cfg(feature = "std")]
+pub mod challenge;
+#[cfg(feature = "std")]
+pub use challenge::*;
+
+pub mod commitment;
+pub use commitment::*;
+
+pub mod public;
+pub use public::*;
+
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub use registry::*;
+
+#[cfg(feature = "pdf")]
 pub mod pdf;
-pub use pdf::ToPdf;
+#[cfg(feature = "pdf")]
+pub use pdf::{
+    CodewordPlacement, FromPdf, MainDocumentLayout, PdfLanguage, PdfOptions, QrDensity, QrEncoding,
+    QrErrorCorrection, TextFallback, ToPdf, ToSvg,
+};
 
 #[cfg(test)]
 mod test {
@@ -423,7 +1454,6 @@ mod test {
 
     use multibase::Base;
     use quickcheck::TestResult;
-    use rand::RngCore;
 
     // NOTE: We use u16s and u8s here (and limit the range) because generating
     //       ridiculously large dealers takes too long because of the amount of
@@ -471,7 +1501,137 @@ mod test {
         // Recover the secret.
         let recovered_secret = quorum.recover_document().unwrap();
 
-        TestResult::from_bool(recovered_secret == secret)
+        TestResult::from_bool(*recovered_secret == secret)
+    }
+
+    #[quickcheck]
+    fn paperback_named_payloads_smoke(quorum_size: u8, secrets: Vec<Vec<u8>>) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        // Construct a backup.
+        let payloads = secrets
+            .iter()
+            .enumerate()
+            .map(|(idx, secret)| NamedPayload::new(format!("secret-{}", idx), secret).unwrap());
+        let backup = Backup::new_with_payloads(quorum_size.into(), payloads, false, None).unwrap();
+        let main_document = {
+            let zbase32_bytes = backup.main_document().to_wire_multibase(Base::Base32Z);
+            MainDocument::from_wire_multibase(zbase32_bytes).unwrap()
+        };
+
+        // Construct a quorum.
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document);
+        for _ in 0..quorum_size {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+
+        // Recover each secret by name.
+        for (idx, secret) in secrets.iter().enumerate() {
+            let recovered_secret = quorum.recover_payload(&format!("secret-{}", idx)).unwrap();
+            if *recovered_secret != *secret {
+                return TestResult::failed();
+            }
+        }
+        let unknown_payload = matches!(
+            quorum.recover_payload("unknown"),
+            Err(Error::UnknownPayload(_))
+        );
+
+        // The whole document can only be recovered with a single payload.
+        let recover_document = match (secrets.len(), quorum.recover_document()) {
+            (1, Ok(recovered_secret)) => *recovered_secret == secrets[0],
+            (_, Err(Error::MultiplePayloads(names))) => names.len() == secrets.len(),
+            _ => false,
+        };
+
+        TestResult::from_bool(unknown_payload && recover_document)
+    }
+
+    #[quickcheck]
+    fn paperback_seed_phrase_smoke(quorum_size: u8, seed_phrase: SeedPhrase) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        // Construct a backup.
+        let backup = Backup::builder()
+            .quorum_size(quorum_size.into())
+            .build_seed_phrase(&seed_phrase)
+            .unwrap();
+        let main_document = {
+            let zbase32_bytes = backup.main_document().to_wire_multibase(Base::Base32Z);
+            MainDocument::from_wire_multibase(zbase32_bytes).unwrap()
+        };
+        if !main_document.contains_seed_phrase() {
+            return TestResult::failed();
+        }
+
+        // Construct a quorum.
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document);
+        for _ in 0..quorum_size {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+
+        // The document is recovered as the (re-rendered) phrase.
+        let recovered_phrase = quorum.recover_document().unwrap();
+        let recovered_seed_phrase = quorum.recover_seed_phrase().unwrap();
+        let no_payloads = matches!(quorum.recover_payloads(), Err(Error::MissingCapability(_)));
+
+        TestResult::from_bool(
+            *recovered_phrase == *seed_phrase.phrase().unwrap().as_bytes()
+                && recovered_seed_phrase == seed_phrase
+                && no_payloads,
+        )
+    }
+
+    #[quickcheck]
+    fn paperback_recipient_smoke(quorum_size: u8, secret: Vec<u8>) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        // Construct a backup wrapped to a recipient.
+        let identity = AgeIdentity::generate();
+        let backup = Backup::new_with_recipient(
+            quorum_size.into(),
+            &secret,
+            &identity.to_public(),
+            false,
+            None,
+        )
+        .unwrap();
+        let main_document = {
+            let zbase32_bytes = backup.main_document().to_wire_multibase(Base::Base32Z);
+            MainDocument::from_wire_multibase(zbase32_bytes).unwrap()
+        };
+        if !main_document.requires_identity() {
+            return TestResult::failed();
+        }
+
+        // Construct a quorum.
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document);
+        for _ in 0..quorum_size {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+        let mut quorum = quorum.validate().unwrap();
+
+        // Recovery must fail without the right identity.
+        let missing_identity = matches!(quorum.recover_document(), Err(Error::MissingIdentity));
+        quorum.set_identity(AgeIdentity::generate());
+        let wrong_identity = matches!(quorum.recover_document(), Err(Error::RecipientDecrypt(_)));
+
+        // And succeed with it.
+        quorum.set_identity(identity);
+        let recovered_secret = quorum.recover_document().unwrap();
+
+        TestResult::from_bool(missing_identity && wrong_identity && *recovered_secret == secret)
     }
 
     fn inner_paperback_expand_smoke<S: AsRef<[u8]>>(quorum_size: u32, secret: S) -> bool {
@@ -541,7 +1701,7 @@ mod test {
         // Recover the secret.
         let recovered_secret = quorum.recover_document().unwrap();
 
-        recovered_secret == secret.as_ref()
+        *recovered_secret == secret.as_ref()
     }
 
     #[cfg(not(debug_assertions))] // is --release?
@@ -569,6 +1729,7 @@ mod test {
         };
     }
 
+    paperback_expand_test!(paperback_expand_smoke_001, 1);
     paperback_expand_test!(paperback_expand_smoke_002, 2);
     paperback_expand_test!(paperback_expand_smoke_003, 3);
     paperback_expand_test!(paperback_expand_smoke_004, 4);
@@ -642,6 +1803,122 @@ mod test {
         shard == shard2
     }
 
+    #[quickcheck]
+    fn key_shard_decrypt_with_entropy(shard: KeyShard) -> bool {
+        let (enc_shard, codewords) = shard.clone().encrypt().unwrap();
+        let entropy = codewords_to_entropy(&codewords, DEFAULT_CODEWORD_LANGUAGE).unwrap();
+        let shard2 = enc_shard.decrypt_with_entropy(&entropy).unwrap();
+        shard == shard2
+            && matches!(
+                enc_shard.decrypt_with_entropy(&[0; CHACHAPOLY_KEY_LENGTH]),
+                Err(Error::WrongCodewords)
+            )
+    }
+
+    #[quickcheck]
+    fn key_shard_encryption_language_roundtrip(shard: KeyShard, lang_idx: usize) -> bool {
+        let language = CODEWORD_LANGUAGES[lang_idx % CODEWORD_LANGUAGES.len()];
+        let (enc_shard, codewords) = shard.clone().encrypt_with_language(language).unwrap();
+        let shard2 = enc_shard
+            .decrypt_with_language(&codewords, language)
+            .unwrap();
+        let shard3 = enc_shard.decrypt(&codewords).unwrap();
+        shard == shard2 && shard == shard3
+    }
+
+    #[quickcheck]
+    fn key_shard_decrypt_errors(shard: KeyShard) -> bool {
+        let (enc_shard, codewords) = shard.clone().encrypt().unwrap();
+        let (_, other_codewords) = shard.encrypt().unwrap();
+
+        let mut typo_codewords = codewords.clone();
+        typo_codewords[0] = "notabip39word".into();
+
+        let mut corrupted_shard = enc_shard.clone();
+        corrupted_shard
+            .ciphertext
+            .truncate(CHACHAPOLY_TAG_LENGTH - 1);
+
+        matches!(
+            enc_shard.decrypt(&typo_codewords),
+            Err(Error::InvalidCodewords(_))
+        ) && matches!(
+            enc_shard.decrypt(&other_codewords),
+            Err(Error::WrongCodewords)
+        ) && matches!(
+            corrupted_shard.decrypt(&codewords),
+            Err(Error::CorruptedCiphertext(_))
+        )
+    }
+
+    #[test]
+    fn key_shard_encrypt_with_codewords() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let shards = (0..3)
+            .map(|_| backup.next_shard().unwrap())
+            .collect::<Vec<_>>();
+
+        // A fixed set of codewords can be used for every key shard.
+        let codewords =
+            key_to_codewords(&[0x5a; CHACHAPOLY_KEY_LENGTH], DEFAULT_CODEWORD_LANGUAGE).unwrap();
+        for shard in &shards {
+            let enc_shard = shard.encrypt_with_codewords(&codewords).unwrap();
+            assert!(!enc_shard.is_hardened());
+            assert_eq!(&enc_shard.decrypt(&codewords).unwrap(), shard);
+        }
+
+        // Codewords in other languages work too.
+        let codewords =
+            key_to_codewords(&[0xa5; CHACHAPOLY_KEY_LENGTH], CodewordLanguage::French).unwrap();
+        let enc_shard = shards[0].encrypt_with_codewords(&codewords).unwrap();
+        assert_eq!(enc_shard.decrypt(&codewords).unwrap(), shards[0]);
+
+        // 15 codewords are a valid BIP-39 phrase, but not a valid key.
+        let codewords = key_to_codewords(&[0x5a; 20], DEFAULT_CODEWORD_LANGUAGE).unwrap();
+        assert!(matches!(
+            shards[0].encrypt_with_codewords(&codewords),
+            Err(Error::WrongCodewordCount(15))
+        ));
+
+        let mut typo_codewords = codewords.clone();
+        typo_codewords[0] = "notabip39word".into();
+        assert!(matches!(
+            shards[0].encrypt_with_codewords(&typo_codewords),
+            Err(Error::InvalidCodewords(_))
+        ));
+    }
+
+    #[quickcheck]
+    fn key_shard_hardened_roundtrip(shard: KeyShard, kdf: Argon2Params) -> bool {
+        // Use small Argon2 parameters so the test doesn't take forever.
+        let (enc_shard, codewords) = shard
+            .encrypt_inner(
+                DEFAULT_CODEWORD_LANGUAGE,
+                Some(kdf),
+                &mut rand::thread_rng(),
+            )
+            .unwrap();
+        let enc_shard = {
+            let zbase32_bytes = enc_shard.to_wire_multibase(Base::Base32Z);
+            EncryptedKeyShard::from_wire_multibase(zbase32_bytes).unwrap()
+        };
+        let (_, other_codewords) = shard
+            .encrypt_inner(
+                DEFAULT_CODEWORD_LANGUAGE,
+                Some(kdf),
+                &mut rand::thread_rng(),
+            )
+            .unwrap();
+
+        enc_shard.is_hardened()
+            && codewords.len() == 12
+            && enc_shard.decrypt(&codewords).unwrap() == shard
+            && matches!(
+                enc_shard.decrypt(&other_codewords),
+                Err(Error::WrongCodewords)
+            )
+    }
+
     #[quickcheck]
     fn paperback_recreate_shards(quorum_size: u8) -> TestResult {
         #[cfg(debug_assertions)] // not --release
@@ -693,6 +1970,18 @@ mod test {
             return TestResult::failed();
         }
 
+        // ... including when using their friendly ids.
+        if !shards.iter().all(|s| {
+            s.clone()
+                == quorum
+                    .new_shard(NewShardKind::ExistingShard(
+                        s.friendly_id().parse().unwrap(),
+                    ))
+                    .unwrap()
+        }) {
+            return TestResult::failed();
+        }
+
         // Make a second quorum and make sure we can consistently recover a
         // never-before-seen shard with an arbitrary id.
         let mut quorum2 = UntrustedQuorum::new();
@@ -703,10 +1992,10 @@ mod test {
 
         let new_shard_id = "hayyayyy";
         let new_shard = quorum
-            .new_shard(NewShardKind::ExistingShard(new_shard_id.to_string()))
+            .new_shard(NewShardKind::ExistingShard(new_shard_id.parse().unwrap()))
             .unwrap();
         let new_shard2 = quorum2
-            .new_shard(NewShardKind::ExistingShard(new_shard_id.to_string()))
+            .new_shard(NewShardKind::ExistingShard(new_shard_id.parse().unwrap()))
             .unwrap();
 
         TestResult::from_bool(
@@ -716,5 +2005,635 @@ mod test {
         )
     }
 
+    #[test]
+    fn paperback_quorum_size_one() {
+        let secret = b"secret which any single key shard can recover";
+        let backup = Backup::new(1, secret).unwrap();
+        let main_document = backup.main_document().clone();
+        let shards = backup.next_shards(3).unwrap();
+
+        for shard in &shards {
+            // Any single key shard is a full quorum.
+            let mut quorum = UntrustedQuorum::new();
+            quorum.main_document(main_document.clone());
+            quorum.push_shard(shard.clone());
+            let quorum = quorum.validate().unwrap();
+            assert_eq!(*quorum.recover_document().unwrap(), secret);
+
+            // ... which can re-create every other key shard.
+            for other in &shards {
+                assert_eq!(
+                    quorum
+                        .new_shard(NewShardKind::ExistingShard(other.id()))
+                        .unwrap(),
+                    *other
+                );
+            }
+
+            // ... or expand the backup with new key shards.
+            let new_shard = quorum.new_shard(NewShardKind::NewShard).unwrap();
+            let mut quorum = UntrustedQuorum::new();
+            quorum.main_document(main_document.clone());
+            quorum.push_shard(new_shard);
+            let quorum = quorum.validate().unwrap();
+            assert_eq!(*quorum.recover_document().unwrap(), secret);
+        }
+
+        // More key shards than the quorum size is not a valid quorum.
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document);
+        quorum.push_shard(shards[0].clone());
+        quorum.push_shard(shards[1].clone());
+        assert_eq!(
+            quorum.validate().unwrap_err().kind(),
+            InconsistentQuorumKind::Inconsistent
+        );
+    }
+
+    #[test]
+    fn key_shard_document_checksum() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let other_backup = Backup::new(2, b"some secret data").unwrap();
+        let shard = backup.next_shard().unwrap();
+
+        assert_eq!(
+            shard.document_checksum_string(),
+            backup.main_document().checksum_string()
+        );
+        assert_ne!(
+            shard.document_checksum_string(),
+            other_backup.main_document().checksum_string()
+        );
+    }
+
+    #[test]
+    fn paperback_shard_at() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let id = ShardId::from_x_value(0x1234_5678);
+        let shard = backup.shard_at(&id).unwrap();
+        assert_eq!(shard.id(), id);
+        assert!(backup.is_issued(&id));
+        assert!(matches!(
+            backup.shard_at(&id),
+            Err(Error::Shamir(ShamirError::ShardAlreadyIssued(_)))
+        ));
+        assert!(matches!(
+            backup.shard_at(&ShardId::from_x_value(0)),
+            Err(Error::Shamir(ShamirError::InvalidShardId(_, _)))
+        ));
+
+        let other = backup.next_shard().unwrap();
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup.main_document().clone());
+        quorum.push_shard(shard.clone());
+        quorum.push_shard(other.clone());
+        let quorum = quorum.validate().unwrap();
+
+        // The quorum's own key shards cannot be minted again as new shards.
+        assert!(quorum.is_issued(&id).unwrap());
+        assert!(matches!(
+            quorum.new_shard(NewShardKind::NewShardAt(other.id())),
+            Err(Error::Shamir(ShamirError::ShardAlreadyIssued(_)))
+        ));
+
+        let new_id = ShardId::from_x_value(0x0bad_cafe);
+        assert!(!quorum.is_issued(&new_id).unwrap());
+        let (new_shard, provenance) = quorum
+            .new_shard_with_provenance(NewShardKind::NewShardAt(new_id.clone()))
+            .unwrap();
+        assert_eq!(new_shard.id(), new_id);
+        assert_eq!(provenance.sequence, Some(1));
+        assert!(quorum.is_issued(&new_id).unwrap());
+        assert_eq!(quorum.extended_shard_count(), 1);
+    }
+
+    #[test]
+    fn paperback_shard_commitment() {
+        let backup = Backup::builder()
+            .quorum_size(2)
+            .commit_shards(3)
+            .build(b"some secret data")
+            .unwrap();
+        let main_document = backup.main_document();
+        assert_eq!(main_document.shard_commitment().unwrap().num_shards(), 3);
+
+        // Asking for more key shards than there are shard ids fails, without
+        // handing out any of the committed key shards.
+        assert!(matches!(
+            backup.next_shards(usize::MAX),
+            Err(Error::Shamir(ShamirError::ShardsExhausted { .. }))
+        ));
+
+        // The committed key shards are handed out first, and survive being
+        // encrypted and decrypted.
+        let committed = backup.next_shards(3).unwrap();
+        for shard in &committed {
+            let (encrypted, codewords) = shard.encrypt().unwrap();
+            let shard = encrypted.decrypt(&codewords).unwrap();
+            assert!(shard.has_commitment_proof());
+            assert_eq!(main_document.is_committed_shard(&shard), Some(true));
+        }
+
+        // Any other key shard signed by the backup is flagged, even if it has
+        // a proof copied from a committed key shard.
+        let extra = backup.next_shard().unwrap();
+        assert!(!extra.has_commitment_proof());
+        assert_eq!(main_document.is_committed_shard(&extra), Some(false));
+        let forged = KeyShard {
+            inner: KeyShardBuilder {
+                commitment_proof: committed[0].inner.commitment_proof.clone(),
+                ..extra.inner.clone()
+            },
+            identity: extra.identity.clone(),
+        };
+        assert_eq!(main_document.is_committed_shard(&forged), Some(false));
+
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document.clone());
+        quorum.push_shard(committed[0].clone());
+        quorum.push_shard(extra.clone());
+        let quorum = quorum.validate().unwrap();
+        assert_eq!(quorum.uncommitted_shards(), vec![extra.id()]);
+
+        // Documents without a commitment cannot tell either way.
+        let other = Backup::new(2, b"some secret data").unwrap();
+        assert_eq!(
+            other
+                .main_document()
+                .is_committed_shard(&other.next_shard().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn thread_safety() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<crate::shamir::Dealer>();
+        assert_send_sync::<Backup>();
+        assert_send_sync::<UntrustedQuorum>();
+        assert_send_sync::<Quorum>();
+    }
+
+    #[test]
+    fn paperback_new_shards_parallel() {
+        let backup = Backup::new(3, b"some secret data").unwrap();
+        let main_document = backup.main_document().clone();
+
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document.clone());
+        for shard in backup.next_shards(3).unwrap() {
+            quorum.push_shard(shard);
+        }
+        let quorum = quorum.validate().unwrap();
+
+        let new_shards = quorum.new_shards_parallel(16).unwrap();
+        assert_eq!(new_shards.len(), 16);
+        assert_eq!(quorum.extended_shard_count(), 16);
+        let ids = new_shards
+            .iter()
+            .map(KeyShard::id)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(ids.len(), 16);
+
+        // The new key shards form quorums of their own.
+        for shards in new_shards.chunks(3).filter(|shards| shards.len() == 3) {
+            let mut quorum = UntrustedQuorum::new();
+            quorum.main_document(main_document.clone());
+            for shard in shards {
+                quorum.push_shard(shard.clone());
+            }
+            assert_eq!(
+                quorum
+                    .validate()
+                    .unwrap()
+                    .recover_document()
+                    .unwrap()
+                    .as_slice(),
+                b"some secret data"
+            );
+        }
+    }
+
+    #[test]
+    fn paperback_shard_provenance() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let shards = (0..2)
+            .map(|_| backup.next_shard().unwrap())
+            .collect::<Vec<_>>();
+
+        let mut quorum = UntrustedQuorum::new();
+        for shard in &shards {
+            quorum.push_shard(shard.clone());
+        }
+        let quorum = quorum.validate().unwrap();
+        assert_eq!(quorum.extended_shard_count(), 0);
+
+        let mut quorum_ids = shards.iter().map(KeyShard::id).collect::<Vec<_>>();
+        for sequence in 1..=3 {
+            let (_, mut provenance) = quorum
+                .new_shard_with_provenance(NewShardKind::NewShard)
+                .unwrap();
+            assert_eq!(provenance.sequence, Some(sequence));
+            provenance.quorum.sort();
+            quorum_ids.sort();
+            assert_eq!(provenance.quorum, quorum_ids);
+        }
+        assert_eq!(quorum.extended_shard_count(), 3);
+
+        // Re-created key shards are not new key shards.
+        let (shard, provenance) = quorum
+            .new_shard_with_provenance(NewShardKind::ExistingShard(shards[0].id()))
+            .unwrap();
+        assert_eq!(shard, shards[0]);
+        assert_eq!(provenance.sequence, None);
+        assert_eq!(quorum.extended_shard_count(), 3);
+    }
+
+    #[test]
+    fn paperback_reconstruct() {
+        let backup = Backup::new(3, b"some secret data").unwrap();
+        let shards = (0..3)
+            .map(|_| backup.next_shard().unwrap())
+            .collect::<Vec<_>>();
+
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup.main_document().clone());
+        for shard in &shards {
+            quorum.push_shard(shard.clone());
+        }
+        let quorum = quorum.validate().unwrap();
+
+        // The same reconstruction is used for everything after priming it.
+        quorum.reconstruct().unwrap();
+        assert_eq!(
+            quorum.recover_document().unwrap().as_slice(),
+            b"some secret data"
+        );
+        let shard = quorum
+            .new_shard(NewShardKind::ExistingShard(shards[1].id()))
+            .unwrap();
+        assert_eq!(shard, shards[1]);
+        assert_eq!(
+            quorum.recover_document().unwrap().as_slice(),
+            b"some secret data"
+        );
+    }
+
+    #[test]
+    fn paperback_shard_policy() {
+        for policy in [ShardPolicy::Sealed, ShardPolicy::NewOnly, ShardPolicy::Any] {
+            let backup = Backup::builder()
+                .quorum_size(2)
+                .shard_policy(policy)
+                .build(b"some secret data")
+                .unwrap();
+            let shards = (0..2)
+                .map(|_| backup.next_shard().unwrap())
+                .collect::<Vec<_>>();
+
+            let mut quorum = UntrustedQuorum::new();
+            for shard in &shards {
+                quorum.push_shard(shard.clone());
+            }
+            let quorum = quorum.validate().unwrap();
+            assert_eq!(quorum.shard_policy().unwrap(), policy);
+
+            let new_shard = quorum.new_shard(NewShardKind::NewShard);
+            let recreated = quorum.new_shard(NewShardKind::ExistingShard(shards[0].id()));
+            match policy {
+                ShardPolicy::Sealed => {
+                    assert!(matches!(new_shard, Err(Error::MissingCapability(_))));
+                    assert!(matches!(recreated, Err(Error::MissingCapability(_))));
+                }
+                ShardPolicy::NewOnly => {
+                    assert!(new_shard.is_ok());
+                    assert!(matches!(recreated, Err(Error::MissingCapability(_))));
+                }
+                ShardPolicy::Any => {
+                    assert!(new_shard.is_ok());
+                    assert_eq!(recreated.unwrap(), shards[0]);
+                }
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn paperback_verify_recoverable(quorum_size: u8, secret: Vec<u8>) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        let backup = Backup::new(quorum_size.into(), &secret).unwrap();
+        let shards = (0..quorum_size)
+            .map(|_| backup.next_shard().unwrap())
+            .collect::<Vec<_>>();
+
+        // Without the main document, there is nothing to verify.
+        let mut quorum = UntrustedQuorum::new();
+        for shard in &shards {
+            quorum.push_shard(shard.clone());
+        }
+        let quorum = quorum.validate().unwrap();
+        if quorum.verify_recoverable().is_ok() {
+            return TestResult::failed();
+        }
+
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup.main_document().clone());
+        for shard in &shards {
+            quorum.push_shard(shard.clone());
+        }
+        let quorum = quorum.validate().unwrap();
+        let check = quorum.verify_recoverable().unwrap();
+
+        TestResult::from_bool(
+            check.length == secret.len()
+                && check.checksum == ChecksumAlgorithm::default().digest(&secret)
+                && check.payloads.is_empty(),
+        )
+    }
+
+    #[quickcheck]
+    fn paperback_unseal(quorum_size: u8, secret: Vec<u8>) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        // Construct a sealed backup and a full quorum for it.
+        let backup = Backup::new_sealed(quorum_size.into(), &secret).unwrap();
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup.main_document().clone());
+        for _ in 0..quorum_size {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+        if quorum.new_shard(NewShardKind::NewShard).is_ok() {
+            return TestResult::failed();
+        }
+
+        // The unsealed backup is a different document ...
+        let unsealed = quorum.unseal().unwrap();
+        let main_document = unsealed.main_document().clone();
+        if main_document.id() == backup.main_document().id() {
+            return TestResult::failed();
+        }
+
+        // ... which can be expanded and still contains the same secret.
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document);
+        for _ in 0..quorum_size {
+            quorum.push_shard(unsealed.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+        let new_shard = quorum.new_shard(NewShardKind::NewShard);
+        let recovered_secret = quorum.recover_document().unwrap();
+
+        // Unsealing an unsealed backup makes no sense.
+        let already_unsealed = matches!(quorum.unseal(), Err(Error::MissingCapability(_)));
+
+        TestResult::from_bool(new_shard.is_ok() && *recovered_secret == secret && already_unsealed)
+    }
+
+    #[quickcheck]
+    fn paperback_rotate_keys(quorum_size: u8, sealed: bool, secret: Vec<u8>) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        let backup = Backup::builder()
+            .quorum_size(quorum_size.into())
+            .sealed(sealed)
+            .build(&secret)
+            .unwrap();
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup.main_document().clone());
+        for _ in 0..quorum_size {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+
+        // The rotated backup is a different document ...
+        let rotated = quorum.rotate_keys().unwrap();
+        let main_document = rotated.main_document().clone();
+        if main_document.id() == backup.main_document().id()
+            || main_document.quorum_size() != backup.main_document().quorum_size()
+        {
+            return TestResult::failed();
+        }
+
+        // ... which the old key shards do not belong to ...
+        if backup.next_shard().unwrap().document_checksum_string()
+            == main_document.checksum_string()
+        {
+            return TestResult::failed();
+        }
+
+        // ... but contains the same secret and has the same shard policy.
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document);
+        for _ in 0..quorum_size {
+            quorum.push_shard(rotated.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+        let recovered_secret = quorum.recover_document().unwrap();
+        let shard_policy = quorum.shard_policy().unwrap();
+
+        TestResult::from_bool(
+            *recovered_secret == secret && (shard_policy == ShardPolicy::Sealed) == sealed,
+        )
+    }
+
+    #[quickcheck]
+    fn paperback_checksum_algorithm(
+        algorithm: ChecksumAlgorithm,
+        quorum_size: u8,
+        secret: Vec<u8>,
+    ) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        let backup = Backup::builder()
+            .quorum_size(quorum_size.into())
+            .checksum_algorithm(algorithm)
+            .build(&secret)
+            .unwrap();
+
+        // The algorithm must survive a round-trip through the wire format.
+        let main_document = MainDocument::from_wire(backup.main_document().to_wire()).unwrap();
+        if main_document.checksum_algorithm() != algorithm
+            || main_document.checksum() != backup.main_document().checksum()
+            || main_document.checksum().code() != algorithm.multihash_code()
+        {
+            return TestResult::failed();
+        }
+
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document);
+        for _ in 0..quorum_size {
+            let shard = backup.next_shard().unwrap();
+            quorum.push_shard(KeyShard::from_wire(shard.to_wire()).unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+
+        TestResult::from_bool(*quorum.recover_document().unwrap() == secret)
+    }
+
+    #[test]
+    fn format_date_known_values() {
+        let date = |secs| format_date(UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_868_800), "2000-03-01");
+        assert_eq!(date(1_709_251_199), "2024-02-29");
+    }
+
+    #[quickcheck]
+    fn backup_expiry_propagates(quorum_size: u8, expires: u32) -> TestResult {
+        if !(1..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+        let expires = UNIX_EPOCH + Duration::from_secs(expires.into());
+
+        let backup = Backup::new_with_expiry(quorum_size.into(), b"secret", expires).unwrap();
+        let main_document = backup.main_document().clone();
+        let shards = (0..quorum_size)
+            .map(|_| backup.next_shard().unwrap())
+            .collect::<Vec<_>>();
+
+        // Timestamps must survive serialisation and be shared by all shards.
+        let main_document = MainDocument::from_wire(main_document.to_wire()).unwrap();
+        let mut quorum = UntrustedQuorum::new();
+        for shard in &shards {
+            let (enc_shard, codewords) = shard.encrypt().unwrap();
+            let shard = enc_shard.decrypt(codewords).unwrap();
+            if shard.created() != main_document.created() || shard.expires() != Some(expires) {
+                return TestResult::failed();
+            }
+            quorum.push_shard(shard);
+        }
+        let new_shard = quorum
+            .validate()
+            .unwrap()
+            .new_shard(NewShardKind::NewShard)
+            .unwrap();
+
+        TestResult::from_bool(
+            main_document.created().is_some()
+                && main_document.expires() == Some(expires)
+                && new_shard.expires() == Some(expires)
+                && new_shard.is_expired() == (expires <= SystemTime::now()),
+        )
+    }
+
+    #[quickcheck]
+    fn quorum_insufficient_shards(quorum_size: u8) -> TestResult {
+        if !(2..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        let backup = Backup::new(quorum_size.into(), b"secret").unwrap();
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup.main_document().clone());
+        for _ in 1..quorum_size {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+
+        match quorum.validate() {
+            Err(err) => {
+                TestResult::from_bool(err.kind() == InconsistentQuorumKind::InsufficientShards)
+            }
+            Ok(_) => TestResult::failed(),
+        }
+    }
+
+    #[quickcheck]
+    fn quorum_conflicting_shards(quorum_size: u8) -> TestResult {
+        if !(2..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        let backup = Backup::new(quorum_size.into(), b"secret").unwrap();
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup.main_document().clone());
+        let shards = (0..quorum_size)
+            .map(|_| backup.next_shard().unwrap())
+            .collect::<Vec<_>>();
+        for shard in &shards {
+            quorum.push_shard(shard.clone());
+        }
+        // Pushing the same key shard again is harmless.
+        quorum.push_shard(shards[0].clone());
+        if !quorum.conflicts().is_empty() || quorum.clone().validate().is_err() {
+            return TestResult::failed();
+        }
+
+        // A tampered copy of a key shard has the same id.
+        let mut forged = shards[0].clone();
+        forged.inner.timestamps.expires = Some(1);
+        quorum.push_shard(forged.clone());
+        // ... which must not replace the original.
+        quorum.push_shard(forged.clone());
+
+        let conflicts = quorum.conflicts();
+        if quorum.num_untrusted_shards() != quorum_size as usize
+            || conflicts.len() != 1
+            || conflicts[0].id != shards[0].id()
+            || conflicts[0].shards != vec![shards[0].clone(), forged]
+        {
+            return TestResult::failed();
+        }
+
+        match quorum.validate() {
+            Err(err) => TestResult::from_bool(
+                err.kind() == InconsistentQuorumKind::ConflictingShards
+                    && err.report().groups[0].members.len() == quorum_size as usize + 2,
+            ),
+            Ok(_) => TestResult::failed(),
+        }
+    }
+
+    #[quickcheck]
+    fn quorum_report_mismatches(quorum_size: u8) -> TestResult {
+        if !(2..=16).contains(&quorum_size) {
+            return TestResult::discard();
+        }
+
+        // Mix key shards from two different backups.
+        let backup1 = Backup::new(quorum_size.into(), b"secret").unwrap();
+        let backup2 = Backup::new(quorum_size.into(), b"secret").unwrap();
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup1.main_document().clone());
+        for _ in 1..quorum_size {
+            quorum.push_shard(backup1.next_shard().unwrap());
+        }
+        let foreign_shard = backup2.next_shard().unwrap();
+        quorum.push_shard(foreign_shard.clone());
+
+        let err = match quorum.validate() {
+            Err(err) => err,
+            Ok(_) => return TestResult::failed(),
+        };
+        let report = err.report();
+        let table = report.to_string();
+
+        TestResult::from_bool(
+            err.kind() == InconsistentQuorumKind::Inconsistent
+                && report.groups.len() == 2
+                && report.groups[0].document_id == backup1.main_document().id()
+                && report.groups[0].members.len() == quorum_size as usize
+                && report.groups[0].mismatches.is_empty()
+                && report.groups[1].document_id == backup2.main_document().id()
+                && report.groups[1].members
+                    == vec![QuorumMember::KeyShard {
+                        id: foreign_shard.id(),
+                        forged: false,
+                    }]
+                && report.groups[1].mismatches
+                    == vec![QuorumField::DocumentChecksum, QuorumField::PublicKey]
+                && table.lines().count() == 3
+                && table.contains(foreign_shard.id().as_str()),
+        )
+    }
+
     // TODO: Add many more tests...
 }
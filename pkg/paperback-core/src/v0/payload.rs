@@ -0,0 +1,155 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::Error;
+
+use std::{collections::HashSet, fmt};
+
+use zeroize::Zeroizing;
+
+/// A named secret, stored alongside other named secrets in a single main
+/// document (see `Backup::new_with_payloads`). The data is wiped from memory
+/// when dropped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct NamedPayload {
+    pub(super) name: String,
+    pub(super) data: Zeroizing<Vec<u8>>,
+    pub(super) mode: Option<u32>,
+}
+
+impl NamedPayload {
+    pub fn new<N: Into<String>, B: AsRef<[u8]>>(name: N, data: B) -> Result<Self, Error> {
+        let name = name.into();
+        if name.is_empty() {
+            return Err(Error::InvalidPayloadName(name));
+        }
+        Ok(Self {
+            name,
+            data: Zeroizing::new(data.as_ref().to_vec()),
+            mode: None,
+        })
+    }
+
+    /// Record the (Unix) permission bits of the file the payload was read
+    /// from, so that it can be restored with the same permissions.
+    pub fn with_mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The permission bits recorded with `NamedPayload::with_mode`, if any.
+    pub fn mode(&self) -> Option<u32> {
+        self.mode
+    }
+
+    pub fn into_data(self) -> Zeroizing<Vec<u8>> {
+        self.data
+    }
+}
+
+// Don't leak the secret data through debug output.
+impl fmt::Debug for NamedPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamedPayload")
+            .field("name", &self.name)
+            .field("data", &format_args!("<{} bytes>", self.data.len()))
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+/// The plaintext of a main document with named payloads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct NamedPayloads(pub(super) Vec<NamedPayload>);
+
+impl NamedPayloads {
+    pub(super) fn new<P: IntoIterator<Item = NamedPayload>>(payloads: P) -> Result<Self, Error> {
+        let payloads = Self(payloads.into_iter().collect());
+        payloads.validate()?;
+        Ok(payloads)
+    }
+
+    /// Make sure every payload has a unique, non-empty name.
+    pub(super) fn validate(&self) -> Result<(), Error> {
+        let mut names = HashSet::new();
+        match self
+            .0
+            .iter()
+            .find(|payload| payload.name.is_empty() || !names.insert(&payload.name))
+        {
+            Some(payload) => Err(Error::InvalidPayloadName(payload.name.clone())),
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn names(&self) -> Vec<String> {
+        self.0.iter().map(|payload| payload.name.clone()).collect()
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for NamedPayloads {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let payloads = Vec::<(String, Vec<u8>, Option<u32>)>::arbitrary(g)
+            .into_iter()
+            .enumerate()
+            // Make sure the names are unique and non-empty.
+            .map(|(idx, (name, data, mode))| NamedPayload {
+                name: format!("{}-{}", idx, name),
+                data: Zeroizing::new(data),
+                mode,
+            });
+        Self(payloads.collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn named_payloads_validate() {
+        let payload = |name: &str| NamedPayload {
+            name: name.into(),
+            data: Zeroizing::new(b"secret".to_vec()),
+            mode: None,
+        };
+
+        assert!(NamedPayloads::new([payload("gpg"), payload("luks")]).is_ok());
+        assert!(matches!(
+            NamedPayloads::new([payload("gpg"), payload("gpg")]),
+            Err(Error::InvalidPayloadName(name)) if name == "gpg"
+        ));
+        assert!(matches!(
+            NamedPayloads::new([payload("gpg"), payload("")]),
+            Err(Error::InvalidPayloadName(name)) if name.is_empty()
+        ));
+        assert!(matches!(
+            NamedPayload::new("", b"secret"),
+            Err(Error::InvalidPayloadName(_))
+        ));
+    }
+}
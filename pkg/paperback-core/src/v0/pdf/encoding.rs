@@ -0,0 +1,331 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{pdf::Error, wire};
+
+use std::collections::HashMap;
+
+use encoding_rs::SHIFT_JIS;
+use once_cell::sync::Lazy;
+
+/// How binary data is converted to text before being stored in a QR code.
+///
+/// QR codes have several "modes" for storing data, and the text encoding of
+/// the data decides which mode (and thus what density) we get. We can't use
+/// the binary mode directly (due to issues with copy-paste, null bytes, and
+/// dodgy readers), so instead we pick an encoding which maps onto one of the
+/// other modes. All of these encodings are auto-detected when decoding.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum QrEncoding {
+    /// Multibase base10, stored in numeric mode.
+    ///
+    /// While counter-intuitive, this gives us almost identical density to
+    /// binary mode (10 bits for every 3 digits), and numeric-mode codes are
+    /// supported by every reader.
+    #[default]
+    Base10,
+
+    /// Base45 (RFC 9285), stored in alphanumeric mode.
+    ///
+    /// Slightly less dense than `Base10`, but encoding is linear-time and the
+    /// text is shorter if it needs to be typed in.
+    Base45,
+
+    /// Multibase uppercase base32, stored in alphanumeric mode.
+    ///
+    /// The least dense encoding, but the data only contains characters which
+    /// are hard to confuse when read aloud or transcribed.
+    Alphanumeric,
+
+    /// Every 12 bits are mapped to a Shift-JIS kanji, stored in kanji mode.
+    ///
+    /// Each kanji takes up 13 bits in the QR code. We don't use all 13 bits
+    /// because many of the code-points are unassigned, and readers which
+    /// convert kanji back to Unicode would mangle them.
+    Kanji,
+}
+
+/// How data is printed as text next to each QR code (in case the QR code
+/// cannot be scanned).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TextFallback {
+    /// Plain multibase zbase32.
+    #[default]
+    Plain,
+
+    /// zbase32 with Reed-Solomon parity bytes and a checksum character added
+    /// to every line.
+    ///
+    /// The text is about 30% longer, but a misread character in each line can
+    /// be corrected (which makes it far more suitable for OCR). Lines must be
+    /// entered in order and on separate lines.
+    ErrorCorrecting,
+}
+
+/// Prefix used for `QrEncoding::Base45` data.
+///
+/// This is the code reserved for base45 in the multibase specification (the
+/// multibase crate doesn't support base45 yet).
+const BASE45_PREFIX: char = 'R';
+
+impl QrEncoding {
+    /// Approximate number of QR code data bits (in thousandths of a bit) used
+    /// to store each byte of data with this encoding.
+    pub(super) fn millibits_per_byte(&self) -> usize {
+        match self {
+            // log10(256) digits per byte, 10 bits per 3 digits.
+            Self::Base10 => 8027,
+            // 3 characters per 2 bytes, 11 bits per 2 characters.
+            Self::Base45 => 8250,
+            // 8 characters per 5 bytes, 11 bits per 2 characters.
+            Self::Alphanumeric => 8800,
+            // 2 kanji per 3 bytes, 13 bits per kanji.
+            Self::Kanji => 8667,
+        }
+    }
+
+    /// Encode data as text using this encoding.
+    pub fn encode<B: AsRef<[u8]>>(&self, data: B) -> String {
+        let data = data.as_ref();
+        match self {
+            Self::Base10 => multibase::encode(multibase::Base::Base10, data),
+            Self::Base45 => format!("{}{}", BASE45_PREFIX, base45::encode(data)),
+            Self::Alphanumeric => multibase::encode(multibase::Base::Base32Upper, data),
+            Self::Kanji => kanji::encode(data),
+        }
+    }
+
+    /// Convert text produced by `encode` into the bytes which should be stored
+    /// in the QR code so that the intended QR code mode is used.
+    pub(super) fn qr_bytes<S: AsRef<str>>(&self, text: S) -> Vec<u8> {
+        let text = text.as_ref();
+        match self {
+            // Kanji mode requires the data to be Shift-JIS encoded.
+            Self::Kanji => SHIFT_JIS.encode(text).0.into_owned(),
+            _ => text.as_bytes().to_vec(),
+        }
+    }
+
+    /// Encode data into the bytes to store in a QR code.
+    pub(super) fn encode_qr<B: AsRef<[u8]>>(&self, data: B) -> Vec<u8> {
+        self.qr_bytes(self.encode(data))
+    }
+
+    /// Decode text which was encoded with any `QrEncoding` (or any other
+    /// multibase encoding, or `TextFallback::ErrorCorrecting`), auto-detecting
+    /// the encoding used.
+    pub fn decode<S: AsRef<str>>(input: S) -> Result<Vec<u8>, Error> {
+        let input = input.as_ref().trim_start();
+        match input.chars().next() {
+            None => Err(Error::ParseQrData("empty qr code data".into())),
+            Some(wire::text::ECC_PREFIX) => wire::text::decode(input).map_err(Error::ParseQrData),
+            Some(ch) if !ch.is_ascii() => kanji::decode(input),
+            Some(BASE45_PREFIX) => {
+                // NOTE: Spaces are part of the base45 alphabet so we can't
+                // strip them, but the encoded data never ends with a space.
+                let data = input[BASE45_PREFIX.len_utf8()..]
+                    .trim_end()
+                    .replace(['\t', '\r', '\n'], "");
+                base45::decode(data)
+            }
+            Some(_) => {
+                let input = wire::multibase_strip(input)
+                    .map_err(|err| Error::ParseQrData(err.to_string()))?;
+                let (_, data) = multibase::decode(input)
+                    .map_err(|err| Error::ParseQrData(format!("{:?}", err)))?;
+                Ok(data)
+            }
+        }
+    }
+}
+
+mod base45 {
+    use crate::v0::pdf::Error;
+
+    const ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+    pub(super) fn encode(data: &[u8]) -> String {
+        let mut output = String::with_capacity((data.len() * 3).div_ceil(2));
+        for chunk in data.chunks(2) {
+            let (mut n, len) = match *chunk {
+                [a, b] => ((a as usize) << 8 | b as usize, 3),
+                [a] => (a as usize, 2),
+                _ => unreachable!(), // Not possible given chunks(2).
+            };
+            for _ in 0..len {
+                output.push(ALPHABET[n % 45] as char);
+                n /= 45;
+            }
+        }
+        output
+    }
+
+    pub(super) fn decode<S: AsRef<str>>(input: S) -> Result<Vec<u8>, Error> {
+        let values = input
+            .as_ref()
+            .bytes()
+            .map(|ch| {
+                ALPHABET.iter().position(|&v| v == ch).ok_or_else(|| {
+                    Error::ParseQrData(format!("invalid base45 character {:?}", ch as char))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut output = Vec::with_capacity(values.len() * 2 / 3);
+        for chunk in values.chunks(3) {
+            let n = chunk.iter().rev().fold(0, |n, v| n * 45 + v);
+            match chunk.len() {
+                3 if n <= 0xFFFF => output.extend_from_slice(&(n as u16).to_be_bytes()),
+                2 if n <= 0xFF => output.push(n as u8),
+                _ => return Err(Error::ParseQrData("invalid base45 data".into())),
+            }
+        }
+        Ok(output)
+    }
+}
+
+mod kanji {
+    use super::*;
+
+    /// Kanji used to represent each 12-bit value.
+    ///
+    /// These are the first 4096 kanji (starting from the JIS level 1 kanji,
+    /// so we don't include any symbols or full-width spaces which readers
+    /// might strip) which round-trip through Shift-JIS and fit in the QR code
+    /// kanji mode range.
+    pub(super) static TABLE: Lazy<Vec<char>> = Lazy::new(|| {
+        (0x88..=0x9Fu8)
+            .chain(0xE0..=0xEA)
+            .flat_map(|hi| {
+                (0x40..=0xFCu8)
+                    .filter(|&lo| lo != 0x7F)
+                    .map(move |lo| [hi, lo])
+            })
+            .filter(|&sjis| sjis >= [0x88, 0x9F])
+            .filter_map(|sjis| {
+                let text = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(&sjis)?;
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) if SHIFT_JIS.encode(&text).0[..] == sjis => Some(ch),
+                    _ => None,
+                }
+            })
+            .take(4096)
+            .collect()
+    });
+
+    pub(super) static REVERSE_TABLE: Lazy<HashMap<char, u16>> = Lazy::new(|| {
+        TABLE
+            .iter()
+            .enumerate()
+            .map(|(idx, &ch)| (ch, idx as u16))
+            .collect()
+    });
+
+    pub(super) fn encode(data: &[u8]) -> String {
+        // Prefix the data with the amount of padding needed to get a multiple
+        // of 3 bytes (24 bits, or two kanji).
+        let padding = (3 - (data.len() + 1) % 3) % 3;
+        let mut padded = Vec::with_capacity(1 + data.len() + padding);
+        padded.push(padding as u8);
+        padded.extend_from_slice(data);
+        padded.resize(1 + data.len() + padding, 0);
+
+        padded
+            .chunks(3)
+            .flat_map(|chunk| {
+                let (a, b, c) = (chunk[0] as usize, chunk[1] as usize, chunk[2] as usize);
+                [(a << 4) | (b >> 4), ((b & 0xF) << 8) | c]
+            })
+            .map(|v| TABLE[v])
+            .collect()
+    }
+
+    pub(super) fn decode(input: &str) -> Result<Vec<u8>, Error> {
+        let values = input
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .map(|ch| {
+                REVERSE_TABLE
+                    .get(&ch)
+                    .copied()
+                    .ok_or_else(|| Error::ParseQrData(format!("invalid kanji character {:?}", ch)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() % 2 != 0 {
+            return Err(Error::ParseQrData("kanji data has odd length".into()));
+        }
+
+        let padded = values
+            .chunks(2)
+            .flat_map(|chunk| {
+                let (x, y) = (chunk[0], chunk[1]);
+                [(x >> 4) as u8, (((x & 0xF) << 4) | (y >> 8)) as u8, y as u8]
+            })
+            .collect::<Vec<_>>();
+        match padded.split_first() {
+            Some((&padding, data)) if padding <= 2 && data.len() >= padding as usize => {
+                Ok(data[..data.len() - padding as usize].to_vec())
+            }
+            _ => Err(Error::ParseQrData("invalid kanji data padding".into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use quickcheck::*;
+
+    impl Arbitrary for QrEncoding {
+        fn arbitrary(g: &mut Gen) -> Self {
+            *g.choose(&[Self::Base10, Self::Base45, Self::Alphanumeric, Self::Kanji])
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn kanji_table_size() {
+        assert_eq!(kanji::TABLE.len(), 4096);
+        assert_eq!(kanji::REVERSE_TABLE.len(), 4096);
+    }
+
+    #[test]
+    fn base45_rfc9285_vectors() {
+        assert_eq!(base45::encode(b"AB"), "BB8");
+        assert_eq!(base45::encode(b"Hello!!"), "%69 VD92EX0");
+        assert_eq!(base45::encode(b"base-45"), "UJCLQE7W581");
+        assert_eq!(base45::decode("QED8WEX0").unwrap(), b"ietf!");
+    }
+
+    #[quickcheck]
+    fn qr_encoding_roundtrip(encoding: QrEncoding, data: Vec<u8>) -> bool {
+        QrEncoding::decode(encoding.encode(&data)).unwrap() == data
+    }
+
+    #[quickcheck]
+    fn text_fallback_ecc_detected(data: Vec<u8>) -> bool {
+        QrEncoding::decode(wire::text::encode(&data).join("\n")).unwrap() == data
+    }
+
+    #[quickcheck]
+    fn qr_encoding_no_trailing_space(encoding: QrEncoding, data: Vec<u8>) -> bool {
+        !encoding.encode(data).ends_with(char::is_whitespace)
+    }
+}
@@ -17,16 +17,29 @@
  */
 
 use crate::v0::{
-    pdf::{qr, qr::PartType, Error},
-    EncryptedKeyShard, KeyShardCodewords, MainDocument, ToWire,
+    format_date,
+    pdf::{
+        qr, qr::PartType, strings::Strings, Error, MainDocumentLayout, PdfOptions, TextFallback,
+        PDF_KEYWORD_ACTIVITY_RECORD, PDF_KEYWORD_KEY_SHARD, PDF_KEYWORD_MAIN_DOCUMENT,
+    },
+    to_unix_timestamp, wire, ActivityRecord, DocumentId, EncryptedKeyShard, InstructionsDocument,
+    KeyShard, KeyShardCodewords, MainDocument, Manifest, ShardProvenance, ToWire,
 };
 
+use std::time::SystemTime;
+
 use multibase::Base;
 use printpdf::*;
-use qrcode::render::svg;
+use qrcode::{render::svg, QrCode};
+use tracing::instrument;
+use zeroize::Zeroizing;
 
 pub trait ToPdf {
-    fn to_pdf(&self) -> Result<PdfDocumentReference, Error>;
+    fn to_pdf(&self) -> Result<PdfDocumentReference, Error> {
+        self.to_pdf_with_options(&PdfOptions::default())
+    }
+
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error>;
 }
 
 // TODO: Use azul-text-layout or some other text layout library to reduce the
@@ -79,6 +92,14 @@ mod colours {
         icc_profile: None,
     });
 
+    // #1f5fa8
+    pub(super) const MANIFEST_TRIM: Color = Color::Rgb(Rgb {
+        r: 0.12157,
+        g: 0.37255,
+        b: 0.65882,
+        icc_profile: None,
+    });
+
     // #2c9f2c
     pub(super) const KEY_SHARD_TRIM: Color = Color::Rgb(Rgb {
         r: 0.17255,
@@ -149,17 +170,101 @@ fn banner(
     BANNER_HEIGHT + banner_margin
 }
 
-fn qr_with_fallback<D: AsRef<[u8]>>(
+/// Describe when a document was created and when it expires (if known).
+pub(super) fn timestamp_details(
+    strings: &Strings,
+    created: Option<SystemTime>,
+    expires: Option<SystemTime>,
+) -> Option<String> {
+    match (created.map(format_date), expires.map(format_date)) {
+        (Some(created), Some(expires)) => Some((strings.created_expires)(&created, &expires)),
+        (Some(created), None) => Some((strings.created)(&created)),
+        (None, Some(expires)) => Some((strings.expires)(&expires)),
+        (None, None) => None,
+    }
+}
+
+/// Width to leave for a header title of `font_size` in the top-right corner.
+/// Titles normally fit in `reserved`, but longer (translated) titles are given
+/// a rough estimate of their width so that they stay on the page.
+pub(super) fn header_width(title: &str, font_size: f32, reserved: Pt) -> Mm {
+    let estimate: Mm = Pt(title.chars().count() as f32 * font_size * 0.55).into();
+    let reserved: Mm = reserved.into();
+    // Can't use std::cmp::max sadly.
+    if estimate > reserved {
+        estimate
+    } else {
+        reserved
+    }
+}
+
+/// Data printed by `qr_with_fallback`.
+enum QrData<'a> {
+    /// Data which is always stored in a single QR code.
+    Single(Vec<u8>),
+    /// Data belonging to a key shard, which is split into several QR codes if
+    /// it is too large for one (see `qr::generate_key_shard_codes`).
+    KeyShard(PartType, &'a KeyShard, Vec<u8>),
+}
+
+/// Lines of the text fallback printed next to the QR codes of `data`.
+pub(super) fn fallback_lines(text_fallback: TextFallback, data: &[u8]) -> Vec<String> {
+    match text_fallback {
+        TextFallback::Plain => multibase::encode(Base::Base32Z, data)
+            // Split the encoded version into 4-char words.
+            .into_bytes()
+            .chunks(4)
+            .map(String::from_utf8_lossy)
+            .collect::<Vec<_>>()
+            // Split the words into rows for printing.
+            .chunks(8)
+            // Join the words with "-". This is to work around the fact that
+            // printpdf appears to generate PDFs such that horizontally-written
+            // words get selected as if they were columns (breaking copy-and-paste
+            // for these data sections).
+            .map(|ws| ws.join("-"))
+            .map(|mut line| match line.len() {
+                39 /* 4*8+7 */ => line, // Line is the right length.
+                l @ 0..=38 => { // Line needs to be padded.
+                    line.push_str(&"-".repeat(39-l));
+                    line
+                },
+                _ => unreachable!(), // Not possible given how this string was constructed.
+            })
+            .collect::<Vec<String>>(),
+        TextFallback::ErrorCorrecting => wire::text::encode(data),
+    }
+}
+
+fn qr_with_fallback(
     layer: &PdfLayerReference,
     top: Mm,
     (width, margin, qr_fraction): (Mm, Mm, f32),
-    data: D,
+    options: &PdfOptions,
+    data: QrData<'_>,
     font: &IndirectFontRef,
     font_size: f32,
 ) -> Result<Mm, Error> {
     const DATA_MARGIN: Mm = Mm(3.0);
 
-    let data = data.as_ref();
+    let (data, codes) = match data {
+        QrData::Single(data) => {
+            let code =
+                qr::generate_one_code(options.qr_encoding, options.qr_error_correction, &data)?;
+            (data, vec![code])
+        }
+        QrData::KeyShard(data_type, shard, data) => {
+            let codes = qr::generate_key_shard_codes(
+                options.qr_encoding,
+                options.qr_error_correction,
+                options.qr_density,
+                data_type,
+                shard,
+                &data,
+            )?;
+            (data, codes)
+        }
+    };
     // Can't use std::cmp::min sadly.
     let qr_size = if top - margin < width * qr_fraction {
         top - margin
@@ -171,62 +276,47 @@ fn qr_with_fallback<D: AsRef<[u8]>>(
     // done for us, as well as being able to use the computed text dimensions to
     // vertically center and horizontally right-adjust the fallback text.
 
-    let data_lines = multibase::encode(Base::Base32Z, data)
-        // Split the encoded version into 4-char words.
-        .into_bytes()
-        .chunks(4)
-        .map(String::from_utf8_lossy)
-        .collect::<Vec<_>>()
-        // Split the words into rows for printing.
-        .chunks(8)
-        // Join the words with "-". This is to work around the fact that
-        // printpdf appears to generate PDFs such that horizontally-written
-        // words get selected as if they were columns (breaking copy-and-paste
-        // for these data sections).
-        .map(|ws| ws.join("-"))
-        .map(|mut line| match line.len() {
-            39 /* 4*8+7 */ => line, // Line is the right length.
-            l @ 0..=38 => { // Line needs to be padded.
-                line.push_str(&"-".repeat(39-l));
-                line
-            },
-            _ => unreachable!(), // Not possible given how this string was constructed.
-        })
-        .collect::<Vec<String>>();
+    // The text fallback always contains all of the data, even if the QR codes
+    // had to be split into several parts.
+    let data_lines = fallback_lines(options.text_fallback, &data);
 
     let data_height: Mm = Pt(font_size + (font_size + 2.0) * data_lines.len() as f32).into();
     let padded_data_height = data_height + DATA_MARGIN * 2.0;
+    // Split codes are stacked on top of each other.
+    let qrs_height = qr_size * codes.len() as f32 + DATA_MARGIN * (codes.len() - 1) as f32;
     // Can't use std::cmp::max sadly.
-    let total_height = if qr_size > padded_data_height {
-        qr_size
+    let total_height = if qrs_height > padded_data_height {
+        qrs_height
     } else {
         padded_data_height
     };
 
-    let (qr_y, data_y) = (
-        total_height / 2.0 + qr_size / 2.0,
+    let (qrs_y, data_y) = (
+        total_height / 2.0 - qrs_height / 2.0,
         total_height / 2.0 - data_height / 2.0 + Mm::from(Pt(font_size)),
     );
     let (qr_x, data_x) = (margin, margin + qr_size + margin);
 
-    // Display svg.
-    let qr_svg = Svg::parse(&qr::generate_one_code(data)?.render::<svg::Color>().build())?
-        .into_xobject(layer);
-    let (scale_x, scale_y) = (
-        qr_size / Mm::from(qr_svg.width.into_pt(SVG_DPI)),
-        qr_size / Mm::from(qr_svg.height.into_pt(SVG_DPI)),
-    );
-    qr_svg.add_to_layer(
-        layer,
-        SvgTransform {
-            translate_x: Some(qr_x.into()),
-            translate_y: Some((top - qr_y).into()),
-            dpi: Some(SVG_DPI),
-            scale_x: Some(scale_x),
-            scale_y: Some(scale_y),
-            ..Default::default()
-        },
-    );
+    // Display svgs.
+    for (idx, code) in codes.iter().enumerate() {
+        let qr_y = qrs_y + (qr_size + DATA_MARGIN) * idx as f32 + qr_size;
+        let qr_svg = Svg::parse(&code.render::<svg::Color>().build())?.into_xobject(layer);
+        let (scale_x, scale_y) = (
+            qr_size / Mm::from(qr_svg.width.into_pt(SVG_DPI)),
+            qr_size / Mm::from(qr_svg.height.into_pt(SVG_DPI)),
+        );
+        qr_svg.add_to_layer(
+            layer,
+            SvgTransform {
+                translate_x: Some(qr_x.into()),
+                translate_y: Some((top - qr_y).into()),
+                dpi: Some(SVG_DPI),
+                scale_x: Some(scale_x),
+                scale_y: Some(scale_y),
+                ..Default::default()
+            },
+        );
+    }
 
     // Display the fallback text.
     layer.begin_text_section();
@@ -239,7 +329,7 @@ fn qr_with_fallback<D: AsRef<[u8]>>(
 
         layer.set_text_cursor(data_x, top - data_y);
         layer.set_fill_color(colours::LIGHT_GREY);
-        layer.write_text("text fallback if barcode scanning fails", font);
+        layer.write_text(options.language.strings().text_fallback, font);
     }
     layer.end_text_section();
     layer.begin_text_section();
@@ -267,62 +357,230 @@ fn qr_with_fallback<D: AsRef<[u8]>>(
     Ok(total_height)
 }
 
-const A4_WIDTH: Mm = Mm(210.0);
-const A4_HEIGHT: Mm = Mm(297.0);
-const A4_MARGIN: Mm = Mm(5.0);
-const QR_MARGIN: Mm = Mm(5.0);
+pub(super) const A4_WIDTH: Mm = Mm(210.0);
+pub(super) const A4_HEIGHT: Mm = Mm(297.0);
+pub(super) const A4_MARGIN: Mm = Mm(5.0);
+pub(super) const QR_MARGIN: Mm = Mm(5.0);
+
+// The checksum QR code needs to be smaller in the landscape layout, to leave
+// enough room for the QR code grid.
+pub(super) const LANDSCAPE_CHECKSUM_QR_FRACTION: f32 = 0.1;
 
 const FONT_ROBOTOSLAB: &[u8] = include_bytes!("fonts/RobotoSlab-Regular.ttf");
 const FONT_B612MONO: &[u8] = include_bytes!("fonts/B612Mono-Regular.ttf");
 const FONT_B612MONO_BOLD: &[u8] = include_bytes!("fonts/B612Mono-Bold.ttf");
 
+/// The fonts embedded in every paperback PDF.
+struct Fonts {
+    monospace: IndirectFontRef,
+    monospace_bold: IndirectFontRef,
+    text: IndirectFontRef,
+}
+
+/// Create a PDF with a single (empty) page of the given size, with the
+/// paperback fonts embedded. Every document type is rendered starting from
+/// this, so that the page and font setup only lives in one place.
+fn new_document<S: Into<String>>(
+    title: S,
+    page_width: Mm,
+    page_height: Mm,
+) -> Result<(PdfDocumentReference, PdfLayerReference, Fonts), Error> {
+    let (doc, page1, layer1) = PdfDocument::new(title, page_width, page_height, "Layer 1");
+    let fonts = Fonts {
+        monospace: doc.add_external_font(FONT_B612MONO)?,
+        monospace_bold: doc.add_external_font(FONT_B612MONO_BOLD)?,
+        text: doc.add_external_font(FONT_ROBOTOSLAB)?,
+    };
+    let layer = doc.get_page(page1).get_layer(layer1);
+    Ok((doc, layer, fonts))
+}
+
+/// Describe a paperback PDF in its metadata, so that document management
+/// systems (and anything else reading the PDF) can identify paperback files
+/// without decoding their QR codes. printpdf stores this both in the document
+/// information dictionary and in the XMP metadata stream.
+///
+/// `subject` is a machine-readable description of the contents, such as
+/// `"paperback-v0 key shard <document id>/<shard id>"`.
+fn with_metadata(
+    doc: PdfDocumentReference,
+    subject: String,
+    document_id: &DocumentId,
+    created: Option<SystemTime>,
+) -> PdfDocumentReference {
+    let doc = doc
+        .with_document_id(document_id.to_string())
+        .with_identifier(document_id.to_string())
+        .with_subject(subject)
+        .with_producer(concat!("paperback ", env!("CARGO_PKG_VERSION")));
+    // Use the creation time of the backup rather than when the PDF was
+    // rendered, so that reprinted documents have the same metadata.
+    match created.and_then(|created| {
+        OffsetDateTime::from_unix_timestamp(to_unix_timestamp(created) as i64).ok()
+    }) {
+        Some(created) => doc.with_creation_date(created),
+        None => doc,
+    }
+}
+
+/// Add a new (empty) page of the given size to the PDF.
+fn add_page(doc: &PdfDocumentReference, page_width: Mm, page_height: Mm) -> PdfLayerReference {
+    let (page, layer) = doc.add_page(page_width, page_height, "Layer 1");
+    doc.get_page(page).get_layer(layer)
+}
+
+/// Maximum number of QR codes a main document can be split into.
+pub(super) const MAX_MAIN_DOCUMENT_CODES: usize = 9;
+
+/// Maximum length of the wire encoding of a main document which can be printed
+/// with `options`.
+pub(crate) fn max_main_document_length(options: &PdfOptions) -> usize {
+    MAX_MAIN_DOCUMENT_CODES
+        * qr::max_data_length(
+            options.qr_encoding,
+            options.qr_error_correction,
+            options.qr_density,
+        )
+}
+
+/// Draw a dashed box where a QR code of `size` would go, with its top-left
+/// corner at (`x`, `y`).
+fn dashed_box(layer: &PdfLayerReference, (x, y): (Mm, Mm), size: Mm) {
+    let polygon = Polygon {
+        rings: vec![vec![
+            (Point::new(x + QR_MARGIN / 2.0, y - QR_MARGIN / 2.0), false),
+            (
+                Point::new(x + size - QR_MARGIN / 2.0, y - QR_MARGIN / 2.0),
+                false,
+            ),
+            (
+                Point::new(x + size - QR_MARGIN / 2.0, y - (size - QR_MARGIN / 2.0)),
+                false,
+            ),
+            (
+                Point::new(x + QR_MARGIN / 2.0, y - (size - QR_MARGIN / 2.0)),
+                false,
+            ),
+        ]],
+        mode: PolygonMode::Stroke,
+        winding_order: WindingOrder::NonZero,
+    };
+
+    let dash_pattern = LineDashPattern {
+        dash_1: Some(6),
+        gap_1: Some(4),
+        ..LineDashPattern::default()
+    };
+
+    layer.set_outline_color(colours::LIGHT_GREY);
+    layer.set_line_dash_pattern(dash_pattern);
+    layer.add_polygon(polygon);
+}
+
+/// Draw a QR code scaled to `size`, with its top-left corner at (`x`, `y`).
+fn scaled_qr(layer: &PdfLayerReference, qr: Svg, (x, y): (Mm, Mm), size: Mm) {
+    let svg = qr.into_xobject(layer);
+    let (width, height) = (svg.width, svg.height);
+    svg.add_to_layer(
+        layer,
+        SvgTransform {
+            translate_x: Some(x.into()),
+            translate_y: Some((y - size).into()),
+            dpi: Some(SVG_DPI),
+            scale_x: Some(size / Mm::from(width.into_pt(SVG_DPI))),
+            scale_y: Some(size / Mm::from(height.into_pt(SVG_DPI))),
+            ..Default::default()
+        },
+    );
+}
+
+/// Lay out `qrs` in a grid of `columns` by `rows` QR codes of `size`, starting
+/// at `top`. Empty slots are drawn as dashed boxes. Returns the height of the
+/// grid.
+fn qr_grid(
+    layer: &PdfLayerReference,
+    top: Mm,
+    margin: Mm,
+    (columns, rows): (usize, usize),
+    size: Mm,
+    qrs: Vec<Svg>,
+) -> Mm {
+    let mut qrs = qrs.into_iter();
+    for row in 0..rows {
+        for column in 0..columns {
+            let corner = (margin + size * column as f32, top - size * row as f32);
+            match qrs.next() {
+                Some(qr) => scaled_qr(layer, qr, corner, size),
+                None => dashed_box(layer, corner, size),
+            }
+        }
+    }
+    size * rows as f32
+}
+
 impl ToPdf for MainDocument {
-    fn to_pdf(&self) -> Result<PdfDocumentReference, Error> {
+    #[instrument(name = "main_document_pdf", skip_all, fields(document_id = %self.id()))]
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error> {
         // Generate QR codes to embed in the PDF.
-        let (data_qrs, data_qr_datas) =
-            qr::generate_codes(PartType::MainDocumentData, self.to_wire())?;
+        let (data_qrs, _) = qr::generate_codes(
+            options.qr_encoding,
+            options.qr_error_correction,
+            options.qr_density,
+            PartType::MainDocumentData,
+            Some(&self.id()),
+            None,
+            self.to_wire(),
+        )?;
+        if data_qrs.len() > MAX_MAIN_DOCUMENT_CODES {
+            return Err(Error::TooManyCodes(format!(
+                "only {} codes allowed in this version of paperback (at most {} bytes of secret data fit in a main document with these options)",
+                MAX_MAIN_DOCUMENT_CODES,
+                self.max_secret_size(options)
+            )));
+        }
         let data_qrs = data_qrs
             .iter()
             .map(|code| code.render::<svg::Color>().build())
             .map(|svg| Svg::parse(&svg))
             .collect::<Result<Vec<_>, _>>()?;
+        let num_codes = data_qrs.len();
+        let strings = options.language.strings();
 
         // Construct an A4 PDF.
-        let (doc, page1, layer1) = PdfDocument::new(
+        let (page_width, page_height) = match options.layout {
+            MainDocumentLayout::Portrait | MainDocumentLayout::Booklet => (A4_WIDTH, A4_HEIGHT),
+            MainDocumentLayout::Landscape => (A4_HEIGHT, A4_WIDTH),
+        };
+        let (doc, current_layer, fonts) = new_document(
             format!("Paperback Main Document {}", self.id()),
-            A4_WIDTH,
-            A4_HEIGHT,
-            "Layer 1",
-        );
-
-        let monospace_font = doc.add_external_font(FONT_B612MONO)?;
-        let text_font = doc.add_external_font(FONT_ROBOTOSLAB)?;
-
-        let current_page = doc.get_page(page1);
-        let current_layer = current_page.get_layer(layer1);
+            page_width,
+            page_height,
+        )?;
 
         let mut current_y = A4_MARGIN + Pt(10.0).into();
 
+        let timestamp_details = timestamp_details(strings, self.created(), self.expires());
+
         // Header.
         current_layer.begin_text_section();
         {
-            current_layer.set_font(&monospace_font, 10.0);
+            current_layer.set_font(&fonts.monospace, 10.0);
             current_layer.set_word_spacing(1.2);
             current_layer.set_character_spacing(1.0);
 
-            current_layer.set_text_cursor(A4_MARGIN, A4_HEIGHT - current_y);
+            current_layer.set_text_cursor(A4_MARGIN, page_height - current_y);
 
             // "Document".
-            current_layer.set_font(&text_font, 10.0);
+            current_layer.set_font(&fonts.text, 10.0);
             current_layer.set_fill_color(colours::GREY);
-            current_layer.write_text("Document", &text_font);
+            current_layer.write_text(strings.document, &fonts.text);
             current_layer.set_fill_color(colours::BLACK);
             current_layer.set_line_height(20.0 + 2.0);
             current_layer.add_line_break();
             // <document id>
-            current_layer.set_font(&monospace_font, 20.0);
+            current_layer.set_font(&fonts.monospace, 20.0);
             current_layer.set_fill_color(colours::MAIN_DOCUMENT_TRIM);
-            current_layer.write_text(self.id(), &monospace_font);
+            current_layer.write_text(self.id(), &fonts.monospace);
             current_layer.set_fill_color(colours::BLACK);
             current_layer.set_line_height(10.0 + 2.0);
 
@@ -330,227 +588,230 @@ impl ToPdf for MainDocument {
             current_layer.add_line_break();
 
             // Details.
-            current_layer.set_font(&text_font, 10.0);
+            current_layer.set_font(&fonts.text, 10.0);
             current_layer.set_line_height(10.0 + 2.0);
+            if let Some(title) = self.title() {
+                current_layer.write_text((strings.main_document_title)(title), &fonts.text);
+                current_layer.add_line_break();
+            }
+            // Public information is derived by a plugin, so it isn't translated.
+            for info in self.public_info() {
+                current_layer
+                    .write_text(format!("{}: {}", info.label(), info.value()), &fonts.text);
+                current_layer.add_line_break();
+            }
+            for line in (strings.main_document_details)(self.quorum_size()) {
+                current_layer.write_text(line, &fonts.text);
+                current_layer.add_line_break();
+            }
             current_layer.write_text(
-                format!(
-                    "This is the main document of a paperback backup. When combined with {} unique",
-                    self.quorum_size()
-                ),
-                &text_font,
-            );
-            current_layer.add_line_break();
-            current_layer.write_text(
-                "key shards, this document can be recovered. In order to recover this document,",
-                &text_font,
-            );
-            current_layer.add_line_break();
-            current_layer.write_text(
-                "download the latest version of paperback from cyphar.com/paperback.",
-                &text_font,
+                (strings.main_document_fingerprint)(&self.fingerprint_words().join(" ")),
+                &fonts.text,
             );
+            if let Some(details) = &timestamp_details {
+                current_layer.add_line_break();
+                current_layer.write_text(details, &fonts.text);
+            }
         }
         current_layer.end_text_section();
         current_layer.begin_text_section();
         {
             // Header. TODO: Right-align this text.
             current_layer.set_text_cursor(
-                A4_WIDTH - (A4_MARGIN + (Pt(15.0) * 12.0).into()),
-                A4_HEIGHT - (current_y + Pt(10.0).into()),
+                page_width
+                    - (A4_MARGIN + header_width(strings.main_document, 20.0, Pt(15.0) * 12.0)),
+                page_height - (current_y + Pt(10.0).into()),
             );
-            current_layer.set_font(&text_font, 20.0);
+            current_layer.set_font(&fonts.text, 20.0);
             current_layer.set_fill_color(colours::MAIN_DOCUMENT_TRIM);
-            current_layer.write_text("Main Document", &text_font);
+            current_layer.write_text(strings.main_document, &fonts.text);
             current_layer.set_fill_color(colours::BLACK);
             current_layer.set_line_height(10.0 + 2.0);
             current_layer.add_line_break();
 
-            current_layer.set_font(&monospace_font, 10.0);
+            current_layer.set_font(&fonts.monospace, 10.0);
             current_layer.set_fill_color(colours::GREY);
-            current_layer.write_text("paperback-v0", &monospace_font);
+            current_layer.write_text("paperback-v0", &fonts.monospace);
             current_layer.set_fill_color(colours::BLACK);
             current_layer.set_line_height(10.0 + 2.0);
         }
         current_layer.end_text_section();
-        current_y += (Pt(22.0) + Pt(12.0) * 4.0).into();
-
+        let details_lines = 5.0
+            + self.title().iter().count() as f32
+            + self.public_info().len() as f32
+            + timestamp_details.iter().count() as f32;
+        current_y += (Pt(22.0) + Pt(12.0) * details_lines).into();
+
+        let document_description = match options.layout {
+            MainDocumentLayout::Booklet => format!(
+                "{} {}",
+                strings.document_description,
+                (strings.booklet_note)(num_codes)
+            ),
+            _ => strings.document_description.to_string(),
+        };
         current_y += banner(
             &current_layer,
-            A4_HEIGHT - current_y,
-            (A4_WIDTH, A4_MARGIN, Mm(3.0)),
+            page_height - current_y,
+            (page_width, A4_MARGIN, Mm(3.0)),
             Text {
-                inner: "① Document",
+                inner: strings.document_banner,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(10.0),
             },
             Some(Text {
-                inner: "Data section, encrypted with secret key stored in the key shards.",
+                inner: &document_description,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(8.0),
             }),
             colours::MAIN_DOCUMENT_TRIM,
         ) + Mm(2.0);
 
-        // TODO: Get rid of this once we have nice QR code scanning.
-        println!("Main Document:");
-        data_qr_datas
-            .iter()
-            .for_each(|code| println!("{}", multibase::encode(multibase::Base::Base10, code)));
-
-        let mut current_x = A4_MARGIN;
-        let mut data_qr_refs = data_qrs
-            .into_iter()
-            .map(|code| code.into_xobject(&current_layer));
-        for _ in 0..9 {
-            let target_size = (A4_WIDTH - A4_MARGIN * 2.0) / 3.0;
-            match data_qr_refs.next() {
-                Some(svg) => {
-                    let (width, height) = (svg.width, svg.height);
-                    svg.add_to_layer(
-                        &current_layer,
-                        SvgTransform {
-                            translate_x: Some(current_x.into()),
-                            translate_y: Some((A4_HEIGHT - (current_y + target_size)).into()),
-                            dpi: Some(SVG_DPI),
-                            scale_x: Some(target_size / Mm::from(width.into_pt(SVG_DPI))),
-                            scale_y: Some(target_size / Mm::from(height.into_pt(SVG_DPI))),
-                            ..Default::default()
-                        },
-                    );
-                }
-                None => {
-                    // Dashed line box where the QR code would go.
-                    let polygon = Polygon {
-                        rings: vec![vec![
-                            (
-                                Point::new(
-                                    current_x + QR_MARGIN / 2.0,
-                                    A4_HEIGHT - (current_y + QR_MARGIN / 2.0),
-                                ),
-                                false,
-                            ),
-                            (
-                                Point::new(
-                                    current_x + target_size - QR_MARGIN / 2.0,
-                                    A4_HEIGHT - (current_y + QR_MARGIN / 2.0),
-                                ),
-                                false,
-                            ),
-                            (
-                                Point::new(
-                                    current_x + target_size - QR_MARGIN / 2.0,
-                                    A4_HEIGHT - (current_y + target_size - QR_MARGIN / 2.0),
-                                ),
-                                false,
-                            ),
-                            (
-                                Point::new(
-                                    current_x + QR_MARGIN / 2.0,
-                                    A4_HEIGHT - (current_y + target_size - QR_MARGIN / 2.0),
-                                ),
-                                false,
-                            ),
-                        ]],
-                        mode: PolygonMode::Stroke,
-                        winding_order: WindingOrder::NonZero,
-                    };
-
-                    let dash_pattern = LineDashPattern {
-                        dash_1: Some(6),
-                        gap_1: Some(4),
-                        ..LineDashPattern::default()
-                    };
-
-                    current_layer.set_outline_color(colours::LIGHT_GREY);
-                    current_layer.set_line_dash_pattern(dash_pattern);
-                    current_layer.add_polygon(polygon);
-                }
-            };
-            current_x += target_size;
-            if current_x + target_size > A4_WIDTH {
-                current_x = A4_MARGIN;
-                current_y += target_size;
+        let mut booklet_qrs = vec![];
+        let checksum_qr_fraction = match options.layout {
+            MainDocumentLayout::Portrait => {
+                let size = (page_width - A4_MARGIN * 2.0) / 3.0;
+                current_y += qr_grid(
+                    &current_layer,
+                    page_height - current_y,
+                    A4_MARGIN,
+                    (3, 3),
+                    size,
+                    data_qrs,
+                );
+                0.18
             }
-        }
-        if data_qr_refs.next().is_some() {
-            return Err(Error::TooManyCodes(
-                "only 9 codes allowed in this version of paperback".to_string(),
-            ));
-        }
+            MainDocumentLayout::Landscape => {
+                // Leave enough room below the grid for the checksum section.
+                let checksum_height = page_width * LANDSCAPE_CHECKSUM_QR_FRACTION;
+                let reserved = current_y + Mm(14.0) + checksum_height + A4_MARGIN;
+                // Can't use std::cmp::min sadly.
+                let (column_size, row_size) = (
+                    (page_width - A4_MARGIN * 2.0) / 5.0,
+                    (page_height - reserved) / 2.0,
+                );
+                let size = if column_size < row_size {
+                    column_size
+                } else {
+                    row_size
+                };
+                current_y += qr_grid(
+                    &current_layer,
+                    page_height - current_y,
+                    A4_MARGIN,
+                    (5, 2),
+                    size,
+                    data_qrs,
+                );
+                LANDSCAPE_CHECKSUM_QR_FRACTION
+            }
+            MainDocumentLayout::Booklet => {
+                booklet_qrs = data_qrs;
+                0.18
+            }
+        };
 
         current_y += banner(
             &current_layer,
-            A4_HEIGHT - current_y,
-            (A4_WIDTH, A4_MARGIN, Mm(3.0)),
+            page_height - current_y,
+            (page_width, A4_MARGIN, Mm(3.0)),
             Text {
-                inner: "② Checksum",
+                inner: strings.checksum_banner,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(10.0),
             },
             Some(Text {
-                inner: "Verifies the document was scanned correctly. The last 8 characters are the document identifier.",
+                inner: strings.main_document_checksum_description,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(8.0),
             }),
             colours::MAIN_DOCUMENT_TRIM,
         ) + Mm(2.0);
 
         // Document checksum.
-        current_y += qr_with_fallback(
+        qr_with_fallback(
             &current_layer,
-            A4_HEIGHT - current_y,
-            (A4_WIDTH, A4_MARGIN, 0.18),
-            self.checksum().to_bytes(),
-            &monospace_font,
+            page_height - current_y,
+            (page_width, A4_MARGIN, checksum_qr_fraction),
+            options,
+            QrData::Single(self.checksum().to_bytes()),
+            &fonts.monospace,
             10.0,
         )?;
 
+        // With the booklet layout, each QR code gets its own page so that it
+        // can be printed as large as possible.
+        for (idx, qr) in booklet_qrs.into_iter().enumerate() {
+            let current_layer = add_page(&doc, page_width, page_height);
+
+            let mut current_y = A4_MARGIN;
+            let caption = (strings.booklet_caption)(idx + 1, num_codes);
+            current_y += banner(
+                &current_layer,
+                page_height - current_y,
+                (page_width, A4_MARGIN, Mm(3.0)),
+                Text {
+                    inner: &caption,
+                    colour: colours::WHITE,
+                    font: &fonts.text,
+                    font_size: Pt(10.0),
+                },
+                Some(Text {
+                    inner: &(strings.booklet_description)(&self.id()),
+                    colour: colours::WHITE,
+                    font: &fonts.text,
+                    font_size: Pt(8.0),
+                }),
+                colours::MAIN_DOCUMENT_TRIM,
+            ) + Mm(2.0);
+
+            scaled_qr(
+                &current_layer,
+                qr,
+                (A4_MARGIN, page_height - current_y),
+                page_width - A4_MARGIN * 2.0,
+            );
+        }
+
+        let doc = with_metadata(
+            doc,
+            format!("paperback-v0 main document {}", self.id()),
+            &self.id(),
+            self.created(),
+        );
+
+        // Store a machine-readable copy of the document for FromPdf.
+        let doc = doc.with_keywords(vec![format!(
+            "{}{}",
+            PDF_KEYWORD_MAIN_DOCUMENT,
+            self.to_wire_multibase(Base::Base32Z)
+        )]);
+
         doc.check_for_errors()?;
         Ok(doc)
     }
 }
 
-const A5_WIDTH: Mm = Mm(148.0);
-const A5_HEIGHT: Mm = Mm(210.0);
-const A5_MARGIN: Mm = Mm(5.0);
+impl ToPdf for Manifest {
+    #[instrument(name = "manifest_pdf", skip_all)]
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error> {
+        const ROW_HEIGHT: Mm = Mm(10.0);
 
-const SCISSORS_SVG: &str = include_str!("scissors.svg");
-
-impl ToPdf for (&EncryptedKeyShard, &KeyShardCodewords) {
-    fn to_pdf(&self) -> Result<PdfDocumentReference, Error> {
-        let (shard, codewords) = self;
-        // TODO: Make this nicer. It's quite ugly we need to decrypt the shard
-        // here just to get the document and shard ids. If we cached them that
-        // would work, but if you just read the shard data from the user you
-        // wouldn't have this information without decrypting it.
-        let decrypted_shard = shard
-            .decrypt(codewords)
-            .map_err(|err| Error::OtherError(format!("failed to decrypt shard: {:?}", err)))?;
-
-        // Construct an A5 PDF.
-        let (doc, page1, layer1) = PdfDocument::new(
-            format!(
-                "Paperback Key Shard {}/{}",
-                decrypted_shard.document_id(),
-                decrypted_shard.id()
-            ),
-            A5_WIDTH,
-            A5_HEIGHT,
-            "Layer 1",
-        );
+        let strings = options.language.strings();
 
-        let monospace_font = doc.add_external_font(FONT_B612MONO)?;
-        let monospace_bold_font = doc.add_external_font(FONT_B612MONO_BOLD)?;
-        let text_font = doc.add_external_font(FONT_ROBOTOSLAB)?;
+        let (doc, mut current_layer, fonts) = new_document(
+            format!("Paperback Shard Manifest {}", self.document_id()),
+            A4_WIDTH,
+            A4_HEIGHT,
+        )?;
 
-        let current_page = doc.get_page(page1);
-        let current_layer = current_page.get_layer(layer1);
+        let mut current_y = A4_MARGIN + Pt(10.0).into();
 
-        let mut current_y = A5_MARGIN + Pt(10.0).into();
+        let timestamp_details = timestamp_details(strings, self.created(), self.expires());
 
         // Header.
         current_layer.begin_text_section();
@@ -558,270 +819,1204 @@ impl ToPdf for (&EncryptedKeyShard, &KeyShardCodewords) {
             current_layer.set_word_spacing(1.2);
             current_layer.set_character_spacing(1.0);
 
-            current_layer.set_text_cursor(A5_MARGIN, A5_HEIGHT - current_y);
+            current_layer.set_text_cursor(A4_MARGIN, A4_HEIGHT - current_y);
 
-            // "Shard".
-            current_layer.set_font(&text_font, 10.0);
+            // "Document".
+            current_layer.set_font(&fonts.text, 10.0);
             current_layer.set_fill_color(colours::GREY);
-            current_layer.write_text("Shard", &text_font);
+            current_layer.write_text(strings.document, &fonts.text);
             current_layer.set_fill_color(colours::BLACK);
             current_layer.set_line_height(20.0 + 2.0);
             current_layer.add_line_break();
-            // <shard id>
-            current_layer.set_font(&monospace_font, 20.0);
-            current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
-            current_layer.write_text(decrypted_shard.id(), &monospace_font);
+            // <document id>
+            current_layer.set_font(&fonts.monospace, 20.0);
+            current_layer.set_fill_color(colours::MANIFEST_TRIM);
+            current_layer.write_text(self.document_id(), &fonts.monospace);
             current_layer.set_fill_color(colours::BLACK);
-            current_layer.set_line_height(14.0 + 2.0);
+            current_layer.set_line_height(10.0 + 2.0);
+
+            current_layer.add_line_break();
             current_layer.add_line_break();
 
-            // "Document".
-            current_layer.set_font(&text_font, 10.0);
-            current_layer.set_fill_color(colours::GREY);
-            current_layer.write_text("Document", &text_font);
-            current_layer.set_fill_color(colours::BLACK);
-            current_layer.set_line_height(20.0 + 2.0);
+            // Details.
+            current_layer.set_font(&fonts.text, 10.0);
+            for line in strings.manifest_details {
+                current_layer.write_text(line, &fonts.text);
+                current_layer.add_line_break();
+            }
+            current_layer.write_text(
+                (strings.manifest_quorum)(self.quorum_size(), self.shard_ids().len()),
+                &fonts.text,
+            );
             current_layer.add_line_break();
-            // <document id>
-            current_layer.set_font(&monospace_font, 20.0);
-            current_layer.set_fill_color(colours::MAIN_DOCUMENT_TRIM);
-            current_layer.write_text(decrypted_shard.document_id(), &monospace_font);
-            current_layer.set_fill_color(colours::BLACK);
+            current_layer.write_text(
+                (strings.manifest_checksum)(self.document_checksum()),
+                &fonts.text,
+            );
+            if let Some(details) = &timestamp_details {
+                current_layer.add_line_break();
+                current_layer.write_text(details, &fonts.text);
+            }
         }
         current_layer.end_text_section();
         current_layer.begin_text_section();
         {
             // Header. TODO: Right-align this text.
             current_layer.set_text_cursor(
-                A5_WIDTH - (A5_MARGIN + (Pt(15.0) * 8.0).into()),
-                A5_HEIGHT - (current_y + Pt(10.0).into()),
+                A4_WIDTH
+                    - (A4_MARGIN + header_width(strings.shard_manifest, 20.0, Pt(15.0) * 12.0)),
+                A4_HEIGHT - (current_y + Pt(10.0).into()),
             );
-            current_layer.set_font(&text_font, 20.0);
-            current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
-            current_layer.write_text("Key Shard", &text_font);
+            current_layer.set_font(&fonts.text, 20.0);
+            current_layer.set_fill_color(colours::MANIFEST_TRIM);
+            current_layer.write_text(strings.shard_manifest, &fonts.text);
             current_layer.set_fill_color(colours::BLACK);
             current_layer.set_line_height(10.0 + 2.0);
             current_layer.add_line_break();
 
-            current_layer.set_font(&monospace_font, 10.0);
+            current_layer.set_font(&fonts.monospace, 10.0);
+            current_layer.set_fill_color(colours::GREY);
+            current_layer.write_text("paperback-v0", &fonts.monospace);
+            current_layer.set_fill_color(colours::BLACK);
+        }
+        current_layer.end_text_section();
+        let details_lines = 5.0 + timestamp_details.iter().count() as f32;
+        current_y += (Pt(22.0) + Pt(12.0) * details_lines).into();
+
+        current_y += banner(
+            &current_layer,
+            A4_HEIGHT - current_y,
+            (A4_WIDTH, A4_MARGIN, Mm(3.0)),
+            Text {
+                inner: strings.manifest_banner,
+                colour: colours::WHITE,
+                font: &fonts.text,
+                font_size: Pt(10.0),
+            },
+            Some(Text {
+                inner: strings.manifest_description,
+                colour: colours::WHITE,
+                font: &fonts.text,
+                font_size: Pt(8.0),
+            }),
+            colours::MANIFEST_TRIM,
+        );
+
+        for shard_id in self.shard_ids() {
+            // Continue on a new page if we run out of space.
+            if current_y + ROW_HEIGHT > A4_HEIGHT - A4_MARGIN {
+                current_layer = add_page(&doc, A4_WIDTH, A4_HEIGHT);
+                current_y = A4_MARGIN;
+            }
+            current_y += ROW_HEIGHT;
+
+            current_layer.begin_text_section();
+            {
+                current_layer.set_word_spacing(1.2);
+                current_layer.set_character_spacing(1.0);
+                current_layer.set_text_cursor(A4_MARGIN, A4_HEIGHT - current_y);
+
+                current_layer.set_font(&fonts.monospace, 12.0);
+                current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
+                current_layer.write_text(shard_id, &fonts.monospace);
+
+                current_layer.set_font(&fonts.text, 10.0);
+                current_layer.set_fill_color(colours::GREY);
+                current_layer.write_text(
+                    format!(
+                        "    {}: ______________________________    {}: ______________",
+                        strings.held_by, strings.date
+                    ),
+                    &fonts.text,
+                );
+            }
+            current_layer.end_text_section();
+        }
+
+        let doc = with_metadata(
+            doc,
+            format!("paperback-v0 shard manifest {}", self.document_id()),
+            self.document_id(),
+            self.created(),
+        );
+
+        doc.check_for_errors()?;
+        Ok(doc)
+    }
+}
+
+impl ToPdf for ActivityRecord {
+    #[instrument(name = "activity_record_pdf", skip_all)]
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error> {
+        const ROW_HEIGHT: Mm = Mm(8.0);
+
+        let strings = options.language.strings();
+
+        let (doc, mut current_layer, fonts) = new_document(
+            format!("Paperback Activity Record {}", self.document_id()),
+            A4_WIDTH,
+            A4_HEIGHT,
+        )?;
+
+        let mut current_y = A4_MARGIN + Pt(10.0).into();
+
+        let minted_details = self
+            .minted()
+            .map(|minted| (strings.activity_minted)(&format_date(minted)));
+
+        // Header.
+        current_layer.begin_text_section();
+        {
+            current_layer.set_word_spacing(1.2);
+            current_layer.set_character_spacing(1.0);
+
+            current_layer.set_text_cursor(A4_MARGIN, A4_HEIGHT - current_y);
+
+            // "Document".
+            current_layer.set_font(&fonts.text, 10.0);
             current_layer.set_fill_color(colours::GREY);
-            current_layer.write_text("paperback-v0", &monospace_font);
+            current_layer.write_text(strings.document, &fonts.text);
             current_layer.set_fill_color(colours::BLACK);
+            current_layer.set_line_height(20.0 + 2.0);
+            current_layer.add_line_break();
+            // <document id>
+            current_layer.set_font(&fonts.monospace, 20.0);
+            current_layer.set_fill_color(colours::MANIFEST_TRIM);
+            current_layer.write_text(self.document_id(), &fonts.monospace);
+            current_layer.set_fill_color(colours::BLACK);
+            current_layer.set_line_height(10.0 + 2.0);
+
+            current_layer.add_line_break();
+            current_layer.add_line_break();
+
+            // Details.
+            current_layer.set_font(&fonts.text, 10.0);
+            for line in strings.activity_details {
+                current_layer.write_text(line, &fonts.text);
+                current_layer.add_line_break();
+            }
+            if let Some(details) = &minted_details {
+                current_layer.write_text(details, &fonts.text);
+                current_layer.add_line_break();
+            }
+            current_layer.write_text(
+                (strings.activity_quorum)(&self.quorum().join(" ")),
+                &fonts.text,
+            );
         }
         current_layer.end_text_section();
         current_layer.begin_text_section();
         {
+            // Header. TODO: Right-align this text.
             current_layer.set_text_cursor(
-                A5_MARGIN + Mm(45.0),
-                A5_HEIGHT - (current_y + Pt(12.0 + 20.0 * 2.0 + 16.0 - 12.0 * 2.0).into()),
+                A4_WIDTH
+                    - (A4_MARGIN + header_width(strings.activity_record, 20.0, Pt(15.0) * 12.0)),
+                A4_HEIGHT - (current_y + Pt(10.0).into()),
             );
-
-            // Details.
-            current_layer.set_font(&text_font, 10.0);
+            current_layer.set_font(&fonts.text, 20.0);
+            current_layer.set_fill_color(colours::MANIFEST_TRIM);
+            current_layer.write_text(strings.activity_record, &fonts.text);
+            current_layer.set_fill_color(colours::BLACK);
             current_layer.set_line_height(10.0 + 2.0);
-            current_layer.write_text("This is a key shard of a paperback backup.", &text_font);
             current_layer.add_line_break();
-            current_layer.write_text("See cyphar.com/paperback for more details.", &text_font);
+
+            current_layer.set_font(&fonts.monospace, 10.0);
+            current_layer.set_fill_color(colours::GREY);
+            current_layer.write_text("paperback-v0", &fonts.monospace);
+            current_layer.set_fill_color(colours::BLACK);
         }
         current_layer.end_text_section();
-        current_y += Mm(25.0);
+        let details_lines = 4.0 + minted_details.iter().count() as f32;
+        current_y += (Pt(22.0) + Pt(12.0) * details_lines).into();
 
+        // Signed copy of the record.
         current_y += banner(
             &current_layer,
-            A5_HEIGHT - current_y,
-            (A5_WIDTH, A5_MARGIN, Mm(1.0)),
+            A4_HEIGHT - current_y,
+            (A4_WIDTH, A4_MARGIN, Mm(3.0)),
             Text {
-                inner: "① Shard",
+                inner: strings.record_banner,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(10.0),
             },
             Some(Text {
-                inner: "Key shard data, encrypted using the codewords.",
+                inner: strings.record_description,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(8.0),
             }),
-            colours::KEY_SHARD_TRIM,
+            colours::MANIFEST_TRIM,
         );
-
         current_y += qr_with_fallback(
             &current_layer,
-            A5_HEIGHT - current_y,
-            (A5_WIDTH, A5_MARGIN, 0.3),
-            shard.to_wire(),
-            &monospace_font,
-            8.0,
+            A4_HEIGHT - current_y,
+            (A4_WIDTH, A4_MARGIN, 0.3),
+            options,
+            QrData::Single(self.to_wire()),
+            &fonts.monospace,
+            10.0,
         )?;
 
         current_y += banner(
             &current_layer,
-            A5_HEIGHT - current_y,
-            (A5_WIDTH, A5_MARGIN, Mm(1.0)),
+            A4_HEIGHT - current_y,
+            (A4_WIDTH, A4_MARGIN, Mm(3.0)),
             Text {
-                inner: "② Checksum",
+                inner: strings.activity_banner,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(10.0),
             },
             Some(Text {
-                inner: "Verifies the key shard was scanned correctly.",
+                inner: strings.activity_description,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(8.0),
             }),
-            colours::KEY_SHARD_TRIM,
+            colours::MANIFEST_TRIM,
         );
 
-        current_y += qr_with_fallback(
-            &current_layer,
-            A5_HEIGHT - current_y,
-            (A5_WIDTH, A5_MARGIN, 0.3),
-            shard.checksum().to_bytes(),
-            &monospace_font,
-            8.0,
+        for shard_id in self.shard_ids() {
+            // Continue on a new page if we run out of space.
+            if current_y + ROW_HEIGHT > A4_HEIGHT - A4_MARGIN {
+                current_layer = add_page(&doc, A4_WIDTH, A4_HEIGHT);
+                current_y = A4_MARGIN;
+            }
+            current_y += ROW_HEIGHT;
+
+            current_layer.begin_text_section();
+            {
+                current_layer.set_word_spacing(1.2);
+                current_layer.set_character_spacing(1.0);
+                current_layer.set_text_cursor(A4_MARGIN, A4_HEIGHT - current_y);
+
+                current_layer.set_font(&fonts.monospace, 12.0);
+                current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
+                current_layer.write_text(shard_id, &fonts.monospace);
+            }
+            current_layer.end_text_section();
+        }
+
+        let doc = with_metadata(
+            doc,
+            format!("paperback-v0 activity record {}", self.document_id()),
+            self.document_id(),
+            self.minted(),
+        );
+
+        // Store a machine-readable copy of the record for FromPdf.
+        let doc = doc.with_keywords(vec![format!(
+            "{}{}",
+            PDF_KEYWORD_ACTIVITY_RECORD,
+            self.to_wire_multibase(Base::Base32Z)
+        )]);
+
+        doc.check_for_errors()?;
+        Ok(doc)
+    }
+}
+
+impl ToPdf for InstructionsDocument {
+    #[instrument(name = "instructions_pdf", skip_all)]
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error> {
+        const ROW_HEIGHT: Mm = Mm(10.0);
+
+        let strings = options.language.strings();
+
+        let (doc, mut current_layer, fonts) = new_document(
+            format!("Paperback Recovery Instructions {}", self.document_id()),
+            A4_WIDTH,
+            A4_HEIGHT,
         )?;
 
-        // "Cut here" line.
-        {
-            let scissors_svg = Svg::parse(SCISSORS_SVG)?;
-            let scissors_svg_ref = scissors_svg.into_xobject(&current_layer);
+        let mut current_y = A4_MARGIN + Pt(10.0).into();
 
-            // For scissors, scale to the target height.
-            let target_height = Mm(5.0);
-            let scale = target_height / Mm::from(scissors_svg_ref.height.into_pt(SVG_DPI));
+        let timestamp_details = timestamp_details(strings, self.created(), self.expires());
 
-            // Dashed line.
-            let line = Line::from_iter(vec![
-                (
-                    Point::new(Mm(0.0), A5_HEIGHT - (current_y + target_height / 2.0)),
-                    false,
-                ),
-                (
-                    Point::new(A5_WIDTH, A5_HEIGHT - (current_y + target_height / 2.0)),
-                    false,
-                ),
-            ]);
+        // Header.
+        current_layer.begin_text_section();
+        {
+            current_layer.set_word_spacing(1.2);
+            current_layer.set_character_spacing(1.0);
 
-            let dash_pattern = LineDashPattern {
-                dash_1: Some(6),
-                gap_1: Some(4),
-                ..LineDashPattern::default()
-            };
+            current_layer.set_text_cursor(A4_MARGIN, A4_HEIGHT - current_y);
 
-            current_layer.set_outline_color(colours::KEY_SHARD_TRIM);
-            current_layer.set_line_dash_pattern(dash_pattern);
-            current_layer.add_line(line);
+            // "Document".
+            current_layer.set_font(&fonts.text, 10.0);
+            current_layer.set_fill_color(colours::GREY);
+            current_layer.write_text(strings.document, &fonts.text);
+            current_layer.set_fill_color(colours::BLACK);
+            current_layer.set_line_height(20.0 + 2.0);
+            current_layer.add_line_break();
+            // <document id>
+            current_layer.set_font(&fonts.monospace, 20.0);
+            current_layer.set_fill_color(colours::MANIFEST_TRIM);
+            current_layer.write_text(self.document_id(), &fonts.monospace);
+            current_layer.set_fill_color(colours::BLACK);
+            current_layer.set_line_height(10.0 + 2.0);
 
-            // Scissors.
-            scissors_svg_ref.add_to_layer(
-                &current_layer,
-                SvgTransform {
-                    translate_x: Some(A5_MARGIN.into()),
-                    translate_y: Some((A5_HEIGHT - (current_y + target_height)).into()),
-                    scale_x: Some(scale),
-                    scale_y: Some(scale),
-                    ..Default::default()
-                },
+            current_layer.add_line_break();
+            current_layer.add_line_break();
+
+            // Details.
+            current_layer.set_font(&fonts.text, 10.0);
+            for line in strings.recovery_details {
+                current_layer.write_text(line, &fonts.text);
+                current_layer.add_line_break();
+            }
+            if let Some(title) = self.title() {
+                current_layer.write_text((strings.main_document_title)(title), &fonts.text);
+                current_layer.add_line_break();
+            }
+            current_layer.write_text((strings.recovery_quorum)(self.quorum_size()), &fonts.text);
+            current_layer.add_line_break();
+            current_layer.write_text(
+                (strings.manifest_checksum)(self.document_checksum()),
+                &fonts.text,
             );
-            current_y += target_height;
+            if let Some(details) = &timestamp_details {
+                current_layer.add_line_break();
+                current_layer.write_text(details, &fonts.text);
+            }
         }
+        current_layer.end_text_section();
+        current_layer.begin_text_section();
+        {
+            // Header. TODO: Right-align this text.
+            current_layer.set_text_cursor(
+                A4_WIDTH
+                    - (A4_MARGIN
+                        + header_width(strings.recovery_instructions, 20.0, Pt(15.0) * 12.0)),
+                A4_HEIGHT - (current_y + Pt(10.0).into()),
+            );
+            current_layer.set_font(&fonts.text, 20.0);
+            current_layer.set_fill_color(colours::MANIFEST_TRIM);
+            current_layer.write_text(strings.recovery_instructions, &fonts.text);
+            current_layer.set_fill_color(colours::BLACK);
+            current_layer.set_line_height(10.0 + 2.0);
+            current_layer.add_line_break();
+
+            current_layer.set_font(&fonts.monospace, 10.0);
+            current_layer.set_fill_color(colours::GREY);
+            current_layer.write_text("paperback-v0", &fonts.monospace);
+            current_layer.set_fill_color(colours::BLACK);
+        }
+        current_layer.end_text_section();
+        let details_lines =
+            5.0 + self.title().iter().count() as f32 + timestamp_details.iter().count() as f32;
+        current_y += (Pt(22.0) + Pt(12.0) * details_lines).into();
 
         current_y += banner(
             &current_layer,
-            A5_HEIGHT - current_y,
-            (A5_WIDTH, A5_MARGIN, Mm(1.0)),
+            A4_HEIGHT - current_y,
+            (A4_WIDTH, A4_MARGIN, Mm(3.0)),
             Text {
-                inner: "③ Codewords",
+                inner: strings.holders_banner,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(10.0),
             },
             Some(Text {
-                inner: "Encrypts the key shard data. Can be optionally cut off.",
+                inner: strings.holders_description,
                 colour: colours::WHITE,
-                font: &text_font,
+                font: &fonts.text,
                 font_size: Pt(8.0),
             }),
-            colours::KEY_SHARD_TRIM,
+            colours::MANIFEST_TRIM,
         );
 
-        current_y = A5_HEIGHT - Mm(30.0);
+        // If no holders were listed, leave enough blank rows for them to be
+        // written in by hand.
+        let num_rows = self
+            .shard_holders()
+            .len()
+            .max(self.quorum_size().max(3) as usize);
+        for idx in 0..num_rows {
+            // Continue on a new page if we run out of space.
+            if current_y + ROW_HEIGHT > A4_HEIGHT - A4_MARGIN {
+                current_layer = add_page(&doc, A4_WIDTH, A4_HEIGHT);
+                current_y = A4_MARGIN;
+            }
+            current_y += ROW_HEIGHT;
+
+            let holder = self
+                .shard_holders()
+                .get(idx)
+                .map(String::as_str)
+                .unwrap_or("______________________________");
+
+            current_layer.begin_text_section();
+            {
+                current_layer.set_word_spacing(1.2);
+                current_layer.set_character_spacing(1.0);
+                current_layer.set_text_cursor(A4_MARGIN, A4_HEIGHT - current_y);
+
+                current_layer.set_font(&fonts.text, 12.0);
+                current_layer.write_text(format!("{}. {}", idx + 1, holder), &fonts.text);
+
+                current_layer.set_font(&fonts.text, 10.0);
+                current_layer.set_fill_color(colours::GREY);
+                current_layer.write_text(
+                    format!("    {}: ______________", strings.contacted),
+                    &fonts.text,
+                );
+                current_layer.set_fill_color(colours::BLACK);
+            }
+            current_layer.end_text_section();
+        }
 
-        // Shard codewords.
-        current_layer.begin_text_section();
-        {
-            current_layer.set_word_spacing(1.2);
-            current_layer.set_character_spacing(1.0);
-            current_layer.set_text_cursor(A5_MARGIN, A5_HEIGHT - current_y);
+        for (heading, lines) in strings.recovery_steps {
+            let height = Mm(15.0) + Pt((10.0 + 4.0) * lines.len() as f32 + 4.0).into();
+            // Continue on a new page if we run out of space.
+            if current_y + height > A4_HEIGHT - A4_MARGIN {
+                current_layer = add_page(&doc, A4_WIDTH, A4_HEIGHT);
+                current_y = A4_MARGIN;
+            }
 
-            // "Shard".
-            current_layer.set_font(&text_font, 10.0);
-            current_layer.set_fill_color(colours::GREY);
-            current_layer.write_text("Shard", &text_font);
-            current_layer.set_fill_color(colours::BLACK);
-            current_layer.set_line_height(20.0 + 2.0);
+            current_y += banner(
+                &current_layer,
+                A4_HEIGHT - current_y,
+                (A4_WIDTH, A4_MARGIN, Mm(6.0)),
+                Text {
+                    inner: heading,
+                    colour: colours::WHITE,
+                    font: &fonts.text,
+                    font_size: Pt(10.0),
+                },
+                None,
+                colours::MANIFEST_TRIM,
+            );
+
+            current_layer.begin_text_section();
+            {
+                current_layer.set_font(&fonts.text, 10.0);
+                current_layer.set_line_height(10.0 + 4.0);
+                current_layer.set_text_cursor(A4_MARGIN, A4_HEIGHT - (current_y + Pt(14.0).into()));
+                for (idx, line) in lines.iter().enumerate() {
+                    if idx > 0 {
+                        current_layer.add_line_break();
+                    }
+                    current_layer.write_text(*line, &fonts.text);
+                }
+            }
+            current_layer.end_text_section();
+            current_y += Pt((10.0 + 4.0) * lines.len() as f32 + 4.0).into();
+        }
+
+        let doc = with_metadata(
+            doc,
+            format!("paperback-v0 recovery instructions {}", self.document_id()),
+            self.document_id(),
+            self.created(),
+        );
+
+        doc.check_for_errors()?;
+        Ok(doc)
+    }
+}
+
+pub(super) const A5_WIDTH: Mm = Mm(148.0);
+pub(super) const A5_HEIGHT: Mm = Mm(210.0);
+pub(super) const A5_MARGIN: Mm = Mm(5.0);
+
+pub(super) const SCISSORS_SVG: &str = include_str!("scissors.svg");
+pub(super) const CODEWORD_QR_SIZE: Mm = Mm(20.0);
+pub(super) const RECOVERY_URL_QR_SIZE: Mm = Mm(12.0);
+
+/// Draw a single key shard (laid out for an A5 page) onto the layer.
+fn draw_key_shard(
+    current_layer: &PdfLayerReference,
+    fonts: &Fonts,
+    (shard, decrypted_shard, codewords): (&EncryptedKeyShard, &KeyShard, &KeyShardCodewords),
+    provenance: Option<&ShardProvenance>,
+    options: &PdfOptions,
+) -> Result<(), Error> {
+    let strings = options.language.strings();
+    let mut current_y = A5_MARGIN + Pt(10.0).into();
+
+    // Header.
+    current_layer.begin_text_section();
+    {
+        current_layer.set_word_spacing(1.2);
+        current_layer.set_character_spacing(1.0);
+
+        current_layer.set_text_cursor(A5_MARGIN, A5_HEIGHT - current_y);
+
+        // "Shard" (with the friendly shard id, which is easier to read out).
+        current_layer.set_font(&fonts.text, 10.0);
+        current_layer.set_fill_color(colours::GREY);
+        current_layer.write_text(strings.shard, &fonts.text);
+        current_layer.set_font(&fonts.monospace, 8.0);
+        current_layer.write_text(
+            format!("  {}", decrypted_shard.friendly_id()),
+            &fonts.monospace,
+        );
+        current_layer.set_fill_color(colours::BLACK);
+        current_layer.set_line_height(20.0 + 2.0);
+        current_layer.add_line_break();
+        // <shard id>
+        current_layer.set_font(&fonts.monospace, 20.0);
+        current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
+        current_layer.write_text(decrypted_shard.id(), &fonts.monospace);
+        current_layer.set_fill_color(colours::BLACK);
+        current_layer.set_line_height(14.0 + 2.0);
+        current_layer.add_line_break();
+
+        // "Document".
+        current_layer.set_font(&fonts.text, 10.0);
+        current_layer.set_fill_color(colours::GREY);
+        current_layer.write_text(strings.document, &fonts.text);
+        current_layer.set_fill_color(colours::BLACK);
+        current_layer.set_line_height(20.0 + 2.0);
+        current_layer.add_line_break();
+        // <document id>
+        current_layer.set_font(&fonts.monospace, 20.0);
+        current_layer.set_fill_color(colours::MAIN_DOCUMENT_TRIM);
+        current_layer.write_text(decrypted_shard.document_id(), &fonts.monospace);
+        current_layer.set_fill_color(colours::BLACK);
+    }
+    current_layer.end_text_section();
+    current_layer.begin_text_section();
+    {
+        // Header. TODO: Right-align this text.
+        current_layer.set_text_cursor(
+            A5_WIDTH - (A5_MARGIN + header_width(strings.key_shard, 20.0, Pt(15.0) * 8.0)),
+            A5_HEIGHT - (current_y + Pt(10.0).into()),
+        );
+        current_layer.set_font(&fonts.text, 20.0);
+        current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
+        current_layer.write_text(strings.key_shard, &fonts.text);
+        current_layer.set_fill_color(colours::BLACK);
+        current_layer.set_line_height(10.0 + 2.0);
+        current_layer.add_line_break();
+
+        current_layer.set_font(&fonts.monospace, 10.0);
+        current_layer.set_fill_color(colours::GREY);
+        current_layer.write_text("paperback-v0", &fonts.monospace);
+        current_layer.set_fill_color(colours::BLACK);
+    }
+    current_layer.end_text_section();
+    current_layer.begin_text_section();
+    {
+        current_layer.set_text_cursor(
+            A5_MARGIN + Mm(45.0),
+            A5_HEIGHT - (current_y + Pt(12.0 + 20.0 * 2.0 + 16.0 - 12.0 * 2.0).into()),
+        );
+
+        // Details.
+        current_layer.set_font(&fonts.text, 10.0);
+        current_layer.set_line_height(10.0 + 2.0);
+        for (idx, line) in strings.key_shard_details.iter().enumerate() {
+            if idx > 0 {
+                current_layer.add_line_break();
+            }
+            current_layer.write_text(*line, &fonts.text);
+        }
+        if let Some(details) = timestamp_details(
+            strings,
+            decrypted_shard.created(),
+            decrypted_shard.expires(),
+        ) {
             current_layer.add_line_break();
-            // <shard id>
-            current_layer.set_font(&monospace_font, 20.0);
-            current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
-            current_layer.write_text(decrypted_shard.id(), &monospace_font);
-            current_layer.set_fill_color(colours::BLACK);
-            current_layer.set_line_height(12.0 + 2.0);
+            current_layer.write_text(details, &fonts.text);
+        }
+        if let Some(provenance) = provenance {
+            current_layer.set_font(&fonts.text, 8.0);
+            current_layer.set_line_height(8.0 + 2.0);
             current_layer.add_line_break();
+            current_layer.write_text(strings.provenance(provenance), &fonts.text);
+        }
+    }
+    current_layer.end_text_section();
+    current_y += Mm(25.0);
+    // Make room for the provenance (the codeword stub has room to spare).
+    if provenance.is_some() {
+        current_y += Pt(8.0 + 2.0).into();
+    }
 
-            // "Document".
-            current_layer.set_font(&text_font, 10.0);
+    current_y += banner(
+        current_layer,
+        A5_HEIGHT - current_y,
+        (A5_WIDTH, A5_MARGIN, Mm(1.0)),
+        Text {
+            inner: strings.shard_banner,
+            colour: colours::WHITE,
+            font: &fonts.text,
+            font_size: Pt(10.0),
+        },
+        Some(Text {
+            inner: strings.shard_description,
+            colour: colours::WHITE,
+            font: &fonts.text,
+            font_size: Pt(8.0),
+        }),
+        colours::KEY_SHARD_TRIM,
+    );
+
+    current_y += qr_with_fallback(
+        current_layer,
+        A5_HEIGHT - current_y,
+        (A5_WIDTH, A5_MARGIN, 0.3),
+        options,
+        QrData::KeyShard(PartType::KeyShardData, decrypted_shard, shard.to_wire()),
+        &fonts.monospace,
+        8.0,
+    )?;
+
+    current_y += banner(
+        current_layer,
+        A5_HEIGHT - current_y,
+        (A5_WIDTH, A5_MARGIN, Mm(1.0)),
+        Text {
+            inner: strings.checksum_banner,
+            colour: colours::WHITE,
+            font: &fonts.text,
+            font_size: Pt(10.0),
+        },
+        Some(Text {
+            inner: &(strings.shard_checksum_description)(&shard.fingerprint_words().join(" ")),
+            colour: colours::WHITE,
+            font: &fonts.text,
+            font_size: Pt(8.0),
+        }),
+        colours::KEY_SHARD_TRIM,
+    );
+
+    current_y += qr_with_fallback(
+        current_layer,
+        A5_HEIGHT - current_y,
+        (A5_WIDTH, A5_MARGIN, 0.3),
+        options,
+        QrData::KeyShard(
+            PartType::KeyShardChecksum,
+            decrypted_shard,
+            shard.checksum().to_bytes(),
+        ),
+        &fonts.monospace,
+        8.0,
+    )?;
+
+    // Recovery instructions URL, below the checksum fallback text (which is
+    // only a few lines long).
+    if let Some(url) = &options.recovery_url {
+        let bottom = A5_HEIGHT - current_y;
+        let qr_svg = Svg::parse(
+            &QrCode::with_error_correction_level(url, options.qr_error_correction.into())?
+                .render::<svg::Color>()
+                .build(),
+        )?
+        .into_xobject(current_layer);
+        let (scale_x, scale_y) = (
+            RECOVERY_URL_QR_SIZE / Mm::from(qr_svg.width.into_pt(SVG_DPI)),
+            RECOVERY_URL_QR_SIZE / Mm::from(qr_svg.height.into_pt(SVG_DPI)),
+        );
+        qr_svg.add_to_layer(
+            current_layer,
+            SvgTransform {
+                translate_x: Some((A5_WIDTH - A5_MARGIN - RECOVERY_URL_QR_SIZE).into()),
+                translate_y: Some(bottom.into()),
+                dpi: Some(SVG_DPI),
+                scale_x: Some(scale_x),
+                scale_y: Some(scale_y),
+                ..Default::default()
+            },
+        );
+
+        current_layer.begin_text_section();
+        {
+            // Line up with the checksum fallback text.
+            current_layer.set_text_cursor(
+                A5_MARGIN + A5_WIDTH * 0.3 + A5_MARGIN,
+                bottom + Pt(3.0 + 9.0).into(),
+            );
+            current_layer.set_font(&fonts.text, 7.0);
             current_layer.set_fill_color(colours::GREY);
-            current_layer.write_text("Document", &text_font);
-            current_layer.set_fill_color(colours::BLACK);
-            current_layer.set_line_height(20.0 + 2.0);
+            current_layer.write_text(strings.recovery_url, &fonts.text);
+            current_layer.set_line_height(7.0 + 2.0);
             current_layer.add_line_break();
-            // <document id>
-            current_layer.set_font(&monospace_font, 20.0);
-            current_layer.set_fill_color(colours::MAIN_DOCUMENT_TRIM);
-            current_layer.write_text(decrypted_shard.document_id(), &monospace_font);
+            current_layer.set_font(&fonts.monospace, 7.0);
             current_layer.set_fill_color(colours::BLACK);
+            current_layer.write_text(url, &fonts.monospace);
         }
         current_layer.end_text_section();
+    }
+
+    // Copy of the codewords kept with the key shard itself. There isn't much
+    // room left above the stub, so they are printed smaller than on the stub.
+    if options.codeword_placement.on_shard() {
+        const FONT_SIZE: f32 = 7.0;
+        const WORDS_PER_LINE: usize = 8;
+
+        current_y += Mm(3.0);
         current_layer.begin_text_section();
         {
             current_layer.set_word_spacing(1.2);
             current_layer.set_character_spacing(1.0);
-            current_layer.set_text_cursor(
-                A5_MARGIN + Mm(45.0),
-                A5_HEIGHT - (current_y + Pt(5.0).into()),
-            );
+            current_layer.set_text_cursor(A5_MARGIN, A5_HEIGHT - (current_y + Pt(8.0).into()));
 
-            // Codewords.
-            current_layer.set_font(&monospace_font, 10.0);
-            current_layer.set_line_height(10.0 + 5.0);
-            for (i, codeword) in codewords.iter().enumerate() {
-                let font = if i % 2 == 0 {
-                    current_layer.set_font(&monospace_font, 10.0);
-                    &monospace_font
-                } else {
-                    current_layer.set_font(&monospace_bold_font, 10.0);
-                    &monospace_bold_font
-                };
-                current_layer.write_text(codeword, font);
-                if i % 5 == 4 {
+            // "Codewords".
+            current_layer.set_font(&fonts.text, 8.0);
+            current_layer.set_fill_color(colours::GREY);
+            current_layer.write_text(strings.codewords, &fonts.text);
+            current_layer.set_fill_color(colours::BLACK);
+            current_layer.set_line_height(FONT_SIZE + 5.0);
+            current_layer.add_line_break();
+
+            write_codewords(current_layer, fonts, codewords, FONT_SIZE, WORDS_PER_LINE);
+        }
+        current_layer.end_text_section();
+        let lines = codewords.len().div_ceil(WORDS_PER_LINE);
+        current_y += Pt(8.0 + (FONT_SIZE + 5.0) * lines as f32).into();
+    }
+
+    // Without a stub there is nothing to cut off.
+    if !options.codeword_placement.on_stub() {
+        return Ok(());
+    }
+
+    // "Cut here" line.
+    {
+        let scissors_svg = Svg::parse(SCISSORS_SVG)?;
+        let scissors_svg_ref = scissors_svg.into_xobject(current_layer);
+
+        // For scissors, scale to the target height.
+        let target_height = Mm(5.0);
+        let scale = target_height / Mm::from(scissors_svg_ref.height.into_pt(SVG_DPI));
+
+        // Dashed line.
+        let line = Line::from_iter(vec![
+            (
+                Point::new(Mm(0.0), A5_HEIGHT - (current_y + target_height / 2.0)),
+                false,
+            ),
+            (
+                Point::new(A5_WIDTH, A5_HEIGHT - (current_y + target_height / 2.0)),
+                false,
+            ),
+        ]);
+
+        let dash_pattern = LineDashPattern {
+            dash_1: Some(6),
+            gap_1: Some(4),
+            ..LineDashPattern::default()
+        };
+
+        current_layer.set_outline_color(colours::KEY_SHARD_TRIM);
+        current_layer.set_line_dash_pattern(dash_pattern);
+        current_layer.add_line(line);
+
+        // Scissors.
+        scissors_svg_ref.add_to_layer(
+            current_layer,
+            SvgTransform {
+                translate_x: Some(A5_MARGIN.into()),
+                translate_y: Some((A5_HEIGHT - (current_y + target_height)).into()),
+                scale_x: Some(scale),
+                scale_y: Some(scale),
+                ..Default::default()
+            },
+        );
+        current_y += target_height;
+    }
+
+    current_y += banner(
+        current_layer,
+        A5_HEIGHT - current_y,
+        (A5_WIDTH, A5_MARGIN, Mm(1.0)),
+        Text {
+            inner: strings.codewords_banner,
+            colour: colours::WHITE,
+            font: &fonts.text,
+            font_size: Pt(10.0),
+        },
+        Some(Text {
+            inner: strings.codewords_description,
+            colour: colours::WHITE,
+            font: &fonts.text,
+            font_size: Pt(8.0),
+        }),
+        colours::KEY_SHARD_TRIM,
+    );
+
+    current_y = A5_HEIGHT - Mm(30.0);
+
+    // Shard codewords.
+    current_layer.begin_text_section();
+    {
+        current_layer.set_word_spacing(1.2);
+        current_layer.set_character_spacing(1.0);
+        current_layer.set_text_cursor(A5_MARGIN, A5_HEIGHT - current_y);
+
+        // "Shard".
+        current_layer.set_font(&fonts.text, 10.0);
+        current_layer.set_fill_color(colours::GREY);
+        current_layer.write_text(strings.shard, &fonts.text);
+        current_layer.set_fill_color(colours::BLACK);
+        current_layer.set_line_height(20.0 + 2.0);
+        current_layer.add_line_break();
+        // <shard id>
+        current_layer.set_font(&fonts.monospace, 20.0);
+        current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
+        current_layer.write_text(decrypted_shard.id(), &fonts.monospace);
+        current_layer.set_fill_color(colours::BLACK);
+        current_layer.set_line_height(12.0 + 2.0);
+        current_layer.add_line_break();
+
+        // "Document".
+        current_layer.set_font(&fonts.text, 10.0);
+        current_layer.set_fill_color(colours::GREY);
+        current_layer.write_text(strings.document, &fonts.text);
+        current_layer.set_fill_color(colours::BLACK);
+        current_layer.set_line_height(20.0 + 2.0);
+        current_layer.add_line_break();
+        // <document id>
+        current_layer.set_font(&fonts.monospace, 20.0);
+        current_layer.set_fill_color(colours::MAIN_DOCUMENT_TRIM);
+        current_layer.write_text(decrypted_shard.document_id(), &fonts.monospace);
+        current_layer.set_fill_color(colours::BLACK);
+    }
+    current_layer.end_text_section();
+    current_layer.begin_text_section();
+    {
+        current_layer.set_word_spacing(1.2);
+        current_layer.set_character_spacing(1.0);
+        current_layer.set_text_cursor(
+            A5_MARGIN + Mm(45.0),
+            A5_HEIGHT - (current_y + Pt(5.0).into()),
+        );
+
+        // Codewords. If there is a codeword QR code, the codewords need to be
+        // squeezed into fewer columns to leave room for it.
+        let (font_size, words_per_line) = if options.codeword_qr {
+            (8.0, 4)
+        } else {
+            (10.0, 5)
+        };
+        write_codewords(current_layer, fonts, codewords, font_size, words_per_line);
+    }
+    current_layer.end_text_section();
+
+    // Codeword QR code, in the bottom-right corner of the stub.
+    if options.codeword_qr {
+        let codewords_text = Zeroizing::new(codewords.join(" "));
+        let qr_svg = Svg::parse(
+            &QrCode::with_error_correction_level(
+                codewords_text.as_bytes(),
+                options.qr_error_correction.into(),
+            )?
+            .render::<svg::Color>()
+            .build(),
+        )?
+        .into_xobject(current_layer);
+        let (scale_x, scale_y) = (
+            CODEWORD_QR_SIZE / Mm::from(qr_svg.width.into_pt(SVG_DPI)),
+            CODEWORD_QR_SIZE / Mm::from(qr_svg.height.into_pt(SVG_DPI)),
+        );
+        qr_svg.add_to_layer(
+            current_layer,
+            SvgTransform {
+                translate_x: Some((A5_WIDTH - A5_MARGIN - CODEWORD_QR_SIZE).into()),
+                translate_y: Some(A5_MARGIN.into()),
+                dpi: Some(SVG_DPI),
+                scale_x: Some(scale_x),
+                scale_y: Some(scale_y),
+                ..Default::default()
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Write the codewords of a key shard into the current text section, alternating
+/// between regular and bold fonts to make it easier to keep track of which
+/// codeword is being read.
+fn write_codewords(
+    current_layer: &PdfLayerReference,
+    fonts: &Fonts,
+    codewords: &KeyShardCodewords,
+    font_size: f32,
+    words_per_line: usize,
+) {
+    current_layer.set_font(&fonts.monospace, font_size);
+    current_layer.set_line_height(font_size + 5.0);
+    for (i, codeword) in codewords.iter().enumerate() {
+        let font = if i % 2 == 0 {
+            current_layer.set_font(&fonts.monospace, font_size);
+            &fonts.monospace
+        } else {
+            current_layer.set_font(&fonts.monospace_bold, font_size);
+            &fonts.monospace_bold
+        };
+        current_layer.write_text(codeword, font);
+        if i % words_per_line == words_per_line - 1 {
+            current_layer.add_line_break();
+        } else {
+            current_layer.write_text(" ", font);
+        }
+    }
+}
+
+/// Draw the instructions for the holder of a key shard (laid out for an A5
+/// page) onto the layer.
+fn draw_key_shard_instructions(
+    current_layer: &PdfLayerReference,
+    fonts: &Fonts,
+    decrypted_shard: &KeyShard,
+    options: &PdfOptions,
+) {
+    let strings = options.language.strings();
+    let mut current_y = A5_MARGIN + Pt(20.0).into();
+
+    // Header.
+    current_layer.begin_text_section();
+    {
+        current_layer.set_word_spacing(1.2);
+        current_layer.set_character_spacing(1.0);
+
+        current_layer.set_text_cursor(A5_MARGIN, A5_HEIGHT - current_y);
+
+        current_layer.set_font(&fonts.text, 20.0);
+        current_layer.set_fill_color(colours::KEY_SHARD_TRIM);
+        current_layer.write_text(strings.instructions_title, &fonts.text);
+        current_layer.set_fill_color(colours::BLACK);
+        current_layer.set_line_height(14.0 + 2.0);
+        current_layer.add_line_break();
+
+        current_layer.set_font(&fonts.text, 10.0);
+        current_layer.write_text(
+            (strings.instructions_shard)(&decrypted_shard.id(), &decrypted_shard.document_id()),
+            &fonts.text,
+        );
+    }
+    current_layer.end_text_section();
+    current_y += Pt(14.0 + 2.0).into();
+
+    for (heading, lines) in strings.instructions {
+        current_y += banner(
+            current_layer,
+            A5_HEIGHT - current_y,
+            (A5_WIDTH, A5_MARGIN, Mm(6.0)),
+            Text {
+                inner: heading,
+                colour: colours::WHITE,
+                font: &fonts.text,
+                font_size: Pt(10.0),
+            },
+            None,
+            colours::KEY_SHARD_TRIM,
+        );
+
+        current_layer.begin_text_section();
+        {
+            current_layer.set_font(&fonts.text, 10.0);
+            current_layer.set_line_height(10.0 + 4.0);
+            current_layer.set_text_cursor(A5_MARGIN, A5_HEIGHT - (current_y + Pt(14.0).into()));
+            for (idx, line) in lines.iter().enumerate() {
+                if idx > 0 {
                     current_layer.add_line_break();
-                } else {
-                    current_layer.write_text(" ", font);
                 }
+                current_layer.write_text(*line, &fonts.text);
             }
         }
         current_layer.end_text_section();
+        current_y += Pt((10.0 + 4.0) * lines.len() as f32 + 4.0).into();
+    }
+}
 
-        doc.check_for_errors()?;
-        Ok(doc)
+#[instrument(name = "key_shards_pdf", skip_all, fields(num_shards = shards.len()))]
+fn key_shards_to_pdf(
+    shards: &[(
+        &EncryptedKeyShard,
+        &KeyShardCodewords,
+        Option<&ShardProvenance>,
+    )],
+    options: &PdfOptions,
+) -> Result<PdfDocumentReference, Error> {
+    options.check_recovery_url()?;
+
+    // We can only fit two A5 pages (side-by-side) on a landscape A4 page.
+    let (page_width, page_height) = match options.shards_per_page {
+        1 => (A5_WIDTH, A5_HEIGHT),
+        2 => (A5_WIDTH * 2.0, A5_HEIGHT),
+        n => return Err(Error::UnsupportedShardsPerPage(n)),
+    };
+
+    // TODO: Make this nicer. It's quite ugly we need to decrypt the shard
+    // here just to get the document and shard ids. If we cached them that
+    // would work, but if you just read the shard data from the user you
+    // wouldn't have this information without decrypting it.
+    let decrypted_shards = shards
+        .iter()
+        .map(|(shard, codewords, _)| {
+            shard
+                .decrypt(codewords)
+                .map_err(|err| Error::OtherError(format!("failed to decrypt shard: {}", err)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The embedded fonts only contain Latin glyphs, so codewords from the
+    // CJK wordlists would be printed as blank boxes.
+    if shards
+        .iter()
+        .flat_map(|(_, codewords, _)| codewords.iter())
+        .flat_map(|codeword| codeword.chars())
+        .any(|ch| ch > '\u{24f}')
+    {
+        return Err(Error::OtherError(
+            "codewords cannot be printed with the embedded fonts -- use a different codeword language".to_string(),
+        ));
+    }
+
+    let title = match &decrypted_shards[..] {
+        [] => return Err(Error::OtherError("no key shards to print".to_string())),
+        [decrypted_shard] => format!(
+            "Paperback Key Shard {}/{}",
+            decrypted_shard.document_id(),
+            decrypted_shard.id()
+        ),
+        [decrypted_shard, ..] => format!(
+            "Paperback Key Shards {}/{}",
+            decrypted_shard.document_id(),
+            decrypted_shards
+                .iter()
+                .map(KeyShard::id)
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    };
+
+    // Construct an A5 (or landscape A4) PDF.
+    let (doc, first_layer, fonts) = new_document(title, page_width, page_height)?;
+    let mut first_layer = Some(first_layer);
+
+    let shards = decrypted_shards.iter().zip(shards).collect::<Vec<_>>();
+    for page_shards in shards.chunks(options.shards_per_page) {
+        let current_layer = first_layer
+            .take()
+            .unwrap_or_else(|| add_page(&doc, page_width, page_height));
+
+        for (idx, &(decrypted_shard, &(shard, codewords, provenance))) in
+            page_shards.iter().enumerate()
+        {
+            // Each shard is drawn as though it were on its own A5 page.
+            current_layer.save_graphics_state();
+            current_layer.set_ctm(CurTransMat::Translate(
+                (A5_WIDTH * idx as f32).into(),
+                Pt(0.0),
+            ));
+            draw_key_shard(
+                &current_layer,
+                &fonts,
+                (shard, decrypted_shard, codewords),
+                provenance,
+                options,
+            )?;
+            current_layer.restore_graphics_state();
+        }
+
+        // Vertical "cut here" lines between shards.
+        for idx in 1..options.shards_per_page {
+            let line = Line::from_iter(vec![
+                (Point::new(A5_WIDTH * idx as f32, Mm(0.0)), false),
+                (Point::new(A5_WIDTH * idx as f32, page_height), false),
+            ]);
+            current_layer.set_outline_color(colours::LIGHT_GREY);
+            current_layer.set_line_dash_pattern(LineDashPattern {
+                dash_1: Some(6),
+                gap_1: Some(4),
+                ..LineDashPattern::default()
+            });
+            current_layer.add_line(line);
+        }
+
+        // Holder instructions go on the following page. The shards are drawn
+        // in reverse order so that each shard's instructions end up on its
+        // back when printed double-sided (flipping along the short edge).
+        if options.include_instructions {
+            let current_layer = add_page(&doc, page_width, page_height);
+            for (idx, &(decrypted_shard, _)) in page_shards.iter().enumerate() {
+                let slot = options.shards_per_page - idx - 1;
+                current_layer.save_graphics_state();
+                current_layer.set_ctm(CurTransMat::Translate(
+                    (A5_WIDTH * slot as f32).into(),
+                    Pt(0.0),
+                ));
+                draw_key_shard_instructions(&current_layer, &fonts, decrypted_shard, options);
+                current_layer.restore_graphics_state();
+            }
+        }
+    }
+
+    let first_shard = &decrypted_shards[0];
+    let doc = with_metadata(
+        doc,
+        format!(
+            "paperback-v0 key shard {}/{}",
+            first_shard.document_id(),
+            decrypted_shards
+                .iter()
+                .map(KeyShard::id)
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        &first_shard.document_id(),
+        first_shard.created(),
+    );
+
+    // Store a machine-readable copy of the shards for FromPdf.
+    let doc = doc.with_keywords(
+        shards
+            .iter()
+            .map(|(_, (shard, _, _))| {
+                format!(
+                    "{}{}",
+                    PDF_KEYWORD_KEY_SHARD,
+                    shard.to_wire_multibase(Base::Base32Z)
+                )
+            })
+            .collect(),
+    );
+
+    doc.check_for_errors()?;
+    Ok(doc)
+}
+
+impl ToPdf for (&EncryptedKeyShard, &KeyShardCodewords) {
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error> {
+        let (shard, codewords) = *self;
+        key_shards_to_pdf(&[(shard, codewords, None)], options)
     }
 }
 
 impl ToPdf for (EncryptedKeyShard, KeyShardCodewords) {
-    fn to_pdf(&self) -> Result<PdfDocumentReference, Error> {
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error> {
         let (shard, codewords) = self;
-        (shard, codewords).to_pdf()
+        (shard, codewords).to_pdf_with_options(options)
+    }
+}
+
+/// Print several key shards in one document, with `PdfOptions::shards_per_page`
+/// shards on each page.
+impl ToPdf for [(EncryptedKeyShard, KeyShardCodewords)] {
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error> {
+        let shards = self
+            .iter()
+            .map(|(shard, codewords)| (shard, codewords, None))
+            .collect::<Vec<_>>();
+        key_shards_to_pdf(&shards, options)
+    }
+}
+
+/// Like the implementation for `[(EncryptedKeyShard, KeyShardCodewords)]`, but
+/// each key shard is annotated with where it came from.
+impl ToPdf for [(EncryptedKeyShard, KeyShardCodewords, ShardProvenance)] {
+    fn to_pdf_with_options(&self, options: &PdfOptions) -> Result<PdfDocumentReference, Error> {
+        let shards = self
+            .iter()
+            .map(|(shard, codewords, provenance)| (shard, codewords, Some(provenance)))
+            .collect::<Vec<_>>();
+        key_shards_to_pdf(&shards, options)
     }
 }
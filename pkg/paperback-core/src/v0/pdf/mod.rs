@@ -16,19 +16,45 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+pub mod encoding;
 pub mod generate;
+pub mod parse;
 pub mod qr;
+mod strings;
+pub mod svg;
 
+pub use encoding::{QrEncoding, TextFallback};
 pub use generate::ToPdf;
+pub use parse::FromPdf;
+pub use qr::{QrDensity, QrErrorCorrection};
+pub use strings::PdfLanguage;
+pub use svg::ToSvg;
+
+use crate::v0::DocumentId;
+
+// Prefixes for the machine-readable copies of paperback objects that are
+// stored in the keywords metadata of generated PDFs (see FromPdf).
+const PDF_KEYWORD_MAIN_DOCUMENT: &str = "paperback-main-document:";
+const PDF_KEYWORD_KEY_SHARD: &str = "paperback-key-shard:";
+const PDF_KEYWORD_ACTIVITY_RECORD: &str = "paperback-activity-record:";
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("mismatched qr code data")]
     MismatchedQrCode,
 
+    #[error("this qr code belongs to document {found}, expected document {expected}")]
+    QrCodeFromDifferentDocument {
+        found: DocumentId,
+        expected: DocumentId,
+    },
+
     #[error("missing qr code segment {}", .idx+1)]
     MissingQrSegment { idx: usize },
 
+    #[error("qr code segment {} conflicts with a different scan of the same segment -- one of the two scans is corrupted", .idx+1)]
+    ConflictingQrPart { idx: usize },
+
     #[error("qr code created using unsupported paperback version {version}")]
     WrongPaperbackVersion { version: u32 },
 
@@ -50,15 +76,141 @@ pub enum Error {
     #[error("pdf generation error: {0}")]
     GeneratePdf(#[from] printpdf::Error),
 
+    #[error("unsupported number of key shards per page: {0}")]
+    UnsupportedShardsPerPage(usize),
+
+    #[error("invalid recovery url '{0}': must be at most {max} printable ascii characters", max = PdfOptions::MAX_RECOVERY_URL_LENGTH)]
+    InvalidRecoveryUrl(String),
+
+    #[error("pdf reading error: {0}")]
+    ReadPdf(#[from] printpdf::lopdf::Error),
+
+    #[error("pdf parsing error: {0}")]
+    ParsePdf(String),
+
     #[error("miscellaneous error: {0}")]
     OtherError(String),
 }
 
-// While counter-intuitive, numerical codes give us almost identical density to
-// binary (which we can't use due to issues with copy-paste, null bytes, and
-// dodgy readers).
-//
-// Another alternative would be to use the Shift-JIS encoding as a dodgy way to
-// get everything out of the two-byte encoding (if we map every 2-byte sequence
-// to a kanji, it would allow us to have almost zero overhead encoding).
-const QRCODE_MULTIBASE: multibase::Base = multibase::Base::Base10;
+/// How the QR codes of a main document are laid out in its PDF. The layout has
+/// no effect on how the main document is recovered, as each QR code is tagged
+/// with its index.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MainDocumentLayout {
+    /// A 3x3 grid of QR codes on a single portrait A4 page.
+    #[default]
+    Portrait,
+
+    /// A 5x2 grid of (slightly larger) QR codes on a single landscape A4 page.
+    Landscape,
+
+    /// A cover page with the document details and checksum, followed by one
+    /// page for each QR code (printed as large as the page allows). This is
+    /// the easiest layout to scan, at the cost of more paper.
+    Booklet,
+}
+
+/// Where the codewords of a key shard are printed. The codewords are needed
+/// (along with the key shard data) to recover the key shard, so where they end
+/// up decides who has to be trusted with them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CodewordPlacement {
+    /// Only on the detachable stub below the "cut here" line, so that the
+    /// codewords can be stored separately from the rest of the key shard.
+    #[default]
+    Stub,
+
+    /// On both the detachable stub and the main part of the key shard. This
+    /// allows the owner to cut off and retain the stub while the holder still
+    /// has a complete copy of the key shard (or vice-versa).
+    Both,
+
+    /// Only on the main part of the key shard. No detachable stub is printed.
+    Shard,
+}
+
+impl CodewordPlacement {
+    fn on_stub(self) -> bool {
+        matches!(self, Self::Stub | Self::Both)
+    }
+
+    fn on_shard(self) -> bool {
+        matches!(self, Self::Both | Self::Shard)
+    }
+}
+
+/// Options controlling how paperback documents are rendered to PDFs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PdfOptions {
+    /// Encoding used for the data stored in QR codes.
+    pub qr_encoding: QrEncoding,
+    /// Error correction level of generated QR codes.
+    pub qr_error_correction: QrErrorCorrection,
+    /// How densely data is packed into the QR codes of main documents. Key
+    /// shards are always stored in a single QR code.
+    pub qr_density: QrDensity,
+    /// Encoding used for the text printed next to each QR code.
+    pub text_fallback: TextFallback,
+    /// Number of key shards to lay out on each page. With `1` each shard gets
+    /// its own A5 page, with `2` shards are printed side-by-side on landscape
+    /// A4 pages with a cut line between them. No other values are supported.
+    pub shards_per_page: usize,
+    /// Layout of the QR codes in main documents.
+    pub layout: MainDocumentLayout,
+    /// Also print the codewords of key shards as a QR code on the detachable
+    /// codeword stub, so that they can be scanned rather than typed. The QR
+    /// code contains the codewords as plain text (separated by spaces). Has no
+    /// effect with [`CodewordPlacement::Shard`], as there is no stub.
+    pub codeword_qr: bool,
+    /// Where the codewords of key shards are printed.
+    pub codeword_placement: CodewordPlacement,
+    /// URL of (up-to-date) instructions for recovering a paperback backup,
+    /// printed on each key shard as a small QR code and as plain text. This is
+    /// meant for the holders of key shards, who might need to find recovery
+    /// tooling many years after the backup was made. It must be at most
+    /// `PdfOptions::MAX_RECOVERY_URL_LENGTH` characters, so use a short URL.
+    pub recovery_url: Option<String>,
+    /// Add a page after each page of key shards with instructions for the
+    /// people holding the shards (what a key shard is, when to hand it over,
+    /// and how to avoid being scammed out of it).
+    pub include_instructions: bool,
+    /// Language of the explanatory text printed on documents.
+    pub language: PdfLanguage,
+}
+
+impl PdfOptions {
+    /// Maximum length of `PdfOptions::recovery_url`, so that it fits next to
+    /// the checksum of each key shard (and in a small QR code).
+    pub const MAX_RECOVERY_URL_LENGTH: usize = 48;
+
+    fn check_recovery_url(&self) -> Result<(), Error> {
+        match &self.recovery_url {
+            Some(url)
+                if url.is_empty()
+                    || url.len() > Self::MAX_RECOVERY_URL_LENGTH
+                    || !url.chars().all(|ch| ch.is_ascii_graphic()) =>
+            {
+                Err(Error::InvalidRecoveryUrl(url.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            qr_encoding: QrEncoding::default(),
+            qr_error_correction: QrErrorCorrection::default(),
+            qr_density: QrDensity::default(),
+            text_fallback: TextFallback::default(),
+            shards_per_page: 1,
+            layout: MainDocumentLayout::default(),
+            codeword_qr: false,
+            codeword_placement: CodewordPlacement::default(),
+            recovery_url: None,
+            include_instructions: false,
+            language: PdfLanguage::default(),
+        }
+    }
+}
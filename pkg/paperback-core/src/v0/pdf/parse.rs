@@ -0,0 +1,306 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    pdf::{Error, PDF_KEYWORD_ACTIVITY_RECORD, PDF_KEYWORD_KEY_SHARD, PDF_KEYWORD_MAIN_DOCUMENT},
+    ActivityRecord, EncryptedKeyShard, FromWire, MainDocument,
+};
+
+use printpdf::lopdf;
+
+/// Reconstruct paperback documents from PDFs generated by [`ToPdf`].
+///
+/// Rather than rasterising the PDF and scanning the QR codes, this reads the
+/// machine-readable copy of the QR code data that [`ToPdf`] stores in the
+/// document metadata. As a result, only PDF files generated by paperback can
+/// be parsed (and not scans of printed documents).
+///
+/// [`ToPdf`]: super::ToPdf
+pub trait FromPdf: Sized {
+    fn from_pdf<B: AsRef<[u8]>>(pdf: B) -> Result<Self, Error>;
+}
+
+/// Extract all of the paperback objects stored with the given keyword prefix.
+fn pdf_objects<T: FromWire>(pdf: &[u8], prefix: &str) -> Result<Vec<T>, Error> {
+    let doc = lopdf::Document::load_mem(pdf)?;
+    let info = doc.get_dictionary(doc.trailer.get(b"Info")?.as_reference()?)?;
+    let keywords = String::from_utf8(info.get(b"Keywords")?.as_str()?.to_vec())
+        .map_err(|err| Error::ParsePdf(format!("invalid keywords metadata: {}", err)))?;
+
+    keywords
+        .split(',')
+        .filter_map(|keyword| keyword.strip_prefix(prefix))
+        .map(|data| T::from_wire_multibase(data).map_err(Error::ParseQrData))
+        .collect()
+}
+
+/// Extract exactly one paperback object stored with the given keyword prefix.
+fn pdf_object<T: FromWire>(pdf: &[u8], prefix: &str, name: &str) -> Result<T, Error> {
+    let mut objects = pdf_objects(pdf, prefix)?;
+    match objects.len() {
+        0 => Err(Error::ParsePdf(format!("pdf does not contain a {}", name))),
+        1 => Ok(objects.remove(0)),
+        n => Err(Error::ParsePdf(format!(
+            "pdf contains {} {}s rather than just one",
+            n, name
+        ))),
+    }
+}
+
+impl FromPdf for MainDocument {
+    fn from_pdf<B: AsRef<[u8]>>(pdf: B) -> Result<Self, Error> {
+        pdf_object(pdf.as_ref(), PDF_KEYWORD_MAIN_DOCUMENT, "main document")
+    }
+}
+
+impl FromPdf for EncryptedKeyShard {
+    fn from_pdf<B: AsRef<[u8]>>(pdf: B) -> Result<Self, Error> {
+        pdf_object(pdf.as_ref(), PDF_KEYWORD_KEY_SHARD, "key shard")
+    }
+}
+
+impl FromPdf for ActivityRecord {
+    fn from_pdf<B: AsRef<[u8]>>(pdf: B) -> Result<Self, Error> {
+        pdf_object(pdf.as_ref(), PDF_KEYWORD_ACTIVITY_RECORD, "activity record")
+    }
+}
+
+/// Extract every key shard from a PDF (which may contain several key shards
+/// if it was generated with [`PdfOptions::shards_per_page`] set).
+///
+/// [`PdfOptions::shards_per_page`]: super::PdfOptions::shards_per_page
+impl FromPdf for Vec<EncryptedKeyShard> {
+    fn from_pdf<B: AsRef<[u8]>>(pdf: B) -> Result<Self, Error> {
+        let shards = pdf_objects(pdf.as_ref(), PDF_KEYWORD_KEY_SHARD)?;
+        if shards.is_empty() {
+            return Err(Error::ParsePdf(
+                "pdf does not contain any key shards".into(),
+            ));
+        }
+        Ok(shards)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::{
+        pdf::{CodewordPlacement, MainDocumentLayout, PdfLanguage, PdfOptions},
+        Backup, InstructionsDocument, NewShardKind, ToPdf, ToWire, UntrustedQuorum,
+    };
+
+    use std::io::BufWriter;
+
+    fn save_pdf<T: ToPdf + ?Sized>(object: &T, options: &PdfOptions) -> Vec<u8> {
+        let mut pdf = Vec::new();
+        object
+            .to_pdf_with_options(options)
+            .unwrap()
+            .save(&mut BufWriter::new(&mut pdf))
+            .unwrap();
+        pdf
+    }
+
+    #[test]
+    fn pdf_roundtrip() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let main_document = backup.main_document().clone();
+        let shards = (0..2)
+            .map(|_| backup.next_shard().unwrap().encrypt().unwrap())
+            .collect::<Vec<_>>();
+
+        let pdf = save_pdf(&main_document, &PdfOptions::default());
+        assert_eq!(
+            MainDocument::from_pdf(&pdf).unwrap().to_wire(),
+            main_document.to_wire()
+        );
+        assert!(EncryptedKeyShard::from_pdf(&pdf).is_err());
+
+        for layout in [MainDocumentLayout::Landscape, MainDocumentLayout::Booklet] {
+            let options = PdfOptions {
+                layout,
+                ..Default::default()
+            };
+            let pdf = save_pdf(&main_document, &options);
+            assert_eq!(
+                MainDocument::from_pdf(&pdf).unwrap().to_wire(),
+                main_document.to_wire()
+            );
+        }
+
+        let pdf = save_pdf(&shards[0], &PdfOptions::default());
+        assert_eq!(
+            EncryptedKeyShard::from_pdf(&pdf).unwrap().to_wire(),
+            shards[0].0.to_wire()
+        );
+        assert!(MainDocument::from_pdf(&pdf).is_err());
+
+        let options = PdfOptions {
+            codeword_qr: true,
+            ..Default::default()
+        };
+        let pdf = save_pdf(&shards[0], &options);
+        assert_eq!(
+            EncryptedKeyShard::from_pdf(&pdf).unwrap().to_wire(),
+            shards[0].0.to_wire()
+        );
+
+        let options = PdfOptions {
+            recovery_url: Some("https://cyphar.com/paperback".to_string()),
+            ..Default::default()
+        };
+        let pdf = save_pdf(&shards[0], &options);
+        assert_eq!(
+            EncryptedKeyShard::from_pdf(&pdf).unwrap().to_wire(),
+            shards[0].0.to_wire()
+        );
+        for recovery_url in ["", "https://example.com/ with spaces", &"a".repeat(49)] {
+            let options = PdfOptions {
+                recovery_url: Some(recovery_url.to_string()),
+                ..Default::default()
+            };
+            assert!(matches!(
+                shards[0].to_pdf_with_options(&options),
+                Err(Error::InvalidRecoveryUrl(_))
+            ));
+        }
+
+        for codeword_placement in [CodewordPlacement::Both, CodewordPlacement::Shard] {
+            let options = PdfOptions {
+                codeword_placement,
+                codeword_qr: true,
+                ..Default::default()
+            };
+            let pdf = save_pdf(&shards[0], &options);
+            assert_eq!(
+                EncryptedKeyShard::from_pdf(&pdf).unwrap().to_wire(),
+                shards[0].0.to_wire()
+            );
+        }
+
+        let options = PdfOptions {
+            shards_per_page: 2,
+            include_instructions: true,
+            ..Default::default()
+        };
+        let pdf = save_pdf(&shards[..], &options);
+        assert_eq!(
+            Vec::<EncryptedKeyShard>::from_pdf(&pdf)
+                .unwrap()
+                .iter()
+                .map(ToWire::to_wire)
+                .collect::<Vec<_>>(),
+            shards
+                .iter()
+                .map(|(shard, _)| shard.to_wire())
+                .collect::<Vec<_>>()
+        );
+        assert!(EncryptedKeyShard::from_pdf(&pdf).is_err());
+
+        for &language in PdfLanguage::ALL {
+            let options = PdfOptions {
+                language,
+                layout: MainDocumentLayout::Booklet,
+                include_instructions: true,
+                ..Default::default()
+            };
+            let pdf = save_pdf(&main_document, &options);
+            assert_eq!(
+                MainDocument::from_pdf(&pdf).unwrap().to_wire(),
+                main_document.to_wire()
+            );
+            let pdf = save_pdf(&shards[0], &options);
+            assert_eq!(
+                EncryptedKeyShard::from_pdf(&pdf).unwrap().to_wire(),
+                shards[0].0.to_wire()
+            );
+        }
+    }
+
+    #[test]
+    fn pdf_metadata() {
+        let info = |pdf: &[u8], key: &[u8]| {
+            let doc = lopdf::Document::load_mem(pdf).unwrap();
+            let info = doc
+                .get_dictionary(doc.trailer.get(b"Info").unwrap().as_reference().unwrap())
+                .unwrap()
+                .clone();
+            String::from_utf8(info.get(key).unwrap().as_str().unwrap().to_vec()).unwrap()
+        };
+
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let main_document = backup.main_document();
+        let shard = backup.next_shard().unwrap().encrypt().unwrap();
+        let document_id = main_document.id().to_string();
+
+        let pdf = save_pdf(main_document, &PdfOptions::default());
+        assert_eq!(info(&pdf, b"Identifier"), document_id);
+        assert_eq!(
+            info(&pdf, b"Subject"),
+            format!("paperback-v0 main document {}", document_id)
+        );
+
+        let pdf = save_pdf(&shard, &PdfOptions::default());
+        assert_eq!(info(&pdf, b"Identifier"), document_id);
+        assert_eq!(
+            info(&pdf, b"Subject"),
+            format!(
+                "paperback-v0 key shard {}/{}",
+                document_id,
+                shard.0.decrypt(&shard.1).unwrap().id()
+            )
+        );
+
+        let instructions = InstructionsDocument::new(main_document, ["Alice", "Bob"]).unwrap();
+        for &language in PdfLanguage::ALL {
+            let options = PdfOptions {
+                language,
+                ..Default::default()
+            };
+            let pdf = save_pdf(&instructions, &options);
+            assert_eq!(info(&pdf, b"Identifier"), document_id);
+            assert_eq!(
+                info(&pdf, b"Subject"),
+                format!("paperback-v0 recovery instructions {}", document_id)
+            );
+            assert!(MainDocument::from_pdf(&pdf).is_err());
+        }
+    }
+
+    #[test]
+    fn activity_record_pdf_roundtrip() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let mut quorum = UntrustedQuorum::new();
+        for _ in 0..2 {
+            quorum.push_shard(backup.next_shard().unwrap());
+        }
+        let quorum = quorum.validate().unwrap();
+        let shard = quorum.new_shard(NewShardKind::NewShard).unwrap();
+        let record = quorum.activity_record([&shard]).unwrap();
+
+        for &language in PdfLanguage::ALL {
+            let options = PdfOptions {
+                language,
+                ..Default::default()
+            };
+            let pdf = save_pdf(&record, &options);
+            assert_eq!(ActivityRecord::from_pdf(&pdf).unwrap(), record);
+            assert!(MainDocument::from_pdf(&pdf).is_err());
+        }
+    }
+}
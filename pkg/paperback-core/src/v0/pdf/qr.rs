@@ -17,22 +17,89 @@
  */
 
 use crate::v0::{
-    pdf::{Error, QRCODE_MULTIBASE},
-    FromWire, ToWire, PAPERBACK_VERSION,
+    pdf::{Error, PdfOptions, QrEncoding},
+    wire::prefixes::{PREFIX_QR_DOCUMENT_ID, PREFIX_QR_ERROR_CORRECTION, PREFIX_QR_SHARD_ID},
+    DocumentId, EncryptedKeyShard, FromWire, KeyShard, KeyShardCodewords, MainDocument, ShardId,
+    ToWire, PAPERBACK_VERSION,
 };
 
-use qrcode::QrCode;
+use qrcode::{bits::Bits, types::QrError, Color, EcLevel, QrCode, Version};
 use unsigned_varint::encode as varuint_encode;
 
+/// Error correction level of generated QR codes.
+///
+/// Higher levels allow more of a QR code to be damaged while still being
+/// readable, at the cost of storing less data in each QR code (so more QR
+/// codes are needed for the main document).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum QrErrorCorrection {
+    /// Roughly 7% of the QR code can be restored.
+    Low, // 'L'
+    /// Roughly 15% of the QR code can be restored.
+    #[default]
+    Medium, // 'M'
+    /// Roughly 25% of the QR code can be restored.
+    Quartile, // 'Q'
+    /// Roughly 30% of the QR code can be restored.
+    High, // 'H'
+}
+
+impl From<QrErrorCorrection> for EcLevel {
+    fn from(level: QrErrorCorrection) -> Self {
+        match level {
+            QrErrorCorrection::Low => EcLevel::L,
+            QrErrorCorrection::Medium => EcLevel::M,
+            QrErrorCorrection::Quartile => EcLevel::Q,
+            QrErrorCorrection::High => EcLevel::H,
+        }
+    }
+}
+
+impl ToWire for QrErrorCorrection {
+    fn to_wire(&self) -> Vec<u8> {
+        match self {
+            Self::Low => "L",
+            Self::Medium => "M",
+            Self::Quartile => "Q",
+            Self::High => "H",
+        }
+        .into()
+    }
+}
+
+impl FromWire for QrErrorCorrection {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        match input.split_first() {
+            Some((b'L', input)) => Ok((input, Self::Low)),
+            Some((b'M', input)) => Ok((input, Self::Medium)),
+            Some((b'Q', input)) => Ok((input, Self::Quartile)),
+            Some((b'H', input)) => Ok((input, Self::High)),
+            None => Err("missing qr error correction level".into()),
+            Some((ch, _)) => Err(format!(
+                "unknown qr error correction level {:?}",
+                *ch as char
+            )),
+        }
+    }
+}
+
+/// The kind of data stored in a set of QR code parts.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub(super) enum PartType {
+pub enum PartType {
+    /// Data of a main document.
     MainDocumentData, // 'D'
+    /// Data of a key shard which was too large to fit in a single QR code.
+    KeyShardData, // 'S'
+    /// Checksum of a key shard which was too large to fit in a single QR code.
+    KeyShardChecksum, // 'C'
 }
 
 impl ToWire for PartType {
     fn to_wire(&self) -> Vec<u8> {
         match self {
             Self::MainDocumentData => "D",
+            Self::KeyShardData => "S",
+            Self::KeyShardChecksum => "C",
         }
         .into()
     }
@@ -42,16 +109,25 @@ impl FromWire for PartType {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
         match input.split_first() {
             Some((b'D', input)) => Ok((input, Self::MainDocumentData)),
-            None => Err("".into()), // TODO
-            Some(_) => Err("".into()),
+            Some((b'S', input)) => Ok((input, Self::KeyShardData)),
+            Some((b'C', input)) => Ok((input, Self::KeyShardChecksum)),
+            None => Err("qr part data type: missing".into()),
+            Some((ch, _)) => Err(format!("qr part data type: unknown type {:?}", *ch as char)),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 struct PartMeta {
     version: u32,
     data_type: PartType,
+    // Older paperback versions did not record the error correction level.
+    ec_level: Option<QrErrorCorrection>,
+    // Older paperback versions did not record which document the part is from.
+    document_id: Option<DocumentId>,
+    // Only set for key shard parts, so that parts of different key shards
+    // from the same document are not mixed up.
+    shard_id: Option<ShardId>,
     num_parts: usize,
 }
 
@@ -68,6 +144,41 @@ impl ToWire for PartMeta {
         // Encode data type.
         bytes.append(&mut self.data_type.to_wire());
 
+        // Encode error correction level (if present).
+        if let Some(ec_level) = self.ec_level {
+            bytes.extend_from_slice(varuint_encode::u64(
+                PREFIX_QR_ERROR_CORRECTION,
+                &mut varuint_encode::u64_buffer(),
+            ));
+            bytes.append(&mut ec_level.to_wire());
+        }
+
+        // Encode document id (if present).
+        if let Some(document_id) = &self.document_id {
+            bytes.extend_from_slice(varuint_encode::u64(
+                PREFIX_QR_DOCUMENT_ID,
+                &mut varuint_encode::u64_buffer(),
+            ));
+            bytes.extend_from_slice(varuint_encode::usize(
+                document_id.len(),
+                &mut varuint_encode::usize_buffer(),
+            ));
+            bytes.extend_from_slice(document_id.as_bytes());
+        }
+
+        // Encode shard id (if present).
+        if let Some(shard_id) = &self.shard_id {
+            bytes.extend_from_slice(varuint_encode::u64(
+                PREFIX_QR_SHARD_ID,
+                &mut varuint_encode::u64_buffer(),
+            ));
+            bytes.extend_from_slice(varuint_encode::usize(
+                shard_id.len(),
+                &mut varuint_encode::usize_buffer(),
+            ));
+            bytes.extend_from_slice(shard_id.as_bytes());
+        }
+
         // Encode number of parts.
         bytes.extend_from_slice(varuint_encode::usize(
             self.num_parts,
@@ -80,26 +191,74 @@ impl ToWire for PartMeta {
 
 impl FromWire for PartMeta {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
-        use nom::{combinator::complete, IResult};
-        use unsigned_varint::nom as varuint_nom;
+        use crate::v0::wire::varuint_nom;
+        use nom::{
+            bytes::streaming::take,
+            combinator::{complete, map_opt, map_res, opt, verify},
+            multi::length_data,
+            sequence::preceded,
+            IResult,
+        };
+
+        fn parse_version(input: &[u8]) -> IResult<&[u8], u32> {
+            complete(varuint_nom::u32)(input)
+        }
 
-        fn parse(input: &[u8]) -> IResult<&[u8], (u32, PartType, usize)> {
-            let (input, version) = varuint_nom::u32(input)?;
-            let (input, data_type) = PartType::from_wire_partial(input).unwrap(); // TODO TODO TODO
-            let (input, num_parts) = varuint_nom::usize(input)?;
+        fn parse_ec_level(input: &[u8]) -> IResult<&[u8], Option<QrErrorCorrection>> {
+            opt(complete(preceded(
+                verify(varuint_nom::u64, |x| *x == PREFIX_QR_ERROR_CORRECTION),
+                map_opt(take(1usize), |level: &[u8]| {
+                    QrErrorCorrection::from_wire(level).ok()
+                }),
+            )))(input)
+        }
 
-            Ok((input, (version, data_type, num_parts)))
+        fn parse_document_id(input: &[u8]) -> IResult<&[u8], Option<DocumentId>> {
+            opt(complete(preceded(
+                verify(varuint_nom::u64, |x| *x == PREFIX_QR_DOCUMENT_ID),
+                map_res(length_data(varuint_nom::usize), |id: &[u8]| {
+                    std::str::from_utf8(id)
+                        .map_err(|err| err.to_string())
+                        .and_then(|id| id.parse::<DocumentId>().map_err(|err| err.to_string()))
+                }),
+            )))(input)
         }
-        let mut parse = complete(parse);
 
-        let (input, (version, data_type, num_parts)) =
-            parse(input).map_err(|err| format!("{:?}", err))?;
+        fn parse_shard_id(input: &[u8]) -> IResult<&[u8], Option<ShardId>> {
+            opt(complete(preceded(
+                verify(varuint_nom::u64, |x| *x == PREFIX_QR_SHARD_ID),
+                map_res(length_data(varuint_nom::usize), |id: &[u8]| {
+                    std::str::from_utf8(id)
+                        .map_err(|err| err.to_string())
+                        .and_then(|id| id.parse::<ShardId>().map_err(|err| err.to_string()))
+                }),
+            )))(input)
+        }
+
+        fn parse_num_parts(input: &[u8]) -> IResult<&[u8], usize> {
+            complete(varuint_nom::usize)(input)
+        }
+
+        let (input, version) =
+            parse_version(input).map_err(|err| format!("qr part version: {:?}", err))?;
+        let (input, data_type) = PartType::from_wire_partial(input)?;
+        let (input, ec_level) = parse_ec_level(input)
+            .map_err(|err| format!("qr part error correction level: {:?}", err))?;
+        let (input, document_id) =
+            parse_document_id(input).map_err(|err| format!("qr part document id: {:?}", err))?;
+        let (input, shard_id) =
+            parse_shard_id(input).map_err(|err| format!("qr part shard id: {:?}", err))?;
+        let (input, num_parts) =
+            parse_num_parts(input).map_err(|err| format!("qr part number of parts: {:?}", err))?;
 
         Ok((
             input,
             PartMeta {
                 version,
                 data_type,
+                ec_level,
+                document_id,
+                shard_id,
                 num_parts,
             },
         ))
@@ -113,6 +272,39 @@ pub struct Part {
     data: Vec<u8>,
 }
 
+impl Part {
+    /// Index of this part within the set of parts (zero-indexed).
+    pub fn index(&self) -> usize {
+        self.part_idx
+    }
+
+    /// Total number of parts in the set this part belongs to.
+    pub fn num_parts(&self) -> usize {
+        self.meta.num_parts
+    }
+
+    /// Error correction level the part's QR code was generated with (if it
+    /// was recorded).
+    pub fn error_correction(&self) -> Option<QrErrorCorrection> {
+        self.meta.ec_level
+    }
+
+    /// Id of the document this part belongs to (if it was recorded).
+    pub fn document_id(&self) -> Option<&DocumentId> {
+        self.meta.document_id.as_ref()
+    }
+
+    /// The kind of data this part contains.
+    pub fn data_type(&self) -> PartType {
+        self.meta.data_type
+    }
+
+    /// Id of the key shard this part belongs to (only for key shard parts).
+    pub fn shard_id(&self) -> Option<&ShardId> {
+        self.meta.shard_id.as_ref()
+    }
+}
+
 impl ToWire for Part {
     fn to_wire(&self) -> Vec<u8> {
         // Start with Pb prefix.
@@ -136,21 +328,24 @@ impl ToWire for Part {
 
 impl FromWire for Part {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::varuint_nom;
         use nom::{bytes::streaming::tag, combinator::complete, IResult};
-        use unsigned_varint::nom as varuint_nom;
 
-        fn parse(input: &[u8]) -> IResult<&[u8], (PartMeta, usize, Vec<u8>)> {
-            let (input, _) = tag(b"Pb")(input)?;
-            let (input, meta) = PartMeta::from_wire_partial(input).unwrap(); // TODO TODO TODO
-            let (input, part_idx) = varuint_nom::usize(input)?;
-            // TODO: Is this correct?
-            let (input, data) = (&input[0..0], input.to_vec());
+        fn parse_header(input: &[u8]) -> IResult<&[u8], &[u8]> {
+            complete(tag(b"Pb"))(input)
+        }
 
-            Ok((input, (meta, part_idx, data)))
+        fn parse_index(input: &[u8]) -> IResult<&[u8], usize> {
+            complete(varuint_nom::usize)(input)
         }
-        let mut parse = complete(parse);
 
-        let (input, (meta, part_idx, data)) = parse(input).map_err(|err| format!("{:?}", err))?;
+        let (input, _) = parse_header(input)
+            .map_err(|_| "qr part header: data is not a main document qr code".to_string())?;
+        let (input, meta) = PartMeta::from_wire_partial(input)?;
+        let (input, part_idx) =
+            parse_index(input).map_err(|err| format!("qr part index: {:?}", err))?;
+        // The data makes up the rest of the part.
+        let (input, data) = (&input[0..0], input.to_vec());
 
         Ok((
             input,
@@ -176,6 +371,7 @@ impl Joiner {
 
     pub fn remaining(&self) -> Option<usize> {
         self.meta
+            .as_ref()
             .map(|_| self.parts.iter().filter(|v| v.is_none()).count())
     }
 
@@ -183,30 +379,100 @@ impl Joiner {
         self.remaining() == Some(0)
     }
 
+    /// Total number of parts expected, once at least one part has been added.
+    pub fn num_parts(&self) -> Option<usize> {
+        self.meta.as_ref().map(|meta| meta.num_parts)
+    }
+
+    /// Id of the document being joined, once at least one part has been added
+    /// (and if the parts record it).
+    pub fn document_id(&self) -> Option<&DocumentId> {
+        self.meta.as_ref()?.document_id.as_ref()
+    }
+
+    /// Returns whether the part with the given index has been added.
+    pub fn has_part(&self, idx: usize) -> bool {
+        matches!(self.parts.get(idx), Some(Some(_)))
+    }
+
+    /// Indices (zero-indexed) of the parts which have been added so far.
+    pub fn present_parts(&self) -> Vec<usize> {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, part)| part.as_ref().map(|_| idx))
+            .collect()
+    }
+
+    /// Indices (zero-indexed) of the parts which still need to be added.
+    pub fn missing_parts(&self) -> Vec<usize> {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, part)| part.is_none().then_some(idx))
+            .collect()
+    }
+
+    /// Remove a previously-added part, so that it can be re-entered.
+    ///
+    /// If the last remaining part is removed, the `Joiner` is reset so that
+    /// parts from a different set can be added.
+    pub fn remove_part(&mut self, idx: usize) -> Option<Part> {
+        let part = self.parts.get_mut(idx)?.take();
+        if self.parts.iter().all(Option::is_none) {
+            *self = Self::default();
+        }
+        part
+    }
+
+    /// Add a part, in any order.
+    ///
+    /// Adding a part which has already been added is a no-op, but a part with
+    /// the same index and different data (such as a misread scan) is rejected
+    /// with `Error::ConflictingQrPart` -- there is no way to tell which of the
+    /// two is correct, so the earlier part must be removed with `remove_part`
+    /// before it can be replaced.
     pub fn add_part(&mut self, part: Part) -> Result<&mut Self, Error> {
-        if let Some(meta) = self.meta {
-            if meta != part.meta || part.part_idx >= meta.num_parts {
-                return Err(Error::MismatchedQrCode);
+        if part.meta.version != PAPERBACK_VERSION {
+            return Err(Error::WrongPaperbackVersion {
+                version: part.meta.version,
+            });
+        }
+        if let Some(meta) = &self.meta {
+            if let (Some(expected), Some(found)) = (&meta.document_id, &part.meta.document_id) {
+                if expected != found {
+                    return Err(Error::QrCodeFromDifferentDocument {
+                        found: found.clone(),
+                        expected: expected.clone(),
+                    });
+                }
             }
-            if part.meta.version != PAPERBACK_VERSION {
-                return Err(Error::WrongPaperbackVersion {
-                    version: part.meta.version,
-                });
+            if *meta != part.meta || part.part_idx >= meta.num_parts {
+                return Err(Error::MismatchedQrCode);
             }
         } else {
-            self.meta = Some(part.meta);
+            if part.part_idx >= part.meta.num_parts {
+                return Err(Error::MismatchedQrCode);
+            }
             self.parts = vec![None; part.meta.num_parts];
+            self.meta = Some(part.meta.clone());
         }
         if part.part_idx >= self.parts.len() {
             return Err(Error::MismatchedQrCode);
         }
         let idx = part.part_idx;
-        self.parts[idx] = Some(part);
+        match &self.parts[idx] {
+            Some(existing) if existing.data != part.data => {
+                return Err(Error::ConflictingQrPart { idx })
+            }
+            Some(_) => (),
+            None => self.parts[idx] = Some(part),
+        }
         Ok(self)
     }
 
     pub fn add_qr_part<B: AsRef<str>>(&mut self, qr_data: B) -> Result<&mut Self, Error> {
-        let part = Part::from_wire_multibase(qr_data.as_ref()).map_err(Error::ParseQrData)?;
+        let part = Part::from_wire(QrEncoding::decode(qr_data)?).map_err(Error::ParseQrData)?;
         self.add_part(part)
     }
 
@@ -227,17 +493,78 @@ impl Joiner {
     }
 }
 
-const DATA_OVERHEAD: usize = 1 /* multibase header */ +
+const DATA_OVERHEAD: usize = 1 /* encoding header */ +
                              1 /* (varuint) version = 0 */ +
                              1 /* data type */ +
+                             9 + 1 + MainDocument::ID_LENGTH /* document id */ +
                              2 * 9 /* 2*varuint length and index */;
 
-// TODO: Make this dynamic based on the error correction mode.
-const MAX_DATA_LENGTH: usize = 926 - DATA_OVERHEAD;
+/// How densely the data of a main document is packed into its QR codes.
+///
+/// Each part of the main document is sized to fill a QR code of a particular
+/// version (size). Denser QR codes need fewer QR codes for the same data, but
+/// are printed with smaller modules, which makes them harder to scan when
+/// printed by a low-quality printer.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum QrDensity {
+    /// Version 10 QR codes (57x57 modules).
+    Low,
+    /// Version 17 QR codes (85x85 modules).
+    Medium,
+    /// Version 25 QR codes (117x117 modules).
+    #[default]
+    High,
+}
+
+impl QrDensity {
+    fn version(self) -> i16 {
+        match self {
+            Self::Low => 10,
+            Self::Medium => 17,
+            Self::High => 25,
+        }
+    }
 
-fn split_data<B: AsRef<[u8]>>(data_type: PartType, data: B) -> Vec<Part> {
+    /// Width (in modules) of the largest QR codes generated at this density.
+    pub fn modules(self) -> usize {
+        17 + 4 * self.version() as usize
+    }
+}
+
+// Mode indicator and character count of the single segment in each QR code.
+// NOTE: The character count is only 16 bits for versions 10 and above.
+const QR_SEGMENT_HEADER_BITS: usize = 4 + 16;
+
+pub(super) fn max_data_length(
+    encoding: QrEncoding,
+    ec_level: QrErrorCorrection,
+    density: QrDensity,
+) -> usize {
+    // Higher error correction levels leave less space for data in the same
+    // size QR code.
+    let qr_data_bits = Bits::new(Version::Normal(density.version()))
+        .max_len(ec_level.into())
+        .expect("all error correction levels are valid for normal qr codes")
+        - QR_SEGMENT_HEADER_BITS;
+    qr_data_bits * 1000 / encoding.millibits_per_byte() - DATA_OVERHEAD
+}
+
+fn split_data<B: AsRef<[u8]>>(
+    encoding: QrEncoding,
+    ec_level: QrErrorCorrection,
+    density: QrDensity,
+    data_type: PartType,
+    document_id: Option<&DocumentId>,
+    shard_id: Option<&ShardId>,
+    data: B,
+) -> Vec<Part> {
     let data = data.as_ref();
-    let chunks = data.chunks(MAX_DATA_LENGTH).collect::<Vec<_>>();
+    // DATA_OVERHEAD doesn't account for the shard id, which is only present
+    // in key shard parts.
+    let shard_id_overhead = shard_id.map_or(0, |id| 9 + 1 + id.len());
+    let chunks = data
+        .chunks(max_data_length(encoding, ec_level, density) - shard_id_overhead)
+        .collect::<Vec<_>>();
     chunks
         .iter()
         .enumerate()
@@ -245,6 +572,9 @@ fn split_data<B: AsRef<[u8]>>(data_type: PartType, data: B) -> Vec<Part> {
             meta: PartMeta {
                 version: PAPERBACK_VERSION,
                 data_type,
+                ec_level: Some(ec_level),
+                document_id: document_id.cloned(),
+                shard_id: shard_id.cloned(),
                 num_parts: chunks.len(),
             },
             part_idx: idx,
@@ -254,41 +584,236 @@ fn split_data<B: AsRef<[u8]>>(data_type: PartType, data: B) -> Vec<Part> {
 }
 
 pub(super) fn generate_codes<B: AsRef<[u8]>>(
+    encoding: QrEncoding,
+    ec_level: QrErrorCorrection,
+    density: QrDensity,
     data_type: PartType,
+    document_id: Option<&DocumentId>,
+    shard_id: Option<&ShardId>,
     data: B,
 ) -> Result<(Vec<QrCode>, Vec<Vec<u8>>), Error> {
-    let codes = split_data(data_type, data)
-        .iter()
-        .map(ToWire::to_wire)
-        .collect::<Vec<_>>();
+    let codes = split_data(
+        encoding,
+        ec_level,
+        density,
+        data_type,
+        document_id,
+        shard_id,
+        data,
+    )
+    .iter()
+    .map(ToWire::to_wire)
+    .collect::<Vec<_>>();
     Ok((
         codes
             .iter()
-            .map(|data| multibase::encode(QRCODE_MULTIBASE, data))
-            .map(QrCode::new)
+            .map(|data| encoding.encode_qr(data))
+            .map(|data| QrCode::with_error_correction_level(data, ec_level.into()))
             .collect::<Result<Vec<_>, _>>()?,
         codes,
     ))
 }
 
-pub(super) fn generate_one_code<B: AsRef<[u8]>>(data: B) -> Result<QrCode, Error> {
+/// The text stored in each of the QR codes of `main_document` when it is
+/// printed with `options` (what a QR code scanner would return for each code),
+/// which can be joined again with `Joiner::add_qr_part`.
+pub fn main_document_qr_text(main_document: &MainDocument, options: &PdfOptions) -> Vec<String> {
+    split_data(
+        options.qr_encoding,
+        options.qr_error_correction,
+        options.qr_density,
+        PartType::MainDocumentData,
+        Some(&main_document.id()),
+        None,
+        main_document.to_wire(),
+    )
+    .iter()
+    .map(|part| options.qr_encoding.encode(part.to_wire()))
+    .collect()
+}
+
+/// The modules of a generated QR code, for rendering QR codes outside of the
+/// generated PDFs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QrModules {
+    width: usize,
+    dark: Vec<bool>,
+}
+
+impl QrModules {
+    fn new(code: &QrCode) -> Self {
+        Self {
+            width: code.width(),
+            dark: code
+                .to_colors()
+                .into_iter()
+                .map(|color| color == Color::Dark)
+                .collect(),
+        }
+    }
+
+    /// Number of modules along each side of the QR code (not including the
+    /// quiet zone).
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Whether the module at column `x` and row `y` is dark.
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+}
+
+/// The QR codes printed on a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentQrCodes {
+    /// The QR codes storing the document itself, in part order.
+    pub data: Vec<QrModules>,
+    /// The QR codes storing the document's checksum.
+    pub checksum: Vec<QrModules>,
+}
+
+fn to_modules(codes: &[QrCode]) -> Vec<QrModules> {
+    codes.iter().map(QrModules::new).collect()
+}
+
+/// The QR codes printed on `main_document` when it is printed with `options`.
+pub fn main_document_qr_codes(
+    main_document: &MainDocument,
+    options: &PdfOptions,
+) -> Result<DocumentQrCodes, Error> {
+    let (data, _) = generate_codes(
+        options.qr_encoding,
+        options.qr_error_correction,
+        options.qr_density,
+        PartType::MainDocumentData,
+        Some(&main_document.id()),
+        None,
+        main_document.to_wire(),
+    )?;
+    let checksum = generate_one_code(
+        options.qr_encoding,
+        options.qr_error_correction,
+        main_document.checksum().to_bytes(),
+    )?;
+    Ok(DocumentQrCodes {
+        data: to_modules(&data),
+        checksum: to_modules(&[checksum]),
+    })
+}
+
+/// The QR codes printed on `shard` when it is printed with `options`. The
+/// codewords are needed to decrypt the shard's document and shard ids.
+pub fn key_shard_qr_codes(
+    shard: &EncryptedKeyShard,
+    codewords: &KeyShardCodewords,
+    options: &PdfOptions,
+) -> Result<DocumentQrCodes, Error> {
+    let decrypted_shard = shard
+        .decrypt(codewords)
+        .map_err(|err| Error::OtherError(format!("failed to decrypt shard: {}", err)))?;
+    let codes = |data_type, data: Vec<u8>| {
+        generate_key_shard_codes(
+            options.qr_encoding,
+            options.qr_error_correction,
+            options.qr_density,
+            data_type,
+            &decrypted_shard,
+            data,
+        )
+    };
+    Ok(DocumentQrCodes {
+        data: to_modules(&codes(PartType::KeyShardData, shard.to_wire())?),
+        checksum: to_modules(&codes(
+            PartType::KeyShardChecksum,
+            shard.checksum().to_bytes(),
+        )?),
+    })
+}
+
+pub(super) fn generate_one_code<B: AsRef<[u8]>>(
+    encoding: QrEncoding,
+    ec_level: QrErrorCorrection,
+    data: B,
+) -> Result<QrCode, Error> {
     // NOTE: We don't use a split code for single-QR-code data segments. The
     // reason for this is that the part header takes up space, and it also
     // causes checksums to be encoded differently (meaning that the document ID
     // would no longer be the last x characters of the hash).
-    Ok(QrCode::new(multibase::encode(QRCODE_MULTIBASE, data))?)
+    Ok(QrCode::with_error_correction_level(
+        encoding.encode_qr(data),
+        ec_level.into(),
+    )?)
+}
+
+/// Generate the QR codes for some data belonging to a key shard (`data_type`
+/// must be `KeyShardData` or `KeyShardChecksum`).
+///
+/// Key shards are stored in a single QR code (without a part header, like
+/// `generate_one_code`) unless they are too large to fit, in which case they
+/// are split into parts tagged with the key shard's id.
+pub(super) fn generate_key_shard_codes<B: AsRef<[u8]>>(
+    encoding: QrEncoding,
+    ec_level: QrErrorCorrection,
+    density: QrDensity,
+    data_type: PartType,
+    shard: &KeyShard,
+    data: B,
+) -> Result<Vec<QrCode>, Error> {
+    let data = data.as_ref();
+    match generate_one_code(encoding, ec_level, data) {
+        Err(Error::GenerateQr(QrError::DataTooLong)) => (),
+        code => return code.map(|code| vec![code]),
+    }
+    let (codes, _) = generate_codes(
+        encoding,
+        ec_level,
+        density,
+        data_type,
+        Some(&shard.document_id()),
+        Some(&shard.id()),
+        data,
+    )?;
+    Ok(codes)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::v0::Backup;
 
     use quickcheck::*;
     use rand::seq::SliceRandom;
 
+    impl Arbitrary for QrErrorCorrection {
+        fn arbitrary(g: &mut Gen) -> Self {
+            *g.choose(&[Self::Low, Self::Medium, Self::Quartile, Self::High])
+                .unwrap()
+        }
+    }
+
+    impl Arbitrary for QrDensity {
+        fn arbitrary(g: &mut Gen) -> Self {
+            *g.choose(&[Self::Low, Self::Medium, Self::High]).unwrap()
+        }
+    }
+
     #[quickcheck]
-    fn split_join_qr_parts(data: Vec<u8>) -> Result<bool, Error> {
-        let mut parts = split_data(PartType::MainDocumentData, &data);
+    fn split_join_qr_parts(
+        encoding: QrEncoding,
+        ec_level: QrErrorCorrection,
+        density: QrDensity,
+        data: Vec<u8>,
+    ) -> Result<bool, Error> {
+        let mut parts = split_data(
+            encoding,
+            ec_level,
+            density,
+            PartType::MainDocumentData,
+            None,
+            None,
+            &data,
+        );
         let mut joiner = Joiner::new();
 
         parts.shuffle(&mut rand::thread_rng());
@@ -297,4 +822,323 @@ mod test {
         }
         Ok(joiner.combine_parts()? == data)
     }
+
+    #[quickcheck]
+    fn joiner_remove_readd_part(data: Vec<u8>, idx: usize) -> Result<TestResult, Error> {
+        let parts = split_data(
+            QrEncoding::default(),
+            QrErrorCorrection::default(),
+            QrDensity::default(),
+            PartType::MainDocumentData,
+            None,
+            None,
+            &data,
+        );
+        if parts.is_empty() {
+            return Ok(TestResult::discard());
+        }
+        let idx = idx % parts.len();
+        let mut joiner = Joiner::new();
+
+        for part in &parts {
+            joiner.add_part(part.clone())?;
+        }
+        if joiner.remove_part(idx).is_none() || joiner.has_part(idx) {
+            return Ok(TestResult::failed());
+        }
+        if parts.len() > 1 && joiner.missing_parts() != vec![idx] {
+            return Ok(TestResult::failed());
+        }
+        joiner.add_part(parts[idx].clone())?;
+        Ok(TestResult::from_bool(
+            joiner.complete() && joiner.combine_parts()? == data,
+        ))
+    }
+
+    #[quickcheck]
+    fn joiner_conflicting_part(data: Vec<u8>, idx: usize) -> Result<TestResult, Error> {
+        let parts = split_data(
+            QrEncoding::default(),
+            QrErrorCorrection::default(),
+            QrDensity::default(),
+            PartType::MainDocumentData,
+            None,
+            None,
+            &data,
+        );
+        if parts.is_empty() {
+            return Ok(TestResult::discard());
+        }
+        let idx = idx % parts.len();
+        let mut joiner = Joiner::new();
+        for part in &parts {
+            joiner.add_part(part.clone())?;
+        }
+
+        // Adding the same part again is harmless.
+        joiner.add_part(parts[idx].clone())?;
+
+        // A misread copy of the part is rejected, and the original is kept.
+        let mut misread = parts[idx].clone();
+        match misread.data.first_mut() {
+            Some(byte) => *byte ^= 0xff,
+            None => misread.data.push(0),
+        }
+        if !matches!(
+            joiner.add_part(misread.clone()),
+            Err(Error::ConflictingQrPart { idx: found }) if found == idx
+        ) {
+            return Ok(TestResult::failed());
+        }
+        if joiner.combine_parts()? != data {
+            return Ok(TestResult::failed());
+        }
+
+        // Once the original is removed, the other copy can be added.
+        joiner.remove_part(idx);
+        joiner.add_part(misread)?;
+        Ok(TestResult::from_bool(joiner.combine_parts()? != data))
+    }
+
+    #[quickcheck]
+    fn encoded_qr_parts_join(
+        encoding: QrEncoding,
+        ec_level: QrErrorCorrection,
+        data: Vec<u8>,
+    ) -> Result<bool, Error> {
+        let parts = split_data(
+            encoding,
+            ec_level,
+            QrDensity::default(),
+            PartType::MainDocumentData,
+            None,
+            None,
+            &data,
+        );
+        let mut joiner = Joiner::new();
+
+        for part in parts {
+            let part = Part::from_wire(QrEncoding::decode(encoding.encode(part.to_wire()))?)
+                .map_err(Error::ParseQrData)?;
+            if part.error_correction() != Some(ec_level) {
+                return Ok(false);
+            }
+            joiner.add_part(part)?;
+        }
+        Ok(joiner.combine_parts()? == data)
+    }
+
+    #[quickcheck]
+    fn main_document_qr_text_join(
+        encoding: QrEncoding,
+        density: QrDensity,
+        secret: Vec<u8>,
+    ) -> Result<bool, Error> {
+        let main_document = Backup::new(2, &secret).unwrap().main_document().clone();
+        let options = PdfOptions {
+            qr_encoding: encoding,
+            qr_density: density,
+            ..Default::default()
+        };
+        let mut joiner = Joiner::new();
+
+        for text in main_document_qr_text(&main_document, &options) {
+            joiner.add_qr_part(text)?;
+        }
+        Ok(joiner.document_id() == Some(&main_document.id())
+            && joiner.combine_parts()? == main_document.to_wire())
+    }
+
+    #[quickcheck]
+    fn main_document_qr_codes_modules(density: QrDensity, secret: Vec<u8>) -> Result<bool, Error> {
+        let main_document = Backup::new(2, &secret).unwrap().main_document().clone();
+        let options = PdfOptions {
+            qr_density: density,
+            ..Default::default()
+        };
+        let codes = main_document_qr_codes(&main_document, &options)?;
+
+        Ok(
+            codes.data.len() == main_document_qr_text(&main_document, &options).len()
+                && codes.checksum.len() == 1
+                && codes
+                    .data
+                    .iter()
+                    .chain(&codes.checksum)
+                    .all(|code| code.dark.len() == code.width() * code.width()),
+        )
+    }
+
+    #[test]
+    fn legacy_part_without_ec_level() {
+        let mut part = split_data(
+            QrEncoding::default(),
+            QrErrorCorrection::default(),
+            QrDensity::default(),
+            PartType::MainDocumentData,
+            None,
+            None,
+            b"legacy data",
+        )
+        .remove(0);
+        part.meta.ec_level = None;
+
+        let part2 = Part::from_wire(part.to_wire()).unwrap();
+        assert_eq!(part2.error_correction(), None);
+        assert_eq!(part2.data, b"legacy data");
+    }
+
+    #[quickcheck]
+    fn parts_from_different_document(data: Vec<u8>) -> Result<TestResult, Error> {
+        let (id1, id2): (DocumentId, DocumentId) =
+            ("ybndrfg8".parse().unwrap(), "ejkmcpqx".parse().unwrap());
+        let split = |document_id| {
+            split_data(
+                QrEncoding::default(),
+                QrErrorCorrection::default(),
+                QrDensity::default(),
+                PartType::MainDocumentData,
+                Some(document_id),
+                None,
+                &data,
+            )
+        };
+        let (parts1, parts2) = (split(&id1), split(&id2));
+        if parts1.is_empty() {
+            return Ok(TestResult::discard());
+        }
+
+        let part = Part::from_wire(parts2[0].to_wire()).map_err(Error::ParseQrData)?;
+        if part.document_id() != Some(&id2) {
+            return Ok(TestResult::failed());
+        }
+
+        let mut joiner = Joiner::new();
+        joiner.add_part(parts1[0].clone())?;
+        Ok(TestResult::from_bool(match joiner.add_part(part) {
+            Err(Error::QrCodeFromDifferentDocument { found, expected }) => {
+                found == id2 && expected == id1 && joiner.document_id() == Some(&id1)
+            }
+            _ => false,
+        }))
+    }
+
+    #[quickcheck]
+    fn parts_from_different_shard(
+        data: Vec<u8>,
+        id1: ShardId,
+        id2: ShardId,
+    ) -> Result<TestResult, Error> {
+        if id1 == id2 {
+            return Ok(TestResult::discard());
+        }
+        let split = |shard_id| {
+            split_data(
+                QrEncoding::default(),
+                QrErrorCorrection::default(),
+                QrDensity::default(),
+                PartType::KeyShardData,
+                None,
+                Some(shard_id),
+                &data,
+            )
+        };
+        let (parts1, parts2) = (split(&id1), split(&id2));
+        if parts1.is_empty() {
+            return Ok(TestResult::discard());
+        }
+
+        let part = Part::from_wire(parts2[0].to_wire()).map_err(Error::ParseQrData)?;
+        if part.shard_id() != Some(&id2) || part.data_type() != PartType::KeyShardData {
+            return Ok(TestResult::failed());
+        }
+
+        let mut joiner = Joiner::new();
+        joiner.add_part(parts1[0].clone())?;
+        Ok(TestResult::from_bool(matches!(
+            joiner.add_part(part),
+            Err(Error::MismatchedQrCode)
+        )))
+    }
+
+    #[test]
+    fn key_shard_codes_split_when_too_large() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let shard = backup.next_shard().unwrap();
+        let generate = |data: &[u8]| {
+            generate_key_shard_codes(
+                QrEncoding::default(),
+                QrErrorCorrection::High,
+                QrDensity::default(),
+                PartType::KeyShardData,
+                &shard,
+                data,
+            )
+            .unwrap()
+        };
+        assert_eq!(generate(&[0xaa; 200]).len(), 1);
+        assert!(generate(&[0xaa; 4096]).len() > 1);
+    }
+
+    #[test]
+    fn malformed_part_errors() {
+        // These used to panic rather than returning an error.
+        for (input, field) in [
+            (&b"Xb"[..], "header"),
+            (b"Pb", "version"),
+            (b"Pb\x80", "version"),
+            (b"Pb\x00", "data type"),
+            (b"Pb\x00X", "data type"),
+            (b"Pb\x00D", "number of parts"),
+            (b"Pb\x00D\x01", "index"),
+        ] {
+            match Part::from_wire(input) {
+                Err(err) => assert!(
+                    err.starts_with(&format!("qr part {}:", field)),
+                    "input {:?} gave error {:?}",
+                    input,
+                    err
+                ),
+                Ok(part) => panic!("input {:?} parsed as {:?}", input, part),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn higher_ec_level_smaller_parts(encoding: QrEncoding) -> bool {
+        let sizes = [
+            QrErrorCorrection::Low,
+            QrErrorCorrection::Medium,
+            QrErrorCorrection::Quartile,
+            QrErrorCorrection::High,
+        ]
+        .map(|ec_level| max_data_length(encoding, ec_level, QrDensity::default()));
+        sizes.windows(2).all(|pair| pair[0] > pair[1])
+    }
+
+    #[quickcheck]
+    fn lower_density_smaller_parts(encoding: QrEncoding, ec_level: QrErrorCorrection) -> bool {
+        let sizes = [QrDensity::Low, QrDensity::Medium, QrDensity::High]
+            .map(|density| max_data_length(encoding, ec_level, density));
+        sizes.windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    #[quickcheck]
+    fn density_limits_qr_size(
+        encoding: QrEncoding,
+        ec_level: QrErrorCorrection,
+        density: QrDensity,
+        data: Vec<u8>,
+    ) -> Result<bool, Error> {
+        let (codes, _) = generate_codes(
+            encoding,
+            ec_level,
+            density,
+            PartType::MainDocumentData,
+            None,
+            None,
+            &data,
+        )?;
+        Ok(codes.iter().all(|code| code.width() <= density.modules()))
+    }
 }
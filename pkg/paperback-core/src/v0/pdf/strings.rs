@@ -0,0 +1,792 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Translations of the text printed on paperback PDFs.
+
+use crate::v0::{format_date, ShardProvenance};
+
+/// Language of the explanatory text printed on paperback PDFs. This only
+/// affects how documents look, not the data stored in them.
+///
+/// NOTE: Only languages written with Latin script are supported, because the
+///       embedded fonts do not contain any other glyphs.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PdfLanguage {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+impl PdfLanguage {
+    pub const ALL: &'static [PdfLanguage] = &[
+        PdfLanguage::English,
+        PdfLanguage::German,
+        PdfLanguage::French,
+        PdfLanguage::Spanish,
+    ];
+
+    /// The ISO 639-1 code of the language.
+    pub fn language_code(self) -> &'static str {
+        match self {
+            PdfLanguage::English => "en",
+            PdfLanguage::German => "de",
+            PdfLanguage::French => "fr",
+            PdfLanguage::Spanish => "es",
+        }
+    }
+
+    pub fn from_language_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|language| language.language_code() == code)
+    }
+
+    pub(super) fn strings(self) -> &'static Strings {
+        match self {
+            PdfLanguage::English => &ENGLISH,
+            PdfLanguage::German => &GERMAN,
+            PdfLanguage::French => &FRENCH,
+            PdfLanguage::Spanish => &SPANISH,
+        }
+    }
+}
+
+/// All of the text printed on paperback PDFs, in one language.
+///
+/// Lines of text are broken by hand (there is no text layout), so each
+/// translation must keep to roughly the same line lengths as the English text.
+pub(super) struct Strings {
+    // Header labels.
+    pub(super) document: &'static str,
+    pub(super) shard: &'static str,
+    pub(super) main_document: &'static str,
+    pub(super) key_shard: &'static str,
+    pub(super) shard_manifest: &'static str,
+    pub(super) activity_record: &'static str,
+    pub(super) text_fallback: &'static str,
+
+    // Creation and expiry dates.
+    pub(super) created_expires: fn(&str, &str) -> String,
+    pub(super) created: fn(&str) -> String,
+    pub(super) expires: fn(&str) -> String,
+
+    // Main document.
+    pub(super) main_document_title: fn(&str) -> String,
+    pub(super) main_document_details: fn(u32) -> [String; 3],
+    pub(super) main_document_fingerprint: fn(&str) -> String,
+    pub(super) document_banner: &'static str,
+    pub(super) document_description: &'static str,
+    pub(super) booklet_note: fn(usize) -> String,
+    pub(super) checksum_banner: &'static str,
+    pub(super) main_document_checksum_description: &'static str,
+    pub(super) booklet_caption: fn(usize, usize) -> String,
+    pub(super) booklet_description: fn(&str) -> String,
+
+    // Shard manifest.
+    pub(super) manifest_details: [&'static str; 2],
+    pub(super) manifest_quorum: fn(u32, usize) -> String,
+    pub(super) manifest_checksum: fn(&str) -> String,
+    pub(super) manifest_banner: &'static str,
+    pub(super) manifest_description: &'static str,
+    pub(super) held_by: &'static str,
+    pub(super) date: &'static str,
+
+    // Recovery instructions.
+    pub(super) recovery_instructions: &'static str,
+    pub(super) recovery_details: [&'static str; 2],
+    pub(super) recovery_quorum: fn(u32) -> String,
+    pub(super) holders_banner: &'static str,
+    pub(super) holders_description: &'static str,
+    pub(super) contacted: &'static str,
+    pub(super) recovery_steps: [(&'static str, &'static [&'static str]); 2],
+
+    // Activity record.
+    pub(super) activity_details: [&'static str; 2],
+    pub(super) activity_minted: fn(&str) -> String,
+    pub(super) activity_quorum: fn(&str) -> String,
+    pub(super) activity_banner: &'static str,
+    pub(super) activity_description: &'static str,
+    pub(super) record_banner: &'static str,
+    pub(super) record_description: &'static str,
+
+    // Key shard.
+    pub(super) key_shard_details: [&'static str; 2],
+    pub(super) shard_banner: &'static str,
+    pub(super) shard_description: &'static str,
+    pub(super) shard_checksum_description: fn(&str) -> String,
+    pub(super) codewords_banner: &'static str,
+    pub(super) codewords_description: &'static str,
+    pub(super) codewords: &'static str,
+    pub(super) recovery_url: &'static str,
+    pub(super) provenance_new: fn(usize, &str) -> String,
+    pub(super) provenance_recreated: fn(&str) -> String,
+    pub(super) provenance_quorum: fn(&str) -> String,
+
+    // Key shard holder instructions.
+    pub(super) instructions_title: &'static str,
+    pub(super) instructions_shard: fn(&str, &str) -> String,
+    pub(super) instructions: [(&'static str, &'static [&'static str]); 3],
+}
+
+impl Strings {
+    /// Describe where a key shard came from (see `ShardProvenance`'s
+    /// `Display` implementation for the English version).
+    pub(super) fn provenance(&self, provenance: &ShardProvenance) -> String {
+        let minted = format_date(provenance.minted);
+        let origin = match provenance.sequence {
+            Some(sequence) => (self.provenance_new)(sequence, &minted),
+            None => (self.provenance_recreated)(&minted),
+        };
+        origin + &(self.provenance_quorum)(&provenance.quorum.join(" "))
+    }
+}
+
+static ENGLISH: Strings = Strings {
+    document: "Document",
+    shard: "Shard",
+    main_document: "Main Document",
+    key_shard: "Key Shard",
+    shard_manifest: "Shard Manifest",
+    activity_record: "Activity Record",
+    text_fallback: "text fallback if barcode scanning fails",
+
+    created_expires: |created, expires| format!("Created {}, expires {}.", created, expires),
+    created: |created| format!("Created {}.", created),
+    expires: |expires| format!("Expires {}.", expires),
+
+    main_document_title: |title| format!("Title: {}", title),
+    main_document_details: |quorum_size| {
+        [
+            format!(
+                "This is the main document of a paperback backup. When combined with {} unique",
+                quorum_size
+            ),
+            "key shards, this document can be recovered. In order to recover this document,"
+                .to_string(),
+            "download the latest version of paperback from cyphar.com/paperback.".to_string(),
+        ]
+    },
+    main_document_fingerprint: |words| {
+        format!(
+            "Fingerprint: {} (read these words aloud to compare copies).",
+            words
+        )
+    },
+    document_banner: "① Document",
+    document_description: "Data section, encrypted with secret key stored in the key shards.",
+    booklet_note: |num_codes| format!("The {} codes are on the following pages.", num_codes),
+    checksum_banner: "② Checksum",
+    main_document_checksum_description: "Verifies the document was scanned correctly. The last 8 characters are the document identifier.",
+    booklet_caption: |idx, num_codes| format!("Code {} of {}", idx, num_codes),
+    booklet_description: |id| format!("Main document {}.", id),
+
+    manifest_details: [
+        "This is the key shard manifest of a paperback backup. It contains no secret",
+        "information, and is used to keep track of who holds each key shard.",
+    ],
+    manifest_quorum: |quorum_size, num_shards| {
+        format!(
+            "Any {} of the {} key shards listed below can recover the backup.",
+            quorum_size, num_shards
+        )
+    },
+    manifest_checksum: |checksum| format!("Main document checksum: {}", checksum),
+    manifest_banner: "Key Shards",
+    manifest_description: "Record who each key shard was given to, and when.",
+    held_by: "Held by",
+    date: "Date",
+
+    recovery_instructions: "Recovery Instructions",
+    recovery_details: [
+        "These are the instructions for recovering a paperback backup. They contain no",
+        "secret information, and are meant for whoever needs to recover the backup.",
+    ],
+    recovery_quorum: |quorum_size| {
+        format!(
+            "Any {} of the key shards (and the main document) are needed to recover the backup.",
+            quorum_size
+        )
+    },
+    holders_banner: "Key Shard Holders",
+    holders_description: "Ask these people for their key shards, and record when each was contacted.",
+    contacted: "Contacted",
+    recovery_steps: [
+        ("Recovering the backup", &[
+            "1. Find the main document of the backup (with the document identifier above).",
+            "2. Contact the key shard holders listed above, and collect enough key shards",
+            "   (with their codewords) in person. Never ask for them to be sent online.",
+            "3. Check that every key shard has the document identifier printed above.",
+            "4. Download the latest version of paperback from cyphar.com/paperback and run",
+            "   \"paperback recover --interactive\" on an offline computer, entering the",
+            "   main document and each key shard (with its codewords) when asked.",
+        ]),
+        ("After recovering the backup", &[
+            "The recovered secret gives full access to whatever the backup protects, so",
+            "store it safely and destroy any copies that are no longer needed. The key",
+            "shards can be returned to their holders.",
+        ]),
+    ],
+
+    activity_details: [
+        "This is an activity record of a paperback backup. It contains no secret",
+        "information, and records that new key shards were minted for the backup.",
+    ],
+    activity_minted: |minted| format!("Key shards minted {}.", minted),
+    activity_quorum: |ids| format!("Minted by a quorum of the key shards [{}].", ids),
+    activity_banner: "Minted Key Shards",
+    activity_description: "Key shards minted by the quorum.",
+    record_banner: "Record",
+    record_description: "Signed copy of this record, which can be verified using paperback.",
+
+    key_shard_details: [
+        "This is a key shard of a paperback backup.",
+        "See cyphar.com/paperback for more details.",
+    ],
+    shard_banner: "① Shard",
+    shard_description: "Key shard data, encrypted using the codewords.",
+    shard_checksum_description: |words| {
+        format!("Verifies the shard was scanned. Fingerprint: {}.", words)
+    },
+    codewords_banner: "③ Codewords",
+    codewords_description: "Encrypts the key shard data. Can be optionally cut off.",
+    codewords: "Codewords",
+    recovery_url: "How to recover:",
+    provenance_new: |sequence, minted| format!("New key shard {}, minted {}", sequence, minted),
+    provenance_recreated: |minted| format!("Re-created {}", minted),
+    provenance_quorum: |ids| format!(" from a quorum of [{}].", ids),
+
+    instructions_title: "Instructions",
+    instructions_shard: |shard_id, document_id| {
+        format!("For the holder of key shard {} (document {}).", shard_id, document_id)
+    },
+    instructions: [
+        ("What is this?", &[
+            "The owner of a paperback backup has trusted you with this key shard.",
+            "On its own it is useless. The backup can only be unlocked by bringing",
+            "together several key shards, each held by a different person.",
+            "Keep it somewhere safe and private, as you would a passport.",
+        ]),
+        ("If the owner asks for it", &[
+            "Only hand this key shard over in person, to the owner or to someone",
+            "you have confirmed (face-to-face or by phone) that the owner sent.",
+            "Check that the shard and document identifiers above match the ones",
+            "they are expecting before handing it over.",
+        ]),
+        ("How to avoid being scammed", &[
+            "Never send a photo or copy of this shard, or read its codewords out.",
+            "Be wary of urgent requests, new phone numbers or email addresses, and",
+            "people who claim to be acting for the owner. If in doubt, contact the",
+            "owner using details you already had. Nobody from paperback will ever",
+            "ask you for this key shard.",
+        ]),
+    ],
+};
+
+static GERMAN: Strings = Strings {
+    document: "Dokument",
+    shard: "Teil",
+    main_document: "Hauptdokument",
+    key_shard: "Schlüsselteil",
+    shard_manifest: "Teileverzeichnis",
+    activity_record: "Aktivitätsprotokoll",
+    text_fallback: "Text als Ersatz, falls das Scannen fehlschlägt",
+
+    created_expires: |created, expires| {
+        format!("Erstellt am {}, läuft ab am {}.", created, expires)
+    },
+    created: |created| format!("Erstellt am {}.", created),
+    expires: |expires| format!("Läuft ab am {}.", expires),
+
+    main_document_title: |title| format!("Titel: {}", title),
+    main_document_details: |quorum_size| {
+        [
+            format!(
+                "Dies ist das Hauptdokument eines paperback-Backups. Zusammen mit {} verschiedenen",
+                quorum_size
+            ),
+            "Schlüsselteilen kann dieses Dokument wiederhergestellt werden. Laden Sie dazu die"
+                .to_string(),
+            "neueste Version von paperback von cyphar.com/paperback herunter.".to_string(),
+        ]
+    },
+    main_document_fingerprint: |words| {
+        format!(
+            "Fingerabdruck: {} (zum Vergleichen von Kopien laut vorlesen).",
+            words
+        )
+    },
+    document_banner: "① Dokument",
+    document_description: "Datenteil, verschlüsselt mit dem in den Schlüsselteilen gespeicherten Schlüssel.",
+    booklet_note: |num_codes| format!("Die {} Codes folgen auf den nächsten Seiten.", num_codes),
+    checksum_banner: "② Prüfsumme",
+    main_document_checksum_description: "Bestätigt, dass das Dokument korrekt gescannt wurde. Die letzten 8 Zeichen sind die Dokumentkennung.",
+    booklet_caption: |idx, num_codes| format!("Code {} von {}", idx, num_codes),
+    booklet_description: |id| format!("Hauptdokument {}.", id),
+
+    manifest_details: [
+        "Dies ist das Verzeichnis der Schlüsselteile eines paperback-Backups. Es enthält keine",
+        "geheimen Informationen und hält fest, wer welchen Schlüsselteil besitzt.",
+    ],
+    manifest_quorum: |quorum_size, num_shards| {
+        format!(
+            "Beliebige {} der {} unten aufgeführten Schlüsselteile können das Backup wiederherstellen.",
+            quorum_size, num_shards
+        )
+    },
+    manifest_checksum: |checksum| format!("Prüfsumme des Hauptdokuments: {}", checksum),
+    manifest_banner: "Schlüsselteile",
+    manifest_description: "Notieren Sie, wem und wann jeder Schlüsselteil übergeben wurde.",
+    held_by: "Besitzer",
+    date: "Datum",
+
+    recovery_instructions: "Anleitung zur Wiederherstellung",
+    recovery_details: [
+        "Dies ist die Anleitung zur Wiederherstellung eines paperback-Backups. Sie enthält",
+        "keine geheimen Informationen und richtet sich an alle, die das Backup benötigen.",
+    ],
+    recovery_quorum: |quorum_size| {
+        format!(
+            "Beliebige {} Schlüsselteile (und das Hauptdokument) stellen das Backup wieder her.",
+            quorum_size
+        )
+    },
+    holders_banner: "Besitzer der Schlüsselteile",
+    holders_description: "Bitten Sie diese Personen um ihre Schlüsselteile und notieren Sie, wann sie kontaktiert wurden.",
+    contacted: "Kontaktiert",
+    recovery_steps: [
+        ("Wiederherstellung des Backups", &[
+            "1. Suchen Sie das Hauptdokument des Backups (mit der obigen Dokumentkennung).",
+            "2. Kontaktieren Sie die oben aufgeführten Besitzer und sammeln Sie genügend",
+            "   Schlüsselteile (mit Codewörtern) persönlich ein, niemals über das Internet.",
+            "3. Prüfen Sie, dass jeder Schlüsselteil die obige Dokumentkennung trägt.",
+            "4. Laden Sie paperback von cyphar.com/paperback herunter und führen Sie",
+            "   \"paperback recover --interactive\" auf einem Offline-Computer aus. Geben",
+            "   Sie das Hauptdokument und jeden Schlüsselteil (mit Codewörtern) ein.",
+        ]),
+        ("Nach der Wiederherstellung", &[
+            "Das wiederhergestellte Geheimnis gewährt vollen Zugriff auf alles, was das",
+            "Backup schützt. Bewahren Sie es sicher auf und vernichten Sie nicht mehr",
+            "benötigte Kopien. Die Schlüsselteile können zurückgegeben werden.",
+        ]),
+    ],
+
+    activity_details: [
+        "Dies ist ein Aktivitätsprotokoll eines paperback-Backups. Es enthält keine geheimen",
+        "Informationen und hält fest, dass neue Schlüsselteile für das Backup erstellt wurden.",
+    ],
+    activity_minted: |minted| format!("Schlüsselteile erstellt am {}.", minted),
+    activity_quorum: |ids| format!("Erstellt von einem Quorum der Schlüsselteile [{}].", ids),
+    activity_banner: "Erstellte Schlüsselteile",
+    activity_description: "Vom Quorum erstellte Schlüsselteile.",
+    record_banner: "Protokoll",
+    record_description: "Signierte Kopie dieses Protokolls, die mit paperback überprüft werden kann.",
+
+    key_shard_details: [
+        "Dies ist ein Teil eines paperback-Backups.",
+        "Mehr dazu unter cyphar.com/paperback.",
+    ],
+    shard_banner: "① Teil",
+    shard_description: "Daten des Schlüsselteils, mit den Codewörtern verschlüsselt.",
+    shard_checksum_description: |words| {
+        format!(
+            "Bestätigt, dass der Teil gescannt wurde. Fingerabdruck: {}.",
+            words
+        )
+    },
+    codewords_banner: "③ Codewörter",
+    codewords_description: "Verschlüsselt die Daten des Schlüsselteils. Kann abgeschnitten werden.",
+    codewords: "Codewörter",
+    recovery_url: "Wiederherstellung:",
+    provenance_new: |sequence, minted| {
+        format!("Neuer Schlüsselteil {}, erstellt am {}", sequence, minted)
+    },
+    provenance_recreated: |minted| format!("Neu erstellt am {}", minted),
+    provenance_quorum: |ids| format!(" aus einem Quorum von [{}].", ids),
+
+    instructions_title: "Hinweise",
+    instructions_shard: |shard_id, document_id| {
+        format!(
+            "Für den Besitzer von Schlüsselteil {} (Dokument {}).",
+            shard_id, document_id
+        )
+    },
+    instructions: [
+        ("Was ist das?", &[
+            "Der Besitzer eines paperback-Backups hat Ihnen diesen Schlüsselteil",
+            "anvertraut. Allein ist er nutzlos. Das Backup kann nur mit mehreren",
+            "Schlüsselteilen entsperrt werden, die verschiedene Personen besitzen.",
+            "Bewahren Sie ihn sicher und privat auf, wie einen Reisepass.",
+        ]),
+        ("Wenn der Besitzer danach fragt", &[
+            "Übergeben Sie diesen Schlüsselteil nur persönlich, an den Besitzer",
+            "oder an jemanden, der nachweislich (persönlich oder telefonisch",
+            "bestätigt) vom Besitzer geschickt wurde. Prüfen Sie vorher, dass die",
+            "Kennungen von Teil und Dokument oben mit den erwarteten übereinstimmen.",
+        ]),
+        ("Wie Sie Betrug vermeiden", &[
+            "Senden Sie nie ein Foto oder eine Kopie dieses Teils und lesen Sie",
+            "nie die Codewörter vor. Seien Sie misstrauisch bei dringenden Anfragen,",
+            "neuen Telefonnummern oder E-Mail-Adressen und bei Personen, die angeblich",
+            "im Auftrag des Besitzers handeln. Kontaktieren Sie im Zweifel den Besitzer",
+            "über Ihnen bekannte Kontaktdaten. Niemand von paperback wird Sie je",
+            "nach diesem Schlüsselteil fragen.",
+        ]),
+    ],
+};
+
+static FRENCH: Strings = Strings {
+    document: "Document",
+    shard: "Fragment",
+    main_document: "Document principal",
+    key_shard: "Fragment de clé",
+    shard_manifest: "Registre des fragments",
+    activity_record: "Journal d'activité",
+    text_fallback: "texte de secours si la lecture du code échoue",
+
+    created_expires: |created, expires| format!("Créé le {}, expire le {}.", created, expires),
+    created: |created| format!("Créé le {}.", created),
+    expires: |expires| format!("Expire le {}.", expires),
+
+    main_document_title: |title| format!("Titre : {}", title),
+    main_document_details: |quorum_size| {
+        [
+            format!(
+                "Ceci est le document principal d'une sauvegarde paperback. Avec {} fragments de clé",
+                quorum_size
+            ),
+            "distincts, ce document peut être récupéré. Pour récupérer ce document, téléchargez"
+                .to_string(),
+            "la dernière version de paperback sur cyphar.com/paperback.".to_string(),
+        ]
+    },
+    main_document_fingerprint: |words| {
+        format!(
+            "Empreinte : {} (lisez ces mots à voix haute pour comparer les copies).",
+            words
+        )
+    },
+    document_banner: "① Document",
+    document_description: "Section de données, chiffrée avec la clé secrète stockée dans les fragments de clé.",
+    booklet_note: |num_codes| format!("Les {} codes figurent sur les pages suivantes.", num_codes),
+    checksum_banner: "② Somme de contrôle",
+    main_document_checksum_description: "Vérifie que le document a été bien numérisé. Les 8 derniers caractères identifient le document.",
+    booklet_caption: |idx, num_codes| format!("Code {} sur {}", idx, num_codes),
+    booklet_description: |id| format!("Document principal {}.", id),
+
+    manifest_details: [
+        "Ceci est le registre des fragments de clé d'une sauvegarde paperback. Il ne contient",
+        "aucune information secrète et sert à savoir qui détient chaque fragment de clé.",
+    ],
+    manifest_quorum: |quorum_size, num_shards| {
+        format!(
+            "{} des {} fragments de clé listés ci-dessous suffisent à récupérer la sauvegarde.",
+            quorum_size, num_shards
+        )
+    },
+    manifest_checksum: |checksum| format!("Somme de contrôle du document principal : {}", checksum),
+    manifest_banner: "Fragments de clé",
+    manifest_description: "Notez à qui chaque fragment de clé a été remis, et quand.",
+    held_by: "Détenu par",
+    date: "Date",
+
+    recovery_instructions: "Instructions de récupération",
+    recovery_details: [
+        "Ceci sont les instructions pour récupérer une sauvegarde paperback. Elles ne",
+        "contiennent aucune information secrète, et sont destinées à qui doit la récupérer.",
+    ],
+    recovery_quorum: |quorum_size| {
+        format!(
+            "{} fragments de clé (et le document principal) suffisent à récupérer la sauvegarde.",
+            quorum_size
+        )
+    },
+    holders_banner: "Détenteurs des fragments de clé",
+    holders_description: "Demandez leurs fragments de clé à ces personnes, et notez quand chacune a été contactée.",
+    contacted: "Contacté le",
+    recovery_steps: [
+        ("Récupérer la sauvegarde", &[
+            "1. Trouvez le document principal de la sauvegarde (avec l'identifiant ci-dessus).",
+            "2. Contactez les détenteurs listés ci-dessus, et récupérez en personne assez de",
+            "   fragments de clé (avec leurs mots de code). Jamais par Internet.",
+            "3. Vérifiez que chaque fragment de clé porte l'identifiant de document ci-dessus.",
+            "4. Téléchargez paperback sur cyphar.com/paperback et lancez",
+            "   \"paperback recover --interactive\" sur un ordinateur hors ligne, puis saisissez",
+            "   le document principal et chaque fragment de clé (avec ses mots de code).",
+        ]),
+        ("Après la récupération", &[
+            "Le secret récupéré donne un accès complet à tout ce que protège la sauvegarde.",
+            "Conservez-le en lieu sûr et détruisez les copies devenues inutiles. Les",
+            "fragments de clé peuvent être rendus à leurs détenteurs.",
+        ]),
+    ],
+
+    activity_details: [
+        "Ceci est un journal d'activité d'une sauvegarde paperback. Il ne contient aucune",
+        "information secrète, et atteste que de nouveaux fragments de clé ont été créés.",
+    ],
+    activity_minted: |minted| format!("Fragments de clé créés le {}.", minted),
+    activity_quorum: |ids| format!("Créés par un quorum des fragments de clé [{}].", ids),
+    activity_banner: "Fragments de clé créés",
+    activity_description: "Fragments de clé créés par le quorum.",
+    record_banner: "Journal",
+    record_description: "Copie signée de ce journal, vérifiable avec paperback.",
+
+    key_shard_details: [
+        "Fragment de clé d'une sauvegarde paperback.",
+        "Plus d'informations sur cyphar.com/paperback.",
+    ],
+    shard_banner: "① Fragment",
+    shard_description: "Données du fragment de clé, chiffrées avec les mots de code.",
+    shard_checksum_description: |words| {
+        format!(
+            "Vérifie que le fragment a été numérisé. Empreinte : {}.",
+            words
+        )
+    },
+    codewords_banner: "③ Mots de code",
+    codewords_description: "Chiffrent les données du fragment de clé. Peut être découpé.",
+    codewords: "Mots de code",
+    recovery_url: "Récupération :",
+    provenance_new: |sequence, minted| {
+        format!("Nouveau fragment de clé {}, créé le {}", sequence, minted)
+    },
+    provenance_recreated: |minted| format!("Recréé le {}", minted),
+    provenance_quorum: |ids| format!(" à partir d'un quorum de [{}].", ids),
+
+    instructions_title: "Instructions",
+    instructions_shard: |shard_id, document_id| {
+        format!(
+            "Pour le détenteur du fragment de clé {} (document {}).",
+            shard_id, document_id
+        )
+    },
+    instructions: [
+        ("Qu'est-ce que c'est ?", &[
+            "Le propriétaire d'une sauvegarde paperback vous a confié ce fragment",
+            "de clé. Seul, il est inutile. La sauvegarde ne peut être déverrouillée",
+            "qu'en réunissant plusieurs fragments, détenus par différentes personnes.",
+            "Conservez-le en lieu sûr et privé, comme un passeport.",
+        ]),
+        ("Si le propriétaire le demande", &[
+            "Ne remettez ce fragment qu'en main propre, au propriétaire ou à une",
+            "personne dont vous avez confirmé (en personne ou par téléphone) qu'elle",
+            "vient de sa part. Vérifiez d'abord que les identifiants du fragment et",
+            "du document ci-dessus correspondent à ceux qui sont attendus.",
+        ]),
+        ("Comment éviter les arnaques", &[
+            "N'envoyez jamais de photo ou de copie de ce fragment, et ne lisez jamais",
+            "ses mots de code à voix haute. Méfiez-vous des demandes urgentes, des",
+            "nouveaux numéros ou adresses e-mail, et des personnes prétendant agir",
+            "pour le propriétaire. En cas de doute, contactez-le avec des coordonnées",
+            "que vous aviez déjà. Personne de paperback ne vous demandera jamais ce",
+            "fragment de clé.",
+        ]),
+    ],
+};
+
+static SPANISH: Strings = Strings {
+    document: "Documento",
+    shard: "Fragmento",
+    main_document: "Documento principal",
+    key_shard: "Fragmento de clave",
+    shard_manifest: "Registro de fragmentos",
+    activity_record: "Registro de actividad",
+    text_fallback: "texto alternativo si falla el escaneo del código",
+
+    created_expires: |created, expires| format!("Creado el {}, caduca el {}.", created, expires),
+    created: |created| format!("Creado el {}.", created),
+    expires: |expires| format!("Caduca el {}.", expires),
+
+    main_document_title: |title| format!("Título: {}", title),
+    main_document_details: |quorum_size| {
+        [
+            format!(
+                "Este es el documento principal de una copia de seguridad de paperback. Junto con {}",
+                quorum_size
+            ),
+            "fragmentos de clave distintos, este documento puede recuperarse. Para recuperarlo,"
+                .to_string(),
+            "descargue la última versión de paperback desde cyphar.com/paperback.".to_string(),
+        ]
+    },
+    main_document_fingerprint: |words| {
+        format!(
+            "Huella: {} (lea estas palabras en voz alta para comparar copias).",
+            words
+        )
+    },
+    document_banner: "① Documento",
+    document_description: "Sección de datos, cifrada con la clave secreta guardada en los fragmentos de clave.",
+    booklet_note: |num_codes| format!("Los {} códigos están en las páginas siguientes.", num_codes),
+    checksum_banner: "② Suma de verificación",
+    main_document_checksum_description: "Verifica que el documento se escaneó bien. Los últimos 8 caracteres identifican el documento.",
+    booklet_caption: |idx, num_codes| format!("Código {} de {}", idx, num_codes),
+    booklet_description: |id| format!("Documento principal {}.", id),
+
+    manifest_details: [
+        "Este es el registro de fragmentos de clave de una copia de seguridad de paperback. No",
+        "contiene información secreta y sirve para saber quién tiene cada fragmento de clave.",
+    ],
+    manifest_quorum: |quorum_size, num_shards| {
+        format!(
+            "Cualesquiera {} de los {} fragmentos de clave listados abajo pueden recuperar la copia.",
+            quorum_size, num_shards
+        )
+    },
+    manifest_checksum: |checksum| {
+        format!("Suma de verificación del documento principal: {}", checksum)
+    },
+    manifest_banner: "Fragmentos de clave",
+    manifest_description: "Anote a quién se entregó cada fragmento de clave, y cuándo.",
+    held_by: "Entregado a",
+    date: "Fecha",
+
+    recovery_instructions: "Instrucciones de recuperación",
+    recovery_details: [
+        "Estas son las instrucciones para recuperar una copia de seguridad paperback. No",
+        "contienen información secreta, y son para quien necesite recuperar la copia.",
+    ],
+    recovery_quorum: |quorum_size| {
+        format!(
+            "Cualesquiera {} fragmentos de clave (y el documento principal) recuperan la copia.",
+            quorum_size
+        )
+    },
+    holders_banner: "Poseedores de los fragmentos de clave",
+    holders_description: "Pida a estas personas sus fragmentos de clave, y anote cuándo contactó a cada una.",
+    contacted: "Contactado",
+    recovery_steps: [
+        ("Recuperar la copia de seguridad", &[
+            "1. Busque el documento principal de la copia (con el identificador de arriba).",
+            "2. Contacte a los poseedores listados arriba y reúna en persona suficientes",
+            "   fragmentos de clave (con sus palabras clave). Nunca por Internet.",
+            "3. Compruebe que cada fragmento de clave lleva el identificador de arriba.",
+            "4. Descargue paperback de cyphar.com/paperback y ejecute",
+            "   \"paperback recover --interactive\" en un ordenador sin conexión, introduciendo",
+            "   el documento principal y cada fragmento de clave (con sus palabras clave).",
+        ]),
+        ("Después de la recuperación", &[
+            "El secreto recuperado da acceso completo a todo lo que protege la copia.",
+            "Guárdelo de forma segura y destruya las copias que ya no necesite. Los",
+            "fragmentos de clave pueden devolverse a sus poseedores.",
+        ]),
+    ],
+
+    activity_details: [
+        "Este es un registro de actividad de una copia de seguridad paperback. No contiene",
+        "información secreta, y deja constancia de que se crearon nuevos fragmentos de clave.",
+    ],
+    activity_minted: |minted| format!("Fragmentos de clave creados el {}.", minted),
+    activity_quorum: |ids| format!("Creados por un quórum de los fragmentos de clave [{}].", ids),
+    activity_banner: "Fragmentos de clave creados",
+    activity_description: "Fragmentos de clave creados por el quórum.",
+    record_banner: "Registro",
+    record_description: "Copia firmada de este registro, verificable con paperback.",
+
+    key_shard_details: [
+        "Fragmento de clave de una copia de paperback.",
+        "Más información en cyphar.com/paperback.",
+    ],
+    shard_banner: "① Fragmento",
+    shard_description: "Datos del fragmento de clave, cifrados con las palabras clave.",
+    shard_checksum_description: |words| {
+        format!("Verifica que el fragmento se escaneó. Huella: {}.", words)
+    },
+    codewords_banner: "③ Palabras clave",
+    codewords_description: "Cifran los datos del fragmento de clave. Se puede recortar.",
+    codewords: "Palabras clave",
+    recovery_url: "Cómo recuperar:",
+    provenance_new: |sequence, minted| {
+        format!("Nuevo fragmento de clave {}, creado el {}", sequence, minted)
+    },
+    provenance_recreated: |minted| format!("Recreado el {}", minted),
+    provenance_quorum: |ids| format!(" a partir de un quórum de [{}].", ids),
+
+    instructions_title: "Instrucciones",
+    instructions_shard: |shard_id, document_id| {
+        format!(
+            "Para quien guarda el fragmento de clave {} (documento {}).",
+            shard_id, document_id
+        )
+    },
+    instructions: [
+        ("¿Qué es esto?", &[
+            "El propietario de una copia de seguridad de paperback le ha confiado",
+            "este fragmento de clave. Por sí solo es inútil. La copia solo puede",
+            "desbloquearse reuniendo varios fragmentos, cada uno en manos de una",
+            "persona distinta. Guárdelo en un lugar seguro y privado, como un pasaporte.",
+        ]),
+        ("Si el propietario lo pide", &[
+            "Entregue este fragmento solo en persona, al propietario o a alguien que",
+            "haya confirmado (en persona o por teléfono) que viene de su parte.",
+            "Compruebe antes que los identificadores del fragmento y del documento",
+            "de arriba coinciden con los que se esperan.",
+        ]),
+        ("Cómo evitar estafas", &[
+            "Nunca envíe una foto o copia de este fragmento, ni lea en voz alta sus",
+            "palabras clave. Desconfíe de peticiones urgentes, números de teléfono o",
+            "correos nuevos, y de quien diga actuar en nombre del propietario. Ante",
+            "la duda, contacte con el propietario usando datos que ya tenía. Nadie de",
+            "paperback le pedirá nunca este fragmento de clave.",
+        ]),
+    ],
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::ShardId;
+
+    use std::time::SystemTime;
+
+    #[test]
+    fn language_code_roundtrip() {
+        for &language in PdfLanguage::ALL {
+            assert_eq!(
+                PdfLanguage::from_language_code(language.language_code()),
+                Some(language)
+            );
+        }
+        assert_eq!(PdfLanguage::from_language_code("ja"), None);
+    }
+
+    #[test]
+    fn english_provenance_matches_display() {
+        let provenance = ShardProvenance {
+            sequence: Some(3),
+            minted: SystemTime::now(),
+            quorum: vec![ShardId::from_x_value(1), ShardId::from_x_value(2)],
+        };
+        assert_eq!(
+            PdfLanguage::English.strings().provenance(&provenance),
+            provenance.to_string()
+        );
+
+        let provenance = ShardProvenance {
+            sequence: None,
+            ..provenance
+        };
+        assert_eq!(
+            PdfLanguage::English.strings().provenance(&provenance),
+            provenance.to_string()
+        );
+    }
+}
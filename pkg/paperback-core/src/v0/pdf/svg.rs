@@ -0,0 +1,1178 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    pdf::{
+        generate::{
+            fallback_lines, header_width, timestamp_details, A4_HEIGHT, A4_MARGIN, A4_WIDTH,
+            A5_HEIGHT, A5_MARGIN, A5_WIDTH, CODEWORD_QR_SIZE, LANDSCAPE_CHECKSUM_QR_FRACTION,
+            MAX_MAIN_DOCUMENT_CODES, QR_MARGIN, RECOVERY_URL_QR_SIZE, SCISSORS_SVG,
+        },
+        qr,
+        qr::PartType,
+        Error, MainDocumentLayout, PdfOptions,
+    },
+    EncryptedKeyShard, KeyShard, KeyShardCodewords, MainDocument, ShardProvenance, ToWire,
+};
+
+use std::fmt::Write;
+
+use printpdf::{Mm, Pt};
+use qrcode::{Color, QrCode};
+use tracing::instrument;
+use zeroize::Zeroizing;
+
+/// Render a paperback document as standalone SVG images, one for each page.
+///
+/// The pages have the same layout as the PDF generated by
+/// [`ToPdf`](super::ToPdf) with the same options, which makes them easier to
+/// post-process (such as for laser engraving). The fonts are referenced by
+/// name rather than embedded, so text is rendered with whatever fonts are
+/// available (falling back to a generic monospace or serif font).
+pub trait ToSvg {
+    fn to_svg(&self) -> Result<Vec<String>, Error> {
+        self.to_svg_with_options(&PdfOptions::default())
+    }
+
+    fn to_svg_with_options(&self, options: &PdfOptions) -> Result<Vec<String>, Error>;
+}
+
+mod colours {
+    pub(super) const BLACK: &str = "#000000";
+    pub(super) const GREY: &str = "#666666";
+    pub(super) const LIGHT_GREY: &str = "#999999";
+    pub(super) const WHITE: &str = "#ffffff";
+    pub(super) const MAIN_DOCUMENT_TRIM: &str = "#ff6600";
+    pub(super) const KEY_SHARD_TRIM: &str = "#2c9f2c";
+}
+
+/// The fonts used by paperback documents (see `generate::Fonts`).
+#[derive(Clone, Copy, Debug)]
+enum Font {
+    Monospace,
+    MonospaceBold,
+    Text,
+}
+
+impl Font {
+    fn attributes(self) -> &'static str {
+        match self {
+            Self::Monospace => r#"font-family="'B612 Mono', monospace""#,
+            Self::MonospaceBold => r#"font-family="'B612 Mono', monospace" font-weight="bold""#,
+            Self::Text => r#"font-family="'Roboto Slab', serif""#,
+        }
+    }
+}
+
+struct Text<'a> {
+    inner: &'a str,
+    colour: &'static str,
+    font: Font,
+    font_size: Pt,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A single SVG page. Unlike PDF coordinates, positions are measured from the
+/// top-left corner of the page.
+struct Page {
+    width: Mm,
+    height: Mm,
+    body: String,
+}
+
+impl Page {
+    fn new(width: Mm, height: Mm) -> Self {
+        Self {
+            width,
+            height,
+            body: String::new(),
+        }
+    }
+
+    fn open_text(&mut self, (x, y): (Mm, Mm), font: Font, font_size: Pt, colour: &str) {
+        write!(
+            self.body,
+            r#"<text x="{:.3}" y="{:.3}" {} font-size="{:.3}" fill="{}">"#,
+            x.0,
+            y.0,
+            font.attributes(),
+            Mm::from(font_size).0,
+            colour
+        )
+        .expect("writing to a String cannot fail");
+    }
+
+    /// Write a line of text with its baseline starting at (`x`, `y`).
+    fn text(&mut self, corner: (Mm, Mm), text: Text<'_>) {
+        self.open_text(corner, text.font, text.font_size, text.colour);
+        self.body.push_str(&escape(text.inner));
+        self.body.push_str("</text>");
+    }
+
+    /// Write several lines of text, `line_height` apart.
+    fn lines<I, S>(&mut self, (x, y): (Mm, Mm), line_height: Pt, lines: I, style: Text<'_>)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for (idx, line) in lines.into_iter().enumerate() {
+            self.text(
+                (x, y + Mm::from(line_height) * idx as f32),
+                Text {
+                    inner: line.as_ref(),
+                    ..style
+                },
+            );
+        }
+    }
+
+    fn rect(&mut self, (x, y): (Mm, Mm), (width, height): (Mm, Mm), fill: &str) {
+        write!(
+            self.body,
+            r#"<rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" fill="{}"/>"#,
+            x.0, y.0, width.0, height.0, fill
+        )
+        .expect("writing to a String cannot fail");
+    }
+
+    fn dashed_line(&mut self, (x1, y1): (Mm, Mm), (x2, y2): (Mm, Mm), colour: &str) {
+        write!(
+            self.body,
+            r#"<line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}" stroke="{}" stroke-width="0.35" stroke-dasharray="2.1 1.4"/>"#,
+            x1.0, y1.0, x2.0, y2.0, colour
+        )
+        .expect("writing to a String cannot fail");
+    }
+
+    /// Draw a dashed box where a QR code of `size` would go, with its top-left
+    /// corner at (`x`, `y`).
+    fn dashed_box(&mut self, (x, y): (Mm, Mm), size: Mm) {
+        let inset = QR_MARGIN / 2.0;
+        write!(
+            self.body,
+            r#"<rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" fill="none" stroke="{}" stroke-width="0.35" stroke-dasharray="2.1 1.4"/>"#,
+            (x + inset).0,
+            (y + inset).0,
+            (size - inset * 2.0).0,
+            (size - inset * 2.0).0,
+            colours::LIGHT_GREY
+        )
+        .expect("writing to a String cannot fail");
+    }
+
+    /// Draw a QR code (including its quiet zone) scaled to `size`, with its
+    /// top-left corner at (`x`, `y`).
+    fn qr(&mut self, code: &QrCode, (x, y): (Mm, Mm), size: Mm) {
+        const QUIET_ZONE: usize = 4;
+
+        let width = code.width();
+        let modules = width + QUIET_ZONE * 2;
+        write!(
+            self.body,
+            r#"<svg x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" viewBox="0 0 {modules} {modules}" shape-rendering="crispEdges"><rect width="{modules}" height="{modules}" fill="{}"/><path fill="{}" d=""#,
+            x.0,
+            y.0,
+            size.0,
+            size.0,
+            colours::WHITE,
+            colours::BLACK,
+        )
+        .expect("writing to a String cannot fail");
+        for (idx, colour) in code.to_colors().into_iter().enumerate() {
+            if colour == Color::Dark {
+                let (column, row) = (idx % width, idx / width);
+                write!(
+                    self.body,
+                    "M{} {}h1v1h-1z",
+                    column + QUIET_ZONE,
+                    row + QUIET_ZONE
+                )
+                .expect("writing to a String cannot fail");
+            }
+        }
+        self.body.push_str(r#""/></svg>"#);
+    }
+
+    /// Embed a standalone SVG image scaled to `height`, with its top-left
+    /// corner at (`x`, `y`).
+    fn image(&mut self, svg: &str, (x, y): (Mm, Mm), height: Mm) -> Result<(), Error> {
+        let invalid = || Error::OtherError("invalid embedded svg image".to_string());
+        let start = svg.find("<svg").ok_or_else(invalid)?;
+        let contents_start = start + svg[start..].find('>').ok_or_else(invalid)? + 1;
+        let contents_end = svg.rfind("</svg>").ok_or_else(invalid)?;
+        let view_box = svg[start..contents_start]
+            .split("viewBox=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .ok_or_else(invalid)?;
+        let (view_width, view_height) = match view_box
+            .split_whitespace()
+            .map(str::parse::<f32>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?[..]
+        {
+            [_, _, width, height] => (width, height),
+            _ => return Err(invalid()),
+        };
+        write!(
+            self.body,
+            r#"<svg x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" viewBox="{}">{}</svg>"#,
+            x.0,
+            y.0,
+            (height * (view_width / view_height)).0,
+            height.0,
+            view_box,
+            &svg[contents_start..contents_end]
+        )
+        .expect("writing to a String cannot fail");
+        Ok(())
+    }
+
+    fn finish(self) -> String {
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#,
+                "\n",
+                r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}">"#,
+                r#"<rect width="{w}" height="{h}" fill="{}"/>{}</svg>"#,
+                "\n"
+            ),
+            colours::WHITE,
+            self.body,
+            w = self.width.0,
+            h = self.height.0,
+        )
+    }
+}
+
+/// See `generate::banner`.
+fn banner(
+    page: &mut Page,
+    mut top: Mm,
+    (margin, banner_margin): (Mm, Mm),
+    header: Text<'_>,
+    description: Option<Text<'_>>,
+    banner_colour: &str,
+) -> Mm {
+    const BANNER_HEIGHT: Mm = Mm(9.0);
+    top += banner_margin;
+
+    let width = page.width;
+    page.rect((Mm(0.0), top), (width, BANNER_HEIGHT), banner_colour);
+
+    let baseline = top + (BANNER_HEIGHT + Mm::from(header.font_size)) / 2.0;
+    page.open_text(
+        (margin, baseline),
+        header.font,
+        header.font_size,
+        header.colour,
+    );
+    page.body.push_str(&escape(header.inner));
+    if let Some(description) = description {
+        write!(
+            page.body,
+            r#"<tspan dx="{:.3}" {} font-size="{:.3}" fill="{}">{}</tspan>"#,
+            Mm::from(description.font_size).0,
+            description.font.attributes(),
+            Mm::from(description.font_size).0,
+            description.colour,
+            escape(description.inner)
+        )
+        .expect("writing to a String cannot fail");
+    }
+    page.body.push_str("</text>");
+
+    BANNER_HEIGHT + banner_margin
+}
+
+/// See `generate::QrData`.
+enum QrData<'a> {
+    Single(Vec<u8>),
+    KeyShard(PartType, &'a KeyShard, Vec<u8>),
+}
+
+/// See `generate::qr_with_fallback`.
+fn qr_with_fallback(
+    page: &mut Page,
+    top: Mm,
+    (margin, qr_fraction): (Mm, f32),
+    options: &PdfOptions,
+    data: QrData<'_>,
+    font_size: f32,
+) -> Result<Mm, Error> {
+    const DATA_MARGIN: Mm = Mm(3.0);
+
+    let (data, codes) = match data {
+        QrData::Single(data) => {
+            let code =
+                qr::generate_one_code(options.qr_encoding, options.qr_error_correction, &data)?;
+            (data, vec![code])
+        }
+        QrData::KeyShard(data_type, shard, data) => {
+            let codes = qr::generate_key_shard_codes(
+                options.qr_encoding,
+                options.qr_error_correction,
+                options.qr_density,
+                data_type,
+                shard,
+                &data,
+            )?;
+            (data, codes)
+        }
+    };
+    // Can't use std::cmp::min sadly.
+    let (width, bottom) = (page.width, page.height - top);
+    let qr_size = if bottom - margin < width * qr_fraction {
+        bottom - margin
+    } else {
+        width * qr_fraction
+    };
+
+    let data_lines = fallback_lines(options.text_fallback, &data);
+
+    let data_height: Mm = Pt(font_size + (font_size + 2.0) * data_lines.len() as f32).into();
+    let padded_data_height = data_height + DATA_MARGIN * 2.0;
+    // Split codes are stacked on top of each other.
+    let qrs_height = qr_size * codes.len() as f32 + DATA_MARGIN * (codes.len() - 1) as f32;
+    // Can't use std::cmp::max sadly.
+    let total_height = if qrs_height > padded_data_height {
+        qrs_height
+    } else {
+        padded_data_height
+    };
+
+    let (qrs_y, data_y) = (
+        total_height / 2.0 - qrs_height / 2.0,
+        total_height / 2.0 - data_height / 2.0 + Mm::from(Pt(font_size)),
+    );
+    let (qr_x, data_x) = (margin, margin + qr_size + margin);
+
+    for (idx, code) in codes.iter().enumerate() {
+        let qr_y = qrs_y + (qr_size + DATA_MARGIN) * idx as f32;
+        page.qr(code, (qr_x, top + qr_y), qr_size);
+    }
+
+    page.text(
+        (data_x, top + data_y),
+        Text {
+            inner: options.language.strings().text_fallback,
+            colour: colours::LIGHT_GREY,
+            font: Font::Monospace,
+            font_size: Pt(font_size - 2.0),
+        },
+    );
+    let line_height: Mm = Pt(font_size + 2.0).into();
+    for (idx, line) in data_lines.iter().enumerate() {
+        page.text(
+            (data_x, top + data_y + line_height * (idx + 1) as f32),
+            Text {
+                inner: line,
+                colour: if idx % 2 == 0 {
+                    colours::BLACK
+                } else {
+                    colours::GREY
+                },
+                font: Font::Monospace,
+                font_size: Pt(font_size),
+            },
+        );
+    }
+
+    Ok(total_height)
+}
+
+/// Write the "Document" (or "Shard") label (followed by an optional `note`)
+/// and an id below it, as in the header of every document. Returns the
+/// baseline of the id.
+fn labelled_id(
+    page: &mut Page,
+    (x, y): (Mm, Mm),
+    (label, note): (&str, Option<&str>),
+    id: &str,
+    colour: &'static str,
+) -> Mm {
+    page.open_text((x, y), Font::Text, Pt(10.0), colours::GREY);
+    page.body.push_str(&escape(label));
+    if let Some(note) = note {
+        write!(
+            page.body,
+            r#"<tspan dx="{:.3}" {} font-size="{:.3}">{}</tspan>"#,
+            Mm::from(Pt(8.0)).0,
+            Font::Monospace.attributes(),
+            Mm::from(Pt(8.0)).0,
+            escape(note)
+        )
+        .expect("writing to a String cannot fail");
+    }
+    page.body.push_str("</text>");
+    let y = y + Pt(20.0 + 2.0).into();
+    page.text(
+        (x, y),
+        Text {
+            inner: id,
+            colour,
+            font: Font::Monospace,
+            font_size: Pt(20.0),
+        },
+    );
+    y
+}
+
+/// Write the document type in the top-right corner of the page.
+fn document_type(
+    page: &mut Page,
+    (margin, y): (Mm, Mm),
+    title: &str,
+    reserved: Pt,
+    colour: &'static str,
+) {
+    let x = page.width - (margin + header_width(title, 20.0, reserved));
+    let y = y + Pt(10.0).into();
+    page.text(
+        (x, y),
+        Text {
+            inner: title,
+            colour,
+            font: Font::Text,
+            font_size: Pt(20.0),
+        },
+    );
+    page.text(
+        (x, y + Pt(10.0 + 2.0).into()),
+        Text {
+            inner: "paperback-v0",
+            colour: colours::GREY,
+            font: Font::Monospace,
+            font_size: Pt(10.0),
+        },
+    );
+}
+
+impl ToSvg for MainDocument {
+    #[instrument(name = "main_document_svg", skip_all, fields(document_id = %self.id()))]
+    fn to_svg_with_options(&self, options: &PdfOptions) -> Result<Vec<String>, Error> {
+        let (data_qrs, _) = qr::generate_codes(
+            options.qr_encoding,
+            options.qr_error_correction,
+            options.qr_density,
+            PartType::MainDocumentData,
+            Some(&self.id()),
+            None,
+            self.to_wire(),
+        )?;
+        if data_qrs.len() > MAX_MAIN_DOCUMENT_CODES {
+            return Err(Error::TooManyCodes(format!(
+                "only {} codes allowed in this version of paperback (at most {} bytes of secret data fit in a main document with these options)",
+                MAX_MAIN_DOCUMENT_CODES,
+                self.max_secret_size(options)
+            )));
+        }
+        let num_codes = data_qrs.len();
+        let strings = options.language.strings();
+
+        let (page_width, page_height) = match options.layout {
+            MainDocumentLayout::Portrait | MainDocumentLayout::Booklet => (A4_WIDTH, A4_HEIGHT),
+            MainDocumentLayout::Landscape => (A4_HEIGHT, A4_WIDTH),
+        };
+        let mut page = Page::new(page_width, page_height);
+        let mut current_y = A4_MARGIN + Pt(10.0).into();
+
+        // Header.
+        let id_y = labelled_id(
+            &mut page,
+            (A4_MARGIN, current_y),
+            (strings.document, None),
+            &self.id(),
+            colours::MAIN_DOCUMENT_TRIM,
+        );
+        let mut details = vec![];
+        if let Some(title) = self.title() {
+            details.push((strings.main_document_title)(title));
+        }
+        details.extend(
+            self.public_info()
+                .iter()
+                .map(|info| format!("{}: {}", info.label(), info.value())),
+        );
+        details.extend((strings.main_document_details)(self.quorum_size()));
+        details.push((strings.main_document_fingerprint)(
+            &self.fingerprint_words().join(" "),
+        ));
+        details.extend(timestamp_details(strings, self.created(), self.expires()));
+        page.lines(
+            (A4_MARGIN, id_y + Pt(12.0 * 2.0).into()),
+            Pt(10.0 + 2.0),
+            &details,
+            Text {
+                inner: "",
+                colour: colours::BLACK,
+                font: Font::Text,
+                font_size: Pt(10.0),
+            },
+        );
+        document_type(
+            &mut page,
+            (A4_MARGIN, current_y),
+            strings.main_document,
+            Pt(15.0) * 12.0,
+            colours::MAIN_DOCUMENT_TRIM,
+        );
+        // Leave the same room for the header as the PDF does.
+        current_y += (Pt(22.0) + Pt(12.0) * (details.len() + 1) as f32).into();
+
+        let document_description = match options.layout {
+            MainDocumentLayout::Booklet => format!(
+                "{} {}",
+                strings.document_description,
+                (strings.booklet_note)(num_codes)
+            ),
+            _ => strings.document_description.to_string(),
+        };
+        current_y += banner(
+            &mut page,
+            current_y,
+            (A4_MARGIN, Mm(3.0)),
+            Text {
+                inner: strings.document_banner,
+                colour: colours::WHITE,
+                font: Font::Text,
+                font_size: Pt(10.0),
+            },
+            Some(Text {
+                inner: &document_description,
+                colour: colours::WHITE,
+                font: Font::Text,
+                font_size: Pt(8.0),
+            }),
+            colours::MAIN_DOCUMENT_TRIM,
+        ) + Mm(2.0);
+
+        let grid = match options.layout {
+            MainDocumentLayout::Portrait => Some(((3, 3), (page_width - A4_MARGIN * 2.0) / 3.0)),
+            MainDocumentLayout::Landscape => {
+                // Leave enough room below the grid for the checksum section.
+                let checksum_height = page_width * LANDSCAPE_CHECKSUM_QR_FRACTION;
+                let reserved = current_y + Mm(14.0) + checksum_height + A4_MARGIN;
+                // Can't use std::cmp::min sadly.
+                let (column_size, row_size) = (
+                    (page_width - A4_MARGIN * 2.0) / 5.0,
+                    (page_height - reserved) / 2.0,
+                );
+                let size = if column_size < row_size {
+                    column_size
+                } else {
+                    row_size
+                };
+                Some(((5, 2), size))
+            }
+            MainDocumentLayout::Booklet => None,
+        };
+        if let Some(((columns, rows), size)) = grid {
+            let mut qrs = data_qrs.iter();
+            for row in 0..rows {
+                for column in 0..columns {
+                    let corner = (
+                        A4_MARGIN + size * column as f32,
+                        current_y + size * row as f32,
+                    );
+                    match qrs.next() {
+                        Some(qr) => page.qr(qr, corner, size),
+                        None => page.dashed_box(corner, size),
+                    }
+                }
+            }
+            current_y += size * rows as f32;
+        }
+        let checksum_qr_fraction = match options.layout {
+            MainDocumentLayout::Landscape => LANDSCAPE_CHECKSUM_QR_FRACTION,
+            _ => 0.18,
+        };
+
+        current_y += banner(
+            &mut page,
+            current_y,
+            (A4_MARGIN, Mm(3.0)),
+            Text {
+                inner: strings.checksum_banner,
+                colour: colours::WHITE,
+                font: Font::Text,
+                font_size: Pt(10.0),
+            },
+            Some(Text {
+                inner: strings.main_document_checksum_description,
+                colour: colours::WHITE,
+                font: Font::Text,
+                font_size: Pt(8.0),
+            }),
+            colours::MAIN_DOCUMENT_TRIM,
+        ) + Mm(2.0);
+
+        // Document checksum.
+        qr_with_fallback(
+            &mut page,
+            current_y,
+            (A4_MARGIN, checksum_qr_fraction),
+            options,
+            QrData::Single(self.checksum().to_bytes()),
+            10.0,
+        )?;
+
+        let mut pages = vec![page.finish()];
+
+        // With the booklet layout, each QR code gets its own page so that it
+        // can be printed as large as possible.
+        if grid.is_none() {
+            for (idx, qr) in data_qrs.iter().enumerate() {
+                let mut page = Page::new(page_width, page_height);
+                let mut current_y = A4_MARGIN;
+                let caption = (strings.booklet_caption)(idx + 1, num_codes);
+                current_y += banner(
+                    &mut page,
+                    current_y,
+                    (A4_MARGIN, Mm(3.0)),
+                    Text {
+                        inner: &caption,
+                        colour: colours::WHITE,
+                        font: Font::Text,
+                        font_size: Pt(10.0),
+                    },
+                    Some(Text {
+                        inner: &(strings.booklet_description)(&self.id()),
+                        colour: colours::WHITE,
+                        font: Font::Text,
+                        font_size: Pt(8.0),
+                    }),
+                    colours::MAIN_DOCUMENT_TRIM,
+                ) + Mm(2.0);
+                page.qr(qr, (A4_MARGIN, current_y), page_width - A4_MARGIN * 2.0);
+                pages.push(page.finish());
+            }
+        }
+
+        Ok(pages)
+    }
+}
+
+/// Write the codewords of a key shard (`words_per_line` to a line), starting
+/// with the baseline at (`x`, `y`). Returns the number of lines written.
+fn write_codewords(
+    page: &mut Page,
+    (x, y): (Mm, Mm),
+    codewords: &KeyShardCodewords,
+    font_size: f32,
+    words_per_line: usize,
+) -> usize {
+    let lines = codewords.chunks(words_per_line).collect::<Vec<_>>();
+    for (row, line) in lines.iter().enumerate() {
+        let y = y + Mm::from(Pt(font_size + 5.0)) * row as f32;
+        page.open_text((x, y), Font::Monospace, Pt(font_size), colours::BLACK);
+        for (idx, codeword) in line.iter().enumerate() {
+            // Alternate between regular and bold fonts to make it easier to
+            // keep track of which codeword is being read.
+            let font = match idx % 2 {
+                0 => Font::Monospace,
+                _ => Font::MonospaceBold,
+            };
+            let separator = if idx > 0 { " " } else { "" };
+            write!(
+                page.body,
+                r#"<tspan {}>{}{}</tspan>"#,
+                font.attributes(),
+                separator,
+                escape(codeword)
+            )
+            .expect("writing to a String cannot fail");
+        }
+        page.body.push_str("</text>");
+    }
+    lines.len()
+}
+
+/// See `generate::draw_key_shard`.
+fn draw_key_shard(
+    page: &mut Page,
+    (shard, decrypted_shard, codewords): (&EncryptedKeyShard, &KeyShard, &KeyShardCodewords),
+    provenance: Option<&ShardProvenance>,
+    options: &PdfOptions,
+) -> Result<(), Error> {
+    let strings = options.language.strings();
+    let mut current_y = A5_MARGIN + Pt(10.0).into();
+
+    // Header (with the friendly shard id, which is easier to read out).
+    labelled_id(
+        page,
+        (A5_MARGIN, current_y),
+        (strings.shard, Some(decrypted_shard.friendly_id().as_str())),
+        &decrypted_shard.id(),
+        colours::KEY_SHARD_TRIM,
+    );
+    labelled_id(
+        page,
+        (A5_MARGIN, current_y + Pt(22.0 + 16.0).into()),
+        (strings.document, None),
+        &decrypted_shard.document_id(),
+        colours::MAIN_DOCUMENT_TRIM,
+    );
+    document_type(
+        page,
+        (A5_MARGIN, current_y),
+        strings.key_shard,
+        Pt(15.0) * 8.0,
+        colours::KEY_SHARD_TRIM,
+    );
+
+    // Details.
+    let details_y = current_y + Pt(12.0 + 20.0 * 2.0 + 16.0 - 12.0 * 2.0).into();
+    let mut details = strings
+        .key_shard_details
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+    details.extend(timestamp_details(
+        strings,
+        decrypted_shard.created(),
+        decrypted_shard.expires(),
+    ));
+    page.lines(
+        (A5_MARGIN + Mm(45.0), details_y),
+        Pt(10.0 + 2.0),
+        &details,
+        Text {
+            inner: "",
+            colour: colours::BLACK,
+            font: Font::Text,
+            font_size: Pt(10.0),
+        },
+    );
+    if let Some(provenance) = provenance {
+        page.text(
+            (
+                A5_MARGIN + Mm(45.0),
+                details_y
+                    + Mm::from(Pt(10.0 + 2.0)) * (details.len() - 1) as f32
+                    + Pt(8.0 + 2.0).into(),
+            ),
+            Text {
+                inner: &strings.provenance(provenance),
+                colour: colours::BLACK,
+                font: Font::Text,
+                font_size: Pt(8.0),
+            },
+        );
+    }
+    current_y += Mm(25.0);
+    // Make room for the provenance (the codeword stub has room to spare).
+    if provenance.is_some() {
+        current_y += Pt(8.0 + 2.0).into();
+    }
+
+    current_y += banner(
+        page,
+        current_y,
+        (A5_MARGIN, Mm(1.0)),
+        Text {
+            inner: strings.shard_banner,
+            colour: colours::WHITE,
+            font: Font::Text,
+            font_size: Pt(10.0),
+        },
+        Some(Text {
+            inner: strings.shard_description,
+            colour: colours::WHITE,
+            font: Font::Text,
+            font_size: Pt(8.0),
+        }),
+        colours::KEY_SHARD_TRIM,
+    );
+
+    current_y += qr_with_fallback(
+        page,
+        current_y,
+        (A5_MARGIN, 0.3),
+        options,
+        QrData::KeyShard(PartType::KeyShardData, decrypted_shard, shard.to_wire()),
+        8.0,
+    )?;
+
+    current_y += banner(
+        page,
+        current_y,
+        (A5_MARGIN, Mm(1.0)),
+        Text {
+            inner: strings.checksum_banner,
+            colour: colours::WHITE,
+            font: Font::Text,
+            font_size: Pt(10.0),
+        },
+        Some(Text {
+            inner: &(strings.shard_checksum_description)(&shard.fingerprint_words().join(" ")),
+            colour: colours::WHITE,
+            font: Font::Text,
+            font_size: Pt(8.0),
+        }),
+        colours::KEY_SHARD_TRIM,
+    );
+
+    current_y += qr_with_fallback(
+        page,
+        current_y,
+        (A5_MARGIN, 0.3),
+        options,
+        QrData::KeyShard(
+            PartType::KeyShardChecksum,
+            decrypted_shard,
+            shard.checksum().to_bytes(),
+        ),
+        8.0,
+    )?;
+
+    // Recovery instructions URL, below the checksum fallback text (which is
+    // only a few lines long).
+    if let Some(url) = &options.recovery_url {
+        let code = QrCode::with_error_correction_level(url, options.qr_error_correction.into())?;
+        page.qr(
+            &code,
+            (
+                A5_WIDTH - A5_MARGIN - RECOVERY_URL_QR_SIZE,
+                current_y - RECOVERY_URL_QR_SIZE,
+            ),
+            RECOVERY_URL_QR_SIZE,
+        );
+        // Line up with the checksum fallback text.
+        let x = A5_MARGIN + A5_WIDTH * 0.3 + A5_MARGIN;
+        page.text(
+            (x, current_y - Pt(3.0 + 9.0).into()),
+            Text {
+                inner: strings.recovery_url,
+                colour: colours::GREY,
+                font: Font::Text,
+                font_size: Pt(7.0),
+            },
+        );
+        page.text(
+            (x, current_y - Pt(3.0).into()),
+            Text {
+                inner: url,
+                colour: colours::BLACK,
+                font: Font::Monospace,
+                font_size: Pt(7.0),
+            },
+        );
+    }
+
+    // Copy of the codewords kept with the key shard itself.
+    if options.codeword_placement.on_shard() {
+        const FONT_SIZE: f32 = 7.0;
+        const WORDS_PER_LINE: usize = 8;
+
+        current_y += Mm(3.0) + Pt(8.0).into();
+        page.text(
+            (A5_MARGIN, current_y),
+            Text {
+                inner: strings.codewords,
+                colour: colours::GREY,
+                font: Font::Text,
+                font_size: Pt(8.0),
+            },
+        );
+        let lines = write_codewords(
+            page,
+            (A5_MARGIN, current_y + Pt(FONT_SIZE + 5.0).into()),
+            codewords,
+            FONT_SIZE,
+            WORDS_PER_LINE,
+        );
+        current_y += Pt((FONT_SIZE + 5.0) * lines as f32).into();
+    }
+
+    // Without a stub there is nothing to cut off.
+    if !options.codeword_placement.on_stub() {
+        return Ok(());
+    }
+
+    // "Cut here" line.
+    let target_height = Mm(5.0);
+    page.dashed_line(
+        (Mm(0.0), current_y + target_height / 2.0),
+        (A5_WIDTH, current_y + target_height / 2.0),
+        colours::KEY_SHARD_TRIM,
+    );
+    page.image(SCISSORS_SVG, (A5_MARGIN, current_y), target_height)?;
+    current_y += target_height;
+
+    banner(
+        page,
+        current_y,
+        (A5_MARGIN, Mm(1.0)),
+        Text {
+            inner: strings.codewords_banner,
+            colour: colours::WHITE,
+            font: Font::Text,
+            font_size: Pt(10.0),
+        },
+        Some(Text {
+            inner: strings.codewords_description,
+            colour: colours::WHITE,
+            font: Font::Text,
+            font_size: Pt(8.0),
+        }),
+        colours::KEY_SHARD_TRIM,
+    );
+
+    let current_y = A5_HEIGHT - Mm(30.0);
+
+    // Shard codewords.
+    labelled_id(
+        page,
+        (A5_MARGIN, current_y),
+        (strings.shard, None),
+        &decrypted_shard.id(),
+        colours::KEY_SHARD_TRIM,
+    );
+    labelled_id(
+        page,
+        (A5_MARGIN, current_y + Pt(22.0 + 14.0).into()),
+        (strings.document, None),
+        &decrypted_shard.document_id(),
+        colours::MAIN_DOCUMENT_TRIM,
+    );
+    // If there is a codeword QR code, the codewords need to be squeezed into
+    // fewer columns to leave room for it.
+    let (font_size, words_per_line) = if options.codeword_qr {
+        (8.0, 4)
+    } else {
+        (10.0, 5)
+    };
+    write_codewords(
+        page,
+        (A5_MARGIN + Mm(45.0), current_y + Pt(5.0).into()),
+        codewords,
+        font_size,
+        words_per_line,
+    );
+
+    // Codeword QR code, in the bottom-right corner of the stub.
+    if options.codeword_qr {
+        let codewords_text = Zeroizing::new(codewords.join(" "));
+        let code = QrCode::with_error_correction_level(
+            codewords_text.as_bytes(),
+            options.qr_error_correction.into(),
+        )?;
+        page.qr(
+            &code,
+            (
+                A5_WIDTH - A5_MARGIN - CODEWORD_QR_SIZE,
+                A5_HEIGHT - A5_MARGIN - CODEWORD_QR_SIZE,
+            ),
+            CODEWORD_QR_SIZE,
+        );
+    }
+
+    Ok(())
+}
+
+/// See `generate::draw_key_shard_instructions`.
+fn draw_key_shard_instructions(page: &mut Page, decrypted_shard: &KeyShard, options: &PdfOptions) {
+    let strings = options.language.strings();
+    let mut current_y = A5_MARGIN + Pt(20.0).into();
+
+    page.text(
+        (A5_MARGIN, current_y),
+        Text {
+            inner: strings.instructions_title,
+            colour: colours::KEY_SHARD_TRIM,
+            font: Font::Text,
+            font_size: Pt(20.0),
+        },
+    );
+    page.text(
+        (A5_MARGIN, current_y + Pt(14.0 + 2.0).into()),
+        Text {
+            inner: &(strings.instructions_shard)(
+                &decrypted_shard.id(),
+                &decrypted_shard.document_id(),
+            ),
+            colour: colours::BLACK,
+            font: Font::Text,
+            font_size: Pt(10.0),
+        },
+    );
+    current_y += Pt(14.0 + 2.0).into();
+
+    for (heading, lines) in strings.instructions {
+        current_y += banner(
+            page,
+            current_y,
+            (A5_MARGIN, Mm(6.0)),
+            Text {
+                inner: heading,
+                colour: colours::WHITE,
+                font: Font::Text,
+                font_size: Pt(10.0),
+            },
+            None,
+            colours::KEY_SHARD_TRIM,
+        );
+        page.lines(
+            (A5_MARGIN, current_y + Pt(14.0).into()),
+            Pt(10.0 + 4.0),
+            lines.iter(),
+            Text {
+                inner: "",
+                colour: colours::BLACK,
+                font: Font::Text,
+                font_size: Pt(10.0),
+            },
+        );
+        current_y += Pt((10.0 + 4.0) * lines.len() as f32 + 4.0).into();
+    }
+}
+
+/// Each key shard gets its own A5 page, followed by a page of instructions
+/// for its holder if `PdfOptions::include_instructions` is set. Unlike PDFs,
+/// `PdfOptions::shards_per_page` is ignored -- the pages can be arranged on
+/// larger sheets by whatever is used to print (or engrave) them.
+#[instrument(name = "key_shards_svg", skip_all, fields(num_shards = shards.len()))]
+fn key_shards_to_svg(
+    shards: &[(
+        &EncryptedKeyShard,
+        &KeyShardCodewords,
+        Option<&ShardProvenance>,
+    )],
+    options: &PdfOptions,
+) -> Result<Vec<String>, Error> {
+    options.check_recovery_url()?;
+
+    let mut pages = vec![];
+    for &(shard, codewords, provenance) in shards {
+        let decrypted_shard = shard
+            .decrypt(codewords)
+            .map_err(|err| Error::OtherError(format!("failed to decrypt shard: {}", err)))?;
+
+        let mut page = Page::new(A5_WIDTH, A5_HEIGHT);
+        draw_key_shard(
+            &mut page,
+            (shard, &decrypted_shard, codewords),
+            provenance,
+            options,
+        )?;
+        pages.push(page.finish());
+
+        if options.include_instructions {
+            let mut page = Page::new(A5_WIDTH, A5_HEIGHT);
+            draw_key_shard_instructions(&mut page, &decrypted_shard, options);
+            pages.push(page.finish());
+        }
+    }
+    Ok(pages)
+}
+
+impl ToSvg for (&EncryptedKeyShard, &KeyShardCodewords) {
+    fn to_svg_with_options(&self, options: &PdfOptions) -> Result<Vec<String>, Error> {
+        let (shard, codewords) = *self;
+        key_shards_to_svg(&[(shard, codewords, None)], options)
+    }
+}
+
+impl ToSvg for (EncryptedKeyShard, KeyShardCodewords) {
+    fn to_svg_with_options(&self, options: &PdfOptions) -> Result<Vec<String>, Error> {
+        let (shard, codewords) = self;
+        (shard, codewords).to_svg_with_options(options)
+    }
+}
+
+impl ToSvg for [(EncryptedKeyShard, KeyShardCodewords)] {
+    fn to_svg_with_options(&self, options: &PdfOptions) -> Result<Vec<String>, Error> {
+        let shards = self
+            .iter()
+            .map(|(shard, codewords)| (shard, codewords, None))
+            .collect::<Vec<_>>();
+        key_shards_to_svg(&shards, options)
+    }
+}
+
+impl ToSvg for [(EncryptedKeyShard, KeyShardCodewords, ShardProvenance)] {
+    fn to_svg_with_options(&self, options: &PdfOptions) -> Result<Vec<String>, Error> {
+        let shards = self
+            .iter()
+            .map(|(shard, codewords, provenance)| (shard, codewords, Some(provenance)))
+            .collect::<Vec<_>>();
+        key_shards_to_svg(&shards, options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::v0::Backup;
+
+    #[test]
+    fn main_document_svg() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let main_document = backup.main_document();
+        for layout in [
+            MainDocumentLayout::Portrait,
+            MainDocumentLayout::Landscape,
+            MainDocumentLayout::Booklet,
+        ] {
+            let options = PdfOptions {
+                layout,
+                ..Default::default()
+            };
+            let pages = main_document.to_svg_with_options(&options).unwrap();
+            let expected_pages = match layout {
+                MainDocumentLayout::Booklet => 2,
+                _ => 1,
+            };
+            assert_eq!(pages.len(), expected_pages, "layout {:?}", layout);
+            assert!(pages[0].starts_with("<?xml"));
+            assert!(pages[0].contains(&main_document.id().to_string()));
+        }
+    }
+
+    #[test]
+    fn key_shard_svg() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let (shard, codewords) = backup.next_shard().unwrap().encrypt().unwrap();
+        let options = PdfOptions {
+            codeword_qr: true,
+            include_instructions: true,
+            recovery_url: Some("https://example.com/r".to_string()),
+            ..Default::default()
+        };
+        let pages = (&shard, &codewords).to_svg_with_options(&options).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert!(codewords
+            .iter()
+            .all(|codeword| pages[0].contains(codeword.as_str())));
+        // The embedded scissors image must not include its XML declaration.
+        assert_eq!(pages[0].matches("<?xml").count(), 1);
+    }
+
+    #[test]
+    fn escape_text() {
+        assert_eq!(escape(r#"<a & "b">"#), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
@@ -0,0 +1,420 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::Error;
+#[cfg(feature = "std")]
+use crate::v0::SeedPhrase;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use hmac::{Hmac, Mac};
+use k256::{
+    elliptic_curve::{ff::PrimeField, sec1::ToEncodedPoint},
+    FieldBytes, Scalar, SecretKey,
+};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::Zeroizing;
+
+/// A labelled piece of public information derived from the secret data of a
+/// backup (such as the extended public key of a cryptocurrency wallet), which
+/// is printed on the main document so that the backup can be identified
+/// without recovering it. See `BackupBuilder::derive_public_info`.
+///
+/// Public information is not encrypted, but it is authenticated along with the
+/// rest of the main document so it cannot be modified without detection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInfo {
+    pub(super) label: String,
+    pub(super) value: String,
+}
+
+impl PublicInfo {
+    /// The maximum length (in bytes) of a label.
+    pub const MAX_LABEL_LENGTH: usize = 64;
+    /// The maximum length (in bytes) of a value.
+    pub const MAX_VALUE_LENGTH: usize = 256;
+    /// The maximum number of entries in a main document.
+    pub const MAX_ENTRIES: usize = 8;
+
+    /// Create a new entry. Labels and values must be non-empty, cannot
+    /// contain control characters and are limited to `MAX_LABEL_LENGTH` and
+    /// `MAX_VALUE_LENGTH` bytes.
+    pub fn new<L: Into<String>, V: Into<String>>(label: L, value: V) -> Result<Self, Error> {
+        let info = Self {
+            label: label.into(),
+            value: value.into(),
+        };
+        info.check()?;
+        Ok(info)
+    }
+
+    pub(super) fn check(&self) -> Result<(), Error> {
+        let valid = |s: &str, max_length| {
+            !s.is_empty() && s.len() <= max_length && !s.chars().any(char::is_control)
+        };
+        if !valid(&self.label, Self::MAX_LABEL_LENGTH)
+            || !valid(&self.value, Self::MAX_VALUE_LENGTH)
+        {
+            return Err(Error::InvalidPublicInfo(format!(
+                "{:?} = {:?}",
+                self.label, self.value
+            )));
+        }
+        Ok(())
+    }
+
+    pub(super) fn check_all(infos: &[Self]) -> Result<(), Error> {
+        if infos.len() > Self::MAX_ENTRIES {
+            return Err(Error::InvalidPublicInfo(format!(
+                "{} entries (at most {} are allowed)",
+                infos.len(),
+                Self::MAX_ENTRIES
+            )));
+        }
+        infos.iter().try_for_each(Self::check)
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for PublicInfo {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let mut string = |max_length| {
+            let s = String::arbitrary(g)
+                .chars()
+                .filter(|ch| !ch.is_control())
+                .take(max_length / 4)
+                .collect::<String>();
+            match s.is_empty() {
+                true => "x".to_string(),
+                false => s,
+            }
+        };
+        Self {
+            label: string(Self::MAX_LABEL_LENGTH),
+            value: string(Self::MAX_VALUE_LENGTH),
+        }
+    }
+}
+
+/// The secret data of a backup, as passed to `DerivePublicInfo`.
+pub enum SecretData<'a> {
+    /// An opaque secret (see `BackupBuilder::build`).
+    Secret(&'a [u8]),
+    /// A BIP-39 seed phrase (see `BackupBuilder::build_seed_phrase`).
+    #[cfg(feature = "std")]
+    SeedPhrase(&'a SeedPhrase),
+}
+
+/// Hook used by `BackupBuilder::derive_public_info` to interpret the secret
+/// data of a backup (such as the seed of a cryptocurrency wallet) and derive
+/// public information from it to print on the main document.
+///
+/// Implementations must only return information which is safe to publish --
+/// the public information is printed in the clear, while the secret data
+/// itself is encrypted as usual.
+pub trait DerivePublicInfo: Send {
+    fn derive_public_info(&self, secret: SecretData<'_>) -> Result<Vec<PublicInfo>, Error>;
+}
+
+/// Derives the BIP-32 master key fingerprint and the extended public key
+/// ("xpub") at a derivation path from a cryptocurrency wallet seed.
+///
+/// Seed phrases are converted to a seed as described in BIP-39, without a
+/// passphrase (wallets protected by a BIP-39 passphrase have different keys,
+/// which cannot be derived without it). Opaque secrets are used as the BIP-32
+/// seed directly, and must be between 16 and 64 bytes long.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bip32 {
+    path: Vec<u32>,
+}
+
+impl Bip32 {
+    /// Child indices at or above this are hardened.
+    pub const HARDENED: u32 = 1 << 31;
+    /// The path of the first account of a BIP-84 (native SegWit) Bitcoin
+    /// wallet.
+    pub const DEFAULT_PATH: &'static str = "m/84'/0'/0'";
+
+    /// Derive the extended public key at `path`, such as `m/84'/0'/0'`
+    /// (hardened indices can be marked with `'` or `h`).
+    pub fn new<S: AsRef<str>>(path: S) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let invalid = || Error::InvalidDerivationPath(path.to_string());
+        let mut components = path.split('/');
+        if components.next() != Some("m") {
+            return Err(invalid());
+        }
+        let path = components
+            .map(|component| {
+                let (index, hardened) = match component
+                    .strip_suffix('\'')
+                    .or_else(|| component.strip_suffix('h'))
+                {
+                    Some(index) => (index, true),
+                    None => (component, false),
+                };
+                match index.parse::<u32>() {
+                    Ok(index) if index < Self::HARDENED => Ok(match hardened {
+                        true => index | Self::HARDENED,
+                        false => index,
+                    }),
+                    _ => Err(invalid()),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if path.len() > u8::MAX as usize {
+            return Err(invalid());
+        }
+        Ok(Self { path })
+    }
+
+    /// The derivation path, in the form accepted by `Bip32::new`.
+    pub fn path(&self) -> String {
+        core::iter::once("m".to_string())
+            .chain(self.path.iter().map(|index| match index & Self::HARDENED {
+                0 => index.to_string(),
+                _ => format!("{}'", index & !Self::HARDENED),
+            }))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl Default for Bip32 {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PATH).expect("default derivation path must be valid")
+    }
+}
+
+impl DerivePublicInfo for Bip32 {
+    fn derive_public_info(&self, secret: SecretData<'_>) -> Result<Vec<PublicInfo>, Error> {
+        let seed = match secret {
+            #[cfg(feature = "std")]
+            SecretData::SeedPhrase(seed_phrase) => seed_phrase.to_seed("")?,
+            SecretData::Secret(secret) if (16..=64).contains(&secret.len()) => {
+                Zeroizing::new(secret.to_vec())
+            }
+            SecretData::Secret(secret) => {
+                return Err(Error::DerivePublicInfo(format!(
+                    "BIP-32 seeds must be between 16 and 64 bytes long, not {}",
+                    secret.len()
+                )))
+            }
+        };
+        let master = ExtendedKey::master(&seed)?;
+        let key = self
+            .path
+            .iter()
+            .try_fold(master.clone(), |key, index| key.child(*index))?;
+        Ok(vec![
+            PublicInfo::new("BIP-32 fingerprint", hex(&master.fingerprint()))?,
+            PublicInfo::new(format!("{} xpub", self.path()), key.xpub())?,
+        ])
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    data.iter().for_each(|data| mac.update(data));
+    let mut output = Zeroizing::new([0u8; 64]);
+    output.copy_from_slice(&mac.finalize().into_bytes());
+    output
+}
+
+/// A BIP-32 extended private key.
+#[derive(Clone)]
+struct ExtendedKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: Zeroizing<[u8; 32]>,
+    key: SecretKey,
+}
+
+impl ExtendedKey {
+    /// Version bytes of a mainnet extended public key.
+    const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+    fn new(
+        output: &[u8; 64],
+        depth: u8,
+        parent_fingerprint: [u8; 4],
+        child_number: u32,
+        key: Option<SecretKey>,
+    ) -> Result<Self, Error> {
+        let key = key.ok_or_else(|| {
+            // The chance of this happening is less than 1 in 2^127.
+            Error::DerivePublicInfo(format!(
+                "invalid BIP-32 key at depth {} (index {})",
+                depth, child_number
+            ))
+        })?;
+        let mut chain_code = Zeroizing::new([0u8; 32]);
+        chain_code.copy_from_slice(&output[32..]);
+        Ok(Self {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            key,
+        })
+    }
+
+    fn master(seed: &[u8]) -> Result<Self, Error> {
+        let output = hmac_sha512(b"Bitcoin seed", &[seed]);
+        let key = SecretKey::from_slice(&output[..32]).ok();
+        Self::new(&output, 0, [0; 4], 0, key)
+    }
+
+    fn child(&self, index: u32) -> Result<Self, Error> {
+        let depth = self.depth.checked_add(1).ok_or_else(|| {
+            Error::DerivePublicInfo("BIP-32 derivation path is too long".to_string())
+        })?;
+        let output = match index & Bip32::HARDENED {
+            0 => hmac_sha512(
+                &self.chain_code[..],
+                &[&self.public_key(), &index.to_be_bytes()],
+            ),
+            _ => hmac_sha512(
+                &self.chain_code[..],
+                &[
+                    &[0],
+                    &Zeroizing::new(self.key.to_bytes()),
+                    &index.to_be_bytes(),
+                ],
+            ),
+        };
+        let tweak: Option<Scalar> =
+            Scalar::from_repr(*FieldBytes::from_slice(&output[..32])).into();
+        let key = tweak
+            .map(|tweak| tweak + *self.key.to_nonzero_scalar())
+            .and_then(|scalar| SecretKey::from_bytes(&scalar.to_repr()).ok());
+        Self::new(&output, depth, self.fingerprint(), index, key)
+    }
+
+    /// The compressed public key.
+    fn public_key(&self) -> [u8; 33] {
+        let mut public_key = [0u8; 33];
+        public_key.copy_from_slice(self.key.public_key().to_encoded_point(true).as_bytes());
+        public_key
+    }
+
+    fn fingerprint(&self) -> [u8; 4] {
+        let hash = Ripemd160::digest(Sha256::digest(self.public_key()));
+        let mut fingerprint = [0u8; 4];
+        fingerprint.copy_from_slice(&hash[..4]);
+        fingerprint
+    }
+
+    /// The Base58Check-encoded extended public key.
+    fn xpub(&self) -> String {
+        let mut bytes = Self::XPUB_VERSION.to_vec();
+        bytes.push(self.depth);
+        bytes.extend_from_slice(&self.parent_fingerprint);
+        bytes.extend_from_slice(&self.child_number.to_be_bytes());
+        bytes.extend_from_slice(&self.chain_code[..]);
+        bytes.extend_from_slice(&self.public_key());
+        let checksum = Sha256::digest(Sha256::digest(&bytes));
+        bytes.extend_from_slice(&checksum[..4]);
+        // Strip the multibase prefix.
+        multibase::encode(multibase::Base::Base58Btc, &bytes)[1..].to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Test vector 1 from BIP-32.
+    const TEST_SEED: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+
+    #[test]
+    fn bip32_test_vector() {
+        let master = ExtendedKey::master(&TEST_SEED).unwrap();
+        assert_eq!(master.xpub(), "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8");
+        assert_eq!(hex(&master.fingerprint()), "3442193e");
+
+        let info = Bip32::new("m/0'")
+            .unwrap()
+            .derive_public_info(SecretData::Secret(&TEST_SEED))
+            .unwrap();
+        assert_eq!(
+            info,
+            vec![
+                PublicInfo::new("BIP-32 fingerprint", "3442193e").unwrap(),
+                PublicInfo::new("m/0' xpub", "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn bip32_seed_phrase() {
+        let seed_phrase = SeedPhrase::from_phrase("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about").unwrap();
+        let info = Bip32::default()
+            .derive_public_info(SecretData::SeedPhrase(&seed_phrase))
+            .unwrap();
+        assert_eq!(info[0].value(), "73c5da0a");
+        assert_eq!(info[1].label(), "m/84'/0'/0' xpub");
+    }
+
+    #[test]
+    fn bip32_path() {
+        for path in ["m", "m/0", "m/84'/0'/0'", "m/44'/60'/0'/0/1"] {
+            assert_eq!(Bip32::new(path).unwrap().path(), path);
+        }
+        assert_eq!(Bip32::new("m/84h/0h/0h").unwrap(), Bip32::default());
+        for path in ["", "84'/0'", "m/", "m/x", "m/2147483648", "m/1''"] {
+            assert!(
+                matches!(Bip32::new(path), Err(Error::InvalidDerivationPath(_))),
+                "{:?} should be rejected",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn public_info_validation() {
+        assert!(PublicInfo::new("label", "value").is_ok());
+        assert!(PublicInfo::new("", "value").is_err());
+        assert!(PublicInfo::new("label", "multi\nline").is_err());
+        assert!(PublicInfo::new("label", "x".repeat(PublicInfo::MAX_VALUE_LENGTH + 1)).is_err());
+        let infos = vec![PublicInfo::new("label", "value").unwrap(); PublicInfo::MAX_ENTRIES + 1];
+        assert!(PublicInfo::check_all(&infos).is_err());
+    }
+}
@@ -19,20 +19,36 @@
 use crate::{
     shamir::{shard, Dealer},
     v0::{
-        Error, FromWire, KeyShard, KeyShardBuilder, MainDocument, Multihash, ShardId, ShardSecret,
+        codewords, format_date, to_unix_timestamp, ActivityRecord, ActivityRecordBuilder,
+        AgeIdentity, Backup, ChaChaPolyKey, ChaChaPolyNonce, ChecksumAlgorithm, DocumentId, Error,
+        FromWire, KeyShard, KeyShardBuilder, MainDocument, Multihash, NamedPayload, NamedPayloads,
+        PlaintextKind, SeedPhrase, ShardId, ShardPolicy, ShardSecret, ToWire,
+        CHACHAPOLY_TAG_LENGTH, CHECKSUM_MULTIBASE,
     },
 };
 
 use std::{
     collections::HashMap,
+    fmt,
     hash::{Hash, Hasher},
+    io::{self, Read},
+    iter,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
 };
 
-use aead::{Aead, Payload};
-use chacha20poly1305::ChaCha20Poly1305;
+use chacha20::{
+    cipher::{KeyIvInit, StreamCipher, StreamCipherSeek},
+    ChaCha20,
+};
 use crypto_common::KeyInit;
 use ed25519_dalek::VerifyingKey;
-use once_cell::unsync::OnceCell;
+use generic_array::GenericArray;
+use once_cell::sync::OnceCell;
+use poly1305::{universal_hash::UniversalHash, Poly1305};
+use rayon::prelude::*;
+use tracing::{debug, instrument};
+use zeroize::Zeroizing;
 
 #[derive(Debug, Clone)]
 pub enum Type {
@@ -60,26 +76,18 @@ impl Type {
 
 impl From<MainDocument> for Type {
     fn from(main: MainDocument) -> Self {
-        let id_public_key = main.identity.id_public_key;
-        match id_public_key.verify_strict(
-            &main.inner.signable_bytes(&id_public_key),
-            &main.identity.id_signature,
-        ) {
-            Ok(_) => Type::MainDocument(main),
-            Err(_) => Type::ForgedMainDocument(main),
+        match main.has_valid_signature() {
+            true => Type::MainDocument(main),
+            false => Type::ForgedMainDocument(main),
         }
     }
 }
 
 impl From<KeyShard> for Type {
     fn from(shard: KeyShard) -> Self {
-        let id_public_key = shard.identity.id_public_key;
-        match id_public_key.verify_strict(
-            &shard.inner.signable_bytes(&id_public_key),
-            &shard.identity.id_signature,
-        ) {
-            Ok(_) => Type::KeyShard(shard),
-            Err(_) => Type::ForgedKeyShard(shard),
+        match shard.has_valid_signature() {
+            true => Type::KeyShard(shard),
+            false => Type::ForgedKeyShard(shard),
         }
     }
 }
@@ -159,19 +167,294 @@ pub struct Grouping(pub Vec<Vec<Type>>);
 pub struct UntrustedQuorum {
     untrusted_quorum_size: Option<u32>,
     untrusted_main_document: Option<MainDocument>,
-    untrusted_shards: HashMap<(GroupId, String), KeyShard>,
+    untrusted_shards: HashMap<(GroupId, ShardId), KeyShard>,
+    // Any other (different) key shards pushed with the same id as one of the
+    // untrusted_shards.
+    conflicting_shards: HashMap<(GroupId, ShardId), Vec<KeyShard>>,
 }
 
-#[derive(Debug)]
+/// Different key shards pushed to an `UntrustedQuorum` with the same id. At
+/// most one of them can be genuine -- the rest are forged or corrupted copies.
+#[derive(Clone, Debug)]
+pub struct ShardConflict {
+    pub id: ShardId,
+    pub document_id: DocumentId,
+    /// Every distinct copy of the key shard, in the order they were pushed.
+    /// The first copy is the one used by the quorum.
+    pub shards: Vec<KeyShard>,
+}
+
+/// The reason an `UntrustedQuorum` failed to validate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InconsistentQuorumKind {
+    /// The documents do not agree on which backup they belong to (for instance,
+    /// a key shard's document checksum doesn't match the main document).
+    Inconsistent,
+    /// One of the documents has an invalid signature.
+    Forged,
+    /// There are fewer key shards than the quorum size.
+    InsufficientShards,
+    /// Different key shards with the same id were provided (see
+    /// `UntrustedQuorum::conflicts`).
+    ConflictingShards,
+}
+
+/// A property that every document in a quorum must agree on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuorumField {
+    Version,
+    DocumentChecksum,
+    QuorumSize,
+    PublicKey,
+}
+
+impl fmt::Display for QuorumField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            QuorumField::Version => "version",
+            QuorumField::DocumentChecksum => "document checksum",
+            QuorumField::QuorumSize => "quorum size",
+            QuorumField::PublicKey => "public key",
+        })
+    }
+}
+
+/// A single document in a `QuorumGroupReport`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuorumMember {
+    MainDocument { forged: bool },
+    KeyShard { id: ShardId, forged: bool },
+}
+
+impl QuorumMember {
+    fn is_forged(&self) -> bool {
+        match self {
+            QuorumMember::MainDocument { forged } | QuorumMember::KeyShard { forged, .. } => {
+                *forged
+            }
+        }
+    }
+}
+
+impl From<&Type> for QuorumMember {
+    fn from(document: &Type) -> Self {
+        match document {
+            Type::MainDocument(_) => QuorumMember::MainDocument { forged: false },
+            Type::ForgedMainDocument(_) => QuorumMember::MainDocument { forged: true },
+            Type::KeyShard(shard) => QuorumMember::KeyShard {
+                id: shard.id(),
+                forged: false,
+            },
+            Type::ForgedKeyShard(shard) => QuorumMember::KeyShard {
+                id: shard.id(),
+                forged: true,
+            },
+        }
+    }
+}
+
+impl fmt::Display for QuorumMember {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuorumMember::MainDocument { .. } => write!(f, "main document")?,
+            QuorumMember::KeyShard { id, .. } => write!(f, "key shard {}", id)?,
+        }
+        if self.is_forged() {
+            write!(f, " (forged)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A group of documents which agree on every `QuorumField`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuorumGroupReport {
+    pub document_id: DocumentId,
+    pub version: u32,
+    pub quorum_size: u32,
+    /// Fingerprint words of the group's identity public key.
+    pub public_key_fingerprint: Vec<&'static str>,
+    pub members: Vec<QuorumMember>,
+    /// The fields which differ from the first group in the report (the group
+    /// containing the main document, or otherwise the largest group).
+    pub mismatches: Vec<QuorumField>,
+}
+
+/// A structured description of the documents in a quorum that failed to
+/// validate. The `Display` implementation renders it as a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuorumReport {
+    pub groups: Vec<QuorumGroupReport>,
+}
+
+impl QuorumReport {
+    fn new(groups: &[Vec<Type>]) -> Self {
+        let mut groups = groups
+            .iter()
+            .filter(|documents| !documents.is_empty())
+            .map(|documents| {
+                let has_main_document = documents.iter().any(|document| {
+                    matches!(
+                        document,
+                        Type::MainDocument(_) | Type::ForgedMainDocument(_)
+                    )
+                });
+                (has_main_document, GroupId::from(&documents[0]), documents)
+            })
+            .collect::<Vec<_>>();
+        // The group containing the main document (or the largest group) is
+        // the reference that every other group is compared against.
+        groups.sort_by(|(main1, id1, docs1), (main2, id2, docs2)| {
+            main2
+                .cmp(main1)
+                .then(docs2.len().cmp(&docs1.len()))
+                .then(id1.doc_chksum.to_bytes().cmp(&id2.doc_chksum.to_bytes()))
+        });
+        let reference = groups.first().map(|(_, id, _)| id.clone());
+
+        let groups = groups
+            .into_iter()
+            .map(|(_, id, documents)| {
+                let mut members = documents.iter().map(QuorumMember::from).collect::<Vec<_>>();
+                members.sort_by_key(|member| match member {
+                    QuorumMember::MainDocument { .. } => None,
+                    QuorumMember::KeyShard { id, .. } => Some(id.clone()),
+                });
+                let mismatches = reference
+                    .as_ref()
+                    .map(|reference| {
+                        [
+                            (QuorumField::Version, reference.version != id.version),
+                            (
+                                QuorumField::DocumentChecksum,
+                                reference.doc_chksum != id.doc_chksum,
+                            ),
+                            (
+                                QuorumField::QuorumSize,
+                                reference.quorum_size != id.quorum_size,
+                            ),
+                            (
+                                QuorumField::PublicKey,
+                                reference.id_public_key != id.id_public_key,
+                            ),
+                        ]
+                        .into_iter()
+                        .filter_map(|(field, mismatch)| mismatch.then_some(field))
+                        .collect()
+                    })
+                    .unwrap_or_default();
+                QuorumGroupReport {
+                    document_id: DocumentId::from_checksum(id.doc_chksum),
+                    version: id.version,
+                    quorum_size: id.quorum_size,
+                    public_key_fingerprint: codewords::fingerprint(
+                        &ChecksumAlgorithm::default().digest(id.id_public_key.0.as_bytes()),
+                    ),
+                    members,
+                    mismatches,
+                }
+            })
+            .collect();
+
+        Self { groups }
+    }
+}
+
+impl fmt::Display for QuorumReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = [
+            "GROUP",
+            "DOCUMENT",
+            "VERSION",
+            "QUORUM",
+            "PUBLIC KEY",
+            "DOCUMENTS",
+            "MISMATCHES",
+        ]
+        .map(String::from);
+        let rows = self.groups.iter().enumerate().map(|(idx, group)| {
+            [
+                (idx + 1).to_string(),
+                group.document_id.to_string(),
+                group.version.to_string(),
+                group.quorum_size.to_string(),
+                group.public_key_fingerprint.join(" "),
+                group
+                    .members
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                match group.mismatches.as_slice() {
+                    [] => "-".to_string(),
+                    fields => fields
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                },
+            ]
+        });
+        let table = std::iter::once(header).chain(rows).collect::<Vec<_>>();
+
+        let mut widths = [0; 7];
+        for row in &table {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for (idx, row) in table.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            write!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("quorum failed to validate -- possible forgery! {message}\n{report}")]
 pub struct InconsistentQuorumError {
     pub message: String, // TODO: Switch to an Error...
+    kind: InconsistentQuorumKind,
     groups: Grouping,
+    report: QuorumReport,
 }
 
 impl InconsistentQuorumError {
+    fn new<S: Into<String>>(
+        message: S,
+        kind: InconsistentQuorumKind,
+        groups: Vec<Vec<Type>>,
+    ) -> Self {
+        let message = message.into();
+        debug!(?kind, "quorum failed to validate: {}", message);
+        Self {
+            message,
+            kind,
+            report: QuorumReport::new(&groups),
+            groups: Grouping(groups),
+        }
+    }
+
+    pub fn kind(&self) -> InconsistentQuorumKind {
+        self.kind
+    }
+
     pub fn as_groups(&self) -> &Grouping {
         &self.groups
     }
+
+    pub fn report(&self) -> &QuorumReport {
+        &self.report
+    }
 }
 
 impl UntrustedQuorum {
@@ -183,14 +466,48 @@ impl UntrustedQuorum {
         self.untrusted_quorum_size
     }
 
+    /// Add a key shard to the quorum. Pushing the same key shard more than once
+    /// has no effect, but if a *different* key shard with the same id was
+    /// already pushed then the new one is recorded as a conflict (and the quorum
+    /// will fail to validate).
     pub fn push_shard(&mut self, shard: KeyShard) -> &mut Self {
         self.untrusted_quorum_size
             .get_or_insert(shard.quorum_size());
-        self.untrusted_shards
-            .insert((GroupId::from(&shard), shard.id()), shard);
+        let key = (GroupId::from(&shard), shard.id());
+        match self.untrusted_shards.get(&key) {
+            None => {
+                self.untrusted_shards.insert(key, shard);
+            }
+            Some(existing) if existing.to_wire() == shard.to_wire() => (),
+            Some(_) => {
+                let copies = self.conflicting_shards.entry(key).or_default();
+                if !copies.iter().any(|copy| copy.to_wire() == shard.to_wire()) {
+                    copies.push(shard);
+                }
+            }
+        }
         self
     }
 
+    /// The key shards which conflict with another key shard with the same id,
+    /// sorted by shard id.
+    pub fn conflicts(&self) -> Vec<ShardConflict> {
+        let mut conflicts = self
+            .conflicting_shards
+            .iter()
+            .map(|(key, copies)| {
+                let shard = &self.untrusted_shards[key];
+                ShardConflict {
+                    id: shard.id(),
+                    document_id: shard.document_id(),
+                    shards: std::iter::once(shard).chain(copies).cloned().collect(),
+                }
+            })
+            .collect::<Vec<_>>();
+        conflicts.sort_by(|a, b| (&a.id, &a.document_id).cmp(&(&b.id, &b.document_id)));
+        conflicts
+    }
+
     pub fn main_document(&mut self, main: MainDocument) -> &mut Self {
         self.untrusted_quorum_size.get_or_insert(main.quorum_size());
         self.untrusted_main_document = Some(main);
@@ -212,6 +529,13 @@ impl UntrustedQuorum {
             .cloned()
             .map(Type::from)
             .chain(self.untrusted_shards.values().cloned().map(Type::from))
+            .chain(
+                self.conflicting_shards
+                    .values()
+                    .flatten()
+                    .cloned()
+                    .map(Type::from),
+            )
             .collect::<Vec<_>>();
 
         let mut groups: HashMap<GroupId, Vec<Type>> = HashMap::new();
@@ -224,17 +548,43 @@ impl UntrustedQuorum {
         groups.values().cloned().collect::<Vec<_>>()
     }
 
+    #[instrument(
+        name = "validate_quorum",
+        skip_all,
+        fields(
+            main_document = self.untrusted_main_document.is_some(),
+            num_shards = self.untrusted_shards.len(),
+        )
+    )]
     pub fn validate(self) -> Result<Quorum, InconsistentQuorumError> {
         let groups = self.group();
 
+        // Must not contain more than one copy of any key shard.
+        let conflicts = self.conflicts();
+        if !conflicts.is_empty() {
+            return Err(InconsistentQuorumError::new(
+                format!(
+                    "conflicting copies of key shards [{}]",
+                    conflicts
+                        .iter()
+                        .map(|conflict| conflict.id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ),
+                InconsistentQuorumKind::ConflictingShards,
+                groups,
+            ));
+        }
+
         // Must only have one grouping of documents.
         let documents = match &groups[..] {
             [documents] => documents,
             _ => {
-                return Err(InconsistentQuorumError {
-                    message: "key shards and documents are inconsistent".into(),
-                    groups: Grouping(groups),
-                })
+                return Err(InconsistentQuorumError::new(
+                    "key shards and documents are inconsistent",
+                    InconsistentQuorumKind::Inconsistent,
+                    groups,
+                ))
             }
         }
         .iter()
@@ -247,11 +597,14 @@ impl UntrustedQuorum {
             Type::MainDocument(_) | Type::KeyShard(_) => Ok(t),
         })
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|err| InconsistentQuorumError {
-            message: err.into(),
-            // NOTE: We have to clone because the compiler doesn't know that if
-            //       we hit this line we are guaranteed to return immediately.
-            groups: Grouping(groups.clone()),
+        .map_err(|err| {
+            InconsistentQuorumError::new(
+                err,
+                InconsistentQuorumKind::Forged,
+                // NOTE: We have to clone because the compiler doesn't know that if
+                //       we hit this line we are guaranteed to return immediately.
+                groups.clone(),
+            )
         })?;
 
         // Extract the main document from the grouping.
@@ -266,10 +619,11 @@ impl UntrustedQuorum {
             [] => None,
             // Nore than one main document.
             _ => {
-                return Err(InconsistentQuorumError {
-                    message: "more than one main document in grouping".into(),
-                    groups: Grouping(groups),
-                });
+                return Err(InconsistentQuorumError::new(
+                    "more than one main document in grouping",
+                    InconsistentQuorumKind::Inconsistent,
+                    groups,
+                ));
             }
         };
 
@@ -296,11 +650,11 @@ impl UntrustedQuorum {
                 shard.document_checksum(),
             )
         } else {
-            return Err(InconsistentQuorumError {
-                message: "[internal error] no main documents or shards present in quorum"
-                    .to_string(),
-                groups: Grouping(groups),
-            });
+            return Err(InconsistentQuorumError::new(
+                "[internal error] no main documents or shards present in quorum",
+                InconsistentQuorumKind::Inconsistent,
+                groups,
+            ));
         };
 
         assert_eq!(shards.len(), self.untrusted_shards.len());
@@ -309,14 +663,18 @@ impl UntrustedQuorum {
             // XXX: Should probably support having more shards than needed, and have
             //      them act as a double-check operation.
             if main_document.quorum_size() as usize != shards.len() {
-                return Err(InconsistentQuorumError {
-                    message: format!(
+                return Err(InconsistentQuorumError::new(
+                    format!(
                         "quorum size required is {} but had {} shards",
                         main_document.quorum_size(),
                         shards.len()
                     ),
-                    groups: Grouping(groups),
-                });
+                    match shards.len() < main_document.quorum_size() as usize {
+                        true => InconsistentQuorumKind::InsufficientShards,
+                        false => InconsistentQuorumKind::Inconsistent,
+                    },
+                    groups,
+                ));
             }
 
             if main_document.checksum() != doc_chksum
@@ -326,10 +684,11 @@ impl UntrustedQuorum {
                     .quorum_size()
                     .map_or(false, |s| s != main_document.quorum_size())
             {
-                return Err(InconsistentQuorumError {
-                    message: "main document has inconsistent identity".to_string(),
-                    groups: Grouping(groups),
-                });
+                return Err(InconsistentQuorumError::new(
+                    "main document has inconsistent identity",
+                    InconsistentQuorumKind::Inconsistent,
+                    groups,
+                ));
             }
         }
         for shard in shards.iter() {
@@ -340,10 +699,11 @@ impl UntrustedQuorum {
                     .quorum_size()
                     .map_or(false, |s| s != shard.quorum_size())
             {
-                return Err(InconsistentQuorumError {
-                    message: "shard has inconsistent identity".to_string(),
-                    groups: Grouping(groups),
-                });
+                return Err(InconsistentQuorumError::new(
+                    "shard has inconsistent identity",
+                    InconsistentQuorumKind::Inconsistent,
+                    groups,
+                ));
             }
         }
 
@@ -356,6 +716,8 @@ impl UntrustedQuorum {
             id_public_key,
             doc_chksum,
             dealer: OnceCell::new(),
+            identity: None,
+            extended_shards: AtomicUsize::new(0),
         })
     }
 }
@@ -364,11 +726,69 @@ impl UntrustedQuorum {
 pub enum NewShardKind {
     /// Create a new shard with a random `ShardId` (x-value).
     NewShard,
-    /// Re-create the shard with the provided `ShardId`.
+    /// Create a new shard with the provided `ShardId`, which must not be the id
+    /// of a key shard already known to the quorum (see `Quorum::is_issued`).
+    NewShardAt(ShardId),
+    /// Re-create the shard with the provided `ShardId` (which can also be
+    /// parsed from the equivalent `KeyShard::friendly_id`). Re-created key
+    /// shards are never part of the main document's shard commitment, even if
+    /// the original key shard was.
     ExistingShard(ShardId),
 }
 
-#[derive(Debug, Clone)]
+/// Where a key shard minted by `Quorum::new_shard_with_provenance` came from.
+/// This is not part of the key shard itself, but can be printed on its PDF.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardProvenance {
+    /// For new key shards, how many new key shards the quorum had minted
+    /// (including this one). `None` for re-created key shards.
+    pub sequence: Option<usize>,
+    /// When the key shard was minted.
+    pub minted: SystemTime,
+    /// Ids of the key shards in the quorum that minted the key shard.
+    pub quorum: Vec<ShardId>,
+}
+
+impl fmt::Display for ShardProvenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.sequence {
+            Some(sequence) => write!(
+                f,
+                "New key shard {}, minted {}",
+                sequence,
+                format_date(self.minted)
+            )?,
+            None => write!(f, "Re-created {}", format_date(self.minted))?,
+        }
+        write!(f, " from a quorum of [{}].", self.quorum.join(" "))
+    }
+}
+
+/// Summary of the secret data recovered by `Quorum::verify_recoverable`,
+/// which can be shown without exposing the secret data itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecoveryCheck {
+    /// Length (in bytes) of the decrypted contents of the main document.
+    pub length: usize,
+    /// Checksum of the decrypted contents of the main document.
+    pub checksum: Multihash,
+    /// Names of the payloads in the main document (empty unless the backup
+    /// was created with named payloads).
+    pub payloads: Vec<String>,
+}
+
+impl RecoveryCheck {
+    pub fn checksum_string(&self) -> String {
+        multibase::encode(CHECKSUM_MULTIBASE, self.checksum.to_bytes())
+    }
+}
+
+/// A validated quorum. The key shards and any secret material reconstructed
+/// from them are wiped from memory when the `Quorum` is dropped.
+///
+/// A `Quorum` is `Send + Sync`, so it can be shared between threads (such as
+/// to mint key shards in parallel, see `Quorum::new_shards_parallel`).
+#[derive(Debug)]
 pub struct Quorum {
     main_document: Option<MainDocument>,
     shards: Vec<KeyShard>,
@@ -378,6 +798,103 @@ pub struct Quorum {
     doc_chksum: Multihash,
     // Lazy-initialised dealer, reconstructed from key shards.
     dealer: OnceCell<Dealer>,
+    // Identity used to unwrap documents encrypted to a recipient.
+    identity: Option<QuorumIdentity>,
+    // Number of new key shards minted by this quorum.
+    extended_shards: AtomicUsize,
+}
+
+// AtomicUsize is not Clone.
+impl Clone for Quorum {
+    fn clone(&self) -> Self {
+        Self {
+            main_document: self.main_document.clone(),
+            shards: self.shards.clone(),
+            version: self.version,
+            id_public_key: self.id_public_key,
+            doc_chksum: self.doc_chksum,
+            dealer: self.dealer.clone(),
+            identity: self.identity.clone(),
+            extended_shards: AtomicUsize::new(self.extended_shard_count()),
+        }
+    }
+}
+
+/// Wrapper to keep the identity out of debug output.
+#[derive(Clone)]
+struct QuorumIdentity(AgeIdentity);
+
+impl fmt::Debug for QuorumIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("QuorumIdentity(<redacted>)")
+    }
+}
+
+/// Number of bytes of the main document processed at a time by
+/// `chachapoly_decrypt_with_progress`, which is how often it reports progress.
+/// This must be a multiple of the Poly1305 block size.
+const DECRYPT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// ChaCha20-Poly1305 decryption of `ciphertext` (with the tag appended), which
+/// is equivalent to `ChaCha20Poly1305::decrypt` but processes the ciphertext in
+/// chunks of `DECRYPT_CHUNK_SIZE` bytes, calling `progress` with the size of
+/// each chunk. As with `ChaCha20Poly1305::decrypt`, the whole ciphertext is
+/// authenticated before any of it is decrypted, so every byte is reported
+/// twice.
+fn chachapoly_decrypt_with_progress<F: FnMut(usize)>(
+    key: &ChaChaPolyKey,
+    nonce: &ChaChaPolyNonce,
+    aad: &[u8],
+    ciphertext: &[u8],
+    mut progress: F,
+) -> Result<Zeroizing<Vec<u8>>, aead::Error> {
+    let tag_offset = ciphertext
+        .len()
+        .checked_sub(CHACHAPOLY_TAG_LENGTH)
+        .ok_or(aead::Error)?;
+    let (ciphertext, tag) = ciphertext.split_at(tag_offset);
+
+    // The Poly1305 key is the first block of the keystream (RFC 8439).
+    let mut cipher = ChaCha20::new(key, nonce);
+    let mut mac_key = Zeroizing::new(poly1305::Key::default());
+    cipher.apply_keystream(&mut mac_key);
+    let mut mac = Poly1305::new(&mac_key);
+    cipher.seek(64u64);
+
+    mac.update_padded(aad);
+    for chunk in ciphertext.chunks(DECRYPT_CHUNK_SIZE) {
+        mac.update_padded(chunk);
+        progress(chunk.len());
+    }
+    let mut lengths = poly1305::Block::default();
+    lengths[..8].copy_from_slice(&(aad.len() as u64).to_le_bytes());
+    lengths[8..].copy_from_slice(&(ciphertext.len() as u64).to_le_bytes());
+    mac.update(&[lengths]);
+    // This is a constant-time comparison.
+    mac.verify(GenericArray::from_slice(tag))
+        .map_err(|_| aead::Error)?;
+
+    let mut plaintext = Zeroizing::new(ciphertext.to_vec());
+    for chunk in plaintext.chunks_mut(DECRYPT_CHUNK_SIZE) {
+        cipher.apply_keystream(chunk);
+        progress(chunk.len());
+    }
+    Ok(plaintext)
+}
+
+/// Reader which calls `progress` with the number of bytes read by each call
+/// to `read`.
+struct ProgressReader<R, F> {
+    inner: R,
+    progress: F,
+}
+
+impl<R: Read, F: FnMut(usize)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        (self.progress)(n);
+        Ok(n)
+    }
 }
 
 impl Quorum {
@@ -385,6 +902,29 @@ impl Quorum {
         self.main_document.is_some()
     }
 
+    /// The ids of the key shards in the quorum which are not part of the main
+    /// document's shard commitment (see `MainDocument::is_committed_shard`).
+    /// Always empty if the quorum has no main document, or the main document
+    /// has no shard commitment.
+    pub fn uncommitted_shards(&self) -> Vec<ShardId> {
+        match &self.main_document {
+            Some(main_document) => self
+                .shards
+                .iter()
+                .filter(|shard| main_document.is_committed_shard(shard) == Some(false))
+                .map(KeyShard::id)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Provide the identity needed to recover documents whose secret was
+    /// encrypted to a recipient (see `Backup::new_with_recipient`).
+    pub fn set_identity(&mut self, identity: AgeIdentity) -> &mut Self {
+        self.identity = Some(QuorumIdentity(identity));
+        self
+    }
+
     fn get_dealer(&self) -> Result<&Dealer, Error> {
         Ok(self.dealer.get_or_try_init(|| {
             Dealer::recover(
@@ -396,21 +936,149 @@ impl Quorum {
         })?)
     }
 
-    pub fn recover_document(&self) -> Result<Vec<u8>, Error> {
+    /// Reconstruct the shared secret from the key shards now, rather than the
+    /// first time it is needed.
+    ///
+    /// The reconstruction is cached, so all later operations on the quorum
+    /// (recovering the main document, creating new key shards, and so on)
+    /// reuse it. This is useful for doing the expensive part of recovery at a
+    /// predictable time, such as before prompting the user for anything else.
+    pub fn reconstruct(&self) -> Result<(), Error> {
+        self.get_dealer().map(|_| ())
+    }
+
+    /// Recover the `ShardSecret` from the (cached) reconstruction.
+    fn shard_secret(&self) -> Result<ShardSecret, Error> {
+        ShardSecret::from_wire(self.get_dealer()?.secret()).map_err(Error::ShardSecretDecode)
+    }
+
+    /// Which kinds of key shards this quorum can create with
+    /// `Quorum::new_shard`, as chosen when the backup was created.
+    pub fn shard_policy(&self) -> Result<ShardPolicy, Error> {
+        Ok(self.shard_secret()?.shard_policy())
+    }
+
+    /// Recover the secret data stored in the main document. The returned
+    /// buffer is wiped from memory when dropped.
+    ///
+    /// If the main document contains named payloads (see
+    /// `Backup::new_with_payloads`), this only succeeds if there is exactly one
+    /// payload. Otherwise, use `Quorum::recover_payload`. If the main document
+    /// contains a seed phrase (see `BackupBuilder::build_seed_phrase`), the
+    /// phrase is returned as text.
+    pub fn recover_document(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
+        self.recover_document_with_progress(|_, _| ())
+    }
+
+    /// Like `Quorum::recover_document`, but `progress` is called with the
+    /// amount of work done so far and the total amount of work while the main
+    /// document is decrypted, which is useful for showing a progress bar when
+    /// recovering large secrets. Progress is reported for every 64 KiB of the
+    /// main document.
+    pub fn recover_document_with_progress<F: FnMut(usize, usize)>(
+        &self,
+        progress: F,
+    ) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let (plaintext_kind, plaintext) = self.decrypt_document_with_progress(progress)?;
+        match plaintext_kind {
+            PlaintextKind::Secret => Ok(plaintext),
+            PlaintextKind::NamedPayloads => {
+                let mut payloads = NamedPayloads::from_wire(plaintext.as_slice())
+                    .map_err(Error::PayloadsDecode)?;
+                match payloads.0.len() {
+                    1 => Ok(payloads.0.remove(0).into_data()),
+                    _ => Err(Error::MultiplePayloads(payloads.names())),
+                }
+            }
+            PlaintextKind::SeedPhrase => {
+                let seed_phrase =
+                    SeedPhrase::from_wire(plaintext.as_slice()).map_err(Error::SeedPhraseDecode)?;
+                Ok(Zeroizing::new(seed_phrase.phrase()?.as_bytes().to_vec()))
+            }
+        }
+    }
+
+    /// Recover the seed phrase stored in the main document.
+    pub fn recover_seed_phrase(&self) -> Result<SeedPhrase, Error> {
+        let (plaintext_kind, plaintext) = self.decrypt_document()?;
+        if plaintext_kind != PlaintextKind::SeedPhrase {
+            return Err(Error::MissingCapability(
+                "main document does not contain a seed phrase",
+            ));
+        }
+
+        SeedPhrase::from_wire(plaintext.as_slice()).map_err(Error::SeedPhraseDecode)
+    }
+
+    /// Recover all of the named payloads stored in the main document.
+    pub fn recover_payloads(&self) -> Result<Vec<NamedPayload>, Error> {
+        let (plaintext_kind, plaintext) = self.decrypt_document()?;
+        if plaintext_kind != PlaintextKind::NamedPayloads {
+            return Err(Error::MissingCapability(
+                "main document does not contain named payloads",
+            ));
+        }
+
+        Ok(NamedPayloads::from_wire(plaintext.as_slice())
+            .map_err(Error::PayloadsDecode)?
+            .0)
+    }
+
+    /// Recover the named payload called `name` from the main document. The
+    /// returned buffer is wiped from memory when dropped.
+    pub fn recover_payload(&self, name: &str) -> Result<Zeroizing<Vec<u8>>, Error> {
+        self.recover_payloads()?
+            .into_iter()
+            .find(|payload| payload.name() == name)
+            .map(NamedPayload::into_data)
+            .ok_or_else(|| Error::UnknownPayload(name.into()))
+    }
+
+    /// Do a complete recovery of the main document (including decrypting its
+    /// contents), but only return a summary of the recovered secret data. The
+    /// decrypted contents are wiped from memory before returning.
+    ///
+    /// This is useful for periodically checking that a backup can still be
+    /// recovered, without exposing the secret data.
+    pub fn verify_recoverable(&self) -> Result<RecoveryCheck, Error> {
+        let (plaintext_kind, plaintext) = self.decrypt_document()?;
+        let payloads = match plaintext_kind {
+            PlaintextKind::NamedPayloads => NamedPayloads::from_wire(plaintext.as_slice())
+                .map_err(Error::PayloadsDecode)?
+                .names(),
+            _ => vec![],
+        };
+        // Use the same algorithm as the main document's checksum.
+        let algorithm =
+            ChecksumAlgorithm::from_multihash_code(self.doc_chksum.code()).unwrap_or_default();
+        Ok(RecoveryCheck {
+            length: plaintext.len(),
+            checksum: algorithm.digest(&plaintext),
+            payloads,
+        })
+    }
+
+    /// Decrypt the plaintext of the main document, returning what kind of
+    /// plaintext it contains.
+    fn decrypt_document(&self) -> Result<(PlaintextKind, Zeroizing<Vec<u8>>), Error> {
+        self.decrypt_document_with_progress(|_, _| ())
+    }
+
+    /// Like `Quorum::decrypt_document`, but `progress` is called with the
+    /// amount of work done so far and the total amount of work (see
+    /// `Quorum::recover_document_with_progress`).
+    fn decrypt_document_with_progress<F: FnMut(usize, usize)>(
+        &self,
+        mut progress: F,
+    ) -> Result<(PlaintextKind, Zeroizing<Vec<u8>>), Error> {
         let main_document = self.main_document.clone().ok_or(Error::MissingCapability(
             "no main document in quorum -- cannot recover",
         ))?;
-        let shards = self
-            .shards
-            .iter()
-            .map(|s| s.inner.shard.clone())
-            .collect::<Vec<_>>();
-        let secret = ShardSecret::from_wire(Dealer::recover(shards)?.secret())
-            .map_err(Error::ShardSecretDecode)?;
+        let secret = self.shard_secret()?;
 
         // Double-check that the private key agrees with the quorum's public key
         // choice.
-        if let Some(id_keypair) = secret.id_keypair {
+        if let Some(ref id_keypair) = secret.id_keypair {
             if id_keypair.verifying_key() != self.id_public_key {
                 return Err(Error::InvariantViolation(
                     "private key doesn't match quorum public key",
@@ -418,26 +1086,161 @@ impl Quorum {
             }
         }
 
+        // The ciphertext is processed twice (see
+        // chachapoly_decrypt_with_progress), and then the age-encrypted
+        // plaintext (which is the same length, minus the tag) is read once more.
+        let meta = &main_document.inner.meta;
+        let ciphertext = &main_document.inner.ciphertext;
+        let plaintext_len = ciphertext.len().saturating_sub(CHACHAPOLY_TAG_LENGTH);
+        let total = 2 * plaintext_len
+            + if meta.recipient_wrapped {
+                plaintext_len
+            } else {
+                0
+            };
+        progress(0, total);
+        let mut done = 0;
+        let mut report = |n: usize| {
+            done += n;
+            progress(done, total);
+        };
+
         // Decrypt the contents.
-        let aead = ChaCha20Poly1305::new(&secret.doc_key);
-        let payload = Payload {
-            msg: &main_document.inner.ciphertext,
-            aad: &main_document.inner.meta.aad(&self.id_public_key),
+        let plaintext = chachapoly_decrypt_with_progress(
+            &secret.doc_key,
+            &main_document.inner.nonce,
+            &meta.aad(&self.id_public_key),
+            ciphertext,
+            &mut report,
+        )
+        .map_err(Error::AeadDecryption)?;
+
+        let plaintext = match meta.recipient_wrapped {
+            true => {
+                let QuorumIdentity(identity) =
+                    self.identity.as_ref().ok_or(Error::MissingIdentity)?;
+                let recipient_decrypt_err =
+                    |err: &dyn fmt::Display| Error::RecipientDecrypt(err.to_string());
+                let reader = ProgressReader {
+                    inner: plaintext.as_slice(),
+                    progress: &mut report,
+                };
+                let mut reader = age::Decryptor::new(reader)
+                    .and_then(|decryptor| decryptor.decrypt(iter::once(identity as _)))
+                    .map_err(|err| recipient_decrypt_err(&err))?;
+                // Pre-allocate the buffer, so that no copies of the secret are
+                // left behind when it is resized.
+                let mut unwrapped = Zeroizing::new(Vec::with_capacity(plaintext.len()));
+                reader
+                    .read_to_end(&mut unwrapped)
+                    .map_err(|err| recipient_decrypt_err(&err))?;
+                unwrapped
+            }
+            false => plaintext,
+        };
+
+        Ok((main_document.inner.meta.plaintext_kind, plaintext))
+    }
+
+    /// Re-create a sealed backup as an equivalent *unsealed* backup, so that
+    /// more key shards can be created for it in the future.
+    ///
+    /// This requires the main document and a full quorum. The new backup
+    /// contains the same secret, quorum size and expiry, but has a new
+    /// document id and is encrypted with new keys -- none of the existing key
+    /// shards can be used with it. If the main document is encrypted to a
+    /// recipient, the new backup is encrypted to the same recipient.
+    pub fn unseal(&self) -> Result<Backup, Error> {
+        let secret = self.shard_secret()?;
+        if secret.id_keypair.is_some() {
+            return Err(Error::MissingCapability(
+                "document is not sealed -- new key shards can already be created",
+            ));
+        }
+
+        self.copy_with_new_keys(ShardPolicy::Any)
+    }
+
+    /// Re-create the backup with entirely new keys, so that all of the
+    /// existing key shards can be retired at once (such as when too many of
+    /// them may have been exposed).
+    ///
+    /// This requires the main document and a full quorum. Like
+    /// `Quorum::unseal`, the new backup contains the same secret, quorum size
+    /// and expiry (and recipient, if any) but has a new document id, and none
+    /// of the existing key shards can be used with it. Unlike
+    /// `Quorum::unseal`, the new backup keeps the same `ShardPolicy`.
+    pub fn rotate_keys(&self) -> Result<Backup, Error> {
+        self.copy_with_new_keys(self.shard_policy()?)
+    }
+
+    fn copy_with_new_keys(&self, shard_policy: ShardPolicy) -> Result<Backup, Error> {
+        let (_, plaintext) = self.decrypt_document()?;
+        let meta = &self
+            .main_document
+            .as_ref()
+            .expect("decrypt_document requires a main document")
+            .inner
+            .meta;
+        let recipient = match meta.recipient_wrapped {
+            true => {
+                let QuorumIdentity(identity) =
+                    self.identity.as_ref().ok_or(Error::MissingIdentity)?;
+                Some(identity.to_public())
+            }
+            false => None,
+        };
+
+        Backup::copy_with_new_keys(meta, &plaintext, recipient.as_ref(), shard_policy)
+    }
+
+    /// Returns the number of new key shards (not counting re-created key shards)
+    /// minted by this quorum, i.e. how many key shards it has added to those in
+    /// circulation.
+    pub fn extended_shard_count(&self) -> usize {
+        self.extended_shards.load(Ordering::Relaxed)
+    }
+
+    /// Like `Quorum::new_shard`, but also describe where the key shard came
+    /// from.
+    pub fn new_shard_with_provenance(
+        &self,
+        shard_type: NewShardKind,
+    ) -> Result<(KeyShard, ShardProvenance), Error> {
+        let is_new = !matches!(shard_type, NewShardKind::ExistingShard(_));
+        let shard = self.new_shard(shard_type)?;
+        let provenance = ShardProvenance {
+            sequence: is_new.then(|| self.extended_shard_count()),
+            minted: SystemTime::now(),
+            quorum: self.shards.iter().map(KeyShard::id).collect(),
         };
-        aead.decrypt(&main_document.inner.nonce, payload)
-            .map_err(Error::AeadDecryption)
+        Ok((shard, provenance))
     }
 
+    #[instrument(
+        skip_all,
+        fields(new = !matches!(shard_type, NewShardKind::ExistingShard(_)))
+    )]
     pub fn new_shard(&self, shard_type: NewShardKind) -> Result<KeyShard, Error> {
         // Conduct a complete recovery.
         let dealer = self.get_dealer()?;
         let secret = ShardSecret::from_wire(dealer.secret()).map_err(Error::ShardSecretDecode)?;
 
         // Get the private key so we can sign the new shards.
-        let id_keypair = secret.id_keypair.ok_or(Error::MissingCapability(
+        let id_keypair = secret.id_keypair.as_ref().ok_or(Error::MissingCapability(
             "document is sealed -- no new key shards allowed",
         ))?;
 
+        // The backup may have been created with a promise to never re-issue
+        // existing key shards.
+        if matches!(shard_type, NewShardKind::ExistingShard(_))
+            && secret.shard_policy() == ShardPolicy::NewOnly
+        {
+            return Err(Error::MissingCapability(
+                "document forbids re-creating key shards -- only new key shards allowed",
+            ));
+        }
+
         // Make sure the private key matches the expected public key.
         let id_public_key = id_keypair.verifying_key();
         if id_public_key != self.id_public_key {
@@ -446,21 +1249,268 @@ impl Quorum {
             ));
         }
 
+        // Shards carry the timestamps of the backup they belong to (rather
+        // than when they were minted) so that re-created shards are identical
+        // to the originals.
+        let timestamps = match self.main_document {
+            Some(ref main_document) => main_document.inner.meta.timestamps,
+            None => self
+                .shards
+                .first()
+                .map(|s| s.inner.timestamps)
+                .unwrap_or_default(),
+        };
+
         // Extend new shards.
         Ok(KeyShardBuilder {
             version: self.version,
             doc_chksum: self.doc_chksum,
             shard: match shard_type {
-                NewShardKind::NewShard => dealer.next_shard(),
-                NewShardKind::ExistingShard(id) => dealer
-                    .shard(shard::parse_id(id).map_err(Error::ShardIdDecode)?)
-                    .ok_or_else(|| {
+                NewShardKind::NewShard => {
+                    self.extended_shards.fetch_add(1, Ordering::Relaxed);
+                    dealer.next_shard()
+                }
+                NewShardKind::NewShardAt(id) => {
+                    let shard = dealer.shard_at(id.x_value())?;
+                    self.extended_shards.fetch_add(1, Ordering::Relaxed);
+                    shard
+                }
+                NewShardKind::ExistingShard(id) => {
+                    dealer.shard(shard::parse_id(&id)).ok_or_else(|| {
                         Error::Other(
                             "requested shard id has x value of 0 -- refusing to create".to_string(),
                         )
-                    })?,
+                    })?
+                }
             },
+            timestamps,
+            // Only the Backup which created the commitment has the proofs.
+            commitment_proof: None,
+        }
+        .sign(id_keypair))
+    }
+
+    /// Create `n` new key shards (see `Quorum::new_shard`), minting them in
+    /// parallel across several threads. As with `Quorum::new_shard`, the
+    /// returned key shards are guaranteed to have distinct shard ids.
+    #[instrument(skip(self))]
+    pub fn new_shards_parallel(&self, n: usize) -> Result<Vec<KeyShard>, Error> {
+        // Reconstruct the dealer up-front, rather than having every thread
+        // wait for whichever thread got to it first.
+        self.get_dealer()?;
+
+        (0..n)
+            .into_par_iter()
+            .map(|_| self.new_shard(NewShardKind::NewShard))
+            .collect()
+    }
+
+    /// Whether a key shard with the given id is known to this quorum -- either
+    /// because it is one of the key shards in the quorum, or because it was
+    /// minted by the quorum. New key shards never reuse the id of a known key
+    /// shard, but key shards minted by other quorums are not known.
+    pub fn is_issued(&self, id: &ShardId) -> Result<bool, Error> {
+        Ok(self.get_dealer()?.is_issued(id.x_value()))
+    }
+
+    /// Create an `ActivityRecord` stating that `shards` were minted by this
+    /// quorum (see `Quorum::new_shard`), signed with the identity key of the
+    /// backup. As with `Quorum::new_shard`, this is not possible for sealed
+    /// backups.
+    pub fn activity_record<'a, I>(&self, shards: I) -> Result<ActivityRecord, Error>
+    where
+        I: IntoIterator<Item = &'a KeyShard>,
+    {
+        // Conduct a complete recovery.
+        let secret = self.shard_secret()?;
+
+        // Get the private key so we can sign the record.
+        let id_keypair = secret.id_keypair.as_ref().ok_or(Error::MissingCapability(
+            "document is sealed -- no new key shards allowed",
+        ))?;
+
+        // Make sure the private key matches the expected public key.
+        if id_keypair.verifying_key() != self.id_public_key {
+            return Err(Error::InvariantViolation(
+                "id_secret_key doesn't match expected id_public_key",
+            ));
+        }
+
+        let shard_ids = shards
+            .into_iter()
+            .map(|shard| {
+                if shard.document_checksum() != self.doc_chksum
+                    || shard.identity.id_public_key != self.id_public_key
+                {
+                    return Err(Error::InvariantViolation(
+                        "key shard does not belong to the quorum's backup",
+                    ));
+                }
+                Ok(shard.id())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ActivityRecordBuilder {
+            version: self.version,
+            document_id: DocumentId::from_checksum(self.doc_chksum),
+            minted: to_unix_timestamp(SystemTime::now()),
+            shard_ids,
+            quorum: self.shards.iter().map(KeyShard::id).collect(),
+        }
+        .sign(id_keypair))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::{AadVersion, CHACHAPOLY_KEY_LENGTH};
+
+    use aead::{Aead, AeadCore, Payload};
+    use chacha20poly1305::ChaCha20Poly1305;
+    use quickcheck::TestResult;
+
+    fn quorum_for(backup: &Backup) -> Quorum {
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(backup.main_document().clone());
+        for shard in backup.next_shards(2).unwrap() {
+            quorum.push_shard(shard);
+        }
+        quorum.validate().unwrap()
+    }
+
+    #[test]
+    fn tampered_meta_fails_decryption() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let mut quorum = quorum_for(&backup);
+        assert_eq!(*quorum.recover_document().unwrap(), b"some secret data");
+
+        // Even if the signature were not checked, modifying the metadata
+        // makes the contents impossible to decrypt.
+        let main_document = quorum.main_document.as_mut().unwrap();
+        main_document.inner.meta.quorum_size += 1;
+        assert!(matches!(
+            quorum.recover_document(),
+            Err(Error::AeadDecryption(_))
+        ));
+    }
+
+    /// Re-encrypt the contents of the main document in `quorum` using the
+    /// associated data of older versions of paperback.
+    fn encrypt_with_legacy_aad(quorum: &mut Quorum, secret: &[u8]) {
+        let doc_key = quorum.shard_secret().unwrap().doc_key;
+        let main_document = quorum.main_document.as_mut().unwrap();
+        let legacy_aad = main_document.inner.meta.legacy_aad(&quorum.id_public_key);
+        assert_ne!(
+            legacy_aad,
+            main_document.inner.meta.v1_aad(&quorum.id_public_key)
+        );
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut rand::thread_rng());
+        main_document.inner.ciphertext = ChaCha20Poly1305::new(&doc_key)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: secret,
+                    aad: &legacy_aad,
+                },
+            )
+            .unwrap();
+        main_document.inner.nonce = nonce;
+    }
+
+    #[test]
+    fn legacy_aad_decryption() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let mut quorum = quorum_for(&backup);
+
+        // Documents from older versions of paperback have no AAD version.
+        quorum
+            .main_document
+            .as_mut()
+            .unwrap()
+            .inner
+            .meta
+            .aad_version = AadVersion::Legacy;
+        encrypt_with_legacy_aad(&mut quorum, b"some secret data");
+        assert_eq!(*quorum.recover_document().unwrap(), b"some secret data");
+    }
+
+    #[test]
+    fn new_document_rejects_legacy_aad() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let mut quorum = quorum_for(&backup);
+
+        // New documents must only be accepted with the versioned AAD.
+        encrypt_with_legacy_aad(&mut quorum, b"some secret data");
+        assert!(matches!(
+            quorum.recover_document(),
+            Err(Error::AeadDecryption(_))
+        ));
+    }
+
+    #[quickcheck]
+    fn chachapoly_decrypt_with_progress_matches_aead(
+        key: Vec<u8>,
+        aad: Vec<u8>,
+        msg: Vec<u8>,
+        tamper: usize,
+    ) -> TestResult {
+        if key.len() < CHACHAPOLY_KEY_LENGTH {
+            return TestResult::discard();
+        }
+        // Make sure there is more than one chunk.
+        let msg = msg.repeat(DECRYPT_CHUNK_SIZE / 64);
+
+        let key = ChaChaPolyKey::from_slice(&key[..CHACHAPOLY_KEY_LENGTH]);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut rand::thread_rng());
+        let payload = Payload {
+            msg: &msg,
+            aad: &aad,
+        };
+        let mut ciphertext = ChaCha20Poly1305::new(key).encrypt(&nonce, payload).unwrap();
+
+        let mut done = 0;
+        let plaintext =
+            chachapoly_decrypt_with_progress(key, &nonce, &aad, &ciphertext, |n| done += n)
+                .unwrap();
+        if *plaintext != msg || done != 2 * msg.len() {
+            return TestResult::failed();
+        }
+
+        let idx = tamper % ciphertext.len();
+        ciphertext[idx] ^= 0x01;
+        TestResult::from_bool(
+            chachapoly_decrypt_with_progress(key, &nonce, &aad, &ciphertext, |_| ()).is_err(),
+        )
+    }
+
+    #[test]
+    fn recover_document_with_progress() {
+        let secret = vec![0xa5; 3 * DECRYPT_CHUNK_SIZE + 7];
+        let identity = AgeIdentity::generate();
+        for backup in [
+            Backup::new(2, &secret).unwrap(),
+            Backup::new_with_recipient(2, &secret, &identity.to_public(), false, None).unwrap(),
+        ] {
+            let mut quorum = quorum_for(&backup);
+            quorum.set_identity(identity.clone());
+
+            let mut calls = vec![];
+            let recovered = quorum
+                .recover_document_with_progress(|done, total| calls.push((done, total)))
+                .unwrap();
+            assert_eq!(*recovered, secret);
+
+            // Progress must be reported for each chunk of the document, not
+            // just at the start and end.
+            let total = calls[0].1;
+            assert!(total >= 2 * secret.len());
+            assert_eq!(calls.first(), Some(&(0, total)));
+            assert_eq!(calls.last(), Some(&(total, total)));
+            assert!(calls.len() > 2 * 3);
+            assert!(calls
+                .windows(2)
+                .all(|w| w[0].0 <= w[1].0 && w[1].1 == total));
         }
-        .sign(&id_keypair))
     }
 }
@@ -0,0 +1,195 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    from_unix_timestamp, to_unix_timestamp, Argon2Params, ChaChaPolyKey, ChaChaPolyNonce,
+    ChallengeChecksum, DocumentId, Error, FromWire, ShardId, ToWire, PAPERBACK_VERSION,
+};
+
+use std::time::SystemTime;
+
+use aead::{Aead, AeadCore};
+use chacha20poly1305::ChaCha20Poly1305;
+use crypto_common::KeyInit;
+use rand::rngs::OsRng;
+use zeroize::Zeroizing;
+
+/// A record of a key shard being handed out, stored in a shard registry.
+///
+/// The registry is a local (encrypted) log of which key shards were created
+/// for which backup, so that you can keep track of how many key shards are in
+/// circulation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryEntry {
+    pub document_id: DocumentId,
+    pub shard_id: ShardId,
+    /// When the key shard was created (to the nearest second).
+    pub created: SystemTime,
+    /// Free-form note, such as who the key shard was given to.
+    pub label: Option<String>,
+    /// Used to verify the key shard holder's responses to a `ShardChallenge`.
+    /// Entries recorded by older paperback versions do not have one.
+    pub challenge_checksum: Option<ChallengeChecksum>,
+}
+
+impl RegistryEntry {
+    /// Construct an entry for a key shard created now.
+    pub fn new(
+        document_id: DocumentId,
+        shard_id: ShardId,
+        challenge_checksum: ChallengeChecksum,
+        label: Option<String>,
+    ) -> Self {
+        let created = from_unix_timestamp(to_unix_timestamp(SystemTime::now()))
+            .expect("current time should be representable");
+        Self {
+            document_id,
+            shard_id,
+            created,
+            // An empty label is the same as no label.
+            label: label.filter(|label| !label.is_empty()),
+            challenge_checksum: Some(challenge_checksum),
+        }
+    }
+}
+
+/// Parameters needed to derive the registry key from a passphrase, along with
+/// a check value to detect incorrect passphrases. This is stored unencrypted
+/// at the start of the registry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryHeader {
+    pub(super) version: u32,
+    pub(super) kdf: Argon2Params,
+    pub(super) check: SealedRegistryEntry,
+}
+
+/// An encrypted `RegistryEntry`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SealedRegistryEntry {
+    pub(super) nonce: ChaChaPolyNonce,
+    pub(super) ciphertext: Vec<u8>,
+}
+
+/// Key used to encrypt and decrypt the entries of a shard registry, derived
+/// from a passphrase using Argon2id.
+pub struct RegistryKey {
+    header: RegistryHeader,
+    key: Zeroizing<ChaChaPolyKey>,
+}
+
+impl RegistryKey {
+    /// Create a key for a new registry, protected by `passphrase`.
+    pub fn new<B: AsRef<[u8]>>(passphrase: B) -> Result<Self, Error> {
+        Self::new_with_kdf(passphrase.as_ref(), Argon2Params::new_rand(&mut OsRng))
+    }
+
+    fn new_with_kdf(passphrase: &[u8], kdf: Argon2Params) -> Result<Self, Error> {
+        let key = kdf.derive_key(passphrase)?;
+        let check = Self::seal_bytes(&key, &[])?;
+        Ok(Self {
+            header: RegistryHeader {
+                version: PAPERBACK_VERSION,
+                kdf,
+                check,
+            },
+            key,
+        })
+    }
+
+    /// Unlock an existing registry with `passphrase`, returning
+    /// `Error::WrongRegistryPassphrase` if the passphrase is incorrect.
+    pub fn unlock<B: AsRef<[u8]>>(header: &RegistryHeader, passphrase: B) -> Result<Self, Error> {
+        let key = header.kdf.derive_key(passphrase.as_ref())?;
+        Self::open_bytes(&key, &header.check)?;
+        Ok(Self {
+            header: header.clone(),
+            key,
+        })
+    }
+
+    /// The header which must be stored alongside the registry entries.
+    pub fn header(&self) -> &RegistryHeader {
+        &self.header
+    }
+
+    fn seal_bytes(key: &ChaChaPolyKey, plaintext: &[u8]) -> Result<SealedRegistryEntry, Error> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = ChaCha20Poly1305::new(key)
+            .encrypt(&nonce, plaintext)
+            .map_err(Error::AeadEncryption)?;
+        Ok(SealedRegistryEntry { nonce, ciphertext })
+    }
+
+    fn open_bytes(
+        key: &ChaChaPolyKey,
+        entry: &SealedRegistryEntry,
+    ) -> Result<Zeroizing<Vec<u8>>, Error> {
+        ChaCha20Poly1305::new(key)
+            .decrypt(&entry.nonce, entry.ciphertext.as_slice())
+            .map(Zeroizing::new)
+            .map_err(|_| Error::WrongRegistryPassphrase)
+    }
+
+    /// Encrypt `entry` so it can be appended to the registry.
+    pub fn seal(&self, entry: &RegistryEntry) -> Result<SealedRegistryEntry, Error> {
+        Self::seal_bytes(&self.key, &Zeroizing::new(entry.to_wire()))
+    }
+
+    /// Decrypt an entry read from the registry.
+    pub fn open(&self, entry: &SealedRegistryEntry) -> Result<RegistryEntry, Error> {
+        RegistryEntry::from_wire(Self::open_bytes(&self.key, entry)?.as_slice())
+            .map_err(Error::RegistryDecode)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[quickcheck]
+    fn registry_entry_roundtrip(
+        kdf: Argon2Params,
+        document_id: DocumentId,
+        shard_id: ShardId,
+        challenge_checksum: ChallengeChecksum,
+        label: Option<String>,
+        legacy: bool,
+    ) -> bool {
+        let mut entry = RegistryEntry::new(document_id, shard_id, challenge_checksum, label);
+        // Entries recorded by older versions have no challenge checksum.
+        if legacy {
+            entry.challenge_checksum = None;
+        }
+
+        let key = RegistryKey::new_with_kdf(b"passphrase", kdf).unwrap();
+        let header = RegistryHeader::from_wire(key.header().to_wire()).unwrap();
+        let sealed = SealedRegistryEntry::from_wire(key.seal(&entry).unwrap().to_wire()).unwrap();
+
+        let key = RegistryKey::unlock(&header, b"passphrase").unwrap();
+        key.open(&sealed).unwrap() == entry
+    }
+
+    #[quickcheck]
+    fn registry_wrong_passphrase(kdf: Argon2Params) -> bool {
+        let key = RegistryKey::new_with_kdf(b"passphrase", kdf).unwrap();
+        matches!(
+            RegistryKey::unlock(key.header(), b"wrong passphrase"),
+            Err(Error::WrongRegistryPassphrase)
+        )
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{codewords, codewords_to_candidate_entropy, CodewordLanguage, Error};
+
+use std::fmt;
+
+use bip39::Mnemonic;
+use zeroize::Zeroizing;
+
+/// A BIP-39 seed phrase (such as the seed of a cryptocurrency wallet), which
+/// can be backed up with `BackupBuilder::build_seed_phrase`.
+///
+/// Only the entropy of the phrase is stored (rather than the text), which is
+/// much more compact. The checksum word is verified when the phrase is parsed,
+/// and computed again when the phrase is rendered with `SeedPhrase::phrase`.
+/// The entropy is wiped from memory when dropped.
+#[derive(Clone, PartialEq)]
+pub struct SeedPhrase {
+    pub(super) language: CodewordLanguage,
+    pub(super) entropy: Zeroizing<Vec<u8>>,
+}
+
+impl SeedPhrase {
+    /// Parse a BIP-39 phrase, auto-detecting its language. Words are
+    /// normalised the same way as key shard codewords (see
+    /// `codewords::normalize`), so unique prefixes of words are accepted.
+    pub fn from_phrase<S: AsRef<str>>(phrase: S) -> Result<Self, Error> {
+        let words = codewords::normalize(&phrase.as_ref().split_whitespace().collect::<Vec<_>>())?;
+        let (language, entropy) = codewords_to_candidate_entropy(&words)
+            .map_err(|err| match err {
+                Error::InvalidCodewords(err) => Error::InvalidSeedPhrase(err),
+                err => err,
+            })?
+            .into_iter()
+            .next()
+            .ok_or(Error::InvariantViolation(
+                "valid seed phrase must have at least one language",
+            ))?;
+        Ok(Self { language, entropy })
+    }
+
+    /// Create a seed phrase from its entropy, which must be 16, 20, 24, 28 or
+    /// 32 bytes long (12, 15, 18, 21 or 24 words).
+    pub fn from_entropy<B: AsRef<[u8]>>(
+        entropy: B,
+        language: CodewordLanguage,
+    ) -> Result<Self, Error> {
+        let entropy = entropy.as_ref();
+        // Make sure the entropy is a valid length.
+        Mnemonic::from_entropy(entropy, language).map_err(Error::Bip39)?;
+        Ok(Self {
+            language,
+            entropy: Zeroizing::new(entropy.to_vec()),
+        })
+    }
+
+    pub fn language(&self) -> CodewordLanguage {
+        self.language
+    }
+
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    /// Number of words in the phrase (including the checksum word).
+    pub fn num_words(&self) -> usize {
+        // Each word encodes 11 bits, with one checksum bit for every 32 bits
+        // of entropy.
+        self.entropy.len() * 3 / 4
+    }
+
+    /// The BIP-39 seed of the phrase with the given `passphrase` (which is
+    /// empty for wallets without a passphrase), as used to derive wallet keys.
+    pub fn to_seed(&self, passphrase: &str) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let mnemonic =
+            Mnemonic::from_entropy(&self.entropy, self.language).map_err(Error::Bip39)?;
+        Ok(Zeroizing::new(
+            bip39::Seed::new(&mnemonic, passphrase).as_bytes().to_vec(),
+        ))
+    }
+
+    /// Render the phrase as text (with the words separated by spaces).
+    pub fn phrase(&self) -> Result<Zeroizing<String>, Error> {
+        // Mnemonic wipes its copy of the phrase when dropped.
+        Ok(Zeroizing::new(
+            Mnemonic::from_entropy(&self.entropy, self.language)
+                .map_err(Error::Bip39)?
+                .phrase()
+                .to_string(),
+        ))
+    }
+}
+
+// Don't leak the seed through debug output.
+impl fmt::Debug for SeedPhrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeedPhrase")
+            .field("language", &self.language)
+            .field("words", &self.num_words())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+impl quickcheck::Arbitrary for SeedPhrase {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        use crate::v0::CODEWORD_LANGUAGES;
+
+        let language = *g.choose(CODEWORD_LANGUAGES).unwrap();
+        let length = *g.choose(&[16, 20, 24, 28, 32]).unwrap();
+        let entropy = (0..length).map(|_| u8::arbitrary(g)).collect::<Vec<_>>();
+        Self {
+            language,
+            entropy: Zeroizing::new(entropy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ZERO_PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn seed_phrase_parse() {
+        let seed_phrase = SeedPhrase::from_phrase(ZERO_PHRASE).unwrap();
+        assert_eq!(seed_phrase.language(), CodewordLanguage::English);
+        assert_eq!(seed_phrase.entropy(), &[0u8; 16]);
+        assert_eq!(seed_phrase.num_words(), 12);
+        assert_eq!(*seed_phrase.phrase().unwrap(), ZERO_PHRASE);
+
+        // Phrases are normalised like codewords.
+        let seed_phrase2 = SeedPhrase::from_phrase(
+            "ABANDON aban aban aban aban aban aban aban aban aban aban abou",
+        )
+        .unwrap();
+        assert_eq!(seed_phrase, seed_phrase2);
+
+        // The checksum word must be correct.
+        assert!(matches!(
+            SeedPhrase::from_phrase(ZERO_PHRASE.replace("about", "abandon")),
+            Err(Error::InvalidSeedPhrase(_))
+        ));
+        assert!(matches!(
+            SeedPhrase::from_phrase(ZERO_PHRASE.replace("about", "aboutt")),
+            Err(Error::UnknownCodeword { .. })
+        ));
+        assert!(SeedPhrase::from_phrase("").is_err());
+    }
+
+    #[quickcheck]
+    fn seed_phrase_render_roundtrip(seed_phrase: SeedPhrase) -> bool {
+        let phrase = seed_phrase.phrase().unwrap();
+        phrase.split_whitespace().count() == seed_phrase.num_words()
+            && SeedPhrase::from_entropy(seed_phrase.entropy(), seed_phrase.language()).unwrap()
+                == seed_phrase
+    }
+}
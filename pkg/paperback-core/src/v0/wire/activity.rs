@@ -0,0 +1,140 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    wire::{varuint_nom, FromWire, ToWire},
+    ActivityRecord, ActivityRecordBuilder, DocumentId, Identity, ShardId,
+};
+
+use unsigned_varint::encode as varuint_encode;
+
+// Internal only -- users can't see ActivityRecordBuilder.
+#[doc(hidden)]
+impl ToWire for ActivityRecordBuilder {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut buffer = varuint_encode::usize_buffer();
+        let mut bytes = vec![];
+
+        // Encode version.
+        varuint_encode::u32(self.version, &mut varuint_encode::u32_buffer())
+            .iter()
+            .for_each(|b| bytes.push(*b));
+
+        // Encode mint time.
+        bytes.extend_from_slice(varuint_encode::u64(
+            self.minted,
+            &mut varuint_encode::u64_buffer(),
+        ));
+
+        // Encode document id (length-prefixed).
+        bytes.extend_from_slice(varuint_encode::usize(self.document_id.len(), &mut buffer));
+        bytes.extend_from_slice(self.document_id.as_bytes());
+
+        // Encode minted and quorum shard ids (count-prefixed lists of
+        // length-prefixed ids).
+        for ids in [&self.shard_ids, &self.quorum] {
+            bytes.extend_from_slice(varuint_encode::usize(ids.len(), &mut buffer));
+            for id in ids {
+                bytes.extend_from_slice(varuint_encode::usize(id.len(), &mut buffer));
+                bytes.extend_from_slice(id.as_bytes());
+            }
+        }
+
+        bytes
+    }
+}
+
+// Internal only -- users can't see ActivityRecordBuilder.
+#[doc(hidden)]
+impl FromWire for ActivityRecordBuilder {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::length_id;
+        use nom::{combinator::complete, multi::length_count, sequence::tuple, IResult};
+
+        type ParseResult = (u32, u64, DocumentId, Vec<ShardId>, Vec<ShardId>);
+
+        fn parse(input: &[u8]) -> IResult<&[u8], ParseResult> {
+            tuple((
+                varuint_nom::u32,
+                varuint_nom::u64,
+                length_id,
+                length_count(varuint_nom::usize, length_id),
+                length_count(varuint_nom::usize, length_id),
+            ))(input)
+        }
+        let mut parse = complete(parse);
+
+        let (input, (version, minted, document_id, shard_ids, quorum)) =
+            parse(input).map_err(|err| format!("{:?}", err))?;
+
+        Ok((
+            input,
+            ActivityRecordBuilder {
+                version,
+                document_id,
+                minted,
+                shard_ids,
+                quorum,
+            },
+        ))
+    }
+}
+
+impl ToWire for ActivityRecord {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        bytes.append(&mut self.inner.to_wire());
+        bytes.append(&mut self.identity.to_wire());
+
+        bytes
+    }
+}
+
+impl FromWire for ActivityRecord {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        let (input, inner) = ActivityRecordBuilder::from_wire_partial(input)?;
+        let (input, identity) = Identity::from_wire_partial(input)?;
+
+        if inner.version != 0 {
+            return Err(format!(
+                "activity record version must be '0' not '{}'",
+                inner.version
+            ));
+        }
+
+        Ok((input, ActivityRecord { inner, identity }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[quickcheck]
+    fn activity_record_builder_roundtrip(inner: ActivityRecordBuilder) -> bool {
+        let inner2 = ActivityRecordBuilder::from_wire(inner.to_wire()).unwrap();
+        inner == inner2
+    }
+
+    #[quickcheck]
+    fn activity_record_roundtrip(record: ActivityRecord) -> bool {
+        let record2 = ActivityRecord::from_wire(record.to_wire()).unwrap();
+        record == record2
+    }
+}
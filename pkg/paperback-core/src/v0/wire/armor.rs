@@ -0,0 +1,341 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The "armored" plain-text format used by `paperback-cli raw` (and by
+//! `paperback-cli backup --also-raw`), which wraps the multibase data of a
+//! document in a framing that also lists some of its details:
+//!
+//! ```text
+//! ----- BEGIN MAIN DOCUMENT -----
+//! Document-ID: <document id>
+//! Checksum: <checksum>
+//!
+//! <multibase data>
+//! ----- END MAIN DOCUMENT -----
+//! ```
+//!
+//! Key shards are framed with `SHARD <N> OF <TOTAL>` instead, and have
+//! `Document-ID`, `Shard-ID`, `Friendly-Shard-ID`, `Checksum` and `Keywords`
+//! (codewords) headers. Several documents can be concatenated.
+//!
+//! When decoding, the headers are checked against the document data, so that
+//! a corrupted document (or one with the wrong headers pasted in) is detected.
+//! The identifiers of a key shard are stored inside the encrypted shard, so
+//! they can only be checked if the `Keywords` header is present.
+
+use crate::v0::{
+    codewords, EncryptedKeyShard, Error, FromWire, KeyShard, KeyShardCodewords, MainDocument,
+    ToWire,
+};
+
+const BEGIN_PREFIX: &str = "----- BEGIN ";
+const END_PREFIX: &str = "----- END ";
+const FRAME_SUFFIX: &str = " -----";
+
+const MAIN_DOCUMENT_LABEL: &str = "MAIN DOCUMENT";
+
+const HEADER_DOCUMENT_ID: &str = "Document-ID";
+const HEADER_SHARD_ID: &str = "Shard-ID";
+const HEADER_FRIENDLY_SHARD_ID: &str = "Friendly-Shard-ID";
+const HEADER_CHECKSUM: &str = "Checksum";
+const HEADER_CODEWORDS: &str = "Keywords";
+
+const ENCODING_BASE: multibase::Base = multibase::Base::Base32Z;
+
+/// A document decoded from its armored form.
+#[derive(Debug)]
+pub enum Armored {
+    MainDocument(Box<MainDocument>),
+    /// A key shard, along with its codewords if they were included.
+    KeyShard(EncryptedKeyShard, Option<KeyShardCodewords>),
+}
+
+fn frame(label: &str, headers: &[(&str, String)], data: &str) -> String {
+    let mut armored = format!("{BEGIN_PREFIX}{label}{FRAME_SUFFIX}\n");
+    for (key, value) in headers {
+        armored.push_str(&format!("{}: {}\n", key, value));
+    }
+    armored.push_str(&format!("\n{data}\n{END_PREFIX}{label}{FRAME_SUFFIX}\n"));
+    armored
+}
+
+/// Armor a main document.
+pub fn encode_main_document(main_document: &MainDocument) -> String {
+    frame(
+        MAIN_DOCUMENT_LABEL,
+        &[
+            (HEADER_DOCUMENT_ID, main_document.id().to_string()),
+            (HEADER_CHECKSUM, main_document.checksum_string()),
+        ],
+        &main_document.to_wire_multibase(ENCODING_BASE),
+    )
+}
+
+/// Armor the `idx`-th (starting from 0) of `total` key shards, including its
+/// codewords. `shard` must be the decrypted form of `encrypted_shard`.
+pub fn encode_key_shard(
+    idx: usize,
+    total: usize,
+    shard: &KeyShard,
+    encrypted_shard: &EncryptedKeyShard,
+    codewords: &KeyShardCodewords,
+) -> String {
+    frame(
+        &format!("SHARD {} OF {}", idx + 1, total),
+        &[
+            (HEADER_DOCUMENT_ID, shard.document_id().to_string()),
+            (HEADER_SHARD_ID, shard.id().to_string()),
+            (HEADER_FRIENDLY_SHARD_ID, shard.friendly_id()),
+            (HEADER_CHECKSUM, encrypted_shard.checksum_string()),
+            (HEADER_CODEWORDS, codewords.join(" ")),
+        ],
+        &encrypted_shard.to_wire_multibase(ENCODING_BASE),
+    )
+}
+
+/// Whether `input` looks like armored data (rather than bare multibase data).
+pub fn is_armored<S: AsRef<str>>(input: S) -> bool {
+    input
+        .as_ref()
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| line.starts_with(BEGIN_PREFIX))
+}
+
+/// Whether `line` is the END line of an armored block, which is useful for
+/// knowing when to stop reading armored data line-by-line.
+pub fn is_end_line<S: AsRef<str>>(line: S) -> bool {
+    frame_label(line.as_ref().trim(), END_PREFIX).is_some()
+}
+
+fn frame_label<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    line.strip_prefix(prefix)?.strip_suffix(FRAME_SUFFIX)
+}
+
+fn is_key_shard_label(label: &str) -> bool {
+    label
+        .strip_prefix("SHARD ")
+        .and_then(|counts| counts.split_once(" OF "))
+        .is_some_and(|(idx, total)| idx.parse::<usize>().is_ok() && total.parse::<usize>().is_ok())
+}
+
+fn check_header(header: &'static str, found: &str, expected: String) -> Result<(), Error> {
+    if found != expected {
+        return Err(Error::ArmorHeaderMismatch {
+            header,
+            found: found.to_string(),
+            expected,
+        });
+    }
+    Ok(())
+}
+
+fn unknown_header(label: &str, header: &str) -> Error {
+    Error::ArmorDecode(format!("unknown header {:?} in {:?} block", header, label))
+}
+
+/// The headers and data of a block.
+type Block<'a> = (Vec<(&'a str, &'a str)>, String);
+
+/// Read the headers and data of a block, up to (and including) its END line.
+fn decode_block<'a, I: Iterator<Item = &'a str>>(
+    label: &str,
+    lines: &mut I,
+) -> Result<Block<'a>, Error> {
+    let mut headers = Vec::new();
+    let mut data = String::new();
+    let mut in_headers = true;
+    for line in lines {
+        if let Some(end_label) = frame_label(line, END_PREFIX) {
+            if end_label != label {
+                return Err(Error::ArmorDecode(format!(
+                    "{:?} block ended with END {:?}",
+                    label, end_label
+                )));
+            }
+            return Ok((headers, data));
+        }
+        if in_headers {
+            if line.is_empty() {
+                in_headers = false;
+                continue;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                headers.push((key.trim(), value.trim()));
+                continue;
+            }
+            in_headers = false;
+        }
+        // The data can be split over several lines.
+        data.extend(line.split_whitespace());
+    }
+    Err(Error::ArmorDecode(format!(
+        "{:?} block has no END line",
+        label
+    )))
+}
+
+fn decode_main_document(
+    label: &str,
+    headers: &[(&str, &str)],
+    data: &str,
+) -> Result<Armored, Error> {
+    let main_document = MainDocument::from_wire_multibase(data).map_err(Error::ArmorDecode)?;
+    for &(key, value) in headers {
+        match key {
+            HEADER_DOCUMENT_ID => {
+                check_header(HEADER_DOCUMENT_ID, value, main_document.id().to_string())?
+            }
+            HEADER_CHECKSUM => {
+                check_header(HEADER_CHECKSUM, value, main_document.checksum_string())?
+            }
+            _ => return Err(unknown_header(label, key)),
+        }
+    }
+    Ok(Armored::MainDocument(Box::new(main_document)))
+}
+
+fn decode_key_shard(label: &str, headers: &[(&str, &str)], data: &str) -> Result<Armored, Error> {
+    let encrypted_shard =
+        EncryptedKeyShard::from_wire_multibase(data).map_err(Error::ArmorDecode)?;
+    let codewords = headers
+        .iter()
+        .find(|(key, _)| *key == HEADER_CODEWORDS)
+        .map(|(_, value)| codewords::normalize(&value.split_whitespace().collect::<Vec<_>>()))
+        .transpose()?;
+    // The identifiers can only be checked once the shard is decrypted.
+    let shard = codewords
+        .as_ref()
+        .map(|codewords| encrypted_shard.decrypt(codewords))
+        .transpose()?;
+
+    for &(key, value) in headers {
+        match (key, &shard) {
+            (HEADER_CHECKSUM, _) => {
+                check_header(HEADER_CHECKSUM, value, encrypted_shard.checksum_string())?
+            }
+            (HEADER_CODEWORDS, _) => (),
+            (HEADER_DOCUMENT_ID, Some(shard)) => {
+                check_header(HEADER_DOCUMENT_ID, value, shard.document_id().to_string())?
+            }
+            (HEADER_SHARD_ID, Some(shard)) => {
+                check_header(HEADER_SHARD_ID, value, shard.id().to_string())?
+            }
+            (HEADER_FRIENDLY_SHARD_ID, Some(shard)) => {
+                check_header(HEADER_FRIENDLY_SHARD_ID, value, shard.friendly_id())?
+            }
+            (HEADER_DOCUMENT_ID | HEADER_SHARD_ID | HEADER_FRIENDLY_SHARD_ID, None) => (),
+            _ => return Err(unknown_header(label, key)),
+        }
+    }
+    Ok(Armored::KeyShard(encrypted_shard, codewords))
+}
+
+/// Decode all of the armored documents in `input`, checking their headers.
+pub fn decode<S: AsRef<str>>(input: S) -> Result<Vec<Armored>, Error> {
+    let mut lines = input.as_ref().lines().map(str::trim);
+    let mut documents = Vec::new();
+    while let Some(line) = lines.next() {
+        if line.is_empty() {
+            continue;
+        }
+        let label = frame_label(line, BEGIN_PREFIX)
+            .ok_or_else(|| Error::ArmorDecode(format!("expected BEGIN line, found {:?}", line)))?;
+        let (headers, data) = decode_block(label, &mut lines)?;
+        documents.push(match label {
+            MAIN_DOCUMENT_LABEL => decode_main_document(label, &headers, &data)?,
+            label if is_key_shard_label(label) => decode_key_shard(label, &headers, &data)?,
+            label => {
+                return Err(Error::ArmorDecode(format!(
+                    "unknown block type {:?}",
+                    label
+                )))
+            }
+        });
+    }
+    if documents.is_empty() {
+        return Err(Error::ArmorDecode("no armored documents found".into()));
+    }
+    Ok(documents)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::v0::Backup;
+
+    #[test]
+    fn armor_roundtrip() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let main_document = backup.main_document().clone();
+        let shard = backup.next_shard().unwrap();
+        let (encrypted_shard, codewords) = shard.encrypt().unwrap();
+
+        let armored = encode_main_document(&main_document)
+            + &encode_key_shard(0, 1, &shard, &encrypted_shard, &codewords);
+        match &decode(armored).unwrap()[..] {
+            [Armored::MainDocument(main_document2), Armored::KeyShard(encrypted_shard2, Some(codewords2))] =>
+            {
+                assert_eq!(main_document2.as_ref(), &main_document);
+                assert_eq!(encrypted_shard2, &encrypted_shard);
+                assert_eq!(codewords2, &codewords);
+            }
+            documents => panic!("unexpected armored documents {:?}", documents),
+        }
+    }
+
+    #[test]
+    fn armor_header_mismatch() {
+        let backup = Backup::new(2, b"some secret data").unwrap();
+        let other_backup = Backup::new(2, b"some secret data").unwrap();
+
+        let armored = encode_main_document(backup.main_document()).replace(
+            &backup.main_document().checksum_string(),
+            &other_backup.main_document().checksum_string(),
+        );
+        assert!(is_armored(&armored));
+        assert!(matches!(
+            decode(armored),
+            Err(Error::ArmorHeaderMismatch {
+                header: HEADER_CHECKSUM,
+                ..
+            })
+        ));
+
+        // Without the codewords, the shard identifiers cannot be checked.
+        let shard = backup.next_shard().unwrap();
+        let other_shard = backup.next_shard().unwrap();
+        let (encrypted_shard, codewords) = shard.encrypt().unwrap();
+        let armored = encode_key_shard(0, 1, &shard, &encrypted_shard, &codewords)
+            .replace(&shard.id().to_string(), other_shard.id().as_ref());
+        assert!(matches!(
+            decode(&armored),
+            Err(Error::ArmorHeaderMismatch {
+                header: HEADER_SHARD_ID,
+                ..
+            })
+        ));
+        let armored = armored.replace(&format!("Keywords: {}\n", codewords.join(" ")), "");
+        assert!(matches!(
+            &decode(armored).unwrap()[..],
+            [Armored::KeyShard(_, None)]
+        ));
+    }
+}
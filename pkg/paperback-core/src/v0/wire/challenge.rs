@@ -0,0 +1,125 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    wire::{prefixes::*, varuint_nom, FromWire, ToWire},
+    ChaChaPolyNonce, ChaChaPolyTag, ShardChallenge, ShardId, ShardResponse, CHACHAPOLY_TAG_LENGTH,
+    PAPERBACK_VERSION,
+};
+
+use unsigned_varint::encode as varuint_encode;
+
+impl ToWire for ShardChallenge {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        // Encode version.
+        bytes.extend_from_slice(varuint_encode::u32(
+            self.version,
+            &mut varuint_encode::u32_buffer(),
+        ));
+
+        // Encode ChaCha20-Poly1305 nonce.
+        varuint_encode::u64(
+            PREFIX_CHACHA20POLY1305_NONCE,
+            &mut varuint_encode::u64_buffer(),
+        )
+        .iter()
+        .chain(&self.nonce)
+        .for_each(|b| bytes.push(*b));
+
+        bytes
+    }
+}
+
+impl FromWire for ShardChallenge {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::take_chachapoly_nonce;
+        use nom::{combinator::complete, sequence::pair, IResult};
+
+        fn parse(input: &[u8]) -> IResult<&[u8], (u32, ChaChaPolyNonce)> {
+            pair(varuint_nom::u32, take_chachapoly_nonce)(input)
+        }
+        let mut parse = complete(parse);
+
+        let (input, (version, nonce)) = parse(input).map_err(|err| format!("{:?}", err))?;
+        if version != PAPERBACK_VERSION {
+            return Err(format!(
+                "shard challenge version must be '{}' not '{}'",
+                PAPERBACK_VERSION, version
+            ));
+        }
+
+        Ok((input, ShardChallenge { version, nonce }))
+    }
+}
+
+impl ToWire for ShardResponse {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        // Encode shard id (length-prefixed).
+        bytes.extend_from_slice(varuint_encode::usize(
+            self.shard_id.len(),
+            &mut varuint_encode::usize_buffer(),
+        ));
+        bytes.extend_from_slice(self.shard_id.as_bytes());
+
+        // Encode response tag.
+        bytes.extend_from_slice(&self.tag);
+
+        bytes
+    }
+}
+
+impl FromWire for ShardResponse {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::length_id;
+        use nom::{bytes::streaming::take, combinator::complete, sequence::pair, IResult};
+
+        fn parse(input: &[u8]) -> IResult<&[u8], (ShardId, &[u8])> {
+            pair(length_id, take(CHACHAPOLY_TAG_LENGTH))(input)
+        }
+        let mut parse = complete(parse);
+
+        let (input, (shard_id, tag)) = parse(input).map_err(|err| format!("{:?}", err))?;
+
+        Ok((
+            input,
+            ShardResponse {
+                shard_id,
+                tag: ChaChaPolyTag::clone_from_slice(tag),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[quickcheck]
+    fn shard_challenge_roundtrip(challenge: ShardChallenge) -> bool {
+        ShardChallenge::from_wire(challenge.to_wire()).unwrap() == challenge
+    }
+
+    #[quickcheck]
+    fn shard_response_roundtrip(response: ShardResponse) -> bool {
+        ShardResponse::from_wire(response.to_wire()).unwrap() == response
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    commitment::{
+        CommitmentHash, ShardCommitment, ShardCommitmentProof, COMMITMENT_HASH_LENGTH,
+        MAX_COMMITMENT_PROOF_LENGTH,
+    },
+    wire::{prefixes::*, varuint_nom, ToWire},
+};
+
+use alloc::{vec, vec::Vec};
+
+use nom::{
+    bytes::streaming::take,
+    combinator::{map, verify},
+    multi::count,
+    sequence::{pair, preceded},
+    IResult,
+};
+use unsigned_varint::encode as varuint_encode;
+
+fn take_commitment_hash(input: &[u8]) -> IResult<&[u8], CommitmentHash> {
+    map(take(COMMITMENT_HASH_LENGTH), |hash: &[u8]| {
+        let mut arr = CommitmentHash::default();
+        arr.copy_from_slice(hash);
+        arr
+    })(input)
+}
+
+impl ToWire for ShardCommitment {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        varuint_encode::u64(PREFIX_SHARD_COMMITMENT, &mut varuint_encode::u64_buffer())
+            .iter()
+            .chain(varuint_encode::u32(
+                self.num_shards,
+                &mut varuint_encode::u32_buffer(),
+            ))
+            .chain(&self.root)
+            .for_each(|b| bytes.push(*b));
+
+        bytes
+    }
+}
+
+/// Parse a `ShardCommitment`, including its prefix.
+pub(super) fn take_shard_commitment(input: &[u8]) -> IResult<&[u8], ShardCommitment> {
+    map(
+        preceded(
+            verify(varuint_nom::u64, |x| *x == PREFIX_SHARD_COMMITMENT),
+            pair(verify(varuint_nom::u32, |n| *n > 0), take_commitment_hash),
+        ),
+        |(num_shards, root)| ShardCommitment { num_shards, root },
+    )(input)
+}
+
+impl ToWire for ShardCommitmentProof {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        varuint_encode::u64(
+            PREFIX_SHARD_COMMITMENT_PROOF,
+            &mut varuint_encode::u64_buffer(),
+        )
+        .iter()
+        .chain(varuint_encode::u32(
+            self.index,
+            &mut varuint_encode::u32_buffer(),
+        ))
+        .chain(varuint_encode::usize(
+            self.path.len(),
+            &mut varuint_encode::usize_buffer(),
+        ))
+        .chain(self.path.iter().flatten())
+        .for_each(|b| bytes.push(*b));
+
+        bytes
+    }
+}
+
+/// Parse a `ShardCommitmentProof`, including its prefix.
+pub(super) fn take_shard_commitment_proof(input: &[u8]) -> IResult<&[u8], ShardCommitmentProof> {
+    let (input, index) = preceded(
+        verify(varuint_nom::u64, |x| *x == PREFIX_SHARD_COMMITMENT_PROOF),
+        varuint_nom::u32,
+    )(input)?;
+    let (input, len) = verify(varuint_nom::usize, |len| {
+        *len <= MAX_COMMITMENT_PROOF_LENGTH
+    })(input)?;
+    let (input, path) = count(take_commitment_hash, len)(input)?;
+
+    Ok((input, ShardCommitmentProof { index, path }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nom::combinator::complete;
+
+    #[quickcheck]
+    fn shard_commitment_roundtrip(commitment: ShardCommitment) -> bool {
+        let wire = commitment.to_wire();
+        let parsed = complete(take_shard_commitment)(&wire);
+        parsed == Ok((&[][..], commitment))
+    }
+
+    #[quickcheck]
+    fn shard_commitment_proof_roundtrip(proof: ShardCommitmentProof) -> bool {
+        let wire = proof.to_wire();
+        let parsed = complete(take_shard_commitment_proof)(&wire);
+        parsed == Ok((&[][..], proof))
+    }
+}
@@ -17,20 +17,29 @@
  */
 
 use crate::v0::{
-    wire::prefixes::*, ChaChaPolyKey, ChaChaPolyNonce, Multihash, CHACHAPOLY_KEY_LENGTH,
-    CHACHAPOLY_NONCE_LENGTH,
+    wire::{prefixes::*, varuint_nom, ToWire},
+    Argon2Params, ChaChaPolyNonce, Multihash, ARGON2_SALT_LENGTH, CHACHAPOLY_NONCE_LENGTH,
 };
-
-use ed25519_dalek::{SecretKey, Signature, SignatureError, VerifyingKey};
+#[cfg(feature = "std")]
+use crate::v0::{ChaChaPolyKey, CHACHAPOLY_KEY_LENGTH};
+
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "std")]
+use core::str::FromStr;
+
+#[cfg(feature = "std")]
+use ed25519_dalek::SecretKey;
+use ed25519_dalek::{Signature, SignatureError, VerifyingKey};
+#[cfg(feature = "std")]
+use nom::{branch::alt, bytes::streaming::tag, combinator::map};
 use nom::{
-    branch::alt,
-    bytes::streaming::{tag, take},
-    combinator::{map, verify},
+    bytes::streaming::take,
+    combinator::{complete, opt, verify},
     error::{Error as NomError, ErrorKind},
-    sequence::tuple,
+    sequence::{preceded, tuple},
     Err as NomErr, IResult, Needed,
 };
-use unsigned_varint::nom as varuint_nom;
+use unsigned_varint::encode as varuint_encode;
 
 pub(super) fn multihash(input: &[u8]) -> IResult<&[u8], Multihash> {
     use nom::sequence::pair;
@@ -58,6 +67,18 @@ pub(super) fn multihash(input: &[u8]) -> IResult<&[u8], Multihash> {
     Ok((input, hash))
 }
 
+/// Parse a length-prefixed identifier (such as a `ShardId` or `DocumentId`).
+#[cfg(feature = "std")]
+pub(super) fn length_id<T: FromStr>(input: &[u8]) -> IResult<&[u8], T> {
+    use nom::{combinator::map_res, multi::length_data};
+
+    map_res(length_data(varuint_nom::usize), |id: &[u8]| {
+        core::str::from_utf8(id)
+            .map_err(|_| ())
+            .and_then(|id| id.parse().map_err(|_| ()))
+    })(input)
+}
+
 pub(super) fn take_ed25519_pub(
     input: &[u8],
 ) -> IResult<&[u8], Result<VerifyingKey, SignatureError>> {
@@ -84,6 +105,7 @@ pub(super) fn take_ed25519_sig(input: &[u8]) -> IResult<&[u8], Result<Signature,
     Ok((input, Signature::from_slice(sig)))
 }
 
+#[cfg(feature = "std")]
 pub(super) fn take_ed25519_sec(input: &[u8]) -> IResult<&[u8], Option<SecretKey>> {
     let (input, (_, private_key)) = alt((
         tuple((
@@ -122,6 +144,7 @@ pub(super) fn take_ed25519_sec(input: &[u8]) -> IResult<&[u8], Option<SecretKey>
     ))
 }
 
+#[cfg(feature = "std")]
 pub(super) fn take_chachapoly_key(input: &[u8]) -> IResult<&[u8], ChaChaPolyKey> {
     let (input, _) = verify(varuint_nom::u64, |x| *x == PREFIX_CHACHA20POLY1305_KEY)(input)?;
     let (input, key) = take(CHACHAPOLY_KEY_LENGTH)(input)?;
@@ -152,3 +175,59 @@ pub(super) fn take_chachapoly_ciphertext(input: &[u8]) -> IResult<&[u8], &[u8]>
 
     take(length)(input)
 }
+
+/// Parse an optional timestamp with the given prefix. Timestamps were added to
+/// the format later, so documents from older paperback versions lack them.
+pub(super) fn take_timestamp(prefix: u64) -> impl Fn(&[u8]) -> IResult<&[u8], Option<u64>> {
+    move |input| {
+        opt(complete(preceded(
+            verify(varuint_nom::u64, |x| *x == prefix),
+            varuint_nom::u64,
+        )))(input)
+    }
+}
+
+// Internal only -- users can't see Argon2Params.
+#[doc(hidden)]
+impl ToWire for Argon2Params {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        bytes.extend_from_slice(varuint_encode::u64(
+            PREFIX_ARGON2ID_PARAMS,
+            &mut varuint_encode::u64_buffer(),
+        ));
+        for param in [self.memory_kib, self.iterations, self.parallelism] {
+            bytes.extend_from_slice(varuint_encode::u32(
+                param,
+                &mut varuint_encode::u32_buffer(),
+            ));
+        }
+        bytes.extend_from_slice(&self.salt);
+
+        bytes
+    }
+}
+
+/// Parse a set of Argon2id parameters. The parameters are untrusted, so callers
+/// must call `Argon2Params::validate` before using them.
+pub(super) fn take_argon2_params(input: &[u8]) -> IResult<&[u8], Argon2Params> {
+    let (input, (memory_kib, iterations, parallelism, salt)) = preceded(
+        verify(varuint_nom::u64, |x| *x == PREFIX_ARGON2ID_PARAMS),
+        tuple((
+            varuint_nom::u32,
+            varuint_nom::u32,
+            varuint_nom::u32,
+            take(ARGON2_SALT_LENGTH),
+        )),
+    )(input)?;
+
+    let mut params = Argon2Params {
+        memory_kib,
+        iterations,
+        parallelism,
+        salt: [0; ARGON2_SALT_LENGTH],
+    };
+    params.salt.copy_from_slice(salt);
+    Ok((input, params))
+}
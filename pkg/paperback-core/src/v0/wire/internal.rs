@@ -16,12 +16,18 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+#[cfg(feature = "std")]
+use crate::v0::{wire::varuint_nom, ChaChaPolyKey, ShardSecret};
 use crate::v0::{
     wire::{prefixes::*, FromWire, ToWire},
-    ChaChaPolyKey, Identity, ShardSecret,
+    Identity, Timestamps,
 };
 
-use ed25519_dalek::{Signature, SignatureError, SigningKey, VerifyingKey};
+use alloc::{format, string::String, vec, vec::Vec};
+
+#[cfg(feature = "std")]
+use ed25519_dalek::SigningKey;
+use ed25519_dalek::{Signature, SignatureError, VerifyingKey};
 use unsigned_varint::encode as varuint_encode;
 
 // TODO: Completely rewrite this code. This is a very quick-and-dirty
@@ -84,6 +90,7 @@ impl FromWire for Identity {
 }
 
 // Internal only -- users can't see ShardSecret.
+#[cfg(feature = "std")]
 impl ToWire for ShardSecret {
     fn to_wire(&self) -> Vec<u8> {
         let mut buffer = varuint_encode::u64_buffer();
@@ -110,41 +117,101 @@ impl ToWire for ShardSecret {
             .chain(&id_private_key[..])
             .for_each(|b| bytes.push(*b));
 
+        // Only mark the restriction if there is one, so that shard secrets
+        // without it are encoded identically to before.
+        if self.id_keypair.is_some() && !self.allow_recreate {
+            bytes.extend_from_slice(varuint_encode::u64(PREFIX_NO_RECREATE_SHARDS, &mut buffer));
+        }
+
         bytes
     }
 }
 
-type ShardSecretParseResult = (ChaChaPolyKey, Option<SigningKey>);
+#[cfg(feature = "std")]
+type ShardSecretParseResult = (ChaChaPolyKey, Option<SigningKey>, bool);
 
 // Internal only -- users can't see ShardSecret.
+#[cfg(feature = "std")]
 impl FromWire for ShardSecret {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
         use crate::v0::wire::helpers::{take_chachapoly_key, take_ed25519_sec};
-        use nom::{combinator::complete, IResult};
+        use nom::{
+            combinator::{complete, opt, verify},
+            IResult,
+        };
 
         fn parse(input: &[u8]) -> IResult<&[u8], ShardSecretParseResult> {
             let (input, doc_key) = take_chachapoly_key(input)?;
             let (input, private_key) = take_ed25519_sec(input)?;
+            let (input, no_recreate) = opt(complete(verify(varuint_nom::u64, |x| {
+                *x == PREFIX_NO_RECREATE_SHARDS
+            })))(input)?;
 
             Ok((
                 input,
-                (doc_key, private_key.as_ref().map(SigningKey::from_bytes)),
+                (
+                    doc_key,
+                    private_key.as_ref().map(SigningKey::from_bytes),
+                    no_recreate.is_none(),
+                ),
             ))
         }
         let mut parse = complete(parse);
 
-        let (input, (doc_key, id_keypair)) = parse(input).map_err(|err| format!("{:?}", err))?;
+        let (input, (doc_key, id_keypair, allow_recreate)) =
+            parse(input).map_err(|err| format!("{:?}", err))?;
 
         Ok((
             input,
             ShardSecret {
                 doc_key,
                 id_keypair,
+                allow_recreate,
             },
         ))
     }
 }
 
+// Internal only -- users can't see Timestamps.
+impl ToWire for Timestamps {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut buffer = varuint_encode::u64_buffer();
+        let mut bytes = vec![];
+
+        // Encode each timestamp (with prefix) only if present, so that
+        // documents without timestamps are encoded identically to before.
+        for (prefix, timestamp) in [
+            (PREFIX_TIMESTAMP_CREATED, self.created),
+            (PREFIX_TIMESTAMP_EXPIRES, self.expires),
+        ] {
+            if let Some(timestamp) = timestamp {
+                bytes.extend_from_slice(varuint_encode::u64(prefix, &mut buffer));
+                bytes.extend_from_slice(varuint_encode::u64(timestamp, &mut buffer));
+            }
+        }
+
+        bytes
+    }
+}
+
+// Internal only -- users can't see Timestamps.
+impl FromWire for Timestamps {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::take_timestamp;
+        use nom::IResult;
+
+        fn parse(input: &[u8]) -> IResult<&[u8], Timestamps> {
+            let (input, created) = take_timestamp(PREFIX_TIMESTAMP_CREATED)(input)?;
+            let (input, expires) = take_timestamp(PREFIX_TIMESTAMP_EXPIRES)(input)?;
+
+            Ok((input, Timestamps { created, expires }))
+        }
+
+        let (input, timestamps) = parse(input).map_err(|err| format!("{:?}", err))?;
+        Ok((input, timestamps))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -158,6 +225,7 @@ mod test {
     impl PartialEq for ShardSecret {
         fn eq(&self, other: &Self) -> bool {
             self.doc_key == other.doc_key
+                && self.shard_policy() == other.shard_policy()
                 && match (&self.id_keypair, &other.id_keypair) {
                     (Some(left), Some(right)) => left.to_bytes() == right.to_bytes(),
                     (None, None) => true,
@@ -183,7 +251,7 @@ mod test {
     }
 
     #[quickcheck]
-    fn shard_secret_roundtrip(_: u32, sealed: bool) -> bool {
+    fn shard_secret_roundtrip(_: u32, sealed: bool, allow_recreate: bool) -> bool {
         let doc_key = ChaCha20Poly1305::generate_key(&mut OsRng);
         let secret = ShardSecret {
             doc_key,
@@ -191,9 +259,16 @@ mod test {
                 true => None,
                 false => Some(SigningKey::generate(&mut OsRng)),
             },
+            allow_recreate,
         };
         let secret2 = ShardSecret::from_wire(secret.to_wire()).unwrap();
 
         secret == secret2
     }
+
+    #[quickcheck]
+    fn timestamps_roundtrip(timestamps: Timestamps) -> bool {
+        let timestamps2 = Timestamps::from_wire(timestamps.to_wire()).unwrap();
+        timestamps == timestamps2
+    }
 }
@@ -19,13 +19,17 @@
 use crate::{
     shamir::Shard,
     v0::{
-        wire::{prefixes::*, FromWire, ToWire},
-        ChaChaPolyNonce, EncryptedKeyShard, Identity, KeyShard, KeyShardBuilder, Multihash,
-        CHACHAPOLY_NONCE_LENGTH, CHECKSUM_ALGORITHM,
+        wire::{
+            commitment::take_shard_commitment_proof, prefixes::*, varuint_nom, FromWire, ToWire,
+        },
+        Argon2Params, ChaChaPolyNonce, ChecksumAlgorithm, EncryptedKeyShard, Identity, KeyShard,
+        KeyShardBuilder, Multihash, Timestamps, CHACHAPOLY_NONCE_LENGTH,
     },
 };
 
-use unsigned_varint::{encode as varuint_encode, nom as varuint_nom};
+use alloc::{borrow::ToOwned, format, string::String, vec, vec::Vec};
+
+use unsigned_varint::encode as varuint_encode;
 
 // Internal only -- users can't see KeyShardBuilder.
 #[doc(hidden)]
@@ -47,6 +51,14 @@ impl ToWire for KeyShardBuilder {
         // Encode shard data.
         bytes.append(&mut self.shard.to_wire());
 
+        // Encode timestamps.
+        bytes.append(&mut self.timestamps.to_wire());
+
+        // Encode key shard commitment proof (if present).
+        if let Some(proof) = &self.commitment_proof {
+            bytes.append(&mut proof.to_wire());
+        }
+
         bytes
     }
 }
@@ -56,7 +68,10 @@ impl ToWire for KeyShardBuilder {
 impl FromWire for KeyShardBuilder {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
         use crate::v0::wire::helpers::multihash;
-        use nom::{combinator::complete, IResult};
+        use nom::{
+            combinator::{complete, opt},
+            IResult,
+        };
 
         fn parse(input: &[u8]) -> IResult<&[u8], (u32, Multihash)> {
             let (input, version) = varuint_nom::u32(input)?;
@@ -68,6 +83,9 @@ impl FromWire for KeyShardBuilder {
 
         let (input, (version, doc_chksum)) = parse(input).map_err(|err| format!("{:?}", err))?;
         let (input, shard) = Shard::from_wire_partial(input)?;
+        let (input, timestamps) = Timestamps::from_wire_partial(input)?;
+        let (input, commitment_proof) = opt(complete(take_shard_commitment_proof))(input)
+            .map_err(|err| format!("shard commitment proof: {:?}", err))?;
 
         Ok((
             input,
@@ -75,6 +93,8 @@ impl FromWire for KeyShardBuilder {
                 version,
                 doc_chksum,
                 shard,
+                timestamps,
+                commitment_proof,
             },
         ))
     }
@@ -100,8 +120,11 @@ impl FromWire for KeyShard {
         let (input, inner) = KeyShardBuilder::from_wire_partial(input)?;
         let (input, identity) = Identity::from_wire_partial(input)?;
 
-        if inner.doc_chksum.code() != CHECKSUM_ALGORITHM.into() {
-            return Err("document checksum must be Blake2b-256".to_string());
+        if ChecksumAlgorithm::from_multihash_code(inner.doc_chksum.code()).is_none() {
+            return Err(format!(
+                "unsupported document checksum algorithm {:#x}",
+                inner.doc_chksum.code()
+            ));
         }
 
         if inner.version != 0 {
@@ -137,14 +160,24 @@ impl ToWire for EncryptedKeyShard {
             .chain(&self.ciphertext)
             .for_each(|b| bytes.push(*b));
 
+        // Encode Argon2id parameters, only for hardened shards.
+        if let Some(kdf) = &self.kdf {
+            bytes.append(&mut kdf.to_wire());
+        }
+
         bytes
     }
 }
 
 impl FromWire for EncryptedKeyShard {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
-        use crate::v0::wire::helpers::{take_chachapoly_ciphertext, take_chachapoly_nonce};
-        use nom::{combinator::complete, IResult};
+        use crate::v0::wire::helpers::{
+            take_argon2_params, take_chachapoly_ciphertext, take_chachapoly_nonce,
+        };
+        use nom::{
+            combinator::{complete, opt},
+            IResult,
+        };
 
         fn parse(input: &[u8]) -> IResult<&[u8], (ChaChaPolyNonce, &[u8])> {
             let (input, nonce) = take_chachapoly_nonce(input)?;
@@ -154,13 +187,22 @@ impl FromWire for EncryptedKeyShard {
         }
         let mut parse = complete(parse);
 
+        fn parse_kdf(input: &[u8]) -> IResult<&[u8], Option<Argon2Params>> {
+            opt(complete(take_argon2_params))(input)
+        }
+
         let (input, (nonce, ciphertext)) = parse(input).map_err(|err| format!("{:?}", err))?;
+        let (input, kdf) = parse_kdf(input).map_err(|err| format!("{:?}", err))?;
+        if let Some(kdf) = &kdf {
+            kdf.validate()?;
+        }
 
         Ok((
             input,
             EncryptedKeyShard {
                 nonce,
                 ciphertext: ciphertext.into(),
+                kdf,
             },
         ))
     }
@@ -17,11 +17,19 @@
  */
 
 use crate::v0::{
-    wire::{prefixes::*, FromWire, ToWire},
-    ChaChaPolyNonce, Identity, MainDocument, MainDocumentBuilder, MainDocumentMeta,
+    wire::{commitment::take_shard_commitment, prefixes::*, varuint_nom, FromWire, ToWire},
+    AadVersion, ChaChaPolyNonce, ChecksumAlgorithm, Identity, MainDocument, MainDocumentBuilder,
+    MainDocumentMeta, PlaintextKind, PublicInfo, Timestamps,
 };
 
-use unsigned_varint::{encode as varuint_encode, nom as varuint_nom};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use unsigned_varint::encode as varuint_encode;
 
 // Internal only -- users can't see MainDocumentMeta.
 #[doc(hidden)]
@@ -40,6 +48,86 @@ impl ToWire for MainDocumentMeta {
             .iter()
             .for_each(|b| bytes.push(*b));
 
+        // Encode timestamps.
+        bytes.append(&mut self.timestamps.to_wire());
+
+        // Only mark documents with named payloads (or wrapped to a recipient),
+        // so that plain single-secret documents are encoded identically to
+        // before.
+        if self.plaintext_kind == PlaintextKind::NamedPayloads {
+            varuint_encode::u64(PREFIX_NAMED_PAYLOADS, &mut varuint_encode::u64_buffer())
+                .iter()
+                .for_each(|b| bytes.push(*b));
+        }
+        if self.recipient_wrapped {
+            varuint_encode::u64(PREFIX_RECIPIENT_WRAPPED, &mut varuint_encode::u64_buffer())
+                .iter()
+                .for_each(|b| bytes.push(*b));
+        }
+
+        // Likewise, only record the checksum algorithm if it isn't the default.
+        if self.checksum_algorithm != ChecksumAlgorithm::default() {
+            varuint_encode::u64(PREFIX_CHECKSUM_ALGORITHM, &mut varuint_encode::u64_buffer())
+                .iter()
+                .chain(varuint_encode::u64(
+                    self.checksum_algorithm.multihash_code(),
+                    &mut varuint_encode::u64_buffer(),
+                ))
+                .for_each(|b| bytes.push(*b));
+        }
+
+        // Seed phrases were added after the checksum algorithm, so they are
+        // marked last.
+        if self.plaintext_kind == PlaintextKind::SeedPhrase {
+            varuint_encode::u64(PREFIX_SEED_PHRASE, &mut varuint_encode::u64_buffer())
+                .iter()
+                .for_each(|b| bytes.push(*b));
+        }
+
+        // Encode title (if present).
+        if let Some(title) = &self.title {
+            varuint_encode::u64(PREFIX_TITLE, &mut varuint_encode::u64_buffer())
+                .iter()
+                .chain(varuint_encode::usize(
+                    title.len(),
+                    &mut varuint_encode::usize_buffer(),
+                ))
+                .chain(title.as_bytes())
+                .for_each(|b| bytes.push(*b));
+        }
+
+        // Encode key shard commitment (if present).
+        if let Some(shard_commitment) = &self.shard_commitment {
+            bytes.append(&mut shard_commitment.to_wire());
+        }
+
+        // Encode public information (if present).
+        if !self.public_info.is_empty() {
+            varuint_encode::u64(PREFIX_PUBLIC_INFO, &mut varuint_encode::u64_buffer())
+                .iter()
+                .chain(varuint_encode::usize(
+                    self.public_info.len(),
+                    &mut varuint_encode::usize_buffer(),
+                ))
+                .for_each(|b| bytes.push(*b));
+            for info in &self.public_info {
+                for field in [&info.label, &info.value] {
+                    varuint_encode::usize(field.len(), &mut varuint_encode::usize_buffer())
+                        .iter()
+                        .chain(field.as_bytes())
+                        .for_each(|b| bytes.push(*b));
+                }
+            }
+        }
+
+        // Documents created before the associated data was versioned don't
+        // record a version, so only mark documents using the new encoding.
+        if self.aad_version == AadVersion::V1 {
+            varuint_encode::u64(PREFIX_DOCUMENT_AAD_V1, &mut varuint_encode::u64_buffer())
+                .iter()
+                .for_each(|b| bytes.push(*b));
+        }
+
         bytes
     }
 }
@@ -48,23 +136,126 @@ impl ToWire for MainDocumentMeta {
 #[doc(hidden)]
 impl FromWire for MainDocumentMeta {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
-        use nom::{combinator::complete, IResult};
+        use nom::{
+            combinator::{complete, cut, map, map_opt, map_res, opt, verify},
+            multi::{count, length_data},
+            sequence::{pair, preceded},
+            IResult,
+        };
 
-        fn parse(input: &[u8]) -> IResult<&[u8], MainDocumentMeta> {
+        fn parse(input: &[u8]) -> IResult<&[u8], (u32, u32)> {
             let (input, version) = varuint_nom::u32(input)?;
             let (input, quorum_size) = varuint_nom::u32(input)?;
 
-            let meta = MainDocumentMeta {
-                version,
-                quorum_size,
-            };
-
-            Ok((input, meta))
+            Ok((input, (version, quorum_size)))
         }
         let mut parse = complete(parse);
 
-        let (input, meta) = parse(input).map_err(|err| format!("{:?}", err))?;
-        Ok((input, meta))
+        fn parse_flag(prefix: u64) -> impl FnMut(&[u8]) -> IResult<&[u8], bool> {
+            move |input| {
+                let (input, flag) =
+                    opt(complete(verify(varuint_nom::u64, |x| *x == prefix)))(input)?;
+
+                Ok((input, flag.is_some()))
+            }
+        }
+
+        fn parse_checksum_algorithm(input: &[u8]) -> IResult<&[u8], Option<ChecksumAlgorithm>> {
+            opt(complete(preceded(
+                verify(varuint_nom::u64, |x| *x == PREFIX_CHECKSUM_ALGORITHM),
+                // Once the prefix has matched, an unknown (or truncated)
+                // algorithm must not be mistaken for the end of the metadata.
+                cut(complete(map_opt(
+                    varuint_nom::u64,
+                    ChecksumAlgorithm::from_multihash_code,
+                ))),
+            )))(input)
+        }
+
+        fn parse_title(input: &[u8]) -> IResult<&[u8], Option<String>> {
+            opt(complete(preceded(
+                verify(varuint_nom::u64, |x| *x == PREFIX_TITLE),
+                // Once the prefix has matched, an invalid (or truncated) title
+                // must not be mistaken for the end of the metadata.
+                cut(complete(parse_string)),
+            )))(input)
+        }
+
+        fn parse_string(input: &[u8]) -> IResult<&[u8], String> {
+            map_res(length_data(varuint_nom::usize), |s: &[u8]| {
+                String::from_utf8(s.to_vec())
+            })(input)
+        }
+
+        fn parse_public_info(input: &[u8]) -> IResult<&[u8], Vec<PublicInfo>> {
+            let (input, _) = verify(varuint_nom::u64, |x| *x == PREFIX_PUBLIC_INFO)(input)?;
+            // Once the prefix has matched, invalid (or truncated) entries must
+            // not be mistaken for the end of the metadata.
+            cut(complete(|input| {
+                let (input, len) =
+                    verify(varuint_nom::usize, |len| *len <= PublicInfo::MAX_ENTRIES)(input)?;
+                count(
+                    map(pair(parse_string, parse_string), |(label, value)| {
+                        PublicInfo { label, value }
+                    }),
+                    len,
+                )(input)
+            }))(input)
+        }
+
+        let (input, (version, quorum_size)) = parse(input).map_err(|err| format!("{:?}", err))?;
+        let (input, timestamps) = Timestamps::from_wire_partial(input)?;
+        let (input, named_payloads) =
+            parse_flag(PREFIX_NAMED_PAYLOADS)(input).map_err(|err| format!("{:?}", err))?;
+        let (input, recipient_wrapped) =
+            parse_flag(PREFIX_RECIPIENT_WRAPPED)(input).map_err(|err| format!("{:?}", err))?;
+        let (input, checksum_algorithm) =
+            parse_checksum_algorithm(input).map_err(|err| format!("{:?}", err))?;
+        let (input, seed_phrase) =
+            parse_flag(PREFIX_SEED_PHRASE)(input).map_err(|err| format!("{:?}", err))?;
+        let (input, title) = parse_title(input).map_err(|err| format!("title: {:?}", err))?;
+        if let Some(title) = &title {
+            MainDocument::check_title(title).map_err(|err| err.to_string())?;
+        }
+        let (input, shard_commitment) = opt(complete(take_shard_commitment))(input)
+            .map_err(|err| format!("shard commitment: {:?}", err))?;
+        let (input, public_info) = opt(complete(parse_public_info))(input)
+            .map_err(|err| format!("public info: {:?}", err))?;
+        let public_info = public_info.unwrap_or_default();
+        PublicInfo::check_all(&public_info).map_err(|err| err.to_string())?;
+        let (input, aad_v1) =
+            parse_flag(PREFIX_DOCUMENT_AAD_V1)(input).map_err(|err| format!("{:?}", err))?;
+
+        let plaintext_kind = match (named_payloads, seed_phrase) {
+            (false, false) => PlaintextKind::Secret,
+            (true, false) => PlaintextKind::NamedPayloads,
+            (false, true) => PlaintextKind::SeedPhrase,
+            (true, true) => {
+                return Err(
+                    "main document cannot contain both named payloads and a seed phrase"
+                        .to_string(),
+                )
+            }
+        };
+
+        Ok((
+            input,
+            MainDocumentMeta {
+                version,
+                quorum_size,
+                timestamps,
+                plaintext_kind,
+                recipient_wrapped,
+                checksum_algorithm: checksum_algorithm.unwrap_or_default(),
+                title,
+                shard_commitment,
+                public_info,
+                aad_version: match aad_v1 {
+                    true => AadVersion::V1,
+                    false => AadVersion::Legacy,
+                },
+            },
+        ))
     }
 }
 
@@ -157,6 +348,29 @@ impl FromWire for MainDocument {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::v0::Backup;
+
+    /// Replace the first occurrence of `from` in `bytes` with `to`.
+    fn replace_bytes(bytes: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+        let idx = bytes
+            .windows(from.len())
+            .position(|window| window == from)
+            .expect("bytes must contain the pattern");
+        [&bytes[..idx], to, &bytes[idx + from.len()..]].concat()
+    }
+
+    fn varuint(x: u64) -> Vec<u8> {
+        varuint_encode::u64(x, &mut varuint_encode::u64_buffer()).to_vec()
+    }
+
+    fn main_document_meta() -> MainDocumentMeta {
+        Backup::new(2, b"secret")
+            .unwrap()
+            .main_document()
+            .inner
+            .meta
+            .clone()
+    }
 
     #[quickcheck]
     fn main_document_roundtrip(main: MainDocument) -> bool {
@@ -166,4 +380,63 @@ mod test {
 
         main == main2 && main.inner == inner2 && main.inner.meta == meta2
     }
+
+    #[test]
+    fn main_document_meta_unknown_checksum_algorithm() {
+        let meta = MainDocumentMeta {
+            checksum_algorithm: ChecksumAlgorithm::Sha2_256,
+            ..main_document_meta()
+        };
+        let field = [
+            varuint(PREFIX_CHECKSUM_ALGORITHM),
+            varuint(ChecksumAlgorithm::Sha2_256.multihash_code()),
+        ]
+        .concat();
+        // sha2-512 is a valid multihash code, but not a supported algorithm.
+        let bad_field = [varuint(PREFIX_CHECKSUM_ALGORITHM), varuint(0x13)].concat();
+        let wire = replace_bytes(&meta.to_wire(), &field, &bad_field);
+        assert!(MainDocumentMeta::from_wire_partial(&wire).is_err());
+    }
+
+    #[test]
+    fn main_document_meta_invalid_public_info() {
+        let meta = MainDocumentMeta {
+            public_info: vec![PublicInfo::new("label", "value").unwrap()],
+            ..main_document_meta()
+        };
+        let wire = meta.to_wire();
+        let field = [varuint(PREFIX_PUBLIC_INFO), varuint(1)].concat();
+
+        // Too many entries.
+        let bad_field = [
+            varuint(PREFIX_PUBLIC_INFO),
+            varuint(PublicInfo::MAX_ENTRIES as u64 + 1),
+        ]
+        .concat();
+        let bad_wire = replace_bytes(&wire, &field, &bad_field);
+        assert!(MainDocumentMeta::from_wire_partial(&bad_wire).is_err());
+
+        // Invalid UTF-8 in a label.
+        let bad_wire = replace_bytes(&wire, b"label", b"l\xffbel");
+        assert!(MainDocumentMeta::from_wire_partial(&bad_wire).is_err());
+    }
+
+    #[test]
+    fn main_document_meta_invalid_title() {
+        let meta = MainDocumentMeta {
+            title: Some("title".to_string()),
+            ..main_document_meta()
+        };
+        let wire = meta.to_wire();
+
+        // Invalid UTF-8 in the title.
+        let bad_wire = replace_bytes(&wire, b"title", b"t\xfftle");
+        assert!(MainDocumentMeta::from_wire_partial(&bad_wire).is_err());
+
+        // Truncated title.
+        let field = [varuint(PREFIX_TITLE), varuint(5)].concat();
+        let bad_field = [varuint(PREFIX_TITLE), varuint(1 << 20)].concat();
+        let bad_wire = replace_bytes(&wire, &field, &bad_field);
+        assert!(MainDocumentMeta::from_wire_partial(&bad_wire).is_err());
+    }
 }
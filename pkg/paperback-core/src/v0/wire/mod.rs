@@ -16,10 +16,36 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use crate::v0::Error;
+
+use alloc::{format, string::String, vec::Vec};
+
+#[cfg(feature = "std")]
+mod activity;
+#[cfg(feature = "std")]
+pub mod armor;
+#[cfg(feature = "std")]
+mod challenge;
+mod commitment;
 mod helpers;
 mod internal;
 mod key_shard;
 mod main_document;
+#[cfg(feature = "pdf")]
+pub mod ocr;
+#[cfg(feature = "std")]
+mod payload;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+mod seed;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "std")]
+mod session;
+#[cfg(feature = "pdf")]
+pub(crate) mod text;
+pub(crate) mod varuint_nom;
 
 pub(crate) mod prefixes {
     // It's easier to read these bytes if they have unconventional groupings.
@@ -34,14 +60,17 @@ pub(crate) mod prefixes {
 
     /// Prefix for an ed25519 secret key.
     // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "std")]
     pub(super) const PREFIX_ED25519_SECRET: u64 = 0xff_ed25519_536b; // "Sk"
 
     /// Prefix for an ed25519 secret key which has been sealed (equivalent to None).
     // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "std")]
     pub(super) const PREFIX_ED25519_SECRET_SEALED: u64 = 0xff_ed25519_0000;
 
     /// Prefix for a ChaCha20-Poly1305 key.
     // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "std")]
     pub(super) const PREFIX_CHACHA20POLY1305_KEY: u64 = 0xff_caca20_1305;
 
     /// Prefix for a ChaCha20-Poly1305 nonce.
@@ -51,27 +80,203 @@ pub(crate) mod prefixes {
     /// Prefix for a ChaCha20-Poly1305 nonce.
     // NOTE: Entirely our own creation and not remotely upstreamable.
     pub(super) const PREFIX_CHACHA20POLY1305_CIPHERTEXT: u64 = 0xfc_caca20_1305;
+
+    /// Prefix for an (optional) document creation time.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_TIMESTAMP_CREATED: u64 = 0xfb_713e_c7ea;
+
+    /// Prefix for an (optional) document expiry time.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_TIMESTAMP_EXPIRES: u64 = 0xfb_713e_e8b1;
+
+    /// Prefix for an (optional) shamir shard integrity code.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(crate) const PREFIX_SHARD_INTEGRITY_CODE: u64 = 0xfa_54a2_c0de;
+
+    /// Prefix for the (optional) error correction level of a QR code part.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "pdf")]
+    pub(crate) const PREFIX_QR_ERROR_CORRECTION: u64 = 0xf9_ec1e_7e1;
+
+    /// Prefix marking a main document whose plaintext is a set of named
+    /// payloads (rather than a single unnamed secret).
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_NAMED_PAYLOADS: u64 = 0xf8_9a71_0ad5;
+
+    /// Prefix for the (optional) Argon2id parameters of a hardened key shard.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_ARGON2ID_PARAMS: u64 = 0xf7_a260_2d1d;
+
+    /// Prefix marking a main document whose secret was additionally encrypted
+    /// to an age recipient.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_RECIPIENT_WRAPPED: u64 = 0xf6_a9e0_7ec1;
+
+    /// Prefix for the (optional) id of the document a QR code part belongs to.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "pdf")]
+    pub(crate) const PREFIX_QR_DOCUMENT_ID: u64 = 0xf5_d0c0_1d;
+
+    /// Prefix for the (optional) checksum algorithm of a main document, if it
+    /// is not the default (Blake2b-256).
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_CHECKSUM_ALGORITHM: u64 = 0xf4_c4ec_5a19;
+
+    /// Prefix marking a main document whose plaintext is a BIP-39 seed phrase.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_SEED_PHRASE: u64 = 0xf3_5eed_b139;
+
+    /// Prefix for the (optional) challenge checksum of a key shard recorded in
+    /// a shard registry entry.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "std")]
+    pub(super) const PREFIX_CHALLENGE_CHECKSUM: u64 = 0xf2_c4a1_1e9e;
+
+    /// Prefix for the (optional) human-readable title of a main document.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_TITLE: u64 = 0xf1_717e_1abe;
+
+    /// Prefix for the (optional) id of the key shard a QR code part belongs to.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "pdf")]
+    pub(crate) const PREFIX_QR_SHARD_ID: u64 = 0xf0_5a2d_1d;
+
+    /// Prefix marking a shard secret whose quorums may not re-create existing
+    /// key shards (see `ShardPolicy::NewOnly`).
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "std")]
+    pub(super) const PREFIX_NO_RECREATE_SHARDS: u64 = 0xef_2ec2_ea7e;
+
+    /// Prefix for the (optional) commitment to the key shards initially
+    /// created for a main document.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_SHARD_COMMITMENT: u64 = 0xee_c033_1770;
+
+    /// Prefix for the (optional) proof that a key shard is part of its main
+    /// document's key shard commitment.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_SHARD_COMMITMENT_PROOF: u64 = 0xed_c033_9f00f;
+
+    /// Prefix for the committed key shards of a backup session which have not
+    /// been handed out yet.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "std")]
+    pub(super) const PREFIX_COMMITTED_SHARDS: u64 = 0xec_c033_5a2d;
+
+    /// Prefix for the (optional) public information derived from the secret
+    /// data of a main document.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(super) const PREFIX_PUBLIC_INFO: u64 = 0xeb_9b11_c1f0;
+
+    /// Prefix (and version) of the associated data used when encrypting the
+    /// contents of a main document. The associated data itself is never
+    /// stored, but the prefix is also used as a flag in the metadata of
+    /// documents using this version.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    pub(crate) const PREFIX_DOCUMENT_AAD_V1: u64 = 0xea_aad0_0001;
+
+    /// Prefix for the (optional) file permission bits of named payloads.
+    // NOTE: Entirely our own creation and not remotely upstreamable.
+    #[cfg(feature = "std")]
+    pub(super) const PREFIX_PAYLOAD_MODES: u64 = 0xe9_f11e_0de5;
+}
+
+/// The alphabet of one of the multibase encodings used by paperback.
+struct MultibaseAlphabet {
+    /// Every character of the encoding (after the multibase prefix).
+    chars: &'static str,
+    /// Converts characters to the case used by `chars`, for case-insensitive
+    /// encodings.
+    fold_case: fn(char) -> char,
+    /// Characters which are not part of the alphabet but are easily mistaken
+    /// for one of its characters (such as by OCR), and the character they were
+    /// most likely meant to be.
+    substitutions: &'static [(char, char)],
 }
 
-pub fn multibase_strip<S: AsRef<str>>(data: S) -> Result<String, String> {
-    let data = data.as_ref();
-    match data.chars().next() {
-        // TODO: Probably we should just retain valid characters in the code.
-        // But multibase doesn't expose this currently.
-        Some(ch) => Ok(data.replace(
-            match multibase::Base::from_code(ch) {
-                Ok(multibase::Base::Base64Url) | Ok(multibase::Base::Base64UrlPad) => {
-                    &['\t', ' ', '\n'][..]
-                } // url-base64 -- do not remove "-"
-                Ok(_) => &['\t', ' ', '\n', '-'][..], // url-base64 -- do not remove "-"
-                Err(err) => return Err(format!("error parsing multibase string: {}", err)),
-            },
-            "",
-        )),
-        None => Err("error parsing multibase string: empty string".to_string()),
+impl MultibaseAlphabet {
+    const BASE64URL: &'static str =
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    const BASE64URL_PAD: &'static str =
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_=";
+
+    /// The alphabet of `base`, or `None` if paperback never generates data
+    /// encoded with `base`.
+    fn of(base: multibase::Base) -> Option<Self> {
+        use multibase::Base;
+
+        match base {
+            Base::Base10 => Some(Self {
+                chars: "0123456789",
+                fold_case: |ch| ch,
+                substitutions: &[('O', '0'), ('o', '0'), ('I', '1'), ('l', '1'), ('|', '1')],
+            }),
+            Base::Base32Upper => Some(Self {
+                chars: "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+                fold_case: |ch| ch.to_ascii_uppercase(),
+                substitutions: &[('0', 'O'), ('1', 'I'), ('8', 'B')],
+            }),
+            // zbase32 deliberately leaves out the characters most easily
+            // confused with the rest of its alphabet.
+            Base::Base32Z => Some(Self {
+                chars: "ybndrfg8ejkmcpqxot1uwisza345h769",
+                fold_case: |ch| ch.to_ascii_lowercase(),
+                substitutions: &[('0', 'o'), ('l', '1'), ('2', 'z')],
+            }),
+            // Base64 is case-sensitive and uses every digit, so no characters
+            // can be safely substituted.
+            Base::Base64Url => Some(Self {
+                chars: Self::BASE64URL,
+                fold_case: |ch| ch,
+                substitutions: &[],
+            }),
+            Base::Base64UrlPad => Some(Self {
+                chars: Self::BASE64URL_PAD,
+                fold_case: |ch| ch,
+                substitutions: &[],
+            }),
+            _ => None,
+        }
+    }
+
+    /// Convert `ch` to the character of the alphabet it most likely was, or
+    /// `None` if it is not part of the encoded data (such as whitespace or the
+    /// dashes used to split up the text fallback).
+    fn normalise(&self, ch: char) -> Option<char> {
+        let ch = (self.fold_case)(ch);
+        let ch = self
+            .substitutions
+            .iter()
+            .find(|(from, _)| *from == ch)
+            .map_or(ch, |(_, to)| *to);
+        self.chars.contains(ch).then_some(ch)
     }
 }
 
+/// Strip every character which is not part of the encoding of multibase
+/// `data` (such as whitespace, or the dashes printed in the text fallback),
+/// and correct characters which are commonly misread as one of the characters
+/// of the encoding (such as "0" for "o" in zbase32).
+///
+/// Only the encodings used by paperback are corrected -- for any other
+/// encoding only whitespace is stripped.
+pub fn multibase_strip<S: AsRef<str>>(data: S) -> Result<String, Error> {
+    let data = data.as_ref().trim_start();
+    let mut chars = data.chars();
+    let prefix = chars
+        .next()
+        .ok_or(Error::MultibaseDecode(multibase::Error::InvalidBaseString))?;
+    let base = multibase::Base::from_code(prefix).map_err(Error::MultibaseDecode)?;
+
+    Ok(match MultibaseAlphabet::of(base) {
+        Some(alphabet) => Some(prefix)
+            .into_iter()
+            .chain(chars.filter_map(|ch| alphabet.normalise(ch)))
+            .collect(),
+        None => data.chars().filter(|ch| !ch.is_whitespace()).collect(),
+    })
+}
+
 // TODO: Switch the errors from String to a proper thiserror error type.
 
 pub trait ToWire {
@@ -87,16 +292,160 @@ pub trait FromWire: Sized {
     fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String>;
 
     fn from_wire<B: AsRef<[u8]>>(input: B) -> Result<Self, String> {
-        match Self::from_wire_partial(input.as_ref())? {
-            ([], ret) => Ok(ret),
-            _ => Err("trailing bytes left after deseralisation".into()),
+        let input = input.as_ref();
+        let ret = match Self::from_wire_partial(input) {
+            Ok(([], ret)) => Ok(ret),
+            Ok(_) => Err("trailing bytes left after deseralisation".into()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = &ret {
+            tracing::debug!(
+                r#type = core::any::type_name::<Self>(),
+                len = input.len(),
+                "failed to parse wire data: {}",
+                err
+            );
         }
+        ret
     }
 
     /// Parse a zbase32-encoded representation of a `FromWire`-implementing type
     /// as that type.
     fn from_wire_multibase<S: AsRef<str>>(input: S) -> Result<Self, String> {
-        let (_, data) = multibase::decode(input).map_err(|err| format!("{:?}", err))?;
+        let (_, data) = multibase::decode(input).map_err(|err| {
+            tracing::debug!(
+                r#type = core::any::type_name::<Self>(),
+                "failed to decode multibase data: {:?}",
+                err
+            );
+            format!("{:?}", err)
+        })?;
         Self::from_wire(data)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use multibase::Base;
+    use quickcheck::TestResult;
+
+    /// Split encoded data into 4-character words joined with `sep`, like the
+    /// text fallback printed on documents.
+    fn split_words(encoded: &str, sep: &str) -> String {
+        encoded
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(4)
+            .map(String::from_iter)
+            .collect::<Vec<_>>()
+            .join(sep)
+    }
+
+    /// Replace characters in the encoded data (but not the multibase prefix).
+    fn mangle(encoded: &str, mangle: impl Fn(char) -> char) -> String {
+        let mut chars = encoded.chars();
+        chars.next().into_iter().chain(chars.map(mangle)).collect()
+    }
+
+    fn strip_decodes(input: &str, data: &[u8]) -> bool {
+        let stripped = multibase_strip(input).unwrap();
+        multibase::decode(stripped).map(|(_, decoded)| decoded).ok() == Some(data.to_vec())
+    }
+
+    #[quickcheck]
+    fn multibase_strip_separators(data: Vec<u8>) -> bool {
+        [Base::Base10, Base::Base32Upper, Base::Base32Z]
+            .into_iter()
+            .all(|base| {
+                let encoded = multibase::encode(base, &data);
+                strip_decodes(&encoded, &data)
+                    && strip_decodes(&split_words(&encoded, "-"), &data)
+                    && strip_decodes(&split_words(&encoded, " \n\t"), &data)
+                    && strip_decodes(&format!("  {}-----\n", encoded), &data)
+            })
+    }
+
+    #[quickcheck]
+    fn multibase_strip_base64url(data: Vec<u8>) -> bool {
+        // "-" is part of the url-safe base64 alphabet, so it must be kept.
+        [Base::Base64Url, Base::Base64UrlPad]
+            .into_iter()
+            .all(|base| {
+                let encoded = multibase::encode(base, &data);
+                strip_decodes(&encoded, &data)
+                    && strip_decodes(&split_words(&encoded, " \n"), &data)
+            })
+    }
+
+    #[quickcheck]
+    fn multibase_strip_ocr_base10(data: Vec<u8>) -> bool {
+        let encoded = multibase::encode(Base::Base10, &data);
+        strip_decodes(
+            &mangle(&encoded, |ch| match ch {
+                '0' => 'O',
+                '1' => 'l',
+                ch => ch,
+            }),
+            &data,
+        )
+    }
+
+    #[quickcheck]
+    fn multibase_strip_ocr_base32upper(data: Vec<u8>) -> bool {
+        let encoded = multibase::encode(Base::Base32Upper, &data);
+        strip_decodes(
+            &mangle(&encoded, |ch| match ch {
+                'O' => '0',
+                'I' => '1',
+                'B' => '8',
+                ch => ch.to_ascii_lowercase(),
+            }),
+            &data,
+        )
+    }
+
+    #[quickcheck]
+    fn multibase_strip_ocr_base32z(data: Vec<u8>) -> bool {
+        let encoded = multibase::encode(Base::Base32Z, &data);
+        strip_decodes(
+            &mangle(&encoded, |ch| match ch {
+                'o' => '0',
+                '1' => 'l',
+                'z' => '2',
+                ch => ch.to_ascii_uppercase(),
+            }),
+            &data,
+        )
+    }
+
+    #[quickcheck]
+    fn multibase_strip_other_bases(data: Vec<u8>) -> TestResult {
+        // Only whitespace is stripped from bases paperback doesn't use.
+        let encoded = multibase::encode(Base::Base58Btc, &data);
+        if encoded.len() < 2 {
+            return TestResult::discard();
+        }
+        TestResult::from_bool(
+            multibase_strip(split_words(&encoded, " ")).unwrap() == encoded
+                && multibase_strip(mangle(&encoded, |_| '-')).unwrap() == mangle(&encoded, |_| '-'),
+        )
+    }
+
+    #[test]
+    fn multibase_strip_invalid() {
+        assert!(matches!(
+            multibase_strip(""),
+            Err(Error::MultibaseDecode(_))
+        ));
+        assert!(matches!(
+            multibase_strip("  \n"),
+            Err(Error::MultibaseDecode(_))
+        ));
+        assert!(matches!(
+            multibase_strip("!abcd"),
+            Err(Error::MultibaseDecode(_))
+        ));
+    }
+}
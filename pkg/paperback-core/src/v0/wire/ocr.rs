@@ -0,0 +1,334 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Reconstruction of text fallbacks read using OCR (optical character
+//! recognition), such as from a photo of a key shard whose QR codes are too
+//! damaged to be scanned.
+//!
+//! OCR engines make fairly predictable mistakes, so the OCR output is cleaned
+//! up using what we know about the layout of the text fallback -- each line is
+//! a series of 4-character zbase32 words joined with "-" (and padded with "-"
+//! to a fixed width). Plain text fallbacks have no redundancy, so characters
+//! which are commonly confused with each other are then swapped until the data
+//! matches the checksum printed next to it. Error-correcting text fallbacks
+//! (see `TextFallback::ErrorCorrecting`) can correct these mistakes on their
+//! own.
+
+use crate::v0::{wire::text, Error};
+
+/// Pairs of zbase32 characters which OCR engines commonly confuse.
+const CONFUSABLE_CHARS: &[(char, char)] = &[('1', 'i'), ('5', 's'), ('9', 'g'), ('8', 'b')];
+
+/// Maximum number of confusable characters swapped at once when looking for
+/// data that matches the checksum.
+pub const MAX_SWAPS: usize = 3;
+
+/// Characters that OCR engines produce in place of "-".
+const DASH_CHARS: &[char] = &['-', '_', '~', '=', '‐', '‑', '–', '—', '−'];
+
+/// Clean up a line of OCR output, returning `None` if it does not look like a
+/// line of a text fallback (such as the label above it, or the rest of the
+/// page).
+fn fallback_line(line: &str) -> Option<String> {
+    let line = line
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .map(|ch| match DASH_CHARS.contains(&ch) {
+            true => '-',
+            false => ch,
+        })
+        .collect::<String>();
+    let data = line.strip_prefix(text::ECC_PREFIX).unwrap_or(&line);
+    // Words are joined with "-", so there are always some in a real line
+    // (unless the whole text fallback is a single word).
+    if !data.contains('-') {
+        return None;
+    }
+    // Use the canonical form of any characters commonly confused with zbase32
+    // characters (such as "0" for "o").
+    let data = data
+        .chars()
+        .map(|ch| match ch {
+            '-' => Some('-'),
+            ch => text::zbase32_value(ch).map(|v| text::ZBASE32_ALPHABET[v as usize] as char),
+        })
+        .collect::<Option<String>>()?;
+    // Most words should be 4 characters long, which rules out most hyphenated
+    // words elsewhere on the page. The last word of the line is not counted,
+    // since the final word of the text fallback can be shorter.
+    let words = data
+        .split('-')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    let (_, words) = words.split_last()?;
+    if words.iter().filter(|word| word.len() % 4 == 0).count() * 2 < words.len() {
+        return None;
+    }
+    match line.starts_with(text::ECC_PREFIX) {
+        true => Some(format!("{}{}", text::ECC_PREFIX, data)),
+        false => Some(data),
+    }
+}
+
+/// Split the lines of a plain text fallback into its 4-character words. Words
+/// whose "-" was missed by the OCR engine are split again, and any other
+/// words of the wrong length are reported as errors.
+fn plain_fallback_words(lines: &[String]) -> Result<Vec<String>, Error> {
+    let mut words = vec![];
+    for (idx, line) in lines.iter().enumerate() {
+        for word in line.split('-').filter(|word| !word.is_empty()) {
+            match word.len() {
+                len if len % 4 == 0 => words.extend(
+                    word.as_bytes()
+                        .chunks(4)
+                        .map(|word| String::from_utf8_lossy(word).into_owned()),
+                ),
+                // Only the final word can be shorter.
+                _ if words.last().is_some_and(|last: &String| last.len() != 4) => {
+                    return Err(Error::TextFallbackDecode(format!(
+                        "line {} contains a word that is not 4 characters long (check the text fallback for missing or extra characters)",
+                        idx + 1
+                    )))
+                }
+                _ => words.push(word.to_string()),
+            }
+        }
+    }
+    // A short word must have been the final word.
+    if let Some(idx) = words.iter().rev().skip(1).position(|word| word.len() != 4) {
+        return Err(Error::TextFallbackDecode(format!(
+            "word {} is not 4 characters long (check the text fallback for missing or extra characters)",
+            words.len() - 1 - idx
+        )));
+    }
+    Ok(words)
+}
+
+/// Call `f` with each `k`-combination of `0..n` (in lexicographic order),
+/// stopping at the first one for which `f` returns `Some`.
+fn find_combination<T, F: FnMut(&[usize]) -> Option<T>>(n: usize, k: usize, mut f: F) -> Option<T> {
+    if k > n {
+        return None;
+    }
+    let mut idxs = (0..k).collect::<Vec<_>>();
+    loop {
+        if let Some(found) = f(&idxs) {
+            return Some(found);
+        }
+        // Find the rightmost index which can still be incremented.
+        let idx = (0..k).rev().find(|&i| idxs[i] < n - k + i)?;
+        idxs[idx] += 1;
+        for i in idx + 1..k {
+            idxs[i] = idxs[i - 1] + 1;
+        }
+    }
+}
+
+/// Reconstruct the data of a text fallback from the (possibly messy) text an
+/// OCR engine read from an image of it. Text before and after the text
+/// fallback is ignored (only the first text fallback is used), but OCR works
+/// best if the image is cropped to just the text fallback.
+///
+/// `is_valid` should check the reconstructed data against a checksum which was
+/// printed along with the text fallback (such as the key shard checksum). For
+/// plain text fallbacks, up to [`MAX_SWAPS`] commonly confused characters are
+/// swapped until `is_valid` accepts the data.
+pub fn reconstruct_text_fallback<S, F>(ocr_text: S, mut is_valid: F) -> Result<Vec<u8>, Error>
+where
+    S: AsRef<str>,
+    F: FnMut(&[u8]) -> bool,
+{
+    // The text fallback starts with a multibase code (or ECC_PREFIX), and its
+    // lines are printed together.
+    let plain_prefix = multibase::Base::Base32Z.code();
+    let lines = ocr_text
+        .as_ref()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(fallback_line)
+        .skip_while(|line| {
+            !line
+                .as_deref()
+                .is_some_and(|line| line.starts_with([text::ECC_PREFIX, plain_prefix]))
+        })
+        .map_while(|line| line)
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return Err(Error::TextFallbackDecode(
+            "no text fallback found in text".into(),
+        ));
+    }
+
+    if lines[0].starts_with(text::ECC_PREFIX) {
+        let data = text::decode(lines.join("\n")).map_err(Error::TextFallbackDecode)?;
+        return match is_valid(&data) {
+            true => Ok(data),
+            false => Err(Error::TextFallbackDecode(
+                "text fallback does not match the checksum".into(),
+            )),
+        };
+    }
+
+    let mut chars = plain_fallback_words(&lines)?
+        .concat()
+        .chars()
+        .collect::<Vec<_>>();
+    let confusable = chars
+        .iter()
+        .enumerate()
+        .filter(|(_, ch)| CONFUSABLE_CHARS.iter().any(|(a, b)| *ch == a || *ch == b))
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    let swap = |ch: char| {
+        CONFUSABLE_CHARS
+            .iter()
+            .find_map(|&(a, b)| match (ch == a, ch == b) {
+                (true, _) => Some(b),
+                (_, true) => Some(a),
+                _ => None,
+            })
+            .expect("only confusable characters are swapped")
+    };
+    (0..=MAX_SWAPS)
+        .find_map(|k| {
+            find_combination(confusable.len(), k, |swaps| {
+                for &idx in swaps {
+                    chars[confusable[idx]] = swap(chars[confusable[idx]]);
+                }
+                let found = multibase::decode(chars.iter().collect::<String>())
+                    .ok()
+                    .map(|(_, data)| data)
+                    .filter(|data| is_valid(data));
+                // Undo the swaps for the next attempt.
+                for &idx in swaps {
+                    chars[confusable[idx]] = swap(chars[confusable[idx]]);
+                }
+                found
+            })
+        })
+        .ok_or_else(|| {
+            Error::TextFallbackDecode(format!(
+                "text fallback does not match the checksum, even after swapping up to {} commonly confused characters (retake the photo, or correct the text by hand)",
+                MAX_SWAPS
+            ))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use multibase::Base;
+
+    /// Lay out `data` like the plain text fallback in a PDF.
+    fn plain_fallback(data: &[u8]) -> Vec<String> {
+        multibase::encode(Base::Base32Z, data)
+            .into_bytes()
+            .chunks(4)
+            .map(|word| String::from_utf8_lossy(word).into_owned())
+            .collect::<Vec<_>>()
+            .chunks(8)
+            .map(|words| format!("{:-<39}", words.join("-")))
+            .collect()
+    }
+
+    #[test]
+    fn find_combination_all() {
+        let mut combinations = vec![];
+        find_combination(4, 2, |idxs| {
+            combinations.push(idxs.to_vec());
+            None::<()>
+        });
+        assert_eq!(
+            combinations,
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+        assert_eq!(find_combination(3, 0, |idxs| Some(idxs.len())), Some(0));
+        assert_eq!(find_combination(2, 3, |_| Some(())), None);
+    }
+
+    #[test]
+    fn reconstruct_plain_fallback() {
+        let data = (0..100u8).map(|i| i.wrapping_mul(37)).collect::<Vec<_>>();
+        let lines = plain_fallback(&data);
+
+        // Typical OCR mistakes: the label and other text on the page, spaces
+        // between characters, uppercase and look-alike characters, odd dashes,
+        // and a missing "-".
+        let mut ocr_lines = vec!["Text fallback:".to_string()];
+        for (idx, line) in lines.iter().enumerate() {
+            let line = match idx {
+                0 => line.replace('-', " - ").to_uppercase(),
+                1 => line.replace('o', "0").replacen('-', "—", 3),
+                2 => line.replacen('-', "", 1),
+                _ => line.clone(),
+            };
+            ocr_lines.push(line);
+        }
+        ocr_lines.push("Checksum".to_string());
+        ocr_lines.push("some-hyphenated-text".to_string());
+        let ocr_text = ocr_lines.join("\n");
+        assert_eq!(
+            reconstruct_text_fallback(&ocr_text, |found| found == data).unwrap(),
+            data
+        );
+
+        // Swapped look-alike characters are fixed using the checksum.
+        let swapped = lines.join("\n").replacen('s', "5", 1).replacen('i', "1", 1);
+        assert_eq!(
+            reconstruct_text_fallback(&swapped, |found| found == data).unwrap(),
+            data
+        );
+
+        // Missing characters cannot be fixed.
+        let truncated = lines[0][1..].to_string() + "\n" + &lines[1..].join("\n");
+        assert!(matches!(
+            reconstruct_text_fallback(truncated, |found| found == data),
+            Err(Error::TextFallbackDecode(_))
+        ));
+        assert!(matches!(
+            reconstruct_text_fallback("no fallback here", |_| true),
+            Err(Error::TextFallbackDecode(_))
+        ));
+    }
+
+    #[test]
+    fn reconstruct_ecc_fallback() {
+        let data = (0..100u8).map(|i| i.wrapping_mul(37)).collect::<Vec<_>>();
+        let ocr_text = text::encode(&data)
+            .iter()
+            .map(|line| line.replacen('-', " — ", 2).to_uppercase())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            reconstruct_text_fallback(&ocr_text, |found| found == data).unwrap(),
+            data
+        );
+        assert!(matches!(
+            reconstruct_text_fallback(&ocr_text, |_| false),
+            Err(Error::TextFallbackDecode(_))
+        ));
+    }
+}
@@ -0,0 +1,143 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    wire::{prefixes::*, varuint_nom, FromWire, ToWire},
+    NamedPayload, NamedPayloads,
+};
+
+use unsigned_varint::encode as varuint_encode;
+use zeroize::Zeroizing;
+
+// Internal only -- users can't see NamedPayloads.
+#[doc(hidden)]
+impl ToWire for NamedPayloads {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut buffer = varuint_encode::usize_buffer();
+        let mut bytes = vec![];
+
+        // Encode number of payloads.
+        bytes.extend_from_slice(varuint_encode::usize(self.0.len(), &mut buffer));
+
+        // Encode each (name, data) pair with a length prefix.
+        for payload in &self.0 {
+            for field in [payload.name.as_bytes(), payload.data.as_slice()] {
+                bytes.extend_from_slice(varuint_encode::usize(field.len(), &mut buffer));
+                bytes.extend_from_slice(field);
+            }
+        }
+
+        // Only encode the modes if any payload has one, so that payloads
+        // without them are encoded identically to before. Each mode is stored
+        // plus one, with zero meaning the payload has no mode.
+        if self.0.iter().any(|payload| payload.mode.is_some()) {
+            let mut buffer = varuint_encode::u64_buffer();
+            bytes.extend_from_slice(varuint_encode::u64(PREFIX_PAYLOAD_MODES, &mut buffer));
+            for payload in &self.0 {
+                let mode = payload.mode.map_or(0, |mode| mode as u64 + 1);
+                bytes.extend_from_slice(varuint_encode::u64(mode, &mut buffer));
+            }
+        }
+
+        bytes
+    }
+}
+
+// Internal only -- users can't see NamedPayloads.
+#[doc(hidden)]
+impl FromWire for NamedPayloads {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use nom::{
+            combinator::{complete, map_res, opt, verify},
+            multi::{count, length_count, length_data},
+            sequence::{pair, preceded},
+            IResult,
+        };
+
+        fn parse(input: &[u8]) -> IResult<&[u8], Vec<(String, &[u8])>> {
+            length_count(
+                varuint_nom::usize,
+                pair(
+                    map_res(length_data(varuint_nom::usize), |name: &[u8]| {
+                        String::from_utf8(name.to_vec())
+                    }),
+                    length_data(varuint_nom::usize),
+                ),
+            )(input)
+        }
+        let mut parse = complete(parse);
+
+        fn parse_modes(num_payloads: usize) -> impl FnMut(&[u8]) -> IResult<&[u8], Vec<u64>> {
+            move |input| {
+                preceded(
+                    verify(varuint_nom::u64, |x| *x == PREFIX_PAYLOAD_MODES),
+                    count(
+                        verify(varuint_nom::u64, |mode| *mode <= u32::MAX as u64 + 1),
+                        num_payloads,
+                    ),
+                )(input)
+            }
+        }
+
+        let (input, payloads) = parse(input).map_err(|err| format!("{:?}", err))?;
+        let (input, modes) = opt(complete(parse_modes(payloads.len())))(input)
+            .map_err(|err| format!("payload modes: {:?}", err))?;
+        let modes = modes.unwrap_or_else(|| vec![0; payloads.len()]);
+        let payloads = NamedPayloads(
+            payloads
+                .into_iter()
+                .zip(modes)
+                .map(|((name, data), mode)| NamedPayload {
+                    name,
+                    data: Zeroizing::new(data.to_vec()),
+                    mode: mode.checked_sub(1).map(|mode| mode as u32),
+                })
+                .collect(),
+        );
+        payloads.validate().map_err(|err| err.to_string())?;
+
+        Ok((input, payloads))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[quickcheck]
+    fn named_payloads_roundtrip(payloads: NamedPayloads) -> bool {
+        let payloads2 = NamedPayloads::from_wire(payloads.to_wire()).unwrap();
+        payloads == payloads2
+    }
+
+    #[test]
+    fn named_payloads_modes() {
+        // Payloads without modes must encode the same way they always have.
+        let payloads = NamedPayloads(vec![NamedPayload::new("a", b"x").unwrap()]);
+        assert_eq!(payloads.to_wire(), vec![1, 1, b'a', 1, b'x']);
+
+        let payloads = NamedPayloads(vec![
+            NamedPayload::new("a", b"x").unwrap().with_mode(0o600),
+            NamedPayload::new("b", b"y").unwrap(),
+        ]);
+        let payloads2 = NamedPayloads::from_wire(payloads.to_wire()).unwrap();
+        assert_eq!(payloads2.0[0].mode(), Some(0o600));
+        assert_eq!(payloads2.0[1].mode(), None);
+        assert_eq!(payloads, payloads2);
+    }
+}
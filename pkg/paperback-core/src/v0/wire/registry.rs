@@ -0,0 +1,210 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    from_unix_timestamp,
+    registry::{RegistryEntry, RegistryHeader, SealedRegistryEntry},
+    to_unix_timestamp,
+    wire::{prefixes::*, varuint_nom, FromWire, ToWire},
+    ChaChaPolyNonce, ChallengeChecksum, DocumentId, ShardId, CHACHAPOLY_NONCE_LENGTH,
+    PAPERBACK_VERSION,
+};
+
+use unsigned_varint::encode as varuint_encode;
+
+impl ToWire for RegistryEntry {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut buffer = varuint_encode::usize_buffer();
+        let mut bytes = vec![];
+
+        // Encode creation time.
+        bytes.extend_from_slice(varuint_encode::u64(
+            to_unix_timestamp(self.created),
+            &mut varuint_encode::u64_buffer(),
+        ));
+
+        // Encode identifiers and label (length-prefixed, with an empty label
+        // meaning no label).
+        for field in [
+            self.document_id.as_str(),
+            self.shard_id.as_str(),
+            self.label.as_deref().unwrap_or_default(),
+        ] {
+            bytes.extend_from_slice(varuint_encode::usize(field.len(), &mut buffer));
+            bytes.extend_from_slice(field.as_bytes());
+        }
+
+        // Encode challenge checksum (if present).
+        if let Some(ChallengeChecksum(checksum)) = &self.challenge_checksum {
+            bytes.extend_from_slice(varuint_encode::u64(
+                PREFIX_CHALLENGE_CHECKSUM,
+                &mut varuint_encode::u64_buffer(),
+            ));
+            bytes.append(&mut checksum.to_bytes());
+        }
+
+        bytes
+    }
+}
+
+impl FromWire for RegistryEntry {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::{length_id, multihash};
+        use nom::{
+            combinator::{complete, map, map_res, opt, verify},
+            multi::length_data,
+            sequence::{preceded, tuple},
+            IResult,
+        };
+
+        fn string(input: &[u8]) -> IResult<&[u8], String> {
+            map_res(length_data(varuint_nom::usize), |field: &[u8]| {
+                String::from_utf8(field.to_vec())
+            })(input)
+        }
+
+        fn parse(input: &[u8]) -> IResult<&[u8], (u64, DocumentId, ShardId, String)> {
+            tuple((varuint_nom::u64, length_id, length_id, string))(input)
+        }
+        let mut parse = complete(parse);
+
+        fn parse_challenge_checksum(input: &[u8]) -> IResult<&[u8], Option<ChallengeChecksum>> {
+            opt(complete(preceded(
+                verify(varuint_nom::u64, |x| *x == PREFIX_CHALLENGE_CHECKSUM),
+                map(multihash, ChallengeChecksum),
+            )))(input)
+        }
+
+        let (input, (created, document_id, shard_id, label)) =
+            parse(input).map_err(|err| format!("{:?}", err))?;
+        let (input, challenge_checksum) = parse_challenge_checksum(input)
+            .map_err(|err| format!("challenge checksum: {:?}", err))?;
+        let created = from_unix_timestamp(created)
+            .ok_or_else(|| format!("creation time {} is out of range", created))?;
+
+        Ok((
+            input,
+            RegistryEntry {
+                document_id,
+                shard_id,
+                created,
+                label: Some(label).filter(|label| !label.is_empty()),
+                challenge_checksum,
+            },
+        ))
+    }
+}
+
+impl ToWire for SealedRegistryEntry {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut buffer = varuint_encode::u64_buffer();
+        let mut bytes = vec![];
+
+        // Encode ChaCha20-Poly1305 nonce.
+        varuint_encode::u64(PREFIX_CHACHA20POLY1305_NONCE, &mut buffer)
+            .iter()
+            .chain(&self.nonce)
+            .for_each(|b| bytes.push(*b));
+        assert_eq!(self.nonce.len(), CHACHAPOLY_NONCE_LENGTH);
+
+        // Encode ChaCha20-Poly1305 ciphertext (length-prefixed).
+        varuint_encode::u64(PREFIX_CHACHA20POLY1305_CIPHERTEXT, &mut buffer)
+            .iter()
+            .chain(varuint_encode::usize(
+                self.ciphertext.len(),
+                &mut varuint_encode::usize_buffer(),
+            ))
+            .chain(&self.ciphertext)
+            .for_each(|b| bytes.push(*b));
+
+        bytes
+    }
+}
+
+impl FromWire for SealedRegistryEntry {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::{take_chachapoly_ciphertext, take_chachapoly_nonce};
+        use nom::{combinator::complete, IResult};
+
+        fn parse(input: &[u8]) -> IResult<&[u8], (ChaChaPolyNonce, &[u8])> {
+            let (input, nonce) = take_chachapoly_nonce(input)?;
+            let (input, ciphertext) = take_chachapoly_ciphertext(input)?;
+
+            Ok((input, (nonce, ciphertext)))
+        }
+        let mut parse = complete(parse);
+
+        let (input, (nonce, ciphertext)) = parse(input).map_err(|err| format!("{:?}", err))?;
+
+        Ok((
+            input,
+            SealedRegistryEntry {
+                nonce,
+                ciphertext: ciphertext.into(),
+            },
+        ))
+    }
+}
+
+impl ToWire for RegistryHeader {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        // Encode version.
+        bytes.extend_from_slice(varuint_encode::u32(
+            self.version,
+            &mut varuint_encode::u32_buffer(),
+        ));
+
+        // Encode Argon2id parameters.
+        bytes.append(&mut self.kdf.to_wire());
+
+        // Encode passphrase check value.
+        bytes.append(&mut self.check.to_wire());
+
+        bytes
+    }
+}
+
+impl FromWire for RegistryHeader {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::take_argon2_params;
+        use nom::{combinator::complete, sequence::pair};
+
+        let (input, (version, kdf)) = complete(pair(varuint_nom::u32, take_argon2_params))(input)
+            .map_err(|err| format!("{:?}", err))?;
+        if version != PAPERBACK_VERSION {
+            return Err(format!(
+                "shard registry version must be '{}' not '{}'",
+                PAPERBACK_VERSION, version
+            ));
+        }
+        kdf.validate()?;
+
+        let (input, check) = SealedRegistryEntry::from_wire_partial(input)?;
+
+        Ok((
+            input,
+            RegistryHeader {
+                version,
+                kdf,
+                check,
+            },
+        ))
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::v0::{
+    wire::{varuint_nom, FromWire, ToWire},
+    CodewordLanguage, Error, SeedPhrase, CODEWORD_LANGUAGES,
+};
+
+use unsigned_varint::encode as varuint_encode;
+
+// Internal only -- the wire format of SeedPhrase is only used as the plaintext
+// of main documents.
+#[doc(hidden)]
+impl ToWire for SeedPhrase {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut buffer = varuint_encode::usize_buffer();
+        let mut bytes = vec![];
+
+        // Encode the language as its index in CODEWORD_LANGUAGES.
+        let language_idx = CODEWORD_LANGUAGES
+            .iter()
+            .position(|&language| language == self.language)
+            .expect("seed phrase language must be a supported codeword language");
+        bytes.extend_from_slice(varuint_encode::usize(language_idx, &mut buffer));
+
+        // Encode the entropy with a length prefix.
+        bytes.extend_from_slice(varuint_encode::usize(self.entropy.len(), &mut buffer));
+        bytes.extend_from_slice(&self.entropy);
+
+        bytes
+    }
+}
+
+// Internal only -- the wire format of SeedPhrase is only used as the plaintext
+// of main documents.
+#[doc(hidden)]
+impl FromWire for SeedPhrase {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use nom::{
+            combinator::{complete, map_opt},
+            multi::length_data,
+            sequence::pair,
+            IResult,
+        };
+
+        fn parse(input: &[u8]) -> IResult<&[u8], (CodewordLanguage, &[u8])> {
+            pair(
+                map_opt(varuint_nom::usize, |idx| {
+                    CODEWORD_LANGUAGES.get(idx).copied()
+                }),
+                length_data(varuint_nom::usize),
+            )(input)
+        }
+        let mut parse = complete(parse);
+
+        let (input, (language, entropy)) = parse(input).map_err(|err| format!("{:?}", err))?;
+        let seed_phrase =
+            SeedPhrase::from_entropy(entropy, language).map_err(|err: Error| err.to_string())?;
+
+        Ok((input, seed_phrase))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[quickcheck]
+    fn seed_phrase_roundtrip(seed_phrase: SeedPhrase) -> bool {
+        let seed_phrase2 = SeedPhrase::from_wire(seed_phrase.to_wire()).unwrap();
+        seed_phrase == seed_phrase2
+    }
+}
@@ -0,0 +1,155 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Serde support for the public paperback types (behind the `serde` feature),
+//! so that paperback data can be embedded in other configuration files.
+//!
+//! Wire types are serialised using their wire format, which is written as a
+//! base32z multibase string for human-readable formats (such as JSON) and as
+//! raw bytes otherwise (such as CBOR).
+
+#[cfg(feature = "pdf")]
+use crate::v0::pdf::qr::Part;
+use crate::v0::{
+    wire::{FromWire, ToWire},
+    ChecksumAlgorithm, EncryptedKeyShard, MainDocument,
+};
+
+use alloc::{format, string::String};
+use core::{fmt, marker::PhantomData};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+const SERDE_MULTIBASE: multibase::Base = multibase::Base::Base32Z;
+
+fn serialize_wire<T: ToWire, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&value.to_wire_multibase(SERDE_MULTIBASE))
+    } else {
+        serializer.serialize_bytes(&value.to_wire())
+    }
+}
+
+struct WireVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FromWire> de::Visitor<'de> for WireVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("paperback wire data (as a multibase string or bytes)")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        T::from_wire_multibase(value).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<T, E> {
+        T::from_wire(value).map_err(E::custom)
+    }
+}
+
+fn deserialize_wire<'de, T: FromWire, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let visitor = WireVisitor(PhantomData);
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_bytes(visitor)
+    }
+}
+
+macro_rules! impl_serde_wire {
+    ($($ty:ty),*) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serialize_wire(self, serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $ty {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    deserialize_wire(deserializer)
+                }
+            }
+        )*
+    };
+}
+
+impl_serde_wire!(MainDocument, EncryptedKeyShard);
+#[cfg(feature = "pdf")]
+impl_serde_wire!(Part);
+
+// Checksum algorithms are serialised using their names (see
+// ChecksumAlgorithm::name).
+impl Serialize for ChecksumAlgorithm {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for ChecksumAlgorithm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        ChecksumAlgorithm::from_name(&name)
+            .ok_or_else(|| de::Error::custom(format!("unsupported checksum algorithm '{}'", name)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v0::{DocumentId, ShardId};
+
+    #[quickcheck]
+    fn main_document_json_roundtrip(main_document: MainDocument) -> bool {
+        let json = serde_json::to_string(&main_document).unwrap();
+        json == format!("\"{}\"", main_document.to_wire_multibase(SERDE_MULTIBASE))
+            && serde_json::from_str::<MainDocument>(&json).unwrap() == main_document
+    }
+
+    #[quickcheck]
+    fn encrypted_key_shard_json_roundtrip(shard: EncryptedKeyShard) -> bool {
+        let json = serde_json::to_string(&shard).unwrap();
+        serde_json::from_str::<EncryptedKeyShard>(&json).unwrap() == shard
+    }
+
+    #[quickcheck]
+    fn checksum_algorithm_json_roundtrip(algorithm: ChecksumAlgorithm) -> bool {
+        let json = serde_json::to_string(&algorithm).unwrap();
+        json == format!("\"{}\"", algorithm.name())
+            && serde_json::from_str::<ChecksumAlgorithm>(&json).unwrap() == algorithm
+    }
+
+    #[quickcheck]
+    fn ids_json_roundtrip(document_id: DocumentId, shard_id: ShardId) -> bool {
+        let json = serde_json::to_string(&(&document_id, &shard_id)).unwrap();
+        json == format!("[\"{}\",\"{}\"]", document_id, shard_id)
+            && serde_json::from_str::<(DocumentId, ShardId)>(&json).unwrap()
+                == (document_id, shard_id)
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert!(serde_json::from_str::<MainDocument>("\"hnotadocument\"").is_err());
+        assert!(serde_json::from_str::<ChecksumAlgorithm>("\"md5\"").is_err());
+        assert!(serde_json::from_str::<ShardId>("\"not a shard id\"").is_err());
+        assert!(serde_json::from_str::<DocumentId>("\"NOTANID!\"").is_err());
+    }
+}
@@ -0,0 +1,238 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use crate::{
+    shamir::Shard,
+    v0::{
+        backup::{BackupSession, EncryptedBackupSession},
+        wire::{prefixes::*, varuint_nom, FromWire, ToWire},
+        ChaChaPolyNonce, KeyShard, MainDocument, CHACHAPOLY_NONCE_LENGTH, PAPERBACK_VERSION,
+    },
+};
+
+use ed25519_dalek::{SecretKey, SigningKey};
+use unsigned_varint::encode as varuint_encode;
+
+// Internal only -- users can't see BackupSession.
+#[doc(hidden)]
+impl ToWire for BackupSession {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        // Encode version.
+        varuint_encode::u32(self.version, &mut varuint_encode::u32_buffer())
+            .iter()
+            .for_each(|b| bytes.push(*b));
+
+        // Encode main document.
+        bytes.append(&mut self.main_document.to_wire());
+
+        // Encode ed25519 private key.
+        varuint_encode::u64(PREFIX_ED25519_SECRET, &mut varuint_encode::u64_buffer())
+            .iter()
+            .chain(&self.id_keypair.to_bytes()[..])
+            .for_each(|b| bytes.push(*b));
+
+        // Encode shards (length-prefixed).
+        varuint_encode::usize(self.shards.len(), &mut varuint_encode::usize_buffer())
+            .iter()
+            .for_each(|b| bytes.push(*b));
+        for shard in &self.shards {
+            bytes.append(&mut shard.to_wire());
+        }
+
+        // Encode committed key shards which have not been handed out yet
+        // (length-prefixed), only if there are any.
+        if !self.committed_shards.is_empty() {
+            varuint_encode::u64(PREFIX_COMMITTED_SHARDS, &mut varuint_encode::u64_buffer())
+                .iter()
+                .chain(varuint_encode::usize(
+                    self.committed_shards.len(),
+                    &mut varuint_encode::usize_buffer(),
+                ))
+                .for_each(|b| bytes.push(*b));
+            for shard in &self.committed_shards {
+                bytes.append(&mut shard.to_wire());
+            }
+        }
+
+        bytes
+    }
+}
+
+// Internal only -- users can't see BackupSession.
+#[doc(hidden)]
+impl FromWire for BackupSession {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::take_ed25519_sec;
+        use nom::{
+            combinator::{complete, opt, verify},
+            sequence::preceded,
+            IResult,
+        };
+
+        fn parse_version(input: &[u8]) -> IResult<&[u8], u32> {
+            varuint_nom::u32(input)
+        }
+        let (input, version) =
+            complete(parse_version)(input).map_err(|err| format!("{:?}", err))?;
+        if version != PAPERBACK_VERSION {
+            return Err(format!(
+                "backup session version must be '{}' not '{}'",
+                PAPERBACK_VERSION, version
+            ));
+        }
+
+        let (input, main_document) = MainDocument::from_wire_partial(input)?;
+
+        fn parse(input: &[u8]) -> IResult<&[u8], (Option<SecretKey>, usize)> {
+            let (input, private_key) = take_ed25519_sec(input)?;
+            let (input, num_shards) = varuint_nom::usize(input)?;
+
+            Ok((input, (private_key, num_shards)))
+        }
+        let mut parse = complete(parse);
+
+        let (mut input, (private_key, num_shards)) =
+            parse(input).map_err(|err| format!("{:?}", err))?;
+        let id_keypair = private_key
+            .as_ref()
+            .map(SigningKey::from_bytes)
+            .ok_or_else(|| "backup session must contain a private key".to_string())?;
+
+        let mut shards = Vec::with_capacity(num_shards);
+        for _ in 0..num_shards {
+            let (remaining, shard) = Shard::from_wire_partial(input)?;
+            shards.push(shard);
+            input = remaining;
+        }
+
+        fn parse_num_committed(input: &[u8]) -> IResult<&[u8], Option<usize>> {
+            opt(complete(preceded(
+                verify(varuint_nom::u64, |x| *x == PREFIX_COMMITTED_SHARDS),
+                varuint_nom::usize,
+            )))(input)
+        }
+        let (remaining, num_committed) =
+            parse_num_committed(input).map_err(|err| format!("{:?}", err))?;
+        input = remaining;
+
+        let mut committed_shards = vec![];
+        for _ in 0..num_committed.unwrap_or(0) {
+            let (remaining, shard) = KeyShard::from_wire_partial(input)?;
+            committed_shards.push(shard);
+            input = remaining;
+        }
+
+        Ok((
+            input,
+            BackupSession {
+                version,
+                main_document,
+                id_keypair,
+                shards,
+                committed_shards,
+            },
+        ))
+    }
+}
+
+impl ToWire for EncryptedBackupSession {
+    fn to_wire(&self) -> Vec<u8> {
+        let mut buffer = varuint_encode::u64_buffer();
+        let mut bytes = vec![];
+
+        // Encode ChaCha20-Poly1305 nonce.
+        varuint_encode::u64(PREFIX_CHACHA20POLY1305_NONCE, &mut buffer)
+            .iter()
+            .chain(&self.nonce)
+            .for_each(|b| bytes.push(*b));
+        assert_eq!(self.nonce.len(), CHACHAPOLY_NONCE_LENGTH);
+
+        // Encode ChaCha20-Poly1305 ciphertext (length-prefixed).
+        varuint_encode::u64(PREFIX_CHACHA20POLY1305_CIPHERTEXT, &mut buffer)
+            .iter()
+            .chain(varuint_encode::usize(
+                self.ciphertext.len(),
+                &mut varuint_encode::usize_buffer(),
+            ))
+            .chain(&self.ciphertext)
+            .for_each(|b| bytes.push(*b));
+
+        bytes
+    }
+}
+
+impl FromWire for EncryptedBackupSession {
+    fn from_wire_partial(input: &[u8]) -> Result<(&[u8], Self), String> {
+        use crate::v0::wire::helpers::{take_chachapoly_ciphertext, take_chachapoly_nonce};
+        use nom::{combinator::complete, IResult};
+
+        fn parse(input: &[u8]) -> IResult<&[u8], (ChaChaPolyNonce, &[u8])> {
+            let (input, nonce) = take_chachapoly_nonce(input)?;
+            let (input, ciphertext) = take_chachapoly_ciphertext(input)?;
+
+            Ok((input, (nonce, ciphertext)))
+        }
+        let mut parse = complete(parse);
+
+        let (input, (nonce, ciphertext)) = parse(input).map_err(|err| format!("{:?}", err))?;
+
+        Ok((
+            input,
+            EncryptedBackupSession {
+                nonce,
+                ciphertext: ciphertext.into(),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::rngs::OsRng;
+
+    #[quickcheck]
+    fn backup_session_roundtrip(
+        main_document: MainDocument,
+        shards: Vec<Shard>,
+        committed_shards: Vec<KeyShard>,
+    ) -> bool {
+        let session = BackupSession {
+            version: PAPERBACK_VERSION,
+            main_document,
+            id_keypair: SigningKey::generate(&mut OsRng),
+            shards,
+            committed_shards,
+        };
+        let session2 = BackupSession::from_wire(session.to_wire()).unwrap();
+
+        session.main_document == session2.main_document
+            && session.id_keypair.to_bytes() == session2.id_keypair.to_bytes()
+            && session.shards == session2.shards
+            && session.committed_shards == session2.committed_shards
+    }
+
+    #[quickcheck]
+    fn encrypted_backup_session_roundtrip(session: EncryptedBackupSession) -> bool {
+        let session2 = EncryptedBackupSession::from_wire(session.to_wire()).unwrap();
+        session == session2
+    }
+}
@@ -0,0 +1,392 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Error-correcting text encoding for the "text fallback" printed next to QR
+//! codes.
+//!
+//! The plain zbase32 text fallback has no redundancy, so a single misread
+//! character makes the data unusable. With this encoding the data is split
+//! into lines of [`DATA_BYTES_PER_LINE`] bytes, each of which has
+//! [`PARITY_BYTES_PER_LINE`] Reed-Solomon parity bytes appended (allowing up
+//! to two corrupted bytes -- or one misread character -- to be corrected per
+//! line). Each line is then zbase32-encoded and has a checksum character
+//! appended, which also covers the line number so that missing or reordered
+//! lines are detected.
+//!
+//! The first line is prefixed with [`ECC_PREFIX`], which is not a multibase
+//! code, so this encoding can be auto-detected.
+
+/// Prefix marking text encoded with this encoding.
+pub(crate) const ECC_PREFIX: char = '#';
+
+/// Number of data bytes stored in each line.
+pub(crate) const DATA_BYTES_PER_LINE: usize = 16;
+
+/// Number of Reed-Solomon parity bytes stored in each line.
+pub(crate) const PARITY_BYTES_PER_LINE: usize = 4;
+
+pub(super) const ZBASE32_ALPHABET: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+fn zbase32_encode(data: &[u8]) -> String {
+    let mut output = String::with_capacity((data.len() * 8).div_ceil(5));
+    let (mut buffer, mut bits) = (0u16, 0);
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(ZBASE32_ALPHABET[(buffer >> bits) as usize & 0x1F] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(ZBASE32_ALPHABET[(buffer << (5 - bits)) as usize & 0x1F] as char);
+    }
+    output
+}
+
+/// Look up the value of a zbase32 character, accepting characters that are
+/// commonly confused with it when transcribing or OCRing the text.
+pub(super) fn zbase32_value(ch: char) -> Option<u8> {
+    let ch = match ch.to_ascii_lowercase() {
+        '0' => 'o',
+        'l' => '1',
+        'v' => 'u',
+        '2' => 'z',
+        ch => ch,
+    };
+    ZBASE32_ALPHABET
+        .iter()
+        .position(|&v| v as char == ch)
+        .map(|v| v as u8)
+}
+
+/// Decode zbase32 text. Invalid characters are decoded as zero, and are left
+/// for the Reed-Solomon code to correct.
+fn zbase32_decode(input: &[char]) -> Option<Vec<u8>> {
+    let len = input.len() * 5 / 8;
+    // Make sure the input is a length we could have produced.
+    if (len * 8).div_ceil(5) != input.len() {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(len);
+    let (mut buffer, mut bits) = (0u16, 0);
+    for &ch in input {
+        buffer = (buffer << 5) | zbase32_value(ch).unwrap_or(0) as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+    output.truncate(len);
+    Some(output)
+}
+
+/// CRC-8 (polynomial 0x07) of the line number and line contents, truncated
+/// to a single zbase32 character.
+fn line_checksum(idx: usize, block: &[u8]) -> char {
+    let crc = (idx as u32)
+        .to_be_bytes()
+        .iter()
+        .chain(block)
+        .fold(0u8, |crc, &byte| {
+            (0..8).fold(crc ^ byte, |crc, _| match crc & 0x80 {
+                0 => crc << 1,
+                _ => (crc << 1) ^ 0x07,
+            })
+        });
+    ZBASE32_ALPHABET[(crc & 0x1F) as usize] as char
+}
+
+/// Encode data as lines of error-correcting text.
+pub(crate) fn encode<B: AsRef<[u8]>>(data: B) -> Vec<String> {
+    let data = data.as_ref();
+    // Always emit at least one line, so that empty data round-trips.
+    let chunks = match data.is_empty() {
+        true => vec![data],
+        false => data.chunks(DATA_BYTES_PER_LINE).collect(),
+    };
+
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let mut block = chunk.to_vec();
+            block.extend(reed_solomon::parity(chunk));
+
+            let mut line = match idx {
+                0 => ECC_PREFIX.to_string(),
+                _ => String::new(),
+            };
+            // Split the line into 4-char words joined with "-" (to match the
+            // layout of the plain text fallback).
+            let words = zbase32_encode(&block)
+                .into_bytes()
+                .chunks(4)
+                .map(|word| String::from_utf8_lossy(word).into_owned())
+                .collect::<Vec<_>>();
+            line.push_str(&words.join("-"));
+            line.push('-');
+            line.push(line_checksum(idx, &block));
+            line
+        })
+        .collect()
+}
+
+/// Decode text produced by [`encode`], correcting any errors in each line.
+pub(crate) fn decode<S: AsRef<str>>(input: S) -> Result<Vec<u8>, String> {
+    let input = input
+        .as_ref()
+        .trim_start()
+        .strip_prefix(ECC_PREFIX)
+        .ok_or_else(|| format!("error-correcting text must start with '{}'", ECC_PREFIX))?;
+
+    let lines = input
+        .lines()
+        .map(|line| {
+            line.chars()
+                .filter(|ch| !ch.is_whitespace() && *ch != '-')
+                .collect::<Vec<_>>()
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return Err("error-correcting text contains no data".into());
+    }
+
+    let mut output = vec![];
+    for (idx, line) in lines.iter().enumerate() {
+        let lineno = idx + 1;
+        let (checksum, line) = line.split_last().expect("empty lines are filtered");
+
+        let mut block = zbase32_decode(line)
+            .filter(|block| block.len() >= PARITY_BYTES_PER_LINE)
+            .ok_or_else(|| format!("line {} has the wrong number of characters", lineno))?;
+        let data_len = block.len() - PARITY_BYTES_PER_LINE;
+        if data_len > DATA_BYTES_PER_LINE
+            || (idx + 1 < lines.len() && data_len != DATA_BYTES_PER_LINE)
+        {
+            return Err(format!(
+                "line {} has the wrong number of characters",
+                lineno
+            ));
+        }
+
+        reed_solomon::correct(&mut block)
+            .ok_or_else(|| format!("line {} has too many errors to be corrected", lineno))?;
+        if zbase32_value(*checksum) != zbase32_value(line_checksum(idx, &block)) {
+            return Err(format!(
+                "line {} failed its checksum (are the lines out of order?)",
+                lineno
+            ));
+        }
+        output.extend_from_slice(&block[..data_len]);
+    }
+    Ok(output)
+}
+
+/// Reed-Solomon code over GF(2^8), with [`PARITY_BYTES_PER_LINE`] parity
+/// bytes.
+///
+/// Blocks are tiny (at most 20 bytes) and we only correct up to two errors,
+/// so rather than implementing Berlekamp-Massey we just solve the syndrome
+/// equations for every possible error location.
+mod reed_solomon {
+    use super::PARITY_BYTES_PER_LINE;
+
+    // Generator polynomial for GF(2^8), with 2 as the primitive element.
+    const PRIMITIVE_POLY: u16 = 0x11D;
+
+    const fn tables() -> ([u8; 512], [u8; 256]) {
+        let (mut exp, mut log) = ([0u8; 512], [0u8; 256]);
+        let (mut x, mut i) = (1u16, 0);
+        while i < 255 {
+            exp[i] = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= PRIMITIVE_POLY;
+            }
+            i += 1;
+        }
+        // Duplicate the table so that mul() doesn't need to reduce mod 255.
+        while i < 512 {
+            exp[i] = exp[i - 255];
+            i += 1;
+        }
+        (exp, log)
+    }
+
+    static EXP: [u8; 512] = tables().0;
+    static LOG: [u8; 256] = tables().1;
+
+    fn mul(a: u8, b: u8) -> u8 {
+        match (a, b) {
+            (0, _) | (_, 0) => 0,
+            (a, b) => EXP[LOG[a as usize] as usize + LOG[b as usize] as usize],
+        }
+    }
+
+    fn div(a: u8, b: u8) -> u8 {
+        assert!(b != 0, "division by zero in GF(2^8)");
+        match a {
+            0 => 0,
+            a => EXP[LOG[a as usize] as usize + 255 - LOG[b as usize] as usize],
+        }
+    }
+
+    /// Returns the primitive element raised to the given power.
+    fn alpha_pow(n: usize) -> u8 {
+        EXP[n % 255]
+    }
+
+    /// Evaluate a polynomial (with the highest-degree coefficient first).
+    fn poly_eval(poly: &[u8], x: u8) -> u8 {
+        poly.iter().fold(0, |y, &coeff| mul(y, x) ^ coeff)
+    }
+
+    /// Generator polynomial, with roots alpha^0 .. alpha^(PARITY-1).
+    fn generator() -> Vec<u8> {
+        (0..PARITY_BYTES_PER_LINE).fold(vec![1], |gen, i| {
+            // gen * (x - alpha^i)
+            let mut next = gen.clone();
+            next.push(0);
+            for (j, &coeff) in gen.iter().enumerate() {
+                next[j + 1] ^= mul(coeff, alpha_pow(i));
+            }
+            next
+        })
+    }
+
+    /// Compute the parity bytes for the given data.
+    pub(super) fn parity(data: &[u8]) -> Vec<u8> {
+        let gen = generator();
+        let mut buffer = data.to_vec();
+        buffer.resize(data.len() + PARITY_BYTES_PER_LINE, 0);
+        // Polynomial long division by the (monic) generator.
+        for i in 0..data.len() {
+            let coeff = buffer[i];
+            if coeff != 0 {
+                for (j, &g) in gen.iter().enumerate().skip(1) {
+                    buffer[i + j] ^= mul(g, coeff);
+                }
+            }
+        }
+        buffer.split_off(data.len())
+    }
+
+    /// Correct up to two corrupted bytes in a block (data followed by parity
+    /// bytes). Returns the number of corrected bytes, or `None` if the block
+    /// could not be corrected.
+    pub(super) fn correct(block: &mut [u8]) -> Option<usize> {
+        let syndromes = (0..PARITY_BYTES_PER_LINE)
+            .map(|i| poly_eval(block, alpha_pow(i)))
+            .collect::<Vec<_>>();
+        if syndromes.iter().all(|&s| s == 0) {
+            return Some(0);
+        }
+
+        // The error locator for the byte at index idx.
+        let n = block.len();
+        let locator = |idx: usize| alpha_pow(n - 1 - idx);
+        // Check whether the given errors (locator, magnitude) produce exactly
+        // the syndromes we computed.
+        let matches = |errors: &[(u8, u8)]| {
+            syndromes.iter().enumerate().all(|(i, &s)| {
+                errors.iter().fold(0, |acc, &(x, e)| {
+                    acc ^ mul(e, (0..i).fold(1, |xi, _| mul(xi, x)))
+                }) == s
+            })
+        };
+
+        // Single error: S_i = e * X^i, so e = S_0.
+        for (idx, byte) in block.iter_mut().enumerate() {
+            let (x, e) = (locator(idx), syndromes[0]);
+            if e != 0 && matches(&[(x, e)]) {
+                *byte ^= e;
+                return Some(1);
+            }
+        }
+
+        // Two errors: solve S_0 = e1 + e2 and S_1 = e1*X1 + e2*X2.
+        for idx1 in 0..n {
+            for idx2 in (idx1 + 1)..n {
+                let (x1, x2) = (locator(idx1), locator(idx2));
+                let e1 = div(syndromes[1] ^ mul(syndromes[0], x2), x1 ^ x2);
+                let e2 = syndromes[0] ^ e1;
+                if e1 != 0 && e2 != 0 && matches(&[(x1, e1), (x2, e2)]) {
+                    block[idx1] ^= e1;
+                    block[idx2] ^= e2;
+                    return Some(2);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use quickcheck::TestResult;
+
+    #[quickcheck]
+    fn ecc_text_roundtrip(data: Vec<u8>) -> bool {
+        decode(encode(&data).join("\n")).unwrap() == data
+    }
+
+    #[quickcheck]
+    fn ecc_text_correct_one_char_per_line(data: Vec<u8>, positions: Vec<usize>) -> TestResult {
+        if positions.is_empty() {
+            return TestResult::discard();
+        }
+        // Replace one data character in every line with a different one.
+        let lines = encode(&data)
+            .into_iter()
+            .zip(positions.iter().cycle())
+            .map(|(line, pos)| {
+                let mut chars = line.chars().collect::<Vec<_>>();
+                let candidates = chars
+                    .iter()
+                    .enumerate()
+                    .take(chars.len() - 1) // Don't touch the checksum.
+                    .filter(|(_, ch)| ZBASE32_ALPHABET.contains(&(**ch as u8)))
+                    .map(|(idx, _)| idx)
+                    .collect::<Vec<_>>();
+                let idx = candidates[pos % candidates.len()];
+                chars[idx] = match chars[idx] {
+                    'y' => 'b',
+                    _ => 'y',
+                };
+                chars.into_iter().collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        TestResult::from_bool(decode(lines.join("\n")).unwrap() == data)
+    }
+
+    #[test]
+    fn ecc_text_reordered_lines() {
+        let data = (0..64).collect::<Vec<u8>>();
+        let mut lines = encode(&data);
+        lines.swap(1, 2);
+        assert!(decode(lines.join("\n")).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `nom` combinators to decode unsigned varints.
+//!
+//! unsigned-varint has its own `nom` feature, but it unconditionally enables
+//! the `std` feature of `nom` (which would stop us from being built without
+//! `std`).
+
+use nom::{
+    error::{ErrorKind, ParseError},
+    Err as NomErr, IResult, Needed,
+};
+use unsigned_varint::decode::{self, Error};
+
+macro_rules! gen {
+    ($($type:ident, $d:expr);*) => {
+        $(
+            #[doc = " `nom` combinator to decode a variable-length encoded "]
+            #[doc = $d]
+            #[doc = "."]
+            pub(crate) fn $type<'a, E: ParseError<&'a [u8]>>(
+                input: &'a [u8],
+            ) -> IResult<&'a [u8], $type, E> {
+                let (n, remain) = decode::$type(input).map_err(|err| match err {
+                    Error::Insufficient => NomErr::Incomplete(Needed::Unknown),
+                    Error::Overflow => NomErr::Error(E::from_error_kind(input, ErrorKind::TooLarge)),
+                    Error::NotMinimal => NomErr::Error(E::from_error_kind(input, ErrorKind::Verify)),
+                    _ => NomErr::Error(E::from_error_kind(input, ErrorKind::Fail)),
+                })?;
+                Ok((remain, n))
+            }
+        )*
+    }
+}
+
+gen! {
+    u32, "`u32`";
+    u64, "`u64`";
+    usize, "`usize`"
+}
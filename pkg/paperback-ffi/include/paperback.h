@@ -0,0 +1,191 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+
+#ifndef PAPERBACK_H
+#define PAPERBACK_H
+
+/* This file is generated by cbindgen from pkg/paperback-ffi. Do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of a fallible `paperback_*` function.
+typedef enum PaperbackResult {
+  // The operation succeeded.
+  PAPERBACK_RESULT_OK = 0,
+  // An argument was NULL, not valid UTF-8, or otherwise unusable.
+  PAPERBACK_RESULT_INVALID_ARGUMENT = 1,
+  // A document or key shard could not be decoded.
+  PAPERBACK_RESULT_PARSE_ERROR = 2,
+  // The codewords did not decrypt the key shard.
+  PAPERBACK_RESULT_WRONG_CODEWORDS = 3,
+  // The documents in the quorum are not consistent with each other.
+  PAPERBACK_RESULT_INCONSISTENT_QUORUM = 4,
+  // Any other error from `paperback-core`.
+  PAPERBACK_RESULT_FAILURE = 5,
+  // `paperback-ffi` panicked. This is always a bug.
+  PAPERBACK_RESULT_PANIC = 6,
+} PaperbackResult;
+
+// Opaque handle to a backup being created.
+typedef struct PaperbackBackup PaperbackBackup;
+
+// Opaque handle to a decrypted key shard.
+typedef struct PaperbackKeyShard PaperbackKeyShard;
+
+// Opaque handle to a set of documents being collected for recovery.
+typedef struct PaperbackQuorum PaperbackQuorum;
+
+// Buffer of bytes allocated by `paperback-ffi`. Must be freed with
+// `paperback_buffer_free()`.
+typedef struct PaperbackBuffer {
+  uint8_t *data;
+  uintptr_t len;
+} PaperbackBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a description of the most recent error returned on this thread, or
+// NULL if no error has occurred. The string is owned by `paperback-ffi` and
+// is only valid until the next failing `paperback_*` call on this thread.
+const char *paperback_last_error(void);
+
+// Free a string returned by `paperback-ffi`. The contents are wiped before
+// the memory is released. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `string` must be NULL or a string returned by `paperback-ffi` that has not
+// already been freed.
+void paperback_string_free(char *string);
+
+// Free a buffer returned by `paperback-ffi`. The contents are wiped before
+// the memory is released.
+//
+// # Safety
+//
+// `buffer` must be NULL or point to a buffer filled by `paperback-ffi` that
+// has not already been freed.
+void paperback_buffer_free(struct PaperbackBuffer *buffer);
+
+// Create a new backup of `secret` which requires `quorum_size` key shards to
+// recover. If `sealed` is set, no more key shards can be created after the
+// backup is freed.
+//
+// # Safety
+//
+// `secret` must point to `secret_len` readable bytes (it may be NULL if
+// `secret_len` is 0), and `out` must be a valid pointer.
+enum PaperbackResult paperback_backup_new(uint32_t quorum_size,
+                                          const uint8_t *secret,
+                                          uintptr_t secret_len,
+                                          bool sealed,
+                                          struct PaperbackBackup **out);
+
+// Free a backup. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `backup` must be NULL or a backup returned by `paperback_backup_new()` that
+// has not already been freed.
+void paperback_backup_free(struct PaperbackBackup *backup);
+
+// Get the main document of a backup as a z-base32 string. The string must be
+// freed with `paperback_string_free()`.
+//
+// # Safety
+//
+// `backup` must be a valid backup and `out` must be a valid pointer.
+enum PaperbackResult paperback_backup_main_document(const struct PaperbackBackup *backup,
+                                                    char **out);
+
+// Create a new key shard for a backup. The key shard must be freed with
+// `paperback_key_shard_free()`.
+//
+// # Safety
+//
+// `backup` must be a valid backup and `out` must be a valid pointer.
+enum PaperbackResult paperback_backup_next_shard(const struct PaperbackBackup *backup,
+                                                 struct PaperbackKeyShard **out);
+
+// Free a key shard. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `shard` must be NULL or a key shard returned by `paperback-ffi` that has
+// not already been freed.
+void paperback_key_shard_free(struct PaperbackKeyShard *shard);
+
+// Encrypt a key shard. The encrypted key shard (a z-base32 string) and its
+// codewords (whitespace-separated words) are both returned as strings that
+// must be freed with `paperback_string_free()`.
+//
+// # Safety
+//
+// `shard` must be a valid key shard, and `out_shard` and `out_codewords`
+// must be valid pointers.
+enum PaperbackResult paperback_key_shard_encrypt(const struct PaperbackKeyShard *shard,
+                                                 char **out_shard,
+                                                 char **out_codewords);
+
+// Decrypt an encrypted key shard (a z-base32 string) using its codewords
+// (whitespace-separated words). The codewords are normalised in the same way
+// as the `paperback` CLI. The key shard must be freed with
+// `paperback_key_shard_free()`.
+//
+// # Safety
+//
+// `encrypted` and `codewords` must be valid NUL-terminated strings, and
+// `out` must be a valid pointer.
+enum PaperbackResult paperback_key_shard_decrypt(const char *encrypted,
+                                                 const char *codewords,
+                                                 struct PaperbackKeyShard **out);
+
+// Create a new, empty quorum. The quorum must be freed with
+// `paperback_quorum_free()`.
+struct PaperbackQuorum *paperback_quorum_new(void);
+
+// Free a quorum. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `quorum` must be NULL or a quorum returned by `paperback_quorum_new()`
+// that has not already been freed.
+void paperback_quorum_free(struct PaperbackQuorum *quorum);
+
+// Add the main document (a z-base32 string) to a quorum.
+//
+// # Safety
+//
+// `quorum` must be a valid quorum and `main_document` must be a valid
+// NUL-terminated string.
+enum PaperbackResult paperback_quorum_main_document(struct PaperbackQuorum *quorum,
+                                                    const char *main_document);
+
+// Add a decrypted key shard to a quorum. The key shard is copied, and must
+// still be freed by the caller.
+//
+// # Safety
+//
+// `quorum` must be a valid quorum and `shard` must be a valid key shard.
+enum PaperbackResult paperback_quorum_push_shard(struct PaperbackQuorum *quorum,
+                                                 const struct PaperbackKeyShard *shard);
+
+// Validate a quorum and recover the secret stored in its main document. The
+// secret must be freed with `paperback_buffer_free()`. The quorum is left
+// unmodified, so more documents can be added if recovery fails.
+//
+// # Safety
+//
+// `quorum` must be a valid quorum and `out` must be a valid pointer.
+enum PaperbackResult paperback_quorum_recover(const struct PaperbackQuorum *quorum,
+                                              struct PaperbackBuffer *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PAPERBACK_H */
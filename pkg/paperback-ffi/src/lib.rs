@@ -0,0 +1,638 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! C API for `paperback-core`.
+//!
+//! This crate exposes the subset of `paperback-core` needed to create and
+//! recover paperback backups from other languages (such as mobile scanning
+//! apps), so that they can reuse the exact same wire format and cryptographic
+//! implementation as the `paperback` CLI. The C header is generated by
+//! `cbindgen` into `include/paperback.h` when the crate is built.
+//!
+//! All documents, key shards and codewords are passed across the API as
+//! NUL-terminated z-base32 strings (the same representation used in the QR
+//! codes and text fallbacks of the printed documents). Codewords are a single
+//! string of whitespace-separated words.
+//!
+//! Every fallible function returns a `PaperbackResult`. If the result is not
+//! `PAPERBACK_RESULT_OK`, a description of the error can be retrieved with
+//! `paperback_last_error()`. Objects and strings returned by this API must be
+//! freed with the corresponding `paperback_*_free()` function.
+
+use paperback_core::latest::{
+    codewords, Backup, EncryptedKeyShard, Error, FromWire, InconsistentQuorumError, KeyShard,
+    MainDocument, ToWire, UntrustedQuorum,
+};
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use zeroize::Zeroize;
+
+const WIRE_MULTIBASE: multibase::Base = multibase::Base::Base32Z;
+
+/// Result of a fallible `paperback_*` function.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaperbackResult {
+    /// The operation succeeded.
+    Ok = 0,
+    /// An argument was NULL, not valid UTF-8, or otherwise unusable.
+    InvalidArgument = 1,
+    /// A document or key shard could not be decoded.
+    ParseError = 2,
+    /// The codewords did not decrypt the key shard.
+    WrongCodewords = 3,
+    /// The documents in the quorum are not consistent with each other.
+    InconsistentQuorum = 4,
+    /// Any other error from `paperback-core`.
+    Failure = 5,
+    /// `paperback-ffi` panicked. This is always a bug.
+    Panic = 6,
+}
+
+/// Opaque handle to a backup being created.
+pub struct PaperbackBackup(Backup);
+
+/// Opaque handle to a decrypted key shard.
+pub struct PaperbackKeyShard(KeyShard);
+
+/// Opaque handle to a set of documents being collected for recovery.
+pub struct PaperbackQuorum(UntrustedQuorum);
+
+/// Buffer of bytes allocated by `paperback-ffi`. Must be freed with
+/// `paperback_buffer_free()`.
+#[repr(C)]
+pub struct PaperbackBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+struct FfiError {
+    result: PaperbackResult,
+    message: String,
+}
+
+impl FfiError {
+    fn invalid_argument<S: Into<String>>(message: S) -> Self {
+        Self {
+            result: PaperbackResult::InvalidArgument,
+            message: message.into(),
+        }
+    }
+
+    fn parse<S: Into<String>>(message: S) -> Self {
+        Self {
+            result: PaperbackResult::ParseError,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for FfiError {
+    fn from(err: Error) -> Self {
+        let result = match err {
+            Error::InvalidCodewords(_) | Error::UnknownCodeword { .. } | Error::WrongCodewords => {
+                PaperbackResult::WrongCodewords
+            }
+            Error::ShardIdDecode(_)
+            | Error::DocumentIdDecode(_)
+            | Error::MultibaseDecode(_)
+            | Error::ArmorDecode(_)
+            | Error::MalformedKeyShard(_)
+            | Error::PayloadsDecode(_)
+            | Error::SeedPhraseDecode(_) => PaperbackResult::ParseError,
+            Error::QuorumTooSmall(_)
+            | Error::QuorumTooLarge { .. }
+            | Error::SecretTooLarge { .. } => PaperbackResult::InvalidArgument,
+            _ => PaperbackResult::Failure,
+        };
+        Self {
+            result,
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<InconsistentQuorumError> for FfiError {
+    fn from(err: InconsistentQuorumError) -> Self {
+        Self {
+            result: PaperbackResult::InconsistentQuorum,
+            message: err.to_string(),
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: &str) {
+    // CString::new only fails on interior NUL bytes, which we strip.
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run an FFI operation, converting errors (and panics, which must not unwind
+/// across the FFI boundary) into a `PaperbackResult`.
+fn ffi_call<F: FnOnce() -> Result<(), FfiError>>(func: F) -> PaperbackResult {
+    match panic::catch_unwind(AssertUnwindSafe(func)) {
+        Ok(Ok(())) => PaperbackResult::Ok,
+        Ok(Err(err)) => {
+            set_last_error(&err.message);
+            err.result
+        }
+        Err(_) => {
+            set_last_error("internal error: paperback-ffi panicked");
+            PaperbackResult::Panic
+        }
+    }
+}
+
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if ptr.is_null() {
+        return Err(FfiError::invalid_argument(format!(
+            "{name} must not be NULL"
+        )));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|err| FfiError::invalid_argument(format!("{name} is not valid UTF-8: {err}")))
+}
+
+unsafe fn ref_arg<'a, T>(ptr: *const T, name: &str) -> Result<&'a T, FfiError> {
+    ptr.as_ref()
+        .ok_or_else(|| FfiError::invalid_argument(format!("{name} must not be NULL")))
+}
+
+unsafe fn mut_arg<'a, T>(ptr: *mut T, name: &str) -> Result<&'a mut T, FfiError> {
+    ptr.as_mut()
+        .ok_or_else(|| FfiError::invalid_argument(format!("{name} must not be NULL")))
+}
+
+fn check_out<T>(out: *mut T, name: &str) -> Result<(), FfiError> {
+    if out.is_null() {
+        Err(FfiError::invalid_argument(format!(
+            "{name} must not be NULL"
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+fn into_c_string(value: String) -> Result<*mut c_char, FfiError> {
+    CString::new(value)
+        .map(CString::into_raw)
+        .map_err(|_| FfiError::invalid_argument("string contains a NUL byte"))
+}
+
+/// Returns a description of the most recent error returned on this thread, or
+/// NULL if no error has occurred. The string is owned by `paperback-ffi` and
+/// is only valid until the next failing `paperback_*` call on this thread.
+#[no_mangle]
+pub extern "C" fn paperback_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Free a string returned by `paperback-ffi`. The contents are wiped before
+/// the memory is released. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `string` must be NULL or a string returned by `paperback-ffi` that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        CString::from_raw(string).into_bytes().zeroize();
+    }
+}
+
+/// Free a buffer returned by `paperback-ffi`. The contents are wiped before
+/// the memory is released.
+///
+/// # Safety
+///
+/// `buffer` must be NULL or point to a buffer filled by `paperback-ffi` that
+/// has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_buffer_free(buffer: *mut PaperbackBuffer) {
+    if let Some(buffer) = buffer.as_mut() {
+        if !buffer.data.is_null() {
+            let data = slice::from_raw_parts_mut(buffer.data, buffer.len);
+            Box::from_raw(data).zeroize();
+        }
+        buffer.data = ptr::null_mut();
+        buffer.len = 0;
+    }
+}
+
+/// Create a new backup of `secret` which requires `quorum_size` key shards to
+/// recover. If `sealed` is set, no more key shards can be created after the
+/// backup is freed.
+///
+/// # Safety
+///
+/// `secret` must point to `secret_len` readable bytes (it may be NULL if
+/// `secret_len` is 0), and `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_backup_new(
+    quorum_size: u32,
+    secret: *const u8,
+    secret_len: usize,
+    sealed: bool,
+    out: *mut *mut PaperbackBackup,
+) -> PaperbackResult {
+    ffi_call(|| {
+        check_out(out, "out")?;
+        let secret = match (secret.is_null(), secret_len) {
+            (true, 0) => &[][..],
+            (true, _) => return Err(FfiError::invalid_argument("secret must not be NULL")),
+            (false, _) => slice::from_raw_parts(secret, secret_len),
+        };
+        let backup = if sealed {
+            Backup::new_sealed(quorum_size, secret)?
+        } else {
+            Backup::new(quorum_size, secret)?
+        };
+        *out = Box::into_raw(Box::new(PaperbackBackup(backup)));
+        Ok(())
+    })
+}
+
+/// Free a backup. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `backup` must be NULL or a backup returned by `paperback_backup_new()` that
+/// has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_backup_free(backup: *mut PaperbackBackup) {
+    if !backup.is_null() {
+        drop(Box::from_raw(backup));
+    }
+}
+
+/// Get the main document of a backup as a z-base32 string. The string must be
+/// freed with `paperback_string_free()`.
+///
+/// # Safety
+///
+/// `backup` must be a valid backup and `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_backup_main_document(
+    backup: *const PaperbackBackup,
+    out: *mut *mut c_char,
+) -> PaperbackResult {
+    ffi_call(|| {
+        let backup = ref_arg(backup, "backup")?;
+        check_out(out, "out")?;
+        *out = into_c_string(backup.0.main_document().to_wire_multibase(WIRE_MULTIBASE))?;
+        Ok(())
+    })
+}
+
+/// Create a new key shard for a backup. The key shard must be freed with
+/// `paperback_key_shard_free()`.
+///
+/// # Safety
+///
+/// `backup` must be a valid backup and `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_backup_next_shard(
+    backup: *const PaperbackBackup,
+    out: *mut *mut PaperbackKeyShard,
+) -> PaperbackResult {
+    ffi_call(|| {
+        let backup = ref_arg(backup, "backup")?;
+        check_out(out, "out")?;
+        let shard = backup.0.next_shard()?;
+        *out = Box::into_raw(Box::new(PaperbackKeyShard(shard)));
+        Ok(())
+    })
+}
+
+/// Free a key shard. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `shard` must be NULL or a key shard returned by `paperback-ffi` that has
+/// not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_key_shard_free(shard: *mut PaperbackKeyShard) {
+    if !shard.is_null() {
+        drop(Box::from_raw(shard));
+    }
+}
+
+/// Encrypt a key shard. The encrypted key shard (a z-base32 string) and its
+/// codewords (whitespace-separated words) are both returned as strings that
+/// must be freed with `paperback_string_free()`.
+///
+/// # Safety
+///
+/// `shard` must be a valid key shard, and `out_shard` and `out_codewords`
+/// must be valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_key_shard_encrypt(
+    shard: *const PaperbackKeyShard,
+    out_shard: *mut *mut c_char,
+    out_codewords: *mut *mut c_char,
+) -> PaperbackResult {
+    ffi_call(|| {
+        let shard = ref_arg(shard, "shard")?;
+        check_out(out_shard, "out_shard")?;
+        check_out(out_codewords, "out_codewords")?;
+        let (encrypted, codewords) = shard.0.encrypt()?;
+        let encrypted = into_c_string(encrypted.to_wire_multibase(WIRE_MULTIBASE))?;
+        let codewords = match into_c_string(codewords.join(" ")) {
+            Ok(codewords) => codewords,
+            Err(err) => {
+                paperback_string_free(encrypted);
+                return Err(err);
+            }
+        };
+        *out_shard = encrypted;
+        *out_codewords = codewords;
+        Ok(())
+    })
+}
+
+/// Decrypt an encrypted key shard (a z-base32 string) using its codewords
+/// (whitespace-separated words). The codewords are normalised in the same way
+/// as the `paperback` CLI. The key shard must be freed with
+/// `paperback_key_shard_free()`.
+///
+/// # Safety
+///
+/// `encrypted` and `codewords` must be valid NUL-terminated strings, and
+/// `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_key_shard_decrypt(
+    encrypted: *const c_char,
+    codewords: *const c_char,
+    out: *mut *mut PaperbackKeyShard,
+) -> PaperbackResult {
+    ffi_call(|| {
+        let encrypted = str_arg(encrypted, "encrypted")?;
+        let codewords = str_arg(codewords, "codewords")?;
+        check_out(out, "out")?;
+        let encrypted = EncryptedKeyShard::from_wire_multibase(encrypted.trim())
+            .map_err(|err| FfiError::parse(format!("failed to decode key shard: {err}")))?;
+        let codewords = codewords.split_whitespace().collect::<Vec<_>>();
+        let codewords = codewords::normalize(&codewords)?;
+        let shard = encrypted.decrypt(&codewords)?;
+        *out = Box::into_raw(Box::new(PaperbackKeyShard(shard)));
+        Ok(())
+    })
+}
+
+/// Create a new, empty quorum. The quorum must be freed with
+/// `paperback_quorum_free()`.
+#[no_mangle]
+pub extern "C" fn paperback_quorum_new() -> *mut PaperbackQuorum {
+    Box::into_raw(Box::new(PaperbackQuorum(UntrustedQuorum::new())))
+}
+
+/// Free a quorum. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `quorum` must be NULL or a quorum returned by `paperback_quorum_new()`
+/// that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_quorum_free(quorum: *mut PaperbackQuorum) {
+    if !quorum.is_null() {
+        drop(Box::from_raw(quorum));
+    }
+}
+
+/// Add the main document (a z-base32 string) to a quorum.
+///
+/// # Safety
+///
+/// `quorum` must be a valid quorum and `main_document` must be a valid
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_quorum_main_document(
+    quorum: *mut PaperbackQuorum,
+    main_document: *const c_char,
+) -> PaperbackResult {
+    ffi_call(|| {
+        let quorum = mut_arg(quorum, "quorum")?;
+        let main_document = str_arg(main_document, "main_document")?;
+        let main_document = MainDocument::from_wire_multibase(main_document.trim())
+            .map_err(|err| FfiError::parse(format!("failed to decode main document: {err}")))?;
+        quorum.0.main_document(main_document);
+        Ok(())
+    })
+}
+
+/// Add a decrypted key shard to a quorum. The key shard is copied, and must
+/// still be freed by the caller.
+///
+/// # Safety
+///
+/// `quorum` must be a valid quorum and `shard` must be a valid key shard.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_quorum_push_shard(
+    quorum: *mut PaperbackQuorum,
+    shard: *const PaperbackKeyShard,
+) -> PaperbackResult {
+    ffi_call(|| {
+        let quorum = mut_arg(quorum, "quorum")?;
+        let shard = ref_arg(shard, "shard")?;
+        quorum.0.push_shard(shard.0.clone());
+        Ok(())
+    })
+}
+
+/// Validate a quorum and recover the secret stored in its main document. The
+/// secret must be freed with `paperback_buffer_free()`. The quorum is left
+/// unmodified, so more documents can be added if recovery fails.
+///
+/// # Safety
+///
+/// `quorum` must be a valid quorum and `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn paperback_quorum_recover(
+    quorum: *const PaperbackQuorum,
+    out: *mut PaperbackBuffer,
+) -> PaperbackResult {
+    ffi_call(|| {
+        let quorum = ref_arg(quorum, "quorum")?;
+        check_out(out, "out")?;
+        let secret = quorum.0.clone().validate()?.recover_document()?;
+        let secret = secret.to_vec().into_boxed_slice();
+        let len = secret.len();
+        *out = PaperbackBuffer {
+            data: Box::into_raw(secret) as *mut u8,
+            len,
+        };
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::{fs, path::Path};
+
+    fn take_string(string: *mut c_char) -> String {
+        let value = unsafe { CStr::from_ptr(string) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        unsafe { paperback_string_free(string) };
+        value
+    }
+
+    #[test]
+    fn ffi_roundtrip() {
+        const QUORUM_SIZE: u32 = 3;
+        let secret = b"the quick brown fox jumps over the lazy dog";
+
+        let mut backup = ptr::null_mut();
+        assert_eq!(
+            unsafe {
+                paperback_backup_new(
+                    QUORUM_SIZE,
+                    secret.as_ptr(),
+                    secret.len(),
+                    false,
+                    &mut backup,
+                )
+            },
+            PaperbackResult::Ok
+        );
+
+        let mut main_document = ptr::null_mut();
+        assert_eq!(
+            unsafe { paperback_backup_main_document(backup, &mut main_document) },
+            PaperbackResult::Ok
+        );
+        let main_document = CString::new(take_string(main_document)).unwrap();
+
+        let quorum = paperback_quorum_new();
+        assert_eq!(
+            unsafe { paperback_quorum_main_document(quorum, main_document.as_ptr()) },
+            PaperbackResult::Ok
+        );
+
+        for _ in 0..QUORUM_SIZE {
+            let mut shard = ptr::null_mut();
+            assert_eq!(
+                unsafe { paperback_backup_next_shard(backup, &mut shard) },
+                PaperbackResult::Ok
+            );
+
+            let (mut encrypted, mut codewords) = (ptr::null_mut(), ptr::null_mut());
+            assert_eq!(
+                unsafe { paperback_key_shard_encrypt(shard, &mut encrypted, &mut codewords) },
+                PaperbackResult::Ok
+            );
+            unsafe { paperback_key_shard_free(shard) };
+
+            // Make sure wrong codewords are reported as such.
+            let mut decrypted = ptr::null_mut();
+            let wrong = CString::new("abandon ".repeat(23) + "art").unwrap();
+            assert_eq!(
+                unsafe { paperback_key_shard_decrypt(encrypted, wrong.as_ptr(), &mut decrypted) },
+                PaperbackResult::WrongCodewords
+            );
+            assert!(!paperback_last_error().is_null());
+
+            assert_eq!(
+                unsafe { paperback_key_shard_decrypt(encrypted, codewords, &mut decrypted) },
+                PaperbackResult::Ok
+            );
+            unsafe {
+                paperback_string_free(encrypted);
+                paperback_string_free(codewords);
+            }
+
+            assert_eq!(
+                unsafe { paperback_quorum_push_shard(quorum, decrypted) },
+                PaperbackResult::Ok
+            );
+            unsafe { paperback_key_shard_free(decrypted) };
+        }
+
+        let mut recovered = PaperbackBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            unsafe { paperback_quorum_recover(quorum, &mut recovered) },
+            PaperbackResult::Ok
+        );
+        assert_eq!(
+            unsafe { slice::from_raw_parts(recovered.data, recovered.len) },
+            &secret[..]
+        );
+
+        unsafe {
+            paperback_buffer_free(&mut recovered);
+            paperback_quorum_free(quorum);
+            paperback_backup_free(backup);
+        }
+    }
+
+    #[test]
+    fn ffi_null_arguments() {
+        let mut backup = ptr::null_mut();
+        assert_eq!(
+            unsafe { paperback_backup_new(2, ptr::null(), 16, false, &mut backup) },
+            PaperbackResult::InvalidArgument
+        );
+        assert!(backup.is_null());
+        assert_eq!(
+            unsafe { paperback_backup_main_document(ptr::null(), ptr::null_mut()) },
+            PaperbackResult::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn header_up_to_date() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+            .expect("cbindgen.toml should be a valid cbindgen configuration");
+        let mut header = Vec::new();
+        cbindgen::Builder::new()
+            .with_crate(crate_dir)
+            .with_config(config)
+            .generate()
+            .expect("failed to generate C bindings for paperback-ffi")
+            .write(&mut header);
+
+        let committed = fs::read(crate_dir.join("include").join("paperback.h")).unwrap();
+        assert!(
+            header == committed,
+            "include/paperback.h is out of date (regenerate it with cbindgen, see README.md)"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use eframe::egui;
+use paperback_core::latest::{Backup, PdfOptions, ToPdf};
+use zeroize::Zeroizing;
+
+use crate::Status;
+
+/// Form for creating a new backup of a file.
+pub(crate) struct BackupTab {
+    input: Option<PathBuf>,
+    title: String,
+    quorum_size: u32,
+    num_shards: u32,
+    sealed: bool,
+    output_dir: Option<PathBuf>,
+    status: Status,
+}
+
+impl Default for BackupTab {
+    fn default() -> Self {
+        Self {
+            input: None,
+            title: String::new(),
+            quorum_size: 3,
+            num_shards: 5,
+            sealed: false,
+            output_dir: None,
+            status: Status::Empty,
+        }
+    }
+}
+
+/// Write a PDF of `object` to a new file called `name` in `dir`, refusing to
+/// overwrite any existing file.
+fn save_pdf<T: ToPdf + ?Sized>(dir: &Path, name: &str, object: &T) -> Result<(), Error> {
+    let pdf = object.to_pdf()?;
+    let path = dir.join(name);
+    let file = File::create_new(&path)
+        .with_context(|| format!("failed to create '{}'", path.display()))?;
+    pdf.save(&mut BufWriter::new(file))
+        .with_context(|| format!("failed to write '{}'", path.display()))?;
+    Ok(())
+}
+
+fn path_label(ui: &mut egui::Ui, path: Option<&PathBuf>) {
+    match path {
+        Some(path) => ui.label(path.display().to_string()),
+        None => ui.weak("(none selected)"),
+    };
+}
+
+impl BackupTab {
+    pub(crate) fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Create a backup");
+        ui.label(
+            "The file is encrypted and printed as a main document, and the key needed to \
+             decrypt it is split into key shards. Give each key shard to a different person \
+             you trust -- the backup can only be recovered with the main document and enough \
+             key shards.",
+        );
+        ui.add_space(8.0);
+
+        egui::Grid::new("backup-form")
+            .num_columns(2)
+            .spacing([12.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("File to back up:");
+                ui.horizontal(|ui| {
+                    if ui.button("Choose…").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_file() {
+                            self.input = Some(path);
+                        }
+                    }
+                    path_label(ui, self.input.as_ref());
+                });
+                ui.end_row();
+
+                ui.label("Title (optional):");
+                ui.text_edit_singleline(&mut self.title);
+                ui.end_row();
+
+                ui.label("Key shards needed to recover:");
+                ui.add(
+                    egui::DragValue::new(&mut self.quorum_size).range(1..=Backup::MAX_QUORUM_SIZE),
+                );
+                ui.end_row();
+
+                ui.label("Key shards to create:");
+                self.num_shards = self.num_shards.max(self.quorum_size);
+                ui.add(
+                    egui::DragValue::new(&mut self.num_shards).range(self.quorum_size..=u32::MAX),
+                );
+                ui.end_row();
+
+                ui.label("");
+                ui.checkbox(
+                    &mut self.sealed,
+                    "Sealed (no more key shards can be created later)",
+                );
+                ui.end_row();
+
+                ui.label("Save documents to:");
+                ui.horizontal(|ui| {
+                    if ui.button("Choose…").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            self.output_dir = Some(path);
+                        }
+                    }
+                    path_label(ui, self.output_dir.as_ref());
+                });
+                ui.end_row();
+            });
+
+        if self.quorum_size == 1 {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "With only one key shard needed, any single key shard can recover the backup.",
+            );
+        }
+
+        ui.add_space(8.0);
+        let ready = self.input.is_some() && self.output_dir.is_some();
+        if ui
+            .add_enabled(ready, egui::Button::new("Create backup"))
+            .clicked()
+        {
+            self.status = Status::from_result(self.create());
+        }
+        self.status.ui(ui);
+    }
+
+    fn create(&self) -> Result<String, Error> {
+        let input = self.input.as_ref().context("no file selected")?;
+        let output_dir = self
+            .output_dir
+            .as_ref()
+            .context("no output folder selected")?;
+        Backup::check_policy(self.quorum_size, self.num_shards)?;
+
+        let secret = Zeroizing::new(
+            fs::read(input).with_context(|| format!("failed to read '{}'", input.display()))?,
+        );
+        let mut builder = Backup::builder()
+            .quorum_size(self.quorum_size)
+            .sealed(self.sealed)
+            .pdf_options(&PdfOptions::default());
+        if !self.title.trim().is_empty() {
+            builder = builder.title(self.title.trim());
+        }
+        let backup = builder.build(&*secret)?;
+
+        let main_document = backup.main_document();
+        save_pdf(
+            output_dir,
+            &format!("main_document-{}.pdf", main_document.id()),
+            main_document,
+        )?;
+        for shard in backup.next_shards(self.num_shards as usize)? {
+            let (document_id, shard_id) = (shard.document_id(), shard.id());
+            save_pdf(
+                output_dir,
+                &format!("key_shard-{}-{}.pdf", document_id, shard_id),
+                &shard.encrypt()?,
+            )?;
+        }
+
+        Ok(format!(
+            "Created backup {} with {} key shards in '{}'. Print every document, and then \
+             delete the PDFs (they contain everything needed to recover the backup).",
+            main_document.id(),
+            self.num_shards,
+            output_dir.display()
+        ))
+    }
+}
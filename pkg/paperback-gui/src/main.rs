@@ -0,0 +1,103 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A minimal graphical interface for paperback, for users who would rather
+//! not use the `paperback` CLI.
+//!
+//! Only the most common operations are supported (creating a backup, and
+//! recovering or verifying a backup from the PDFs generated by paperback).
+//! Everything else (such as expanding a quorum or scanning printed documents)
+//! still requires the CLI.
+
+#![forbid(unsafe_code)]
+
+mod backup;
+mod recover;
+
+use eframe::egui;
+
+/// The result of the last operation, shown at the bottom of each tab.
+#[derive(Debug, Default)]
+enum Status {
+    #[default]
+    Empty,
+    Info(String),
+    Error(String),
+}
+
+impl Status {
+    fn from_result(result: Result<String, anyhow::Error>) -> Self {
+        match result {
+            Ok(message) => Self::Info(message),
+            Err(err) => Self::Error(format!("{:#}", err)),
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        match self {
+            Self::Empty => {}
+            Self::Info(message) => {
+                ui.separator();
+                ui.label(message);
+            }
+            Self::Error(message) => {
+                ui.separator();
+                ui.colored_label(ui.visuals().error_fg_color, message);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    #[default]
+    Backup,
+    Recover,
+}
+
+#[derive(Default)]
+struct App {
+    tab: Tab,
+    backup: backup::BackupTab,
+    recover: recover::RecoverTab,
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::top("tabs").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.tab, Tab::Backup, "Create backup");
+                ui.selectable_value(&mut self.tab, Tab::Recover, "Recover or verify");
+            });
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| match self.tab {
+                Tab::Backup => self.backup.ui(ui),
+                Tab::Recover => self.recover.ui(ctx, ui),
+            })
+        });
+    }
+}
+
+fn main() -> eframe::Result {
+    eframe::run_native(
+        "paperback",
+        eframe::NativeOptions::default(),
+        Box::new(|_| Ok(Box::<App>::default())),
+    )
+}
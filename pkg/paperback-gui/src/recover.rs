@@ -0,0 +1,264 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fs;
+
+use anyhow::{anyhow, Context, Error};
+use eframe::egui;
+use paperback_core::latest::{
+    codewords, EncryptedKeyShard, FromPdf, FromWire, MainDocument, QrEncoding, Quorum,
+    UntrustedQuorum,
+};
+use zeroize::Zeroizing;
+
+use crate::Status;
+
+/// A key shard which has been loaded, along with the codewords entered for
+/// it by the user.
+struct ShardEntry {
+    source: String,
+    shard: EncryptedKeyShard,
+    codewords: Zeroizing<String>,
+}
+
+/// Form for recovering (or just verifying) a backup from the PDFs generated
+/// by paperback.
+#[derive(Default)]
+pub(crate) struct RecoverTab {
+    main_document: Option<(String, MainDocument)>,
+    shards: Vec<ShardEntry>,
+    pasted: String,
+    status: Status,
+}
+
+impl RecoverTab {
+    /// Add the main document or key shards contained in a PDF.
+    fn load_pdf(&mut self, source: String, pdf: &[u8]) -> Result<(), Error> {
+        if let Ok(main_document) = MainDocument::from_pdf(pdf) {
+            self.main_document = Some((source, main_document));
+            return Ok(());
+        }
+        let shards = Vec::<EncryptedKeyShard>::from_pdf(pdf).with_context(|| {
+            format!(
+                "'{}' is not a main document or key shard PDF generated by paperback",
+                source
+            )
+        })?;
+        self.add_shards(source, shards);
+        Ok(())
+    }
+
+    /// Add the main document or key shard contained in the text of a single
+    /// QR code (or its text fallback).
+    fn load_text(&mut self, text: &str) -> Result<(), Error> {
+        let data = QrEncoding::decode(text).context("failed to decode data")?;
+        if let Ok(main_document) = MainDocument::from_wire(&data) {
+            self.main_document = Some(("pasted data".into(), main_document));
+            return Ok(());
+        }
+        let shard = EncryptedKeyShard::from_wire(&data)
+            .map_err(|err| anyhow!("data is not a main document or key shard: {}", err))?;
+        self.add_shards("pasted data".into(), vec![shard]);
+        Ok(())
+    }
+
+    fn add_shards(&mut self, source: String, shards: Vec<EncryptedKeyShard>) {
+        let num_shards = shards.len();
+        for (idx, shard) in shards.into_iter().enumerate() {
+            self.shards.push(ShardEntry {
+                source: match num_shards {
+                    1 => source.clone(),
+                    _ => format!("{} (key shard {})", source, idx + 1),
+                },
+                shard,
+                codewords: Default::default(),
+            });
+        }
+    }
+
+    fn load_files(&mut self, files: Vec<egui::DroppedFile>) {
+        for file in files {
+            let result = match (&file.path, &file.bytes) {
+                (_, Some(bytes)) => self.load_pdf(file.name.clone(), bytes),
+                (Some(path), None) => fs::read(path)
+                    .with_context(|| format!("failed to read '{}'", path.display()))
+                    .and_then(|pdf| self.load_pdf(path.display().to_string(), &pdf)),
+                (None, None) => Ok(()),
+            };
+            if let Err(err) = result {
+                self.status = Status::Error(format!("{:#}", err));
+            }
+        }
+    }
+
+    fn quorum(&self) -> Result<Quorum, Error> {
+        let mut quorum = UntrustedQuorum::new();
+        if let Some((_, main_document)) = &self.main_document {
+            quorum.main_document(main_document.clone());
+        }
+        for entry in &self.shards {
+            let codewords =
+                codewords::normalize(&entry.codewords.split_whitespace().collect::<Vec<_>>())
+                    .with_context(|| format!("invalid codewords for {}", entry.source))?;
+            let shard = entry
+                .shard
+                .decrypt(&codewords)
+                .with_context(|| format!("failed to decrypt {}", entry.source))?;
+            quorum.push_shard(shard);
+        }
+        Ok(quorum.validate()?)
+    }
+
+    fn verify(&self) -> Result<String, Error> {
+        let check = self.quorum()?.verify_recoverable()?;
+        let mut message = format!(
+            "The backup can be recovered ({} bytes, checksum {}).",
+            check.length,
+            check.checksum_string()
+        );
+        if !check.payloads.is_empty() {
+            message += &format!(" It contains: {}.", check.payloads.join(", "));
+        }
+        Ok(message)
+    }
+
+    fn recover(&self) -> Result<String, Error> {
+        let secret = self.quorum()?.recover_document()?;
+        let path = match rfd::FileDialog::new().save_file() {
+            Some(path) => path,
+            None => return Ok("Recovery cancelled.".into()),
+        };
+        fs::write(&path, &*secret)
+            .with_context(|| format!("failed to write '{}'", path.display()))?;
+        Ok(format!(
+            "Recovered {} bytes to '{}'.",
+            secret.len(),
+            path.display()
+        ))
+    }
+
+    pub(crate) fn ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let dropped = ctx.input(|input| input.raw.dropped_files.clone());
+        if !dropped.is_empty() {
+            self.load_files(dropped);
+        }
+
+        ui.heading("Recover or verify a backup");
+        ui.label(
+            "Drag and drop the PDFs of the main document and key shards onto this window \
+             (or open them below), then type in the codewords written on each key shard.",
+        );
+        ui.add_space(8.0);
+
+        ui.horizontal(|ui| {
+            if ui.button("Open PDFs…").clicked() {
+                let paths = rfd::FileDialog::new()
+                    .add_filter("PDF", &["pdf"])
+                    .pick_files()
+                    .unwrap_or_default();
+                for path in paths {
+                    let result = fs::read(&path)
+                        .with_context(|| format!("failed to read '{}'", path.display()))
+                        .and_then(|pdf| self.load_pdf(path.display().to_string(), &pdf));
+                    if let Err(err) = result {
+                        self.status = Status::Error(format!("{:#}", err));
+                    }
+                }
+            }
+            if ui.button("Clear").clicked() {
+                *self = Default::default();
+            }
+        });
+
+        ui.collapsing("Enter the data of a QR code instead", |ui| {
+            ui.text_edit_multiline(&mut self.pasted);
+            if ui.button("Add").clicked() {
+                let pasted = std::mem::take(&mut self.pasted);
+                if let Err(err) = self.load_text(&pasted) {
+                    self.status = Status::Error(format!("{:#}", err));
+                    self.pasted = pasted;
+                }
+            }
+        });
+        ui.add_space(8.0);
+
+        ui.strong("Main document");
+        match &self.main_document {
+            Some((source, main_document)) => {
+                ui.label(format!(
+                    "Document {} from {} ({} key shards needed).",
+                    main_document.id(),
+                    source,
+                    main_document.quorum_size()
+                ));
+                if let Some(title) = main_document.title() {
+                    ui.label(format!("Title: {}", title));
+                }
+            }
+            None => {
+                ui.weak("(none loaded)");
+            }
+        }
+        ui.add_space(8.0);
+
+        ui.strong("Key shards");
+        if self.shards.is_empty() {
+            ui.weak("(none loaded)");
+        }
+        let mut remove = None;
+        egui::Grid::new("recover-shards")
+            .num_columns(3)
+            .spacing([12.0, 8.0])
+            .show(ui, |ui| {
+                for (idx, entry) in self.shards.iter_mut().enumerate() {
+                    ui.label(&entry.source);
+                    ui.add(
+                        egui::TextEdit::singleline(&mut *entry.codewords)
+                            .hint_text("codewords")
+                            .desired_width(400.0),
+                    );
+                    if ui.button("Remove").clicked() {
+                        remove = Some(idx);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(idx) = remove {
+            self.shards.remove(idx);
+        }
+        ui.add_space(8.0);
+
+        ui.horizontal(|ui| {
+            let ready = self.main_document.is_some() && !self.shards.is_empty();
+            if ui
+                .add_enabled(ready, egui::Button::new("Verify"))
+                .on_hover_text("Check that the backup can be recovered, without saving it.")
+                .clicked()
+            {
+                self.status = Status::from_result(self.verify());
+            }
+            if ui
+                .add_enabled(ready, egui::Button::new("Recover and save…"))
+                .clicked()
+            {
+                self.status = Status::from_result(self.recover());
+            }
+        });
+        self.status.ui(ui);
+    }
+}
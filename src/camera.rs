@@ -0,0 +1,151 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Context, Error};
+use nokhwa::{
+    pixel_format::LumaFormat,
+    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    Camera,
+};
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{pdf::qr, FromWire};
+
+use crate::{format_part_indices, parse_multibase};
+
+/// Scans paperback QR codes live from a webcam.
+pub(crate) struct QrScanner {
+    camera: Camera,
+    // QR codes usually stay in view for many frames, so we only handle each
+    // distinct code once.
+    seen: HashSet<String>,
+}
+
+impl QrScanner {
+    /// Open the default camera and start streaming frames from it.
+    pub(crate) fn open() -> Result<Self, Error> {
+        let format =
+            RequestedFormat::new::<LumaFormat>(RequestedFormatType::AbsoluteHighestResolution);
+        let mut camera =
+            Camera::new(CameraIndex::Index(0), format).context("failed to open camera")?;
+        camera
+            .open_stream()
+            .context("failed to start camera stream")?;
+        Ok(Self {
+            camera,
+            seen: HashSet::new(),
+        })
+    }
+
+    /// Capture a frame and return the data of any QR codes in it which have
+    /// not been seen before.
+    fn next_codes(&mut self) -> Result<Vec<String>, Error> {
+        let frame = self
+            .camera
+            .frame()
+            .context("failed to capture camera frame")?
+            .decode_image::<LumaFormat>()
+            .context("failed to decode camera frame")?;
+        let mut image = rqrr::PreparedImage::prepare_from_greyscale(
+            frame.width() as usize,
+            frame.height() as usize,
+            |x, y| frame.get_pixel(x as u32, y as u32).0[0],
+        );
+        Ok(image
+            .detect_grids()
+            .iter()
+            .filter_map(|grid| grid.decode().ok())
+            .map(|(_, data)| data)
+            .filter(|data| self.seen.insert(data.clone()))
+            .collect())
+    }
+
+    /// Scan a single QR code containing a `T`.
+    ///
+    /// QR codes which do not contain a `T` (such as QR codes from another
+    /// document that are still in view) are skipped.
+    pub(crate) fn scan_multibase<S: AsRef<str>, T: FromWire>(
+        &mut self,
+        prompt: S,
+    ) -> Result<T, Error> {
+        println!("{} (hold the QR code up to the camera)...", prompt.as_ref());
+        loop {
+            for code in self.next_codes()? {
+                // Key shards which are too large for a single QR code are
+                // split into several parts.
+                if let Ok(part) = parse_multibase::<_, qr::Part>(&code) {
+                    if part.data_type() == qr::PartType::KeyShardData {
+                        let mut joiner = qr::Joiner::new();
+                        joiner.add_part(part)?;
+                        return self.join_multibase_qr(joiner);
+                    }
+                }
+                match parse_multibase(&code) {
+                    Ok(data) => return Ok(data),
+                    Err(err) => println!("Skipping unexpected QR code: {:#}", err),
+                }
+            }
+        }
+    }
+
+    /// Scan all of the QR codes making up a `T` (in any order), showing which
+    /// codes have not yet been scanned.
+    pub(crate) fn scan_multibase_qr<S: AsRef<str>, T: FromWire>(
+        &mut self,
+        prompt: S,
+    ) -> Result<T, Error> {
+        println!(
+            "{} (hold the QR codes up to the camera)...",
+            prompt.as_ref()
+        );
+        self.join_multibase_qr(qr::Joiner::new())
+    }
+
+    /// Scan the QR codes still missing from `joiner`, and parse the combined
+    /// data as a `T`.
+    fn join_multibase_qr<T: FromWire>(&mut self, mut joiner: qr::Joiner) -> Result<T, Error> {
+        while !joiner.complete() {
+            for code in self.next_codes()? {
+                let part: qr::Part = match parse_multibase(&code) {
+                    Ok(part) => part,
+                    Err(err) => {
+                        println!("Skipping unexpected QR code: {:#}", err);
+                        continue;
+                    }
+                };
+                let (idx, num_parts) = (part.index(), part.num_parts());
+                if let Err(err) = joiner.add_part(part) {
+                    println!("Code {} of {} rejected: {}", idx + 1, num_parts, err);
+                    continue;
+                }
+                println!(
+                    "Scanned code {} of {} (missing [{}]).",
+                    idx + 1,
+                    num_parts,
+                    format_part_indices(&joiner.missing_parts()),
+                );
+            }
+        }
+        T::from_wire(joiner.combine_parts()?)
+            .map_err(|err| anyhow!("parse inner qr code data: {}", err))
+    }
+}
@@ -0,0 +1,193 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use anyhow::{anyhow, bail, Context, Error};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{ShardChallenge, ShardResponse, ToWire};
+
+use crate::{
+    codewords_or_read, parse_multibase, read_key_shard, read_shard_file, registry::Registry,
+};
+
+const ENCODING_BASE: multibase::Base = multibase::Base::Base32Z;
+
+// paperback-cli challenge new
+fn new_cli() -> Command {
+    Command::new("new").about("Generate a new random challenge to send to a key shard holder.")
+}
+
+fn new() -> Result<(), Error> {
+    let challenge = ShardChallenge::new();
+    println!("Send this challenge to the key shard holder (they can answer it with \"challenge respond\"):");
+    println!("{}", challenge.to_wire_multibase(ENCODING_BASE));
+    Ok(())
+}
+
+// paperback-cli challenge respond [--shard-file <PATH>] <CHALLENGE>
+fn respond_cli() -> Command {
+    Command::new("respond")
+        .about("Answer a challenge from the owner of a backup, proving that you still have a working key shard. Neither the challenge nor the response reveal anything about your key shard.")
+        .arg(
+            Arg::new("shard-file")
+                .long("shard-file")
+                .value_name("PATH")
+                .help("Read the key shard from a text file containing its data on the first line (such as those written by --export-text), rather than asking for it interactively.")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("CHALLENGE")
+                .help("Challenge generated by \"challenge new\".")
+                .action(ArgAction::Set)
+                .required(true)
+                .index(1),
+        )
+}
+
+fn respond(matches: &ArgMatches) -> Result<(), Error> {
+    let challenge: ShardChallenge = parse_multibase(
+        matches
+            .get_one::<String>("CHALLENGE")
+            .context("required CHALLENGE argument not provided")?,
+    )
+    .context("failed to parse challenge")?;
+
+    let (encrypted_shard, codewords) = match matches.get_one::<String>("shard-file") {
+        Some(path) => (read_shard_file(path)?, None),
+        None => read_key_shard("Enter your key shard")?,
+    };
+    let codewords = codewords_or_read(codewords, "Enter your key shard codewords")?;
+    let shard = encrypted_shard
+        .decrypt(&codewords)
+        .context("decrypting key shard")?;
+
+    let response = challenge.respond(&shard);
+    println!("Send this response back to the owner of the backup:");
+    println!("{}", response.to_wire_multibase(ENCODING_BASE));
+    Ok(())
+}
+
+// paperback-cli challenge verify <CHALLENGE> <RESPONSE>
+fn verify_cli() -> Command {
+    Command::new("verify")
+        .about("Check a key shard holder's response to a challenge against the shard registry. Only key shards recorded with --register can be checked.")
+        .arg(
+            Arg::new("CHALLENGE")
+                .help("Challenge generated by \"challenge new\".")
+                .action(ArgAction::Set)
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::new("RESPONSE")
+                .help("Response generated by \"challenge respond\".")
+                .action(ArgAction::Set)
+                .required(true)
+                .index(2),
+        )
+}
+
+fn verify(matches: &ArgMatches) -> Result<(), Error> {
+    let challenge: ShardChallenge = parse_multibase(
+        matches
+            .get_one::<String>("CHALLENGE")
+            .context("required CHALLENGE argument not provided")?,
+    )
+    .context("failed to parse challenge")?;
+    let response: ShardResponse = parse_multibase(
+        matches
+            .get_one::<String>("RESPONSE")
+            .context("required RESPONSE argument not provided")?,
+    )
+    .context("failed to parse response")?;
+    let shard_id = response.shard_id();
+
+    let entries = Registry::open_existing()?
+        .entries()?
+        .into_iter()
+        .filter(|entry| &entry.shard_id == shard_id)
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        bail!(
+            "key shard {} is not recorded in the shard registry",
+            shard_id
+        );
+    }
+    // The same key shard may have been recorded more than once (such as when
+    // it was re-created with recreate-shards).
+    let entry = entries.iter().find(|entry| {
+        entry
+            .challenge_checksum
+            .as_ref()
+            .is_some_and(|checksum| challenge.verify(checksum, &response))
+    });
+    match entry {
+        Some(entry) => {
+            print!(
+                "Key shard {} of document {} passed the challenge",
+                shard_id, entry.document_id
+            );
+            match &entry.label {
+                Some(label) => println!(" (label: {}).", label),
+                None => println!("."),
+            }
+            Ok(())
+        }
+        None if entries
+            .iter()
+            .all(|entry| entry.challenge_checksum.is_none()) =>
+        {
+            bail!(
+                "key shard {} was recorded in the shard registry by an older paperback version and cannot be challenged",
+                shard_id
+            )
+        }
+        None => bail!("key shard {} failed the challenge", shard_id),
+    }
+}
+
+pub(crate) fn submatch(app: &mut Command, matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("new", _)) => new(),
+        Some(("respond", sub_matches)) => respond(sub_matches),
+        Some(("verify", sub_matches)) => verify(sub_matches),
+        Some((subcommand, _)) => {
+            // We should never end up here.
+            app.print_help()?;
+            Err(anyhow!("unknown subcommand 'challenge {}'", subcommand))
+        }
+        None => {
+            app.print_help()?;
+            Err(anyhow!("no 'challenge' subcommand specified"))
+        }
+    }
+}
+
+pub(crate) fn subcommands() -> Command {
+    Command::new("challenge")
+            .about("Check that a key shard holder still has a working key shard (including its codewords), without gathering a quorum. The owner generates a challenge, the key shard holder answers it using their key shard, and the owner checks the response against the shard registry.")
+            // paperback-cli challenge new
+            .subcommand(new_cli())
+            // paperback-cli challenge respond [--shard-file <PATH>] <CHALLENGE>
+            .subcommand(respond_cli())
+            // paperback-cli challenge verify <CHALLENGE> <RESPONSE>
+            .subcommand(verify_cli())
+}
@@ -0,0 +1,331 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+};
+
+use anyhow::{anyhow, ensure, Context, Error};
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{pdf::qr, EncryptedKeyShard, FromWire, MainDocument, QrEncoding, ShardId};
+
+use crate::{format_part_indices, parse_multibase};
+
+/// Prefix added to each decoded QR code by zbarimg (unless run with `--raw`).
+const ZBAR_QR_PREFIX: &str = "QR-Code:";
+
+/// Sorts decoded paperback QR codes (from any source, in any order) into the
+/// main document and key shards, joining any codes which are split into
+/// several parts.
+#[derive(Default)]
+pub(crate) struct CodeCollector {
+    joiner: qr::Joiner,
+    /// Main document read directly (such as from a PDF file) rather than
+    /// joined from its QR codes.
+    main_document: Option<MainDocument>,
+    /// Key shards which were split into several QR codes, by shard id.
+    shard_joiners: BTreeMap<ShardId, qr::Joiner>,
+    shards: Vec<EncryptedKeyShard>,
+    /// The same QR code may be provided more than once.
+    seen: HashSet<String>,
+}
+
+impl CodeCollector {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the decoded data of a QR code found at `location` (which is only
+    /// used in messages), which may be a part of a larger set of QR codes.
+    pub(crate) fn add_code(&mut self, code: String, location: &str) {
+        match QrEncoding::decode(&code)
+            .ok()
+            .and_then(|data| qr::Part::from_wire(data).ok())
+        {
+            Some(part) => self.add_part(part, location),
+            None => self.add_single_code(code, location),
+        }
+    }
+
+    /// Add one part of a set of QR codes found at `location`.
+    pub(crate) fn add_part(&mut self, part: qr::Part, location: &str) {
+        let (idx, num_parts) = (part.index(), part.num_parts());
+        let (name, result) = match (part.data_type(), part.shard_id().cloned()) {
+            (qr::PartType::MainDocumentData, _) => ("Main document", self.joiner.add_part(part)),
+            (qr::PartType::KeyShardData, Some(shard_id)) => (
+                "Key shard",
+                self.shard_joiners
+                    .entry(shard_id)
+                    .or_default()
+                    .add_part(part),
+            ),
+            // Key shard checksums are only for checking by hand.
+            _ => return,
+        };
+        if let Err(err) = result {
+            println!(
+                "{} code {} of {} {} rejected: {}",
+                name,
+                idx + 1,
+                num_parts,
+                location,
+                err
+            );
+        }
+    }
+
+    /// Add the data of a QR code found at `location` which is not part of a
+    /// set (such as a key shard).
+    pub(crate) fn add_single_code(&mut self, code: String, location: &str) {
+        if !self.seen.insert(code.clone()) {
+            return;
+        }
+        match parse_multibase(&code) {
+            Ok(shard) => self.shards.push(shard),
+            Err(err) => println!("Skipping unexpected QR code {}: {:#}", location, err),
+        }
+    }
+
+    /// Combine the collected QR codes into the main document and key shards,
+    /// reporting any QR codes which were not found in `source`.
+    pub(crate) fn finish(
+        self,
+        source: &str,
+    ) -> Result<(MainDocument, Vec<EncryptedKeyShard>), Error> {
+        let mut shards = self.shards;
+        for (shard_id, shard_joiner) in self.shard_joiners {
+            if !shard_joiner.complete() {
+                println!(
+                    "Key shard {} codes [{}] were not found {}.",
+                    shard_id,
+                    format_part_indices(&shard_joiner.missing_parts()),
+                    source
+                );
+                continue;
+            }
+            let shard = EncryptedKeyShard::from_wire(shard_joiner.combine_parts()?)
+                .map_err(|err| anyhow!("parse key shard {} qr code data: {}", shard_id, err))?;
+            shards.push(shard);
+        }
+
+        if let Some(main_document) = self.main_document {
+            return Ok((main_document, shards));
+        }
+        let joiner = self.joiner;
+        ensure!(
+            joiner.num_parts().is_some(),
+            "no main document QR codes found {}",
+            source
+        );
+        ensure!(
+            joiner.complete(),
+            "main document QR codes [{}] were not found {}",
+            format_part_indices(&joiner.missing_parts()),
+            source
+        );
+        let main_document = MainDocument::from_wire(joiner.combine_parts()?)
+            .map_err(|err| anyhow!("parse inner qr code data: {}", err))?;
+        Ok((main_document, shards))
+    }
+}
+
+/// Used to track progress while collecting codes (see `--watch-dir`).
+#[cfg_attr(not(feature = "watch"), allow(dead_code))]
+impl CodeCollector {
+    /// Add a main document read from `location` (such as a PDF file).
+    pub(crate) fn add_main_document(&mut self, main_document: MainDocument, location: &str) {
+        match &self.main_document {
+            Some(existing) if existing.checksum_string() != main_document.checksum_string() => {
+                println!("Skipping different main document {}.", location)
+            }
+            Some(_) => (),
+            None => self.main_document = Some(main_document),
+        }
+    }
+
+    /// Add a key shard read from somewhere other than a QR code (such as a PDF
+    /// file).
+    pub(crate) fn add_shard(&mut self, shard: EncryptedKeyShard) {
+        let checksum = shard.checksum_string();
+        if !self
+            .shards
+            .iter()
+            .any(|existing| existing.checksum_string() == checksum)
+        {
+            self.shards.push(shard);
+        }
+    }
+
+    /// The main document, if it has been read or all of its QR codes have
+    /// been collected.
+    pub(crate) fn main_document(&self) -> Option<MainDocument> {
+        if self.main_document.is_some() || !self.joiner.complete() {
+            return self.main_document.clone();
+        }
+        self.joiner
+            .combine_parts()
+            .ok()
+            .and_then(|data| MainDocument::from_wire(data).ok())
+    }
+
+    /// A description of which main document QR codes are still missing, or
+    /// `None` if the main document is complete.
+    pub(crate) fn missing_main_document(&self) -> Option<String> {
+        if self.main_document.is_some() || self.joiner.complete() {
+            return None;
+        }
+        Some(match self.joiner.num_parts() {
+            Some(num_parts) => format!(
+                "main document codes [{}] of {} missing",
+                format_part_indices(&self.joiner.missing_parts()),
+                num_parts
+            ),
+            None => "no main document codes found".into(),
+        })
+    }
+
+    /// Number of key shards which have been completely collected.
+    pub(crate) fn num_shards(&self) -> usize {
+        self.shards.len()
+            + self
+                .shard_joiners
+                .values()
+                .filter(|shard_joiner| shard_joiner.complete())
+                .count()
+    }
+}
+
+/// Read the main document and key shards from files containing the decoded
+/// data of QR codes (one per line), as produced by most QR code scanning apps
+/// or by running `zbarimg` on the scanned documents.
+pub(crate) fn read_qr_strings<'a>(
+    paths: impl IntoIterator<Item = &'a String>,
+) -> Result<(MainDocument, Vec<EncryptedKeyShard>), Error> {
+    let mut collector = CodeCollector::new();
+    for path in paths {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read qr strings file '{}'", path))?;
+        let mut found = 0;
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            let code = line.strip_prefix(ZBAR_QR_PREFIX).unwrap_or(line).trim();
+            if code.is_empty() {
+                continue;
+            }
+            found += 1;
+            collector.add_code(
+                code.to_string(),
+                &format!("on line {} of '{}'", idx + 1, path),
+            );
+        }
+        println!("Found {} codes in '{}'.", found, path);
+    }
+    collector.finish("in the qr strings files")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use paperback::{pdf::qr::main_document_qr_text, Backup, PdfOptions, ToWire};
+
+    fn backup_codes() -> (MainDocument, EncryptedKeyShard, Vec<String>) {
+        let backup = Backup::new(2, [0x42; 4096]).unwrap();
+        let main_document = backup.main_document().clone();
+        let (shard, _) = backup.next_shard().unwrap().encrypt().unwrap();
+        let codes = main_document_qr_text(&main_document, &PdfOptions::default());
+        (main_document, shard, codes)
+    }
+
+    #[test]
+    fn collect_shuffled_codes() {
+        let (main_document, shard, codes) = backup_codes();
+        assert!(codes.len() > 1, "main document should need several codes");
+
+        let mut collector = CodeCollector::new();
+        collector.add_code(
+            shard.to_wire_multibase(multibase::Base::Base32Z),
+            "on line 1",
+        );
+        for (idx, code) in codes.iter().enumerate().rev() {
+            collector.add_code(code.clone(), &format!("on line {}", idx + 2));
+        }
+        // Duplicate codes are harmless.
+        collector.add_code(codes[0].clone(), "on the last line");
+
+        let (got_document, got_shards) = collector.finish("in the test").unwrap();
+        assert_eq!(got_document.to_wire(), main_document.to_wire());
+        assert_eq!(
+            got_shards.iter().map(ToWire::to_wire).collect::<Vec<_>>(),
+            vec![shard.to_wire()]
+        );
+    }
+
+    #[test]
+    fn collect_missing_codes() {
+        let (_, _, codes) = backup_codes();
+
+        let mut collector = CodeCollector::new();
+        for code in codes.iter().skip(1) {
+            collector.add_code(code.clone(), "in the test");
+        }
+        let err = collector.finish("in the test").unwrap_err();
+        assert!(
+            err.to_string().contains("[1]"),
+            "error should report the missing code: {}",
+            err
+        );
+
+        assert!(CodeCollector::new().finish("in the test").is_err());
+    }
+
+    #[test]
+    fn collect_progress() {
+        let (main_document, shard, codes) = backup_codes();
+
+        let mut collector = CodeCollector::new();
+        assert_eq!(
+            collector.missing_main_document().as_deref(),
+            Some("no main document codes found")
+        );
+        for code in codes.iter().skip(1) {
+            collector.add_code(code.clone(), "in the test");
+        }
+        assert!(collector.main_document().is_none());
+        assert!(collector
+            .missing_main_document()
+            .unwrap()
+            .starts_with("main document codes [1] of"));
+
+        collector.add_code(codes[0].clone(), "in the test");
+        assert_eq!(collector.missing_main_document(), None);
+        assert_eq!(
+            collector.main_document().map(|doc| doc.to_wire()),
+            Some(main_document.to_wire())
+        );
+
+        // The same key shard is only counted once.
+        collector.add_shard(shard.clone());
+        collector.add_shard(shard);
+        assert_eq!(collector.num_shards(), 1);
+    }
+}
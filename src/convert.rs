@@ -0,0 +1,277 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io, path::PathBuf};
+
+use anyhow::{anyhow, ensure, Context, Error};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use zeroize::Zeroizing;
+
+extern crate paperback_core;
+use paperback_core::{
+    latest as paperback,
+    slip39::{self, GroupPolicy, Share},
+};
+
+use paperback::{Backup, PdfOptions};
+
+use crate::{
+    check_backup_policy, get_key_shard_pdf_options, get_layout, get_max_quorum_size,
+    get_qr_density,
+    output::{self, Output},
+    print_created, read_multiline, read_recovery_quorum, recover_selected, recovery_source_args,
+    registry::Registry,
+    save_backup, save_backup_args, select_arg,
+};
+
+fn passphrase_arg() -> Arg {
+    Arg::new("passphrase")
+        .long("passphrase")
+        .help("Prompt for the SLIP-0039 passphrase (without echoing it to the terminal). Without this flag, the passphrase is empty. Note that a wrong passphrase cannot be detected -- it simply results in a different secret.")
+        .action(ArgAction::SetTrue)
+}
+
+fn read_passphrase(matches: &ArgMatches, confirm: bool) -> Result<Zeroizing<String>, Error> {
+    if !matches.get_flag("passphrase") {
+        return Ok(Zeroizing::new(String::new()));
+    }
+
+    let passphrase = Zeroizing::new(
+        rpassword::prompt_password("Enter SLIP-0039 passphrase: ")
+            .context("failed to read passphrase from prompt")?,
+    );
+    if confirm {
+        let confirm = Zeroizing::new(
+            rpassword::prompt_password("Confirm SLIP-0039 passphrase: ")
+                .context("failed to read passphrase from prompt")?,
+        );
+        ensure!(*passphrase == *confirm, "entered passphrases did not match");
+    }
+    Ok(passphrase)
+}
+
+/// Read SLIP-0039 share mnemonics (one per line) from `path` ("-" for stdin),
+/// or interactively if no path was given.
+fn read_mnemonics(path: Option<&String>) -> Result<Vec<Share>, Error> {
+    let input = Zeroizing::new(match path.map(String::as_str) {
+        Some("-") => io::read_to_string(io::stdin()).context("failed to read shares from stdin")?,
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("failed to read shares file '{}'", path))?,
+        None => read_multiline("Enter SLIP-0039 shares (one per line)")?,
+    });
+
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(idx, mnemonic)| {
+            Share::from_mnemonic(mnemonic)
+                .with_context(|| format!("failed to parse SLIP-0039 share {}", idx + 1))
+        })
+        .collect()
+}
+
+// paperback-cli convert from-slip39 [--passphrase] [--sealed] [--title <TITLE>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -n <QUORUM SIZE> -k <SHARDS> [SHARES]
+fn from_slip39_cli() -> Command {
+    save_backup_args(Command::new("from-slip39")
+        .about(r#"Create a paperback backup of a secret stored as SLIP-0039 (Trezor "Shamir Backup") shares. The master secret is recovered from the shares and backed up like any other secret data. The SLIP-0039 shares stay valid, so destroy them if they should no longer be usable."#)
+        .arg(passphrase_arg())
+        .arg(Arg::new("sealed")
+            .long("sealed")
+            .help("Create a sealed backup, which cannot be expanded (have new shards be created) after creation.")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("title")
+            .long("title")
+            .value_name("TITLE")
+            .help("Human-readable title for the backup (see backup --title).")
+            .action(ArgAction::Set))
+        .arg(Arg::new("quorum-size")
+            .short('n')
+            .long("quorum-size")
+            .value_name("QUORUM SIZE")
+            .help("Number of shards required to recover the document (must not be larger than --shards).")
+            .action(ArgAction::Set)
+            .required(true))
+        .arg(Arg::new("shards")
+            .short('k')
+            .long("shards")
+            .value_name("NUM SHARDS")
+            .help("Number of shards to create (must not be smaller than --quorum-size).")
+            .action(ArgAction::Set)
+            .required(true))
+        .arg(Arg::new("with-manifest")
+            .long("with-manifest")
+            .help("Also create a manifest document for the new backup (see backup --with-manifest).")
+            .action(ArgAction::SetTrue)))
+        .arg(Arg::new("SHARES")
+            .help(r#"Path to a file containing the SLIP-0039 share mnemonics, one per line ("-" to read from stdin). If not given, the shares are requested interactively."#)
+            .action(ArgAction::Set)
+            .allow_hyphen_values(true)
+            .index(1))
+}
+
+fn from_slip39(matches: &ArgMatches) -> Result<Vec<PathBuf>, Error> {
+    let quorum_size: u32 = matches
+        .get_one::<String>("quorum-size")
+        .context("required --quorum-size argument not provided")?
+        .parse()
+        .context("--quorum-size argument was not an unsigned integer")?;
+    let num_shards: u32 = matches
+        .get_one::<String>("shards")
+        .context("required --shards argument not provided")?
+        .parse()
+        .context("--shards argument was not an unsigned integer")?;
+    let max_quorum_size = get_max_quorum_size(matches)?;
+    check_backup_policy(quorum_size, num_shards, max_quorum_size)?;
+    let pdf_options = PdfOptions {
+        layout: get_layout(matches)?,
+        qr_density: get_qr_density(matches)?,
+        ..get_key_shard_pdf_options(matches)?
+    };
+    let mut output = Output::from_matches(matches)?
+        .with_export_text(matches.get_one::<String>("export-text"))
+        .with_format(output::get_output_format(matches)?);
+    let mut registry = Registry::from_matches(matches)?;
+
+    let shares = read_mnemonics(matches.get_one::<String>("SHARES"))?;
+    let passphrase = read_passphrase(matches, false)?;
+    let master_secret = slip39::combine_shares(&shares, passphrase.as_bytes())
+        .context("recovering secret from SLIP-0039 shares")?;
+    println!(
+        "Recovered {}-byte master secret from SLIP-0039 shares.",
+        master_secret.len()
+    );
+
+    let mut builder = Backup::builder()
+        .quorum_size(quorum_size)
+        .max_quorum_size(max_quorum_size)
+        .sealed(matches.get_flag("sealed"));
+    if let Some(title) = matches.get_one::<String>("title") {
+        builder = builder.title(title);
+    }
+    let backup = builder.build(&master_secret)?;
+
+    save_backup(
+        matches,
+        &backup,
+        num_shards,
+        None,
+        &pdf_options,
+        &mut output,
+        registry.as_mut(),
+    )?;
+    Ok(output.into_created())
+}
+
+// paperback-cli convert to-slip39 [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--select <NAME>] [--passphrase] [--group-threshold <GROUPS>] --group <THRESHOLD/COUNT>...
+fn to_slip39_cli() -> Command {
+    recovery_source_args(Command::new("to-slip39")
+        .about(r#"Recover a paperback backup and split its secret data into SLIP-0039 (Trezor "Shamir Backup") shares, which are printed to stdout. The secret data must be an even number of bytes, and at least 16 bytes long (such as a wallet master secret)."#))
+        .arg(select_arg())
+        .arg(passphrase_arg())
+        .arg(Arg::new("group")
+            .long("group")
+            .value_name("THRESHOLD/COUNT")
+            .help(r#"Create a group of COUNT shares, THRESHOLD of which are required to recover the group. Can be given multiple times to create several groups (at most 16), such as "--group 1/1 --group 2/3"."#)
+            .action(ArgAction::Append)
+            .required(true))
+        .arg(Arg::new("group-threshold")
+            .long("group-threshold")
+            .value_name("GROUPS")
+            .help("Number of groups required to recover the secret.")
+            .default_value("1")
+            .action(ArgAction::Set))
+}
+
+fn parse_group(group: &str) -> Result<GroupPolicy, Error> {
+    let (threshold, count) = group
+        .split_once('/')
+        .with_context(|| format!("--group '{}' must be THRESHOLD/COUNT", group))?;
+    Ok(GroupPolicy::new(
+        threshold
+            .parse()
+            .with_context(|| format!("--group '{}' threshold is not a valid number", group))?,
+        count
+            .parse()
+            .with_context(|| format!("--group '{}' count is not a valid number", group))?,
+    ))
+}
+
+fn to_slip39(matches: &ArgMatches) -> Result<(), Error> {
+    let groups = matches
+        .get_many::<String>("group")
+        .context("required --group argument not provided")?
+        .map(|group| parse_group(group))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let group_threshold: u8 = matches
+        .get_one::<String>("group-threshold")
+        .context("required --group-threshold argument not provided")?
+        .parse()
+        .context("--group-threshold argument was not a valid number")?;
+
+    let quorum = read_recovery_quorum(matches)?;
+    let secret = recover_selected(matches, &quorum)?;
+    let passphrase = read_passphrase(matches, true)?;
+    let shares = slip39::split_secret(group_threshold, &groups, &secret, passphrase.as_bytes())
+        .context("splitting secret data into SLIP-0039 shares")?;
+
+    for (idx, (group, shares)) in groups.iter().zip(shares).enumerate() {
+        println!(
+            "Group {} ({} of {} shares required):",
+            idx + 1,
+            group.threshold,
+            group.count
+        );
+        for (idx, share) in shares.iter().enumerate() {
+            println!("  Share {}: {}", idx + 1, share.to_mnemonic().as_str());
+        }
+    }
+    if groups.len() > 1 {
+        println!(
+            "{} of {} groups are required to recover the secret.",
+            group_threshold,
+            groups.len()
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn submatch(app: &mut Command, matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("from-slip39", sub_matches)) => from_slip39(sub_matches).map(print_created),
+        Some(("to-slip39", sub_matches)) => to_slip39(sub_matches),
+        Some((subcommand, _)) => {
+            // We should never end up here.
+            app.print_help()?;
+            Err(anyhow!("unknown subcommand 'convert {}'", subcommand))
+        }
+        None => {
+            app.print_help()?;
+            Err(anyhow!("no 'convert' subcommand specified"))
+        }
+    }
+}
+
+pub(crate) fn subcommands() -> Command {
+    Command::new("convert")
+        .about(r#"Convert secrets between paperback backups and other secret sharing schemes."#)
+        // paperback-cli convert from-slip39 [--passphrase] [--sealed] [--title <TITLE>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -n <QUORUM SIZE> -k <SHARDS> [SHARES]
+        .subcommand(from_slip39_cli())
+        // paperback-cli convert to-slip39 [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--select <NAME>] [--passphrase] [--group-threshold <GROUPS>] --group <THRESHOLD/COUNT>...
+        .subcommand(to_slip39_cli())
+}
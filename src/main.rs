@@ -16,248 +16,2187 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+#[cfg(feature = "camera")]
+mod camera;
+mod challenge;
+mod collect;
+mod convert;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod output;
 mod raw;
+mod recipe;
+mod registry;
+#[cfg(feature = "scan")]
+mod scan;
+mod selftest;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "wizard")]
+mod wizard;
 
 use std::{
-    error::Error as StdError,
-    fs::File,
+    env, fmt, fs,
+    fs::{File, OpenOptions},
     io,
-    io::{prelude::*, BufReader, BufWriter},
+    io::{prelude::*, BufReader},
+    mem,
+    path::{Component, Path, PathBuf},
+    process::{self, ExitCode, Stdio},
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{anyhow, bail, ensure, Context, Error};
 use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use indicatif::{ProgressBar, ProgressStyle};
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
+use zeroize::Zeroizing;
 
 extern crate paperback_core;
-use paperback_core::latest as paperback;
+use paperback_core::{any::AnyMainDocument, latest as paperback};
 
 use paperback::{
-    pdf::qr, wire, Backup, EncryptedKeyShard, FromWire, KeyShard, KeyShardCodewords, MainDocument,
-    NewShardKind, ToPdf, UntrustedQuorum,
+    codewords, format_date,
+    pdf::qr::{self, DocumentQrCodes},
+    wire::{armor, ocr::reconstruct_text_fallback},
+    ActivityRecord, AgeIdentity, AgeRecipient, Backup, Bip32, ChallengeChecksum, ChecksumAlgorithm,
+    CodewordLanguage, CodewordPlacement, DocumentId, EncryptedBackupSession, EncryptedKeyShard,
+    FromPdf, FromWire, InconsistentQuorumError, InconsistentQuorumKind, InstructionsDocument,
+    KeyShard, KeyShardCodewords, MainDocument, MainDocumentLayout, Manifest, NamedPayload,
+    NewShardKind, PdfLanguage, PdfOptions, QrDensity, QrEncoding, QrErrorCorrection, Quorum,
+    SeedPhrase, ShardId, ShardPolicy, ShardProvenance, TextFallback, ToPdf, ToWire,
+    UntrustedQuorum,
 };
 
-// paperback-cli backup [--sealed] -n <QUORUM SIZE> -k <SHARDS> INPUT
+use output::Output;
+use recipe::Recipe;
+use registry::Registry;
+
+/// `--codeword-language` for commands which print key shards as PDFs. The
+/// fonts embedded in the PDFs only contain Latin glyphs, so the CJK wordlists
+/// are rejected up-front (rather than after the main document has been
+/// written).
+pub(crate) fn codeword_language_arg() -> Arg {
+    Arg::new("codeword-language")
+        .long("codeword-language")
+        .value_name("LANGUAGE")
+        .help(r#"Language of the BIP-39 wordlist used for new key shard codewords ("en", "es", "fr", or "it"). The language is auto-detected when codewords are entered."#)
+        .value_parser(["en", "es", "fr", "it"])
+        .default_value("en")
+        .action(ArgAction::Set)
+}
+
+/// `--codeword-language` for commands which print key shards as text, which
+/// can use any of the BIP-39 wordlists.
+pub(crate) fn raw_codeword_language_arg() -> Arg {
+    Arg::new("codeword-language")
+        .long("codeword-language")
+        .value_name("LANGUAGE")
+        .help(r#"Language of the BIP-39 wordlist used for new key shard codewords ("en", "es", "fr", "it", "ja", "ko", "zh-hans", or "zh-hant"). The language is auto-detected when codewords are entered."#)
+        .default_value("en")
+        .action(ArgAction::Set)
+}
+
+pub(crate) fn get_codeword_language(matches: &ArgMatches) -> Result<CodewordLanguage, Error> {
+    let language_code = matches
+        .get_one::<String>("codeword-language")
+        .context("required --codeword-language argument not provided")?;
+    CodewordLanguage::from_language_code(language_code)
+        .with_context(|| format!("unsupported --codeword-language '{}'", language_code))
+}
+
+pub(crate) fn input_prompt_arg() -> Arg {
+    Arg::new("input-prompt")
+        .long("input-prompt")
+        .help("Interactively prompt for the secret data to backup (without echoing it to the terminal), rather than reading it from a file.")
+        .action(ArgAction::SetTrue)
+        .conflicts_with_all(["input-env", "INPUT"])
+}
+
+pub(crate) fn input_env_arg() -> Arg {
+    Arg::new("input-env")
+        .long("input-env")
+        .value_name("VAR")
+        .help("Read the secret data to backup from the given environment variable, rather than reading it from a file.")
+        .action(ArgAction::Set)
+        .conflicts_with("INPUT")
+}
+
+/// Read the secret data to backup from the source selected by
+/// `--input-prompt`, `--input-env` or the `INPUT` argument.
+pub(crate) fn read_secret_input(matches: &ArgMatches) -> Result<Zeroizing<Vec<u8>>, Error> {
+    if matches.get_flag("input-prompt") {
+        let secret = Zeroizing::new(
+            rpassword::prompt_password("Enter secret data: ")
+                .context("failed to read secret data from prompt")?,
+        );
+        let confirm = Zeroizing::new(
+            rpassword::prompt_password("Confirm secret data: ")
+                .context("failed to read secret data from prompt")?,
+        );
+        ensure!(*secret == *confirm, "entered secret data did not match");
+        ensure!(!secret.is_empty(), "no secret data entered");
+        return Ok(Zeroizing::new(secret.as_bytes().to_vec()));
+    }
+
+    if let Some(var) = matches.get_one::<String>("input-env") {
+        let secret = Zeroizing::new(env::var(var).with_context(|| {
+            format!(
+                "failed to read secret data from environment variable '{}'",
+                var
+            )
+        })?);
+        return Ok(Zeroizing::new(secret.as_bytes().to_vec()));
+    }
+
+    let input_path = matches
+        .get_one::<String>("INPUT")
+        .context("required INPUT argument not provided")?;
+
+    let (mut stdin_reader, mut file_reader);
+    let input: &mut dyn Read = if input_path == "-" {
+        stdin_reader = io::stdin();
+        &mut stdin_reader
+    } else {
+        file_reader = File::open(input_path)
+            .with_context(|| format!("failed to open secret data file '{}'", input_path))?;
+        &mut file_reader
+    };
+    let mut buffer_input = BufReader::new(input);
+
+    let mut secret = Zeroizing::new(Vec::new());
+    buffer_input
+        .read_to_end(&mut secret)
+        .with_context(|| format!("failed to read secret data from '{}'", input_path))?;
+    Ok(secret)
+}
+
+fn hardened_codewords_arg() -> Arg {
+    Arg::new("hardened-codewords")
+        .long("hardened-codewords")
+        .help("Use 12 codewords (rather than 24) for new key shards. The key shard key is derived from the codewords using Argon2id to make guessing the codewords expensive, so decrypting each key shard takes a few seconds.")
+        .action(ArgAction::SetTrue)
+}
+
+/// Encrypt `shard` with codewords from the given `language`, using hardened
+/// codewords if `hardened` is set.
+fn encrypt_key_shard(
+    shard: &KeyShard,
+    language: CodewordLanguage,
+    hardened: bool,
+) -> Result<(EncryptedKeyShard, KeyShardCodewords), Error> {
+    Ok(match hardened {
+        true => shard.encrypt_hardened(language),
+        false => shard.encrypt_with_language(language),
+    }?)
+}
+
+fn qr_encoding_arg() -> Arg {
+    Arg::new("qr-encoding")
+        .long("qr-encoding")
+        .value_name("ENCODING")
+        .help(r#"Encoding used for data stored in QR codes. "base10" is the densest and most widely supported, "base45" and "alphanumeric" produce shorter text for manual entry, and "kanji" packs data into Shift-JIS kanji. The encoding is auto-detected when codes are entered."#)
+        .value_parser(["base10", "base45", "alphanumeric", "kanji"])
+        .default_value("base10")
+        .action(ArgAction::Set)
+}
+
+fn qr_error_correction_arg() -> Arg {
+    Arg::new("qr-error-correction")
+        .long("qr-error-correction")
+        .value_name("LEVEL")
+        .help(r#"Error correction level of generated QR codes ("L", "M", "Q", or "H"). Higher levels make QR codes more resilient to damage, but store less data in each QR code so larger secrets need more QR codes."#)
+        .value_parser(["L", "M", "Q", "H"])
+        .default_value("M")
+        .action(ArgAction::Set)
+}
+
+fn text_fallback_arg() -> Arg {
+    Arg::new("text-fallback")
+        .long("text-fallback")
+        .value_name("ENCODING")
+        .help(r#"Encoding used for the text printed next to QR codes. "plain" is the most compact, while "ecc" adds error correction to every line so that a misread character per line can be corrected (useful when using OCR). The encoding is auto-detected when the text is entered."#)
+        .value_parser(["plain", "ecc"])
+        .default_value("plain")
+        .action(ArgAction::Set)
+}
+
+fn pdf_language_arg() -> Arg {
+    Arg::new("pdf-language")
+        .long("pdf-language")
+        .value_name("LANGUAGE")
+        .help(r#"Language of the explanatory text printed on generated documents ("en", "de", "fr", or "es"). This has no effect on the data stored in the documents."#)
+        .value_parser(["en", "de", "fr", "es"])
+        .default_value("en")
+        .action(ArgAction::Set)
+}
+
+fn get_pdf_options(matches: &ArgMatches) -> Result<PdfOptions, Error> {
+    let qr_encoding = match matches
+        .get_one::<String>("qr-encoding")
+        .context("required --qr-encoding argument not provided")?
+        .as_str()
+    {
+        "base10" => QrEncoding::Base10,
+        "base45" => QrEncoding::Base45,
+        "alphanumeric" => QrEncoding::Alphanumeric,
+        "kanji" => QrEncoding::Kanji,
+        // We should never reach here.
+        encoding => bail!("unsupported --qr-encoding '{}'", encoding),
+    };
+    let qr_error_correction = match matches
+        .get_one::<String>("qr-error-correction")
+        .context("required --qr-error-correction argument not provided")?
+        .as_str()
+    {
+        "L" => QrErrorCorrection::Low,
+        "M" => QrErrorCorrection::Medium,
+        "Q" => QrErrorCorrection::Quartile,
+        "H" => QrErrorCorrection::High,
+        // We should never reach here.
+        level => bail!("unsupported --qr-error-correction '{}'", level),
+    };
+    let text_fallback = match matches
+        .get_one::<String>("text-fallback")
+        .context("required --text-fallback argument not provided")?
+        .as_str()
+    {
+        "plain" => TextFallback::Plain,
+        "ecc" => TextFallback::ErrorCorrecting,
+        // We should never reach here.
+        encoding => bail!("unsupported --text-fallback '{}'", encoding),
+    };
+    let language_code = matches
+        .get_one::<String>("pdf-language")
+        .context("required --pdf-language argument not provided")?;
+    let language = PdfLanguage::from_language_code(language_code)
+        .with_context(|| format!("unsupported --pdf-language '{}'", language_code))?;
+    Ok(PdfOptions {
+        qr_encoding,
+        qr_error_correction,
+        text_fallback,
+        language,
+        ..Default::default()
+    })
+}
+
+fn layout_arg() -> Arg {
+    Arg::new("layout")
+        .long("layout")
+        .value_name("LAYOUT")
+        .help(r#"Layout of the QR codes in the main document. "portrait" fits all QR codes on one portrait A4 page, "landscape" fits slightly larger QR codes on one landscape A4 page, and "booklet" prints each QR code as large as possible on its own page (which makes them easiest to scan)."#)
+        .value_parser(["portrait", "landscape", "booklet"])
+        .default_value("portrait")
+        .action(ArgAction::Set)
+}
+
+fn qr_density_arg() -> Arg {
+    Arg::new("qr-density")
+        .long("qr-density")
+        .value_name("DENSITY")
+        .help(r#"How densely data is packed into the QR codes of the main document ("low", "medium", or "high"). Lower densities need more QR codes, but each QR code has larger modules, which makes them easier to scan when printed by a low-quality printer."#)
+        .value_parser(["low", "medium", "high"])
+        .default_value("high")
+        .action(ArgAction::Set)
+}
+
+pub(crate) fn get_qr_density(matches: &ArgMatches) -> Result<QrDensity, Error> {
+    let density = match matches
+        .get_one::<String>("qr-density")
+        .context("required --qr-density argument not provided")?
+        .as_str()
+    {
+        "low" => QrDensity::Low,
+        "medium" => QrDensity::Medium,
+        "high" => QrDensity::High,
+        // We should never reach here.
+        density => bail!("unsupported --qr-density '{}'", density),
+    };
+    Ok(density)
+}
+
+pub(crate) fn get_layout(matches: &ArgMatches) -> Result<MainDocumentLayout, Error> {
+    let layout = match matches
+        .get_one::<String>("layout")
+        .context("required --layout argument not provided")?
+        .as_str()
+    {
+        "portrait" => MainDocumentLayout::Portrait,
+        "landscape" => MainDocumentLayout::Landscape,
+        "booklet" => MainDocumentLayout::Booklet,
+        // We should never reach here.
+        layout => bail!("unsupported --layout '{}'", layout),
+    };
+    Ok(layout)
+}
+
+fn shards_per_page_arg() -> Arg {
+    Arg::new("shards-per-page")
+        .long("shards-per-page")
+        .value_name("NUM SHARDS")
+        .help(r#"Number of key shards to print on each page. With "2", key shards are laid out side-by-side on landscape A4 pages with a cut line between them, and each page is saved as a single PDF."#)
+        .value_parser(["1", "2"])
+        .default_value("1")
+        .action(ArgAction::Set)
+}
+
+fn codeword_qr_arg() -> Arg {
+    Arg::new("codeword-qr")
+        .long("codeword-qr")
+        .help("Also print the codewords of each key shard as a QR code on the detachable codeword stub, so they can be scanned instead of typed. A QR code is much easier to capture with a camera than text, so take extra care not to photograph the stub.")
+        .action(ArgAction::SetTrue)
+}
+
+fn codeword_placement_arg() -> Arg {
+    Arg::new("codeword-placement")
+        .long("codeword-placement")
+        .value_name("PLACEMENT")
+        .help(r#"Where to print the codewords of each key shard. "stub" prints them only on the detachable stub, "both" also prints a copy on the key shard itself (so one side can be cut off and kept while the other is still complete), and "shard" prints them only on the key shard (without a stub)."#)
+        .value_parser(["stub", "both", "shard"])
+        .default_value("stub")
+        .action(ArgAction::Set)
+}
+
+fn get_codeword_placement(matches: &ArgMatches) -> Result<CodewordPlacement, Error> {
+    let placement = match matches
+        .get_one::<String>("codeword-placement")
+        .context("required --codeword-placement argument not provided")?
+        .as_str()
+    {
+        "stub" => CodewordPlacement::Stub,
+        "both" => CodewordPlacement::Both,
+        "shard" => CodewordPlacement::Shard,
+        // We should never reach here.
+        placement => bail!("unsupported --codeword-placement '{}'", placement),
+    };
+    Ok(placement)
+}
+
+fn recovery_url_arg() -> Arg {
+    Arg::new("recovery-url")
+        .long("recovery-url")
+        .value_name("URL")
+        .help("Print a (short) URL linking to instructions for recovering the backup on each key shard, both as plain text and as a small QR code, so that key shard holders can find up-to-date recovery tooling many years from now.")
+        .action(ArgAction::Set)
+}
+
+fn include_instructions_arg() -> Arg {
+    Arg::new("include-instructions")
+        .long("include-instructions")
+        .help("Add a page of instructions for the holder of each key shard, explaining what a key shard is, when to hand it over, and how to avoid being scammed out of it. Print double-sided so that the instructions end up on the back of each key shard.")
+        .action(ArgAction::SetTrue)
+}
+
+pub(crate) fn get_key_shard_pdf_options(matches: &ArgMatches) -> Result<PdfOptions, Error> {
+    let shards_per_page = matches
+        .get_one::<String>("shards-per-page")
+        .context("required --shards-per-page argument not provided")?
+        .parse()
+        .context("--shards-per-page argument was not an unsigned integer")?;
+    Ok(PdfOptions {
+        shards_per_page,
+        codeword_qr: matches.get_flag("codeword-qr"),
+        codeword_placement: get_codeword_placement(matches)?,
+        recovery_url: matches.get_one::<String>("recovery-url").cloned(),
+        include_instructions: matches.get_flag("include-instructions"),
+        ..get_pdf_options(matches)?
+    })
+}
+
+fn export_text_arg() -> Arg {
+    Arg::new("export-text")
+        .long("export-text")
+        .value_name("DIR")
+        .help(r#"Also write the data of each generated document (along with its checksum) to a plain-text file in DIR. These files can be passed directly to "raw restore". Key shard codewords are not included."#)
+        .action(ArgAction::Set)
+}
+
+/// Write each of `codes` to `{name}-data-{index}.png` and
+/// `{name}-checksum-{index}.png` in the `--export-qr-png` directory (indices
+/// start at 1, matching the part numbers printed on the documents).
+fn export_qr_png(output: &mut Output, name: &str, codes: &DocumentQrCodes) -> Result<(), Error> {
+    for (kind, codes) in [("data", &codes.data), ("checksum", &codes.checksum)] {
+        for (idx, code) in codes.iter().enumerate() {
+            output.write_qr_png(&format!("{}-{}-{}.png", name, kind, idx + 1), code)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write `data` followed by `fields` to `{name}.txt` in the `--export-text`
+/// directory. The data must be on the first line, so that the file can be
+/// read by "raw restore".
+fn write_text_export(
+    output: &mut Output,
+    name: &str,
+    data: &str,
+    fields: &[(&str, String)],
+) -> Result<(), Error> {
+    let mut contents = format!("{}\n\n", data);
+    for (key, value) in fields {
+        contents.push_str(&format!("{}: {}\n", key, value));
+    }
+    output.write_text_export(&format!("{}.txt", name), &contents)
+}
+
+fn export_main_document_text(
+    output: &mut Output,
+    main_document: &MainDocument,
+) -> Result<(), Error> {
+    write_text_export(
+        output,
+        &format!("main_document-{}", main_document.id()),
+        &main_document.to_wire_multibase(multibase::Base::Base32Z),
+        &[
+            ("Document-ID", main_document.id().to_string()),
+            ("Checksum", main_document.checksum_string()),
+            ("Fingerprint", main_document.fingerprint_words().join(" ")),
+        ],
+    )
+}
+
+fn export_key_shard_text(
+    output: &mut Output,
+    document_id: &str,
+    shard_id: &str,
+    shard: &EncryptedKeyShard,
+) -> Result<(), Error> {
+    write_text_export(
+        output,
+        &format!("key_shard-{}-{}", document_id, shard_id),
+        &shard.to_wire_multibase(multibase::Base::Base32Z),
+        &[
+            ("Document-ID", document_id.to_string()),
+            ("Shard-ID", shard_id.to_string()),
+            ("Checksum", shard.checksum_string()),
+            ("Fingerprint", shard.fingerprint_words().join(" ")),
+        ],
+    )
+}
+
+/// A key shard to be saved by `save_key_shards`, optionally annotated with
+/// where it came from.
+type SavedKeyShard = (
+    DocumentId,
+    ShardId,
+    ChallengeChecksum,
+    (EncryptedKeyShard, KeyShardCodewords),
+    Option<ShardProvenance>,
+);
+
+fn save_key_shards(
+    shards: Vec<SavedKeyShard>,
+    pdf_options: &PdfOptions,
+    output: &mut Output,
+) -> Result<Vec<(DocumentId, ShardId, ChallengeChecksum)>, Error> {
+    if output.exports_text() {
+        for (document_id, shard_id, _, (shard, _), _) in &shards {
+            export_key_shard_text(output, document_id, shard_id, shard)?;
+        }
+    }
+    if output.exports_qr_png() {
+        for (document_id, shard_id, _, (shard, codewords), _) in &shards {
+            export_qr_png(
+                output,
+                &format!("key_shard-{}-{}", document_id, shard_id),
+                &qr::key_shard_qr_codes(shard, codewords, pdf_options)?,
+            )?;
+        }
+    }
+    for page in shards.chunks(pdf_options.shards_per_page) {
+        let document_id = &page[0].0;
+        let shard_ids = page
+            .iter()
+            .map(|(_, id, _, _, _)| id.as_str())
+            .collect::<Vec<_>>();
+        let name = match &shard_ids[..] {
+            [shard_id] => format!("key_shard-{}-{}", document_id, shard_id),
+            _ => format!("key_shards-{}-{}", document_id, shard_ids.join("-")),
+        };
+        match page
+            .iter()
+            .map(|(_, _, _, _, provenance)| provenance.clone())
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(provenances) => output.write_document(
+                &name,
+                &page
+                    .iter()
+                    .zip(provenances)
+                    .map(|((_, _, _, (shard, codewords), _), provenance)| {
+                        (shard.clone(), codewords.clone(), provenance)
+                    })
+                    .collect::<Vec<_>>()[..],
+                pdf_options,
+            )?,
+            None => output.write_document(
+                &name,
+                &page
+                    .iter()
+                    .map(|(_, _, _, shard, _)| shard.clone())
+                    .collect::<Vec<_>>()[..],
+                pdf_options,
+            )?,
+        }
+    }
+    Ok(shards
+        .into_iter()
+        .map(|(document_id, shard_id, checksum, _, _)| (document_id, shard_id, checksum))
+        .collect())
+}
+
+fn activity_record_arg() -> Arg {
+    Arg::new("activity-record")
+        .long("activity-record")
+        .help("Also create an activity record stating which key shards were minted, when, and by which quorum. The record is signed with the backup's identity key, so key shard holders can use it to audit how the backup has been expanded (see info --verify-with). Only possible for unsealed backups.")
+        .action(ArgAction::SetTrue)
+}
+
+fn save_activity_record(
+    record: &ActivityRecord,
+    pdf_options: &PdfOptions,
+    output: &mut Output,
+) -> Result<(), Error> {
+    let name = format!(
+        "activity_record-{}-{}",
+        record.document_id(),
+        record.shard_ids().first().map_or("none", ShardId::as_str)
+    );
+    let pdf = record.to_pdf_with_options(pdf_options)?;
+    output.write(&format!("{}.pdf", name), |file| Ok(pdf.save(file)?))?;
+    if output.exports_text() {
+        write_text_export(
+            output,
+            &name,
+            &record.to_wire_multibase(multibase::Base::Base32Z),
+            &[
+                ("Document-ID", record.document_id().to_string()),
+                ("Minted-Shard-IDs", record.shard_ids().join(" ")),
+                ("Quorum-Shard-IDs", record.quorum().join(" ")),
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+// paperback-cli backup [--sealed|--no-recreate-shards] [--commit-shards] [--title <TITLE>] [--derive-xpub [<PATH>]] [--expires-after <DAYS>] [--recipient <RECIPIENT>] [--format <FORMAT>] [--save-session <PATH>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -n <QUORUM SIZE> -k <SHARDS> [--input-prompt|--input-env <VAR>|--payload <NAME=PATH>...|--input-many <FILE>...|INPUT]
+// paperback-cli backup --resume <PATH> [--save-session <PATH>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--register [--label <LABEL>]...] -k <SHARDS>
+// paperback-cli backup --recipe <PATH> [--commit-shards] [--title <TITLE>] [--derive-xpub [<PATH>]] [--expires-after <DAYS>] [--recipient <RECIPIENT>] [--format <FORMAT>] [--save-session <PATH>] [--with-manifest] [--with-recovery-instructions] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register]
 fn backup_cli() -> Command {
-    Command::new("backup")
+    save_backup_args(Command::new("backup")
             .about(r#"Create a paperback backup."#)
             .arg(Arg::new("sealed")
                 .long("sealed")
                 .help("Create a sealed backup, which cannot be expanded (have new shards be created) after creation.")
                 .action(ArgAction::SetTrue))
+            .arg(Arg::new("no-recreate-shards")
+                .long("no-recreate-shards")
+                .help("Forbid re-creating existing key shards (with recreate-shards) after creation, so that a key shard reported as lost can never be re-issued. New key shards can still be created with expand-shards.")
+                .conflicts_with("sealed")
+                .action(ArgAction::SetTrue))
+            .arg(Arg::new("commit-shards")
+                .long("commit-shards")
+                .help("Commit to the key shards created by this command in the main document. Key shards which are not part of the original set (created later with expand-shards or recreate-shards, or forged by someone who saw the backup's private key before it was sealed) are then flagged when recovering the backup and by info --verify-with.")
+                .action(ArgAction::SetTrue))
             .arg(Arg::new("quorum-size")
                 .short('n')
                 .long("quorum-size")
                 .value_name("QUORUM SIZE")
                 .help("Number of shards required to recover the document (must not be larger than --shards).")
                 .action(ArgAction::Set)
-                .required(true))
+                .required_unless_present_any(["resume", "recipe"]))
             .arg(Arg::new("shards")
                 .short('k')
                 .long("shards")
                 .value_name("NUM SHARDS")
                 .help("Number of shards to create (must not be smaller than --quorum-size).")
                 .action(ArgAction::Set)
-                .required(true))
+                .required_unless_present("recipe"))
+            .arg(Arg::new("title")
+                .long("title")
+                .value_name("TITLE")
+                .help(r#"Human-readable title for the backup (such as "2024 estate documents"), which is printed on the main document and shown by info. The title is not secret, but it is authenticated so it cannot be modified without detection."#)
+                .action(ArgAction::Set))
+            .arg(Arg::new("derive-xpub")
+                .long("derive-xpub")
+                .value_name("PATH")
+                .help(format!(r#"Treat the secret data as a cryptocurrency wallet seed (a BIP-39 phrase with --format bip39, or a raw 16 to 64 byte BIP-32 seed), and print its BIP-32 master key fingerprint and the extended public key ("xpub") at the derivation PATH (defaults to "{}") on the main document, so that the wallet can be identified without recovering the backup. Only this public information is printed, the secret data is encrypted as usual. BIP-39 passphrases are not supported. Cannot be combined with --payload."#, Bip32::DEFAULT_PATH))
+                .num_args(0..=1)
+                .default_missing_value(Bip32::DEFAULT_PATH)
+                .action(ArgAction::Set)
+                .conflicts_with("payload"))
+            .arg(Arg::new("expires-after")
+                .long("expires-after")
+                .value_name("DAYS")
+                .help("Mark the backup as expiring after the given number of days. Paperback will warn you if you try to recover an expired backup, which is useful if you regularly create new backups to replace old ones.")
+                .action(ArgAction::Set))
+            .arg(Arg::new("recipient")
+                .long("recipient")
+                .value_name("RECIPIENT")
+                .help("Encrypt the secret data to the given age recipient (an age1... public key) before backing it up. The matching age identity is then required (in addition to a quorum of key shards) to recover the backup. Cannot be combined with --payload.")
+                .action(ArgAction::Set)
+                .conflicts_with("payload"))
+            .arg(Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help(r#"Format of the secret data ("raw" or "bip39"). With "bip39", the secret data must be a BIP-39 phrase (such as a wallet seed), which is validated (including its checksum word) and stored in a compact binary form. The phrase is printed again as text when the backup is recovered. Cannot be combined with --payload."#)
+                .value_parser(["raw", "bip39"])
+                .default_value("raw")
+                .action(ArgAction::Set)
+                .conflicts_with("payload"))
+            .arg(Arg::new("checksum-algorithm")
+                .long("checksum-algorithm")
+                .value_name("ALGORITHM")
+                .help(r#"Digest algorithm used for the main document checksum, which the document id is derived from ("blake2b-256", "sha2-256", or "blake3"). Backups using any of these algorithms can be recovered."#)
+                .value_parser(["blake2b-256", "sha2-256", "blake3"])
+                .default_value("blake2b-256")
+                .action(ArgAction::Set))
+            .arg(Arg::new("save-session")
+                .long("save-session")
+                .value_name("SESSION PATH")
+                .help("Save an encrypted copy of the backup session to the given path, so that more shards can be created later with --resume. The session codewords are printed to stdout. The session file must be stored as carefully as the secret data itself. Not possible for sealed backups.")
+                .action(ArgAction::Set)
+                .conflicts_with("sealed"))
+            .arg(Arg::new("resume")
+                .long("resume")
+                .value_name("SESSION PATH")
+                .help("Resume a backup session saved with --save-session, creating more shards for the same main document.")
+                .action(ArgAction::Set)
+                .conflicts_with_all(["sealed", "no-recreate-shards", "commit-shards", "title", "derive-xpub", "expires-after", "recipient", "format", "checksum-algorithm", "quorum-size", "max-quorum-size", "INPUT", "input-prompt", "input-env", "payload", "input-many"]))
+            .arg(Arg::new("with-manifest")
+                .long("with-manifest")
+                .help("Also create a manifest document listing the document id, quorum policy and the ids of the key shards created, with space to record who each key shard was given to. The manifest contains no secret information.")
+                .action(ArgAction::SetTrue)))
+            .arg(recipe::recipe_arg())
+            .arg(input_prompt_arg())
+            .arg(input_env_arg())
+            .arg(Arg::new("payload")
+                .long("payload")
+                .value_name("NAME=PATH")
+                .help("Add the contents of the file at PATH to the backup as a payload called NAME. Can be given multiple times to back up several secrets with the same key shards; each payload can be recovered individually with recover --select.")
+                .action(ArgAction::Append)
+                .conflicts_with_all(["INPUT", "input-prompt", "input-env"]))
+            .arg(Arg::new("input-many")
+                .long("input-many")
+                .value_name("FILE")
+                .help("Back up several files at once, each stored as a payload named after the file (along with its permissions), rather than having to bundle them into a single file first. File names must be unique. All of the files can be restored with recover --extract-dir, or individually with recover --select.")
+                .num_args(1..)
+                .action(ArgAction::Append)
+                .conflicts_with_all(["INPUT", "input-prompt", "input-env", "payload", "recipe", "derive-xpub", "recipient", "format"]))
             .arg(Arg::new("INPUT")
                 .help(r#"Path to file containing secret data to backup ("-" to read from stdin)."#)
                 .action(ArgAction::Set)
                 .allow_hyphen_values(true)
-                .required(true)
+                .required_unless_present_any(["resume", "recipe", "input-prompt", "input-env", "payload", "input-many"])
                 .index(1))
 }
 
-fn backup(matches: &ArgMatches) -> Result<(), Error> {
-    let sealed = matches.get_flag("sealed");
-    let quorum_size: u32 = matches
-        .get_one::<String>("quorum-size")
-        .context("required --quorum-size argument not provided")?
-        .parse()
-        .context("--quorum-size argument was not an unsigned integer")?;
-    let num_shards: u32 = matches
-        .get_one::<String>("shards")
-        .context("required --quorum-size argument not provided")?
-        .parse()
-        .context("--shards argument was not an unsigned integer")?;
-    let input_path = matches
-        .get_one::<String>("INPUT")
-        .context("required INPUT argument not provided")?;
+pub(crate) fn max_quorum_size_arg() -> Arg {
+    Arg::new("max-quorum-size")
+        .long("max-quorum-size")
+        .value_name("QUORUM SIZE")
+        .help(format!("Largest quorum size to permit (defaults to {}). Creating, recovering and expanding backups with very large quorums can take a long time, and every key shard has to be printed and scanned separately.", Backup::MAX_QUORUM_SIZE))
+        .action(ArgAction::Set)
+}
 
-    let (mut stdin_reader, mut file_reader);
-    let input: &mut dyn Read = if input_path == "-" {
-        stdin_reader = io::stdin();
-        &mut stdin_reader
-    } else {
-        file_reader = File::open(input_path)
-            .with_context(|| format!("failed to open secret data file '{}'", input_path))?;
-        &mut file_reader
+pub(crate) fn get_max_quorum_size(matches: &ArgMatches) -> Result<u32, Error> {
+    matches
+        .get_one::<String>("max-quorum-size")
+        .map(|max| {
+            max.parse()
+                .context("--max-quorum-size argument was not an unsigned integer")
+        })
+        .unwrap_or(Ok(Backup::MAX_QUORUM_SIZE))
+}
+
+/// Quorum sizes above this are considered large enough to warn about how long
+/// creating the backup will take.
+const LARGE_QUORUM_SIZE: u32 = 32;
+
+/// Number of key shards generated to estimate how long generating all of the
+/// key shards will take.
+const ESTIMATE_SAMPLE_SHARDS: u32 = 4;
+
+fn print_backup_estimate(
+    quorum_size: u32,
+    num_shards: u32,
+    max_quorum_size: u32,
+) -> Result<(), Error> {
+    // How long generating key shards takes depends heavily on the machine, so
+    // time a few key shards of a throwaway backup with the same quorum size.
+    let sample = num_shards.clamp(1, ESTIMATE_SAMPLE_SHARDS);
+    let start = Instant::now();
+    Backup::builder()
+        .quorum_size(quorum_size)
+        .max_quorum_size(max_quorum_size)
+        .build([0; 32])?
+        .next_shards(sample as usize)?;
+    let estimate = start.elapsed() * num_shards / sample;
+
+    eprintln!("WARNING: a quorum size of {} is very large.", quorum_size);
+    eprintln!(
+        "  Generating {} key shards will take roughly {:.1}s.",
+        num_shards,
+        estimate.as_secs_f64()
+    );
+    eprintln!(
+        "  Each key shard has its own QR code ({} in total), and recovering the backup requires scanning {} of them (as well as the main document).",
+        num_shards, quorum_size
+    );
+    Ok(())
+}
+
+pub(crate) fn check_backup_policy(
+    quorum_size: u32,
+    num_shards: u32,
+    max_quorum_size: u32,
+) -> Result<(), Error> {
+    Backup::check_policy_with_limit(quorum_size, num_shards, max_quorum_size)
+        .context("invalid arguments")?;
+    if quorum_size == 1 {
+        eprintln!("WARNING: with a quorum size of 1, any single key shard can recover the backup.");
+    }
+    if quorum_size > LARGE_QUORUM_SIZE {
+        print_backup_estimate(quorum_size, num_shards, max_quorum_size)
+            .context("estimating backup creation time")?;
+    }
+    Ok(())
+}
+
+fn new_backup(
+    matches: &ArgMatches,
+    num_shards: u32,
+    recipe: Option<&Recipe>,
+) -> Result<Backup, Error> {
+    let (sealed, quorum_size) = match recipe {
+        Some(recipe) => (recipe.sealed, recipe.quorum_size),
+        None => (
+            matches.get_flag("sealed"),
+            matches
+                .get_one::<String>("quorum-size")
+                .context("required --quorum-size argument not provided")?
+                .parse()
+                .context("--quorum-size argument was not an unsigned integer")?,
+        ),
     };
-    let mut buffer_input = BufReader::new(input);
+    let max_quorum_size = get_max_quorum_size(matches)?;
+    check_backup_policy(quorum_size, num_shards, max_quorum_size)?;
+    let expires = matches
+        .get_one::<String>("expires-after")
+        .map(|days| -> Result<SystemTime, Error> {
+            let days: u64 = days
+                .parse()
+                .context("--expires-after argument was not an unsigned integer")?;
+            days.checked_mul(24 * 60 * 60)
+                .and_then(|secs| SystemTime::now().checked_add(Duration::from_secs(secs)))
+                .context("--expires-after argument is too large")
+        })
+        .transpose()?;
+    let checksum_algorithm = matches
+        .get_one::<String>("checksum-algorithm")
+        .context("required --checksum-algorithm argument not provided")?;
+    let checksum_algorithm = ChecksumAlgorithm::from_name(checksum_algorithm)
+        // We should never reach here.
+        .with_context(|| format!("unsupported --checksum-algorithm '{}'", checksum_algorithm))?;
 
-    let mut secret = Vec::new();
-    buffer_input
-        .read_to_end(&mut secret)
-        .with_context(|| format!("failed to read secret data from '{}'", input_path))?;
+    let shard_policy = match (sealed, matches.get_flag("no-recreate-shards")) {
+        (true, _) => ShardPolicy::Sealed,
+        (false, true) => ShardPolicy::NewOnly,
+        (false, false) => ShardPolicy::Any,
+    };
 
-    let backup = if sealed {
-        Backup::new_sealed(quorum_size, &secret)
-    } else {
-        Backup::new(quorum_size, &secret)
-    }?;
+    let mut builder = Backup::builder()
+        .quorum_size(quorum_size)
+        .max_quorum_size(max_quorum_size)
+        .shard_policy(shard_policy)
+        .checksum_algorithm(checksum_algorithm);
+    if matches.get_flag("commit-shards") {
+        builder = builder.commit_shards(num_shards);
+    }
+    if let Some(title) = matches.get_one::<String>("title") {
+        builder = builder.title(title);
+    }
+    if let Some(path) = matches.get_one::<String>("derive-xpub") {
+        builder = builder.derive_public_info(
+            Bip32::new(path).context("--derive-xpub argument is not a BIP-32 derivation path")?,
+        );
+    }
+    if let Some(expires) = expires {
+        builder = builder.expires(expires);
+    }
+
+    if let Some(payloads) = matches.get_many::<String>("payload") {
+        let payloads = payloads
+            .map(|payload| {
+                let (name, path) = payload
+                    .split_once('=')
+                    .with_context(|| format!("--payload '{}' must be NAME=PATH", payload))?;
+                let data = Zeroizing::new(
+                    fs::read(path)
+                        .with_context(|| format!("failed to read payload file '{}'", path))?,
+                );
+                Ok(NamedPayload::new(name, &*data)?)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        return Ok(builder.build_with_payloads(payloads)?);
+    }
+    if let Some(paths) = matches.get_many::<String>("input-many") {
+        let payloads = paths
+            .map(|path| read_file_payload(path))
+            .collect::<Result<Vec<_>, Error>>()?;
+        return Ok(builder.build_with_payloads(payloads)?);
+    }
+
+    let secret = match recipe {
+        Some(recipe) => Zeroizing::new(fs::read(&recipe.input).with_context(|| {
+            format!(
+                "failed to read secret data file '{}'",
+                recipe.input.display()
+            )
+        })?),
+        None => read_secret_input(matches)?,
+    };
+
+    if let Some(recipient) = matches.get_one::<String>("recipient") {
+        let recipient: AgeRecipient = recipient
+            .parse()
+            .map_err(|err| anyhow!("--recipient argument is not a valid age recipient: {}", err))?;
+        builder = builder.recipient(recipient);
+    }
+
+    match matches
+        .get_one::<String>("format")
+        .context("required --format argument not provided")?
+        .as_str()
+    {
+        "raw" => Ok(builder.build(&secret)?),
+        "bip39" => {
+            let phrase = std::str::from_utf8(&secret)
+                .context("--format bip39 secret data is not a valid UTF-8 string")?;
+            let seed_phrase = SeedPhrase::from_phrase(phrase)
+                .context("--format bip39 secret data is not a valid BIP-39 phrase")?;
+            println!(
+                "Backing up {}-word BIP-39 seed phrase.",
+                seed_phrase.num_words()
+            );
+            Ok(builder.build_seed_phrase(&seed_phrase)?)
+        }
+        // We should never reach here.
+        format => bail!("unsupported --format '{}'", format),
+    }
+}
+
+/// Read the file at `path` as a payload named after the file, recording its
+/// permissions (for `--input-many`).
+fn read_file_payload(path: &str) -> Result<NamedPayload, Error> {
+    let name = Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("--input-many path '{}' has no valid file name", path))?;
+    let data =
+        Zeroizing::new(fs::read(path).with_context(|| format!("failed to read file '{}'", path))?);
+    let payload = NamedPayload::new(name, &*data)?;
+    #[cfg(unix)]
+    let payload = {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata =
+            fs::metadata(path).with_context(|| format!("failed to stat file '{}'", path))?;
+        payload.with_mode(metadata.permissions().mode() & 0o7777)
+    };
+    Ok(payload)
+}
+
+fn resume_backup(session_path: &str) -> Result<Backup, Error> {
+    let session = fs::read_to_string(session_path)
+        .with_context(|| format!("failed to read backup session file '{}'", session_path))?;
+    let session: EncryptedBackupSession = parse_multibase(session.trim())
+        .with_context(|| format!("failed to parse backup session file '{}'", session_path))?;
+
+    let codewords = read_codewords("Enter backup session codewords")?;
+    let backup = Backup::resume_session(&session, codewords).context("resuming backup session")?;
+
+    println!(
+        "Resumed backup session for document {}.",
+        backup.main_document().id()
+    );
+    Ok(backup)
+}
+
+fn backup(matches: &ArgMatches) -> Result<Vec<PathBuf>, Error> {
+    let recipe = matches
+        .get_one::<String>("recipe")
+        .map(Recipe::from_file)
+        .transpose()?;
+    let num_shards: u32 = match recipe {
+        Some(ref recipe) => recipe.shards,
+        None => matches
+            .get_one::<String>("shards")
+            .context("required --shards argument not provided")?
+            .parse()
+            .context("--shards argument was not an unsigned integer")?,
+    };
+    let pdf_options = PdfOptions {
+        layout: get_layout(matches)?,
+        qr_density: get_qr_density(matches)?,
+        ..get_key_shard_pdf_options(matches)?
+    };
+    let mut output = Output::from_matches(matches)?
+        .with_export_text(matches.get_one::<String>("export-text"))
+        .with_export_qr_png(matches.get_one::<String>("export-qr-png"))?
+        .with_format(output::get_output_format(matches)?)
+        .with_output_dir(recipe.as_ref().and_then(|recipe| recipe.output_dir.clone()));
+    let mut registry = Registry::from_matches(matches)?;
+
+    let backup = match matches.get_one::<String>("resume") {
+        Some(session_path) => resume_backup(session_path)?,
+        None => new_backup(matches, num_shards, recipe.as_ref())?,
+    };
+
+    if let Some(session_path) = matches.get_one::<String>("save-session") {
+        let (session, codewords) = backup.seal_session().context("exporting backup session")?;
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // The session can be used to recover the secret data.
+            options.mode(0o600);
+        }
+        options
+            .open(session_path)
+            .and_then(|mut file| {
+                file.write_all(
+                    session
+                        .to_wire_multibase(multibase::Base::Base32Z)
+                        .as_bytes(),
+                )
+            })
+            .with_context(|| format!("failed to write backup session file '{}'", session_path))?;
+        println!("Backup session saved to '{}'.", session_path);
+        println!("Backup session codewords: {}", codewords.join(" "));
+    }
+
+    save_backup(
+        matches,
+        &backup,
+        num_shards,
+        recipe.as_ref().map(|recipe| &recipe.labels[..]),
+        &pdf_options,
+        &mut output,
+        registry.as_mut(),
+    )?;
+    Ok(output.into_created())
+}
+
+/// Add the arguments used by `save_backup` (other than `--with-manifest`) to
+/// `command`.
+pub(crate) fn save_backup_args(command: Command) -> Command {
+    command
+        .arg(codeword_language_arg())
+        .arg(hardened_codewords_arg())
+        .arg(qr_encoding_arg())
+        .arg(qr_error_correction_arg())
+        .arg(text_fallback_arg())
+        .arg(pdf_language_arg())
+        .arg(shards_per_page_arg())
+        .arg(codeword_qr_arg())
+        .arg(codeword_placement_arg())
+        .arg(recovery_url_arg())
+        .arg(include_instructions_arg())
+        .arg(layout_arg())
+        .arg(qr_density_arg())
+        .arg(export_text_arg())
+            .arg(output::export_qr_png_arg())
+        .arg(max_quorum_size_arg())
+        .arg(output::output_dir_arg())
+        .arg(output::output_format_arg())
+        .arg(output::force_arg())
+        .arg(registry::register_arg())
+        .arg(registry::label_arg())
+        .arg(Arg::new("with-recovery-instructions")
+            .long("with-recovery-instructions")
+            .help("Also create a recovery instructions document for the new backup, explaining to whoever needs to recover the backup (such as the executors of your estate) how to do so and who holds the key shards. It contains no secret information, so it can be stored with your will.")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("holder")
+            .long("holder")
+            .value_name("NAME")
+            .help("Name (and contact details) of someone who will hold a key shard, to be listed in the recovery instructions. Can be given multiple times. If no holders are given, blank lines are left to fill in by hand.")
+            .requires("with-recovery-instructions")
+            .action(ArgAction::Append))
+        .arg(Arg::new("also-raw")
+            .long("also-raw")
+            .help(r#"Also write each generated main document and key shard to a plain-text file (next to the PDF files) in the format printed by "raw backup". Unlike --export-text, the key shard files include the key shard codewords, so they must be handled just as carefully as the key shard PDFs."#)
+            .action(ArgAction::SetTrue))
+}
+
+/// Save the main document of `backup` and `num_shards` new key shards for it
+/// (along with anything else requested by the backup arguments). If `labels`
+/// are given, they are used instead of the `--holder` and `--label` arguments.
+pub(crate) fn save_backup(
+    matches: &ArgMatches,
+    backup: &Backup,
+    num_shards: u32,
+    labels: Option<&[String]>,
+    pdf_options: &PdfOptions,
+    output: &mut Output,
+    registry: Option<&mut Registry>,
+) -> Result<(), Error> {
+    let codeword_language = get_codeword_language(matches)?;
     let main_document = backup.main_document().clone();
-    let shards = (0..num_shards)
-        .map(|_| backup.next_shard().unwrap())
-        .map(|s| (s.id(), s.encrypt().unwrap()))
+    // Make sure the main document can be printed before creating any files.
+    main_document.check_secret_size(pdf_options)?;
+    let key_shards = backup.next_shards(num_shards as usize)?;
+
+    if matches.get_flag("with-manifest") {
+        // NOTE: With --resume, the manifest only lists the key shards created
+        //       in this invocation.
+        let pdf = Manifest::new(&main_document, &key_shards)?.to_pdf_with_options(pdf_options)?;
+        output.write(&format!("manifest-{}.pdf", main_document.id()), |file| {
+            Ok(pdf.save(file)?)
+        })?;
+    }
+
+    if matches.get_flag("with-recovery-instructions") {
+        let holders = match labels {
+            Some(labels) => labels.iter().collect(),
+            None => matches
+                .get_many::<String>("holder")
+                .unwrap_or_default()
+                .collect::<Vec<_>>(),
+        };
+        let pdf =
+            InstructionsDocument::new(&main_document, holders)?.to_pdf_with_options(pdf_options)?;
+        output.write(
+            &format!("recovery_instructions-{}.pdf", main_document.id()),
+            |file| Ok(pdf.save(file)?),
+        )?;
+    }
+
+    let hardened_codewords = matches.get_flag("hardened-codewords");
+    let shards = key_shards
+        .iter()
+        .map(|s| {
+            Ok((
+                s.document_id(),
+                s.id(),
+                s.challenge_checksum(),
+                encrypt_key_shard(s, codeword_language, hardened_codewords)?,
+                None,
+            ))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    output.write_document(
+        &format!("main_document-{}", main_document.id()),
+        &main_document,
+        pdf_options,
+    )?;
+    if output.exports_text() {
+        export_main_document_text(output, &main_document)?;
+    }
+    if output.exports_qr_png() {
+        export_qr_png(
+            output,
+            &format!("main_document-{}", main_document.id()),
+            &qr::main_document_qr_codes(&main_document, pdf_options)?,
+        )?;
+    }
+
+    if matches.get_flag("also-raw") {
+        output.write(
+            &format!("main_document-{}.txt", main_document.id()),
+            |file| Ok(file.write_all(armor::encode_main_document(&main_document).as_bytes())?),
+        )?;
+        for (idx, (key_shard, (_, shard_id, _, (shard, codewords), _))) in
+            key_shards.iter().zip(&shards).enumerate()
+        {
+            let text = armor::encode_key_shard(idx, shards.len(), key_shard, shard, codewords);
+            output.write(
+                &format!("key_shard-{}-{}.txt", main_document.id(), shard_id),
+                |file| Ok(file.write_all(text.as_bytes())?),
+            )?;
+        }
+    }
+
+    let shard_ids = save_key_shards(shards, pdf_options, output)?;
+    if let Some(registry) = registry {
+        match labels {
+            Some(labels) => registry.register_with_labels(labels, &shard_ids)?,
+            None => registry.register(matches, &shard_ids)?,
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_multiline<S: AsRef<str>>(prompt: S) -> Result<String, Error> {
+    print!("{}: ", prompt.as_ref());
+    io::stdout().flush()?;
+
+    // Input ends with an empty line, unless it is armored (which contains an
+    // empty line after the headers) in which case it ends with the END line.
+    let mut lines = Vec::new();
+    let mut armored = false;
+    for line in BufReader::new(io::stdin()).lines() {
+        let line = line.map_err(|err| anyhow!("failed to read data: {}", err))?;
+        armored |= lines.is_empty() && armor::is_armored(&line);
+        match armored {
+            true if armor::is_end_line(&line) => {
+                lines.push(line);
+                break;
+            }
+            false if line.is_empty() => break,
+            _ => lines.push(line),
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+pub(crate) fn parse_multibase<S: AsRef<str>, T: FromWire>(input: S) -> Result<T, Error> {
+    // QrEncoding::decode auto-detects both our QR code encodings and plain
+    // multibase (such as the text fallback).
+    T::from_wire(QrEncoding::decode(input).context("failed to decode data")?)
+        .map_err(|err| anyhow!("failed to parse data: {}", err))
+}
+
+pub(crate) fn read_multibase<S: AsRef<str>, T: FromWire>(prompt: S) -> Result<T, Error> {
+    let input = read_multiline(prompt.as_ref())?;
+    parse_multibase_input(prompt, input)
+}
+
+/// Parse data entered in response to `prompt`, asking for the rest of the QR
+/// codes if `input` was only one part of a split QR code.
+fn parse_multibase_input<S: AsRef<str>, T: FromWire>(prompt: S, input: String) -> Result<T, Error> {
+    // Key shards which are too large for a single QR code are split into
+    // several parts, so ask for the rest of them.
+    match parse_multibase::<_, qr::Part>(&input) {
+        Ok(part) => {
+            let mut joiner = qr::Joiner::new();
+            joiner.add_part(part)?;
+            join_multibase_qr(prompt, joiner)
+        }
+        Err(_) => parse_multibase(input),
+    }
+}
+
+/// Read a key shard interactively. The key shard can also be pasted in its
+/// armored form (as printed by "raw backup" and "backup --also-raw"), in which
+/// case the codewords from its `Keywords` header are returned as well so they
+/// do not need to be entered separately.
+pub(crate) fn read_key_shard<S: AsRef<str>>(
+    prompt: S,
+) -> Result<(EncryptedKeyShard, Option<KeyShardCodewords>), Error> {
+    let input = read_multiline(prompt.as_ref())?;
+    if !armor::is_armored(&input) {
+        return Ok((parse_multibase_input(prompt, input)?, None));
+    }
+    let input = Zeroizing::new(input);
+    let mut shards = armor::decode(&*input)
+        .context("failed to parse armored key shard")?
+        .into_iter()
+        .filter_map(|document| match document {
+            armor::Armored::KeyShard(shard, codewords) => Some((shard, codewords)),
+            _ => None,
+        })
         .collect::<Vec<_>>();
+    ensure!(
+        shards.len() == 1,
+        "armored input must contain exactly one key shard (found {})",
+        shards.len()
+    );
+    Ok(shards.remove(0))
+}
 
-    main_document
-        .to_pdf()?
-        .save(&mut BufWriter::new(File::create(format!(
-            "main_document-{}.pdf",
-            main_document.id()
-        ))?))?;
-
-    for (shard_id, (shard, codewords)) in shards {
-        (shard, codewords)
-            .to_pdf()?
-            .save(&mut BufWriter::new(File::create(format!(
-                "key_shard-{}-{}.pdf",
-                main_document.id(),
-                shard_id
-            ))?))?;
+/// Use the codewords that came with an armored key shard (see
+/// `read_key_shard`), or ask for them if there were none.
+pub(crate) fn codewords_or_read<S: AsRef<str>>(
+    codewords: Option<KeyShardCodewords>,
+    prompt: S,
+) -> Result<KeyShardCodewords, Error> {
+    match codewords {
+        Some(codewords) => {
+            println!("Using the codewords included with the armored key shard.");
+            Ok(codewords)
+        }
+        None => read_codewords(prompt),
+    }
+}
+
+pub(crate) fn read_codewords<S: AsRef<str>>(prompt: S) -> Result<KeyShardCodewords, Error> {
+    let input = Zeroizing::new(read_multiline(prompt)?);
+    Ok(codewords::normalize(
+        &input.split_whitespace().collect::<Vec<_>>(),
+    )?)
+}
+
+fn print_timestamps(name: &str, created: Option<SystemTime>, expires: Option<SystemTime>) {
+    if let Some(created) = created {
+        println!("{} created: {}", name, format_date(created));
+    }
+    if let Some(expires) = expires {
+        if expires <= SystemTime::now() {
+            println!(
+                "WARNING: {} expired on {} -- a newer backup may have replaced it.",
+                name,
+                format_date(expires)
+            );
+        } else {
+            println!("{} expires: {}", name, format_date(expires));
+        }
+    }
+}
+
+pub(crate) fn format_part_indices(idxs: &[usize]) -> String {
+    idxs.iter()
+        .map(|idx| (idx + 1).to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn read_multibase_qr<S: AsRef<str>, T: FromWire>(prompt: S) -> Result<T, Error> {
+    join_multibase_qr(prompt, qr::Joiner::new())
+}
+
+/// Ask for the QR codes still missing from `joiner`, and parse the combined
+/// data as a `T`.
+fn join_multibase_qr<S: AsRef<str>, T: FromWire>(
+    prompt: S,
+    mut joiner: qr::Joiner,
+) -> Result<T, Error> {
+    let prompt = prompt.as_ref();
+    while !joiner.complete() {
+        let status = match joiner.num_parts() {
+            None => "no codes entered yet".to_string(),
+            Some(n) => format!(
+                "entered codes [{}] of {}, missing [{}]",
+                format_part_indices(&joiner.present_parts()),
+                n,
+                format_part_indices(&joiner.missing_parts()),
+            ),
+        };
+        let input = read_multiline(format!(
+            "{} ({}; enter \"delete N\" to remove code N)",
+            prompt, status
+        ))?;
+        let input = input.trim();
+        ensure!(!input.is_empty(), "no qr code data entered -- aborting");
+
+        // Allow the user to remove a mistyped part so it can be re-entered.
+        if let Some(idx) = input.strip_prefix("delete") {
+            match idx.trim().parse::<usize>() {
+                Ok(idx) if idx > 0 && joiner.remove_part(idx - 1).is_some() => {
+                    println!("Removed code {}.", idx)
+                }
+                _ => println!("Invalid code number '{}' to delete.", idx.trim()),
+            }
+            continue;
+        }
+
+        let part: qr::Part = match parse_multibase(input) {
+            Ok(part) => part,
+            Err(err) => {
+                println!("Invalid code (please re-enter it): {:#}", err);
+                continue;
+            }
+        };
+        let (idx, num_parts) = (part.index(), part.num_parts());
+        let duplicate = joiner.has_part(idx);
+        match joiner.add_part(part) {
+            Ok(_) if duplicate => println!("Code {} of {} was already entered.", idx + 1, num_parts),
+            Ok(_) => println!("Added code {} of {}.", idx + 1, num_parts),
+            Err(err @ paperback::pdf::Error::ConflictingQrPart { .. }) => println!(
+                "Code {} of {} rejected: {} (enter \"delete {}\" to remove the earlier code if it was the one which was misread)",
+                idx + 1,
+                num_parts,
+                err,
+                idx + 1
+            ),
+            Err(err) => println!("Code {} of {} rejected: {}", idx + 1, num_parts, err),
+        }
     }
-
-    Ok(())
+    T::from_wire(joiner.combine_parts()?)
+        .map_err(|err| anyhow!("parse inner qr code data: {}", err))
 }
 
-fn read_multiline<S: AsRef<str>>(prompt: S) -> Result<String, Error> {
-    print!("{}: ", prompt.as_ref());
-    io::stdout().flush()?;
+fn scan_camera_arg() -> Arg {
+    Arg::new("scan-camera")
+        .long("scan-camera")
+        .help("Scan QR codes live using a webcam rather than entering them interactively. Requires paperback to be built with the \"camera\" feature.")
+        .action(ArgAction::SetTrue)
+}
 
-    let buffer_stdin = BufReader::new(io::stdin());
-    Ok(buffer_stdin
-        .lines()
-        .take_while(|s| !matches!(s.as_deref(), Ok("") | Err(_)))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|err| anyhow!("failed to read data: {}", err))?
-        .join("\n"))
+/// Source of the data stored in QR codes.
+enum QrInput {
+    /// Ask the user to enter the QR code data.
+    Interactive,
+    /// Scan the QR codes using a webcam.
+    #[cfg(feature = "camera")]
+    Camera(camera::QrScanner),
 }
 
-fn read_multibase<S: AsRef<str>, T: FromWire>(prompt: S) -> Result<T, Error> {
-    T::from_wire_multibase(
-        wire::multibase_strip(read_multiline(prompt)?)
-            .map_err(|err| anyhow!("failed to strip out non-multibase characters: {}", err))?,
-    )
-    .map_err(|err| anyhow!("failed to parse data: {}", err))
+impl QrInput {
+    fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
+        if matches.get_flag("scan-camera") {
+            #[cfg(feature = "camera")]
+            return Ok(Self::Camera(camera::QrScanner::open()?));
+            #[cfg(not(feature = "camera"))]
+            bail!("--scan-camera requires paperback to be built with the \"camera\" feature");
+        }
+        Ok(Self::Interactive)
+    }
+
+    fn read_key_shard<S: AsRef<str>>(
+        &mut self,
+        prompt: S,
+    ) -> Result<(EncryptedKeyShard, Option<KeyShardCodewords>), Error> {
+        match self {
+            Self::Interactive => read_key_shard(prompt),
+            #[cfg(feature = "camera")]
+            Self::Camera(scanner) => Ok((scanner.scan_multibase(prompt)?, None)),
+        }
+    }
+
+    fn read_multibase_qr<S: AsRef<str>, T: FromWire>(&mut self, prompt: S) -> Result<T, Error> {
+        match self {
+            Self::Interactive => read_multibase_qr(prompt),
+            #[cfg(feature = "camera")]
+            Self::Camera(scanner) => scanner.scan_multibase_qr(prompt),
+        }
+    }
 }
 
-fn read_codewords<S: AsRef<str>>(prompt: S) -> Result<KeyShardCodewords, Error> {
-    Ok(read_multiline(prompt)?
-        .split_whitespace()
-        .map(|s| s.to_owned())
-        .collect::<Vec<_>>())
+pub(crate) fn select_arg() -> Arg {
+    Arg::new("select")
+        .long("select")
+        .value_name("NAME")
+        .help("Only recover the named payload NAME (for backups created with backup --payload).")
+        .action(ArgAction::Set)
 }
 
-fn read_multibase_qr<S: AsRef<str>, T: FromWire>(prompt: S) -> Result<T, Error> {
-    let prompt = prompt.as_ref();
-    let mut joiner = qr::Joiner::new();
-    while !joiner.complete() {
-        let part: qr::Part = read_multibase(format!(
-            "{} ({} codes remaining)",
-            prompt,
-            match joiner.remaining() {
-                None => "unknown number of".to_string(),
-                Some(n) => n.to_string(),
-            }
-        ))?;
-        joiner.add_part(part)?;
+/// Recover the secret data of `quorum`, or the payload selected with
+/// `--select`.
+pub(crate) fn recover_selected(
+    matches: &ArgMatches,
+    quorum: &Quorum,
+) -> Result<Zeroizing<Vec<u8>>, Error> {
+    match matches.get_one::<String>("select") {
+        Some(name) => quorum
+            .recover_payload(name)
+            .with_context(|| format!("recovering payload '{}'", name)),
+        None => recover_document_with_progress(quorum),
     }
-    T::from_wire(joiner.combine_parts()?)
-        .map_err(|err| anyhow!("parse inner qr code data: {}", err))
 }
 
-// paperback-cli recover --interactive
-fn recover_cli() -> Command {
-    Command::new("recover")
-        .about(r#"Recover a paperback backup."#)
+/// Recover the secret data of `quorum`, showing a progress bar (on stderr)
+/// while the main document is decrypted.
+pub(crate) fn recover_document_with_progress(quorum: &Quorum) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let progress = ProgressBar::new(0).with_style(
+        ProgressStyle::with_template("Recovering secret {wide_bar} {percent}% (eta {eta})")
+            .expect("progress bar template should be valid"),
+    );
+    let secret = quorum
+        .recover_document_with_progress(|done, total| {
+            progress.set_length(total as u64);
+            progress.set_position(done as u64);
+        })
+        .context("recovering secret data");
+    progress.finish_and_clear();
+    secret
+}
+
+/// Add the arguments used by `read_recovery_quorum` to `command`.
+pub(crate) fn recovery_source_args(command: Command) -> Command {
+    command
         .arg(
             Arg::new("interactive")
                 .long("interactive")
                 .help("Ask for data stored in QR codes interactively rather than scanning images.")
                 .action(ArgAction::SetTrue)
-                // TODO: Make this optional.
+                .conflicts_with("scan-camera"),
+        )
+        .arg(
+            Arg::new("pdf")
+                .long("pdf")
+                .value_name("PDF")
+                .help("Read the main document and key shards from PDF files generated by paperback. Key shard codewords (and any key shards missing from the PDF files) are still requested interactively.")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("scan-image")
+                .long("scan-image")
+                .value_name("IMAGE")
+                .help("Read the main document and key shards from the QR codes in scanned images (or photos) of the printed documents. Key shard codewords (and any key shards missing from the images) are still requested interactively. Requires paperback to be built with the \"scan\" feature.")
+                .action(ArgAction::Append)
+                .conflicts_with("pdf"),
+        )
+        .arg(
+            Arg::new("qr-strings")
+                .long("qr-strings")
+                .value_name("PATH")
+                .help(r#"Read the main document and key shards from text files containing the decoded data of their QR codes, one QR code per line and in any order (as produced by most QR code scanner apps, or by running "zbarimg" on the scanned documents). Key shard codewords (and any key shards missing from the files) are still requested interactively."#)
+                .action(ArgAction::Append)
+                .conflicts_with_all(["pdf", "scan-image"]),
+        )
+        .arg(
+            Arg::new("watch-dir")
+                .long("watch-dir")
+                .value_name("DIR")
+                .help("Read the main document and key shards from scanned images (or PDF files) in DIR, waiting for new scans to be added to DIR until the main document and a quorum of key shards have been found. Progress is printed as each scan is read. Key shard codewords are still requested interactively. Requires paperback to be built with the \"watch\" feature.")
+                .action(ArgAction::Set)
+                .conflicts_with_all(["pdf", "scan-image", "qr-strings"]),
+        )
+        .arg(
+            Arg::new("shard-file")
+                .long("shard-file")
+                .value_name("PATH")
+                .help(r#"Read a key shard from a text file containing its data on the first line (such as those written by --export-text). Use "-" to enter a key shard interactively at that point. Can be combined with the other sources, in which case key shards from --pdf (or --scan-image or --qr-strings) are used first, then --shard-file, and any remaining key shards are requested interactively (or scanned)."#)
+                .action(ArgAction::Append),
+        )
+        .arg(scan_camera_arg())
+        .arg(
+            Arg::new("session")
+                .long("session")
+                .value_name("PATH")
+                .help("Save the main document and the key shards entered interactively to PATH as they are entered (without their codewords), so that an interrupted recovery can be resumed by passing the same --session again. The file is removed once a full quorum of key shards has been entered.")
+                .action(ArgAction::Set),
+        )
+        .group(
+            ArgGroup::new("source")
+                .arg("interactive")
+                .arg("scan-camera")
+                .arg("pdf")
+                .arg("scan-image")
+                .arg("qr-strings")
+                .arg("watch-dir")
+                .arg("shard-file")
+                .multiple(true)
                 .required(true),
         )
+        .arg(
+            Arg::new("identity")
+                .long("identity")
+                .value_name("PATH")
+                .help("Path to an age identity file, required to recover backups created with backup --recipient.")
+                .action(ArgAction::Set),
+        )
+}
+
+// paperback-cli recover [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--session <PATH>] [--identity <PATH>] [--select <NAME>] [--digest-algorithm <ALGORITHM>] [--expect-digest <DIGEST>] OUTPUT
+// paperback-cli recover [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--session <PATH>] [--identity <PATH>] [--select <NAME>] [--digest-algorithm <ALGORITHM>] [--expect-digest <DIGEST>] --exec <COMMAND>
+// paperback-cli recover [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--session <PATH>] [--identity <PATH>] [--select <NAME>] [--digest-algorithm <ALGORITHM>] [--expect-digest <DIGEST>] --dry-run
+// paperback-cli recover [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--session <PATH>] [--identity <PATH>] [--digest-algorithm <ALGORITHM>] --extract-dir <DIR>
+fn recover_cli() -> Command {
+    recovery_source_args(Command::new("recover").about(r#"Recover a paperback backup."#))
+        .arg(select_arg())
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Check that the backup can be recovered (including decrypting the secret data) without writing the secret data anywhere. Only the length and digest of the secret data are printed (for backups with several named payloads, the digest of each payload is printed unless --select is given).")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("OUTPUT"),
+        )
+        .arg(
+            Arg::new("exec")
+                .long("exec")
+                .value_name("COMMAND")
+                .help(r#"Run COMMAND (with "sh -c", or "cmd /C" on Windows) and write the recovered secret data to its stdin, rather than to a file. The secret data is never written to disk, and is wiped from memory once COMMAND exits. For example, --exec "cryptsetup open --key-file=- /dev/sdb1 backup"."#)
+                .action(ArgAction::Set)
+                .conflicts_with_all(["dry-run", "OUTPUT"]),
+        )
+        .arg(
+            Arg::new("extract-dir")
+                .long("extract-dir")
+                .value_name("DIR")
+                .help("Write each named payload (such as the files backed up with backup --input-many) to a file in DIR named after the payload, restoring the permissions the files were backed up with. DIR is created if it doesn't exist, but existing files are never overwritten. The digest of each file is printed.")
+                .action(ArgAction::Set)
+                .conflicts_with_all(["dry-run", "exec", "select", "expect-digest", "OUTPUT"]),
+        )
+        .arg(
+            Arg::new("digest-algorithm")
+                .long("digest-algorithm")
+                .value_name("ALGORITHM")
+                .help(r#"Digest algorithm used for the digest of the recovered secret data printed after recovery ("sha2-256", "blake2b-256", or "blake3"). The "sha2-256" digest matches the output of sha256sum."#)
+                .value_parser(["sha2-256", "blake2b-256", "blake3"])
+                .default_value("sha2-256")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("expect-digest")
+                .long("expect-digest")
+                .value_name("DIGEST")
+                .help("Expected (hex-encoded) digest of the recovered secret data, using --digest-algorithm. If the digest of the recovered secret data does not match, recovery fails before the secret data is written anywhere.")
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("OUTPUT")
                 .help(r#"Path to write recovered secret data to ("-" to write to stdout)."#)
                 .action(ArgAction::Set)
                 .allow_hyphen_values(true)
-                .required(true)
+                .required_unless_present_any(["dry-run", "exec", "extract-dir"])
                 .index(1),
         )
 }
 
-fn recover(matches: &ArgMatches) -> Result<(), Error> {
-    let interactive = matches.get_flag("interactive");
-    ensure!(interactive, "PDF scanning not yet implemented");
-    let output_path = matches
-        .get_one::<String>("OUTPUT")
-        .context("required OUTPUT argument not provided")?;
+fn read_pdf<T: FromPdf>(path: &str) -> Result<T, Error> {
+    let pdf = fs::read(path).with_context(|| format!("failed to read PDF file '{}'", path))?;
+    T::from_pdf(pdf).with_context(|| format!("failed to parse PDF file '{}'", path))
+}
+
+fn read_recovery_pdfs<'a>(
+    paths: impl IntoIterator<Item = &'a String>,
+) -> Result<(MainDocument, Vec<EncryptedKeyShard>), Error> {
+    let mut main_document = None;
+    let mut shards = vec![];
+    for path in paths {
+        let pdf = fs::read(path).with_context(|| format!("failed to read PDF file '{}'", path))?;
+        if let Ok(document) = MainDocument::from_pdf(&pdf) {
+            ensure!(
+                main_document.is_none(),
+                "more than one main document PDF file provided"
+            );
+            main_document = Some(document);
+        } else {
+            shards.extend(Vec::<EncryptedKeyShard>::from_pdf(&pdf).with_context(|| {
+                format!(
+                    "PDF file '{}' contains neither a main document nor key shards",
+                    path
+                )
+            })?);
+        }
+    }
+    let main_document = main_document.context("no main document PDF file provided")?;
+    Ok((main_document, shards))
+}
+
+#[cfg_attr(not(feature = "scan"), allow(unused_variables))]
+fn read_recovery_scans<'a>(
+    paths: impl IntoIterator<Item = &'a String>,
+) -> Result<(MainDocument, Vec<EncryptedKeyShard>), Error> {
+    #[cfg(feature = "scan")]
+    {
+        scan::read_recovery_scans(paths)
+    }
+    #[cfg(not(feature = "scan"))]
+    {
+        bail!("--scan-image requires paperback to be built with the \"scan\" feature")
+    }
+}
+
+#[cfg_attr(not(feature = "watch"), allow(unused_variables))]
+fn read_recovery_watch_dir(dir: &str) -> Result<(MainDocument, Vec<EncryptedKeyShard>), Error> {
+    #[cfg(feature = "watch")]
+    {
+        watch::read_recovery_watch_dir(dir)
+    }
+    #[cfg(not(feature = "watch"))]
+    {
+        bail!("--watch-dir requires paperback to be built with the \"watch\" feature")
+    }
+}
+
+pub(crate) fn read_shard_file(path: &str) -> Result<EncryptedKeyShard, Error> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read key shard file '{}'", path))?;
+    let data = contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .with_context(|| format!("key shard file '{}' is empty", path))?;
+    parse_multibase(data).with_context(|| format!("failed to parse key shard file '{}'", path))
+}
+
+fn read_identity(path: &str) -> Result<AgeIdentity, Error> {
+    let contents = Zeroizing::new(
+        fs::read_to_string(path)
+            .with_context(|| format!("failed to read identity file '{}'", path))?,
+    );
+    // Identity files generated by age-keygen contain comments.
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .with_context(|| format!("identity file '{}' contains no identity", path))?
+        .parse()
+        .map_err(|err| anyhow!("identity file '{}' is invalid: {}", path, err))
+}
+
+/// The main document and key shards entered during a recovery, saved to disk
+/// (without any codewords) so that an interrupted recovery can be resumed
+/// without having to enter them all again. See `--session`.
+struct RecoverySession {
+    path: PathBuf,
+    main_document: Option<MainDocument>,
+    shards: Vec<EncryptedKeyShard>,
+}
+
+impl RecoverySession {
+    /// Load the session stored at `path`, or start a new one if there is no
+    /// such file.
+    fn open(path: &str) -> Result<Self, Error> {
+        let mut session = Self {
+            path: PathBuf::from(path),
+            main_document: None,
+            shards: vec![],
+        };
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(session),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read recovery session file '{}'", path))
+            }
+        };
+        // The main document is on the first line, followed by one key shard
+        // per line.
+        let mut lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        session.main_document = lines
+            .next()
+            .map(parse_multibase)
+            .transpose()
+            .with_context(|| format!("failed to parse recovery session file '{}'", path))?;
+        session.shards = lines
+            .map(parse_multibase)
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("failed to parse recovery session file '{}'", path))?;
+        Ok(session)
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let contents = self
+            .main_document
+            .iter()
+            .map(|main_document| main_document.to_wire_multibase(multibase::Base::Base32Z))
+            .chain(
+                self.shards
+                    .iter()
+                    .map(|shard| shard.to_wire_multibase(multibase::Base::Base32Z)),
+            )
+            .collect::<Vec<_>>()
+            .join("\n");
+        // Write to a temporary file first, so that being interrupted while
+        // saving doesn't lose the whole session.
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, contents + "\n")
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .with_context(|| {
+                format!(
+                    "failed to write recovery session file '{}'",
+                    self.path.display()
+                )
+            })
+    }
+
+    fn set_main_document(&mut self, main_document: &MainDocument) -> Result<(), Error> {
+        if let Some(session_document) = &self.main_document {
+            ensure!(
+                session_document.id() == main_document.id(),
+                "recovery session file '{}' is for document {}, not {}",
+                self.path.display(),
+                session_document.id(),
+                main_document.id()
+            );
+            return Ok(());
+        }
+        self.main_document = Some(main_document.clone());
+        self.save()
+    }
+
+    fn push_shard(&mut self, shard: &EncryptedKeyShard) -> Result<(), Error> {
+        self.shards.push(shard.clone());
+        self.save()
+    }
 
-    let main_document: MainDocument = read_multibase_qr("Enter a main document code")?;
+    fn remove_shard(&mut self, shard: &EncryptedKeyShard) -> Result<(), Error> {
+        let wire = shard.to_wire();
+        self.shards.retain(|other| other.to_wire() != wire);
+        self.save()
+    }
+
+    /// The session is no longer needed once a quorum has been entered.
+    fn finish(self) -> Result<(), Error> {
+        fs::remove_file(&self.path).with_context(|| {
+            format!(
+                "failed to remove recovery session file '{}'",
+                self.path.display()
+            )
+        })
+    }
+}
+
+/// What to do after a key shard failed to decrypt.
+enum DecryptRetry {
+    /// Ask for the codewords of the same key shard again.
+    Codewords,
+    /// Ask for the data of the key shard again (interactively).
+    ShardData,
+    /// Move on to the next key shard.
+    Skip,
+}
+
+/// Ask the user how to continue after a key shard failed to decrypt. Returns
+/// `None` if the recovery should be aborted.
+fn read_decrypt_retry() -> Result<Option<DecryptRetry>, Error> {
+    loop {
+        print!("Re-enter [c]odewords, re-enter shard [d]ata, or [s]kip this key shard? (leave empty to abort): ");
+        io::stdout().flush()?;
+        let mut choice = String::new();
+        io::stdin().read_line(&mut choice)?;
+        match choice.trim() {
+            "c" | "codewords" => return Ok(Some(DecryptRetry::Codewords)),
+            "d" | "data" => return Ok(Some(DecryptRetry::ShardData)),
+            "s" | "skip" => return Ok(Some(DecryptRetry::Skip)),
+            "" => return Ok(None),
+            choice => println!("Unknown choice '{}'.", choice),
+        }
+    }
+}
+
+/// Read a main document and a quorum of key shards for it, from the sources
+/// given by the `recovery_source_args` arguments.
+pub(crate) fn read_recovery_quorum(matches: &ArgMatches) -> Result<Quorum, Error> {
+    let identity = matches
+        .get_one::<String>("identity")
+        .map(|path| read_identity(path))
+        .transpose()?;
+
+    let mut session = matches
+        .get_one::<String>("session")
+        .map(|path| RecoverySession::open(path))
+        .transpose()?;
+    let mut input = QrInput::from_matches(matches)?;
+    let (main_document, document_shards) = match (
+        matches.get_many::<String>("pdf"),
+        matches.get_many::<String>("scan-image"),
+        matches.get_many::<String>("qr-strings"),
+        matches.get_one::<String>("watch-dir"),
+        session
+            .as_ref()
+            .and_then(|session| session.main_document.clone()),
+    ) {
+        (Some(paths), _, _, _, _) => read_recovery_pdfs(paths)?,
+        (None, Some(paths), _, _, _) => read_recovery_scans(paths)?,
+        (None, None, Some(paths), _, _) => collect::read_qr_strings(paths)?,
+        (None, None, None, Some(dir), _) => read_recovery_watch_dir(dir)?,
+        (None, None, None, None, Some(main_document)) => (main_document, vec![]),
+        (None, None, None, None, None) => (
+            input.read_multibase_qr("Enter a main document code")?,
+            vec![],
+        ),
+    };
+    // Key shards from a resumed session are used before any other source.
+    let mut session_shards = match &mut session {
+        Some(session) => {
+            session.set_main_document(&main_document)?;
+            if !session.shards.is_empty() {
+                println!(
+                    "Resuming recovery session with {} key shards (their codewords must be entered again).",
+                    session.shards.len()
+                );
+            }
+            session.shards.clone()
+        }
+        None => vec![],
+    }
+    .into_iter();
+    let mut document_shards = document_shards.into_iter();
+    let mut shard_files = matches
+        .get_many::<String>("shard-file")
+        .into_iter()
+        .flatten();
     let quorum_size = main_document.quorum_size();
     // TODO: Ask the user to input the checksum...
     println!(
         "Main document checksum: {}",
         main_document.checksum_string()
     );
+    println!(
+        "Main document fingerprint: {}",
+        main_document.fingerprint_words().join(" ")
+    );
 
     println!("Document ID: {}", main_document.id());
+    if let Some(title) = main_document.title() {
+        println!("Title: {}", title);
+    }
+    for info in main_document.public_info() {
+        println!("{}: {}", info.label(), info.value());
+    }
+    print_timestamps(
+        "Main document",
+        main_document.created(),
+        main_document.expires(),
+    );
     println!("{} key shards required.", quorum_size);
+    if main_document.requires_identity() {
+        ensure!(
+            identity.is_some(),
+            "main document was encrypted to an age recipient: its identity must be provided with --identity"
+        );
+        println!("Secret data is encrypted to an age recipient.");
+    }
+    if main_document.contains_seed_phrase() {
+        println!("Secret data is a BIP-39 seed phrase.");
+    }
 
     let mut quorum = UntrustedQuorum::new();
     quorum.main_document(main_document);
+    // Set if the user asked to re-enter the data of a key shard that failed to
+    // decrypt, in which case it is always entered interactively.
+    let mut reenter_shard = false;
     while quorum.num_untrusted_shards() < quorum_size as usize {
         let idx = quorum.num_untrusted_shards() as u32;
-        let encrypted_shard: EncryptedKeyShard = read_multibase(format!(
-            "Quorum contains [{}] key shards.\nEnter key shard {} of {}",
-            quorum
-                .untrusted_shards()
-                .map(KeyShard::id)
-                .collect::<Vec<_>>()
-                .join(" "),
-            idx + 1,
-            quorum_size
-        ))?;
+        let source_shard = match mem::take(&mut reenter_shard) {
+            true => None,
+            false => match session_shards.next().or_else(|| document_shards.next()) {
+                Some(shard) => Some(shard),
+                // "-" means the shard should be entered interactively.
+                None => shard_files
+                    .next()
+                    .filter(|path| *path != "-")
+                    .map(|path| read_shard_file(path))
+                    .transpose()?,
+            },
+        };
+        let (encrypted_shard, mut inline_codewords) = match source_shard {
+            Some(shard) => (shard, None),
+            None => {
+                let (shard, codewords) = input.read_key_shard(format!(
+                    "Quorum contains [{}] key shards.\nEnter key shard {} of {}",
+                    quorum
+                        .untrusted_shards()
+                        .map(KeyShard::id)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    idx + 1,
+                    quorum_size
+                ))?;
+                if let Some(session) = &mut session {
+                    session.push_shard(&shard)?;
+                }
+                (shard, codewords)
+            }
+        };
         // TODO: Ask the user to input the checksum...
         println!(
             "Key shard {} checksum: {}",
             idx + 1,
             encrypted_shard.checksum_string()
         );
+        println!(
+            "Key shard {} fingerprint: {}",
+            idx + 1,
+            encrypted_shard.fingerprint_words().join(" ")
+        );
 
-        let codewords = read_codewords(format!("Enter key shard {} codewords", idx + 1))?;
-        let shard = encrypted_shard
-            .decrypt(&codewords)
-            .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
-            .with_context(|| format!("decrypting key shard {}", idx + 1))?;
+        let shard = loop {
+            let err = match codewords_or_read(
+                inline_codewords.take(),
+                format!("Enter key shard {} codewords", idx + 1),
+            )
+            .and_then(|codewords| encrypted_shard.decrypt(&codewords).map_err(Error::from))
+            {
+                Ok(shard) => break Some(shard),
+                Err(err) => err,
+            };
+            println!("Failed to decrypt key shard {}: {:#}", idx + 1, err);
+            match read_decrypt_retry()? {
+                Some(DecryptRetry::Codewords) => continue,
+                Some(DecryptRetry::ShardData) => reenter_shard = true,
+                Some(DecryptRetry::Skip) => (),
+                None => return Err(err.context(format!("decrypting key shard {}", idx + 1))),
+            }
+            break None;
+        };
+        let shard = match shard {
+            Some(shard) => shard,
+            None => {
+                if let Some(session) = &mut session {
+                    session.remove_shard(&encrypted_shard)?;
+                }
+                continue;
+            }
+        };
 
-        println!("Loaded key shard {}.", shard.id());
+        println!("Loaded key shard {} ({}).", shard.id(), shard.friendly_id());
+        // Shards carry the backup's creation time, so only show the expiry.
+        print_timestamps(&format!("Key shard {}", shard.id()), None, shard.expires());
+        let shard_id = shard.id();
         quorum.push_shard(shard);
+        if quorum
+            .conflicts()
+            .iter()
+            .any(|conflict| conflict.id == shard_id)
+        {
+            println!(
+                "Warning: key shard {} differs from an earlier key shard with the same id -- one of them may be forged.",
+                shard_id
+            );
+        }
     }
 
-    let quorum = quorum.validate().map_err(|err| {
-        anyhow!(
-            "quorum failed to validate -- possible forgery! {}; groupings: {:?}",
-            err.message,
-            err.as_groups()
-        )
-    })?;
+    let mut quorum = quorum.validate()?;
+    let uncommitted = quorum.uncommitted_shards();
+    if !uncommitted.is_empty() {
+        println!(
+            "Warning: key shards [{}] are not among the key shards the backup was created with -- they were created later (with expand-shards or recreate-shards) or forged by someone who had the backup's private key.",
+            uncommitted
+                .iter()
+                .map(ShardId::as_str)
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+    }
+    if let Some(session) = session {
+        session.finish()?;
+    }
+    if let Some(identity) = identity {
+        quorum.set_identity(identity);
+    }
+    Ok(quorum)
+}
 
-    let secret = quorum
-        .recover_document()
+fn recover(matches: &ArgMatches) -> Result<(), Error> {
+    if matches.get_flag("dry-run") {
+        return recover_dry_run(matches, &read_recovery_quorum(matches)?);
+    }
+
+    // Either --exec, --extract-dir or OUTPUT is required (checked by clap).
+    let exec_command = matches.get_one::<String>("exec");
+    let output_path = matches.get_one::<String>("OUTPUT");
+    let quorum = read_recovery_quorum(matches)?;
+    if let Some(dir) = matches.get_one::<String>("extract-dir") {
+        return extract_payloads(matches, &quorum, dir);
+    }
+    let secret = recover_selected(matches, &quorum)?;
+
+    // Print the digest to stderr, as the secret data might be written to
+    // stdout.
+    let digest = SecretDigest::new(matches, &secret)?;
+    eprintln!("{}", digest);
+    if let Some(expected) = matches.get_one::<String>("expect-digest") {
+        digest.check(expected)?;
+    }
+
+    match (exec_command, output_path) {
+        (Some(command), _) => exec_secret(command, &secret),
+        (None, output_path) => write_secret(
+            output_path.context("required OUTPUT argument not provided")?,
+            &secret,
+        ),
+    }
+}
+
+/// Check that `quorum` can be recovered (for `--dry-run`), printing the digest
+/// of the secret data that would have been recovered.
+fn recover_dry_run(matches: &ArgMatches, quorum: &Quorum) -> Result<(), Error> {
+    let check = quorum
+        .verify_recoverable()
         .context("recovering secret data")?;
 
+    if check.payloads.len() > 1 && matches.get_one::<String>("select").is_none() {
+        // There is no single secret to take the digest of, so print the
+        // digest of each payload instead (as with --extract-dir).
+        ensure!(
+            matches.get_one::<String>("expect-digest").is_none(),
+            "--expect-digest requires --select for backups with several named payloads"
+        );
+        println!(
+            "Backup is recoverable. Named payloads: {}",
+            check.payloads.join(", ")
+        );
+        for payload in quorum
+            .recover_payloads()
+            .context("recovering named payloads")?
+        {
+            println!(
+                "Payload '{}': {}",
+                payload.name(),
+                SecretDigest::new(matches, payload.data())?
+            );
+        }
+        return Ok(());
+    }
+
+    let secret = recover_selected(matches, quorum)?;
+    let digest = SecretDigest::new(matches, &secret)?;
+    if let Some(expected) = matches.get_one::<String>("expect-digest") {
+        digest.check(expected)?;
+    }
+    println!("Backup is recoverable. {}", digest);
+    if !check.payloads.is_empty() {
+        println!("Named payloads: {}", check.payloads.join(", "));
+    }
+    Ok(())
+}
+
+/// Whether `name` can be used as a file name in `--extract-dir` without
+/// escaping it.
+fn is_plain_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) && !name.contains(['/', '\\'])
+}
+
+/// Write each named payload in `quorum` to a file in `dir` (for
+/// `--extract-dir`), restoring any permissions recorded for it.
+fn extract_payloads(matches: &ArgMatches, quorum: &Quorum, dir: &str) -> Result<(), Error> {
+    let payloads = quorum
+        .recover_payloads()
+        .context("recovering named payloads")?;
+    // Check every name before writing anything, since the names come from
+    // the backup rather than the user.
+    for payload in &payloads {
+        ensure!(
+            is_plain_file_name(payload.name()),
+            "payload name '{}' is not a valid file name (recover it with --select instead)",
+            payload.name()
+        );
+    }
+
+    fs::create_dir_all(dir).with_context(|| format!("failed to create directory '{}'", dir))?;
+    for payload in &payloads {
+        let path = Path::new(dir).join(payload.name());
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // Keep the secret data private until its permissions are restored.
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("failed to create file '{}'", path.display()))?;
+        file.write_all(payload.data())
+            .with_context(|| format!("failed to write file '{}'", path.display()))?;
+        #[cfg(unix)]
+        if let Some(mode) = payload.mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode))
+                .with_context(|| format!("failed to set permissions of '{}'", path.display()))?;
+        }
+        println!(
+            "Extracted '{}'. {}",
+            path.display(),
+            SecretDigest::new(matches, payload.data())?
+        );
+    }
+    Ok(())
+}
+
+/// Digest of the recovered secret data, so that whoever is recovering a backup
+/// can confirm that they recovered the data they expected.
+struct SecretDigest {
+    algorithm: ChecksumAlgorithm,
+    length: usize,
+    digest: String,
+}
+
+impl SecretDigest {
+    fn new(matches: &ArgMatches, secret: &[u8]) -> Result<Self, Error> {
+        let algorithm = matches
+            .get_one::<String>("digest-algorithm")
+            .context("required --digest-algorithm argument not provided")?;
+        let algorithm = ChecksumAlgorithm::from_name(algorithm)
+            // We should never reach here.
+            .with_context(|| format!("unsupported --digest-algorithm '{}'", algorithm))?;
+        let digest = algorithm
+            .digest(secret)
+            .digest()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok(Self {
+            algorithm,
+            length: secret.len(),
+            digest,
+        })
+    }
+
+    fn check(&self, expected: &str) -> Result<(), Error> {
+        if !self.digest.eq_ignore_ascii_case(expected.trim()) {
+            return Err(DigestMismatchError {
+                expected: expected.trim().to_string(),
+                actual: self.digest.clone(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SecretDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Recovered {} bytes of secret data ({} digest {}).",
+            self.length,
+            self.algorithm.name(),
+            self.digest
+        )
+    }
+}
+
+/// The digest of the recovered secret data did not match `--expect-digest`.
+#[derive(Debug)]
+struct DigestMismatchError {
+    expected: String,
+    actual: String,
+}
+
+impl fmt::Display for DigestMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "recovered secret data digest {} does not match --expect-digest {}",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for DigestMismatchError {}
+
+/// Run `command` with a shell, writing `secret` to its stdin. The caller keeps
+/// ownership of `secret`, so it is only wiped once the command has exited.
+fn exec_secret(command: &str, secret: &[u8]) -> Result<(), Error> {
+    let mut child = if cfg!(windows) {
+        process::Command::new("cmd")
+            .args(["/C", command])
+            .stdin(Stdio::piped())
+            .spawn()
+    } else {
+        process::Command::new("sh")
+            .args(["-c", command])
+            .stdin(Stdio::piped())
+            .spawn()
+    }
+    .with_context(|| format!("failed to run '{}'", command))?;
+
+    let mut stdin = child.stdin.take().expect("child stdin should be piped");
+    let written = match stdin.write_all(secret) {
+        // The command doesn't have to read all of its input (such as when it
+        // only needs a fixed-size key), so only its exit status matters.
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        written => written,
+    };
+    // Close stdin so the command sees the end of the secret data.
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .with_context(|| format!("failed to wait for '{}'", command))?;
+    written.with_context(|| format!("failed to write secret data to '{}'", command))?;
+    ensure!(status.success(), "'{}' failed ({})", command, status);
+
+    Ok(())
+}
+
+fn write_secret(output_path: &str, secret: &[u8]) -> Result<(), Error> {
     let (mut stdout_writer, mut file_writer);
     let output_file: &mut dyn Write = if output_path == "-" {
         stdout_writer = io::stdout();
@@ -269,17 +2208,53 @@ fn recover(matches: &ArgMatches) -> Result<(), Error> {
     };
 
     output_file
-        .write_all(&secret)
+        .write_all(secret)
         .context("write secret data to file")?;
 
     Ok(())
 }
 
-fn new_shards(new_shard_types: impl IntoIterator<Item = NewShardKind>) -> Result<(), Error> {
+// paperback-cli wizard OUTPUT
+fn wizard_cli() -> Command {
+    Command::new("wizard")
+        .about(r#"Recover a paperback backup using an interactive terminal interface."#)
+        .arg(
+            Arg::new("OUTPUT")
+                .help(r#"Path to write recovered secret data to ("-" to write to stdout)."#)
+                .action(ArgAction::Set)
+                .allow_hyphen_values(true)
+                .required(true)
+                .index(1),
+        )
+}
+
+fn wizard(matches: &ArgMatches) -> Result<(), Error> {
+    let output_path = matches
+        .get_one::<String>("OUTPUT")
+        .context("required OUTPUT argument not provided")?;
+
+    #[cfg(feature = "wizard")]
+    return write_secret(output_path, &wizard::recover()?);
+    #[cfg(not(feature = "wizard"))]
+    bail!(
+        "cannot recover to '{}': wizard requires paperback to be built with the \"wizard\" feature",
+        output_path
+    );
+}
+
+fn new_shards(
+    new_shard_types: impl IntoIterator<Item = NewShardKind>,
+    codeword_language: CodewordLanguage,
+    hardened_codewords: bool,
+    annotate: bool,
+    activity_record: bool,
+    pdf_options: &PdfOptions,
+    output: &mut Output,
+) -> Result<Vec<(DocumentId, ShardId, ChallengeChecksum)>, Error> {
     let mut quorum = UntrustedQuorum::new();
     loop {
         let idx = quorum.num_untrusted_shards() as u32;
-        let encrypted_shard: EncryptedKeyShard = read_multibase(match quorum.quorum_size() {
+        let (encrypted_shard, codewords) = read_key_shard(match quorum.quorum_size() {
             None => format!(
                 "Quorum contains no key shards.\nEnter key shard {}",
                 idx + 1
@@ -297,18 +2272,25 @@ fn new_shards(new_shard_types: impl IntoIterator<Item = NewShardKind>) -> Result
         })?;
         // TODO: Ask the user to input the checksum...
         println!(
-            "Key shard {} checksum: {}",
+            "Key shard {} checksum: {}",
+            idx + 1,
+            encrypted_shard.checksum_string()
+        );
+        println!(
+            "Key shard {} fingerprint: {}",
             idx + 1,
-            encrypted_shard.checksum_string()
+            encrypted_shard.fingerprint_words().join(" ")
         );
 
-        let codewords = read_codewords(format!("Enter key shard {} codewords", idx + 1))?;
+        let codewords =
+            codewords_or_read(codewords, format!("Enter key shard {} codewords", idx + 1))?;
         let shard = encrypted_shard
             .decrypt(&codewords)
-            .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
             .with_context(|| format!("decrypting key shard {}", idx + 1))?;
 
-        println!("Loaded key shard {}.", shard.id());
+        println!("Loaded key shard {} ({}).", shard.id(), shard.friendly_id());
+        // Shards carry the backup's creation time, so only show the expiry.
+        print_timestamps(&format!("Key shard {}", shard.id()), None, shard.expires());
         quorum.push_shard(shard);
 
         if idx + 1
@@ -320,39 +2302,52 @@ fn new_shards(new_shard_types: impl IntoIterator<Item = NewShardKind>) -> Result
         }
     }
 
-    let quorum = quorum.validate().map_err(|err| {
-        anyhow!(
-            "quorum failed to validate -- possible forgery! {}; groupings: {:?}",
-            err.message,
-            err.as_groups()
-        )
-    })?;
+    let quorum = quorum.validate()?;
 
-    let new_shards = new_shard_types
+    let minted = new_shard_types
         .into_iter()
         .map(|new| {
-            let s = quorum.new_shard(new).context("minting new key shards")?;
+            quorum
+                .new_shard_with_provenance(new)
+                .context("minting new key shards")
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let activity_record = activity_record
+        .then(|| {
+            quorum
+                .activity_record(minted.iter().map(|(s, _)| s))
+                .context("creating activity record")
+        })
+        .transpose()?;
+
+    let new_shards = minted
+        .into_iter()
+        .map(|(s, provenance)| {
+            println!("Key shard {}: {}", s.id(), provenance);
             Ok((
                 s.document_id(),
                 s.id(),
-                s.encrypt().expect("encrypt new shard"),
+                s.challenge_checksum(),
+                encrypt_key_shard(&s, codeword_language, hardened_codewords)?,
+                annotate.then_some(provenance),
             ))
         })
         .collect::<Result<Vec<_>, Error>>()?;
-
-    for (document_id, shard_id, (shard, codewords)) in new_shards {
-        (shard, codewords)
-            .to_pdf()?
-            .save(&mut BufWriter::new(File::create(format!(
-                "key_shard-{}-{}.pdf",
-                document_id, shard_id
-            ))?))?;
+    if quorum.extended_shard_count() > 0 {
+        println!(
+            "Added {} key shards to those in circulation.",
+            quorum.extended_shard_count()
+        );
     }
 
-    Ok(())
+    let shard_ids = save_key_shards(new_shards, pdf_options, output)?;
+    if let Some(record) = activity_record {
+        save_activity_record(&record, pdf_options, output)?;
+    }
+    Ok(shard_ids)
 }
 
-// paperback-cli expand-shards --interactive -n <SHARDS>
+// paperback-cli expand-shards --interactive [--annotate] [--activity-record] [--register [--label <LABEL>]...] -n <SHARDS>
 fn expand_shards_cli() -> Command {
     Command::new("expand-shards")
             .about(r#"Create new key shards from a quorum of old key shards. The new key shards are separate to existing key shards, which means you are increasing the number of shards in circulation. This operation is recommended when you wish to add a new key shard holder to an existing quorum (and you are still confident that no more than N-1 shard holders will conspire against you)."#)
@@ -369,18 +2364,65 @@ fn expand_shards_cli() -> Command {
                 .help(r#"Number of new shards to create."#)
                 .action(ArgAction::Set)
                 .required(true))
+            .arg(codeword_language_arg())
+            .arg(hardened_codewords_arg())
+            .arg(qr_encoding_arg())
+            .arg(qr_error_correction_arg())
+            .arg(text_fallback_arg())
+            .arg(pdf_language_arg())
+            .arg(shards_per_page_arg())
+            .arg(codeword_qr_arg())
+            .arg(codeword_placement_arg())
+            .arg(recovery_url_arg())
+            .arg(include_instructions_arg())
+            .arg(Arg::new("annotate")
+                .long("annotate")
+                .help("Print where each new key shard came from (when it was minted, and the ids of the key shards in the quorum) on its PDF.")
+                .action(ArgAction::SetTrue))
+            .arg(activity_record_arg())
+            .arg(export_text_arg())
+            .arg(output::export_qr_png_arg())
+            .arg(output::output_dir_arg())
+            .arg(output::output_format_arg())
+            .arg(output::force_arg())
+            .arg(registry::register_arg())
+            .arg(registry::label_arg())
 }
 
-fn expand_shards(matches: &ArgMatches) -> Result<(), Error> {
+fn expand_shards(matches: &ArgMatches) -> Result<Vec<PathBuf>, Error> {
     let num_new_shards: u32 = matches
         .get_one::<String>("new-shards")
         .context("required --new-shards argument not provided")?
         .parse()
         .context("--new-shards argument was not an unsigned integer")?;
-    new_shards((0..num_new_shards).map(|_| NewShardKind::NewShard))
+    let mut output = Output::from_matches(matches)?
+        .with_export_text(matches.get_one::<String>("export-text"))
+        .with_export_qr_png(matches.get_one::<String>("export-qr-png"))?
+        .with_format(output::get_output_format(matches)?);
+    let mut registry = Registry::from_matches(matches)?;
+    let shard_ids = new_shards(
+        (0..num_new_shards).map(|_| NewShardKind::NewShard),
+        get_codeword_language(matches)?,
+        matches.get_flag("hardened-codewords"),
+        matches.get_flag("annotate"),
+        matches.get_flag("activity-record"),
+        &get_key_shard_pdf_options(matches)?,
+        &mut output,
+    )?;
+    if let Some(registry) = &mut registry {
+        registry.register(matches, &shard_ids)?;
+        if let Some((document_id, _, _)) = shard_ids.first() {
+            println!(
+                "The shard registry records {} key shards in circulation for document {}.",
+                registry.count(document_id)?,
+                document_id
+            );
+        }
+    }
+    Ok(output.into_created())
 }
 
-// paperback-cli recreate-shards --interactive <SHARD-ID>...
+// paperback-cli recreate-shards --interactive [--activity-record] <SHARD-ID>...
 fn recreate_shards_cli() -> Command {
     Command::new("recreate-shards")
             .about(r#"Re-create key shards with a given identifier from a quorum of old key shards. The re-created key shards are identical to the original versions of said key shards. This operation is recommended when one of the key shard holders lose their key shard and need a replacement (this ensures that they cannot fool you into getting an distinct new shard in addition to the original)."#)
@@ -392,21 +2434,168 @@ fn recreate_shards_cli() -> Command {
                 .required(true))
             .arg(Arg::new("shard-ids")
                 .value_name("SHARD ID")
-                .help(r#"Shard identifier(s) of the shard(s) to recreate."#)
+                .help(r#"Shard identifier(s) of the shard(s) to recreate. Either the usual form ("hxxxxxxx") or the friendly form made of three words ("word-word-word") can be used."#)
+                .value_parser(clap::value_parser!(ShardId))
                 .action(ArgAction::Append)
                 .required(true))
+            .arg(codeword_language_arg())
+            .arg(hardened_codewords_arg())
+            .arg(qr_encoding_arg())
+            .arg(qr_error_correction_arg())
+            .arg(text_fallback_arg())
+            .arg(pdf_language_arg())
+            .arg(shards_per_page_arg())
+            .arg(codeword_qr_arg())
+            .arg(codeword_placement_arg())
+            .arg(recovery_url_arg())
+            .arg(include_instructions_arg())
+            .arg(activity_record_arg())
+            .arg(export_text_arg())
+            .arg(output::export_qr_png_arg())
+            .arg(output::output_dir_arg())
+            .arg(output::output_format_arg())
+            .arg(output::force_arg())
 }
 
-fn recreate_shards(matches: &ArgMatches) -> Result<(), Error> {
+fn recreate_shards(matches: &ArgMatches) -> Result<Vec<PathBuf>, Error> {
     let new_shard_list = matches
-        .get_many::<String>("shard-ids")
+        .get_many::<ShardId>("shard-ids")
         .context("required shard id arguments not given")?
         .cloned()
         .map(NewShardKind::ExistingShard);
-    new_shards(new_shard_list)
+    let mut output = Output::from_matches(matches)?
+        .with_export_text(matches.get_one::<String>("export-text"))
+        .with_export_qr_png(matches.get_one::<String>("export-qr-png"))?
+        .with_format(output::get_output_format(matches)?);
+    new_shards(
+        new_shard_list,
+        get_codeword_language(matches)?,
+        matches.get_flag("hardened-codewords"),
+        false,
+        matches.get_flag("activity-record"),
+        &get_key_shard_pdf_options(matches)?,
+        &mut output,
+    )?;
+    Ok(output.into_created())
+}
+
+// paperback-cli unseal [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -k <SHARDS>
+fn unseal_cli() -> Command {
+    save_backup_args(recovery_source_args(Command::new("unseal")
+            .about(r#"Re-create a sealed backup as an equivalent unsealed backup, so that new key shards can be created for it with expand-shards. This requires the main document and a full quorum of key shards. The new backup contains the same secret data, but has a new document ID and new key shards -- the old main document and key shards cannot be used with it, and should be destroyed once the new backup has been distributed."#))
+            .arg(Arg::new("shards")
+                .short('k')
+                .long("shards")
+                .value_name("NUM SHARDS")
+                .help("Number of key shards to create for the new backup (must not be smaller than the quorum size).")
+                .action(ArgAction::Set)
+                .required(true))
+            .arg(Arg::new("with-manifest")
+                .long("with-manifest")
+                .help("Also create a manifest document for the new backup (see backup --with-manifest).")
+                .action(ArgAction::SetTrue)))
+}
+
+fn unseal(matches: &ArgMatches) -> Result<Vec<PathBuf>, Error> {
+    let num_shards: u32 = matches
+        .get_one::<String>("shards")
+        .context("required --shards argument not provided")?
+        .parse()
+        .context("--shards argument was not an unsigned integer")?;
+    let pdf_options = PdfOptions {
+        layout: get_layout(matches)?,
+        qr_density: get_qr_density(matches)?,
+        ..get_key_shard_pdf_options(matches)?
+    };
+    let mut output = Output::from_matches(matches)?
+        .with_export_text(matches.get_one::<String>("export-text"))
+        .with_export_qr_png(matches.get_one::<String>("export-qr-png"))?
+        .with_format(output::get_output_format(matches)?);
+    let mut registry = Registry::from_matches(matches)?;
+
+    let quorum = read_recovery_quorum(matches)?;
+    let backup = quorum.unseal().context("unsealing backup")?;
+    check_backup_policy(
+        backup.main_document().quorum_size(),
+        num_shards,
+        get_max_quorum_size(matches)?,
+    )?;
+    println!(
+        "Unsealed backup as new document {}.",
+        backup.main_document().id()
+    );
+
+    save_backup(
+        matches,
+        &backup,
+        num_shards,
+        None,
+        &pdf_options,
+        &mut output,
+        registry.as_mut(),
+    )?;
+    Ok(output.into_created())
+}
+
+// paperback-cli rotate [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -k <SHARDS>
+fn rotate_cli() -> Command {
+    save_backup_args(recovery_source_args(Command::new("rotate")
+            .about(r#"Re-create a backup with entirely new keys, so that all of its existing key shards can be retired in one go (such as when too many key shards may have been exposed). This requires the main document and a full quorum of key shards. The new backup contains the same secret data and can create the same kinds of key shards as the old one, but has a new document ID and new key shards -- the old main document and key shards cannot be used with it, and should be destroyed once the new backup has been distributed."#))
+            .arg(Arg::new("shards")
+                .short('k')
+                .long("shards")
+                .value_name("NUM SHARDS")
+                .help("Number of key shards to create for the new backup (must not be smaller than the quorum size).")
+                .action(ArgAction::Set)
+                .required(true))
+            .arg(Arg::new("with-manifest")
+                .long("with-manifest")
+                .help("Also create a manifest document for the new backup (see backup --with-manifest).")
+                .action(ArgAction::SetTrue)))
+}
+
+fn rotate(matches: &ArgMatches) -> Result<Vec<PathBuf>, Error> {
+    let num_shards: u32 = matches
+        .get_one::<String>("shards")
+        .context("required --shards argument not provided")?
+        .parse()
+        .context("--shards argument was not an unsigned integer")?;
+    let pdf_options = PdfOptions {
+        layout: get_layout(matches)?,
+        qr_density: get_qr_density(matches)?,
+        ..get_key_shard_pdf_options(matches)?
+    };
+    let mut output = Output::from_matches(matches)?
+        .with_export_text(matches.get_one::<String>("export-text"))
+        .with_export_qr_png(matches.get_one::<String>("export-qr-png"))?
+        .with_format(output::get_output_format(matches)?);
+    let mut registry = Registry::from_matches(matches)?;
+
+    let quorum = read_recovery_quorum(matches)?;
+    let backup = quorum.rotate_keys().context("rotating backup keys")?;
+    check_backup_policy(
+        backup.main_document().quorum_size(),
+        num_shards,
+        get_max_quorum_size(matches)?,
+    )?;
+    println!(
+        "Rotated backup keys as new document {}.",
+        backup.main_document().id()
+    );
+
+    save_backup(
+        matches,
+        &backup,
+        num_shards,
+        None,
+        &pdf_options,
+        &mut output,
+        registry.as_mut(),
+    )?;
+    Ok(output.into_created())
 }
 
-// paperback-cli reprint --interactive [--main-document|--shard]
+// paperback-cli reprint [--interactive|--pdf <PDF>] [--main-document|--shard] [--layout <LAYOUT>] [--qr-density <DENSITY>]
 fn reprint_cli() -> Command {
     Command::new("reprint")
         .about(r#""Re-print" a paperback document by generating a new PDF from an existing PDF."#)
@@ -414,8 +2603,19 @@ fn reprint_cli() -> Command {
             Arg::new("interactive")
                 .long("interactive")
                 .help("Ask for data stored in QR codes interactively rather than scanning images.")
-                .action(ArgAction::SetTrue)
-                // TODO: Make this optional.
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pdf")
+                .long("pdf")
+                .value_name("PDF")
+                .help("Read the document to reprint from a PDF file generated by paperback. Key shard codewords are still requested interactively.")
+                .action(ArgAction::Set),
+        )
+        .group(
+            ArgGroup::new("source")
+                .arg("interactive")
+                .arg("pdf")
                 .required(true),
         )
         .arg(
@@ -436,11 +2636,24 @@ fn reprint_cli() -> Command {
                 .arg("shard")
                 .required(true),
         )
+        .arg(qr_encoding_arg())
+        .arg(qr_error_correction_arg())
+        .arg(text_fallback_arg())
+        .arg(pdf_language_arg())
+        .arg(layout_arg())
+        .arg(qr_density_arg())
+        .arg(output::output_dir_arg())
+        .arg(output::force_arg())
 }
 
-fn reprint(matches: &ArgMatches) -> Result<(), Error> {
-    let interactive = matches.get_flag("interactive");
-    ensure!(interactive, "PDF scanning not yet implemented");
+fn reprint(matches: &ArgMatches) -> Result<Vec<PathBuf>, Error> {
+    let pdf_path = matches.get_one::<String>("pdf");
+    let pdf_options = PdfOptions {
+        layout: get_layout(matches)?,
+        qr_density: get_qr_density(matches)?,
+        ..get_pdf_options(matches)?
+    };
+    let mut output = Output::from_matches(matches)?;
 
     let mut main_document: MainDocument;
     let mut shard_pair: (EncryptedKeyShard, KeyShardCodewords);
@@ -450,25 +2663,38 @@ fn reprint(matches: &ArgMatches) -> Result<(), Error> {
         .as_str()
     {
         "main-document" => {
-            main_document = read_multibase_qr("Enter a main document code")?;
+            main_document = match pdf_path {
+                Some(path) => read_pdf(path)?,
+                None => read_multibase_qr("Enter a main document code")?,
+            };
             // TODO: Ask the user to input the checksum...
             println!(
                 "Main document checksum: {}",
                 main_document.checksum_string()
             );
+            println!(
+                "Main document fingerprint: {}",
+                main_document.fingerprint_words().join(" ")
+            );
 
             let pathname = format!("main-document-{}.pdf", main_document.id());
             (&mut main_document, pathname)
         }
         "shard" => {
-            let encrypted_shard: EncryptedKeyShard = read_multibase("Enter key shard")?;
+            let (encrypted_shard, codewords) = match pdf_path {
+                Some(path) => (read_pdf(path)?, None),
+                None => read_key_shard("Enter key shard")?,
+            };
             // TODO: Ask the user to input the checksum...
             println!("Key shard checksum: {}", encrypted_shard.checksum_string());
-            let codewords = read_codewords("Key shard codewords")?;
+            println!(
+                "Key shard fingerprint: {}",
+                encrypted_shard.fingerprint_words().join(" ")
+            );
+            let codewords = codewords_or_read(codewords, "Key shard codewords")?;
 
             let shard = encrypted_shard
                 .decrypt(codewords.clone())
-                .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
                 .with_context(|| "decrypting shard")?;
             let pathname = format!("key-shard-{}-{}.pdf", shard.document_id(), shard.id());
 
@@ -479,9 +2705,473 @@ fn reprint(matches: &ArgMatches) -> Result<(), Error> {
         _ => bail!("neither --shard nor --main-document type flags passed"),
     };
 
-    pdf.to_pdf()?
-        .save(&mut BufWriter::new(File::create(path_basename)?))?;
+    let pdf = pdf.to_pdf_with_options(&pdf_options)?;
+    output.write(&path_basename, |file| Ok(pdf.save(file)?))?;
+    Ok(output.into_created())
+}
+
+// paperback-cli info [--interactive --main-document|--shard|--activity-record|INPUT] [--decrypt] [--verify-with <MAIN DOCUMENT>]
+fn info_cli() -> Command {
+    Command::new("info")
+        .about(r#"Show information about a paperback main document, key shard, or activity record, without needing a quorum of key shards."#)
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .help("Ask for data stored in QR codes interactively rather than reading it from a file.")
+                .action(ArgAction::SetTrue)
+                .requires("type"),
+        )
+        .arg(
+            Arg::new("main-document")
+                .long("main-document")
+                .help(r#"The document is a paperback main document."#)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("shard")
+                .long("shard")
+                .help(r#"The document is a paperback key shard."#)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("activity-record")
+                .long("activity-record")
+                .help(r#"The document is a paperback activity record (see expand-shards --activity-record)."#)
+                .action(ArgAction::SetTrue),
+        )
+        .group(
+            ArgGroup::new("type")
+                .arg("main-document")
+                .arg("shard")
+                .arg("activity-record"),
+        )
+        .arg(
+            Arg::new("decrypt")
+                .long("decrypt")
+                .help("Ask for the codewords of a key shard in order to show the information stored inside the (encrypted) key shard.")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("verify-with")
+                .long("verify-with")
+                .value_name("MAIN DOCUMENT")
+                .help(r#"Path to the main document (a PDF file or text data) of the backup that an activity record or (decrypted) key shard is expected to belong to. Activity records are only reported as valid if they were signed by that backup, and key shards are checked against the key shards the backup was created with (see backup --commit-shards)."#)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("INPUT")
+                .help(r#"Path to a PDF file generated by paperback, or a file containing the text data of a document (one QR code per line for main documents). The document type is auto-detected unless --main-document, --shard or --activity-record is given."#)
+                .action(ArgAction::Set)
+                .index(1),
+        )
+        .group(
+            ArgGroup::new("source")
+                .arg("interactive")
+                .arg("INPUT")
+                .required(true),
+        )
+}
+
+enum Document {
+    MainDocument(MainDocument),
+    KeyShard(EncryptedKeyShard),
+    ActivityRecord(ActivityRecord),
+}
+
+fn parse_main_document_text(text: &str) -> Result<MainDocument, Error> {
+    let lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    // A single line might be the entire document rather than a QR code part.
+    if let [line] = &lines[..] {
+        if let Ok(main_document) = parse_multibase(line) {
+            return Ok(main_document);
+        }
+    }
+    let mut joiner = qr::Joiner::new();
+    for line in lines {
+        joiner.add_part(parse_multibase(line)?)?;
+    }
+    ensure!(
+        joiner.complete(),
+        "missing main document qr codes [{}]",
+        format_part_indices(&joiner.missing_parts())
+    );
+    // Dispatch on the version, so that documents from unsupported versions of
+    // paperback get a clear error.
+    match AnyMainDocument::from_wire(joiner.combine_parts()?).context("parse inner qr code data")? {
+        AnyMainDocument::V0(main_document) => Ok(main_document),
+    }
+}
+
+fn read_document_file(path: &str, document_type: Option<&str>) -> Result<Document, Error> {
+    let data = fs::read(path).with_context(|| format!("failed to read file '{}'", path))?;
+    let (main_document, key_shard, activity_record) = if data.starts_with(b"%PDF-") {
+        (
+            MainDocument::from_pdf(&data).map_err(Error::from),
+            EncryptedKeyShard::from_pdf(&data).map_err(Error::from),
+            ActivityRecord::from_pdf(&data).map_err(Error::from),
+        )
+    } else {
+        let text = String::from_utf8(data).context("file is neither a PDF nor text")?;
+        (
+            parse_main_document_text(&text),
+            parse_multibase(text.trim()),
+            parse_multibase(text.trim()),
+        )
+    };
+    match document_type {
+        Some("main-document") => main_document.map(Document::MainDocument),
+        Some("shard") => key_shard.map(Document::KeyShard),
+        Some("activity-record") => activity_record.map(Document::ActivityRecord),
+        _ => main_document
+            .map(Document::MainDocument)
+            .or_else(|_| key_shard.map(Document::KeyShard))
+            .or_else(|_| activity_record.map(Document::ActivityRecord))
+            .context("file contains neither a main document, a key shard, nor an activity record"),
+    }
+    .with_context(|| format!("failed to parse '{}'", path))
+}
+
+fn info(matches: &ArgMatches) -> Result<(), Error> {
+    let document_type = matches.get_one::<clap::Id>("type").map(clap::Id::as_str);
+    let document = match matches.get_one::<String>("INPUT") {
+        Some(path) => read_document_file(path, document_type)?,
+        None => match document_type {
+            Some("main-document") => {
+                Document::MainDocument(read_multibase_qr("Enter a main document code")?)
+            }
+            Some("shard") => Document::KeyShard(read_multibase("Enter key shard")?),
+            Some("activity-record") => {
+                Document::ActivityRecord(read_multibase("Enter activity record")?)
+            }
+            // We should never reach here.
+            _ => bail!("neither --shard, --main-document nor --activity-record type flags passed"),
+        },
+    };
+
+    match document {
+        Document::MainDocument(main_document) => {
+            println!("Type: main document");
+            println!("Version: {}", main_document.version());
+            println!("Document ID: {}", main_document.id());
+            if let Some(title) = main_document.title() {
+                println!("Title: {}", title);
+            }
+            for info in main_document.public_info() {
+                println!("{}: {}", info.label(), info.value());
+            }
+            println!("Quorum size: {}", main_document.quorum_size());
+            if let Some(commitment) = main_document.shard_commitment() {
+                println!("Committed key shards: {}", commitment.num_shards());
+            }
+            if main_document.contains_seed_phrase() {
+                println!("Contents: BIP-39 seed phrase");
+            }
+            println!("Checksum: {}", main_document.checksum_string());
+            println!(
+                "Fingerprint: {}",
+                main_document.fingerprint_words().join(" ")
+            );
+            print_timestamps(
+                "Main document",
+                main_document.created(),
+                main_document.expires(),
+            );
+            // Whether a backup is sealed is only stored in the secret shared
+            // between the key shards.
+            println!("Sealed: unknown (requires a quorum of key shards)");
+            println!(
+                "Signature: {}",
+                match main_document.has_valid_signature() {
+                    true => "valid",
+                    false => "INVALID -- possible forgery!",
+                }
+            );
+        }
+        Document::KeyShard(encrypted_shard) => {
+            println!("Type: key shard");
+            println!("Checksum: {}", encrypted_shard.checksum_string());
+            println!(
+                "Fingerprint: {}",
+                encrypted_shard.fingerprint_words().join(" ")
+            );
+            if !matches.get_flag("decrypt") {
+                println!("(Use --decrypt and enter the key shard codewords to show more details.)");
+                return Ok(());
+            }
+
+            let codewords = read_codewords("Enter key shard codewords")?;
+            let shard = encrypted_shard
+                .decrypt(&codewords)
+                .context("decrypting key shard")?;
+            println!("Version: {}", shard.version());
+            println!("Document ID: {}", shard.document_id());
+            println!("Shard ID: {}", shard.id());
+            println!("Friendly Shard ID: {}", shard.friendly_id());
+            println!("Quorum size: {}", shard.quorum_size());
+            print_timestamps("Backup", shard.created(), shard.expires());
+            println!("Sealed: unknown (requires a quorum of key shards)");
+            println!(
+                "Signature: {}",
+                match shard.has_valid_signature() {
+                    true => "valid",
+                    false => "INVALID -- possible forgery!",
+                }
+            );
+            if let Some(path) = matches.get_one::<String>("verify-with") {
+                let main_document = read_verify_with(path)?;
+                println!(
+                    "Commitment: {}",
+                    match main_document.is_committed_shard(&shard) {
+                        Some(true) =>
+                            format!("valid (created along with document {})", main_document.id()),
+                        Some(false) => format!(
+                            "NOT COMMITTED -- not created along with document {} (created later, or forged)!",
+                            main_document.id()
+                        ),
+                        None => format!(
+                            "unknown (document {} has no key shard commitment)",
+                            main_document.id()
+                        ),
+                    }
+                );
+            }
+        }
+        Document::ActivityRecord(record) => {
+            println!("Type: activity record");
+            println!("Version: {}", record.version());
+            println!("Document ID: {}", record.document_id());
+            if let Some(minted) = record.minted() {
+                println!("Minted: {}", format_date(minted));
+            }
+            println!("Minted key shards: {}", record.shard_ids().join(" "));
+            println!("Quorum: {}", record.quorum().join(" "));
+            match matches.get_one::<String>("verify-with") {
+                Some(path) => {
+                    let main_document = read_verify_with(path)?;
+                    println!(
+                        "Signature: {}",
+                        match record.is_signed_by(&main_document) {
+                            true => format!("valid (signed by document {})", main_document.id()),
+                            false =>
+                                format!("INVALID -- not signed by document {}!", main_document.id()),
+                        }
+                    );
+                }
+                None => {
+                    println!(
+                        "Signature: {}",
+                        match record.has_valid_signature() {
+                            true =>
+                                "valid (use --verify-with to check it against the main document)",
+                            false => "INVALID -- possible forgery!",
+                        }
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the main document given with info --verify-with.
+fn read_verify_with(path: &str) -> Result<MainDocument, Error> {
+    match read_document_file(path, Some("main-document"))? {
+        Document::MainDocument(main_document) => Ok(main_document),
+        _ => unreachable!("read_document_file returned a non-main document"),
+    }
+}
+
+// paperback-cli check-shard [--document-checksum <CHECKSUM>] [--shard-checksum <CHECKSUM>] [--interactive|INPUT]
+fn check_shard_cli() -> Command {
+    Command::new("check-shard")
+        .about(r#"Check that a key shard is intact and belongs to a particular backup, without needing a quorum of key shards. This lets key shard holders check their key shard (given its codewords and the checksum printed on the main document) at any time."#)
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .help("Ask for the key shard data interactively rather than reading it from a file.")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("document-checksum")
+                .long("document-checksum")
+                .value_name("CHECKSUM")
+                .help("Checksum of the main document the key shard is expected to belong to, as printed on the main document. If not given, it is requested interactively.")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("shard-checksum")
+                .long("shard-checksum")
+                .value_name("CHECKSUM")
+                .help("Checksum printed on the key shard. If not given, the checksum of the key shard data is printed so it can be compared by hand.")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("INPUT")
+                .help(r#"Path to a key shard PDF file generated by paperback, or a file containing the text data of a key shard."#)
+                .action(ArgAction::Set)
+                .index(1),
+        )
+        .group(
+            ArgGroup::new("source")
+                .arg("interactive")
+                .arg("INPUT")
+                .required(true),
+        )
+}
+
+/// Compare a checksum typed in by the user with the expected checksum string,
+/// ignoring whitespace and case (checksums are z-base-32, which is lowercase).
+fn checksum_matches(input: &str, checksum: &str) -> bool {
+    input
+        .split_whitespace()
+        .collect::<String>()
+        .eq_ignore_ascii_case(checksum)
+}
+
+fn check_shard(matches: &ArgMatches) -> Result<(), Error> {
+    let (encrypted_shard, codewords) = match matches.get_one::<String>("INPUT") {
+        Some(path) => match read_document_file(path, Some("shard"))? {
+            Document::KeyShard(encrypted_shard) => (encrypted_shard, None),
+            _ => unreachable!("read_document_file returned a non-key shard"),
+        },
+        None => read_key_shard("Enter key shard")?,
+    };
+    let shard_checksum = matches.get_one::<String>("shard-checksum");
+    let document_checksum = match matches.get_one::<String>("document-checksum") {
+        Some(checksum) => checksum.clone(),
+        None => read_multiline("Enter main document checksum")?,
+    };
+
+    let codewords = codewords_or_read(codewords, "Enter key shard codewords")?;
+    // Decryption checks that the key shard data has not been corrupted.
+    let shard = encrypted_shard
+        .decrypt(&codewords)
+        .context("decrypting key shard")?;
+
+    println!("Key shard: {}", shard.id());
+    println!("Document ID: {}", shard.document_id());
+    let mut failures = Vec::new();
+    match shard_checksum {
+        Some(checksum) if checksum_matches(checksum, &encrypted_shard.checksum_string()) => {
+            println!("Shard checksum: matches");
+        }
+        Some(_) => {
+            println!(
+                "Shard checksum: MISMATCH (key shard data has checksum {})",
+                encrypted_shard.checksum_string()
+            );
+            failures.push("the key shard data does not match the key shard checksum");
+        }
+        None => println!(
+            "Shard checksum: {} (compare with the checksum printed on the key shard)",
+            encrypted_shard.checksum_string()
+        ),
+    }
+    match shard.has_valid_signature() {
+        true => println!("Signature: valid"),
+        false => {
+            println!("Signature: INVALID -- possible forgery!");
+            failures.push("the key shard has an invalid signature");
+        }
+    }
+    match checksum_matches(&document_checksum, &shard.document_checksum_string()) {
+        true => println!("Document checksum: matches"),
+        false => {
+            println!(
+                "Document checksum: MISMATCH (key shard belongs to the main document with checksum {})",
+                shard.document_checksum_string()
+            );
+            failures.push("the key shard does not belong to the given main document");
+        }
+    }
+    print_timestamps("Backup", shard.created(), shard.expires());
+
+    ensure!(
+        failures.is_empty(),
+        "key shard {} failed the check: {}",
+        shard.id(),
+        failures.join(", ")
+    );
+    println!(
+        "Key shard {} is intact and belongs to document {}.",
+        shard.id(),
+        shard.document_id()
+    );
+    Ok(())
+}
+
+fn ocr_shard_cli() -> Command {
+    Command::new("ocr-shard")
+        .about(r#"Reconstruct a key shard from a photo or scan of its text fallback, for key shards whose QR codes are too damaged to be scanned. Requires paperback to be built with the "ocr" feature."#)
+        .arg(Arg::new("shard-checksum")
+            .long("shard-checksum")
+            .value_name("CHECKSUM")
+            .help("Checksum printed on the key shard, which is used to correct characters misread by OCR. If not provided, you will be asked to enter it.")
+            .action(ArgAction::Set))
+        .arg(Arg::new("IMAGE")
+            .help("Photo or scan of the key shard (ideally cropped to the text fallback of the key shard data).")
+            .action(ArgAction::Set)
+            .required(true)
+            .index(1))
+        .arg(Arg::new("OUTPUT")
+            .help(r#"Path to write the reconstructed key shard to ("-" to write to stdout), which can be passed to recover --shard-file."#)
+            .action(ArgAction::Set)
+            .allow_hyphen_values(true)
+            .required(true)
+            .index(2))
+}
+
+#[cfg_attr(not(feature = "ocr"), allow(unused_variables))]
+fn read_ocr_text(path: &str) -> Result<String, Error> {
+    #[cfg(feature = "ocr")]
+    {
+        ocr::read_text(path)
+    }
+    #[cfg(not(feature = "ocr"))]
+    {
+        bail!("ocr-shard requires paperback to be built with the \"ocr\" feature")
+    }
+}
+
+fn ocr_shard(matches: &ArgMatches) -> Result<(), Error> {
+    let image_path = matches
+        .get_one::<String>("IMAGE")
+        .context("required IMAGE argument not provided")?;
+    let output_path = matches
+        .get_one::<String>("OUTPUT")
+        .context("required OUTPUT argument not provided")?;
+
+    let text = read_ocr_text(image_path)?;
+    let checksum = match matches.get_one::<String>("shard-checksum") {
+        Some(checksum) => checksum.clone(),
+        None => read_multiline("Enter the key shard checksum")?,
+    };
+    // The checksum may have been copied from its text fallback.
+    let checksum = checksum.replace('-', "");
+
+    let data = reconstruct_text_fallback(&text, |data| {
+        EncryptedKeyShard::from_wire(data)
+            .is_ok_and(|shard| checksum_matches(&checksum, &shard.checksum_string()))
+    })
+    .with_context(|| format!("failed to reconstruct key shard from '{}'", image_path))?;
+    let shard = EncryptedKeyShard::from_wire(data).map_err(|err| anyhow!(err))?;
 
+    let contents = shard.to_wire_multibase(multibase::Base::Base32Z) + "\n";
+    if output_path == "-" {
+        print!("{}", contents);
+    } else {
+        fs::write(output_path, contents)
+            .with_context(|| format!("failed to write key shard to '{}'", output_path))?;
+        println!(
+            "Reconstructed key shard with checksum {}.",
+            shard.checksum_string()
+        );
+    }
     Ok(())
 }
 
@@ -490,30 +3180,149 @@ fn cli() -> Command {
         .version("0.0.0")
         .author("Aleksa Sarai <cyphar@cyphar.com>")
         .about("Operate on a paperback backup using a basic CLI interface.")
-        // paperback-cli backup [--sealed] -n <QUORUM SIZE> -k <SHARDS> INPUT
+        .arg(Arg::new("verbose")
+            .short('v')
+            .long("verbose")
+            .help("Log what paperback is doing (and how long each step takes) to stderr. Pass twice for even more detail.")
+            .action(ArgAction::Count)
+            .global(true))
+        // paperback-cli backup [--sealed|--no-recreate-shards] [--commit-shards] [--title <TITLE>] [--derive-xpub [<PATH>]] [--expires-after <DAYS>] [--recipient <RECIPIENT>] [--format <FORMAT>] [--save-session <PATH>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -n <QUORUM SIZE> -k <SHARDS> [--input-prompt|--input-env <VAR>|--payload <NAME=PATH>...|--input-many <FILE>...|INPUT]
+        // paperback-cli backup --resume <PATH> [--save-session <PATH>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--register [--label <LABEL>]...] -k <SHARDS>
+        // paperback-cli backup --recipe <PATH> [--commit-shards] [--title <TITLE>] [--derive-xpub [<PATH>]] [--expires-after <DAYS>] [--recipient <RECIPIENT>] [--format <FORMAT>] [--save-session <PATH>] [--with-manifest] [--with-recovery-instructions] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register]
         .subcommand(backup_cli())
-        // paperback-cli recover --interactive
+        // paperback-cli recover [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--select <NAME>] [--digest-algorithm <ALGORITHM>] [--expect-digest <DIGEST>] OUTPUT
+        // paperback-cli recover [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--select <NAME>] [--digest-algorithm <ALGORITHM>] [--expect-digest <DIGEST>] --exec <COMMAND>
+        // paperback-cli recover [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--select <NAME>] [--digest-algorithm <ALGORITHM>] [--expect-digest <DIGEST>] --dry-run
+        // paperback-cli recover [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--digest-algorithm <ALGORITHM>] --extract-dir <DIR>
         .subcommand(recover_cli())
-        // paperback-cli expand-shards --interactive -n <SHARDS>
+        // paperback-cli wizard OUTPUT
+        .subcommand(wizard_cli())
+        // paperback-cli self-test [--quorum-size <QUORUM SIZE>] [--shards <SHARDS>] [--secret-size <BYTES>]
+        .subcommand(selftest::self_test_cli())
+        // paperback-cli expand-shards --interactive [--annotate] [--activity-record] [--register [--label <LABEL>]...] -n <SHARDS>
         .subcommand(expand_shards_cli())
-        // paperback-cli recreate-shards --interactive <SHARD-ID>...
+        // paperback-cli recreate-shards --interactive [--activity-record] <SHARD-ID>...
         .subcommand(recreate_shards_cli())
-        // paperback-cli reprint --interactive [--main-document|--shard]
+        // paperback-cli unseal [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -k <SHARDS>
+        .subcommand(unseal_cli())
+        // paperback-cli rotate [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -k <SHARDS>
+        .subcommand(rotate_cli())
+        // paperback-cli reprint [--interactive|--pdf <PDF>] [--main-document|--shard] [--layout <LAYOUT>] [--qr-density <DENSITY>]
         .subcommand(reprint_cli())
+        // paperback-cli info [--interactive --main-document|--shard|--activity-record|INPUT] [--decrypt] [--verify-with <MAIN DOCUMENT>]
+        .subcommand(info_cli())
+        // paperback-cli check-shard [--document-checksum <CHECKSUM>] [--shard-checksum <CHECKSUM>] [--interactive|INPUT]
+        .subcommand(check_shard_cli())
+        // paperback-cli ocr-shard [--shard-checksum <CHECKSUM>] IMAGE OUTPUT
+        .subcommand(ocr_shard_cli())
+        // paperback-cli shards list <DOCUMENT ID>
+        .subcommand(registry::subcommands())
+        // paperback-cli challenge new
+        // paperback-cli challenge respond [--shard-file <PATH>] <CHALLENGE>
+        // paperback-cli challenge verify <CHALLENGE> <RESPONSE>
+        .subcommand(challenge::subcommands())
+        // paperback-cli convert from-slip39 [--passphrase] [--sealed] [--title <TITLE>] [--with-manifest] [--with-recovery-instructions [--holder <NAME>]...] [--layout <LAYOUT>] [--qr-density <DENSITY>] [--export-text <DIR>] [--also-raw] [--max-quorum-size <QUORUM SIZE>] [--register [--label <LABEL>]...] -n <QUORUM SIZE> -k <SHARDS> [SHARES]
+        // paperback-cli convert to-slip39 [--interactive|--scan-camera] [--pdf <PDF>...|--scan-image <IMAGE>...|--qr-strings <PATH>...|--watch-dir <DIR>] [--shard-file <PATH>...] [--identity <PATH>] [--select <NAME>] [--passphrase] [--group-threshold <GROUPS>] --group <THRESHOLD/COUNT>...
+        .subcommand(convert::subcommands())
         // paperback-cli raw ...
         .subcommand(raw::subcommands())
 }
 
-fn main() -> Result<(), Box<dyn StdError>> {
+// Exit codes, so that scripts can tell different kinds of failures apart.
+// NOTE: These are documented in the README, so keep them in sync.
+const EXIT_FAILURE: u8 = 1;
+// This matches the exit code clap uses for usage errors.
+const EXIT_BAD_ARGUMENTS: u8 = 2;
+const EXIT_CHECKSUM_MISMATCH: u8 = 3;
+const EXIT_FORGED_QUORUM: u8 = 4;
+const EXIT_INSUFFICIENT_SHARDS: u8 = 5;
+const EXIT_DECRYPTION_FAILURE: u8 = 6;
+
+fn exit_code(err: &Error) -> u8 {
+    for cause in err.chain() {
+        if cause.is::<DigestMismatchError>() {
+            return EXIT_CHECKSUM_MISMATCH;
+        }
+        if let Some(err) = cause.downcast_ref::<InconsistentQuorumError>() {
+            return match err.kind() {
+                InconsistentQuorumKind::Inconsistent => EXIT_CHECKSUM_MISMATCH,
+                InconsistentQuorumKind::Forged | InconsistentQuorumKind::ConflictingShards => {
+                    EXIT_FORGED_QUORUM
+                }
+                InconsistentQuorumKind::InsufficientShards => EXIT_INSUFFICIENT_SHARDS,
+            };
+        }
+        if let Some(err) = cause.downcast_ref::<paperback::Error>() {
+            return match err {
+                paperback::Error::QuorumTooSmall(_)
+                | paperback::Error::QuorumTooLarge { .. }
+                | paperback::Error::SecretTooLarge { .. }
+                | paperback::Error::ShardsLessThanQuorum { .. }
+                | paperback::Error::InvalidPayloadName(_)
+                | paperback::Error::UnknownPayload(_)
+                | paperback::Error::MultiplePayloads(_) => EXIT_BAD_ARGUMENTS,
+                paperback::Error::CorruptedCiphertext(_)
+                | paperback::Error::Shamir(_)
+                | paperback::Error::ArmorHeaderMismatch { .. } => EXIT_CHECKSUM_MISMATCH,
+                // The recovered secrets don't match the quorum's identity.
+                paperback::Error::InvariantViolation(_) => EXIT_FORGED_QUORUM,
+                paperback::Error::InvalidCodewords(_)
+                | paperback::Error::UnknownCodeword { .. }
+                | paperback::Error::WrongCodewords
+                | paperback::Error::AeadDecryption(_)
+                | paperback::Error::MissingIdentity
+                | paperback::Error::RecipientDecrypt(_) => EXIT_DECRYPTION_FAILURE,
+                _ => EXIT_FAILURE,
+            };
+        }
+    }
+    EXIT_FAILURE
+}
+
+/// Print the paths of the files created by a subcommand.
+pub(crate) fn print_created(paths: Vec<PathBuf>) {
+    for path in paths {
+        println!("Wrote '{}'.", path.display());
+    }
+}
+
+/// Install a `tracing` subscriber which logs to stderr, if any `--verbose`
+/// flags were passed.
+fn init_tracing(verbose: u8) {
+    let level = match verbose {
+        0 => return,
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(io::stderr)
+        .init();
+}
+
+fn run() -> Result<(), Error> {
     let mut app = cli();
+    let matches = app.get_matches_mut();
+    init_tracing(matches.get_count("verbose"));
 
-    match app.get_matches_mut().subcommand() {
+    match matches.subcommand() {
         Some(("raw", sub_matches)) => raw::submatch(&mut app, sub_matches),
-        Some(("backup", sub_matches)) => backup(sub_matches),
+        Some(("backup", sub_matches)) => backup(sub_matches).map(print_created),
         Some(("recover", sub_matches)) => recover(sub_matches),
-        Some(("expand-shards", sub_matches)) => expand_shards(sub_matches),
-        Some(("recreate-shards", sub_matches)) => recreate_shards(sub_matches),
-        Some(("reprint", sub_matches)) => reprint(sub_matches),
+        Some(("wizard", sub_matches)) => wizard(sub_matches),
+        Some(("self-test", sub_matches)) => selftest::self_test(sub_matches),
+        Some(("expand-shards", sub_matches)) => expand_shards(sub_matches).map(print_created),
+        Some(("recreate-shards", sub_matches)) => recreate_shards(sub_matches).map(print_created),
+        Some(("unseal", sub_matches)) => unseal(sub_matches).map(print_created),
+        Some(("rotate", sub_matches)) => rotate(sub_matches).map(print_created),
+        Some(("reprint", sub_matches)) => reprint(sub_matches).map(print_created),
+        Some(("info", sub_matches)) => info(sub_matches),
+        Some(("check-shard", sub_matches)) => check_shard(sub_matches),
+        Some(("ocr-shard", sub_matches)) => ocr_shard(sub_matches),
+        Some(("shards", sub_matches)) => registry::submatch(&mut app, sub_matches),
+        Some(("challenge", sub_matches)) => challenge::submatch(&mut app, sub_matches),
+        Some(("convert", sub_matches)) => convert::submatch(&mut app, sub_matches),
         Some((subcommand, _)) => {
             // We should never end up here.
             app.print_help()?;
@@ -523,12 +3332,85 @@ fn main() -> Result<(), Box<dyn StdError>> {
             app.print_help()?;
             Err(anyhow!("no subcommand specified"))
         }
-    }?;
+    }
+}
 
-    Ok(())
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            ExitCode::from(exit_code(&err))
+        }
+    }
 }
 
 #[test]
 fn verify_cli() {
     cli().debug_assert();
 }
+
+#[test]
+fn cjk_codeword_language_rejected_for_pdfs() {
+    let backup = |language| {
+        cli().try_get_matches_from([
+            "paperback",
+            "backup",
+            "--codeword-language",
+            language,
+            "-n",
+            "2",
+            "-k",
+            "3",
+            "secret.txt",
+        ])
+    };
+    assert!(backup("es").is_ok());
+    assert!(backup("ja").is_err());
+
+    let raw_backup = |language| {
+        cli().try_get_matches_from([
+            "paperback",
+            "raw",
+            "backup",
+            "--codeword-language",
+            language,
+            "-n",
+            "2",
+            "-k",
+            "3",
+            "secret.txt",
+        ])
+    };
+    assert!(raw_backup("ja").is_ok());
+}
+
+#[test]
+fn recover_dry_run_digest() {
+    let backup = paperback::Backup::new(2, b"hello").unwrap();
+    let mut quorum = UntrustedQuorum::new();
+    quorum.main_document(backup.main_document().clone());
+    for shard in backup.next_shards(2).unwrap() {
+        quorum.push_shard(shard);
+    }
+    let quorum = quorum.validate().unwrap();
+
+    let dry_run = |args: &[&str]| {
+        let matches = cli()
+            .try_get_matches_from(
+                ["paperback", "recover", "--interactive", "--dry-run"]
+                    .iter()
+                    .chain(args),
+            )
+            .unwrap();
+        let (_, matches) = matches.subcommand().unwrap();
+        recover_dry_run(matches, &quorum)
+    };
+    // sha256sum and b3sum of "hello".
+    let sha256 = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    let blake3 = "ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f";
+    assert!(dry_run(&[]).is_ok());
+    assert!(dry_run(&["--expect-digest", sha256]).is_ok());
+    assert!(dry_run(&["--expect-digest", blake3]).is_err());
+    assert!(dry_run(&["--digest-algorithm", "blake3", "--expect-digest", blake3]).is_ok());
+}
@@ -0,0 +1,42 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! OCR of the text fallbacks printed next to QR codes, for key shards whose QR
+//! codes are too damaged to be scanned.
+
+use anyhow::{anyhow, Context, Error};
+use leptess::{LepTess, Variable};
+
+/// Characters which can appear in a text fallback, as well as the characters
+/// they are commonly confused with (which `reconstruct_text_fallback` maps
+/// back to zbase32 characters).
+const OCR_CHARS: &str = "ybndrfg8ejkmcpqxot1uwisza345h769YBNDRFGEJKMCPQXOTUWISZAH0lLvV2#-";
+
+/// Read the text in the image at `path` using Tesseract.
+pub(crate) fn read_text(path: &str) -> Result<String, Error> {
+    let mut tesseract = LepTess::new(None, "eng").context("failed to initialise tesseract")?;
+    tesseract
+        .set_variable(Variable::TesseditCharWhitelist, OCR_CHARS)
+        .context("failed to configure tesseract")?;
+    tesseract
+        .set_image(path)
+        .with_context(|| format!("failed to load image '{}'", path))?;
+    tesseract
+        .get_utf8_text()
+        .map_err(|err| anyhow!("failed to read text from image '{}': {}", path, err))
+}
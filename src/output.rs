@@ -0,0 +1,287 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{bail, Context, Error};
+use clap::{Arg, ArgAction, ArgMatches};
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{pdf::qr::QrModules, PdfOptions, ToPdf, ToSvg};
+
+pub(crate) fn output_dir_arg() -> Arg {
+    Arg::new("output-dir")
+        .long("output-dir")
+        .value_name("DIR")
+        .help("Directory to write the generated documents to (created if it doesn't exist).")
+        .default_value(".")
+        .action(ArgAction::Set)
+}
+
+pub(crate) fn force_arg() -> Arg {
+    Arg::new("force")
+        .long("force")
+        .help("Overwrite existing files with the same name as a generated document, rather than refusing to continue.")
+        .action(ArgAction::SetTrue)
+}
+
+pub(crate) fn output_format_arg() -> Arg {
+    Arg::new("output-format")
+        .long("output-format")
+        .value_name("FORMAT")
+        .help(r#"Format of the generated main documents and key shards ("pdf" or "svg"). With "svg", each page is written to its own file (with a "-<PAGE>" suffix if there are several pages), which is useful for post-processing the documents (such as for laser engraving). Other documents (such as manifests) are always PDFs."#)
+        .value_parser(["pdf", "svg"])
+        .default_value("pdf")
+        .action(ArgAction::Set)
+}
+
+pub(crate) fn export_qr_png_arg() -> Arg {
+    Arg::new("export-qr-png")
+        .long("export-qr-png")
+        .value_name("DIR")
+        .help(r#"Also write each QR code of the generated main documents and key shards to its own high-resolution PNG image in DIR (named after the document, the kind of QR code, and its index). This is useful for printing the QR codes with other tools."#)
+        .action(ArgAction::Set)
+}
+
+/// Number of pixels along each side of a QR code module in the images
+/// written for `--export-qr-png`.
+#[cfg(feature = "qr-png")]
+const QR_PNG_MODULE_PIXELS: u32 = 16;
+
+/// Width of the blank border (in modules) around the QR codes in the images
+/// written for `--export-qr-png`, as required by the QR code specification.
+#[cfg(feature = "qr-png")]
+const QR_PNG_QUIET_ZONE: usize = 4;
+
+/// Format of generated main documents and key shards (see `--output-format`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum DocumentFormat {
+    #[default]
+    Pdf,
+    Svg,
+}
+
+pub(crate) fn get_output_format(matches: &ArgMatches) -> Result<DocumentFormat, Error> {
+    match matches
+        .get_one::<String>("output-format")
+        .context("required --output-format argument not provided")?
+        .as_str()
+    {
+        "pdf" => Ok(DocumentFormat::Pdf),
+        "svg" => Ok(DocumentFormat::Svg),
+        // We should never reach here.
+        format => bail!("unsupported --output-format '{}'", format),
+    }
+}
+
+/// Where generated documents are written.
+///
+/// Each file is first written to a temporary file in the same directory and
+/// then renamed into place, so a failure never leaves a truncated document
+/// behind. Existing files are not overwritten unless `--force` was given.
+pub(crate) struct Output {
+    dir: PathBuf,
+    export_text: Option<PathBuf>,
+    export_qr_png: Option<PathBuf>,
+    format: DocumentFormat,
+    force: bool,
+    created: Vec<PathBuf>,
+}
+
+impl Output {
+    pub(crate) fn from_matches(matches: &ArgMatches) -> Result<Self, Error> {
+        let dir = matches
+            .get_one::<String>("output-dir")
+            .context("required --output-dir argument not provided")?;
+        Ok(Self {
+            dir: PathBuf::from(dir),
+            export_text: None,
+            export_qr_png: None,
+            format: DocumentFormat::default(),
+            force: matches.get_flag("force"),
+            created: vec![],
+        })
+    }
+
+    /// Also write plain-text exports of the generated documents to `dir` (see
+    /// `--export-text`).
+    pub(crate) fn with_export_text(mut self, dir: Option<&String>) -> Self {
+        self.export_text = dir.map(PathBuf::from);
+        self
+    }
+
+    /// Also write the QR codes of the generated documents as PNG images to
+    /// `dir` (see `--export-qr-png`).
+    pub(crate) fn with_export_qr_png(mut self, dir: Option<&String>) -> Result<Self, Error> {
+        if dir.is_some() && cfg!(not(feature = "qr-png")) {
+            bail!("--export-qr-png requires paperback to be built with the \"qr-png\" feature");
+        }
+        self.export_qr_png = dir.map(PathBuf::from);
+        Ok(self)
+    }
+
+    /// Write the generated documents to `dir` rather than the `--output-dir`
+    /// directory, if `dir` is given.
+    pub(crate) fn with_output_dir(mut self, dir: Option<PathBuf>) -> Self {
+        if let Some(dir) = dir {
+            self.dir = dir;
+        }
+        self
+    }
+
+    /// Write main documents and key shards in `format` (see
+    /// `Output::write_document`).
+    pub(crate) fn with_format(mut self, format: DocumentFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub(crate) fn exports_text(&self) -> bool {
+        self.export_text.is_some()
+    }
+
+    pub(crate) fn exports_qr_png(&self) -> bool {
+        self.export_qr_png.is_some()
+    }
+
+    /// Write a file called `name` in the output directory, with the contents
+    /// generated by `write`.
+    pub(crate) fn write<F>(&mut self, name: &str, write: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut BufWriter<File>) -> Result<(), Error>,
+    {
+        let path = write_atomic(&self.dir, name, self.force, write)?;
+        self.created.push(path);
+        Ok(())
+    }
+
+    /// Write a main document or key shards called `name` (without a file
+    /// extension) in the output format.
+    pub(crate) fn write_document<T>(
+        &mut self,
+        name: &str,
+        document: &T,
+        options: &PdfOptions,
+    ) -> Result<(), Error>
+    where
+        T: ToPdf + ToSvg + ?Sized,
+    {
+        match self.format {
+            DocumentFormat::Pdf => {
+                let pdf = document.to_pdf_with_options(options)?;
+                self.write(&format!("{}.pdf", name), |file| Ok(pdf.save(file)?))
+            }
+            DocumentFormat::Svg => {
+                let pages = document.to_svg_with_options(options)?;
+                let num_pages = pages.len();
+                for (idx, page) in pages.iter().enumerate() {
+                    let path = match num_pages {
+                        1 => format!("{}.svg", name),
+                        _ => format!("{}-{}.svg", name, idx + 1),
+                    };
+                    self.write(&path, |file| Ok(file.write_all(page.as_bytes())?))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Write a plain-text export called `name` to the `--export-text`
+    /// directory. Does nothing if no such directory was given.
+    pub(crate) fn write_text_export(&mut self, name: &str, contents: &str) -> Result<(), Error> {
+        if let Some(dir) = &self.export_text {
+            let path = write_atomic(dir, name, self.force, |file| {
+                Ok(file.write_all(contents.as_bytes())?)
+            })?;
+            self.created.push(path);
+        }
+        Ok(())
+    }
+
+    /// Write `code` as a PNG image called `name` to the `--export-qr-png`
+    /// directory. Does nothing if no such directory was given.
+    #[cfg_attr(not(feature = "qr-png"), allow(unused_variables))]
+    pub(crate) fn write_qr_png(&mut self, name: &str, code: &QrModules) -> Result<(), Error> {
+        #[cfg(feature = "qr-png")]
+        if let Some(dir) = &self.export_qr_png {
+            use image::{GrayImage, ImageFormat, Luma};
+
+            let size = (code.width() + 2 * QR_PNG_QUIET_ZONE) as u32 * QR_PNG_MODULE_PIXELS;
+            let image = GrayImage::from_fn(size, size, |x, y| {
+                let (x, y) = (
+                    (x / QR_PNG_MODULE_PIXELS) as usize,
+                    (y / QR_PNG_MODULE_PIXELS) as usize,
+                );
+                let dark = (QR_PNG_QUIET_ZONE..QR_PNG_QUIET_ZONE + code.width()).contains(&x)
+                    && (QR_PNG_QUIET_ZONE..QR_PNG_QUIET_ZONE + code.width()).contains(&y)
+                    && code.is_dark(x - QR_PNG_QUIET_ZONE, y - QR_PNG_QUIET_ZONE);
+                Luma([if dark { 0 } else { 255 }])
+            });
+            let path = write_atomic(dir, name, self.force, |file| {
+                Ok(image.write_to(file, ImageFormat::Png)?)
+            })?;
+            self.created.push(path);
+        }
+        Ok(())
+    }
+
+    /// The paths of all of the files written so far, in order.
+    pub(crate) fn into_created(self) -> Vec<PathBuf> {
+        self.created
+    }
+}
+
+fn write_atomic<F>(dir: &Path, name: &str, force: bool, write: F) -> Result<PathBuf, Error>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), Error>,
+{
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create output directory '{}'", dir.display()))?;
+    let path = dir.join(name);
+    if !force && path.exists() {
+        bail!(
+            "refusing to overwrite existing file '{}' (use --force to overwrite it)",
+            path.display()
+        );
+    }
+
+    let tmp_path = dir.join(format!(".{}.{}.tmp", name, process::id()));
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)
+        .with_context(|| format!("failed to create temporary file '{}'", tmp_path.display()))?;
+    let result = (|| -> Result<(), Error> {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        Ok(fs::rename(&tmp_path, &path)?)
+    })();
+    if let Err(err) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err.context(format!("failed to write '{}'", path.display())));
+    }
+    Ok(path)
+}
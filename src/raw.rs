@@ -17,20 +17,32 @@
  */
 
 use std::{
-    fs::File,
+    fs::{self, File},
     io,
     io::{prelude::*, BufReader},
 };
 
-use anyhow::{anyhow, Context, Error};
-use clap::{Arg, ArgAction, ArgMatches, Command};
+use anyhow::{anyhow, bail, ensure, Context, Error};
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use zeroize::Zeroizing;
 
 extern crate paperback_core;
-use paperback_core::latest as paperback;
+use paperback_core::{latest as paperback, sss};
 
-const ENCODING_BASE: multibase::Base = multibase::Base::Base32Z;
+use paperback::{
+    codewords,
+    wire::armor::{self, Armored},
+    Backup, CodewordLanguage, EncryptedKeyShard, FromWire, KeyShardCodewords, MainDocument,
+    NewShardKind, Quorum, ShardId, ToWire, UntrustedQuorum,
+};
+
+use crate::{
+    check_backup_policy, get_codeword_language, get_max_quorum_size, input_env_arg,
+    input_prompt_arg, max_quorum_size_arg, parse_multibase, raw_codeword_language_arg,
+    read_multiline, read_secret_input, recover_document_with_progress,
+};
 
-// paperback-cli raw backup [--sealed] --quorum-size <QUORUM SIZE> --shards <SHARDS> INPUT
+// paperback-cli raw backup [--sealed] [--max-quorum-size <QUORUM SIZE>] --quorum-size <QUORUM SIZE> --shards <SHARDS> [--input-prompt|--input-env <VAR>|INPUT]
 fn raw_backup_cli() -> Command {
     Command::new("backup")
                 .about("Create a new paperback backup.")
@@ -52,17 +64,19 @@ fn raw_backup_cli() -> Command {
                     .help("Number of shards to create (must not be smaller than --quorum-size).")
                     .action(ArgAction::Set)
                     .required(true))
+                .arg(max_quorum_size_arg())
+                .arg(raw_codeword_language_arg())
+                .arg(input_prompt_arg())
+                .arg(input_env_arg())
                 .arg(Arg::new("INPUT")
                     .help(r#"Path to file containing secret data to backup ("-" to read from stdin)."#)
                     .action(ArgAction::Set)
                     .allow_hyphen_values(true)
-                    .required(true)
+                    .required_unless_present_any(["input-prompt", "input-env"])
                     .index(1))
 }
 
 fn raw_backup(matches: &ArgMatches) -> Result<(), Error> {
-    use paperback::{Backup, ToWire};
-
     let sealed = matches.get_flag("sealed");
     let quorum_size: u32 = matches
         .get_one::<String>("quorum-size")
@@ -74,64 +88,47 @@ fn raw_backup(matches: &ArgMatches) -> Result<(), Error> {
         .context("required --quorum-size argument not provided")?
         .parse()
         .context("--shards argument was not an unsigned integer")?;
-    let input_path = matches
-        .get_one::<String>("INPUT")
-        .context("required INPUT argument not provided")?;
-
-    if num_shards < quorum_size {
-        return Err(anyhow!("invalid arguments: number of shards cannot be smaller than quorum size (such a backup is unrecoverable)"));
-    }
+    let codeword_language = get_codeword_language(matches)?;
 
-    let (mut stdin_reader, mut file_reader);
-    let input: &mut dyn Read = if input_path == "-" {
-        stdin_reader = io::stdin();
-        &mut stdin_reader
-    } else {
-        file_reader = File::open(input_path)
-            .with_context(|| format!("failed to open secret data file '{}'", input_path))?;
-        &mut file_reader
-    };
-    let mut buffer_input = BufReader::new(input);
+    let max_quorum_size = get_max_quorum_size(matches)?;
+    check_backup_policy(quorum_size, num_shards, max_quorum_size)?;
 
-    let mut secret = Vec::new();
-    buffer_input
-        .read_to_end(&mut secret)
-        .with_context(|| format!("failed to read secret data from '{}'", input_path))?;
+    let secret = read_secret_input(matches)?;
 
-    let backup = if sealed {
-        Backup::new_sealed(quorum_size, &secret)
-    } else {
-        Backup::new(quorum_size, &secret)
-    }?;
+    let backup = Backup::builder()
+        .quorum_size(quorum_size)
+        .max_quorum_size(max_quorum_size)
+        .sealed(sealed)
+        .build(&secret)?;
     let main_document = backup.main_document().clone();
-    let shards = (0..num_shards)
-        .map(|_| backup.next_shard().unwrap())
-        .map(|s| s.encrypt().unwrap())
+    let shards = backup
+        .next_shards(num_shards as usize)?
+        .into_iter()
+        .map(|s| s.encrypt_with_language(codeword_language).unwrap())
         .collect::<Vec<_>>();
 
-    println!("----- BEGIN MAIN DOCUMENT -----");
-    println!("Document-ID: {}", main_document.id());
-    println!("Checksum: {}", main_document.checksum_string());
-    println!("\n{}", main_document.to_wire_multibase(ENCODING_BASE));
-    println!("----- END MAIN DOCUMENT -----");
-
-    for (i, (shard, keyword)) in shards.iter().enumerate() {
-        let decrypted_shard = shard.clone().decrypt(keyword).unwrap();
-        println!("----- BEGIN SHARD {} OF {} -----", i + 1, quorum_size);
-        println!("Document-ID: {}", decrypted_shard.document_id());
-        println!("Shard-ID: {}", decrypted_shard.id());
-        println!("Checksum: {}", shard.checksum_string());
-        println!("Keywords: {}", keyword.join(" "));
-        println!("\n{}", shard.to_wire_multibase(ENCODING_BASE));
-        println!("----- END SHARD {} OF {} -----", i + 1, quorum_size);
-    }
+    print_main_document(&main_document);
+    print_shards(&shards);
 
     Ok(())
 }
 
-fn read_oneline_file(prompt: &str, path_or_stdin: &str) -> Result<String, Error> {
+fn print_main_document(main_document: &MainDocument) {
+    print!("{}", armor::encode_main_document(main_document));
+}
+
+/// The contents of a raw input file.
+enum RawInput {
+    /// The bare multibase data of a single document.
+    Multibase(String),
+    /// Armored documents (as printed by "raw backup").
+    Armored(Vec<Armored>),
+}
+
+fn read_raw_input(prompt: &str, path_or_stdin: &str) -> Result<RawInput, Error> {
+    let is_stdin = path_or_stdin == "-";
     let (mut stdin_reader, mut file_reader);
-    let input: &mut dyn Read = if path_or_stdin == "-" {
+    let input: &mut dyn Read = if is_stdin {
         print!("{}: ", prompt);
         io::stdout().flush()?;
         stdin_reader = io::stdin();
@@ -141,11 +138,45 @@ fn read_oneline_file(prompt: &str, path_or_stdin: &str) -> Result<String, Error>
             .with_context(|| format!("failed to open file '{}'", path_or_stdin))?;
         &mut file_reader
     };
-    let buffer_input = BufReader::new(input);
-    Ok(buffer_input
-        .lines()
-        .next()
-        .ok_or_else(|| anyhow!("no lines read"))??)
+    let mut lines = BufReader::new(input).lines();
+    let first_line = loop {
+        match lines.next().transpose()? {
+            Some(line) if line.trim().is_empty() => continue,
+            Some(line) => break line,
+            None => bail!("no lines read"),
+        }
+    };
+    if !armor::is_armored(&first_line) {
+        return Ok(RawInput::Multibase(first_line));
+    }
+
+    // Files can contain several armored documents, but only read a single
+    // document from stdin (since more input may be requested afterwards).
+    let mut armored = first_line + "\n";
+    for line in lines {
+        let line = line?;
+        armored.push_str(&line);
+        armored.push('\n');
+        if is_stdin && armor::is_end_line(&line) {
+            break;
+        }
+    }
+    Ok(RawInput::Armored(armor::decode(armored)?))
+}
+
+fn read_main_document(path_or_stdin: &str) -> Result<MainDocument, Error> {
+    match read_raw_input("Main Document Data", path_or_stdin).context("open main document")? {
+        RawInput::Multibase(data) => MainDocument::from_wire_multibase(data)
+            .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
+            .context("decode main document"),
+        RawInput::Armored(documents) => documents
+            .into_iter()
+            .find_map(|document| match document {
+                Armored::MainDocument(main_document) => Some(*main_document),
+                _ => None,
+            })
+            .context("armored input does not contain a main document"),
+    }
 }
 
 // paperback-cli raw restore --main-document <MAIN DOCUMENT> (--shards <SHARD>)... OUTPUT
@@ -183,8 +214,6 @@ fn raw_restore_cli() -> Command {
 }
 
 fn raw_restore(matches: &ArgMatches) -> Result<(), Error> {
-    use paperback::{EncryptedKeyShard, FromWire, MainDocument, UntrustedQuorum};
-
     let main_document_path = matches
         .get_one::<String>("main_document")
         .context("required --main-document argument not provided")?;
@@ -195,54 +224,38 @@ fn raw_restore(matches: &ArgMatches) -> Result<(), Error> {
         .get_one::<String>("OUTPUT")
         .context("required OUTPUT argument not provided")?;
 
-    let main_document = MainDocument::from_wire_multibase(
-        read_oneline_file("Main Document Data", main_document_path)
-            .context("open main document")?,
-    )
-    .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
-    .context("decode main document")?;
+    let main_document = read_main_document(main_document_path)?;
 
     println!("Document ID: {}", main_document.id());
     println!("Document Checksum: {}", main_document.checksum_string());
+    println!(
+        "Document Fingerprint: {}",
+        main_document.fingerprint_words().join(" ")
+    );
 
     let mut quorum = UntrustedQuorum::new();
     quorum.main_document(main_document);
     for (idx, shard_path) in shard_paths.enumerate() {
-        let encrypted_shard = EncryptedKeyShard::from_wire_multibase(
-            read_oneline_file(&format!("Shard {} Data", idx + 1), shard_path)
-                .with_context(|| format!("read shard {}", idx + 1))?,
-        )
-        .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
-        .with_context(|| format!("decode shard {}", idx + 1))?;
-
+        let (encrypted_shard, codewords) = read_shard(idx, shard_path)?;
         println!("Shard Checksum: {}", encrypted_shard.checksum_string());
-        print!("Shard {} Codeword: ", idx + 1);
-        io::stdout().flush()?;
-        let mut codeword_input = String::new();
-        io::stdin().read_line(&mut codeword_input)?;
+        println!(
+            "Shard Fingerprint: {}",
+            encrypted_shard.fingerprint_words().join(" ")
+        );
+        let codewords = match codewords {
+            Some(codewords) => codewords,
+            None => read_codewords(&format!("Shard {} Codeword", idx + 1))?,
+        };
 
-        let codewords = codeword_input
-            .split_whitespace()
-            .map(|s| s.to_owned())
-            .collect::<Vec<_>>();
         let shard = encrypted_shard
             .decrypt(&codewords)
-            .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
             .with_context(|| format!("decrypting shard {}", idx + 1))?;
         quorum.push_shard(shard);
     }
 
-    let quorum = quorum.validate().map_err(|err| {
-        anyhow!(
-            "quorum failed to validate -- possible forgery! {}; groupings: {:?}",
-            err.message,
-            err.as_groups()
-        )
-    })?;
+    let quorum = quorum.validate()?;
 
-    let secret = quorum
-        .recover_document()
-        .context("recovering secret data")?;
+    let secret = recover_document_with_progress(&quorum)?;
 
     let (mut stdout_writer, mut file_writer);
     let output_file: &mut dyn Write = if output_path == "-" {
@@ -261,10 +274,111 @@ fn raw_restore(matches: &ArgMatches) -> Result<(), Error> {
     Ok(())
 }
 
+fn shards_arg() -> Arg {
+    Arg::new("shards")
+        .short('s')
+        .long("shard")
+        .value_name("SHARDS")
+        .help(r#"Path to each paperback shard ("-" to read from stdin)."#)
+        .action(ArgAction::Append)
+        .allow_hyphen_values(true)
+        .required(true)
+}
+
+fn read_codewords(prompt: &str) -> Result<KeyShardCodewords, Error> {
+    print!("{}: ", prompt);
+    io::stdout().flush()?;
+    let mut codeword_input = Zeroizing::new(String::new());
+    io::stdin().read_line(&mut codeword_input)?;
+
+    Ok(codewords::normalize(
+        &codeword_input.split_whitespace().collect::<Vec<_>>(),
+    )?)
+}
+
+/// Read a key shard, along with its codewords if the key shard was armored
+/// with them.
+fn read_shard(
+    idx: usize,
+    shard_path: &str,
+) -> Result<(EncryptedKeyShard, Option<KeyShardCodewords>), Error> {
+    match read_raw_input(&format!("Shard {} Data", idx + 1), shard_path)
+        .with_context(|| format!("read shard {}", idx + 1))?
+    {
+        RawInput::Multibase(data) => Ok((
+            EncryptedKeyShard::from_wire_multibase(data)
+                .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
+                .with_context(|| format!("decode shard {}", idx + 1))?,
+            None,
+        )),
+        RawInput::Armored(documents) => {
+            let mut shards = documents
+                .into_iter()
+                .filter_map(|document| match document {
+                    Armored::KeyShard(shard, codewords) => Some((shard, codewords)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            ensure!(
+                shards.len() == 1,
+                "armored input for shard {} must contain exactly one key shard (found {})",
+                idx + 1,
+                shards.len()
+            );
+            Ok(shards.remove(0))
+        }
+    }
+}
+
+fn read_quorum<'a>(shard_paths: impl Iterator<Item = &'a String>) -> Result<Quorum, Error> {
+    let mut quorum = UntrustedQuorum::new();
+    for (idx, shard_path) in shard_paths.enumerate() {
+        let (encrypted_shard, codewords) = read_shard(idx, shard_path)?;
+        let codewords = match codewords {
+            Some(codewords) => codewords,
+            None => read_codewords(&format!("Shard {} Codeword", idx + 1))?,
+        };
+
+        let shard = encrypted_shard
+            .decrypt(&codewords)
+            .with_context(|| format!("decrypting shard {}", idx + 1))?;
+        quorum.push_shard(shard);
+    }
+
+    Ok(quorum.validate()?)
+}
+
+fn print_shards(shards: &[(EncryptedKeyShard, KeyShardCodewords)]) {
+    for (i, (shard, keyword)) in shards.iter().enumerate() {
+        let decrypted_shard = shard.clone().decrypt(keyword).unwrap();
+        print!(
+            "{}",
+            armor::encode_key_shard(i, shards.len(), &decrypted_shard, shard, keyword)
+        );
+    }
+}
+
+fn new_shards(
+    quorum: &Quorum,
+    new_shard_list: impl IntoIterator<Item = NewShardKind>,
+    codeword_language: CodewordLanguage,
+) -> Result<Vec<(EncryptedKeyShard, KeyShardCodewords)>, Error> {
+    new_shard_list
+        .into_iter()
+        .map(|kind| {
+            Ok(quorum
+                .new_shard(kind)
+                .context("minting new shards")?
+                .encrypt_with_language(codeword_language)
+                .expect("encrypt new shard"))
+        })
+        .collect()
+}
+
 // paperback-cli raw expand --new-shards <N> (--shards <SHARD>)...
 fn raw_expand_cli() -> Command {
     Command::new("expand")
-        .about("Restore the secret data from a paperback backup.")
+        .about("Create new key shards from a quorum of key shards.")
         .arg(
             Arg::new("new-shards")
                 .short('n')
@@ -274,21 +388,11 @@ fn raw_expand_cli() -> Command {
                 .action(ArgAction::Set)
                 .required(true),
         )
-        .arg(
-            Arg::new("shards")
-                .short('s')
-                .long("shard")
-                .value_name("SHARDS")
-                .help(r#"Path to each paperback shard ("-" to read from stdin)."#)
-                .action(ArgAction::Append)
-                .allow_hyphen_values(true)
-                .required(true),
-        )
+        .arg(shards_arg())
+        .arg(raw_codeword_language_arg())
 }
 
 fn raw_expand(matches: &ArgMatches) -> Result<(), Error> {
-    use paperback::{EncryptedKeyShard, FromWire, NewShardKind, ToWire, UntrustedQuorum};
-
     let shard_paths = matches
         .get_many::<String>("shards")
         .context("required --shard argument not provided")?;
@@ -297,69 +401,264 @@ fn raw_expand(matches: &ArgMatches) -> Result<(), Error> {
         .context("required --new-shards argument not provided")?
         .parse()
         .context("--new-shards argument was not an unsigned integer")?;
+    let codeword_language = get_codeword_language(matches)?;
 
-    let mut quorum = UntrustedQuorum::new();
-    for (idx, shard_path) in shard_paths.enumerate() {
-        let encrypted_shard = EncryptedKeyShard::from_wire_multibase(
-            read_oneline_file(&format!("Shard {} Data", idx + 1), shard_path)
-                .with_context(|| format!("read shard {}", idx + 1))?,
+    let quorum = read_quorum(shard_paths)?;
+    let new_shards = new_shards(
+        &quorum,
+        (0..num_new_shards).map(|_| NewShardKind::NewShard),
+        codeword_language,
+    )?;
+    print_shards(&new_shards);
+
+    Ok(())
+}
+
+// paperback-cli raw recreate (--shard-id <SHARD ID>)... (--shards <SHARD>)...
+fn raw_recreate_cli() -> Command {
+    Command::new("recreate")
+        .about("Re-create key shards with the given identifiers from a quorum of key shards.")
+        .arg(
+            Arg::new("shard-ids")
+                .short('i')
+                .long("shard-id")
+                .value_name("SHARD ID")
+                .help(r#"Shard identifier of a shard to recreate."#)
+                .value_parser(clap::value_parser!(ShardId))
+                .action(ArgAction::Append)
+                .required(true),
         )
-        .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
-        .with_context(|| format!("decode shard {}", idx + 1))?;
+        .arg(shards_arg())
+        .arg(raw_codeword_language_arg())
+}
 
-        print!("Shard {} Codeword: ", idx + 1);
-        io::stdout().flush()?;
-        let mut codeword_input = String::new();
-        io::stdin().read_line(&mut codeword_input)?;
+fn raw_recreate(matches: &ArgMatches) -> Result<(), Error> {
+    let shard_paths = matches
+        .get_many::<String>("shards")
+        .context("required --shard argument not provided")?;
+    let shard_ids = matches
+        .get_many::<ShardId>("shard-ids")
+        .context("required --shard-id argument not provided")?;
+    let codeword_language = get_codeword_language(matches)?;
+
+    let quorum = read_quorum(shard_paths)?;
+    let new_shards = new_shards(
+        &quorum,
+        shard_ids.cloned().map(NewShardKind::ExistingShard),
+        codeword_language,
+    )?;
+    print_shards(&new_shards);
 
-        let codewords = codeword_input
-            .split_whitespace()
-            .map(|s| s.to_owned())
-            .collect::<Vec<_>>();
+    Ok(())
+}
 
-        let shard = encrypted_shard
+// paperback-cli raw reprint (--main-document <MAIN DOCUMENT>|--shard <SHARD>)
+fn raw_reprint_cli() -> Command {
+    Command::new("reprint")
+        .about("Re-print a paperback document, after verifying it.")
+        .arg(
+            Arg::new("main_document")
+                .short('M')
+                .long("main-document")
+                .value_name("MAIN DOCUMENT PATH")
+                .help(r#"Path to paperback main document ("-" to read from stdin)."#)
+                .action(ArgAction::Set)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("shard")
+                .short('s')
+                .long("shard")
+                .value_name("SHARD PATH")
+                .help(r#"Path to paperback shard ("-" to read from stdin)."#)
+                .action(ArgAction::Set)
+                .allow_hyphen_values(true),
+        )
+        .group(
+            ArgGroup::new("type")
+                .arg("main_document")
+                .arg("shard")
+                .required(true),
+        )
+}
+
+fn raw_reprint(matches: &ArgMatches) -> Result<(), Error> {
+    if let Some(main_document_path) = matches.get_one::<String>("main_document") {
+        let main_document = read_main_document(main_document_path)?;
+        print_main_document(&main_document);
+    } else {
+        let shard_path = matches
+            .get_one::<String>("shard")
+            .context("neither --main-document nor --shard provided")?;
+        let (encrypted_shard, codewords) = read_shard(0, shard_path)?;
+        println!("Shard Checksum: {}", encrypted_shard.checksum_string());
+        // Make sure the codewords are correct before we print them.
+        let codewords = match codewords {
+            Some(codewords) => codewords,
+            None => read_codewords("Shard Codeword")?,
+        };
+        encrypted_shard
             .decrypt(&codewords)
-            .map_err(|err| anyhow!(err)) // TODO: Fix this once FromWire supports non-String errors.
-            .with_context(|| format!("decrypting shard {}", idx + 1))?;
-        quorum.push_shard(shard);
+            .context("decrypting shard")?;
+        print_shards(&[(encrypted_shard, codewords)]);
     }
 
-    let quorum = quorum.validate().map_err(|err| {
-        anyhow!(
-            "quorum failed to validate -- possible forgery! {}; groupings: {:?}",
-            err.message,
-            err.as_groups()
+    Ok(())
+}
+
+// paperback-cli raw shamir split [--max-quorum-size <QUORUM SIZE>] --quorum-size <QUORUM SIZE> --shards <SHARDS> [--input-prompt|--input-env <VAR>|INPUT]
+fn raw_shamir_split_cli() -> Command {
+    Command::new("split")
+        .about("Split secret data into shards, which are printed to stdout (one per line).")
+        .arg(
+            Arg::new("quorum-size")
+                .short('n')
+                .long("quorum-size")
+                .value_name("QUORUM SIZE")
+                .help("Number of shards required to recover the secret (must not be larger than --shards).")
+                .action(ArgAction::Set)
+                .required(true),
         )
-    })?;
+        .arg(
+            Arg::new("shards")
+                .short('k')
+                .long("shards")
+                .value_name("NUM SHARDS")
+                .help("Number of shards to create (must not be smaller than --quorum-size).")
+                .action(ArgAction::Set)
+                .required(true),
+        )
+        .arg(max_quorum_size_arg())
+        .arg(input_prompt_arg())
+        .arg(input_env_arg())
+        .arg(
+            Arg::new("INPUT")
+                .help(r#"Path to file containing secret data to split ("-" to read from stdin)."#)
+                .action(ArgAction::Set)
+                .allow_hyphen_values(true)
+                .required_unless_present_any(["input-prompt", "input-env"])
+                .index(1),
+        )
+}
 
-    let new_shards = (0..num_new_shards)
-        .map(|_| {
-            Ok(quorum
-                .new_shard(NewShardKind::NewShard)
-                .context("minting new shards")?
-                .encrypt()
-                .expect("encrypt new shard"))
-        })
-        .collect::<Result<Vec<_>, Error>>()?;
+fn raw_shamir_split(matches: &ArgMatches) -> Result<(), Error> {
+    let quorum_size: u32 = matches
+        .get_one::<String>("quorum-size")
+        .context("required --quorum-size argument not provided")?
+        .parse()
+        .context("--quorum-size argument was not an unsigned integer")?;
+    let num_shards: u32 = matches
+        .get_one::<String>("shards")
+        .context("required --shards argument not provided")?
+        .parse()
+        .context("--shards argument was not an unsigned integer")?;
 
-    for (i, (shard, keyword)) in new_shards.iter().enumerate() {
-        let decrypted_shard = shard.clone().decrypt(keyword).unwrap();
-        println!("----- BEGIN SHARD {} OF {} -----", i + 1, num_new_shards);
-        println!("Document-ID: {}", decrypted_shard.document_id());
-        println!("Shard-ID: {}", decrypted_shard.id());
-        println!("Keywords: {}", keyword.join(" "));
-        println!("\n{}", shard.to_wire_multibase(ENCODING_BASE));
-        println!("----- END SHARD {} OF {} -----", i, num_new_shards);
+    let max_quorum_size = get_max_quorum_size(matches)?;
+    check_backup_policy(quorum_size, num_shards, max_quorum_size)?;
+
+    let secret = read_secret_input(matches)?;
+    let shards =
+        sss::split(quorum_size, num_shards as usize, &secret).context("splitting secret data")?;
+    for shard in shards {
+        println!("{}", shard.to_wire_multibase(multibase::Base::Base32Z));
     }
+    eprintln!(
+        "Split secret data into {} shards, any {} of which can recover it.",
+        num_shards, quorum_size
+    );
 
     Ok(())
 }
 
+// paperback-cli raw shamir join [--output <OUTPUT>] [SHARDS]
+fn raw_shamir_join_cli() -> Command {
+    Command::new("join")
+        .about("Recover secret data from shards created by raw shamir split.")
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT")
+                .help(r#"Path to write the recovered secret data to ("-" to write to stdout)."#)
+                .default_value("-")
+                .action(ArgAction::Set)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("SHARDS")
+                .help(r#"Path to a file containing the shards, one per line ("-" to read from stdin). If not given, the shards are requested interactively."#)
+                .action(ArgAction::Set)
+                .allow_hyphen_values(true)
+                .index(1),
+        )
+}
+
+fn raw_shamir_join(matches: &ArgMatches) -> Result<(), Error> {
+    let output_path = matches
+        .get_one::<String>("output")
+        .context("required --output argument not provided")?;
+
+    let input = match matches.get_one::<String>("SHARDS").map(String::as_str) {
+        Some("-") => io::read_to_string(io::stdin()).context("failed to read shards from stdin")?,
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("failed to read shards file '{}'", path))?,
+        None => read_multiline("Enter shards (one per line)")?,
+    };
+    let shards = input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(idx, line)| {
+            parse_multibase::<_, sss::Shard>(line)
+                .with_context(|| format!("failed to parse shard {}", idx + 1))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (mut stdout_writer, mut file_writer);
+    let output_file: &mut dyn Write = if output_path == "-" {
+        stdout_writer = io::stdout();
+        &mut stdout_writer
+    } else {
+        file_writer = File::create(output_path)
+            .with_context(|| format!("failed to open output file '{}' for writing", output_path))?;
+        &mut file_writer
+    };
+
+    // The secret is written out as it is recovered, so large secrets don't
+    // need to be held in memory all at once.
+    sss::join_into(&shards, output_file).context("recovering secret data")?;
+
+    Ok(())
+}
+
+// paperback-cli raw shamir (split|join)
+fn raw_shamir_cli() -> Command {
+    Command::new("shamir")
+        .about("Split arbitrary secret data using paperback's Shamir Secret Sharing implementation directly. The shards are not signed or encrypted, and no main document is created -- use raw backup unless you know you need this.")
+        // paperback-cli raw shamir split [--max-quorum-size <QUORUM SIZE>] --quorum-size <QUORUM SIZE> --shards <SHARDS> [--input-prompt|--input-env <VAR>|INPUT]
+        .subcommand(raw_shamir_split_cli())
+        // paperback-cli raw shamir join [--output <OUTPUT>] [SHARDS]
+        .subcommand(raw_shamir_join_cli())
+}
+
+fn raw_shamir(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("split", sub_matches)) => raw_shamir_split(sub_matches),
+        Some(("join", sub_matches)) => raw_shamir_join(sub_matches),
+        Some((subcommand, _)) => bail!("unknown subcommand 'raw shamir {}'", subcommand),
+        None => bail!("no 'raw shamir' subcommand specified (split or join)"),
+    }
+}
+
 pub(crate) fn submatch(app: &mut Command, matches: &ArgMatches) -> Result<(), Error> {
     match matches.subcommand() {
         Some(("backup", sub_matches)) => raw_backup(sub_matches),
         Some(("restore", sub_matches)) => raw_restore(sub_matches),
         Some(("expand", sub_matches)) => raw_expand(sub_matches),
+        Some(("recreate", sub_matches)) => raw_recreate(sub_matches),
+        Some(("reprint", sub_matches)) => raw_reprint(sub_matches),
+        Some(("shamir", sub_matches)) => raw_shamir(sub_matches),
         Some((subcommand, _)) => {
             // We should never end up here.
             app.print_help()?;
@@ -375,10 +674,16 @@ pub(crate) fn submatch(app: &mut Command, matches: &ArgMatches) -> Result<(), Er
 pub(crate) fn subcommands() -> Command {
     Command::new("raw")
             .about("Operate using raw text data, rather than on PDF documents. This mode is not recommended for general use, since it might be more complicated for inexperienced users to recover the document.")
-            // paperback-cli raw backup [--sealed] --quorum-size <QUORUM SIZE> --shards <SHARDS> INPUT
+            // paperback-cli raw backup [--sealed] [--max-quorum-size <QUORUM SIZE>] --quorum-size <QUORUM SIZE> --shards <SHARDS> [--input-prompt|--input-env <VAR>|INPUT]
             .subcommand(raw_backup_cli())
             // paperback-cli raw restore --main-document <MAIN DOCUMENT> (--shards <SHARD>)... OUTPUT
             .subcommand(raw_restore_cli())
             // paperback-cli raw expand --new-shards <N> (--shards <SHARD>)...
             .subcommand(raw_expand_cli())
+            // paperback-cli raw recreate (--shard-id <SHARD ID>)... (--shards <SHARD>)...
+            .subcommand(raw_recreate_cli())
+            // paperback-cli raw reprint (--main-document <MAIN DOCUMENT>|--shard <SHARD>)
+            .subcommand(raw_reprint_cli())
+            // paperback-cli raw shamir (split|join)
+            .subcommand(raw_shamir_cli())
 }
@@ -0,0 +1,194 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Backup "recipes", which describe a backup ceremony in a file (rather than
+//! as command-line arguments) so that it can be reviewed ahead of time and
+//! repeated exactly.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Error};
+use clap::{Arg, ArgAction};
+use serde::Deserialize;
+
+pub(crate) fn recipe_arg() -> Arg {
+    Arg::new("recipe")
+        .long("recipe")
+        .value_name("RECIPE PATH")
+        .help(r#"Read the input path, quorum size, number of shards, whether the backup is sealed, the key shard holder labels, the paper size and the output directory from a recipe file (in TOML or YAML, depending on the file extension) rather than from the command line. Relative paths in the recipe are relative to the directory containing the recipe."#)
+        .action(ArgAction::Set)
+        .conflicts_with_all(["resume", "quorum-size", "shards", "sealed", "no-recreate-shards", "output-dir", "label", "holder", "INPUT", "input-prompt", "input-env", "payload"])
+}
+
+/// Paper sizes that documents can be printed on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PaperSize {
+    // NOTE: Paperback only generates A4 documents at the moment, but recipes
+    //       state the paper size explicitly so that a recipe written for a
+    //       different paper size is rejected rather than silently ignored.
+    #[default]
+    A4,
+}
+
+/// A description of a backup, equivalent to the corresponding `backup`
+/// arguments.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Recipe {
+    /// Path to the file containing the secret data (see `INPUT`).
+    pub(crate) input: PathBuf,
+    /// See `--quorum-size`.
+    pub(crate) quorum_size: u32,
+    /// See `--shards`.
+    pub(crate) shards: u32,
+    /// See `--sealed`.
+    #[serde(default)]
+    pub(crate) sealed: bool,
+    /// Who will hold each key shard (in the order they are created). These are
+    /// used both as `--holder` names and as shard registry `--label`s.
+    #[serde(default)]
+    pub(crate) labels: Vec<String>,
+    #[serde(default)]
+    pub(crate) paper_size: PaperSize,
+    /// See `--output-dir`.
+    pub(crate) output_dir: Option<PathBuf>,
+}
+
+impl Recipe {
+    fn parse(path: &Path, contents: &str) -> Result<Self, Error> {
+        let recipe: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(contents)?,
+            Some("yaml" | "yml") => serde_yaml::from_str(contents)?,
+            _ => bail!("unknown recipe format (recipe files must end in .toml, .yaml or .yml)"),
+        };
+        ensure!(
+            recipe.labels.len() <= recipe.shards as usize,
+            "recipe has more labels ({}) than key shards ({})",
+            recipe.labels.len(),
+            recipe.shards
+        );
+        ensure!(
+            recipe.labels.iter().all(|label| !label.trim().is_empty()),
+            "recipe labels must not be empty"
+        );
+        Ok(recipe)
+    }
+
+    /// Load the recipe at `path`, resolving the paths in the recipe relative
+    /// to the directory containing it.
+    pub(crate) fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read recipe file '{}'", path.display()))?;
+        let mut recipe = Self::parse(path, &contents)
+            .with_context(|| format!("failed to parse recipe file '{}'", path.display()))?;
+
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        recipe.input = base.join(&recipe.input);
+        recipe.output_dir = recipe.output_dir.map(|dir| base.join(dir));
+        Ok(recipe)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TOML_RECIPE: &str = r#"
+input = "secret.txt"
+quorum-size = 2
+shards = 3
+sealed = true
+labels = ["Alice", "Bob"]
+paper-size = "a4"
+output-dir = "out"
+"#;
+
+    const YAML_RECIPE: &str = r#"
+input: secret.txt
+quorum-size: 2
+shards: 3
+sealed: true
+labels:
+  - Alice
+  - Bob
+paper-size: a4
+output-dir: out
+"#;
+
+    #[test]
+    fn parse_recipe_formats() {
+        let expected = Recipe {
+            input: PathBuf::from("secret.txt"),
+            quorum_size: 2,
+            shards: 3,
+            sealed: true,
+            labels: vec!["Alice".to_string(), "Bob".to_string()],
+            paper_size: PaperSize::A4,
+            output_dir: Some(PathBuf::from("out")),
+        };
+        assert_eq!(
+            Recipe::parse(Path::new("recipe.toml"), TOML_RECIPE).unwrap(),
+            expected
+        );
+        assert_eq!(
+            Recipe::parse(Path::new("recipe.yaml"), YAML_RECIPE).unwrap(),
+            expected
+        );
+        assert!(Recipe::parse(Path::new("recipe.json"), TOML_RECIPE).is_err());
+    }
+
+    #[test]
+    fn parse_recipe_defaults() {
+        let recipe = Recipe::parse(
+            Path::new("recipe.toml"),
+            "input = \"secret.txt\"\nquorum-size = 2\nshards = 3\n",
+        )
+        .unwrap();
+        assert!(!recipe.sealed);
+        assert!(recipe.labels.is_empty());
+        assert_eq!(recipe.paper_size, PaperSize::A4);
+        assert_eq!(recipe.output_dir, None);
+    }
+
+    #[test]
+    fn parse_recipe_invalid() {
+        // Unknown fields (such as typos) are rejected.
+        assert!(Recipe::parse(
+            Path::new("recipe.toml"),
+            "input = \"secret.txt\"\nquorum-size = 2\nshards = 3\nseal = true\n",
+        )
+        .is_err());
+        // Only A4 paper is supported.
+        assert!(Recipe::parse(
+            Path::new("recipe.toml"),
+            "input = \"secret.txt\"\nquorum-size = 2\nshards = 3\npaper-size = \"letter\"\n",
+        )
+        .is_err());
+        // Each key shard has at most one label.
+        assert!(Recipe::parse(
+            Path::new("recipe.toml"),
+            "input = \"secret.txt\"\nquorum-size = 1\nshards = 1\nlabels = [\"Alice\", \"Bob\"]\n",
+        )
+        .is_err());
+    }
+}
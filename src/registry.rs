@@ -0,0 +1,324 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, ensure, Context, Error};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use zeroize::Zeroizing;
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{
+    format_date, ChallengeChecksum, DocumentId, RegistryEntry, RegistryHeader, RegistryKey,
+    SealedRegistryEntry, ShardId, ToWire,
+};
+
+const ENCODING_BASE: multibase::Base = multibase::Base::Base32Z;
+
+pub(crate) fn register_arg() -> Arg {
+    Arg::new("register")
+        .long("register")
+        .help("Record the created key shards in the encrypted shard registry (see the \"shards\" subcommand). You will be asked for the registry passphrase.")
+        .action(ArgAction::SetTrue)
+}
+
+pub(crate) fn label_arg() -> Arg {
+    Arg::new("label")
+        .long("label")
+        .value_name("LABEL")
+        .help("Label to record in the shard registry for a created key shard (such as who it was given to). Can be given multiple times, once for each key shard in the order they are created.")
+        .action(ArgAction::Append)
+        .requires("register")
+}
+
+/// Path of the shard registry, following the XDG base directory specification.
+fn registry_path() -> Result<PathBuf, Error> {
+    let data_home = match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(
+            env::var_os("HOME").context("cannot find shard registry: $HOME is not set")?,
+        )
+        .join(".local/share"),
+    };
+    Ok(data_home.join("paperback").join("registry"))
+}
+
+fn read_passphrase(confirm: bool) -> Result<Zeroizing<String>, Error> {
+    let passphrase = Zeroizing::new(
+        rpassword::prompt_password("Enter shard registry passphrase: ")
+            .context("failed to read shard registry passphrase")?,
+    );
+    if confirm {
+        let confirmation = Zeroizing::new(
+            rpassword::prompt_password("Confirm shard registry passphrase: ")
+                .context("failed to read shard registry passphrase")?,
+        );
+        ensure!(
+            *passphrase == *confirmation,
+            "entered shard registry passphrases did not match"
+        );
+        ensure!(
+            !passphrase.is_empty(),
+            "no shard registry passphrase entered"
+        );
+    }
+    Ok(passphrase)
+}
+
+/// An unlocked shard registry.
+///
+/// The registry is an append-only text file. The first line is the (plaintext)
+/// registry header, and each following line is a single encrypted entry.
+pub(crate) struct Registry {
+    path: PathBuf,
+    key: RegistryKey,
+    lines: Vec<String>,
+    // Whether the header still needs to be written.
+    needs_header: bool,
+}
+
+impl Registry {
+    /// Open the shard registry, creating a new one if it doesn't exist yet.
+    fn open(create: bool) -> Result<Self, Error> {
+        let path = registry_path()?;
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && create => String::new(),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read shard registry '{}'", path.display()))
+            }
+        };
+        let mut lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string);
+
+        let (key, lines, needs_header) = match lines.next() {
+            Some(header) => {
+                let header: RegistryHeader = crate::parse_multibase(&header)
+                    .context("failed to parse shard registry header")?;
+                let passphrase = read_passphrase(false)?;
+                let key = RegistryKey::unlock(&header, passphrase.as_bytes())?;
+                (key, lines.collect(), false)
+            }
+            None => {
+                println!("Creating a new shard registry at '{}'.", path.display());
+                let passphrase = read_passphrase(true)?;
+                let key = RegistryKey::new(passphrase.as_bytes())?;
+                (key, vec![], true)
+            }
+        };
+
+        Ok(Self {
+            path,
+            key,
+            lines,
+            needs_header,
+        })
+    }
+
+    /// Open the existing shard registry.
+    pub(crate) fn open_existing() -> Result<Self, Error> {
+        Self::open(false)
+    }
+
+    /// Open the shard registry if `--register` was passed.
+    pub(crate) fn from_matches(matches: &ArgMatches) -> Result<Option<Self>, Error> {
+        match matches.get_flag("register") {
+            true => Self::open(true).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    /// Append entries for the given key shards, labelled using `--label`.
+    pub(crate) fn register(
+        &mut self,
+        matches: &ArgMatches,
+        shards: &[(DocumentId, ShardId, ChallengeChecksum)],
+    ) -> Result<(), Error> {
+        let labels = matches
+            .get_many::<String>("label")
+            .unwrap_or_default()
+            .cloned()
+            .collect::<Vec<_>>();
+        ensure!(
+            labels.len() <= shards.len(),
+            "more --label arguments ({}) than key shards created ({})",
+            labels.len(),
+            shards.len()
+        );
+        self.register_with_labels(&labels, shards)
+    }
+
+    /// Like `Registry::register`, but with the labels given explicitly rather
+    /// than with `--label` arguments.
+    pub(crate) fn register_with_labels(
+        &mut self,
+        labels: &[String],
+        shards: &[(DocumentId, ShardId, ChallengeChecksum)],
+    ) -> Result<(), Error> {
+        ensure!(
+            labels.len() <= shards.len(),
+            "more labels ({}) than key shards created ({})",
+            labels.len(),
+            shards.len()
+        );
+
+        let mut new_lines = vec![];
+        if self.needs_header {
+            new_lines.push(self.key.header().to_wire_multibase(ENCODING_BASE));
+        }
+        for (idx, (document_id, shard_id, challenge_checksum)) in shards.iter().enumerate() {
+            let entry = RegistryEntry::new(
+                document_id.clone(),
+                shard_id.clone(),
+                challenge_checksum.clone(),
+                labels.get(idx).cloned(),
+            );
+            let line = self.key.seal(&entry)?.to_wire_multibase(ENCODING_BASE);
+            self.lines.push(line.clone());
+            new_lines.push(line);
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).with_context(|| {
+                format!(
+                    "failed to create shard registry directory '{}'",
+                    dir.display()
+                )
+            })?;
+        }
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // The registry is encrypted, but there's no need to share it.
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&self.path)
+            .with_context(|| format!("failed to open shard registry '{}'", self.path.display()))?;
+        for line in new_lines {
+            writeln!(file, "{}", line).context("failed to append to shard registry")?;
+        }
+        self.needs_header = false;
+
+        println!(
+            "Recorded {} key shards in the shard registry.",
+            shards.len()
+        );
+        Ok(())
+    }
+
+    /// Number of key shards recorded for the given document.
+    pub(crate) fn count(&self, document_id: &DocumentId) -> Result<usize, Error> {
+        Ok(self
+            .entries()?
+            .iter()
+            .filter(|entry| &entry.document_id == document_id)
+            .count())
+    }
+
+    pub(crate) fn entries(&self) -> Result<Vec<RegistryEntry>, Error> {
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                let entry: SealedRegistryEntry = crate::parse_multibase(line)
+                    .with_context(|| format!("failed to parse shard registry entry {}", idx + 1))?;
+                Ok(self.key.open(&entry)?)
+            })
+            .collect()
+    }
+}
+
+// paperback-cli shards list <DOCUMENT ID>
+fn list_cli() -> Command {
+    Command::new("list")
+        .about("List the key shards recorded in the shard registry for a backup.")
+        .arg(
+            Arg::new("DOCUMENT ID")
+                .help("Document identifier of the backup.")
+                .value_parser(clap::value_parser!(DocumentId))
+                .action(ArgAction::Set)
+                .required(true)
+                .index(1),
+        )
+}
+
+fn list(matches: &ArgMatches) -> Result<(), Error> {
+    let document_id = matches
+        .get_one::<DocumentId>("DOCUMENT ID")
+        .context("required DOCUMENT ID argument not provided")?;
+
+    let entries = Registry::open(false)?
+        .entries()?
+        .into_iter()
+        .filter(|entry| &entry.document_id == document_id)
+        .collect::<Vec<_>>();
+
+    println!(
+        "{} key shards recorded for document {}.",
+        entries.len(),
+        document_id
+    );
+    for entry in entries {
+        match entry.label {
+            Some(label) => println!(
+                "{}  {}  {}",
+                entry.shard_id,
+                format_date(entry.created),
+                label
+            ),
+            None => println!("{}  {}", entry.shard_id, format_date(entry.created)),
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn submatch(app: &mut Command, matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("list", sub_matches)) => list(sub_matches),
+        Some((subcommand, _)) => {
+            // We should never end up here.
+            app.print_help()?;
+            Err(anyhow!("unknown subcommand 'shards {}'", subcommand))
+        }
+        None => {
+            app.print_help()?;
+            Err(anyhow!("no 'shards' subcommand specified"))
+        }
+    }
+}
+
+pub(crate) fn subcommands() -> Command {
+    Command::new("shards")
+            .about("Query the shard registry, an encrypted local record of the key shards created with --register.")
+            // paperback-cli shards list <DOCUMENT ID>
+            .subcommand(list_cli())
+}
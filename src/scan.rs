@@ -0,0 +1,373 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Error};
+use image::{
+    imageops::{self, FilterType},
+    DynamicImage, GrayImage, Luma,
+};
+use tracing::{debug, instrument};
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{pdf::qr, EncryptedKeyShard, FromWire, MainDocument, QrEncoding};
+
+use crate::collect::CodeCollector;
+
+/// Scales at which images are searched for QR codes. Scanners often produce
+/// very large images, and downscaling them (which blurs away paper texture and
+/// printer noise) frequently helps QR code detection.
+const SCALES: [f64; 3] = [1.0, 0.5, 0.25];
+/// Images are not downscaled below this size (on their shortest side).
+const MIN_SCALED_SIZE: u32 = 256;
+/// Pixels this much darker (in percent) than their surroundings are treated
+/// as black by `adaptive_threshold`.
+const THRESHOLD_PERCENT: u64 = 15;
+/// Largest skew (in degrees, in either direction) detected by `estimate_skew`.
+const MAX_SKEW: f64 = 10.0;
+/// Resolution (in degrees) of `estimate_skew`.
+const SKEW_STEP: f64 = 0.5;
+/// Size (on the longest side) of the downscaled image used by `estimate_skew`.
+const SKEW_ESTIMATE_SIZE: u32 = 512;
+
+/// The paperback QR codes found in an image by `scan_image`.
+#[derive(Debug, Default)]
+pub(crate) struct ScannedImage {
+    /// QR codes which are part of a set (the main document, or key shards too
+    /// large for a single QR code).
+    pub(crate) parts: Vec<qr::Part>,
+    /// Data of any other QR codes (such as key shards).
+    pub(crate) codes: Vec<String>,
+    /// Number of QR codes which were decoded.
+    pub(crate) found: usize,
+    /// Number of QR codes which were detected (including any which could not
+    /// be decoded).
+    pub(crate) detected: usize,
+    /// Problems with the image which may have caused QR codes to be missed.
+    pub(crate) warnings: Vec<String>,
+}
+
+/// Scan an image of printed paperback documents (such as a scan or photo of a
+/// page) for QR codes.
+///
+/// Real-world scans are rarely clean, so the image is converted to greyscale,
+/// binarised with an adaptive threshold (to handle uneven lighting), deskewed,
+/// and searched at several scales. Every QR code decoded by any of these
+/// passes is included in the result.
+#[instrument(skip_all, fields(width = image.width(), height = image.height()))]
+pub(crate) fn scan_image(image: &DynamicImage) -> ScannedImage {
+    let mut scanned = ScannedImage::default();
+
+    let grey = image.to_luma8();
+    let skew = estimate_skew(&grey);
+    let mut variants = vec![adaptive_threshold(&grey), grey];
+    if skew.abs() >= SKEW_STEP {
+        debug!(
+            "page is skewed by {:.1} degrees, also scanning a deskewed copy",
+            skew
+        );
+        let deskewed = rotate(&variants[1], -skew);
+        variants.push(adaptive_threshold(&deskewed));
+        variants.push(deskewed);
+    }
+
+    let mut seen = HashSet::new();
+    let mut codes = vec![];
+    for (idx, variant) in variants.iter().enumerate() {
+        for scale in SCALES {
+            let (width, height) = (
+                (variant.width() as f64 * scale) as u32,
+                (variant.height() as f64 * scale) as u32,
+            );
+            let (detected, decoded) = if scale >= 1.0 {
+                detect_codes(variant)
+            } else if width.min(height) >= MIN_SCALED_SIZE {
+                detect_codes(&imageops::resize(
+                    variant,
+                    width,
+                    height,
+                    FilterType::Triangle,
+                ))
+            } else {
+                continue;
+            };
+            debug!(
+                variant = idx,
+                scale,
+                detected,
+                decoded = decoded.len(),
+                "scanned image for qr codes"
+            );
+            // The same QR codes are usually detected by several passes.
+            scanned.detected = scanned.detected.max(detected);
+            codes.extend(decoded.into_iter().filter(|code| seen.insert(code.clone())));
+        }
+    }
+
+    scanned.found = codes.len();
+    scanned.detected = scanned.detected.max(scanned.found);
+    if scanned.found < scanned.detected {
+        scanned.warnings.push(format!(
+            "{} QR codes could not be decoded (try rescanning at a higher resolution)",
+            scanned.detected - scanned.found
+        ));
+    }
+
+    for code in codes {
+        match QrEncoding::decode(&code)
+            .ok()
+            .and_then(|data| qr::Part::from_wire(data).ok())
+        {
+            Some(part) => scanned.parts.push(part),
+            None => scanned.codes.push(code),
+        }
+    }
+    scanned
+}
+
+/// Detect and decode the QR codes in `image`, returning the number of QR codes
+/// detected and the data of those which could be decoded.
+fn detect_codes(image: &GrayImage) -> (usize, Vec<String>) {
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0],
+    );
+    let grids = prepared.detect_grids();
+    let codes = grids
+        .iter()
+        .filter_map(|grid| grid.decode().ok())
+        .map(|(_, data)| data)
+        .collect();
+    (grids.len(), codes)
+}
+
+/// Convert `image` to black and white, comparing each pixel against the mean
+/// of its surroundings rather than a single global threshold, so that shadows
+/// and uneven lighting don't wash out parts of the page.
+fn adaptive_threshold(image: &GrayImage) -> GrayImage {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let radius = width.max(height) / 16;
+
+    // Summed-area table of the image, with an extra leading row and column of
+    // zeroes so that the sum of any window can be computed without branches.
+    let stride = width + 1;
+    let mut sums = vec![0u64; stride * (height + 1)];
+    for y in 0..height {
+        let mut row = 0;
+        for x in 0..width {
+            row += image.get_pixel(x as u32, y as u32).0[0] as u64;
+            sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row;
+        }
+    }
+
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let (x, y) = (x as usize, y as usize);
+        let (x0, y0) = (x.saturating_sub(radius), y.saturating_sub(radius));
+        let (x1, y1) = ((x + radius + 1).min(width), (y + radius + 1).min(height));
+        let sum = (sums[y1 * stride + x1] + sums[y0 * stride + x0])
+            - (sums[y0 * stride + x1] + sums[y1 * stride + x0]);
+        let count = ((x1 - x0) * (y1 - y0)) as u64;
+        let value = image.get_pixel(x as u32, y as u32).0[0] as u64;
+        if value * count * 100 < sum * (100 - THRESHOLD_PERCENT) {
+            Luma([0])
+        } else {
+            Luma([u8::MAX])
+        }
+    })
+}
+
+/// Estimate how far (in degrees) the page in `image` is rotated.
+///
+/// Pages are made up of rows of text and QR codes, so when a page is straight
+/// its dark pixels are concentrated in a few rows. The skew is the angle at
+/// which the projection of the dark pixels onto the vertical axis is the most
+/// concentrated (has the largest sum of squares).
+fn estimate_skew(image: &GrayImage) -> f64 {
+    let (width, height) = image.dimensions();
+    let scale = (SKEW_ESTIMATE_SIZE as f64 / width.max(height) as f64).min(1.0);
+    let small = adaptive_threshold(&imageops::resize(
+        image,
+        ((width as f64 * scale) as u32).max(1),
+        ((height as f64 * scale) as u32).max(1),
+        FilterType::Triangle,
+    ));
+
+    let dark = small
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0[0] == 0)
+        .map(|(x, y, _)| (x as f64, y as f64))
+        .collect::<Vec<_>>();
+    let diagonal = (small.width() as f64).hypot(small.height() as f64).ceil() as isize;
+
+    // Smaller angles are tried first, so that they win any ties (such as for a
+    // blank page).
+    let steps = (MAX_SKEW / SKEW_STEP) as i32;
+    (0..=steps)
+        .flat_map(|step| [step, -step])
+        .map(|step| {
+            let angle = step as f64 * SKEW_STEP;
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let mut profile = vec![0u64; 2 * diagonal as usize + 1];
+            for (x, y) in &dark {
+                let row = (y * cos - x * sin).round() as isize + diagonal;
+                profile[row as usize] += 1;
+            }
+            (angle, profile.iter().map(|n| n * n).sum::<u64>())
+        })
+        .fold((0.0, 0), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+/// Rotate `image` by `angle` degrees about its centre, filling any uncovered
+/// areas with white.
+fn rotate(image: &GrayImage, angle: f64) -> GrayImage {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (cx, cy) = (image.width() as f64 / 2.0, image.height() as f64 / 2.0);
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        // Find the pixel which ends up at (x, y) after rotation.
+        let (dx, dy) = (x as f64 - cx, y as f64 - cy);
+        let (sx, sy) = (dx * cos + dy * sin + cx, dy * cos - dx * sin + cy);
+        if sx >= 0.0 && sy >= 0.0 && (sx as u32) < image.width() && (sy as u32) < image.height() {
+            *image.get_pixel(sx as u32, sy as u32)
+        } else {
+            Luma([u8::MAX])
+        }
+    })
+}
+
+/// Read the main document and key shards from the QR codes in images of the
+/// printed documents.
+pub(crate) fn read_recovery_scans<'a>(
+    paths: impl IntoIterator<Item = &'a String>,
+) -> Result<(MainDocument, Vec<EncryptedKeyShard>), Error> {
+    let mut collector = CodeCollector::new();
+    for (idx, path) in paths.into_iter().enumerate() {
+        collect_scan(&mut collector, path, idx + 1)?;
+    }
+    collector.finish("in the scanned images")
+}
+
+/// Scan the image at `path` (page number `page` of the scanned documents) and
+/// add the QR codes found in it to `collector`.
+pub(crate) fn collect_scan(
+    collector: &mut CodeCollector,
+    path: &str,
+    page: usize,
+) -> Result<(), Error> {
+    let image =
+        image::open(path).with_context(|| format!("failed to read image file '{}'", path))?;
+    let scanned = scan_image(&image);
+    println!(
+        "Found {}/{} codes on page {} ('{}').",
+        scanned.found, scanned.detected, page, path
+    );
+    for warning in &scanned.warnings {
+        println!("Warning: page {}: {}.", page, warning);
+    }
+
+    let location = format!("on page {}", page);
+    for part in scanned.parts {
+        collector.add_part(part, &location);
+    }
+    for code in scanned.codes {
+        collector.add_single_code(code, &location);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// White image with horizontal black stripes (like rows of text).
+    fn striped_image() -> GrayImage {
+        GrayImage::from_fn(400, 400, |_, y| {
+            if (50..350).contains(&y) && y % 20 < 4 {
+                Luma([0])
+            } else {
+                Luma([u8::MAX])
+            }
+        })
+    }
+
+    #[test]
+    fn threshold_uneven_lighting() {
+        // Dark squares on a background which gets brighter from left to
+        // right, much darker than any global threshold could handle.
+        let image = GrayImage::from_fn(400, 400, |x, y| {
+            let background = 100 + (x * 150 / 400) as u8;
+            if x % 50 < 10 && y % 50 < 10 {
+                Luma([background - 80])
+            } else {
+                Luma([background])
+            }
+        });
+        let threshold = adaptive_threshold(&image);
+        for (x, y, pixel) in threshold.enumerate_pixels() {
+            let expected = if x % 50 < 10 && y % 50 < 10 {
+                0
+            } else {
+                u8::MAX
+            };
+            assert_eq!(pixel.0[0], expected, "pixel ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn skew_straight() {
+        assert_eq!(estimate_skew(&striped_image()), 0.0);
+        assert_eq!(estimate_skew(&GrayImage::new(100, 100)), 0.0);
+    }
+
+    #[test]
+    fn skew_rotated() {
+        for angle in [-7.0, -2.5, 1.0, 4.0, 9.5] {
+            let skew = estimate_skew(&rotate(&striped_image(), angle));
+            assert!(
+                (skew - angle).abs() <= SKEW_STEP,
+                "estimated skew {} for image rotated by {}",
+                skew,
+                angle
+            );
+        }
+    }
+
+    #[test]
+    fn rotate_roundtrip() {
+        let image = striped_image();
+        assert_eq!(rotate(&image, 0.0), image);
+        // Rotating back and forth (mostly) restores the image.
+        let restored = rotate(&rotate(&image, 5.0), -5.0);
+        let differences = image
+            .pixels()
+            .zip(restored.pixels())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(differences < (image.width() * image.height() / 10) as usize);
+    }
+}
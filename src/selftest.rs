@@ -0,0 +1,244 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `paperback-cli self-test`, which runs a complete backup and recovery in
+//! memory to check that the installed binary works before it is trusted with
+//! real secret data.
+
+use std::{
+    io::BufWriter,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Error};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{
+    pdf::qr::{self, Joiner},
+    Backup, EncryptedKeyShard, FromPdf, FromWire, InconsistentQuorumKind, KeyShardCodewords,
+    MainDocument, PdfOptions, QrEncoding, ToPdf, ToWire, UntrustedQuorum,
+};
+
+use crate::parse_multibase;
+
+// paperback-cli self-test [--quorum-size <QUORUM SIZE>] [--shards <SHARDS>] [--secret-size <BYTES>]
+pub(crate) fn self_test_cli() -> Command {
+    Command::new("self-test")
+        .about("Check that paperback works on this machine, by backing up random data and recovering it again. Every document goes through the same encoding as a real backup (including splitting the main document into QR codes and generating the PDFs), but everything is kept in memory and no files are written.")
+        .arg(
+            Arg::new("quorum-size")
+                .short('n')
+                .long("quorum-size")
+                .value_name("QUORUM SIZE")
+                .help("Number of shards required to recover the test backup.")
+                .default_value("3")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("shards")
+                .short('k')
+                .long("shards")
+                .value_name("NUM SHARDS")
+                .help("Number of shards to create for the test backup.")
+                .default_value("5")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("secret-size")
+                .long("secret-size")
+                .value_name("BYTES")
+                .help("Size of the random secret data to back up.")
+                .default_value("1024")
+                .action(ArgAction::Set),
+        )
+}
+
+/// Run one step of the self-test, printing how long it took.
+fn step<T, F: FnOnce() -> Result<T, Error>>(
+    name: &str,
+    total: &mut Duration,
+    func: F,
+) -> Result<T, Error> {
+    let start = Instant::now();
+    let result = func().with_context(|| format!("self-test failed: {}", name))?;
+    let elapsed = start.elapsed();
+    *total += elapsed;
+    println!("  {:<32} {:>9.1}ms", name, elapsed.as_secs_f64() * 1000.0);
+    Ok(result)
+}
+
+fn pdf_bytes<T: ToPdf + ?Sized>(object: &T, options: &PdfOptions) -> Result<Vec<u8>, Error> {
+    let mut pdf = Vec::new();
+    object
+        .to_pdf_with_options(options)?
+        .save(&mut BufWriter::new(&mut pdf))?;
+    Ok(pdf)
+}
+
+type Shards = Vec<(EncryptedKeyShard, KeyShardCodewords)>;
+
+pub(crate) fn self_test(matches: &ArgMatches) -> Result<(), Error> {
+    let quorum_size: u32 = matches
+        .get_one::<String>("quorum-size")
+        .context("required --quorum-size argument not provided")?
+        .parse()
+        .context("--quorum-size argument was not an unsigned integer")?;
+    let num_shards: u32 = matches
+        .get_one::<String>("shards")
+        .context("required --shards argument not provided")?
+        .parse()
+        .context("--shards argument was not an unsigned integer")?;
+    let secret_size: usize = matches
+        .get_one::<String>("secret-size")
+        .context("required --secret-size argument not provided")?
+        .parse()
+        .context("--secret-size argument was not an unsigned integer")?;
+    Backup::check_policy(quorum_size, num_shards).context("invalid arguments")?;
+    let options = PdfOptions::default();
+
+    println!(
+        "Running self-test with {} bytes of random secret data ({} of {} shards required):",
+        secret_size, quorum_size, num_shards
+    );
+    let mut total = Duration::ZERO;
+
+    let mut secret = Zeroizing::new(vec![0; secret_size]);
+    rand::thread_rng().fill_bytes(&mut secret);
+
+    let backup = step("create backup", &mut total, || {
+        Ok(Backup::builder()
+            .quorum_size(quorum_size)
+            .pdf_options(&options)
+            .build(&secret)?)
+    })?;
+    let main_document = backup.main_document().clone();
+
+    let shards: Shards = step("create key shards", &mut total, || {
+        backup
+            .next_shards(num_shards as usize)?
+            .iter()
+            .map(|shard| Ok(shard.encrypt()?))
+            .collect()
+    })?;
+
+    let (main_document, shards) = step("wire encoding", &mut total, || {
+        let main_document2: MainDocument =
+            parse_multibase(main_document.to_wire_multibase(multibase::Base::Base32Z))?;
+        ensure!(
+            main_document2.checksum_string() == main_document.checksum_string(),
+            "main document changed after wire round-trip"
+        );
+        let shards2 = shards
+            .iter()
+            .map(|(shard, codewords)| {
+                let shard2: EncryptedKeyShard =
+                    parse_multibase(shard.to_wire_multibase(multibase::Base::Base32Z))?;
+                ensure!(
+                    shard2.checksum_string() == shard.checksum_string(),
+                    "key shard changed after wire round-trip"
+                );
+                Ok((shard2, codewords.clone()))
+            })
+            .collect::<Result<Shards, Error>>()?;
+        Ok((main_document2, shards2))
+    })?;
+
+    let (main_document, shards) = step("qr code encoding", &mut total, || {
+        let mut joiner = Joiner::new();
+        for text in qr::main_document_qr_text(&main_document, &options) {
+            joiner.add_qr_part(text)?;
+        }
+        ensure!(joiner.complete(), "main document qr codes are incomplete");
+        let main_document2 = MainDocument::from_wire(joiner.combine_parts()?)
+            .map_err(|err| anyhow!("failed to parse main document: {}", err))?;
+        ensure!(
+            main_document2.checksum_string() == main_document.checksum_string(),
+            "main document changed after qr code round-trip"
+        );
+        let shards2 = shards
+            .iter()
+            .map(|(shard, codewords)| {
+                let text = options.qr_encoding.encode(shard.to_wire());
+                let shard2 = EncryptedKeyShard::from_wire(QrEncoding::decode(text)?)
+                    .map_err(|err| anyhow!("failed to parse key shard: {}", err))?;
+                ensure!(
+                    shard2.checksum_string() == shard.checksum_string(),
+                    "key shard changed after qr code round-trip"
+                );
+                Ok((shard2, codewords.clone()))
+            })
+            .collect::<Result<Shards, Error>>()?;
+        Ok((main_document2, shards2))
+    })?;
+
+    let (main_document, shards) = step("pdf generation", &mut total, || {
+        let main_document2 = MainDocument::from_pdf(pdf_bytes(&main_document, &options)?)?;
+        ensure!(
+            main_document2.checksum_string() == main_document.checksum_string(),
+            "main document changed after pdf round-trip"
+        );
+        let shards2 = shards
+            .iter()
+            .map(|(shard, codewords)| {
+                let pdf = pdf_bytes(&(shard, codewords), &options)?;
+                let shard2 = EncryptedKeyShard::from_pdf(pdf)?;
+                ensure!(
+                    shard2.checksum_string() == shard.checksum_string(),
+                    "key shard changed after pdf round-trip"
+                );
+                Ok((shard2, codewords.clone()))
+            })
+            .collect::<Result<Shards, Error>>()?;
+        Ok((main_document2, shards2))
+    })?;
+
+    step("recover secret", &mut total, || {
+        let mut quorum = UntrustedQuorum::new();
+        quorum.main_document(main_document);
+        let mut short_quorum = quorum.clone();
+        for (idx, (shard, codewords)) in shards.into_iter().take(quorum_size as usize).enumerate() {
+            let shard = shard.decrypt(&codewords)?;
+            if idx + 1 < quorum_size as usize {
+                short_quorum.push_shard(shard.clone());
+            }
+            quorum.push_shard(shard);
+        }
+
+        // One key shard fewer than the quorum size must not be enough.
+        match short_quorum.validate() {
+            Err(err) if err.kind() == InconsistentQuorumKind::InsufficientShards => (),
+            Err(err) => return Err(Error::from(err).context("validating incomplete quorum")),
+            Ok(_) => bail!("incomplete quorum was accepted"),
+        }
+
+        let recovered = quorum.validate()?.recover_document()?;
+        ensure!(
+            *recovered == *secret,
+            "recovered secret data does not match"
+        );
+        Ok(())
+    })?;
+
+    println!("Self-test passed in {:.1}ms.", total.as_secs_f64() * 1000.0);
+    Ok(())
+}
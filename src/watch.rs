@@ -0,0 +1,181 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use anyhow::{Context, Error};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{EncryptedKeyShard, FromPdf, MainDocument};
+
+use crate::{collect::CodeCollector, scan};
+
+/// Scans (images or PDF files) read from a watched directory so far.
+#[derive(Default)]
+struct WatchedScans {
+    collector: CodeCollector,
+    /// Files which have been read successfully.
+    read: HashSet<PathBuf>,
+    /// Files which could not be read. Scanners often write files in several
+    /// steps, so these are retried whenever they change, but the error is only
+    /// reported once.
+    failed: HashSet<PathBuf>,
+}
+
+impl WatchedScans {
+    /// Read the scan at `path` (if it has not already been read), returning
+    /// whether any new data was collected.
+    fn add_file(&mut self, path: &Path) -> bool {
+        let hidden = path
+            .file_name()
+            .is_none_or(|name| name.to_string_lossy().starts_with('.'));
+        if hidden || self.read.contains(path) || !path.is_file() {
+            return false;
+        }
+        let display = path.display().to_string();
+        let is_pdf = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        let result = if is_pdf {
+            self.add_pdf(&display)
+        } else {
+            scan::collect_scan(&mut self.collector, &display, self.read.len() + 1)
+        };
+        match result {
+            Ok(()) => {
+                self.failed.remove(path);
+                self.read.insert(path.to_path_buf());
+                true
+            }
+            Err(err) => {
+                if self.failed.insert(path.to_path_buf()) {
+                    println!(
+                        "Could not read '{}' (it will be read again if it changes): {:#}",
+                        display, err
+                    );
+                }
+                false
+            }
+        }
+    }
+
+    fn add_pdf(&mut self, path: &str) -> Result<(), Error> {
+        let pdf = fs::read(path).with_context(|| format!("failed to read PDF file '{}'", path))?;
+        if let Ok(main_document) = MainDocument::from_pdf(&pdf) {
+            println!("Found main document in '{}'.", path);
+            self.collector
+                .add_main_document(main_document, &format!("in '{}'", path));
+        } else {
+            let shards = Vec::<EncryptedKeyShard>::from_pdf(&pdf).with_context(|| {
+                format!(
+                    "PDF file '{}' contains neither a main document nor key shards",
+                    path
+                )
+            })?;
+            println!("Found {} key shards in '{}'.", shards.len(), path);
+            for shard in shards {
+                self.collector.add_shard(shard);
+            }
+        }
+        Ok(())
+    }
+
+    /// Print how close the collected scans are to a full quorum, returning
+    /// whether the main document and enough key shards have been collected.
+    fn report_progress(&self) -> bool {
+        let num_shards = self.collector.num_shards();
+        match self.collector.main_document() {
+            Some(main_document) => {
+                let quorum_size = main_document.quorum_size() as usize;
+                println!(
+                    "Progress: main document complete, {}/{} key shards.",
+                    num_shards.min(quorum_size),
+                    quorum_size
+                );
+                num_shards >= quorum_size
+            }
+            None => {
+                println!(
+                    "Progress: {}, {} key shards.",
+                    self.collector
+                        .missing_main_document()
+                        .unwrap_or_else(|| "main document could not be parsed".into()),
+                    num_shards
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Read the main document and key shards from scans (images or PDF files)
+/// placed in `dir`, waiting for new scans to appear until the main document
+/// and a quorum of key shards have been found.
+pub(crate) fn read_recovery_watch_dir(
+    dir: &str,
+) -> Result<(MainDocument, Vec<EncryptedKeyShard>), Error> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).context("failed to create directory watcher")?;
+    // Start watching before reading the existing files, so that no scans are
+    // missed in between.
+    watcher
+        .watch(Path::new(dir), RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch directory '{}'", dir))?;
+
+    let mut scans = WatchedScans::default();
+    let mut existing = fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory '{}'", dir))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("failed to read directory '{}'", dir))?;
+    existing.sort();
+    for path in existing {
+        scans.add_file(&path);
+    }
+
+    println!(
+        "Watching '{}' for scanned documents (press Ctrl-C to stop).",
+        dir
+    );
+    while !scans.report_progress() {
+        let mut changed = false;
+        while !changed {
+            let event = rx
+                .recv()
+                .context("directory watcher stopped unexpectedly")?
+                .with_context(|| format!("failed to watch directory '{}'", dir))?;
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                for path in event.paths {
+                    changed |= scans.add_file(&path);
+                }
+            }
+        }
+    }
+    drop(watcher);
+
+    scans.collector.finish(&format!("in '{}'", dir))
+}
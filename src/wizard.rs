@@ -0,0 +1,563 @@
+/*
+ * paperback: paper backup generator suitable for long-term storage
+ * Copyright (C) 2018-2022 Aleksa Sarai <cyphar@cyphar.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::mem;
+
+use anyhow::{anyhow, Context, Error};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+use zeroize::Zeroizing;
+
+extern crate paperback_core;
+use paperback_core::latest as paperback;
+
+use paperback::{
+    codewords, pdf::qr, EncryptedKeyShard, FromWire, KeyShard, MainDocument, UntrustedQuorum,
+};
+
+use crate::{format_part_indices, parse_multibase};
+
+/// Maximum number of codeword completions shown at once.
+const MAX_SUGGESTIONS: usize = 8;
+
+/// The step of the recovery the wizard is currently at.
+enum Stage {
+    /// Entering the QR codes of the main document.
+    MainDocument,
+    /// Entering the data of the next key shard.
+    KeyShard,
+    /// Entering the codewords of the key shard that was just entered.
+    Codewords(EncryptedKeyShard),
+    /// The secret was recovered, waiting for the user to exit.
+    Finished(Zeroizing<Vec<u8>>),
+}
+
+/// Interactive terminal interface which walks through recovering a backup.
+struct Wizard {
+    stage: Stage,
+    // The input may contain codewords, so wipe it when it is replaced.
+    input: Zeroizing<String>,
+    status: Result<String, String>,
+    log: Vec<String>,
+    joiner: qr::Joiner,
+    // Key shards too large for a single QR code are split into parts.
+    shard_joiner: qr::Joiner,
+    main_document: Option<MainDocument>,
+    quorum: UntrustedQuorum,
+}
+
+/// The longest prefix shared by all of `words`.
+fn common_prefix<'a>(words: &[&'a str]) -> Option<&'a str> {
+    let (first, rest) = words.split_first()?;
+    let len = rest.iter().fold(first.len(), |len, word| {
+        first
+            .char_indices()
+            .zip(word.chars())
+            .take_while(|((idx, a), b)| *idx < len && a == b)
+            .map(|((idx, a), _)| idx + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+    });
+    Some(&first[..len])
+}
+
+/// The tail of `input` which fits in `width` columns.
+fn visible_tail(input: &str, width: usize) -> &str {
+    let skip = input.chars().count().saturating_sub(width);
+    match input.char_indices().nth(skip) {
+        Some((idx, _)) => &input[idx..],
+        None => "",
+    }
+}
+
+impl Wizard {
+    fn new() -> Self {
+        Self {
+            stage: Stage::MainDocument,
+            input: Zeroizing::new(String::new()),
+            status: Ok("Enter the first main document QR code.".into()),
+            log: vec![],
+            joiner: qr::Joiner::new(),
+            shard_joiner: qr::Joiner::new(),
+            main_document: None,
+            quorum: UntrustedQuorum::new(),
+        }
+    }
+
+    fn quorum_size(&self) -> u32 {
+        self.main_document
+            .as_ref()
+            .map(MainDocument::quorum_size)
+            .unwrap_or_default()
+    }
+
+    /// The last (partially typed) codeword in the input.
+    fn current_word(&self) -> &str {
+        match self.input.ends_with(char::is_whitespace) {
+            true => "",
+            false => self.input.split_whitespace().last().unwrap_or(""),
+        }
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<Zeroizing<Vec<u8>>, Error> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            // Any key exits once the secret has been recovered.
+            if let Stage::Finished(secret) = &self.stage {
+                return Ok(secret.clone());
+            }
+            match key.code {
+                KeyCode::Esc => return Err(anyhow!("recovery aborted")),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Err(anyhow!("recovery aborted"))
+                }
+                KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.input = Zeroizing::new(String::new())
+                }
+                KeyCode::Char(ch) => self.input.push(ch),
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Tab => self.complete_codeword(),
+                KeyCode::Enter => {
+                    let input = mem::replace(&mut self.input, Zeroizing::new(String::new()));
+                    self.status = self
+                        .submit(input.trim())
+                        .map_err(|err| format!("{:#}", err));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn complete_codeword(&mut self) {
+        if !matches!(self.stage, Stage::Codewords(_)) {
+            return;
+        }
+        let word = self.current_word();
+        if word.is_empty() {
+            return;
+        }
+        let completions = codewords::completions(word);
+        let completion = match completions.as_slice() {
+            [completion] => format!("{} ", completion),
+            _ => match common_prefix(&completions) {
+                Some(prefix) => prefix.to_string(),
+                None => return,
+            },
+        };
+        let new_len = self.input.len() - word.len();
+        self.input.truncate(new_len);
+        self.input.push_str(&completion);
+    }
+
+    fn submit(&mut self, input: &str) -> Result<String, Error> {
+        match mem::replace(&mut self.stage, Stage::MainDocument) {
+            Stage::MainDocument => self.submit_main_document(input),
+            Stage::KeyShard => {
+                self.stage = Stage::KeyShard;
+                self.submit_key_shard(input)
+            }
+            Stage::Codewords(shard) => self.submit_codewords(shard, input),
+            Stage::Finished(secret) => {
+                self.stage = Stage::Finished(secret);
+                Ok("Recovery complete.".into())
+            }
+        }
+    }
+
+    fn submit_main_document(&mut self, input: &str) -> Result<String, Error> {
+        // Allow the user to remove a mistyped code so it can be re-entered.
+        if let Some(idx) = input.strip_prefix("delete") {
+            return match idx.trim().parse::<usize>() {
+                Ok(idx) if idx > 0 && self.joiner.remove_part(idx - 1).is_some() => {
+                    self.log
+                        .push(format!("Removed main document code {}.", idx));
+                    Ok(format!("Removed code {}.", idx))
+                }
+                _ => Err(anyhow!("invalid code number '{}' to delete", idx.trim())),
+            };
+        }
+
+        let part: qr::Part = parse_multibase(input).context("invalid code")?;
+        let (idx, num_parts) = (part.index(), part.num_parts());
+        self.joiner
+            .add_part(part)
+            .with_context(|| format!("code {} of {} rejected", idx + 1, num_parts))?;
+        self.log.push(format!(
+            "Added main document code {} of {}.",
+            idx + 1,
+            num_parts
+        ));
+        if !self.joiner.complete() {
+            return Ok(format!("Added code {} of {}.", idx + 1, num_parts));
+        }
+
+        let main_document = self
+            .joiner
+            .combine_parts()
+            .map_err(Error::from)
+            .and_then(|data| {
+                MainDocument::from_wire(data)
+                    .map_err(|err| anyhow!("parse inner qr code data: {}", err))
+            });
+        let main_document = match main_document {
+            Ok(main_document) => main_document,
+            Err(err) => {
+                // Start over, since we can't tell which code was wrong.
+                self.joiner = qr::Joiner::new();
+                self.log
+                    .push("Main document was invalid -- cleared all codes.".into());
+                return Err(err.context("main document codes are invalid, please re-enter them"));
+            }
+        };
+
+        self.log.push(format!(
+            "Loaded main document {} (checksum {}, fingerprint \"{}\").",
+            main_document.id(),
+            main_document.checksum_string(),
+            main_document.fingerprint_words().join(" ")
+        ));
+        if main_document.is_expired() {
+            self.log.push(
+                "WARNING: this backup has expired -- a newer backup may have replaced it.".into(),
+            );
+        }
+        self.quorum.main_document(main_document.clone());
+        self.main_document = Some(main_document);
+        self.stage = Stage::KeyShard;
+        Ok(format!(
+            "Main document loaded. Enter key shard 1 of {}.",
+            self.quorum_size()
+        ))
+    }
+
+    fn submit_key_shard(&mut self, input: &str) -> Result<String, Error> {
+        let shard: EncryptedKeyShard = match parse_multibase::<_, qr::Part>(input) {
+            Ok(part) => {
+                let (idx, num_parts) = (part.index(), part.num_parts());
+                self.shard_joiner
+                    .add_part(part)
+                    .with_context(|| format!("code {} of {} rejected", idx + 1, num_parts))?;
+                if !self.shard_joiner.complete() {
+                    return Ok(format!(
+                        "Added key shard code {} of {}.",
+                        idx + 1,
+                        num_parts
+                    ));
+                }
+                let data = mem::take(&mut self.shard_joiner).combine_parts()?;
+                EncryptedKeyShard::from_wire(data)
+                    .map_err(|err| anyhow!("invalid key shard: {}", err))?
+            }
+            Err(_) => parse_multibase(input).context("invalid key shard")?,
+        };
+        let idx = self.quorum.num_untrusted_shards() + 1;
+        self.log.push(format!(
+            "Entered key shard {} (checksum {}, fingerprint \"{}\").",
+            idx,
+            shard.checksum_string(),
+            shard.fingerprint_words().join(" ")
+        ));
+        self.stage = Stage::Codewords(shard);
+        Ok(format!("Enter the codewords of key shard {}.", idx))
+    }
+
+    fn submit_codewords(
+        &mut self,
+        encrypted_shard: EncryptedKeyShard,
+        input: &str,
+    ) -> Result<String, Error> {
+        let idx = self.quorum.num_untrusted_shards() + 1;
+        let shard = match codewords::normalize(&input.split_whitespace().collect::<Vec<_>>())
+            .and_then(|codewords| encrypted_shard.decrypt(&codewords))
+        {
+            Ok(shard) => shard,
+            Err(err) => {
+                self.stage = Stage::Codewords(encrypted_shard);
+                return Err(Error::from(err).context(format!("decrypting key shard {}", idx)));
+            }
+        };
+
+        self.log
+            .push(format!("Loaded key shard {} ({}).", idx, shard.id()));
+        self.quorum.push_shard(shard);
+        if self.quorum.num_untrusted_shards() < self.quorum_size() as usize {
+            self.stage = Stage::KeyShard;
+            return Ok(format!(
+                "Loaded key shard {}. Enter key shard {} of {}.",
+                idx,
+                idx + 1,
+                self.quorum_size()
+            ));
+        }
+
+        let secret = mem::take(&mut self.quorum)
+            .validate()?
+            .recover_document()
+            .context("recovering secret data")?;
+        self.log.push("Recovered secret data.".into());
+        self.stage = Stage::Finished(secret);
+        Ok("Recovery complete. Press any key to save the secret data.".into())
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, body, input, status] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [progress, details] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(body);
+
+        frame.render_widget(
+            Paragraph::new("paperback recovery wizard (Esc to abort, Ctrl-U to clear input)")
+                .style(Style::default().fg(Color::Cyan)),
+            header,
+        );
+
+        // Only show the most recent progress entries that fit.
+        let log_height = progress.height.saturating_sub(2) as usize;
+        let log = self
+            .log
+            .iter()
+            .skip(self.log.len().saturating_sub(log_height))
+            .map(|line| Line::from(line.as_str()))
+            .collect::<Vec<_>>();
+        frame.render_widget(
+            Paragraph::new(log)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title("Progress")),
+            progress,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.details())
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(self.title())),
+            details,
+        );
+
+        self.draw_input(frame, input);
+
+        let (message, colour) = match &self.status {
+            Ok(message) => (message.as_str(), Color::Green),
+            Err(message) => (message.as_str(), Color::Red),
+        };
+        frame.render_widget(
+            Paragraph::new(message).style(Style::default().fg(colour)),
+            status,
+        );
+    }
+
+    fn draw_input(&self, frame: &mut Frame, area: Rect) {
+        let width = area.width.saturating_sub(3) as usize;
+        let visible = visible_tail(&self.input, width);
+        frame.render_widget(
+            Paragraph::new(visible).block(Block::bordered().title("Input")),
+            area,
+        );
+        if !matches!(self.stage, Stage::Finished(_)) {
+            frame.set_cursor_position((area.x + 1 + visible.chars().count() as u16, area.y + 1));
+        }
+    }
+
+    fn title(&self) -> String {
+        match &self.stage {
+            Stage::MainDocument => "Main Document".into(),
+            Stage::KeyShard | Stage::Codewords(_) => format!(
+                "Key Shard {} of {}",
+                self.quorum.num_untrusted_shards() + 1,
+                self.quorum_size()
+            ),
+            Stage::Finished(_) => "Done".into(),
+        }
+    }
+
+    fn details(&self) -> Vec<Line<'static>> {
+        let ok = Style::default().fg(Color::Green);
+        let bad = Style::default().fg(Color::Yellow);
+        let mut lines = vec![];
+        match &self.stage {
+            Stage::MainDocument => {
+                lines.push(Line::from(
+                    "Enter each main document QR code, one at a time. Enter \"delete N\" to remove code N.",
+                ));
+                lines.push(Line::from(""));
+                lines.push(Line::from(match self.joiner.num_parts() {
+                    None => "No codes entered yet.".to_string(),
+                    Some(n) => format!(
+                        "Entered codes [{}] of {}, missing [{}].",
+                        format_part_indices(&self.joiner.present_parts()),
+                        n,
+                        format_part_indices(&self.joiner.missing_parts()),
+                    ),
+                }));
+                if !self.input.is_empty() && !self.input.starts_with("delete") {
+                    lines.push(match parse_multibase::<_, qr::Part>(self.input.trim()) {
+                        Ok(part) => Line::styled(
+                            format!("Valid code {} of {}.", part.index() + 1, part.num_parts()),
+                            ok,
+                        ),
+                        Err(_) => Line::styled("Not a valid code (yet).", bad),
+                    });
+                }
+            }
+            Stage::KeyShard => {
+                lines.push(Line::from(
+                    "Enter the key shard data (from its QR code or text fallback).",
+                ));
+                lines.push(Line::from(""));
+                if let Some(n) = self.shard_joiner.num_parts() {
+                    lines.push(Line::from(format!(
+                        "Entered key shard codes [{}] of {}, missing [{}].",
+                        format_part_indices(&self.shard_joiner.present_parts()),
+                        n,
+                        format_part_indices(&self.shard_joiner.missing_parts()),
+                    )));
+                }
+                if let Ok(part) = parse_multibase::<_, qr::Part>(self.input.trim()) {
+                    lines.push(Line::styled(
+                        format!(
+                            "Valid key shard code {} of {}.",
+                            part.index() + 1,
+                            part.num_parts()
+                        ),
+                        ok,
+                    ));
+                } else if !self.input.is_empty() {
+                    lines.extend(
+                        match parse_multibase::<_, EncryptedKeyShard>(self.input.trim()) {
+                            Ok(shard) => vec![
+                                Line::styled(
+                                    format!("Key shard checksum: {}", shard.checksum_string()),
+                                    ok,
+                                ),
+                                Line::styled(
+                                    format!(
+                                        "Key shard fingerprint: {}",
+                                        shard.fingerprint_words().join(" ")
+                                    ),
+                                    ok,
+                                ),
+                                Line::from(
+                                    "Make sure it matches the checksum printed on the key shard.",
+                                ),
+                            ],
+                            Err(_) => vec![Line::styled("Not a valid key shard (yet).", bad)],
+                        },
+                    );
+                }
+            }
+            Stage::Codewords(shard) => {
+                lines.push(Line::from(format!(
+                    "Enter the codewords of the key shard with checksum {}. Press Tab to autocomplete a codeword.",
+                    shard.checksum_string()
+                )));
+                lines.push(Line::from(""));
+                let words = self.input.split_whitespace().collect::<Vec<_>>();
+                lines.push(Line::from(format!("{} codewords entered.", words.len())));
+                let current_word = self.current_word();
+                let invalid = words
+                    .iter()
+                    .filter(|word| **word != current_word && codewords::normalize(&[word]).is_err())
+                    .copied()
+                    .collect::<Vec<_>>();
+                if !invalid.is_empty() {
+                    lines.push(Line::styled(
+                        format!("Unknown codewords: {}", invalid.join(" ")),
+                        bad,
+                    ));
+                }
+                if !current_word.is_empty() {
+                    let completions = codewords::completions(current_word);
+                    lines.push(match completions.len() {
+                        0 => Line::styled(
+                            format!("No codewords start with '{}'.", current_word),
+                            bad,
+                        ),
+                        n if n > MAX_SUGGESTIONS => Line::from(format!(
+                            "Suggestions: {} ... ({} more)",
+                            completions[..MAX_SUGGESTIONS].join(" "),
+                            n - MAX_SUGGESTIONS
+                        )),
+                        _ => Line::styled(format!("Suggestions: {}", completions.join(" ")), ok),
+                    });
+                }
+            }
+            Stage::Finished(secret) => {
+                lines.push(Line::styled(
+                    format!("Recovered {} bytes of secret data.", secret.len()),
+                    ok,
+                ));
+                lines.push(Line::from(
+                    "Press any key to save the secret data and exit.",
+                ));
+            }
+        }
+
+        if !matches!(self.stage, Stage::MainDocument) {
+            let shards = self
+                .quorum
+                .untrusted_shards()
+                .map(KeyShard::id)
+                .collect::<Vec<_>>();
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!(
+                "Quorum contains [{}] key shards.",
+                shards.join(" ")
+            )));
+        }
+        lines
+    }
+}
+
+/// Run the recovery wizard, returning the recovered secret data.
+pub(crate) fn recover() -> Result<Zeroizing<Vec<u8>>, Error> {
+    let mut terminal = ratatui::init();
+    let result = Wizard::new().run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn codeword_common_prefix() {
+        assert_eq!(common_prefix(&[]), None);
+        assert_eq!(common_prefix(&["abandon"]), Some("abandon"));
+        assert_eq!(common_prefix(&["abandon", "ability", "able"]), Some("ab"));
+        assert_eq!(common_prefix(&["zoo", "abandon"]), Some(""));
+    }
+}